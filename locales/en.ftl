@@ -0,0 +1,253 @@
+# English messages. This is the reference catalog: every other locale falls
+# back to it for messages it doesn't translate.
+
+## Permission prompts
+
+permission-select-option = Select an option
+permission-selection = Selection: { $selection }
+
+permission-bash-summary = Command requires permission
+permission-bash-title = 🛡️ Security Check
+permission-bash-intro = The following command is not in the allowlist:
+permission-bash-allow-once = Allow this time only (don't add to allowlist)
+permission-bash-allow-always = Allow and add to allowlist
+permission-bash-allow-wildcard = Allow and add to allowlist with wildcard: '{ $pattern }'
+permission-bash-deny = Deny this command
+permission-bash-allowed-once = Command allowed for this time only
+permission-bash-allowed-always = Command allowed and added to allowlist
+permission-bash-wildcard-added = Command wildcard pattern added to allowlist: '{ $pattern }'
+permission-bash-denied = Command denied
+permission-bash-invalid = Invalid selection, denying command for safety
+permission-bash-input-failed = Failed to get user input, denying command for safety
+
+permission-file-summary = File operation requires permission
+permission-file-title = 🛡️ File Operation Security Check
+permission-file-intro = The following file operation requires permission:
+permission-file-operation = Operation: { $operation }
+permission-file-path = Path: { $path }
+permission-file-files = Files:
+permission-file-allow-once = Allow this operation only
+permission-file-allow-session = Allow all file operations this session
+permission-file-deny = Deny this operation
+permission-file-allowed-once = File operation allowed for this time only
+permission-file-allowed-session = All file operations allowed for this session
+permission-file-denied = File operation denied
+permission-file-invalid = Invalid selection, denying file operation for safety
+permission-file-input-failed = Failed to get user input, denying file operation for safety
+
+permission-script-commands = Commands this adds to the script:
+permission-workflow-commands = Commands this adds to the CI workflow:
+permission-script-more = …and { $count } more
+permission-script-note = These run later, whenever the script or workflow does.
+permission-script-risky-summary = File operation adds commands to review: { $risks }
+permission-risk-network = network call
+permission-risk-privilege = privilege escalation
+
+permission-sampling-summary = MCP server '{ $server }' wants to send a request to the model
+permission-sampling-title = 🛡️ MCP Sampling Request
+permission-sampling-intro = MCP server '{ $server }' wants to send these messages to the model:
+permission-sampling-allow-once = Allow this request
+permission-sampling-allow-session = Allow all requests from '{ $server }' this session
+permission-sampling-deny = Deny
+
+permission-question-summary = The assistant has a question
+permission-question-title = ❓ Question
+permission-question-other = None of these, I'll answer in my next message
+permission-question-reply = I'll answer in my next message
+
+permission-mcp-add-summary = Add the { $server } MCP server?
+permission-mcp-add-allow = Add and connect it
+permission-mcp-add-deny = Don't add it
+
+## YOLO mode warnings
+
+yolo-title = ⚠️  WARNING: YOLO MODE ENABLED  ⚠️
+yolo-subtitle = ALL SECURITY PERMISSIONS ARE BYPASSED - USE WITH EXTREME CAUTION
+yolo-files = • File operations (read/write/delete) will execute WITHOUT prompts
+yolo-bash = • Bash commands will execute WITHOUT permission checks
+yolo-mcp = • MCP tools will execute WITHOUT security validation
+yolo-lists = • No allowlist/denylist filtering will be applied
+yolo-auto-approve = • All tool calls are automatically approved
+yolo-damage = 🚨 This mode can cause irreversible damage to your system!
+yolo-cancel = Press Ctrl+C NOW to cancel if this was not intended!
+yolo-proceeding = 🔥 Proceeding in YOLO mode... You have been warned! 🔥
+yolo-mcp-title = 🔌 MCP Configuration Complete - YOLO Mode Active 🔌
+yolo-mcp-subtitle = ⚠️  MCP TOOLS WILL EXECUTE WITHOUT SECURITY VALIDATION ⚠️
+yolo-mcp-prompts = • MCP server tools are now available and will execute WITHOUT prompts
+yolo-mcp-checks = • No permission checks will be applied to MCP tool calls
+yolo-mcp-auto-approve = • All MCP operations (file access, commands, etc.) are auto-approved
+yolo-mcp-access = • External MCP server connections have unrestricted access
+yolo-mcp-modify = 🚨 MCP tools can potentially access and modify your system!
+yolo-mcp-active = 🔥 All MCP servers and their tools are operating in YOLO mode! 🔥
+
+## /help
+
+help-title = 🤖 Flexorama - Slash Commands
+help-available-commands = Available commands:
+help-cmd-help = Show this help message
+help-cmd-stats = Show token usage statistics
+help-cmd-usage = Show token usage statistics (alias for /stats)
+help-cmd-context = Show how the context window is used, item by item
+help-cmd-context-remove = Remove context file or image n from the context
+help-cmd-context-messages = List every message in the context
+help-cmd-provider = Show active LLM provider, model, and base URL
+help-cmd-model = Show or set the active model
+help-cmd-search = Search previous conversations
+help-cmd-resume = Resume a previous conversation
+help-cmd-rename = Name this conversation for --resume <name>
+help-cmd-env = Set an environment variable for this conversation's shell commands
+help-cmd-history = Browse prompts from earlier sessions to run, edit or copy
+help-cmd-diff = Show the files changed this session in a pane beside the output
+help-cmd-checkpoints = List the commits made after turns that changed files
+help-cmd-checkpoints-revert = Undo a checkpoint's changes (the latest by default)
+help-cmd-pr = Push this branch and open a pull request described from the conversation
+help-cmd-share = Export this conversation with secrets redacted and print a link to it
+help-cmd-learn = Propose conventions from this conversation as additions to AGENTS.md
+help-cmd-voice = Dictate a message, transcribed locally with whisper.cpp
+help-cmd-clear = Clear all conversation context (keeps AGENTS.md if it exists)
+help-cmd-reset-stats = Reset token usage statistics
+help-cmd-permissions = Manage bash command security permissions
+help-cmd-file-permissions = Manage file operation security permissions
+help-cmd-hooks = View and manage hooks configuration
+help-cmd-config-sources = Show where each configuration setting came from
+help-cmd-config-get = Show common settings (model, stream, auto-accept, budget)
+help-cmd-config-set = Change a setting and save it to the config file
+help-cmd-copy = Copy code block n (or the latest) to the clipboard
+help-cmd-mcp = Manage MCP (Model Context Protocol) servers
+help-cmd-skill = Manage skills (list, create, update, delete, deactivate)
+help-cmd-exit = Exit the program
+
+help-navigation = Navigation:
+help-nav-history = Navigate through input history
+help-nav-cursor = Move cursor left/right in current input
+help-nav-tab = Auto-complete file paths and commands
+help-nav-search = Start reverse history search (like readline)
+help-nav-copy = Copy the latest code block to the clipboard
+help-nav-model = Open the model picker
+help-nav-cancel = Cancel current AI conversation (during processing)
+help-nav-exit = Exit the program immediately
+
+help-reverse-search = Reverse Search (Ctrl+R):
+help-search-start = Start reverse search mode
+help-search-type = Type text
+help-search-type-desc = Search for matching history entries
+help-search-next = Find next match
+help-search-navigate = Navigate between matches
+help-search-accept = Accept current match
+help-search-cancel = Cancel search and restore original input
+help-search-backspace = Remove last character from search query
+
+help-shell-commands = Shell Commands:
+help-shell-run = Execute a shell command directly (bypasses all security)
+help-shell-examples = Examples: { $examples }
+help-shell-note = Note: ! commands execute immediately without permission checks
+
+help-security-commands = Security Commands:
+help-sec-permissions = Show current bash security settings
+help-sec-file-permissions = Show current file security settings
+help-sec-allow = Add command to allowlist
+help-sec-deny = Add command to denylist
+help-sec-test = Test if command is allowed
+help-sec-file-test = Test if file operation is allowed
+help-sec-plan = Toggle plan mode at runtime
+help-sec-plan-run = Load and execute a saved plan by ID
+
+help-mcp-commands = MCP Commands:
+help-mcp-list = List MCP servers
+help-mcp-add-stdio = Add stdio server
+help-mcp-add-ws = Add WebSocket server
+help-mcp-add-http = Add HTTP server
+help-mcp-add-sse = Add legacy SSE server
+help-mcp-test = Test command availability
+help-mcp-connect = Connect to server
+help-mcp-tools = List available tools
+help-mcp-help = Show MCP help
+
+help-context-files = Context Files:
+help-context-flag = Use -f or --file to include files as context
+help-context-mention = Use @path/to/file syntax in messages to auto-include files
+help-context-agents = AGENTS.md is automatically included from ~/.flexorama/AGENTS.md (priority)
+help-context-agents-fallback = Falls back to ./AGENTS.md if home directory version doesn't exist
+help-context-only = Messages with only @file references will NOT make API calls
+
+help-system-prompts = System Prompts:
+help-system-flag = Use -s or --system to set a custom system prompt
+help-system-purpose = System prompts set the behavior and personality of the AI
+
+help-streaming = Streaming:
+help-streaming-default = Streaming is the default for all CLI modes
+help-streaming-disable = Use --no-stream to disable streaming (spinner + formatted response)
+
+help-plan-mode = Plan Mode:
+help-plan-flag = Use --plan-mode to generate a read-only plan in Markdown
+help-plan-readonly = Plan mode disables mutating tools and saves the plan to the database
+
+help-examples = Examples:
+
+help-history = History Navigation:
+help-history-up = • Press UP arrow to cycle through previous commands
+help-history-down = • Press DOWN arrow to cycle through more recent commands
+help-history-search = • Press Ctrl+R to start reverse history search
+help-history-typing = • Start typing to exit history navigation mode
+help-history-session = • History is preserved across the entire session
+help-history-duplicates = • Duplicate and empty commands are not stored
+
+help-footer = Any other input will be sent to the Flexorama for processing.
+
+## /permissions help
+
+help-permissions-title = 🔒 Permissions Commands
+help-permissions-view = View Permissions:
+help-permissions-show = Show current permissions and security settings
+help-permissions-alias = Alias for /permissions
+help-permissions-help = Show this help message
+help-permissions-allowlist = Manage Allowlist:
+help-permissions-allow = Add command to allowlist
+help-permissions-remove-allow = Remove from allowlist
+help-permissions-denylist = Manage Denylist:
+help-permissions-deny = Add command to denylist
+help-permissions-remove-deny = Remove from denylist
+help-permissions-settings = Security Settings:
+help-permissions-enable = Enable bash security
+help-permissions-disable = Disable bash security
+help-permissions-ask-on = Enable asking for permission
+help-permissions-ask-off = Disable asking for permission
+help-permissions-testing = Testing:
+help-permissions-test = Test if a command is allowed
+help-permissions-patterns = Pattern Matching:
+help-permissions-pattern-wildcard = • Use wildcards: 'git *' allows all git commands
+help-permissions-pattern-exact = • Use exact match: 'cargo test' allows only that command
+help-permissions-pattern-prefix = • Prefix matching: 'git' matches 'git status', 'git log', etc.
+help-permissions-example-allow = Allow all git commands
+help-permissions-example-deny = Deny dangerous rm commands
+help-permissions-example-test = Test if ls -la is allowed
+help-permissions-example-enable = Turn security on
+help-permissions-example-ask = Ask for unknown commands
+help-permissions-tips = Security Tips:
+help-permissions-tip-specific = • Be specific with allowlist entries for better security
+help-permissions-tip-denylist = • Use denylist for dangerous command patterns
+help-permissions-tip-ask = • Enable 'ask for permission' for unknown commands
+help-permissions-tip-saved = • Changes are automatically saved to config file
+
+## /file-permissions help
+
+help-file-permissions-title = 🔒 File Permissions Commands
+help-file-permissions-view = View File Permissions:
+help-file-permissions-show = Show current file permissions and security settings
+help-file-permissions-alias = Alias for /file-permissions
+help-file-permissions-test = Test if file operation is allowed
+help-file-permissions-operations = Operations: { $operations }
+help-file-permissions-enable = Enable file security
+help-file-permissions-disable = Disable file security
+help-file-permissions-reset = Reset session permissions
+help-file-permissions-options = Permission Options:
+help-file-permissions-options-intro = When a file operation requires permission, you can choose:
+help-file-permissions-option-once = • Allow this operation only - One-time permission
+help-file-permissions-option-session = • Allow all file operations this session - Session-wide permission
+help-file-permissions-option-deny = • Deny this operation - Block the operation
+help-file-permissions-tip-ask = • Enable 'ask for permission' for better security
+help-file-permissions-tip-once = • Use 'Allow this operation only' for one-off edits
+help-file-permissions-tip-session = • Use 'Allow all file operations this session' for trusted sessions
+help-file-permissions-tip-operations = • File operations include: { $operations }
+help-file-permissions-tip-reads = • Read operations (read_file, list_directory) are always allowed
+help-file-permissions-tip-reset = • Session permissions are reset when you restart the agent
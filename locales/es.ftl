@@ -0,0 +1,252 @@
+# Spanish messages. Anything missing here is shown in English.
+
+## Permission prompts
+
+permission-select-option = Selecciona una opción
+permission-selection = Selección: { $selection }
+
+permission-bash-summary = El comando necesita permiso
+permission-bash-title = 🛡️ Control de seguridad
+permission-bash-intro = El siguiente comando no está en la lista de permitidos:
+permission-bash-allow-once = Permitir solo esta vez (sin añadirlo a la lista de permitidos)
+permission-bash-allow-always = Permitir y añadir a la lista de permitidos
+permission-bash-allow-wildcard = Permitir y añadir a la lista de permitidos con comodín: '{ $pattern }'
+permission-bash-deny = Denegar este comando
+permission-bash-allowed-once = Comando permitido solo esta vez
+permission-bash-allowed-always = Comando permitido y añadido a la lista de permitidos
+permission-bash-wildcard-added = Patrón con comodín añadido a la lista de permitidos: '{ $pattern }'
+permission-bash-denied = Comando denegado
+permission-bash-invalid = Selección no válida; por seguridad se deniega el comando
+permission-bash-input-failed = No se pudo leer la respuesta; por seguridad se deniega el comando
+
+permission-file-summary = La operación de archivo necesita permiso
+permission-file-title = 🛡️ Control de seguridad de archivos
+permission-file-intro = La siguiente operación de archivo necesita permiso:
+permission-file-operation = Operación: { $operation }
+permission-file-path = Ruta: { $path }
+permission-file-files = Archivos:
+permission-file-allow-once = Permitir solo esta operación
+permission-file-allow-session = Permitir todas las operaciones de archivo en esta sesión
+permission-file-deny = Denegar esta operación
+permission-file-allowed-once = Operación de archivo permitida solo esta vez
+permission-file-allowed-session = Todas las operaciones de archivo permitidas en esta sesión
+permission-file-denied = Operación de archivo denegada
+permission-file-invalid = Selección no válida; por seguridad se deniega la operación de archivo
+permission-file-input-failed = No se pudo leer la respuesta; por seguridad se deniega la operación de archivo
+
+permission-script-commands = Comandos que se añaden al script:
+permission-workflow-commands = Comandos que se añaden al workflow de CI:
+permission-script-more = …y { $count } más
+permission-script-note = Se ejecutarán más tarde, cada vez que se ejecute el script o el workflow.
+permission-script-risky-summary = La operación de archivo añade comandos con: { $risks }
+permission-risk-network = llamada de red
+permission-risk-privilege = escalada de privilegios
+
+permission-sampling-summary = El servidor MCP '{ $server }' quiere enviar una petición al modelo
+permission-sampling-title = 🛡️ Petición de muestreo MCP
+permission-sampling-intro = El servidor MCP '{ $server }' quiere enviar estos mensajes al modelo:
+permission-sampling-allow-once = Permitir esta petición
+permission-sampling-allow-session = Permitir todas las peticiones de '{ $server }' en esta sesión
+permission-sampling-deny = Denegar
+
+permission-question-summary = El asistente tiene una pregunta
+permission-question-title = ❓ Pregunta
+permission-question-other = Ninguna, responderé en mi próximo mensaje
+permission-question-reply = Responderé en mi próximo mensaje
+
+permission-mcp-add-summary = ¿Añadir el servidor MCP { $server }?
+permission-mcp-add-allow = Añadirlo y conectarlo
+permission-mcp-add-deny = No añadirlo
+
+## YOLO mode warnings
+
+yolo-title = ⚠️  ADVERTENCIA: MODO YOLO ACTIVADO  ⚠️
+yolo-subtitle = SE OMITEN TODOS LOS PERMISOS DE SEGURIDAD - ÚSALO CON EXTREMA PRECAUCIÓN
+yolo-files = • Las operaciones de archivo (leer/escribir/borrar) se ejecutarán SIN preguntar
+yolo-bash = • Los comandos bash se ejecutarán SIN comprobar permisos
+yolo-mcp = • Las herramientas MCP se ejecutarán SIN validación de seguridad
+yolo-lists = • No se aplicará ningún filtro de listas de permitidos o denegados
+yolo-auto-approve = • Todas las llamadas a herramientas se aprueban automáticamente
+yolo-damage = 🚨 ¡Este modo puede causar daños irreversibles en tu sistema!
+yolo-cancel = ¡Pulsa Ctrl+C AHORA para cancelar si no era tu intención!
+yolo-proceeding = 🔥 Continuando en modo YOLO... ¡Estás avisado! 🔥
+yolo-mcp-title = 🔌 Configuración MCP completa - Modo YOLO activo 🔌
+yolo-mcp-subtitle = ⚠️  LAS HERRAMIENTAS MCP SE EJECUTARÁN SIN VALIDACIÓN DE SEGURIDAD ⚠️
+yolo-mcp-prompts = • Las herramientas de los servidores MCP ya están disponibles y se ejecutarán SIN preguntar
+yolo-mcp-checks = • No se comprobarán permisos en las llamadas a herramientas MCP
+yolo-mcp-auto-approve = • Todas las operaciones MCP (acceso a archivos, comandos, etc.) se aprueban automáticamente
+yolo-mcp-access = • Las conexiones a servidores MCP externos tienen acceso sin restricciones
+yolo-mcp-modify = 🚨 ¡Las herramientas MCP pueden acceder a tu sistema y modificarlo!
+yolo-mcp-active = 🔥 ¡Todos los servidores MCP y sus herramientas funcionan en modo YOLO! 🔥
+
+## /help
+
+help-title = 🤖 Flexorama - Comandos
+help-available-commands = Comandos disponibles:
+help-cmd-help = Muestra esta ayuda
+help-cmd-stats = Muestra las estadísticas de uso de tokens
+help-cmd-usage = Muestra las estadísticas de uso de tokens (alias de /stats)
+help-cmd-context = Muestra cómo se usa la ventana de contexto, elemento a elemento
+help-cmd-context-remove = Quita del contexto el archivo o la imagen n
+help-cmd-context-messages = Lista todos los mensajes del contexto
+help-cmd-provider = Muestra el proveedor, el modelo y la URL base activos
+help-cmd-model = Muestra o cambia el modelo activo
+help-cmd-search = Busca en conversaciones anteriores
+help-cmd-resume = Retoma una conversación anterior
+help-cmd-rename = Pone nombre a esta conversación para --resume <nombre>
+help-cmd-env = Define una variable de entorno para los comandos de shell de esta conversación
+help-cmd-history = Explora los prompts de sesiones anteriores para ejecutarlos, editarlos o copiarlos
+help-cmd-diff = Muestra los archivos cambiados en esta sesión en un panel junto a la salida
+help-cmd-checkpoints = Lista los commits creados tras los turnos que cambiaron archivos
+help-cmd-checkpoints-revert = Deshace los cambios de un checkpoint (el último por defecto)
+help-cmd-pr = Sube esta rama y abre un pull request descrito a partir de la conversación
+help-cmd-share = Exporta esta conversación sin secretos y muestra un enlace a ella
+help-cmd-learn = Propone las convenciones de esta conversación como añadidos a AGENTS.md
+help-cmd-voice = Dicta un mensaje, transcrito localmente con whisper.cpp
+help-cmd-clear = Borra todo el contexto de la conversación (conserva AGENTS.md si existe)
+help-cmd-reset-stats = Reinicia las estadísticas de uso de tokens
+help-cmd-permissions = Gestiona los permisos de seguridad de los comandos bash
+help-cmd-file-permissions = Gestiona los permisos de seguridad de las operaciones de archivo
+help-cmd-hooks = Consulta y gestiona la configuración de hooks
+help-cmd-config-sources = Muestra de dónde sale cada ajuste de configuración
+help-cmd-config-get = Muestra los ajustes habituales (modelo, streaming, auto-aceptar, presupuesto)
+help-cmd-config-set = Cambia un ajuste y lo guarda en el archivo de configuración
+help-cmd-copy = Copia el bloque de código n (o el último) al portapapeles
+help-cmd-mcp = Gestiona los servidores MCP (Model Context Protocol)
+help-cmd-skill = Gestiona las skills (listar, crear, actualizar, borrar, desactivar)
+help-cmd-exit = Sale del programa
+
+help-navigation = Navegación:
+help-nav-history = Recorre el historial de entradas
+help-nav-cursor = Mueve el cursor a izquierda/derecha en la entrada
+help-nav-tab = Autocompleta rutas de archivo y comandos
+help-nav-search = Inicia la búsqueda inversa en el historial (como readline)
+help-nav-copy = Copia el último bloque de código al portapapeles
+help-nav-model = Abre el selector de modelos
+help-nav-cancel = Cancela la conversación en curso (durante el procesamiento)
+help-nav-exit = Sale del programa inmediatamente
+
+help-reverse-search = Búsqueda inversa (Ctrl+R):
+help-search-start = Inicia el modo de búsqueda inversa
+help-search-type = Escribe texto
+help-search-type-desc = Busca entradas coincidentes en el historial
+help-search-next = Busca la siguiente coincidencia
+help-search-navigate = Recorre las coincidencias
+help-search-accept = Acepta la coincidencia actual
+help-search-cancel = Cancela la búsqueda y restaura la entrada original
+help-search-backspace = Borra el último carácter de la búsqueda
+
+help-shell-commands = Comandos de shell:
+help-shell-run = Ejecuta un comando de shell directamente (sin ningún control de seguridad)
+help-shell-examples = Ejemplos: { $examples }
+help-shell-note = Nota: los comandos con ! se ejecutan al momento sin comprobar permisos
+
+help-security-commands = Comandos de seguridad:
+help-sec-permissions = Muestra los ajustes de seguridad de bash
+help-sec-file-permissions = Muestra los ajustes de seguridad de archivos
+help-sec-allow = Añade un comando a la lista de permitidos
+help-sec-deny = Añade un comando a la lista de denegados
+help-sec-test = Comprueba si un comando está permitido
+help-sec-file-test = Comprueba si una operación de archivo está permitida
+help-sec-plan = Activa o desactiva el modo plan
+help-sec-plan-run = Carga y ejecuta un plan guardado por su ID
+
+help-mcp-commands = Comandos MCP:
+help-mcp-list = Lista los servidores MCP
+help-mcp-add-stdio = Añade un servidor stdio
+help-mcp-add-ws = Añade un servidor WebSocket
+help-mcp-add-http = Añade un servidor HTTP
+help-mcp-add-sse = Añade un servidor SSE antiguo
+help-mcp-test = Comprueba si un comando está disponible
+help-mcp-connect = Conecta con un servidor
+help-mcp-tools = Lista las herramientas disponibles
+help-mcp-help = Muestra la ayuda de MCP
+
+help-context-files = Archivos de contexto:
+help-context-flag = Usa -f o --file para incluir archivos como contexto
+help-context-mention = Usa la sintaxis @ruta/al/archivo en los mensajes para incluir archivos
+help-context-agents = AGENTS.md se incluye automáticamente desde ~/.flexorama/AGENTS.md (prioritario)
+help-context-agents-fallback = Si no existe en el directorio personal, se usa ./AGENTS.md
+help-context-only = Los mensajes que solo tienen referencias @archivo NO llaman a la API
+
+help-system-prompts = Prompts de sistema:
+help-system-flag = Usa -s o --system para fijar un prompt de sistema propio
+help-system-purpose = El prompt de sistema define el comportamiento y la personalidad de la IA
+
+help-streaming = Streaming:
+help-streaming-default = El streaming está activado por defecto en todos los modos de CLI
+help-streaming-disable = Usa --no-stream para desactivarlo (indicador de espera + respuesta formateada)
+
+help-plan-mode = Modo plan:
+help-plan-flag = Usa --plan-mode para generar un plan de solo lectura en Markdown
+help-plan-readonly = El modo plan desactiva las herramientas que modifican y guarda el plan en la base de datos
+
+help-examples = Ejemplos:
+
+help-history = Navegación por el historial:
+help-history-up = • Pulsa la flecha ARRIBA para recorrer los comandos anteriores
+help-history-down = • Pulsa la flecha ABAJO para ir a los comandos más recientes
+help-history-search = • Pulsa Ctrl+R para iniciar la búsqueda inversa
+help-history-typing = • Empieza a escribir para salir de la navegación por el historial
+help-history-session = • El historial se conserva durante toda la sesión
+help-history-duplicates = • No se guardan comandos duplicados ni vacíos
+
+help-footer = Cualquier otra entrada se envía a Flexorama para que la procese.
+
+## /permissions help
+
+help-permissions-title = 🔒 Comandos de permisos
+help-permissions-view = Ver permisos:
+help-permissions-show = Muestra los permisos y ajustes de seguridad actuales
+help-permissions-alias = Alias de /permissions
+help-permissions-help = Muestra esta ayuda
+help-permissions-allowlist = Lista de permitidos:
+help-permissions-allow = Añade un comando a la lista de permitidos
+help-permissions-remove-allow = Quita un comando de la lista de permitidos
+help-permissions-denylist = Lista de denegados:
+help-permissions-deny = Añade un comando a la lista de denegados
+help-permissions-remove-deny = Quita un comando de la lista de denegados
+help-permissions-settings = Ajustes de seguridad:
+help-permissions-enable = Activa la seguridad de bash
+help-permissions-disable = Desactiva la seguridad de bash
+help-permissions-ask-on = Activa la petición de permiso
+help-permissions-ask-off = Desactiva la petición de permiso
+help-permissions-testing = Pruebas:
+help-permissions-test = Comprueba si un comando está permitido
+help-permissions-patterns = Coincidencia de patrones:
+help-permissions-pattern-wildcard = • Usa comodines: 'git *' permite todos los comandos git
+help-permissions-pattern-exact = • Usa coincidencia exacta: 'cargo test' permite solo ese comando
+help-permissions-pattern-prefix = • Coincidencia por prefijo: 'git' coincide con 'git status', 'git log', etc.
+help-permissions-example-allow = Permite todos los comandos git
+help-permissions-example-deny = Deniega los peligrosos comandos rm
+help-permissions-example-test = Comprueba si ls -la está permitido
+help-permissions-example-enable = Activa la seguridad
+help-permissions-example-ask = Pregunta por los comandos desconocidos
+help-permissions-tips = Consejos de seguridad:
+help-permissions-tip-specific = • Sé específico en la lista de permitidos para mayor seguridad
+help-permissions-tip-denylist = • Usa la lista de denegados para patrones de comandos peligrosos
+help-permissions-tip-ask = • Activa la petición de permiso para los comandos desconocidos
+help-permissions-tip-saved = • Los cambios se guardan automáticamente en el archivo de configuración
+
+## /file-permissions help
+
+help-file-permissions-title = 🔒 Comandos de permisos de archivos
+help-file-permissions-view = Ver permisos de archivos:
+help-file-permissions-show = Muestra los permisos de archivos y ajustes de seguridad actuales
+help-file-permissions-alias = Alias de /file-permissions
+help-file-permissions-test = Comprueba si una operación de archivo está permitida
+help-file-permissions-operations = Operaciones: { $operations }
+help-file-permissions-enable = Activa la seguridad de archivos
+help-file-permissions-disable = Desactiva la seguridad de archivos
+help-file-permissions-reset = Reinicia los permisos de la sesión
+help-file-permissions-options = Opciones de permiso:
+help-file-permissions-options-intro = Cuando una operación de archivo necesita permiso, puedes elegir:
+help-file-permissions-option-once = • Permitir solo esta operación - Permiso puntual
+help-file-permissions-option-session = • Permitir todas las operaciones de archivo en esta sesión - Permiso para toda la sesión
+help-file-permissions-option-deny = • Denegar esta operación - Bloquea la operación
+help-file-permissions-tip-ask = • Activa la petición de permiso para mayor seguridad
+help-file-permissions-tip-once = • Usa 'Permitir solo esta operación' para ediciones puntuales
+help-file-permissions-tip-session = • Usa 'Permitir todas las operaciones de archivo en esta sesión' en sesiones de confianza
+help-file-permissions-tip-operations = • Las operaciones de archivo incluyen: { $operations }
+help-file-permissions-tip-reads = • Las lecturas (read_file, list_directory) siempre están permitidas
+help-file-permissions-tip-reset = • Los permisos de la sesión se reinician al reiniciar el agente
@@ -0,0 +1,252 @@
+# French messages. Anything missing here is shown in English.
+
+## Permission prompts
+
+permission-select-option = Choisissez une option
+permission-selection = Sélection : { $selection }
+
+permission-bash-summary = La commande nécessite une autorisation
+permission-bash-title = 🛡️ Contrôle de sécurité
+permission-bash-intro = La commande suivante ne figure pas dans la liste autorisée :
+permission-bash-allow-once = Autoriser cette fois seulement (sans l'ajouter à la liste autorisée)
+permission-bash-allow-always = Autoriser et ajouter à la liste autorisée
+permission-bash-allow-wildcard = Autoriser et ajouter à la liste autorisée avec joker : '{ $pattern }'
+permission-bash-deny = Refuser cette commande
+permission-bash-allowed-once = Commande autorisée pour cette fois seulement
+permission-bash-allowed-always = Commande autorisée et ajoutée à la liste autorisée
+permission-bash-wildcard-added = Motif avec joker ajouté à la liste autorisée : '{ $pattern }'
+permission-bash-denied = Commande refusée
+permission-bash-invalid = Sélection invalide, commande refusée par sécurité
+permission-bash-input-failed = Impossible de lire la réponse, commande refusée par sécurité
+
+permission-file-summary = L'opération sur le fichier nécessite une autorisation
+permission-file-title = 🛡️ Contrôle de sécurité des fichiers
+permission-file-intro = L'opération sur le fichier suivante nécessite une autorisation :
+permission-file-operation = Opération : { $operation }
+permission-file-path = Chemin : { $path }
+permission-file-files = Fichiers :
+permission-file-allow-once = Autoriser cette opération seulement
+permission-file-allow-session = Autoriser toutes les opérations sur les fichiers pour cette session
+permission-file-deny = Refuser cette opération
+permission-file-allowed-once = Opération sur le fichier autorisée pour cette fois seulement
+permission-file-allowed-session = Toutes les opérations sur les fichiers sont autorisées pour cette session
+permission-file-denied = Opération sur le fichier refusée
+permission-file-invalid = Sélection invalide, opération sur le fichier refusée par sécurité
+permission-file-input-failed = Impossible de lire la réponse, opération sur le fichier refusée par sécurité
+
+permission-script-commands = Commandes ajoutées au script :
+permission-workflow-commands = Commandes ajoutées au workflow de CI :
+permission-script-more = …et { $count } de plus
+permission-script-note = Elles s'exécuteront plus tard, à chaque exécution du script ou du workflow.
+permission-script-risky-summary = L'opération sur le fichier ajoute des commandes avec : { $risks }
+permission-risk-network = appel réseau
+permission-risk-privilege = élévation de privilèges
+
+permission-sampling-summary = Le serveur MCP '{ $server }' veut envoyer une requête au modèle
+permission-sampling-title = 🛡️ Requête d'échantillonnage MCP
+permission-sampling-intro = Le serveur MCP '{ $server }' veut envoyer ces messages au modèle :
+permission-sampling-allow-once = Autoriser cette requête
+permission-sampling-allow-session = Autoriser toutes les requêtes de '{ $server }' pour cette session
+permission-sampling-deny = Refuser
+
+permission-question-summary = L'assistant a une question
+permission-question-title = ❓ Question
+permission-question-other = Aucune, je répondrai dans mon prochain message
+permission-question-reply = Je répondrai dans mon prochain message
+
+permission-mcp-add-summary = Ajouter le serveur MCP { $server } ?
+permission-mcp-add-allow = L'ajouter et le connecter
+permission-mcp-add-deny = Ne pas l'ajouter
+
+## YOLO mode warnings
+
+yolo-title = ⚠️  ATTENTION : MODE YOLO ACTIVÉ  ⚠️
+yolo-subtitle = TOUTES LES AUTORISATIONS DE SÉCURITÉ SONT IGNORÉES - À UTILISER AVEC LA PLUS GRANDE PRUDENCE
+yolo-files = • Les opérations sur les fichiers (lecture/écriture/suppression) s'exécuteront SANS confirmation
+yolo-bash = • Les commandes bash s'exécuteront SANS vérification des autorisations
+yolo-mcp = • Les outils MCP s'exécuteront SANS validation de sécurité
+yolo-lists = • Aucun filtrage par liste autorisée ou refusée ne sera appliqué
+yolo-auto-approve = • Tous les appels d'outils sont approuvés automatiquement
+yolo-damage = 🚨 Ce mode peut causer des dommages irréversibles à votre système !
+yolo-cancel = Appuyez sur Ctrl+C MAINTENANT pour annuler si ce n'était pas voulu !
+yolo-proceeding = 🔥 Poursuite en mode YOLO... Vous êtes prévenu ! 🔥
+yolo-mcp-title = 🔌 Configuration MCP terminée - Mode YOLO actif 🔌
+yolo-mcp-subtitle = ⚠️  LES OUTILS MCP S'EXÉCUTERONT SANS VALIDATION DE SÉCURITÉ ⚠️
+yolo-mcp-prompts = • Les outils des serveurs MCP sont disponibles et s'exécuteront SANS confirmation
+yolo-mcp-checks = • Aucune vérification d'autorisation ne sera appliquée aux appels d'outils MCP
+yolo-mcp-auto-approve = • Toutes les opérations MCP (accès aux fichiers, commandes, etc.) sont approuvées automatiquement
+yolo-mcp-access = • Les connexions aux serveurs MCP externes ont un accès illimité
+yolo-mcp-modify = 🚨 Les outils MCP peuvent accéder à votre système et le modifier !
+yolo-mcp-active = 🔥 Tous les serveurs MCP et leurs outils fonctionnent en mode YOLO ! 🔥
+
+## /help
+
+help-title = 🤖 Flexorama - Commandes
+help-available-commands = Commandes disponibles :
+help-cmd-help = Affiche cette aide
+help-cmd-stats = Affiche les statistiques d'utilisation des tokens
+help-cmd-usage = Affiche les statistiques d'utilisation des tokens (alias de /stats)
+help-cmd-context = Montre comment la fenêtre de contexte est utilisée, élément par élément
+help-cmd-context-remove = Retire du contexte le fichier ou l'image n
+help-cmd-context-messages = Liste tous les messages du contexte
+help-cmd-provider = Affiche le fournisseur, le modèle et l'URL de base actifs
+help-cmd-model = Affiche ou change le modèle actif
+help-cmd-search = Recherche dans les conversations précédentes
+help-cmd-resume = Reprend une conversation précédente
+help-cmd-rename = Nomme cette conversation pour --resume <nom>
+help-cmd-env = Définit une variable d'environnement pour les commandes shell de cette conversation
+help-cmd-history = Parcourt les prompts des sessions précédentes pour les relancer, modifier ou copier
+help-cmd-diff = Affiche les fichiers modifiés pendant la session dans un panneau à côté de la sortie
+help-cmd-checkpoints = Liste les commits créés après les tours qui ont modifié des fichiers
+help-cmd-checkpoints-revert = Annule les modifications d'un checkpoint (le dernier par défaut)
+help-cmd-pr = Pousse cette branche et ouvre une pull request décrite à partir de la conversation
+help-cmd-share = Exporte cette conversation sans les secrets et affiche un lien vers elle
+help-cmd-learn = Propose les conventions de cette conversation comme ajouts à AGENTS.md
+help-cmd-voice = Dicte un message, transcrit localement avec whisper.cpp
+help-cmd-clear = Efface tout le contexte de la conversation (conserve AGENTS.md s'il existe)
+help-cmd-reset-stats = Réinitialise les statistiques d'utilisation des tokens
+help-cmd-permissions = Gère les autorisations de sécurité des commandes bash
+help-cmd-file-permissions = Gère les autorisations de sécurité des opérations sur les fichiers
+help-cmd-hooks = Affiche et gère la configuration des hooks
+help-cmd-config-sources = Indique d'où vient chaque réglage de configuration
+help-cmd-config-get = Affiche les réglages courants (modèle, streaming, acceptation auto, budget)
+help-cmd-config-set = Modifie un réglage et l'enregistre dans le fichier de configuration
+help-cmd-copy = Copie le bloc de code n (ou le dernier) dans le presse-papiers
+help-cmd-mcp = Gère les serveurs MCP (Model Context Protocol)
+help-cmd-skill = Gère les skills (lister, créer, mettre à jour, supprimer, désactiver)
+help-cmd-exit = Quitte le programme
+
+help-navigation = Navigation :
+help-nav-history = Parcourt l'historique des saisies
+help-nav-cursor = Déplace le curseur à gauche/droite dans la saisie
+help-nav-tab = Complète les chemins de fichiers et les commandes
+help-nav-search = Lance la recherche inversée dans l'historique (comme readline)
+help-nav-copy = Copie le dernier bloc de code dans le presse-papiers
+help-nav-model = Ouvre le sélecteur de modèles
+help-nav-cancel = Annule la conversation en cours (pendant le traitement)
+help-nav-exit = Quitte le programme immédiatement
+
+help-reverse-search = Recherche inversée (Ctrl+R) :
+help-search-start = Lance le mode de recherche inversée
+help-search-type = Tapez du texte
+help-search-type-desc = Cherche les entrées correspondantes de l'historique
+help-search-next = Trouve la correspondance suivante
+help-search-navigate = Parcourt les correspondances
+help-search-accept = Accepte la correspondance actuelle
+help-search-cancel = Annule la recherche et restaure la saisie d'origine
+help-search-backspace = Supprime le dernier caractère de la recherche
+
+help-shell-commands = Commandes shell :
+help-shell-run = Exécute directement une commande shell (sans aucun contrôle de sécurité)
+help-shell-examples = Exemples : { $examples }
+help-shell-note = Remarque : les commandes ! s'exécutent immédiatement sans vérification des autorisations
+
+help-security-commands = Commandes de sécurité :
+help-sec-permissions = Affiche les réglages de sécurité bash
+help-sec-file-permissions = Affiche les réglages de sécurité des fichiers
+help-sec-allow = Ajoute une commande à la liste autorisée
+help-sec-deny = Ajoute une commande à la liste refusée
+help-sec-test = Vérifie si une commande est autorisée
+help-sec-file-test = Vérifie si une opération sur un fichier est autorisée
+help-sec-plan = Active ou désactive le mode plan
+help-sec-plan-run = Charge et exécute un plan enregistré par son ID
+
+help-mcp-commands = Commandes MCP :
+help-mcp-list = Liste les serveurs MCP
+help-mcp-add-stdio = Ajoute un serveur stdio
+help-mcp-add-ws = Ajoute un serveur WebSocket
+help-mcp-add-http = Ajoute un serveur HTTP
+help-mcp-add-sse = Ajoute un ancien serveur SSE
+help-mcp-test = Vérifie si une commande est disponible
+help-mcp-connect = Se connecte à un serveur
+help-mcp-tools = Liste les outils disponibles
+help-mcp-help = Affiche l'aide MCP
+
+help-context-files = Fichiers de contexte :
+help-context-flag = Utilisez -f ou --file pour inclure des fichiers comme contexte
+help-context-mention = Utilisez la syntaxe @chemin/vers/fichier dans les messages pour inclure des fichiers
+help-context-agents = AGENTS.md est inclus automatiquement depuis ~/.flexorama/AGENTS.md (prioritaire)
+help-context-agents-fallback = À défaut, ./AGENTS.md est utilisé
+help-context-only = Les messages ne contenant que des références @fichier n'appellent PAS l'API
+
+help-system-prompts = Prompts système :
+help-system-flag = Utilisez -s ou --system pour définir un prompt système personnalisé
+help-system-purpose = Le prompt système définit le comportement et la personnalité de l'IA
+
+help-streaming = Streaming :
+help-streaming-default = Le streaming est activé par défaut dans tous les modes CLI
+help-streaming-disable = Utilisez --no-stream pour le désactiver (indicateur d'attente + réponse mise en forme)
+
+help-plan-mode = Mode plan :
+help-plan-flag = Utilisez --plan-mode pour générer un plan en lecture seule en Markdown
+help-plan-readonly = Le mode plan désactive les outils qui modifient et enregistre le plan dans la base de données
+
+help-examples = Exemples :
+
+help-history = Navigation dans l'historique :
+help-history-up = • Flèche HAUT pour parcourir les commandes précédentes
+help-history-down = • Flèche BAS pour revenir aux commandes plus récentes
+help-history-search = • Ctrl+R pour lancer la recherche inversée
+help-history-typing = • Commencez à taper pour quitter la navigation dans l'historique
+help-history-session = • L'historique est conservé pendant toute la session
+help-history-duplicates = • Les commandes en double et vides ne sont pas enregistrées
+
+help-footer = Toute autre saisie est envoyée à Flexorama pour traitement.
+
+## /permissions help
+
+help-permissions-title = 🔒 Commandes d'autorisations
+help-permissions-view = Afficher les autorisations :
+help-permissions-show = Affiche les autorisations et réglages de sécurité actuels
+help-permissions-alias = Alias de /permissions
+help-permissions-help = Affiche cette aide
+help-permissions-allowlist = Liste autorisée :
+help-permissions-allow = Ajoute une commande à la liste autorisée
+help-permissions-remove-allow = Retire une commande de la liste autorisée
+help-permissions-denylist = Liste refusée :
+help-permissions-deny = Ajoute une commande à la liste refusée
+help-permissions-remove-deny = Retire une commande de la liste refusée
+help-permissions-settings = Réglages de sécurité :
+help-permissions-enable = Active la sécurité bash
+help-permissions-disable = Désactive la sécurité bash
+help-permissions-ask-on = Active la demande d'autorisation
+help-permissions-ask-off = Désactive la demande d'autorisation
+help-permissions-testing = Tests :
+help-permissions-test = Vérifie si une commande est autorisée
+help-permissions-patterns = Correspondance de motifs :
+help-permissions-pattern-wildcard = • Jokers : 'git *' autorise toutes les commandes git
+help-permissions-pattern-exact = • Correspondance exacte : 'cargo test' n'autorise que cette commande
+help-permissions-pattern-prefix = • Préfixe : 'git' correspond à 'git status', 'git log', etc.
+help-permissions-example-allow = Autorise toutes les commandes git
+help-permissions-example-deny = Refuse les commandes rm dangereuses
+help-permissions-example-test = Vérifie si ls -la est autorisée
+help-permissions-example-enable = Active la sécurité
+help-permissions-example-ask = Demande pour les commandes inconnues
+help-permissions-tips = Conseils de sécurité :
+help-permissions-tip-specific = • Soyez précis dans la liste autorisée pour plus de sécurité
+help-permissions-tip-denylist = • Utilisez la liste refusée pour les motifs de commandes dangereuses
+help-permissions-tip-ask = • Activez la demande d'autorisation pour les commandes inconnues
+help-permissions-tip-saved = • Les modifications sont enregistrées automatiquement dans le fichier de configuration
+
+## /file-permissions help
+
+help-file-permissions-title = 🔒 Commandes d'autorisations des fichiers
+help-file-permissions-view = Afficher les autorisations des fichiers :
+help-file-permissions-show = Affiche les autorisations des fichiers et les réglages de sécurité actuels
+help-file-permissions-alias = Alias de /file-permissions
+help-file-permissions-test = Vérifie si une opération sur un fichier est autorisée
+help-file-permissions-operations = Opérations : { $operations }
+help-file-permissions-enable = Active la sécurité des fichiers
+help-file-permissions-disable = Désactive la sécurité des fichiers
+help-file-permissions-reset = Réinitialise les autorisations de la session
+help-file-permissions-options = Options d'autorisation :
+help-file-permissions-options-intro = Quand une opération sur un fichier nécessite une autorisation, vous pouvez choisir :
+help-file-permissions-option-once = • Autoriser cette opération seulement - Autorisation ponctuelle
+help-file-permissions-option-session = • Autoriser toutes les opérations sur les fichiers pour cette session - Autorisation pour la session
+help-file-permissions-option-deny = • Refuser cette opération - Bloque l'opération
+help-file-permissions-tip-ask = • Activez la demande d'autorisation pour plus de sécurité
+help-file-permissions-tip-once = • Utilisez 'Autoriser cette opération seulement' pour les modifications ponctuelles
+help-file-permissions-tip-session = • Utilisez 'Autoriser toutes les opérations sur les fichiers pour cette session' pour les sessions de confiance
+help-file-permissions-tip-operations = • Les opérations sur les fichiers comprennent : { $operations }
+help-file-permissions-tip-reads = • Les lectures (read_file, list_directory) sont toujours autorisées
+help-file-permissions-tip-reset = • Les autorisations de la session sont réinitialisées au redémarrage de l'agent
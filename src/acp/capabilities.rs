@@ -70,6 +70,28 @@ pub struct ClientCapabilities {
     /// Progress report support
     #[serde(skip_serializing_if = "Option::is_none")]
     pub progress: Option<bool>,
+
+    /// File system methods the client implements for the agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fs: Option<ClientFileSystemCapabilities>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientFileSystemCapabilities {
+    /// Client answers `fs/read_text_file`
+    #[serde(default)]
+    pub read_text_file: bool,
+    /// Client answers `fs/write_text_file`, applying the change to its buffers
+    #[serde(default)]
+    pub write_text_file: bool,
+}
+
+impl ClientCapabilities {
+    /// Whether file writes can be handed to the client instead of touching the disk
+    pub fn can_write_text_files(&self) -> bool {
+        self.fs.as_ref().is_some_and(|fs| fs.write_text_file)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -246,6 +268,7 @@ mod tests {
             }),
             permissions: Some(true),
             progress: Some(true),
+            fs: None,
         };
 
         let serialized = serde_json::to_string(&client_caps).unwrap();
@@ -265,6 +288,7 @@ mod tests {
             }),
             permissions: Some(true),
             progress: Some(false),
+            fs: None,
         };
 
         caps.negotiate(&client_caps);
@@ -274,6 +298,20 @@ mod tests {
         assert!(caps.file_system.is_some());
     }
 
+    #[test]
+    fn test_client_file_system_capabilities() {
+        let caps: ClientCapabilities = serde_json::from_value(serde_json::json!({
+            "fs": { "readTextFile": true, "writeTextFile": true },
+            "terminal": true
+        }))
+        .unwrap();
+        assert!(caps.can_write_text_files());
+
+        let caps: ClientCapabilities =
+            serde_json::from_value(serde_json::json!({ "fs": { "readTextFile": true } })).unwrap();
+        assert!(!caps.can_write_text_files());
+    }
+
     #[test]
     fn test_workspace_capabilities() {
         let workspace_caps = WorkspaceCapabilities {
@@ -306,6 +344,7 @@ mod tests {
             workspace: None,
             permissions: None,
             progress: None,
+            fs: None,
         };
 
         let serialized = serde_json::to_string(&caps).unwrap();
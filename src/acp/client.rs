@@ -0,0 +1,415 @@
+use crate::acp::errors::{AcpError, AcpResult};
+use crate::acp::types::{JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
+use crate::security::{
+    FileWrite, FileWriteHandler, PermissionHandler, PermissionKind, PermissionPrompt,
+};
+use agent_client_protocol_schema::{
+    ContentBlock, Diff, PermissionOption, PermissionOptionId, PermissionOptionKind,
+    RequestPermissionOutcome, RequestPermissionRequest, RequestPermissionResponse, SessionId,
+    SessionNotification, SessionUpdate, TextContent, ToolCall, ToolCallContent, ToolCallId,
+    ToolCallLocation, ToolCallStatus, ToolCallUpdate, ToolCallUpdateFields, ToolKind,
+    WriteTextFileRequest,
+};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use uuid::Uuid;
+
+/// Sends requests from the agent to the connected client (the editor) and
+/// matches up the client's responses
+pub struct AcpClient {
+    outgoing: mpsc::UnboundedSender<JsonRpcMessage>,
+    pending: Mutex<HashMap<String, oneshot::Sender<JsonRpcResponse>>>,
+    next_id: AtomicU64,
+}
+
+impl AcpClient {
+    pub fn new(outgoing: mpsc::UnboundedSender<JsonRpcMessage>) -> Self {
+        Self {
+            outgoing,
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Send a request to the client and wait for its result
+    pub async fn request(&self, method: &str, params: Value) -> AcpResult<Value> {
+        let id = format!("flexorama-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id.clone(), tx);
+
+        debug!("Sending {} request to client ({})", method, id);
+        let request = JsonRpcMessage::Request(JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(id)),
+            method: method.to_string(),
+            params: Some(params),
+        });
+        if self.outgoing.send(request).is_err() {
+            self.pending.lock().await.remove(&id);
+            return Err(AcpError::Unknown("Client connection is closed".to_string()));
+        }
+
+        let response = rx.await.map_err(|_| {
+            AcpError::Unknown(format!("Client disconnected before answering {}", method))
+        })?;
+        match response.error {
+            Some(error) => Err(AcpError::Agent(anyhow::anyhow!(
+                "Client rejected {}: {}",
+                method,
+                error.message
+            ))),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
+
+    /// Send a notification to the client
+    pub fn notify(&self, method: &str, params: Value) {
+        let notification = JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+        });
+        if self.outgoing.send(notification).is_err() {
+            warn!(
+                "Client connection is closed, dropping {} notification",
+                method
+            );
+        }
+    }
+
+    /// Deliver a response from the client to the request waiting for it.
+    /// Returns false if no request with that id is pending.
+    pub async fn handle_response(&self, response: JsonRpcResponse) -> bool {
+        let id = match &response.id {
+            Some(Value::String(id)) => id.clone(),
+            Some(other) => other.to_string(),
+            None => return false,
+        };
+        match self.pending.lock().await.remove(&id) {
+            Some(sender) => {
+                let _ = sender.send(response);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fail every outstanding request, e.g. once the client has gone away
+    pub async fn fail_pending(&self) {
+        self.pending.lock().await.clear();
+    }
+}
+
+/// Write handler that hands file changes to the editor with `fs/write_text_file`,
+/// announcing each one as an edit tool call with a diff so the editor can show
+/// it in its own review UI
+pub fn editor_write_handler(client: Arc<AcpClient>, session_id: String) -> FileWriteHandler {
+    Arc::new(move |write: FileWrite| {
+        let client = client.clone();
+        let session_id = session_id.clone();
+        Box::pin(async move {
+            let notification = SessionNotification {
+                session_id: SessionId::from(session_id.clone()),
+                update: SessionUpdate::ToolCall(edit_tool_call(&write)),
+                meta: None,
+            };
+            client.notify("session/update", serde_json::to_value(notification)?);
+
+            let request = WriteTextFileRequest {
+                session_id: SessionId::from(session_id),
+                path: write.path.clone(),
+                content: write.new_content,
+                meta: None,
+            };
+            client
+                .request("fs/write_text_file", serde_json::to_value(request)?)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            info!("Client applied edit to {}", write.path.display());
+            Ok(())
+        })
+    })
+}
+
+/// Permission handler that asks the editor with `session/request_permission`,
+/// so each session's prompts show up in the editor tab that owns it rather than
+/// on the terminal, whose stdin carries the protocol
+pub fn editor_permission_handler(client: Arc<AcpClient>, session_id: String) -> PermissionHandler {
+    Arc::new(move |prompt: PermissionPrompt| {
+        let client = client.clone();
+        let session_id = session_id.clone();
+        Box::pin(async move {
+            let request = permission_request(&session_id, &prompt);
+            let params = serde_json::to_value(request).ok()?;
+            let result = match client.request("session/request_permission", params).await {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("Permission request failed, denying: {}", e);
+                    return None;
+                }
+            };
+            match serde_json::from_value::<RequestPermissionResponse>(result) {
+                Ok(RequestPermissionResponse {
+                    outcome: RequestPermissionOutcome::Selected { option_id },
+                    ..
+                }) => option_id.0.parse().ok(),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("Malformed permission response, denying: {}", e);
+                    None
+                }
+            }
+        })
+    })
+}
+
+fn permission_request(session_id: &str, prompt: &PermissionPrompt) -> RequestPermissionRequest {
+    let kind = match prompt.kind {
+        PermissionKind::Bash => ToolKind::Execute,
+        PermissionKind::File => ToolKind::Edit,
+        PermissionKind::Sampling | PermissionKind::Question => ToolKind::Other,
+    };
+    // Option ids are the indexes the security managers expect back
+    let options = prompt
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, name)| PermissionOption {
+            id: PermissionOptionId::from(i.to_string()),
+            name: name.clone(),
+            kind: match prompt.kind {
+                // Answers, with the last one declining to pick
+                PermissionKind::Question if i + 1 < prompt.options.len() => {
+                    PermissionOptionKind::AllowOnce
+                }
+                PermissionKind::Question => PermissionOptionKind::RejectOnce,
+                _ => permission_option_kind(i, prompt.options.len()),
+            },
+            meta: None,
+        })
+        .collect();
+    RequestPermissionRequest {
+        session_id: SessionId::from(session_id.to_string()),
+        tool_call: ToolCallUpdate {
+            id: ToolCallId::from(format!("permission-{}", Uuid::new_v4())),
+            fields: ToolCallUpdateFields {
+                kind: Some(kind),
+                status: Some(ToolCallStatus::Pending),
+                title: Some(prompt.summary.clone()),
+                content: Some(vec![ToolCallContent::Content {
+                    content: ContentBlock::Text(TextContent {
+                        text: prompt.detail.clone(),
+                        annotations: None,
+                        meta: None,
+                    }),
+                }]),
+                ..Default::default()
+            },
+            meta: None,
+        },
+        options,
+        meta: None,
+    }
+}
+
+/// The option's meaning, for the editor's icons. Every prompt lists allowing
+/// once first and denying last, with the remembered choices in between; the
+/// labels can't be relied on since they're translated.
+fn permission_option_kind(index: usize, count: usize) -> PermissionOptionKind {
+    if index + 1 == count {
+        PermissionOptionKind::RejectOnce
+    } else if index == 0 {
+        PermissionOptionKind::AllowOnce
+    } else {
+        PermissionOptionKind::AllowAlways
+    }
+}
+
+fn edit_tool_call(write: &FileWrite) -> ToolCall {
+    let verb = if write.old_content.is_some() {
+        "Edit"
+    } else {
+        "Create"
+    };
+    ToolCall {
+        id: ToolCallId::from(format!("edit-{}", Uuid::new_v4())),
+        title: format!("{} {}", verb, write.path.display()),
+        kind: ToolKind::Edit,
+        status: ToolCallStatus::Completed,
+        content: vec![ToolCallContent::Diff {
+            diff: Diff {
+                path: write.path.clone(),
+                old_text: write.old_content.clone(),
+                new_text: write.new_content.clone(),
+                meta: None,
+            },
+        }],
+        locations: vec![ToolCallLocation {
+            path: write.path.clone(),
+            line: None,
+            meta: None,
+        }],
+        raw_input: None,
+        raw_output: None,
+        meta: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Play the editor: answer the first request with `reply` and return
+    /// everything sent up to and including it
+    fn fake_editor(
+        mut outgoing: mpsc::UnboundedReceiver<JsonRpcMessage>,
+        client: Arc<AcpClient>,
+        reply: Value,
+    ) -> tokio::task::JoinHandle<Vec<JsonRpcMessage>> {
+        tokio::spawn(async move {
+            let mut seen = Vec::new();
+            while let Some(message) = outgoing.recv().await {
+                let request_id = match &message {
+                    JsonRpcMessage::Request(request) => Some(request.id.clone()),
+                    _ => None,
+                };
+                seen.push(message);
+                if let Some(id) = request_id {
+                    client
+                        .handle_response(JsonRpcResponse::success(id, reply))
+                        .await;
+                    break;
+                }
+            }
+            seen
+        })
+    }
+
+    #[tokio::test]
+    async fn request_waits_for_matching_response() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client = Arc::new(AcpClient::new(tx));
+        let editor = fake_editor(rx, client.clone(), json!({ "ok": true }));
+
+        let result = client.request("ping", json!({})).await.unwrap();
+        assert_eq!(result, json!({ "ok": true }));
+        assert!(
+            !client
+                .handle_response(JsonRpcResponse::success(
+                    Some(json!("unknown")),
+                    json!(null)
+                ))
+                .await
+        );
+        assert_eq!(editor.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn editor_write_handler_sends_diff_then_write() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client = Arc::new(AcpClient::new(tx));
+        let editor = fake_editor(rx, client.clone(), Value::Null);
+
+        let handler = editor_write_handler(client.clone(), "session-1".to_string());
+        handler(FileWrite {
+            path: PathBuf::from("/work/src/main.rs"),
+            old_content: Some("fn main() {}\n".to_string()),
+            new_content: "fn main() { run() }\n".to_string(),
+        })
+        .await
+        .unwrap();
+
+        let seen = editor.await.unwrap();
+        match &seen[0] {
+            JsonRpcMessage::Notification(notification) => {
+                let params = notification.params.as_ref().unwrap();
+                assert_eq!(params["update"]["sessionUpdate"], "tool_call");
+                assert_eq!(params["update"]["kind"], "edit");
+                assert_eq!(params["update"]["content"][0]["type"], "diff");
+                assert_eq!(params["update"]["content"][0]["oldText"], "fn main() {}\n");
+            }
+            other => panic!("expected a notification, got {:?}", other),
+        }
+        match &seen[1] {
+            JsonRpcMessage::Request(request) => {
+                assert_eq!(request.method, "fs/write_text_file");
+                let params = request.params.as_ref().unwrap();
+                assert_eq!(params["sessionId"], "session-1");
+                assert_eq!(params["content"], "fn main() { run() }\n");
+            }
+            other => panic!("expected a request, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn permission_handler_returns_the_selected_option() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client = Arc::new(AcpClient::new(tx));
+        let editor = fake_editor(
+            rx,
+            client.clone(),
+            json!({ "outcome": { "outcome": "selected", "optionId": "2" } }),
+        );
+
+        let handler = editor_permission_handler(client, "session-1".to_string());
+        let selection = handler(PermissionPrompt {
+            kind: PermissionKind::Bash,
+            summary: "Command requires permission".to_string(),
+            detail: "rm -rf build".to_string(),
+            options: vec![
+                "Allow this time only (don't add to allowlist)".to_string(),
+                "Allow and add to allowlist".to_string(),
+                "Deny this command".to_string(),
+            ],
+        })
+        .await;
+        assert_eq!(selection, Some(2));
+
+        let seen = editor.await.unwrap();
+        let JsonRpcMessage::Request(request) = &seen[0] else {
+            panic!("expected a request");
+        };
+        assert_eq!(request.method, "session/request_permission");
+        let params = request.params.as_ref().unwrap();
+        assert_eq!(params["sessionId"], "session-1");
+        assert_eq!(params["toolCall"]["kind"], "execute");
+        assert_eq!(params["options"][1]["kind"], "allow_always");
+        assert_eq!(params["options"][2]["kind"], "reject_once");
+    }
+
+    #[tokio::test]
+    async fn client_errors_fail_the_write() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<JsonRpcMessage>();
+        let client = Arc::new(AcpClient::new(tx));
+        let responder = client.clone();
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                if let JsonRpcMessage::Request(request) = message {
+                    responder
+                        .handle_response(JsonRpcResponse::error(
+                            request.id,
+                            -32603,
+                            "buffer is read-only".to_string(),
+                            None,
+                        ))
+                        .await;
+                }
+            }
+        });
+
+        let handler = editor_write_handler(client, "session-1".to_string());
+        let err = handler(FileWrite {
+            path: PathBuf::from("/work/new.txt"),
+            old_content: None,
+            new_content: "hello".to_string(),
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("buffer is read-only"));
+    }
+}
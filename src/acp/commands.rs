@@ -0,0 +1,180 @@
+use crate::agent::Agent;
+use crate::custom_commands;
+use agent_client_protocol_schema::{AvailableCommand, AvailableCommandInput};
+use anyhow::Result;
+use log::warn;
+
+/// Built-in slash commands that make sense without a terminal:
+/// (name, description, input hint)
+const BUILTIN_COMMANDS: &[(&str, &str, Option<&str>)] = &[
+    (
+        "clear",
+        "Clear the conversation context (AGENTS.md is kept)",
+        None,
+    ),
+    ("plan", "Plan mode", Some("on | off | run <plan_id>")),
+    ("model", "Show or switch the active model", Some("<model>")),
+    ("stats", "Show token usage for this session", None),
+    ("reset-stats", "Reset token usage statistics", None),
+];
+
+/// What to do with a slash command sent as a prompt
+#[derive(Debug, PartialEq)]
+pub enum CommandOutcome {
+    /// The command ran; show this text to the user
+    Reply(String),
+    /// The command expands to a message for the agent
+    Prompt(String),
+}
+
+/// Commands to advertise to the client with an `available_commands_update`
+pub async fn available_commands() -> Vec<AvailableCommand> {
+    let mut commands: Vec<AvailableCommand> = BUILTIN_COMMANDS
+        .iter()
+        .map(|(name, description, hint)| AvailableCommand {
+            name: name.to_string(),
+            description: description.to_string(),
+            input: hint.map(|hint| AvailableCommandInput::Unstructured {
+                hint: hint.to_string(),
+            }),
+            meta: None,
+        })
+        .collect();
+
+    match custom_commands::list_custom_commands().await {
+        Ok(mut custom) => {
+            custom.sort_by(|a, b| a.name.cmp(&b.name));
+            for command in custom {
+                // Built-ins win, the same as in the terminal
+                if commands.iter().any(|c| c.name == command.name) {
+                    continue;
+                }
+                commands.push(AvailableCommand {
+                    description: command
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| "Custom command".to_string()),
+                    input: command
+                        .argument_hint
+                        .clone()
+                        .map(|hint| AvailableCommandInput::Unstructured { hint }),
+                    name: command.name,
+                    meta: None,
+                });
+            }
+        }
+        Err(e) => warn!("Failed to list custom commands for ACP: {}", e),
+    }
+
+    commands
+}
+
+/// Run a slash command typed in the editor. Returns `None` if the prompt isn't
+/// a command Flexorama knows, so it can go to the agent unchanged.
+pub async fn run_command(input: &str, agent: &mut Agent) -> Result<Option<CommandOutcome>> {
+    let trimmed = input.trim();
+    let Some(command) = trimmed.strip_prefix('/') else {
+        return Ok(None);
+    };
+    let (name, args) = match command.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (command, ""),
+    };
+
+    let reply = match name {
+        "clear" => {
+            agent.clear_conversation_keep_agents_md().await?;
+            "Conversation context cleared (AGENTS.md preserved if it existed).".to_string()
+        }
+        "plan" => {
+            let (sub, rest) = match args.split_once(char::is_whitespace) {
+                Some((sub, rest)) => (sub, rest.trim()),
+                None => (args, ""),
+            };
+            match sub {
+                "on" => {
+                    agent.set_plan_mode(true).await?;
+                    "Plan mode enabled: generating read-only plans and saving them to the database."
+                        .to_string()
+                }
+                "off" => {
+                    agent.set_plan_mode(false).await?;
+                    "Plan mode disabled: execution tools restored.".to_string()
+                }
+                "run" if !rest.is_empty() => {
+                    let message = agent.load_plan_for_execution(rest).await?;
+                    return Ok(Some(CommandOutcome::Prompt(message)));
+                }
+                "run" => "Usage: /plan run <plan_id>".to_string(),
+                _ => format!(
+                    "Plan mode is {}. Use '/plan on', '/plan off', or '/plan run <id>'.",
+                    if agent.plan_mode() { "on" } else { "off" }
+                ),
+            }
+        }
+        "model" if args.is_empty() => format!(
+            "Provider: {}\nCurrent model: {}",
+            agent.provider(),
+            agent.model()
+        ),
+        "model" => {
+            agent.set_model(args.to_string()).await?;
+            format!("Active model set to {}", args)
+        }
+        "stats" | "usage" => {
+            let usage = agent.get_token_usage();
+            format!(
+                "Requests made: {}\nInput tokens: {}\nOutput tokens: {}\nTotal tokens: {}",
+                usage.request_count,
+                usage.total_input_tokens,
+                usage.total_output_tokens,
+                usage.total_tokens()
+            )
+        }
+        "reset-stats" => {
+            agent.reset_token_usage();
+            "Token usage statistics reset.".to_string()
+        }
+        _ => match custom_commands::render_custom_command_input(trimmed).await {
+            Ok(Some(mut rendered)) => {
+                if let Err(e) = rendered
+                    .expand(
+                        &agent.get_bash_security_manager(),
+                        agent.yolo_mode(),
+                        agent.conversation_env(),
+                    )
+                    .await
+                {
+                    return Ok(Some(CommandOutcome::Reply(e.to_string())));
+                }
+                if let Some(model) = rendered.command.model.clone() {
+                    agent.set_model(model).await?;
+                }
+                return Ok(Some(CommandOutcome::Prompt(rendered.message)));
+            }
+            Ok(None) => return Ok(None),
+            Err(e) => e.to_string(),
+        },
+    };
+
+    Ok(Some(CommandOutcome::Reply(reply)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn advertises_builtin_commands_with_hints() {
+        let commands = available_commands().await;
+        let plan = commands.iter().find(|c| c.name == "plan").unwrap();
+        assert_eq!(
+            plan.input,
+            Some(AvailableCommandInput::Unstructured {
+                hint: "on | off | run <plan_id>".to_string()
+            })
+        );
+        let clear = commands.iter().find(|c| c.name == "clear").unwrap();
+        assert!(clear.input.is_none());
+    }
+}
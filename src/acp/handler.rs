@@ -1,25 +1,295 @@
 use crate::acp::capabilities::ClientCapabilities;
+use crate::acp::client::{editor_permission_handler, editor_write_handler, AcpClient};
+use crate::acp::commands::{self, CommandOutcome};
 use crate::acp::errors::{AcpError, AcpResult};
 use crate::acp::filesystem::FileSystemHandler;
-use crate::acp::session::SessionManager;
+use crate::acp::session::{SessionManager, SharedServices};
 use crate::acp::types::{
     JsonRpcError, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, NotificationSender,
 };
-use crate::agent::Agent;
+use crate::agent::{Agent, StreamToolEvent, STREAM_SCHEMA_VERSION};
 use crate::config::Config;
 use agent_client_protocol_schema::{
-    AgentCapabilities, ContentBlock, ContentChunk, Implementation, InitializeResponse,
-    McpCapabilities, NewSessionResponse, PromptCapabilities, PromptResponse, SessionId,
-    SessionNotification, SessionUpdate, StopReason, TextContent, V1 as PROTOCOL_V1,
+    AgentCapabilities, AvailableCommandsUpdate, ContentBlock, ContentChunk, Implementation,
+    InitializeResponse, McpCapabilities, NewSessionResponse, PromptCapabilities, PromptResponse,
+    SessionId, SessionNotification, SessionUpdate, StopReason, TextContent, V1 as PROTOCOL_V1,
 };
 use log::{debug, error, info, warn};
 use serde_json::{json, Value};
+use std::future::Future;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+/// Cancels prompts from outside the request loop, which is busy with the very
+/// prompt the editor's stop button is meant to interrupt
+#[derive(Clone)]
+pub struct CancelHandle {
+    sessions: SessionManager,
+    legacy_flag: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    /// Apply a `session/cancel` or `agent/cancel` message. Returns false for
+    /// any other method.
+    pub async fn apply(&self, method: &str, params: Option<&Value>) -> bool {
+        match method {
+            "session/cancel" => {
+                let session_id = params
+                    .and_then(|p| p.get("sessionId"))
+                    .and_then(|v| v.as_str());
+                match session_id {
+                    Some(session_id) => info!("Cancelling session: {}", session_id),
+                    None => warn!("No sessionId provided, cancelling all sessions"),
+                }
+                self.sessions.cancel(session_id).await;
+                true
+            }
+            "agent/cancel" => {
+                info!("Cancelling current operation");
+                self.legacy_flag
+                    .store(true, std::sync::atomic::Ordering::SeqCst);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Send a session/update notification with agent message content
+fn send_agent_message(sender: Option<&NotificationSender>, session_id: &str, content: &str) {
+    send_session_update(
+        sender,
+        session_id,
+        SessionUpdate::AgentMessageChunk(text_chunk(content)),
+    );
+}
+
+fn text_chunk(text: &str) -> ContentChunk {
+    ContentChunk {
+        content: ContentBlock::Text(TextContent {
+            text: text.to_string(),
+            annotations: None,
+            meta: None,
+        }),
+        meta: None,
+    }
+}
+
+/// Send a session/update notification
+fn send_session_update(
+    sender: Option<&NotificationSender>,
+    session_id: &str,
+    update: SessionUpdate,
+) {
+    if let Some(sender) = sender {
+        // Build the SessionNotification using official ACP schema types
+        let notification = SessionNotification {
+            session_id: SessionId::from(session_id.to_string()),
+            update,
+            meta: None,
+        };
+
+        // Convert to JSON-RPC notification
+        let json_notification = JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: "session/update".to_string(),
+            params: Some(serde_json::to_value(notification).unwrap()),
+        };
+
+        if let Err(e) = sender.send(json_notification) {
+            error!("Failed to send session/update notification: {}", e);
+        } else {
+            debug!(
+                "Sent session/update notification for session {}",
+                session_id
+            );
+        }
+    } else {
+        warn!("No notification sender configured, cannot send session/update");
+    }
+}
+
+/// Forward a turn event to the client. Thinking is a standard thought chunk;
+/// everything else goes out as a `_flexorama/stream_event` extension
+/// notification carrying the shared stream schema.
+fn send_stream_event(sender: &NotificationSender, session_id: &str, event: StreamToolEvent) {
+    if let StreamToolEvent::ThinkingDelta { text } = &event {
+        send_session_update(
+            Some(sender),
+            session_id,
+            SessionUpdate::AgentThoughtChunk(text_chunk(text)),
+        );
+        return;
+    }
+    let notification = JsonRpcNotification {
+        jsonrpc: "2.0".to_string(),
+        method: "_flexorama/stream_event".to_string(),
+        params: Some(json!({
+            "sessionId": session_id,
+            "schemaVersion": STREAM_SCHEMA_VERSION,
+            "event": event,
+        })),
+    };
+    if let Err(e) = sender.send(notification) {
+        error!("Failed to send stream event notification: {}", e);
+    }
+}
+
+/// Process a prompt with the session's own agent. Prompts for different sessions
+/// run concurrently; each session's agent lock keeps its own turns in order.
+async fn run_session_prompt(
+    sessions: &SessionManager,
+    notifier: Option<&NotificationSender>,
+    params: Option<Value>,
+) -> AcpResult<Value> {
+    let params = params.ok_or_else(|| AcpError::InvalidRequest("Missing params".to_string()))?;
+
+    // Extract session_id (required)
+    let session_id = params
+        .get("sessionId")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AcpError::InvalidRequest("Missing sessionId".to_string()))?;
+
+    // Extract prompt content blocks
+    let prompt_blocks = params
+        .get("prompt")
+        .ok_or_else(|| AcpError::InvalidRequest("Missing prompt".to_string()))?;
+
+    // Convert ContentBlocks to a simple text prompt
+    // Handles text blocks and resource blocks (with text content)
+    let prompt_text = if let Some(blocks) = prompt_blocks.as_array() {
+        blocks
+            .iter()
+            .filter_map(|block| {
+                // Handle text content blocks: {"type": "text", "text": "..."}
+                if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                    Some(text.to_string())
+                }
+                // Handle resource blocks: {"type": "resource", "resource": {"text": "...", "uri": "..."}}
+                else if let Some(resource) = block.get("resource") {
+                    if let Some(text) = resource.get("text").and_then(|v| v.as_str()) {
+                        // Include URI context if available
+                        let uri = resource.get("uri").and_then(|v| v.as_str());
+                        if let Some(uri) = uri {
+                            Some(format!(
+                                "--- Content of {} ---\n{}\n--- End of {} ---",
+                                uri, text, uri
+                            ))
+                        } else {
+                            Some(text.to_string())
+                        }
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        prompt_blocks.to_string()
+    };
+
+    info!(
+        "Processing prompt for session {}: {}",
+        session_id,
+        &prompt_text[..prompt_text.len().min(50)]
+    );
+
+    // Look up the session
+    let session = sessions
+        .get_session(session_id)
+        .await
+        .ok_or_else(|| AcpError::InvalidRequest(format!("Session not found: {}", session_id)))?;
+
+    // Reset cancellation flag for this session
+    session
+        .cancellation_flag
+        .store(false, std::sync::atomic::Ordering::SeqCst);
+
+    let mut agent = session.agent.lock().await;
+
+    // Slash commands either answer directly or expand into a prompt
+    let prompt_text = match commands::run_command(&prompt_text, &mut agent).await {
+        Ok(None) => prompt_text,
+        Ok(Some(CommandOutcome::Prompt(message))) => message,
+        Ok(Some(CommandOutcome::Reply(reply))) => {
+            send_agent_message(notifier, session_id, &reply);
+            return Ok(serde_json::to_value(PromptResponse {
+                stop_reason: StopReason::EndTurn,
+                meta: None,
+            })
+            .unwrap());
+        }
+        Err(e) => {
+            send_agent_message(notifier, session_id, &format!("Error: {}", e));
+            return Ok(serde_json::to_value(PromptResponse {
+                stop_reason: StopReason::EndTurn,
+                meta: None,
+            })
+            .unwrap());
+        }
+    };
+
+    // Process with the session's agent
+    let on_event = notifier.cloned().map(|sender| {
+        let session_id = session_id.to_string();
+        Arc::new(move |event| send_stream_event(&sender, &session_id, event))
+            as Arc<dyn Fn(StreamToolEvent) + Send + Sync>
+    });
+    let result = agent
+        .process_message_with_stream(
+            &prompt_text,
+            None,
+            on_event,
+            session.cancellation_flag.clone(),
+        )
+        .await;
+
+    // Build official ACP PromptResponse using schema types
+    // According to ACP spec, cancellation should return PromptResponse with StopReason::Cancelled
+    let acp_response = match result {
+        Ok(response) => {
+            info!("Session {} prompt completed", session_id);
+
+            // Send the response content via session/update notification (ACP compliant)
+            send_agent_message(notifier, &session_id, &response);
+
+            PromptResponse {
+                stop_reason: StopReason::EndTurn,
+                meta: None,
+            }
+        }
+        Err(e) if e.to_string().contains("CANCELLED") => {
+            info!("Session {} prompt cancelled", session_id);
+            PromptResponse {
+                stop_reason: StopReason::Cancelled,
+                meta: None,
+            }
+        }
+        Err(e) => {
+            return Err(AcpError::Agent(e));
+        }
+    };
+
+    Ok(serde_json::to_value(acp_response).unwrap())
+}
+
+fn into_response(method: &str, id: Option<Value>, result: AcpResult<Value>) -> JsonRpcResponse {
+    match result {
+        Ok(value) => JsonRpcResponse::success(id, value),
+        Err(err) => {
+            error!("Error handling {}: {}", method, err);
+            let rpc_error: JsonRpcError = err.into();
+            JsonRpcResponse::error(id, rpc_error.code, rpc_error.message, rpc_error.data)
+        }
+    }
+}
+
 /// Flexorama ACP Handler
 /// Implements the Agent Client Protocol server-side logic
 pub struct FlexoramaAcpHandler {
@@ -52,6 +322,12 @@ pub struct FlexoramaAcpHandler {
 
     /// Notification sender for sending session/update notifications
     notification_sender: Option<NotificationSender>,
+
+    /// Sends requests to the client, e.g. to apply file edits in the editor
+    client: Option<Arc<AcpClient>>,
+
+    /// Notifications to send once the current request has been answered
+    deferred_notifications: Vec<JsonRpcNotification>,
 }
 
 impl FlexoramaAcpHandler {
@@ -71,7 +347,8 @@ impl FlexoramaAcpHandler {
 
         // Create session manager for managing ACP sessions
         let session_manager =
-            SessionManager::new(config.clone(), model.clone(), yolo_mode, plan_mode);
+            SessionManager::new(config.clone(), model.clone(), yolo_mode, plan_mode)
+                .with_shared_services(SharedServices::from_agent(&agent));
 
         Self {
             agent: Arc::new(Mutex::new(agent)),
@@ -84,6 +361,8 @@ impl FlexoramaAcpHandler {
             filesystem,
             yolo_mode,
             notification_sender: None,
+            client: None,
+            deferred_notifications: Vec::new(),
         }
     }
 
@@ -92,40 +371,27 @@ impl FlexoramaAcpHandler {
         self.notification_sender = Some(sender);
     }
 
-    /// Send a session/update notification with agent message content
-    fn send_agent_message_notification(&self, session_id: &str, content: &str) {
-        if let Some(ref sender) = self.notification_sender {
-            // Build the SessionNotification using official ACP schema types
-            let notification = SessionNotification {
-                session_id: SessionId::from(session_id.to_string()),
-                update: SessionUpdate::AgentMessageChunk(ContentChunk {
-                    content: ContentBlock::Text(TextContent {
-                        text: content.to_string(),
-                        annotations: None,
-                        meta: None,
-                    }),
-                    meta: None,
-                }),
-                meta: None,
-            };
+    /// Set the connection used to send requests to the client
+    pub fn set_client(&mut self, client: Arc<AcpClient>) {
+        self.client = Some(client);
+    }
 
-            // Convert to JSON-RPC notification
-            let json_notification = JsonRpcNotification {
-                jsonrpc: "2.0".to_string(),
-                method: "session/update".to_string(),
-                params: Some(serde_json::to_value(notification).unwrap()),
-            };
+    /// Notifications that must follow the response to the request just handled,
+    /// e.g. updates about a session the client only learns of from that response
+    pub fn take_deferred_notifications(&mut self) -> Vec<JsonRpcNotification> {
+        std::mem::take(&mut self.deferred_notifications)
+    }
 
-            if let Err(e) = sender.send(json_notification) {
-                error!("Failed to send session/update notification: {}", e);
-            } else {
-                debug!(
-                    "Sent session/update notification for session {}",
-                    session_id
-                );
-            }
+    /// Client to hand file writes to, if it advertised `fs.writeTextFile`
+    fn editor_write_client(&self) -> Option<Arc<AcpClient>> {
+        let supported = self
+            .client_capabilities
+            .as_ref()
+            .is_some_and(|caps| caps.can_write_text_files());
+        if supported {
+            self.client.clone()
         } else {
-            warn!("No notification sender configured, cannot send session/update");
+            None
         }
     }
 
@@ -179,18 +445,27 @@ impl FlexoramaAcpHandler {
             }
         };
 
-        match result {
-            Ok(value) => JsonRpcResponse::success(request.id, value),
-            Err(err) => {
-                error!("Error handling {}: {}", request.method, err);
-                let rpc_error: JsonRpcError = err.into();
-                JsonRpcResponse::error(
-                    request.id,
-                    rpc_error.code,
-                    rpc_error.message,
-                    rpc_error.data,
-                )
-            }
+        into_response(&request.method, request.id, result)
+    }
+
+    /// Run a `session/prompt` request detached from the handler, so the server can
+    /// keep serving other sessions while this one's agent works
+    pub fn session_prompt_task(
+        &self,
+        request: JsonRpcRequest,
+    ) -> impl Future<Output = JsonRpcResponse> + Send + 'static {
+        let initialized = self.initialized;
+        let sessions = self.session_manager.clone();
+        let notifier = self.notification_sender.clone();
+        async move {
+            let result = if initialized {
+                run_session_prompt(&sessions, notifier.as_ref(), request.params).await
+            } else {
+                Err(AcpError::InvalidRequest(
+                    "Server not initialized".to_string(),
+                ))
+            };
+            into_response(&request.method, request.id, result)
         }
     }
 
@@ -330,6 +605,33 @@ impl FlexoramaAcpHandler {
             .await
             .map_err(|e| AcpError::Agent(e))?;
 
+        // Ask for permissions in the editor tab that owns this session
+        if let Some(client) = self.client.clone() {
+            session
+                .agent
+                .lock()
+                .await
+                .set_permission_handler(Some(editor_permission_handler(
+                    client,
+                    session_id.to_string(),
+                )))
+                .await;
+        }
+
+        // Let the editor apply Write/Edit changes so they show up in its review UI
+        // instead of changing files underneath it
+        if let Some(client) = self.editor_write_client() {
+            let file_security = session.agent.lock().await.get_file_security_manager();
+            file_security
+                .write()
+                .await
+                .set_write_handler(Some(editor_write_handler(client, session_id.to_string())));
+            info!(
+                "Session {} routes file edits through the client",
+                session_id
+            );
+        }
+
         // If a workspace was specified, update the session's workspace
         if let Some(workspace) = session_workspace {
             // Note: We could set workspace on the session's agent here if needed
@@ -342,6 +644,21 @@ impl FlexoramaAcpHandler {
             session_id, session.conversation_id
         );
 
+        // Offer slash commands and custom commands in the editor's command palette
+        let update = SessionNotification {
+            session_id: session_id.clone(),
+            update: SessionUpdate::AvailableCommandsUpdate(AvailableCommandsUpdate {
+                available_commands: commands::available_commands().await,
+                meta: None,
+            }),
+            meta: None,
+        };
+        self.deferred_notifications.push(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: "session/update".to_string(),
+            params: Some(serde_json::to_value(update).unwrap()),
+        });
+
         // Build response using official ACP type
         let response = NewSessionResponse {
             session_id,
@@ -359,141 +676,30 @@ impl FlexoramaAcpHandler {
                 "Server not initialized".to_string(),
             ));
         }
-
-        let params =
-            params.ok_or_else(|| AcpError::InvalidRequest("Missing params".to_string()))?;
-
-        // Extract session_id (required)
-        let session_id = params
-            .get("sessionId")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| AcpError::InvalidRequest("Missing sessionId".to_string()))?;
-
-        // Extract prompt content blocks
-        let prompt_blocks = params
-            .get("prompt")
-            .ok_or_else(|| AcpError::InvalidRequest("Missing prompt".to_string()))?;
-
-        // Convert ContentBlocks to a simple text prompt
-        // Handles text blocks and resource blocks (with text content)
-        let prompt_text = if let Some(blocks) = prompt_blocks.as_array() {
-            blocks
-                .iter()
-                .filter_map(|block| {
-                    // Handle text content blocks: {"type": "text", "text": "..."}
-                    if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
-                        Some(text.to_string())
-                    }
-                    // Handle resource blocks: {"type": "resource", "resource": {"text": "...", "uri": "..."}}
-                    else if let Some(resource) = block.get("resource") {
-                        if let Some(text) = resource.get("text").and_then(|v| v.as_str()) {
-                            // Include URI context if available
-                            let uri = resource.get("uri").and_then(|v| v.as_str());
-                            if let Some(uri) = uri {
-                                Some(format!(
-                                    "--- Content of {} ---\n{}\n--- End of {} ---",
-                                    uri, text, uri
-                                ))
-                            } else {
-                                Some(text.to_string())
-                            }
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("\n")
-        } else {
-            prompt_blocks.to_string()
-        };
-
-        info!(
-            "Processing prompt for session {}: {}",
-            session_id,
-            &prompt_text[..prompt_text.len().min(50)]
-        );
-
-        // Look up the session
-        let session = self
-            .session_manager
-            .get_session(session_id)
-            .await
-            .ok_or_else(|| {
-                AcpError::InvalidRequest(format!("Session not found: {}", session_id))
-            })?;
-
-        // Reset cancellation flag for this session
-        session
-            .cancellation_flag
-            .store(false, std::sync::atomic::Ordering::SeqCst);
-
-        // Process with the session's agent
-        let mut agent = session.agent.lock().await;
-        let result = agent
-            .process_message(&prompt_text, session.cancellation_flag.clone())
-            .await;
-
-        // Build official ACP PromptResponse using schema types
-        // According to ACP spec, cancellation should return PromptResponse with StopReason::Cancelled
-        let acp_response = match result {
-            Ok(response) => {
-                info!("Session {} prompt completed", session_id);
-
-                // Send the response content via session/update notification (ACP compliant)
-                self.send_agent_message_notification(&session_id, &response);
-
-                PromptResponse {
-                    stop_reason: StopReason::EndTurn,
-                    meta: None,
-                }
-            }
-            Err(e) if e.to_string().contains("CANCELLED") => {
-                info!("Session {} prompt cancelled", session_id);
-                PromptResponse {
-                    stop_reason: StopReason::Cancelled,
-                    meta: None,
-                }
-            }
-            Err(e) => {
-                return Err(AcpError::Agent(e));
-            }
-        };
-
-        Ok(serde_json::to_value(acp_response).unwrap())
+        run_session_prompt(
+            &self.session_manager,
+            self.notification_sender.as_ref(),
+            params,
+        )
+        .await
     }
 
     /// Handle session/cancel request (ACP official protocol)
     async fn handle_session_cancel(&mut self, params: Option<Value>) -> AcpResult<Value> {
-        // Extract session_id if provided
-        if let Some(params) = params {
-            if let Some(session_id) = params.get("sessionId").and_then(|v| v.as_str()) {
-                info!("Cancelling session: {}", session_id);
-
-                // Look up the session and cancel it
-                if let Some(session) = self.session_manager.get_session(session_id).await {
-                    session.cancel();
-                    info!("Session {} cancelled", session_id);
-                } else {
-                    warn!("Session {} not found for cancellation", session_id);
-                }
-            }
-        } else {
-            // No session ID provided - cancel all sessions (legacy behavior)
-            warn!("No sessionId provided, cancelling all sessions");
-            // Cancel all active sessions
-            for session_id in self.session_manager.list_session_ids().await {
-                if let Some(session) = self.session_manager.get_session(&session_id).await {
-                    session.cancel();
-                }
-            }
-        }
-
+        self.cancel_handle()
+            .apply("session/cancel", params.as_ref())
+            .await;
         Ok(json!({"cancelled": true}))
     }
 
+    /// Handle for cancelling in-flight prompts without going through the request loop
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            sessions: self.session_manager.clone(),
+            legacy_flag: self.cancellation_flag.clone(),
+        }
+    }
+
     /// Handle configuration change
     async fn handle_configuration_change(&mut self, _params: Option<Value>) -> AcpResult<Value> {
         debug!("Configuration changed");
@@ -568,10 +774,10 @@ impl FlexoramaAcpHandler {
     }
 
     /// Handle cancel request
-    async fn handle_cancel(&mut self, _params: Option<Value>) -> AcpResult<Value> {
-        info!("Cancelling current operation");
-        self.cancellation_flag
-            .store(true, std::sync::atomic::Ordering::SeqCst);
+    async fn handle_cancel(&mut self, params: Option<Value>) -> AcpResult<Value> {
+        self.cancel_handle()
+            .apply("agent/cancel", params.as_ref())
+            .await;
         Ok(json!({"cancelled": true}))
     }
 
@@ -764,21 +970,12 @@ impl FlexoramaAcpHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Provider;
 
     fn create_test_handler() -> FlexoramaAcpHandler {
         let config = Config {
             api_key: "test-key".to_string(),
-            provider: Provider::Anthropic,
-            base_url: "https://api.anthropic.com/v1".to_string(),
             default_model: "test-model".to_string(),
-            max_tokens: 4096,
-            temperature: 0.7,
-            default_system_prompt: None,
-            bash_security: Default::default(),
-            file_security: Default::default(),
-            mcp: Default::default(),
-            skills: Default::default(),
+            ..Config::default()
         };
 
         let agent = Agent::new(config.clone(), "test-model".to_string(), false, false);
@@ -786,6 +983,41 @@ mod tests {
         FlexoramaAcpHandler::new(agent, config, "test-model".to_string(), false)
     }
 
+    #[test]
+    fn test_stream_events_become_thoughts_and_extension_notifications() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        send_stream_event(
+            &tx,
+            "s1",
+            StreamToolEvent::ThinkingDelta {
+                text: "Check the tests".to_string(),
+            },
+        );
+        send_stream_event(
+            &tx,
+            "s1",
+            StreamToolEvent::SubagentEnd {
+                name: "reviewer".to_string(),
+            },
+        );
+
+        let thought = rx.try_recv().unwrap();
+        assert_eq!(thought.method, "session/update");
+        let update = &thought.params.as_ref().unwrap()["update"];
+        assert_eq!(update["sessionUpdate"], "agent_thought_chunk");
+        assert_eq!(update["content"]["text"], "Check the tests");
+
+        let lifecycle = rx.try_recv().unwrap();
+        assert_eq!(lifecycle.method, "_flexorama/stream_event");
+        let params = lifecycle.params.unwrap();
+        assert_eq!(params["sessionId"], "s1");
+        assert_eq!(params["schemaVersion"], STREAM_SCHEMA_VERSION);
+        assert_eq!(
+            params["event"],
+            json!({"type": "subagent_end", "name": "reviewer"})
+        );
+    }
+
     #[tokio::test]
     async fn test_initialize() {
         let mut handler = create_test_handler();
@@ -943,6 +1175,107 @@ mod tests {
         assert!(session.is_some());
     }
 
+    #[tokio::test]
+    async fn test_session_slash_commands_are_advertised_and_run() {
+        let mut handler = create_test_handler();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        handler.set_notification_sender(tx);
+        handler.initialized = true;
+
+        let value = handler.handle_session_new(None).await.unwrap();
+        let session_id = value["sessionId"].as_str().unwrap().to_string();
+        let deferred = handler.take_deferred_notifications();
+        assert_eq!(deferred.len(), 1);
+        let update = &deferred[0].params.as_ref().unwrap()["update"];
+        assert_eq!(update["sessionUpdate"], "available_commands_update");
+        assert!(update["availableCommands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|c| c["name"] == "plan"));
+
+        // Answered without calling the model
+        let result = handler
+            .handle_session_prompt(Some(json!({
+                "sessionId": session_id,
+                "prompt": [{ "type": "text", "text": "/stats" }]
+            })))
+            .await
+            .unwrap();
+        assert_eq!(result["stopReason"], "end_turn");
+        let reply = rx.try_recv().unwrap();
+        let text = &reply.params.unwrap()["update"]["content"]["text"];
+        assert!(text.as_str().unwrap().contains("Requests made: 0"));
+    }
+
+    #[tokio::test]
+    async fn test_session_prompt_task_runs_detached() {
+        let mut handler = create_test_handler();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        handler.set_notification_sender(tx);
+
+        let request = |session_id: &str| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(7)),
+            method: "session/prompt".to_string(),
+            params: Some(json!({
+                "sessionId": session_id,
+                "prompt": [{ "type": "text", "text": "/stats" }]
+            })),
+        };
+
+        let response = tokio::spawn(handler.session_prompt_task(request("none")))
+            .await
+            .unwrap();
+        assert!(response.error.is_some());
+
+        handler.initialized = true;
+        let value = handler.handle_session_new(None).await.unwrap();
+        let session_id = value["sessionId"].as_str().unwrap().to_string();
+        let task = handler.session_prompt_task(request(&session_id));
+        // The handler stays usable while the prompt runs
+        handler.handle_session_new(None).await.unwrap();
+        let response = tokio::spawn(task).await.unwrap();
+        assert_eq!(response.id, Some(json!(7)));
+        assert_eq!(response.result.unwrap()["stopReason"], "end_turn");
+    }
+
+    #[tokio::test]
+    async fn test_session_new_routes_writes_to_capable_client() {
+        let mut handler = create_test_handler();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        handler.set_client(Arc::new(AcpClient::new(tx)));
+        handler
+            .handle_initialize(Some(json!({
+                "protocolVersion": 1,
+                "clientCapabilities": { "fs": { "readTextFile": true, "writeTextFile": true } }
+            })))
+            .await
+            .unwrap();
+
+        let value = handler.handle_session_new(None).await.unwrap();
+        let session_id = value["sessionId"].as_str().unwrap();
+        let session = handler
+            .session_manager
+            .get_session(session_id)
+            .await
+            .unwrap();
+        let file_security = session.agent.lock().await.get_file_security_manager();
+        assert!(file_security.read().await.write_handler().is_some());
+
+        // Without the capability, writes keep going to disk
+        handler.client_capabilities = None;
+        let value = handler.handle_session_new(None).await.unwrap();
+        let session_id = value["sessionId"].as_str().unwrap();
+        let session = handler
+            .session_manager
+            .get_session(session_id)
+            .await
+            .unwrap();
+        let file_security = session.agent.lock().await.get_file_security_manager();
+        assert!(file_security.read().await.write_handler().is_none());
+    }
+
     #[tokio::test]
     async fn test_session_new_not_initialized() {
         let mut handler = create_test_handler();
@@ -1029,6 +1362,30 @@ mod tests {
         assert_eq!(value.get("cancelled"), Some(&json!(true)));
     }
 
+    #[tokio::test]
+    async fn test_cancel_handle_sets_session_flag() {
+        let mut handler = create_test_handler();
+        handler.initialized = true;
+        let value = handler.handle_session_new(None).await.unwrap();
+        let session_id = value["sessionId"].as_str().unwrap().to_string();
+        let session = handler
+            .session_manager
+            .get_session(&session_id)
+            .await
+            .unwrap();
+
+        let cancel = handler.cancel_handle();
+        assert!(!cancel.apply("session/prompt", None).await);
+        assert!(
+            cancel
+                .apply("session/cancel", Some(&json!({ "sessionId": session_id })))
+                .await
+        );
+        assert!(session
+            .cancellation_flag
+            .load(std::sync::atomic::Ordering::SeqCst));
+    }
+
     #[tokio::test]
     async fn test_session_cancel_no_params() {
         let mut handler = create_test_handler();
@@ -10,6 +10,8 @@
 /// - `handler`: Request handling and agent operations
 /// - `transport`: Stdio transport layer for JSON-RPC messages
 /// - `capabilities`: Capability negotiation with clients
+/// - `commands`: Slash commands offered to the client's command palette
+/// - `client`: Requests from the agent to the client, such as file writes
 /// - `types`: JSON-RPC and ACP type definitions
 /// - `errors`: Error types and conversions
 ///
@@ -19,6 +21,8 @@
 /// flexorama --acp
 /// ```
 pub mod capabilities;
+pub mod client;
+pub mod commands;
 pub mod errors;
 pub mod filesystem;
 pub mod handler;
@@ -1,11 +1,15 @@
+use crate::acp::client::AcpClient;
 use crate::acp::errors::AcpResult;
-use crate::acp::handler::FlexoramaAcpHandler;
+use crate::acp::handler::{CancelHandle, FlexoramaAcpHandler};
 use crate::acp::transport::StdioTransport;
-use crate::acp::types::{JsonRpcMessage, JsonRpcNotification, JsonRpcRequest};
+use crate::acp::types::{JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
 use crate::agent::Agent;
 use crate::config::Config;
 use log::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::task::JoinSet;
 
 /// Run the ACP server
 pub async fn run_acp_server(
@@ -17,29 +21,79 @@ pub async fn run_acp_server(
     info!("Starting ACP server (debug: {})", debug);
     info!("Model: {}", model);
 
-    let mut transport = StdioTransport::new(debug);
     let mut handler = FlexoramaAcpHandler::new(agent, config, model, debug);
 
     // Create notification channel for sending session/update notifications
     let (notification_tx, mut notification_rx) = mpsc::unbounded_channel::<JsonRpcNotification>();
     handler.set_notification_sender(notification_tx);
 
+    // Everything sent to the client goes through one writer task, so requests the
+    // agent makes while a prompt is running aren't stuck behind that prompt
+    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<JsonRpcMessage>();
+    let mut writer_transport = StdioTransport::new(debug);
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outgoing_rx.recv().await {
+            if let Err(e) = writer_transport.write_message(&message).await {
+                error!("Failed to write message: {}", e);
+                break;
+            }
+        }
+    });
+
+    let client = Arc::new(AcpClient::new(outgoing_tx.clone()));
+    handler.set_client(client.clone());
+
+    // Responses from the client are delivered straight to the waiting request,
+    // since the main loop may be busy with the prompt that sent it
+    let (incoming_tx, mut incoming_rx) = mpsc::unbounded_channel::<JsonRpcMessage>();
+    let mut reader_transport = StdioTransport::new(debug);
+    let reader_client = client.clone();
+    let cancel = handler.cancel_handle();
+    let reader = tokio::spawn(async move {
+        loop {
+            let message = match reader_transport.read_message().await {
+                Ok(msg) => msg,
+                Err(e) => {
+                    if reader_transport.is_closed().await {
+                        info!("Client disconnected, shutting down");
+                        break;
+                    }
+                    error!("Failed to read message: {}", e);
+                    continue;
+                }
+            };
+
+            match message {
+                JsonRpcMessage::Response(response) => {
+                    let id = response.id.clone();
+                    if !reader_client.handle_response(response).await {
+                        warn!("Received unexpected response message: {:?}", id);
+                    }
+                }
+                message => {
+                    if apply_cancel_early(&message, &cancel).await {
+                        continue;
+                    }
+                    if incoming_tx.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        reader_client.fail_pending().await;
+    });
+
     info!("ACP server ready, waiting for messages...");
 
+    // Prompts run as tasks so one session's turn doesn't hold up the others
+    let mut prompts: JoinSet<JsonRpcResponse> = JoinSet::new();
+
     loop {
         tokio::select! {
             // Handle incoming messages from stdin
-            message_result = transport.read_message() => {
-                let message = match message_result {
-                    Ok(msg) => msg,
-                    Err(e) => {
-                        if transport.is_closed().await {
-                            info!("Client disconnected, shutting down");
-                            break;
-                        }
-                        error!("Failed to read message: {}", e);
-                        continue;
-                    }
+            message = incoming_rx.recv() => {
+                let Some(message) = message else {
+                    break;
                 };
 
                 // Handle message
@@ -53,13 +107,26 @@ pub async fn run_acp_server(
                             break;
                         }
 
+                        if request.method == "session/prompt" {
+                            prompts.spawn(handler.session_prompt_task(request));
+                            continue;
+                        }
+
                         let response = handler.handle_request(request).await;
 
+                        // Updates produced while handling the request go out before its response
+                        while let Ok(notification) = notification_rx.try_recv() {
+                            let _ = outgoing_tx.send(JsonRpcMessage::Notification(notification));
+                        }
+
                         // Send response
-                        if let Err(e) = transport.write_message(&JsonRpcMessage::Response(response)).await {
-                            error!("Failed to write response: {}", e);
+                        if outgoing_tx.send(JsonRpcMessage::Response(response)).is_err() {
+                            error!("Failed to queue response, writer has stopped");
                             break;
                         }
+                        for notification in handler.take_deferred_notifications() {
+                            let _ = outgoing_tx.send(JsonRpcMessage::Notification(notification));
+                        }
                     }
 
                     JsonRpcMessage::Notification(notification) => {
@@ -79,18 +146,37 @@ pub async fn run_acp_server(
                     }
 
                     JsonRpcMessage::Response(response) => {
+                        // The reader task delivers responses to the client directly
                         warn!("Received unexpected response message: {:?}", response.id);
-                        // Servers shouldn't receive responses, only send them
                     }
                 }
             }
 
+            // Answer prompts as they finish
+            Some(finished) = prompts.join_next(), if !prompts.is_empty() => {
+                let response = match finished {
+                    Ok(response) => response,
+                    Err(e) => {
+                        error!("Prompt task failed: {}", e);
+                        continue;
+                    }
+                };
+                // The prompt's updates go out before its response
+                while let Ok(notification) = notification_rx.try_recv() {
+                    let _ = outgoing_tx.send(JsonRpcMessage::Notification(notification));
+                }
+                if outgoing_tx.send(JsonRpcMessage::Response(response)).is_err() {
+                    error!("Failed to queue response, writer has stopped");
+                    break;
+                }
+            }
+
             // Handle outgoing notifications from the handler
             notification = notification_rx.recv() => {
                 if let Some(notification) = notification {
                     debug!("Sending notification: {}", notification.method);
-                    if let Err(e) = transport.write_message(&JsonRpcMessage::Notification(notification)).await {
-                        error!("Failed to write notification: {}", e);
+                    if outgoing_tx.send(JsonRpcMessage::Notification(notification)).is_err() {
+                        error!("Failed to queue notification, writer has stopped");
                         break;
                     }
                 }
@@ -98,10 +184,42 @@ pub async fn run_acp_server(
         }
     }
 
+    // Drop every sender so the writer can flush what's queued and stop
+    prompts.abort_all();
+    reader.abort();
+    drop(handler);
+    drop(client);
+    drop(outgoing_tx);
+    if tokio::time::timeout(Duration::from_secs(1), writer)
+        .await
+        .is_err()
+    {
+        debug!("Timed out flushing outgoing ACP messages");
+    }
+
     info!("ACP server shut down");
     Ok(())
 }
 
+/// Cancels take effect as soon as they're read, because the main loop is busy
+/// with the prompt being cancelled. Returns true when nothing else needs to
+/// handle the message.
+async fn apply_cancel_early(message: &JsonRpcMessage, cancel: &CancelHandle) -> bool {
+    match message {
+        JsonRpcMessage::Notification(notification) => {
+            cancel
+                .apply(&notification.method, notification.params.as_ref())
+                .await
+        }
+        // Requests still reach the handler so they get a response
+        JsonRpcMessage::Request(request) => {
+            cancel.apply(&request.method, request.params.as_ref()).await;
+            false
+        }
+        JsonRpcMessage::Response(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
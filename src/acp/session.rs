@@ -1,5 +1,8 @@
 use crate::agent::Agent;
 use crate::config::Config;
+use crate::database::DatabaseManager;
+use crate::mcp::McpManager;
+use crate::skill::SkillManager;
 use agent_client_protocol_schema::SessionId;
 use anyhow::Result;
 use log::{info, warn};
@@ -23,19 +26,51 @@ pub struct Session {
     pub conversation_id: Option<String>,
 }
 
+/// Services every session's agent shares with the one the server started with,
+/// the same way the web UI's conversation agents share them
+#[derive(Clone, Default)]
+pub struct SharedServices {
+    pub mcp_manager: Option<Arc<McpManager>>,
+    pub database_manager: Option<Arc<DatabaseManager>>,
+    pub skill_manager: Option<Arc<Mutex<SkillManager>>>,
+}
+
+impl SharedServices {
+    /// Take the services configured on `agent`
+    pub fn from_agent(agent: &Agent) -> Self {
+        Self {
+            mcp_manager: agent.mcp_manager(),
+            database_manager: agent.database_manager(),
+            skill_manager: agent.skill_manager(),
+        }
+    }
+}
+
 impl Session {
-    /// Create a new session with its own agent
+    /// Create a new session with its own agent, tool registry and security managers
     pub async fn new(
         session_id: SessionId,
         config: &Config,
         model: &str,
         yolo_mode: bool,
         plan_mode: bool,
+        services: &SharedServices,
     ) -> Result<Self> {
         info!("Creating new session: {}", session_id);
 
         // Create a new agent for this session
-        let mut agent = Agent::new(config.clone(), model.to_string(), yolo_mode, plan_mode);
+        let mut agent =
+            Agent::new_with_plan_mode(config.clone(), model.to_string(), yolo_mode, plan_mode)
+                .await;
+        if let Some(mcp_manager) = &services.mcp_manager {
+            agent = agent.with_mcp_manager(mcp_manager.clone());
+        }
+        if let Some(database_manager) = &services.database_manager {
+            agent = agent.with_database_manager(database_manager.clone());
+        }
+        if let Some(skill_manager) = &services.skill_manager {
+            agent = agent.with_skill_manager(skill_manager.clone());
+        }
 
         // Suppress output in ACP mode
         agent.set_suppress_output(true);
@@ -71,7 +106,8 @@ impl Session {
     }
 }
 
-/// Manages all active ACP sessions
+/// Manages all active ACP sessions. Clones share the same sessions.
+#[derive(Clone)]
 pub struct SessionManager {
     /// Map of session IDs to sessions
     sessions: Arc<Mutex<HashMap<String, Arc<Session>>>>,
@@ -87,6 +123,9 @@ pub struct SessionManager {
 
     /// Plan mode flag
     plan_mode: bool,
+
+    /// Services shared by every session's agent
+    services: SharedServices,
 }
 
 impl SessionManager {
@@ -98,9 +137,16 @@ impl SessionManager {
             model,
             yolo_mode,
             plan_mode,
+            services: SharedServices::default(),
         }
     }
 
+    /// Share these services with the agent of every session created from now on
+    pub fn with_shared_services(mut self, services: SharedServices) -> Self {
+        self.services = services;
+        self
+    }
+
     /// Create a new session
     pub async fn create_session(&self, session_id: SessionId) -> Result<Arc<Session>> {
         let session = Arc::new(
@@ -110,6 +156,7 @@ impl SessionManager {
                 &self.model,
                 self.yolo_mode,
                 self.plan_mode,
+                &self.services,
             )
             .await?,
         );
@@ -155,6 +202,30 @@ impl SessionManager {
         info!("Cleared all {} sessions", count);
     }
 
+    /// Cancel one session, or every session when no ID is given.
+    /// Returns false if the named session doesn't exist.
+    pub async fn cancel(&self, session_id: Option<&str>) -> bool {
+        let sessions = self.sessions.lock().await;
+        match session_id {
+            Some(session_id) => match sessions.get(session_id) {
+                Some(session) => {
+                    session.cancel();
+                    true
+                }
+                None => {
+                    warn!("Session {} not found for cancellation", session_id);
+                    false
+                }
+            },
+            None => {
+                for session in sessions.values() {
+                    session.cancel();
+                }
+                true
+            }
+        }
+    }
+
     /// List all active session IDs
     pub async fn list_session_ids(&self) -> Vec<String> {
         let sessions = self.sessions.lock().await;
@@ -165,21 +236,12 @@ impl SessionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Provider;
 
     fn create_test_config() -> Config {
         Config {
             api_key: "test-key".to_string(),
-            provider: Provider::Anthropic,
-            base_url: "https://api.anthropic.com/v1".to_string(),
             default_model: "test-model".to_string(),
-            max_tokens: 4096,
-            temperature: 0.7,
-            default_system_prompt: None,
-            bash_security: Default::default(),
-            file_security: Default::default(),
-            mcp: Default::default(),
-            skills: Default::default(),
+            ..Config::default()
         }
     }
 
@@ -195,6 +257,40 @@ mod tests {
         assert_eq!(manager.session_count().await, 1);
     }
 
+    #[tokio::test]
+    async fn test_sessions_have_independent_agents() {
+        let config = create_test_config();
+        let manager = SessionManager::new(config, "test-model".to_string(), false, false);
+
+        let first = manager
+            .create_session(SessionId::from("tab-1".to_string()))
+            .await
+            .unwrap();
+        let second = manager
+            .create_session(SessionId::from("tab-2".to_string()))
+            .await
+            .unwrap();
+
+        assert!(!Arc::ptr_eq(&first.agent, &second.agent));
+        let first_bash = first.agent.lock().await.get_bash_security_manager();
+        let second_bash = second.agent.lock().await.get_bash_security_manager();
+        assert!(!Arc::ptr_eq(&first_bash, &second_bash));
+
+        // A permission handler set on one session doesn't leak into the other
+        first
+            .agent
+            .lock()
+            .await
+            .set_permission_handler(Some(Arc::new(|_| Box::pin(async { Some(0) }))))
+            .await;
+        assert!(first_bash.read().await.permission_handler().is_some());
+        assert!(second_bash.read().await.permission_handler().is_none());
+
+        // Holding one session's agent doesn't block the other
+        let _busy = first.agent.lock().await;
+        assert!(second.agent.try_lock().is_ok());
+    }
+
     #[tokio::test]
     async fn test_session_manager_get() {
         let config = create_test_config();
@@ -266,9 +362,16 @@ mod tests {
         let config = create_test_config();
         let session_id = SessionId::from("test-session-cancel".to_string());
 
-        let session = Session::new(session_id, &config, "test-model", false, false)
-            .await
-            .unwrap();
+        let session = Session::new(
+            session_id,
+            &config,
+            "test-model",
+            false,
+            false,
+            &SharedServices::default(),
+        )
+        .await
+        .unwrap();
 
         assert!(!session
             .cancellation_flag
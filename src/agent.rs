@@ -1,12 +1,12 @@
 use crate::mcp::McpManager;
-use crate::security::{BashSecurityManager, FileSecurityManager};
+use crate::security::{BashSecurityManager, CurrentModel, FileSecurityManager};
 use crate::tools::display::DisplayFactory;
 use anyhow::{anyhow, Result};
 use colored::*;
 use log::{debug, error, info, warn};
 use serde::Serialize;
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::{Mutex as AsyncMutex, RwLock};
@@ -18,6 +18,8 @@ struct SavedConversationContext {
     system_prompt: Option<String>,
     current_conversation_id: Option<String>,
     model: String,
+    lock: Option<crate::conversation_lock::ConversationLock>,
+    read_only: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -26,14 +28,51 @@ pub struct SnapshotMessage {
     pub content: Vec<ContentBlock>,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct StreamToolEvent {
-    pub event: String,
-    pub tool_use_id: String,
-    pub name: String,
-    pub input: Option<serde_json::Value>,
-    pub content: Option<String>,
-    pub is_error: Option<bool>,
+/// Version of the [`StreamToolEvent`] payloads sent to the web UI, ACP
+/// clients and `--output-format stream-json`. Bump it when a field changes
+/// meaning or goes away; new event types don't need a bump.
+pub const STREAM_SCHEMA_VERSION: u32 = 1;
+
+/// Something that happened during a turn, besides the reply text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamToolEvent {
+    /// The model called a tool, which is about to run
+    ToolCall {
+        tool_use_id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// A piece of a tool call's JSON arguments as the model streams them
+    ToolInputDelta {
+        tool_use_id: String,
+        name: String,
+        delta: String,
+    },
+    ToolResult {
+        tool_use_id: String,
+        name: String,
+        content: String,
+        is_error: bool,
+    },
+    /// A piece of the model's reasoning, for providers that stream it
+    ThinkingDelta { text: String },
+    /// Tokens used by the request that just finished, and by the session
+    Usage {
+        input_tokens: u32,
+        output_tokens: u32,
+        total_tokens: u32,
+    },
+    /// Older messages or tool results were dropped to make the conversation
+    /// fit the context window
+    ContextCompacted {
+        messages_before: usize,
+        messages_after: usize,
+    },
+    /// The turn is answered by a subagent
+    SubagentStart { name: String, model: String },
+    /// The subagent that answered earlier turns was exited
+    SubagentEnd { name: String },
 }
 
 #[derive(Debug, Clone)]
@@ -47,14 +86,18 @@ pub struct ConversationSnapshot {
 use crate::anthropic::{ContentBlock, Message, Usage};
 use crate::config::{Config, Provider};
 use crate::conversation::ConversationManager;
-use crate::database::{Conversation as StoredConversation, DatabaseManager};
+use crate::conversation_env::ConversationEnv;
+use crate::database::{Conversation as StoredConversation, DatabaseManager, ToolCallRecord};
 use crate::hooks::{HookAction, HookManager};
-use crate::llm::LlmClient;
+use crate::llm::{DeltaCallback, LlmClient, ReplyDelta};
+use crate::session_diff::SessionDiff;
 use crate::subagent;
+use crate::tool_filter::ToolFilter;
 use crate::tools::{
-    bash, create_directory, delete_file, edit_file, get_builtin_tools, write_file, Tool, ToolCall,
-    ToolRegistry, ToolResult,
+    bash_with_env, create_directory, delete_file, edit_file, get_builtin_tools, write_file, Tool,
+    ToolCall, ToolRegistry, ToolResult,
 };
+use crate::webhooks::{WebhookDispatcher, WebhookEvent};
 
 #[derive(Debug, Clone)]
 pub struct TokenUsage {
@@ -89,12 +132,22 @@ impl TokenUsage {
     }
 }
 
+/// Called with the session's token usage as it changes, including an
+/// estimate of the output of a reply that's still streaming
+pub type UsageListener = Arc<dyn Fn(&TokenUsage) + Send + Sync>;
+
+/// Least time between live usage updates while a reply streams
+const LIVE_USAGE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
 pub struct Agent {
-    client: LlmClient,
+    client: Arc<LlmClient>,
     model: String,
+    /// `model`, as the security managers' hooks and webhooks read it
+    current_model: CurrentModel,
     tools: Arc<RwLock<HashMap<String, Tool>>>,
     conversation_manager: ConversationManager,
     token_usage: TokenUsage,
+    usage_listener: Option<UsageListener>,
     mcp_manager: Option<Arc<McpManager>>,
     last_mcp_tools_version: u64,
     bash_security_manager: Arc<RwLock<BashSecurityManager>>,
@@ -104,13 +157,23 @@ pub struct Agent {
     plan_mode_saved_system_prompt: Option<Option<String>>,
     // Store previous context when switching to subagent
     saved_conversation_context: Option<SavedConversationContext>,
+    /// The subagent the last turn's stream said was answering
+    reported_subagent: Option<String>,
     // New display system components
     pub tool_registry: Arc<RwLock<ToolRegistry>>,
     provider: Provider,
     base_url: String,
+    /// API key and base URL of each provider used this session, so switching
+    /// back to one keeps the credentials it was started with
+    provider_credentials: HashMap<Provider, (String, String)>,
+    /// Wraps the client again when the provider changes
+    response_cache: Option<crate::response_cache::ResponseCache>,
     // Skill management
     skill_manager: Option<Arc<AsyncMutex<crate::skill::SkillManager>>>,
     active_skills: Vec<String>,
+    // Skills scoped to this agent's conversation; None falls back to the global active set
+    conversation_skills: Option<Vec<String>>,
+    temperature: f32,
     // Todo management
     todos: Arc<AsyncMutex<Vec<crate::tools::create_todo::TodoItem>>>,
     todos_by_conversation:
@@ -119,7 +182,61 @@ pub struct Agent {
     available_models: Arc<RwLock<Vec<String>>>,
     // Suppress output (for ACP mode where stdout must be clean)
     suppress_output: bool,
+    /// Keep the crash handler's snapshot of this session up to date
+    crash_recovery: bool,
+    /// Titles and summarizes conversations after each turn
+    summary_worker: Option<crate::processing::SummaryWorker>,
+    /// Commits the files each turn changes, when checkpoints are enabled
+    checkpointer: Option<crate::checkpoint::Checkpointer>,
+    /// Reports errors in the files Write and Edit change, when enabled
+    lsp: Option<Arc<crate::lsp::LspManager>>,
+    /// Check the syntax of the files Write and Edit change
+    check_syntax: bool,
+    /// Formatters run on the files Write and Edit change, when enabled
+    format: crate::config::FormatConfig,
+    /// Where `/share` sends transcripts
+    share: crate::config::ShareConfig,
+    /// How `/voice` records and transcribes
+    voice: crate::config::VoiceConfig,
+    /// Whether to suggest or add the files a prompt mentions without an @
+    discovery: crate::config::DiscoveryConfig,
+    /// The project's test command and whether to run it after changes
+    tests: crate::config::TestsConfig,
+    /// Whether the last turn changed files, so the tests need running
+    last_turn_modified_files: bool,
+    /// Files changed this session, for the diff pane and `/diff`
+    session_diff: Arc<std::sync::Mutex<SessionDiff>>,
     hook_manager: Option<Arc<HookManager>>,
+    webhooks: Option<Arc<WebhookDispatcher>>,
+    token_budget: Option<u64>,
+    /// Tools never offered to the model, from the `denied_tools` config option
+    denied_tools: HashSet<String>,
+    /// Model requests allowed per message before the agent stops
+    max_turns: usize,
+    /// Whether the last message stopped because it hit `max_turns`
+    max_turns_reached: bool,
+    /// Restrictions from `--allowed-tools` and `--disallowed-tools`
+    tool_filter: ToolFilter,
+}
+
+/// Default limit on model requests per message
+const DEFAULT_MAX_TURNS: usize = 500;
+
+/// An error from the LLM provider: a network failure, an error status or a
+/// response that couldn't be parsed. Displays as the underlying error.
+#[derive(Debug)]
+pub struct LlmRequestError(pub anyhow::Error);
+
+impl std::fmt::Display for LlmRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for LlmRequestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
 }
 
 impl Agent {
@@ -143,6 +260,8 @@ impl Agent {
     }
     pub fn new(config: Config, model: String, yolo_mode: bool, plan_mode: bool) -> Self {
         let base_url = config.base_url.clone();
+        let provider_credentials =
+            HashMap::from([(config.provider, (config.api_key.clone(), base_url.clone()))]);
         let client = LlmClient::new(config.provider, config.api_key, base_url.clone());
         let tools = get_builtin_tools()
             .into_iter()
@@ -153,7 +272,11 @@ impl Agent {
         let mut bash_security_manager = BashSecurityManager::new(config.bash_security.clone());
 
         // Create file security manager
-        let mut file_security_manager = FileSecurityManager::new(config.file_security.clone());
+        let mut file_security = config.file_security.clone();
+        if config.auto_accept_edits {
+            file_security.ask_for_permission = false;
+        }
+        let mut file_security_manager = FileSecurityManager::new(file_security);
 
         // Create conversation manager
         let conversation_manager =
@@ -167,10 +290,7 @@ impl Agent {
         let todos_by_conversation = Arc::new(AsyncMutex::new(HashMap::new()));
 
         // Initialize available models with defaults from config
-        let default_models = crate::config::provider_models(config.provider)
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
+        let default_models = crate::config::provider_models(config.provider);
         let available_models = Arc::new(RwLock::new(default_models));
 
         let hook_manager = match HookManager::load() {
@@ -182,9 +302,16 @@ impl Agent {
         };
 
         // Set hook manager on security managers if available
+        let current_model = CurrentModel::new(model.clone());
         if let Some(ref hm) = hook_manager {
-            bash_security_manager.set_hook_manager(hm.clone(), None, model.clone());
-            file_security_manager.set_hook_manager(hm.clone(), None, model.clone());
+            bash_security_manager.set_hook_manager(hm.clone(), None, current_model.clone());
+            file_security_manager.set_hook_manager(hm.clone(), None, current_model.clone());
+        }
+
+        let webhooks = WebhookDispatcher::from_config(&config.webhooks);
+        if let Some(ref wh) = webhooks {
+            bash_security_manager.set_webhook_dispatcher(wh.clone(), current_model.clone());
+            file_security_manager.set_webhook_dispatcher(wh.clone(), current_model.clone());
         }
 
         // Now wrap in Arc<RwLock>
@@ -192,11 +319,13 @@ impl Agent {
         let file_security_manager = Arc::new(RwLock::new(file_security_manager));
 
         Self {
-            client,
+            client: Arc::new(client),
             model,
+            current_model,
             tools: Arc::new(RwLock::new(tools)),
             conversation_manager,
             token_usage: TokenUsage::new(),
+            usage_listener: None,
             mcp_manager: None,
             last_mcp_tools_version: 0,
             bash_security_manager,
@@ -205,16 +334,39 @@ impl Agent {
             plan_mode,
             plan_mode_saved_system_prompt: None,
             saved_conversation_context: None,
+            reported_subagent: None,
             tool_registry,
             provider: config.provider,
             base_url,
+            provider_credentials,
+            response_cache: None,
             skill_manager: None,
             active_skills: Vec::new(),
+            conversation_skills: None,
+            temperature: config.temperature,
             todos,
             todos_by_conversation,
             available_models,
             suppress_output: false,
+            crash_recovery: false,
+            summary_worker: None,
+            checkpointer: None,
+            lsp: None,
+            check_syntax: config.check_syntax,
+            format: config.format.clone(),
+            share: config.share.clone(),
+            voice: config.voice.clone(),
+            discovery: config.discovery.clone(),
+            tests: config.tests.clone(),
+            last_turn_modified_files: false,
+            session_diff: Arc::default(),
             hook_manager,
+            webhooks,
+            token_budget: config.token_budget,
+            denied_tools: config.denied_tools.into_iter().collect(),
+            max_turns: DEFAULT_MAX_TURNS,
+            max_turns_reached: false,
+            tool_filter: ToolFilter::default(),
         }
     }
 
@@ -246,13 +398,17 @@ impl Agent {
                 "use_skill".to_string(),
                 Tool {
                     name: "use_skill".to_string(),
-                    description: "Load the full content of an active skill to access its detailed instructions and guidelines. Use this when you need the complete skill knowledge to help with the user's request.".to_string(),
+                    description: "Load the full content of an active skill to access its detailed instructions and guidelines. Use this when you need the complete skill knowledge to help with the user's request. Pass `reference` to load one of the reference files the skill lists.".to_string(),
                     input_schema: json!({
                         "type": "object",
                         "properties": {
                             "name": {
                                 "type": "string",
                                 "description": "The name of the skill to load (must be an active skill)"
+                            },
+                            "reference": {
+                                "type": "string",
+                                "description": "A reference file listed by the skill, to load instead of the skill itself"
                             }
                         },
                         "required": ["name"]
@@ -270,13 +426,21 @@ impl Agent {
                     metadata: None,
                 },
             );
+            // Asking for clarification is read-only, so plan mode keeps it too
+            tools.insert(
+                "ask_user".to_string(),
+                crate::tools::ask_user::create_ask_user_tool(),
+            );
+            for tool in crate::tools::mcp_setup::create_mcp_setup_tools() {
+                tools.insert(tool.name.clone(), tool);
+            }
         }
 
         // Apply plan mode filtering
         let _ = agent.apply_plan_mode_filtering().await;
 
-        // Fetch Ollama models if using Ollama provider
-        let _ = agent.fetch_ollama_models().await;
+        // Ask Ollama, or a custom provider that lists its models, what it has
+        let _ = agent.fetch_provider_models().await;
 
         agent
     }
@@ -312,9 +476,30 @@ impl Agent {
         Ok(())
     }
 
-    /// Fetch available models from Ollama if using Ollama provider
-    pub async fn fetch_ollama_models(&self) -> Result<()> {
-        if self.provider == Provider::Ollama {
+    /// Fetch available models from Ollama, or from a custom provider whose
+    /// server lists them
+    pub async fn fetch_provider_models(&self) -> Result<()> {
+        if crate::providers::spec(self.provider).lists_models {
+            let api_key = self
+                .provider_credentials
+                .get(&self.provider)
+                .map(|(api_key, _)| api_key.clone())
+                .unwrap_or_default();
+            let client = crate::openai::OpenAIClient::new(api_key, self.base_url.clone());
+            match client.fetch_models().await {
+                Ok(models) if !models.is_empty() => {
+                    debug!("Fetched {} models from {}", models.len(), self.provider);
+                    *self.available_models.write().await = models;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch {} models: {}. Using the configured ones.",
+                        self.provider, e
+                    );
+                }
+            }
+        } else if self.provider == Provider::OLLAMA {
             // Try to fetch models from Ollama
             let ollama_client = crate::ollama::OllamaClient::new(
                 String::new(), // API key not needed for local Ollama
@@ -410,6 +595,17 @@ impl Agent {
         self
     }
 
+    /// Handler for MCP sampling requests, answered with this agent's provider and model
+    /// and approved through its permission prompts
+    pub fn mcp_sampling_handler(&self) -> crate::mcp_sampling::McpSamplingHandler {
+        crate::mcp_sampling::create_sampling_handler(
+            self.client.clone(),
+            self.model.clone(),
+            self.temperature,
+            self.bash_security_manager.clone(),
+        )
+    }
+
     pub fn with_database_manager(mut self, database_manager: Arc<DatabaseManager>) -> Self {
         self.conversation_manager.database_manager = Some(database_manager);
         self
@@ -553,6 +749,30 @@ impl Agent {
         &self.active_skills
     }
 
+    /// Scope skills to this agent's conversation without touching the global active set.
+    /// Passing `None` reverts to the globally active skills.
+    pub async fn set_conversation_skills(&mut self, skills: Option<Vec<String>>) -> Result<()> {
+        if let Some(names) = &skills {
+            let skill_manager = self
+                .skill_manager
+                .as_ref()
+                .ok_or_else(|| anyhow!("Skill manager not initialized"))?;
+            let manager = skill_manager.lock().await;
+            for name in names {
+                if manager.get_skill(name).is_none() {
+                    return Err(anyhow!("Skill '{}' not found", name));
+                }
+            }
+        }
+        self.conversation_skills = skills;
+        Ok(())
+    }
+
+    /// Get the skills scoped to this conversation, if any
+    pub fn conversation_skills(&self) -> Option<&[String]> {
+        self.conversation_skills.as_deref()
+    }
+
     /// Helper method to add core tools (bash and file operations) to the tools map
     ///
     /// # Arguments
@@ -569,16 +789,22 @@ impl Agent {
         check_exists: bool,
     ) {
         use crate::tools::{
-            create_bash_tool, create_create_directory_tool, create_delete_file_tool,
-            create_edit_file_tool, create_write_file_tool,
+            create_bash_tool, create_create_directory_tool, create_create_pr_tool,
+            create_delete_file_tool, create_edit_file_tool, create_replace_in_files_tool,
+            create_write_file_tool,
         };
 
         // Add Bash tool
         if !check_exists || !tools.contains_key("Bash") {
-            let bash_tool = create_bash_tool(bash_security_manager, yolo_mode);
+            let bash_tool = create_bash_tool(bash_security_manager.clone(), yolo_mode);
             tools.insert("Bash".to_string(), bash_tool);
         }
 
+        if !check_exists || !tools.contains_key("create_pr") {
+            let create_pr_tool = create_create_pr_tool(bash_security_manager, yolo_mode);
+            tools.insert("create_pr".to_string(), create_pr_tool);
+        }
+
         // Add file operation tools
         if !check_exists || !tools.contains_key("Write") {
             let write_file_tool = create_write_file_tool(file_security_manager.clone(), yolo_mode);
@@ -590,6 +816,12 @@ impl Agent {
             tools.insert("Edit".to_string(), edit_file_tool);
         }
 
+        if !check_exists || !tools.contains_key("replace_in_files") {
+            let replace_in_files_tool =
+                create_replace_in_files_tool(file_security_manager.clone(), yolo_mode);
+            tools.insert("replace_in_files".to_string(), replace_in_files_tool);
+        }
+
         if !check_exists || !tools.contains_key("delete_file") {
             let delete_file_tool =
                 create_delete_file_tool(file_security_manager.clone(), yolo_mode);
@@ -638,14 +870,10 @@ impl Agent {
             );
 
             // Get all MCP tools
-            match mcp_manager.get_all_tools().await {
+            match mcp_manager.get_named_tools().await {
                 Ok(mcp_tools) => {
-                    for (server_name, mcp_tool) in mcp_tools {
-                        let tool = crate::tools::create_mcp_tool(
-                            &server_name,
-                            mcp_tool,
-                            mcp_manager.clone(),
-                        );
+                    for named in mcp_tools {
+                        let tool = crate::tools::create_named_mcp_tool(named, mcp_manager.clone());
                         tools.insert(tool.name.clone(), tool);
                     }
                     self.last_mcp_tools_version = current_version;
@@ -700,6 +928,170 @@ impl Agent {
         self.suppress_output = suppress;
     }
 
+    /// Track this agent's session so it can be recovered after a crash. Only
+    /// one agent, the interactive session's, should do this.
+    pub fn set_crash_recovery(&mut self, enabled: bool) {
+        self.crash_recovery = enabled;
+    }
+
+    pub fn set_summary_worker(&mut self, worker: Option<crate::processing::SummaryWorker>) {
+        self.summary_worker = worker;
+    }
+
+    pub fn summary_worker(&self) -> Option<&crate::processing::SummaryWorker> {
+        self.summary_worker.as_ref()
+    }
+
+    pub fn set_checkpointer(&mut self, checkpointer: Option<crate::checkpoint::Checkpointer>) {
+        self.checkpointer = checkpointer;
+    }
+
+    pub fn checkpointer(&self) -> Option<&crate::checkpoint::Checkpointer> {
+        self.checkpointer.as_ref()
+    }
+
+    pub fn share_config(&self) -> &crate::config::ShareConfig {
+        &self.share
+    }
+
+    pub fn voice_config(&self) -> &crate::config::VoiceConfig {
+        &self.voice
+    }
+
+    pub fn set_lsp(&mut self, lsp: Option<Arc<crate::lsp::LspManager>>) {
+        self.lsp = lsp;
+    }
+
+    /// Remember the file a Write, Edit or delete is about to change, for the
+    /// session diff
+    fn record_for_session_diff(&self, call: &ToolCall) {
+        if let Some((_, resolved)) = Self::edited_path(call) {
+            self.session_diff
+                .lock()
+                .expect("session diff lock")
+                .record(&resolved);
+        }
+    }
+
+    /// The path a Write or Edit call changes, as given and resolved
+    fn edited_path(call: &ToolCall) -> Option<(&str, std::path::PathBuf)> {
+        let path = call.arguments.get("path").and_then(|v| v.as_str())?;
+        let resolved = crate::tools::path::resolve_project_path(path).ok()?;
+        Some((path, resolved))
+    }
+
+    /// Whether the file a Write or Edit is about to change passes the syntax
+    /// check, so that only errors the change introduces are reported. A file
+    /// that doesn't exist yet passes.
+    async fn syntax_ok_before_edit(&self, call: &ToolCall) -> bool {
+        let Some((_, resolved)) = Self::edited_path(call) else {
+            return false;
+        };
+        if !self.check_syntax || !crate::syntax_check::is_supported(&resolved) {
+            return false;
+        }
+        match tokio::fs::read_to_string(&resolved).await {
+            Ok(text) => crate::syntax_check::check(&resolved, &text).is_none(),
+            Err(_) => true,
+        }
+    }
+
+    /// Tell the model, and the user, when a successful Write or Edit left a
+    /// file that passed the syntax check before with a syntax error
+    async fn append_syntax_error(&self, call: &ToolCall, result: &mut ToolResult, ok_before: bool) {
+        if !ok_before || result.is_error {
+            return;
+        }
+        let Some((path, resolved)) = Self::edited_path(call) else {
+            return;
+        };
+        let Ok(text) = tokio::fs::read_to_string(&resolved).await else {
+            return;
+        };
+        if let Some(error) = crate::syntax_check::check(&resolved, &text) {
+            if !self.suppress_output {
+                app_println!(
+                    "{} {}:{}:{}: {}",
+                    "⚠ Syntax error after this change:".yellow(),
+                    path,
+                    error.line,
+                    error.column,
+                    error.message
+                );
+            }
+            result.content.push_str("\n\n");
+            result
+                .content
+                .push_str(&crate::syntax_check::format_error(path, &error));
+        }
+    }
+
+    /// Run the configured formatter on the file a successful Write or Edit
+    /// changed, and quote what it changed in the result so the model's next
+    /// edit matches the file on disk
+    async fn format_edited_file(&self, call: &ToolCall, result: &mut ToolResult) {
+        if !self.format.enabled || result.is_error {
+            return;
+        }
+        let Some((path, resolved)) = Self::edited_path(call) else {
+            return;
+        };
+        let Some(formatter) = crate::autoformat::formatter_for(&self.format, &resolved) else {
+            return;
+        };
+        match crate::autoformat::format_file(formatter, &resolved).await {
+            Ok(Some(change)) => {
+                if !self.suppress_output {
+                    app_println!(
+                        "{}",
+                        format!("Formatted {} with {}", path, formatter.command).dimmed()
+                    );
+                }
+                result.content.push_str("\n\n");
+                result.content.push_str(&crate::autoformat::format_note(
+                    path,
+                    &formatter.command,
+                    &change,
+                ));
+            }
+            Ok(None) => {}
+            Err(e) => debug!("{} didn't format {}: {}", formatter.command, path, e),
+        }
+    }
+
+    /// Add the language server's errors in the file a successful Write or
+    /// Edit changed to its result, so the model sees a broken edit at once
+    async fn append_diagnostics(&self, call: &ToolCall, result: &mut ToolResult) {
+        let Some(lsp) = &self.lsp else {
+            return;
+        };
+        if result.is_error {
+            return;
+        }
+        let Some((path, resolved)) = Self::edited_path(call) else {
+            return;
+        };
+        if let Some(diagnostics) = lsp.diagnostics(&resolved).await {
+            if !diagnostics.is_empty() {
+                result.content.push_str("\n\n");
+                result
+                    .content
+                    .push_str(&crate::lsp::format_diagnostics(path, &diagnostics));
+            }
+        }
+    }
+
+    /// The client this agent sends requests with
+    pub fn llm_client(&self) -> Arc<LlmClient> {
+        self.client.clone()
+    }
+
+    /// Answer requests this agent has sent before from `cache`
+    pub fn set_response_cache(&mut self, cache: crate::response_cache::ResponseCache) {
+        self.client = Arc::new(self.client.with_response_cache(cache.clone()));
+        self.response_cache = Some(cache);
+    }
+
     /// Apply the plan-mode system prompt while preserving any existing prompt context
     pub fn apply_plan_mode_prompt(&mut self) {
         let existing_prompt = self.conversation_manager.system_prompt.clone();
@@ -768,6 +1160,11 @@ impl Agent {
         self.conversation_manager.add_context_file(file_path).await
     }
 
+    /// Add text as a labeled context block, e.g. content piped to stdin
+    pub fn add_context_text(&mut self, label: &str, content: &str) {
+        self.conversation_manager.add_context_text(label, content)
+    }
+
     /// Extract file paths from message using @path syntax
     pub fn extract_context_files(&self, message: &str) -> Vec<String> {
         self.conversation_manager.extract_context_files(message)
@@ -794,15 +1191,198 @@ impl Agent {
         on_tool_event: Option<Arc<dyn Fn(StreamToolEvent) + Send + Sync + 'static>>,
         cancellation_flag: Arc<AtomicBool>,
     ) -> Result<String> {
+        let mut response = self
+            .run_turn(
+                message,
+                true,
+                on_stream_content.clone(),
+                on_tool_event.clone(),
+                cancellation_flag.clone(),
+            )
+            .await?;
+
+        let command = match &self.tests.command {
+            Some(command) if self.tests.auto_run && !command.trim().is_empty() => command.clone(),
+            _ => return Ok(response),
+        };
+        let max_attempts = self.tests.max_fix_attempts;
+        let mut attempt = 0;
+        while self.last_turn_modified_files && !cancellation_flag.load(Ordering::SeqCst) {
+            let Some(run) = self.run_tests(&command).await else {
+                break;
+            };
+            if run.passed {
+                break;
+            }
+            if attempt == max_attempts {
+                if !self.suppress_output {
+                    app_println!(
+                        "{} Tests still fail after {} fix attempts",
+                        "✗".red(),
+                        max_attempts
+                    );
+                }
+                break;
+            }
+            attempt += 1;
+            if !self.suppress_output {
+                app_println!(
+                    "{} Tests failed; asking for a fix (attempt {}/{})",
+                    "✗".red(),
+                    attempt,
+                    max_attempts
+                );
+            }
+            let prompt = crate::test_runner::fix_prompt(&command, &run, attempt, max_attempts);
+            response = self
+                .run_turn(
+                    &prompt,
+                    false,
+                    on_stream_content.clone(),
+                    on_tool_event.clone(),
+                    cancellation_flag.clone(),
+                )
+                .await?;
+        }
+        Ok(response)
+    }
+
+    /// Run the project's tests under the bash security policy. None when
+    /// they couldn't be run, e.g. because permission was denied.
+    async fn run_tests(&self, command: &str) -> Option<crate::test_runner::TestRun> {
+        if !self.suppress_output {
+            app_println!("{} {}", "🧪 Running tests:".cyan(), command);
+        }
+        let timeout = std::time::Duration::from_secs(self.tests.timeout_secs);
+        let result = {
+            let mut manager = self.bash_security_manager.write().await;
+            crate::test_runner::run(command, timeout, &mut manager, self.yolo_mode).await
+        };
+        match result {
+            Ok(run) => {
+                if run.passed && !self.suppress_output {
+                    app_println!("{} Tests pass", "✓".green());
+                }
+                Some(run)
+            }
+            Err(e) => {
+                warn!("Couldn't run the tests: {}", e);
+                if !self.suppress_output {
+                    app_println!("{} Couldn't run the tests: {}", "⚠".yellow(), e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Suggest, or add to the context, the files `message` names or whose
+    /// types and functions it mentions without an @
+    async fn discover_files(&mut self, message: &str, mentioned: &[String]) {
+        use path_absolutize::Absolutize;
+
+        let Ok(root) = std::env::current_dir() else {
+            return;
+        };
+        let search_root = root.clone();
+        let search_message = message.to_string();
+        let max_files = self.discovery.max_files;
+        let found = match tokio::task::spawn_blocking(move || {
+            crate::file_discovery::discover(&search_root, &search_message, max_files)
+        })
+        .await
+        {
+            Ok(found) => found,
+            Err(e) => {
+                warn!("File discovery failed: {}", e);
+                return;
+            }
+        };
+
+        let mentioned: Vec<std::path::PathBuf> = mentioned
+            .iter()
+            .filter_map(|path| {
+                let expanded = shellexpand::tilde(path);
+                std::path::Path::new(&*expanded)
+                    .absolutize()
+                    .ok()
+                    .map(|p| p.to_path_buf())
+            })
+            .collect();
+        let mut suggestions = Vec::new();
+        for discovery in found {
+            let absolute = root.join(&discovery.path);
+            let label = format!("Context from file '{}'", absolute.display());
+            let in_context = self
+                .conversation_manager
+                .conversation
+                .iter()
+                .any(|message| {
+                    message.content.iter().any(|block| {
+                        block
+                            .text
+                            .as_deref()
+                            .is_some_and(|text| text.starts_with(&label))
+                    })
+                });
+            if in_context || mentioned.contains(&absolute) {
+                continue;
+            }
+            let display = discovery.path.display().to_string();
+            match self.discovery.mode {
+                crate::config::DiscoveryMode::Auto => {
+                    match self.add_context_file(&absolute.to_string_lossy()).await {
+                        Ok(()) if !self.suppress_output => app_println!(
+                            "{} Added {} to the context ({})",
+                            "📎".cyan(),
+                            display,
+                            discovery.reason
+                        ),
+                        Ok(()) => {}
+                        Err(e) => warn!("Failed to add discovered file {}: {}", display, e),
+                    }
+                }
+                _ => suggestions.push(format!("@{} ({})", display, discovery.reason)),
+            }
+        }
+        if !suggestions.is_empty() && !self.suppress_output {
+            app_println!(
+                "{} Files that may help: {}",
+                "💡".yellow(),
+                suggestions.join(", ")
+            );
+        }
+    }
+
+    /// One user turn. `expand_mentions` is false for the follow-up turns
+    /// Flexorama sends itself, whose text shouldn't be read for @file syntax.
+    async fn run_turn(
+        &mut self,
+        message: &str,
+        expand_mentions: bool,
+        on_stream_content: Option<Arc<dyn Fn(String) + Send + Sync + 'static>>,
+        on_tool_event: Option<Arc<dyn Fn(StreamToolEvent) + Send + Sync + 'static>>,
+        cancellation_flag: Arc<AtomicBool>,
+    ) -> Result<String> {
+        if self.conversation_manager.read_only {
+            return Err(anyhow!(
+                "This conversation is open read-only because another Flexorama process is using it. Resume it again once that process is done, or start a new conversation."
+            ));
+        }
+
         // Log incoming user message
         debug!("Processing user message: {}", message);
         debug!(
             "Current conversation length: {}",
             self.conversation_manager.conversation.len()
         );
+        self.last_turn_modified_files = false;
 
         // Extract and add context files from @ syntax
-        let context_files = self.extract_context_files(message);
+        let context_files = if expand_mentions {
+            self.extract_context_files(message)
+        } else {
+            Vec::new()
+        };
         for file_path in &context_files {
             debug!("Auto-adding context file from @ syntax: {}", file_path);
             match self.add_context_file(file_path).await {
@@ -816,8 +1396,16 @@ impl Agent {
             }
         }
 
+        if expand_mentions && self.discovery.mode != crate::config::DiscoveryMode::Off {
+            self.discover_files(message, &context_files).await;
+        }
+
         // Clean message by removing @file syntax
-        let mut cleaned_message = self.clean_message(message);
+        let mut cleaned_message = if expand_mentions {
+            self.clean_message(message)
+        } else {
+            message.to_string()
+        };
 
         if let Some(hook_manager) = &self.hook_manager {
             let hook_decision = hook_manager
@@ -861,6 +1449,29 @@ impl Agent {
         {
             warn!("Failed to save user message to database: {}", e);
         }
+        if self.crash_recovery {
+            crate::crash::turn_started(
+                self.conversation_manager.current_conversation_id.clone(),
+                &self.model,
+                &cleaned_message,
+            );
+        }
+
+        // The files as the turn found them, so its checkpoint holds only what it changed
+        let checkpoint_base = match &self.checkpointer {
+            Some(checkpointer) => match checkpointer.snapshot().await {
+                Ok(tree) => Some(tree),
+                Err(e) => {
+                    warn!(
+                        "Failed to snapshot the working tree for a checkpoint: {}",
+                        e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let mut modified_files = false;
 
         // Refresh MCP tools before processing the message (only if they have changed)
         if let Err(e) = self.refresh_mcp_tools().await {
@@ -869,9 +1480,13 @@ impl Agent {
         }
 
         // Inject active skills into system prompt
+        let skill_tool_rules = self.skill_tool_rules().await;
         if let Some(skill_manager) = &self.skill_manager {
             let manager = skill_manager.lock().await;
-            let skills_content = manager.get_active_skills_content();
+            let skills_content = match &self.conversation_skills {
+                Some(names) => manager.get_skills_content_for(names),
+                None => manager.get_active_skills_content(),
+            };
 
             if !skills_content.is_empty() {
                 // Prepend skills to system prompt
@@ -885,10 +1500,34 @@ impl Agent {
             }
         }
 
+        if let Some(callback) = &on_tool_event {
+            for event in self.subagent_events() {
+                callback(event);
+            }
+        }
+        let on_delta = on_tool_event.clone().map(|callback| -> DeltaCallback {
+            Arc::new(move |delta| {
+                callback(match delta {
+                    ReplyDelta::Thinking(text) => StreamToolEvent::ThinkingDelta { text },
+                    ReplyDelta::ToolInput {
+                        tool_use_id,
+                        name,
+                        delta,
+                    } => StreamToolEvent::ToolInputDelta {
+                        tool_use_id,
+                        name,
+                        delta,
+                    },
+                })
+            })
+        });
+
         let mut final_response = String::new();
         let mut final_response_tokens: Option<i32> = None;
-        let max_iterations = 500;
+        let max_iterations = self.max_turns;
         let mut iteration = 0;
+        let mut context_reduced = false;
+        self.max_turns_reached = false;
 
         while iteration < max_iterations {
             iteration += 1;
@@ -898,45 +1537,122 @@ impl Agent {
                 return Err(anyhow::anyhow!("CANCELLED"));
             }
 
-            // Get available tools
+            // Pick up servers connected during the turn, such as by add_mcp_server
+            if iteration > 1 {
+                if let Err(e) = self.refresh_mcp_tools().await {
+                    warn!("Failed to refresh MCP tools: {}", e);
+                }
+            }
 
-            let available_tools: Vec<Tool> = {
-                let tools = self.tools.read().await;
-                let registry = self.tool_registry.read().await;
-                tools
-                    .values()
-                    .filter(|tool| !self.plan_mode || registry.is_readonly(&tool.name))
-                    .cloned()
-                    .collect()
-            };
+            // Get available tools
+            let available_tools = self.offered_tools(&skill_tool_rules).await;
+
+            let request_id = uuid::Uuid::new_v4().to_string();
+            let request_started = std::time::Instant::now();
+            crate::json_log::event(
+                "llm_request",
+                json!({
+                    "request_id": request_id,
+                    "conversation_id": self.conversation_manager.current_conversation_id,
+                    "provider": self.provider.to_string(),
+                    "model": self.model,
+                    "messages": self.conversation_manager.conversation.len(),
+                    "tools": available_tools.len(),
+                }),
+            );
 
             // Call Anthropic API with streaming if callback provided
-            let response = if let Some(ref on_content) = on_stream_content {
-                self.client
-                    .create_message_stream(
-                        &self.model,
-                        self.conversation_manager.conversation.clone(),
-                        &available_tools,
-                        4096,
-                        0.7,
-                        self.conversation_manager.system_prompt.as_ref(),
-                        Arc::clone(on_content),
-                        cancellation_flag.clone(),
-                    )
-                    .await?
-            } else {
-                self.client
-                    .create_message(
-                        &self.model,
-                        self.conversation_manager.conversation.clone(),
-                        &available_tools,
-                        4096,
-                        0.7,
-                        self.conversation_manager.system_prompt.as_ref(),
-                        cancellation_flag.clone(),
-                    )
-                    .await?
+            let request = async {
+                if let Some(ref on_content) = on_stream_content {
+                    self.client
+                        .create_message_stream(
+                            &self.model,
+                            self.conversation_manager.conversation.clone(),
+                            &available_tools,
+                            4096,
+                            self.temperature,
+                            self.conversation_manager.system_prompt.as_ref(),
+                            self.with_live_usage(Arc::clone(on_content)),
+                            on_delta.clone(),
+                            cancellation_flag.clone(),
+                        )
+                        .await
+                } else {
+                    self.client
+                        .create_message(
+                            &self.model,
+                            self.conversation_manager.conversation.clone(),
+                            &available_tools,
+                            4096,
+                            self.temperature,
+                            self.conversation_manager.system_prompt.as_ref(),
+                            cancellation_flag.clone(),
+                        )
+                        .await
+                }
+            };
+            // Stop waiting on the provider as soon as the user cancels
+            let response = tokio::select! {
+                response = request => response,
+                _ = crate::cancellation::cancelled(&cancellation_flag) => {
+                    return Err(anyhow::anyhow!("CANCELLED"));
+                }
             };
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    crate::json_log::event(
+                        "llm_error",
+                        json!({
+                            "request_id": request_id,
+                            "duration_ms": request_started.elapsed().as_millis() as u64,
+                            "error": error,
+                        }),
+                    );
+                    if error.contains("CANCELLED") {
+                        return Err(e);
+                    }
+                    // Retry once with less context rather than lose the turn
+                    if !context_reduced && crate::context_usage::is_context_overflow(&error) {
+                        context_reduced = true;
+                        let messages_before = self.conversation_manager.conversation.len();
+                        if crate::context_usage::reduce_context(
+                            &mut self.conversation_manager.conversation,
+                        ) {
+                            let messages_after = self.conversation_manager.conversation.len();
+                            warn!(
+                                "Context overflow; retrying with {} of {} messages",
+                                messages_after, messages_before
+                            );
+                            if !self.suppress_output {
+                                app_println!(
+                                    "{} The conversation doesn't fit {}'s context window; retrying with less of the conversation",
+                                    "⚠️".yellow(),
+                                    self.model
+                                );
+                            }
+                            if let Some(callback) = &on_tool_event {
+                                callback(StreamToolEvent::ContextCompacted {
+                                    messages_before,
+                                    messages_after,
+                                });
+                            }
+                            continue;
+                        }
+                    }
+                    return Err(anyhow::Error::new(LlmRequestError(e)));
+                }
+            };
+            crate::json_log::event(
+                "llm_response",
+                json!({
+                    "request_id": request_id,
+                    "duration_ms": request_started.elapsed().as_millis() as u64,
+                    "input_tokens": response.usage.as_ref().map(|u| u.input_tokens),
+                    "output_tokens": response.usage.as_ref().map(|u| u.output_tokens),
+                }),
+            );
             // Track token usage
             let response_total_tokens = response
                 .usage
@@ -944,7 +1660,21 @@ impl Agent {
                 .map(|u| (u.input_tokens + u.output_tokens) as i32)
                 .unwrap_or(0);
             if let Some(usage) = &response.usage {
+                let tokens_before = self.token_usage.total_tokens() as u64;
                 self.token_usage.add_usage(usage);
+                if let Some(budget) = self.token_budget {
+                    let tokens_after = self.token_usage.total_tokens() as u64;
+                    if tokens_before < budget && tokens_after >= budget {
+                        warn!(
+                            "Token budget of {} exceeded ({} tokens used)",
+                            budget, tokens_after
+                        );
+                        self.emit_webhook(
+                            WebhookEvent::BudgetExceeded,
+                            json!({ "token_budget": budget, "total_tokens": tokens_after }),
+                        );
+                    }
+                }
                 debug!(
                     "Updated token usage - Total: {} (Input: {}, Output: {})",
                     self.token_usage.total_tokens(),
@@ -963,7 +1693,16 @@ impl Agent {
                 {
                     warn!("Failed to update database usage stats: {}", e);
                 }
+                if let Some(callback) = &on_tool_event {
+                    callback(StreamToolEvent::Usage {
+                        input_tokens: usage.input_tokens,
+                        output_tokens: usage.output_tokens,
+                        total_tokens: self.token_usage.total_tokens(),
+                    });
+                }
             }
+            // Replaces the estimate made while the reply streamed
+            self.report_usage();
 
             // Extract and output the text response from this API call
             let response_content = self.client.create_response_content(&response.content);
@@ -1012,7 +1751,9 @@ impl Agent {
                 }
             }
 
-            // Execute tool calls using the new display system
+            // Execute tool calls using the new display system. The calls are
+            // saved along with the assistant message once they've all run.
+            let mut tool_call_records = Vec::new();
             let tool_results: Vec<ToolResult> = {
                 let mut results = Vec::new();
                 for call in &tool_calls {
@@ -1049,34 +1790,21 @@ impl Agent {
                         call_to_run.name, call_to_run.id
                     );
 
-                    if let (Some(db), Some(conversation_id)) = (
-                        self.conversation_manager.database_manager.clone(),
-                        self.conversation_manager.current_conversation_id.clone(),
-                    ) {
-                        let args_str = serde_json::to_string(&call_to_run.arguments)
-                            .unwrap_or_else(|_| call_to_run.arguments.to_string());
-                        if let Err(e) = db
-                            .add_tool_call(
-                                &conversation_id,
-                                None,
-                                &call_to_run.id,
-                                &call_to_run.name,
-                                &args_str,
-                            )
-                            .await
-                        {
-                            warn!("Failed to record tool call {}: {}", call_to_run.name, e);
-                        }
-                    }
+                    tool_call_records.push(ToolCallRecord {
+                        id: call_to_run.id.clone(),
+                        tool_name: call_to_run.name.clone(),
+                        tool_arguments: serde_json::to_string(&call_to_run.arguments)
+                            .unwrap_or_else(|_| call_to_run.arguments.to_string()),
+                        result_content: None,
+                        is_error: false,
+                        created_at: chrono::Utc::now(),
+                    });
 
                     if let Some(callback) = &on_tool_event {
-                        callback(StreamToolEvent {
-                            event: "tool_call".to_string(),
+                        callback(StreamToolEvent::ToolCall {
                             tool_use_id: call_to_run.id.clone(),
                             name: call_to_run.name.clone(),
-                            input: Some(call_to_run.arguments.clone()),
-                            content: None,
-                            is_error: None,
+                            input: call_to_run.arguments.clone(),
                         });
                     }
 
@@ -1097,29 +1825,70 @@ impl Agent {
                         }
                     }
 
-                    // Use the new display system and execute tool
-                    let result = self.execute_tool_with_display(&call_to_run).await;
-                    if let (Some(db), Some(_conversation_id)) = (
-                        self.conversation_manager.database_manager.clone(),
-                        self.conversation_manager.current_conversation_id.clone(),
-                    ) {
-                        if let Err(e) = db
-                            .complete_tool_call(&call_to_run.id, &result.content, result.is_error)
-                            .await
-                        {
-                            warn!("Failed to record tool result {}: {}", call_to_run.name, e);
+                    // Use the new display system and execute tool. Cancelling drops
+                    // the tool future, which kills any process it started.
+                    let tool_started = std::time::Instant::now();
+                    if self.crash_recovery {
+                        crate::crash::tool_started(&call_to_run.id, &call_to_run.name);
+                    }
+                    let result = tokio::select! {
+                        result = self.execute_tool_with_display(&call_to_run) => result,
+                        _ = crate::cancellation::cancelled(&cancellation_flag) => {
+                            info!("Cancelled while running tool '{}'", call_to_run.name);
+                            return Err(anyhow::anyhow!("CANCELLED"));
                         }
+                    };
+                    if self.crash_recovery {
+                        crate::crash::tool_finished(
+                            &call_to_run.id,
+                            &result.content,
+                            result.is_error,
+                        );
+                    }
+                    crate::json_log::event(
+                        "tool_call",
+                        json!({
+                            "request_id": request_id,
+                            "tool_use_id": call_to_run.id,
+                            "tool": call_to_run.name,
+                            "duration_ms": tool_started.elapsed().as_millis() as u64,
+                            "is_error": result.is_error,
+                            "error": result.is_error.then(|| truncate_for_log(&result.content)),
+                        }),
+                    );
+                    if let Some(record) = tool_call_records.last_mut() {
+                        record.result_content = Some(result.content.clone());
+                        record.is_error = result.is_error;
+                    }
+                    if !result.is_error
+                        && !self
+                            .tool_registry
+                            .read()
+                            .await
+                            .is_readonly(&call_to_run.name)
+                    {
+                        modified_files = true;
+                        self.last_turn_modified_files = true;
                     }
                     if let Some(callback) = &on_tool_event {
-                        callback(StreamToolEvent {
-                            event: "tool_result".to_string(),
+                        callback(StreamToolEvent::ToolResult {
                             tool_use_id: call_to_run.id.clone(),
                             name: call_to_run.name.clone(),
-                            input: None,
-                            content: Some(result.content.clone()),
-                            is_error: Some(result.is_error),
+                            content: result.content.clone(),
+                            is_error: result.is_error,
                         });
                     }
+                    if result.is_error {
+                        self.emit_webhook(
+                            WebhookEvent::ToolError,
+                            json!({
+                                "tool_use_id": call_to_run.id,
+                                "tool_name": call_to_run.name,
+                                "arguments": call_to_run.arguments,
+                                "error": result.content,
+                            }),
+                        );
+                    }
 
                     if let Some(hook_manager) = &self.hook_manager {
                         let hook_decision = hook_manager
@@ -1154,13 +1923,14 @@ impl Agent {
                 content: assistant_content.clone(),
             });
 
-            // Save assistant response to database
+            // Save assistant response and its tool calls to database
             if let Err(e) = self
                 .conversation_manager
-                .save_message_to_conversation(
+                .save_message_with_tool_calls(
                     "assistant",
                     &self.client.create_response_content(&assistant_content),
                     response_total_tokens,
+                    &tool_call_records,
                 )
                 .await
             {
@@ -1181,6 +1951,7 @@ impl Agent {
         }
 
         if iteration >= max_iterations {
+            self.max_turns_reached = true;
             final_response.push_str("\n\n(Note: Maximum tool iterations reached)");
         }
 
@@ -1204,6 +1975,25 @@ impl Agent {
                 warn!("Failed to save final assistant message to database: {}", e);
             }
         }
+        if self.crash_recovery {
+            crate::crash::turn_finished();
+        }
+        if let (Some(checkpointer), Some(base), true) =
+            (&self.checkpointer, &checkpoint_base, modified_files)
+        {
+            match checkpointer.create(base, &cleaned_message).await {
+                Ok(Some(checkpoint)) if !self.suppress_output => app_println!(
+                    "{}",
+                    format!(
+                        "Checkpoint {} saved · /checkpoints revert to undo",
+                        checkpoint.short
+                    )
+                    .dimmed()
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to create checkpoint: {}", e),
+            }
+        }
         if let Some(hook_manager) = &self.hook_manager {
             // Use SubagentStop when in subagent mode, Stop otherwise
             let hook_decision = if self.conversation_manager.subagent.is_some() {
@@ -1230,16 +2020,104 @@ impl Agent {
             }
         }
 
+        self.emit_webhook(
+            WebhookEvent::TurnCompleted,
+            json!({
+                "response": final_response,
+                "iterations": iteration,
+                "total_tokens": self.token_usage.total_tokens(),
+            }),
+        );
+
         debug!("Final response generated ({} chars)", final_response.len());
         Ok(final_response)
     }
 
+    /// Send an event to the configured webhooks, if any
+    fn emit_webhook(&self, event: WebhookEvent, data: serde_json::Value) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.emit(
+                event,
+                self.conversation_manager.current_conversation_id.as_deref(),
+                &self.model,
+                data,
+            );
+        }
+    }
+
     pub fn get_token_usage(&self) -> &TokenUsage {
         &self.token_usage
     }
 
     pub fn reset_token_usage(&mut self) {
         self.token_usage.reset();
+        self.report_usage();
+    }
+
+    pub fn set_usage_listener(&mut self, listener: Option<UsageListener>) {
+        self.usage_listener = listener;
+    }
+
+    fn report_usage(&self) {
+        if let Some(listener) = &self.usage_listener {
+            listener(&self.token_usage);
+        }
+    }
+
+    /// Wrap a stream callback so the usage listener hears about the reply's
+    /// output as it arrives, estimated until the provider reports the count
+    fn with_live_usage(
+        &self,
+        on_content: Arc<dyn Fn(String) + Send + Sync + 'static>,
+    ) -> Arc<dyn Fn(String) + Send + Sync + 'static> {
+        let Some(listener) = self.usage_listener.clone() else {
+            return on_content;
+        };
+        let base = self.token_usage.clone();
+        let streamed = std::sync::Mutex::new((String::new(), None::<std::time::Instant>));
+        Arc::new(move |text: String| {
+            let live = {
+                let mut streamed = streamed.lock().expect("streamed text lock");
+                streamed.0.push_str(&text);
+                if streamed
+                    .1
+                    .is_some_and(|reported| reported.elapsed() < LIVE_USAGE_INTERVAL)
+                {
+                    None
+                } else {
+                    streamed.1 = Some(std::time::Instant::now());
+                    let mut live = base.clone();
+                    live.total_output_tokens +=
+                        crate::context_usage::estimate_tokens(&streamed.0) as u32;
+                    Some(live)
+                }
+            };
+            on_content(text);
+            if let Some(live) = live {
+                listener(&live);
+            }
+        })
+    }
+
+    /// Whether the configured token budget has been used up
+    pub fn token_budget_exceeded(&self) -> bool {
+        self.token_budget
+            .is_some_and(|budget| self.token_usage.total_tokens() as u64 >= budget)
+    }
+
+    /// Limit the model requests made for a single message
+    pub fn set_max_turns(&mut self, max_turns: usize) {
+        self.max_turns = max_turns.max(1);
+    }
+
+    /// Restrict the tools offered and the calls allowed for this run
+    pub fn set_tool_filter(&mut self, filter: ToolFilter) {
+        self.tool_filter = filter;
+    }
+
+    /// Whether the last message stopped because it hit the turn limit
+    pub fn max_turns_reached(&self) -> bool {
+        self.max_turns_reached
     }
 
     /// Display the current conversation context
@@ -1247,6 +2125,65 @@ impl Agent {
         self.conversation_manager.display_context();
     }
 
+    /// The allowed and denied tools of each conversation-scoped skill.
+    /// These restrict tools per request instead of mutating the shared tool
+    /// map like global activation does.
+    async fn skill_tool_rules(&self) -> Vec<(HashSet<String>, HashSet<String>)> {
+        let (Some(skill_manager), Some(names)) = (&self.skill_manager, &self.conversation_skills)
+        else {
+            return Vec::new();
+        };
+        let manager = skill_manager.lock().await;
+        names
+            .iter()
+            .filter_map(|name| manager.get_skill(name))
+            .map(|skill| (skill.allowed_tools.clone(), skill.denied_tools.clone()))
+            .collect()
+    }
+
+    /// The tools offered to the model with the next request
+    async fn offered_tools(
+        &self,
+        skill_tool_rules: &[(HashSet<String>, HashSet<String>)],
+    ) -> Vec<Tool> {
+        let tools = self.tools.read().await;
+        let registry = self.tool_registry.read().await;
+        tools
+            .values()
+            .filter(|tool| !self.plan_mode || registry.is_readonly(&tool.name))
+            .filter(|tool| !self.denied_tools.contains(&tool.name))
+            .filter(|tool| {
+                self.mcp_manager.is_some()
+                    || !crate::tools::mcp_setup::MCP_SETUP_TOOLS.contains(&tool.name.as_str())
+            })
+            .filter(|tool| self.tool_filter.offers(&tool.name))
+            .filter(|tool| {
+                skill_tool_rules.iter().all(|(allowed, denied)| {
+                    !denied.contains(&tool.name)
+                        && (allowed.is_empty() || allowed.contains(&tool.name))
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// How the next request would spend the model's context window
+    pub async fn context_breakdown(&self) -> crate::context_usage::ContextBreakdown {
+        let tools = self.offered_tools(&self.skill_tool_rules().await).await;
+        crate::context_usage::ContextBreakdown::build(
+            &self.model,
+            self.conversation_manager.system_prompt.as_deref(),
+            &self.conversation_manager.conversation,
+            &tools,
+        )
+    }
+
+    /// Drop a context file or image from the conversation, by its index in
+    /// [`ContextItem::message_index`](crate::context_usage::ContextItem)
+    pub fn remove_context_message(&mut self, index: usize) -> Result<()> {
+        self.conversation_manager.remove_context_message(index)
+    }
+
     /// Get the current todo list (for TUI display)
     pub async fn get_todos(&self) -> Vec<crate::tools::create_todo::TodoItem> {
         self.get_todos_for(None).await
@@ -1271,6 +2208,12 @@ impl Agent {
         Vec::new()
     }
 
+    /// The files changed this session, shared so the TUI can refresh its
+    /// diff pane after a tool call
+    pub fn session_diff_handle(&self) -> Arc<std::sync::Mutex<SessionDiff>> {
+        Arc::clone(&self.session_diff)
+    }
+
     pub fn todos_handle(&self) -> Arc<AsyncMutex<Vec<crate::tools::create_todo::TodoItem>>> {
         Arc::clone(&self.todos)
     }
@@ -1288,6 +2231,15 @@ impl Agent {
         self.bash_security_manager.clone()
     }
 
+    /// Variables set with `/env` for the current conversation
+    pub fn conversation_env(&self) -> &ConversationEnv {
+        &self.conversation_manager.env
+    }
+
+    pub fn conversation_env_mut(&mut self) -> &mut ConversationEnv {
+        &mut self.conversation_manager.env
+    }
+
     /// Get the file security manager
     pub fn get_file_security_manager(&self) -> Arc<RwLock<FileSecurityManager>> {
         self.file_security_manager.clone()
@@ -1303,6 +2255,51 @@ impl Agent {
         file.set_permission_handler(handler);
     }
 
+    /// Apply a reloaded config. Only settings that are safe to change mid-session
+    /// are touched, and the model and temperature only follow the config if they
+    /// haven't been changed since it was loaded.
+    pub async fn apply_config_reload(&mut self, previous: &Config, config: &Config) -> Result<()> {
+        {
+            let mut manager = self.bash_security_manager.write().await;
+            let mut security = manager.get_security().clone();
+            crate::config_watch::apply_set_diff(
+                &mut security.allowed_commands,
+                &previous.bash_security.allowed_commands,
+                &config.bash_security.allowed_commands,
+            );
+            crate::config_watch::apply_set_diff(
+                &mut security.denied_commands,
+                &previous.bash_security.denied_commands,
+                &config.bash_security.denied_commands,
+            );
+            security.ask_for_permission = config.bash_security.ask_for_permission;
+            security.enabled = config.bash_security.enabled;
+            manager.update_security(security);
+        }
+        if previous.auto_accept_edits != config.auto_accept_edits {
+            let mut manager = self.file_security_manager.write().await;
+            let mut security = manager.get_file_security().clone();
+            security.ask_for_permission = !config.auto_accept_edits;
+            manager.update_file_security(security);
+        }
+        self.denied_tools = config.denied_tools.iter().cloned().collect();
+        self.token_budget = config.token_budget;
+        self.check_syntax = config.check_syntax;
+        self.tests = config.tests.clone();
+        self.format = config.format.clone();
+        self.share = config.share.clone();
+        self.voice = config.voice.clone();
+        self.discovery = config.discovery.clone();
+
+        if self.temperature == previous.temperature {
+            self.temperature = config.temperature;
+        }
+        if self.model == previous.default_model && self.model != config.default_model {
+            self.set_model(config.default_model.clone()).await?;
+        }
+        Ok(())
+    }
+
     /// Display the active LLM provider info
     pub fn display_provider(&self) {
         if !self.suppress_output {
@@ -1323,6 +2320,16 @@ impl Agent {
         &self.model
     }
 
+    /// Get the sampling temperature used for requests
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// Override the sampling temperature for subsequent requests
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.temperature = temperature;
+    }
+
     /// Get the current plan mode state
     pub fn plan_mode(&self) -> bool {
         self.plan_mode
@@ -1335,17 +2342,56 @@ impl Agent {
 
     /// Update the active model for this session
     pub async fn set_model(&mut self, model: String) -> Result<()> {
-        self.model = model.clone();
+        self.replace_model(model.clone());
         self.conversation_manager
             .update_conversation_model(model)
             .await
     }
 
+    /// Switch to `provider` and its `model`. The API key is read the way it is
+    /// at startup: from the provider's environment variable or the keyring.
+    pub async fn set_provider(&mut self, provider: Provider, model: String) -> Result<()> {
+        if provider != self.provider {
+            let (api_key, base_url) = match self.provider_credentials.get(&provider) {
+                Some(credentials) => credentials.clone(),
+                None => {
+                    let api_key = provider_api_key(provider);
+                    if api_key.is_empty() && !crate::config::provider_api_key_optional(provider) {
+                        return Err(anyhow!(
+                            "No API key for {}; set {}",
+                            provider,
+                            crate::config::provider_api_key_env(provider)
+                        ));
+                    }
+                    let credentials = (api_key, crate::config::provider_default_base_url(provider));
+                    self.provider_credentials
+                        .insert(provider, credentials.clone());
+                    credentials
+                }
+            };
+            let mut client = LlmClient::new(provider, api_key, base_url.clone());
+            if let Some(cache) = &self.response_cache {
+                client = client.with_response_cache(cache.clone());
+            }
+            self.client = Arc::new(client);
+            self.provider = provider;
+            self.base_url = base_url;
+            *self.available_models.write().await = crate::config::provider_models(provider);
+            self.fetch_provider_models().await?;
+        }
+        self.set_model(model).await
+    }
+
     fn set_model_local(&mut self, model: String) {
-        self.model = model.clone();
+        self.replace_model(model.clone());
         self.conversation_manager.model = model;
     }
 
+    fn replace_model(&mut self, model: String) {
+        self.current_model.set(model.clone());
+        self.model = model;
+    }
+
     /// Get current configuration (for saving permissions)
     pub async fn get_config_for_save(&self) -> crate::config::Config {
         use crate::config::Config;
@@ -1358,6 +2404,7 @@ impl Agent {
         Config {
             api_key: "".to_string(), // Don't save API key from this method
             provider: self.provider,
+            api_key_secret: None,
             base_url: self.base_url.clone(),
             default_model: self.model.clone(),
             max_tokens: 4096,
@@ -1367,6 +2414,33 @@ impl Agent {
             file_security: crate::security::FileSecurity::default(),
             mcp: crate::config::McpConfig::default(),
             skills: crate::config::SkillConfig::default(),
+            web: crate::config::WebConfig::default(),
+            token_budget: None,
+            webhooks: Vec::new(),
+            denied_tools: Vec::new(),
+            commands_dir: None,
+            stream: true,
+            auto_accept_edits: false,
+            proxy: None,
+            tls: crate::config::TlsConfig::default(),
+            code_line_numbers: false,
+            output_width: None,
+            registry_url: None,
+            logging: crate::config::LoggingConfig::default(),
+            summarize_conversations: true,
+            locale: None,
+            checkpoints: crate::config::CheckpointConfig::default(),
+            lsp: crate::config::LspConfig::default(),
+            check_syntax: self.check_syntax,
+            tests: self.tests.clone(),
+            format: self.format.clone(),
+            share: self.share.clone(),
+            voice: self.voice.clone(),
+            discovery: self.discovery.clone(),
+            response_cache: crate::config::ResponseCacheConfig::default(),
+            images: crate::config::ImagesConfig::default(),
+            templates: std::collections::HashMap::new(),
+            providers: Vec::new(),
         }
     }
 
@@ -1375,6 +2449,16 @@ impl Agent {
         self.conversation_manager.database_manager.clone()
     }
 
+    /// Get the MCP manager (if configured)
+    pub fn mcp_manager(&self) -> Option<Arc<McpManager>> {
+        self.mcp_manager.clone()
+    }
+
+    /// Get the skill manager (if configured)
+    pub fn skill_manager(&self) -> Option<Arc<AsyncMutex<crate::skill::SkillManager>>> {
+        self.skill_manager.clone()
+    }
+
     /// Get the current conversation ID (if any)
     pub fn current_conversation_id(&self) -> Option<String> {
         self.conversation_manager.current_conversation_id.clone()
@@ -1423,6 +2507,17 @@ impl Agent {
         base64_data: String,
         description: Option<String>,
     ) {
+        let (media_type, base64_data) =
+            match crate::image::prepare_base64(&media_type, &base64_data) {
+                Ok(image) => {
+                    debug!("Adding image: {}", image.summary());
+                    (image.media_type.clone(), image.base64())
+                }
+                Err(e) => {
+                    warn!("Sending the image unchanged: {}", e);
+                    (media_type, base64_data)
+                }
+            };
         let mut content = vec![ContentBlock::image(media_type, base64_data)];
 
         if let Some(desc) = description {
@@ -1452,18 +2547,41 @@ impl Agent {
             .await
     }
 
-    /// Replace the active conversation with one loaded from the database
+    /// Replace the active conversation with one loaded from the database.
+    /// Fails with [`ConversationInUse`](crate::conversation_lock::ConversationInUse)
+    /// if another process has it open.
     pub async fn resume_conversation(&mut self, conversation_id: &str) -> Result<()> {
+        self.load_conversation(conversation_id, false).await
+    }
+
+    /// Load a conversation another process has open to look at, without
+    /// taking its lock. Messages can't be sent until another one is resumed.
+    pub async fn open_conversation_read_only(&mut self, conversation_id: &str) -> Result<()> {
+        self.load_conversation(conversation_id, true).await
+    }
+
+    /// Whether the conversation was opened with [`Self::open_conversation_read_only`]
+    pub fn is_read_only(&self) -> bool {
+        self.conversation_manager.read_only
+    }
+
+    async fn load_conversation(&mut self, conversation_id: &str, read_only: bool) -> Result<()> {
         let database_manager = self
             .conversation_manager
             .database_manager
-            .as_ref()
+            .clone()
             .ok_or_else(|| anyhow!("Database is not configured"))?;
 
         let conversation = database_manager
             .get_conversation(conversation_id)
             .await?
             .ok_or_else(|| anyhow!("Conversation {} not found", conversation_id))?;
+        if read_only {
+            self.conversation_manager.lock = None;
+        } else {
+            self.conversation_manager
+                .lock_conversation(conversation_id)?;
+        }
 
         let messages = database_manager
             .get_conversation_messages(conversation_id)
@@ -1472,7 +2590,7 @@ impl Agent {
             .get_conversation_tool_calls(conversation_id)
             .await?;
 
-        self.model = conversation.model.clone();
+        self.replace_model(conversation.model.clone());
         self.conversation_manager.set_conversation_from_records(
             conversation.id.clone(),
             conversation.system_prompt.clone(),
@@ -1481,6 +2599,7 @@ impl Agent {
             &messages,
             &tool_calls,
         );
+        self.conversation_manager.read_only = read_only;
         self.sync_todos_for_current_conversation().await;
 
         Ok(())
@@ -1503,6 +2622,8 @@ impl Agent {
             system_prompt: self.conversation_manager.system_prompt.clone(),
             current_conversation_id: self.conversation_manager.current_conversation_id.clone(),
             model: self.model.clone(),
+            lock: self.conversation_manager.lock.clone(),
+            read_only: self.conversation_manager.read_only,
         });
 
         // Set system prompt
@@ -1554,6 +2675,8 @@ impl Agent {
             self.conversation_manager.system_prompt = saved_context.system_prompt;
             self.conversation_manager.current_conversation_id =
                 saved_context.current_conversation_id;
+            self.conversation_manager.lock = saved_context.lock;
+            self.conversation_manager.read_only = saved_context.read_only;
             self.set_model_local(saved_context.model);
         } else {
             // Reset to default configuration if no saved context
@@ -1566,6 +2689,27 @@ impl Agent {
         Ok(())
     }
 
+    /// Lifecycle events for the stream when the subagent answering turns has
+    /// changed since the last turn reported it
+    fn subagent_events(&mut self) -> Vec<StreamToolEvent> {
+        let active = self.conversation_manager.subagent.clone();
+        if active == self.reported_subagent {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        if let Some(name) = self.reported_subagent.take() {
+            events.push(StreamToolEvent::SubagentEnd { name });
+        }
+        if let Some(name) = active.clone() {
+            events.push(StreamToolEvent::SubagentStart {
+                name,
+                model: self.model.clone(),
+            });
+        }
+        self.reported_subagent = active;
+        events
+    }
+
     pub fn is_subagent_mode(&self) -> bool {
         self.conversation_manager.system_prompt.is_some()
             && !self
@@ -1634,14 +2778,32 @@ impl Agent {
 
     /// Internal tool execution logic (shared between old and new display systems)
     async fn execute_tool_internal(&self, call: &ToolCall) -> Result<ToolResult> {
+        if self.denied_tools.contains(&call.name) {
+            return Ok(ToolResult {
+                tool_use_id: call.id.clone(),
+                content: format!("Tool '{}' is disabled by configuration", call.name),
+                is_error: true,
+            });
+        }
+        if let Err(reason) = self.tool_filter.check_call(&call.name, &call.arguments) {
+            return Ok(ToolResult {
+                tool_use_id: call.id.clone(),
+                content: format!("🔒 Security: Tool call '{}' {}", call.name, reason),
+                is_error: true,
+            });
+        }
+
         // Handle MCP tools
         if call.name.starts_with("mcp_") {
             if let Some(mcp_manager) = &self.mcp_manager {
-                // Extract server name and tool name from the call
-                let parts: Vec<&str> = call.name.splitn(3, '_').collect();
-                if parts.len() >= 3 {
-                    let server_name = parts[1];
-                    let tool_name = parts[2..].join("_");
+                // Look up the server and tool behind the exposed (possibly aliased) name
+                let resolved = mcp_manager
+                    .resolve_tool_name(&call.name)
+                    .await
+                    .ok()
+                    .flatten();
+                if let Some((server_name, tool_name)) = resolved {
+                    let server_name = server_name.as_str();
 
                     match mcp_manager
                         .call_tool(server_name, &tool_name, Some(call.arguments.clone()))
@@ -1692,7 +2854,7 @@ impl Agent {
                         }
                     }
                 } else {
-                    let error_content = format!("Invalid MCP tool name format: {}", call.name);
+                    let error_content = format!("Unknown MCP tool: {}", call.name);
                     Ok(ToolResult {
                         tool_use_id: call.id.clone(),
                         content: error_content,
@@ -1716,8 +2878,32 @@ impl Agent {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow!("Missing 'name' parameter for use_skill"))?;
 
+                let reference = call.arguments.get("reference").and_then(|v| v.as_str());
+
                 let manager = skill_manager.lock().await;
-                match manager.get_skill_full_content(skill_name) {
+                let active = self.conversation_skills.as_deref();
+                let content = match (reference, active) {
+                    (Some(reference), _) => {
+                        manager
+                            .read_skill_reference(skill_name, reference, active)
+                            .await
+                    }
+                    (None, Some(names)) => manager.get_skill_full_content_for(skill_name, names),
+                    (None, None) => manager.get_skill_full_content(skill_name),
+                };
+                // The skill tells the model to run its scripts, so loading it
+                // approves them. Project skills come with the repository, so
+                // their scripts are asked about like any other command.
+                if content.is_ok() && reference.is_none() && !manager.is_project_skill(skill_name) {
+                    if let Some(dir) = manager.skill_dir(skill_name) {
+                        self.bash_security_manager
+                            .write()
+                            .await
+                            .approve_scripts_in(&dir.join("scripts"));
+                    }
+                }
+                drop(manager);
+                match content {
                     Ok(content) => {
                         info!("Loaded full content for skill: {}", skill_name);
                         Ok(ToolResult {
@@ -1749,28 +2935,87 @@ impl Agent {
 
             // We need to get a mutable reference to the security manager
             let mut manager = security_manager.write().await;
-            let result = bash(&call_clone, &mut *manager, self.yolo_mode).await;
+            let result = bash_with_env(
+                &call_clone,
+                &mut *manager,
+                self.yolo_mode,
+                &self.conversation_manager.env,
+            )
+            .await;
             drop(manager); // Explicitly drop the lock guard
             result
+        } else if call.name == "ask_user" {
+            // Take the handler out first so the lock isn't held while the user thinks
+            let handler = self.bash_security_manager.read().await.permission_handler();
+            crate::tools::ask_user::ask_user(call, handler).await
+        } else if call.name == "find_mcp_server" || call.name == "add_mcp_server" {
+            let Some(mcp_manager) = &self.mcp_manager else {
+                return Ok(ToolResult {
+                    tool_use_id: call.id.clone(),
+                    content: "MCP is not available in this session.".to_string(),
+                    is_error: true,
+                });
+            };
+            if call.name == "find_mcp_server" {
+                crate::tools::mcp_setup::find_mcp_server(call, mcp_manager).await
+            } else {
+                let handler = self.bash_security_manager.read().await.permission_handler();
+                crate::tools::mcp_setup::add_mcp_server(call, mcp_manager, handler).await
+            }
+        } else if call.name == "create_pr" {
+            // Handle create_pr tool, which pushes and runs gh, with bash security
+            let mut manager = self.bash_security_manager.write().await;
+            crate::tools::create_pr::create_pr(call, &mut manager, self.yolo_mode).await
         } else if call.name == "Write" {
+            self.record_for_session_diff(call);
             // Handle Write tool with security
             let file_security_manager = self.file_security_manager.clone();
             let call_clone = call.clone();
 
+            let ok_before = self.syntax_ok_before_edit(call).await;
             let mut manager = file_security_manager.write().await;
             let result = write_file(&call_clone, &mut *manager, self.yolo_mode).await;
             drop(manager); // Explicitly drop the lock guard
-            result
+            let mut result = result?;
+            self.format_edited_file(call, &mut result).await;
+            self.append_syntax_error(call, &mut result, ok_before).await;
+            self.append_diagnostics(call, &mut result).await;
+            Ok(result)
         } else if call.name == "Edit" {
+            self.record_for_session_diff(call);
             // Handle Edit tool with security
             let file_security_manager = self.file_security_manager.clone();
             let call_clone = call.clone();
 
+            let ok_before = self.syntax_ok_before_edit(call).await;
             let mut manager = file_security_manager.write().await;
             let result = edit_file(&call_clone, &mut *manager, self.yolo_mode).await;
             drop(manager); // Explicitly drop the lock guard
+            let mut result = result?;
+            self.format_edited_file(call, &mut result).await;
+            self.append_syntax_error(call, &mut result, ok_before).await;
+            self.append_diagnostics(call, &mut result).await;
+            Ok(result)
+        } else if call.name == "replace_in_files" {
+            // Record only the files that will change, since the glob may match many
+            if let Ok(planned) = crate::tools::replace_in_files::plan_replacements(call).await {
+                let mut session_diff = self.session_diff.lock().expect("session diff lock");
+                for file in &planned {
+                    session_diff.record(&file.path);
+                }
+            }
+            let file_security_manager = self.file_security_manager.clone();
+            let mut manager = file_security_manager.write().await;
+            let result = crate::tools::replace_in_files::replace_in_files(
+                call,
+                &mut manager,
+                self.yolo_mode,
+            )
+            .await;
+            drop(manager); // Explicitly drop the lock guard
             result
         } else if call.name == "delete_file" {
+            self.record_for_session_diff(call);
             // Handle delete_file tool with security
             let file_security_manager = self.file_security_manager.clone();
             let call_clone = call.clone();
@@ -1828,10 +3073,31 @@ impl Agent {
     }
 }
 
+/// Resolves once `flag` is set, for racing against work that should stop on cancel
 fn is_todo_tool(tool_name: &str) -> bool {
     matches!(tool_name, "create_todo" | "complete_todo" | "list_todos")
 }
 
+/// The start of a tool error, so a huge one doesn't swamp the JSON log
+fn truncate_for_log(text: &str) -> String {
+    const MAX_CHARS: usize = 500;
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// The API key for `provider` from its environment variable, or from the
+/// keyring if `flexorama init` stored one there
+fn provider_api_key(provider: Provider) -> String {
+    let api_key = crate::config::provider_default_api_key(provider);
+    if !api_key.is_empty() {
+        return api_key;
+    }
+    let account = crate::init_wizard::keyring_account(provider);
+    crate::secrets::resolve_secret(&format!("keyring:{}", account)).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2013,6 +3279,58 @@ mod tests {
         assert_eq!(title.as_deref(), Some("Actual Title"));
     }
 
+    #[tokio::test]
+    async fn denied_tools_are_refused() {
+        let config = Config {
+            denied_tools: vec!["list_directory".to_string()],
+            ..Config::default()
+        };
+        let agent = Agent::new(config, "test-model".to_string(), false, false);
+
+        let result = agent
+            .execute_tool_internal(&ToolCall {
+                id: "call-1".to_string(),
+                name: "list_directory".to_string(),
+                arguments: json!({ "path": "." }),
+            })
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.content.contains("disabled by configuration"));
+    }
+
+    #[tokio::test]
+    async fn config_reload_keeps_session_overrides() {
+        let previous = Config::default();
+        let mut agent = Agent::new(previous.clone(), "picked-by-user".to_string(), false, false);
+        agent
+            .get_bash_security_manager()
+            .write()
+            .await
+            .add_to_allowlist("make".to_string());
+
+        let mut config = Config {
+            default_model: "new-default".to_string(),
+            temperature: 0.1,
+            denied_tools: vec!["bash".to_string()],
+            ..previous.clone()
+        };
+        config
+            .bash_security
+            .denied_commands
+            .insert("git push*".to_string());
+        agent.apply_config_reload(&previous, &config).await.unwrap();
+
+        // The model was chosen on the command line, so it stays
+        assert_eq!(agent.model(), "picked-by-user");
+        assert_eq!(agent.temperature(), 0.1);
+        assert!(agent.denied_tools.contains("bash"));
+        let manager = agent.get_bash_security_manager();
+        let manager = manager.read().await;
+        assert!(manager.get_security().allowed_commands.contains("make"));
+        assert!(manager.get_security().denied_commands.contains("git push*"));
+    }
+
     #[test]
     fn extract_context_files_from_message() {
         let config = Config::default();
@@ -2150,6 +3468,149 @@ mod tests {
         assert_eq!(agent.conversation_manager.model, "new-model");
     }
 
+    #[tokio::test]
+    async fn set_provider_switches_client_and_restores_credentials() {
+        let config = Config {
+            base_url: "http://127.0.0.1:9/v1".to_string(),
+            api_key: "startup-key".to_string(),
+            ..Config::default()
+        };
+        let original = config.provider;
+        let mut agent = Agent::new(config, "old-model".to_string(), false, false);
+
+        agent
+            .set_provider(Provider::OLLAMA, "llama3".to_string())
+            .await
+            .unwrap();
+        assert_eq!(agent.provider(), Provider::OLLAMA);
+        assert_eq!(agent.llm_client().provider(), Provider::OLLAMA);
+        assert_eq!(agent.model(), "llama3");
+
+        agent
+            .set_provider(original, "new-model".to_string())
+            .await
+            .unwrap();
+        assert_eq!(agent.provider(), original);
+        assert_eq!(agent.base_url, "http://127.0.0.1:9/v1");
+        assert_eq!(agent.conversation_manager.model, "new-model");
+    }
+
+    #[tokio::test]
+    async fn retries_once_with_less_context_after_an_overflow() {
+        use crate::llm::tests::{configure_no_proxy, spawn_server};
+        use axum::{http::StatusCode, response::IntoResponse, routing::post, Router};
+
+        configure_no_proxy();
+        // Rejects any request still carrying the big tool result
+        let app = Router::new().route(
+            "/chat/completions",
+            post(|body: String| async move {
+                if body.contains(&"x".repeat(1000)) {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        r#"{"error":{"code":"context_length_exceeded"}}"#,
+                    )
+                        .into_response()
+                } else {
+                    axum::Json(json!({
+                        "choices": [{"message": {"role": "assistant", "content": "ok"}}]
+                    }))
+                    .into_response()
+                }
+            }),
+        );
+        let base_url = spawn_server(app).await;
+
+        let config = Config {
+            provider: Provider::OPENAI,
+            api_key: "test-key".to_string(),
+            base_url,
+            ..Config::default()
+        };
+        let mut agent = Agent::new(config, "gpt-5".to_string(), true, false);
+        agent.conversation_manager.conversation.extend([
+            Message {
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::tool_use(
+                    "call-1".to_string(),
+                    "Read".to_string(),
+                    json!({}),
+                )],
+            },
+            Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::tool_result(
+                    "call-1".to_string(),
+                    "x".repeat(2000),
+                    None,
+                )],
+            },
+        ]);
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let response = agent
+            .process_message_with_stream(
+                "Summarize it",
+                None,
+                Some(Arc::new(move |event| seen.lock().unwrap().push(event))),
+                Arc::new(AtomicBool::new(false)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response, "ok");
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&StreamToolEvent::ContextCompacted {
+                messages_before: 3,
+                messages_after: 3,
+            }));
+    }
+
+    #[tokio::test]
+    async fn a_conversation_another_process_has_opens_read_only() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("project.db");
+        let db = Arc::new(DatabaseManager::new(db_path.clone()).await.unwrap());
+        let mut first = Agent::new(Config::default(), "model".to_string(), false, false)
+            .with_database_manager(db.clone());
+        let conversation_id = first.start_new_conversation().await.unwrap();
+        first.resume_conversation(&conversation_id).await.unwrap();
+        drop(first);
+
+        // The lock file opened apart from this process's table of held
+        // locks stands in for another process
+        let path = crate::conversation_lock::lock_path(&db_path, &conversation_id);
+        let other = std::fs::File::create(&path).unwrap();
+        other.try_lock().unwrap();
+
+        let mut second = Agent::new(Config::default(), "model".to_string(), false, false)
+            .with_database_manager(db);
+        let error = second
+            .resume_conversation(&conversation_id)
+            .await
+            .unwrap_err();
+        assert!(error
+            .downcast_ref::<crate::conversation_lock::ConversationInUse>()
+            .is_some());
+
+        second
+            .open_conversation_read_only(&conversation_id)
+            .await
+            .unwrap();
+        assert!(second.is_read_only());
+        let error = second
+            .process_message("hello", Arc::new(AtomicBool::new(false)))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("open read-only"));
+
+        drop(other);
+        second.resume_conversation(&conversation_id).await.unwrap();
+        assert!(!second.is_read_only());
+    }
+
     #[test]
     fn conversation_len_returns_message_count() {
         let config = Config::default();
@@ -2525,6 +3986,8 @@ mod tests {
             system_prompt: Some("Prompt".to_string()),
             current_conversation_id: Some("id-1".to_string()),
             model: "model-1".to_string(),
+            lock: None,
+            read_only: false,
         };
 
         let cloned = context.clone();
@@ -2536,18 +3999,52 @@ mod tests {
 
     #[test]
     fn stream_tool_event_serializes() {
-        let event = StreamToolEvent {
-            event: "tool_call".to_string(),
+        let event = StreamToolEvent::ToolCall {
             tool_use_id: "id-1".to_string(),
             name: "Read".to_string(),
-            input: Some(json!({"file_path": "test.txt"})),
-            content: None,
-            is_error: None,
+            input: json!({"file_path": "test.txt"}),
         };
 
-        let serialized = serde_json::to_string(&event).unwrap();
-        assert!(serialized.contains("tool_call"));
-        assert!(serialized.contains("Read"));
+        let serialized = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "type": "tool_call",
+                "tool_use_id": "id-1",
+                "name": "Read",
+                "input": {"file_path": "test.txt"},
+            })
+        );
+        let thinking = serde_json::to_value(StreamToolEvent::ThinkingDelta {
+            text: "Hmm".to_string(),
+        })
+        .unwrap();
+        assert_eq!(thinking, json!({"type": "thinking_delta", "text": "Hmm"}));
+    }
+
+    #[test]
+    fn subagent_changes_are_reported_once() {
+        let config = Config::default();
+        let mut agent = Agent::new(config, "test-model".to_string(), false, false);
+        assert!(agent.subagent_events().is_empty());
+
+        agent.conversation_manager.subagent = Some("reviewer".to_string());
+        assert_eq!(
+            agent.subagent_events(),
+            vec![StreamToolEvent::SubagentStart {
+                name: "reviewer".to_string(),
+                model: "test-model".to_string(),
+            }]
+        );
+        assert!(agent.subagent_events().is_empty());
+
+        agent.conversation_manager.subagent = None;
+        assert_eq!(
+            agent.subagent_events(),
+            vec![StreamToolEvent::SubagentEnd {
+                name: "reviewer".to_string(),
+            }]
+        );
     }
 
     #[tokio::test]
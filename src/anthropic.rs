@@ -7,6 +7,7 @@ use serde_json::Value;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use crate::llm::{DeltaCallback, ReplyDelta};
 use crate::tools::Tool;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +149,13 @@ pub struct StreamEvent {
     pub delta: Option<StreamDelta>,
     pub content_block: Option<ContentBlock>,
     pub usage: Option<Usage>,
+    /// The message being started, in `message_start` events
+    pub message: Option<StreamMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamMessage {
+    pub usage: Option<Usage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -155,6 +163,7 @@ pub struct StreamDelta {
     #[serde(rename = "type")]
     pub delta_type: Option<String>,
     pub text: Option<String>,
+    pub thinking: Option<String>,
     pub partial_json: Option<String>,
     pub id: Option<String>,
     pub name: Option<String>,
@@ -162,10 +171,26 @@ pub struct StreamDelta {
 
 #[derive(Debug, Deserialize)]
 pub struct Usage {
+    /// Missing from the `message_delta` events of a stream
+    #[serde(default)]
     pub input_tokens: u32,
+    #[serde(default)]
     pub output_tokens: u32,
 }
 
+/// Fold the usage in a stream event into the usage so far. `message_start`
+/// carries the input tokens and `message_delta` the running output total.
+fn merge_stream_usage(usage: &mut Option<Usage>, event: &Usage) {
+    let input_tokens = match usage {
+        Some(usage) if event.input_tokens == 0 => usage.input_tokens,
+        _ => event.input_tokens,
+    };
+    *usage = Some(Usage {
+        input_tokens,
+        output_tokens: event.output_tokens,
+    });
+}
+
 pub struct AnthropicClient {
     client: Client,
     api_key: String,
@@ -175,7 +200,7 @@ pub struct AnthropicClient {
 impl AnthropicClient {
     pub fn new(api_key: String, base_url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http_client::client(),
             api_key,
             base_url,
         }
@@ -257,6 +282,7 @@ impl AnthropicClient {
         temperature: f32,
         system_prompt: Option<&String>,
         on_content: Arc<dyn Fn(String) + Send + Sync + 'static>,
+        on_delta: Option<DeltaCallback>,
         cancellation_flag: Arc<AtomicBool>,
     ) -> Result<AnthropicResponse> {
         // Try the standard endpoint first, then fall back to alternatives if needed
@@ -273,6 +299,7 @@ impl AnthropicClient {
                     temperature,
                     system_prompt,
                     on_content.clone(),
+                    on_delta.clone(),
                     cancellation_flag.clone(),
                 )
                 .await
@@ -299,6 +326,7 @@ impl AnthropicClient {
                 temperature,
                 system_prompt,
                 on_content.clone(),
+                on_delta.clone(),
                 cancellation_flag.clone(),
             )
             .await;
@@ -430,6 +458,7 @@ impl AnthropicClient {
         temperature: f32,
         system_prompt: Option<&String>,
         on_content: Arc<dyn Fn(String) + Send + Sync + 'static>,
+        on_delta: Option<DeltaCallback>,
         cancellation_flag: Arc<AtomicBool>,
     ) -> Result<AnthropicResponse> {
         let tool_definitions = if tools.is_empty() {
@@ -623,6 +652,19 @@ impl AnthropicClient {
                                                     if let Some(ref mut tool_block) =
                                                         current_tool_block
                                                     {
+                                                        if let Some(on_delta) = &on_delta {
+                                                            on_delta(ReplyDelta::ToolInput {
+                                                                tool_use_id: tool_block
+                                                                    .id
+                                                                    .clone()
+                                                                    .unwrap_or_default(),
+                                                                name: tool_block
+                                                                    .name
+                                                                    .clone()
+                                                                    .unwrap_or_default(),
+                                                                delta: partial_json.clone(),
+                                                            });
+                                                        }
                                                         if let Some(Value::String(mut existing)) =
                                                             tool_block.input.take()
                                                         {
@@ -644,6 +686,10 @@ impl AnthropicClient {
                                                                 Some(Value::String(partial_json));
                                                         }
                                                     }
+                                                } else if let Some(thinking) = delta.thinking {
+                                                    if let Some(on_delta) = &on_delta {
+                                                        on_delta(ReplyDelta::Thinking(thinking));
+                                                    }
                                                 } else {
                                                     debug!("content_block_delta has no text or partial_json, delta: {:?}", delta);
                                                 }
@@ -715,8 +761,13 @@ impl AnthropicClient {
                                         }
                                     }
 
-                                    if let Some(usage) = event.usage {
-                                        usage_info = Some(usage);
+                                    if let Some(usage) = event
+                                        .message
+                                        .as_ref()
+                                        .and_then(|message| message.usage.as_ref())
+                                        .or(event.usage.as_ref())
+                                    {
+                                        merge_stream_usage(&mut usage_info, usage);
                                     }
                                     }
                                     Err(e) => {
@@ -779,3 +830,30 @@ impl AnthropicClient {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_usage_combines_start_and_delta_events() {
+        let mut usage = None;
+        for data in [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":1200,"output_tokens":1}}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":87}}"#,
+        ] {
+            let event: StreamEvent = serde_json::from_str(data).unwrap();
+            if let Some(event_usage) = event
+                .message
+                .as_ref()
+                .and_then(|message| message.usage.as_ref())
+                .or(event.usage.as_ref())
+            {
+                merge_stream_usage(&mut usage, event_usage);
+            }
+        }
+        let usage = usage.unwrap();
+        assert_eq!(usage.input_tokens, 1200);
+        assert_eq!(usage.output_tokens, 87);
+    }
+}
@@ -84,6 +84,34 @@ mod tests {
         assert_eq!(completed, "/help");
     }
 
+    #[test]
+    fn test_custom_command_completion_by_namespace() {
+        let names = vec![
+            "deploy".to_string(),
+            "git:commit".to_string(),
+            "git:push".to_string(),
+            "git:remote:add".to_string(),
+        ];
+
+        assert_eq!(
+            complete_custom_command("/gi", &names),
+            Some("/git:".to_string())
+        );
+        assert_eq!(
+            complete_custom_command("/git:", &names),
+            Some("/git:commit".to_string())
+        );
+        assert_eq!(
+            complete_custom_command("/git:r", &names),
+            Some("/git:remote:".to_string())
+        );
+        assert_eq!(
+            complete_custom_command("/dep", &names),
+            Some("/deploy".to_string())
+        );
+        assert_eq!(complete_custom_command("/deploy now", &names), None);
+    }
+
     #[test]
     fn test_no_command_completion_in_middle() {
         // Test that command completion doesn't trigger in middle of text
@@ -128,11 +156,22 @@ pub fn get_completion(input: &str, cursor_pos: usize) -> Option<String> {
         "/context",
         "/search",
         "/resume",
+        "/rename",
+        "/env",
+        "/checkpoints",
+        "/pr",
+        "/share",
+        "/learn",
+        "/history",
+        "/diff",
+        "/voice",
         "/clear",
         "/reset-stats",
         "/permissions",
         "/file-permissions",
         "/mcp",
+        "/config",
+        "/copy",
         "/exit",
         "/quit",
     ];
@@ -150,6 +189,12 @@ pub fn get_completion(input: &str, cursor_pos: usize) -> Option<String> {
             }
         }
 
+        if let Some(completion) =
+            complete_custom_command(input, &crate::custom_commands::custom_command_names())
+        {
+            return Some(completion);
+        }
+
         // MCP command completions
         if input.starts_with("/mcp ") {
             let mcp_part = &input[5..];
@@ -163,6 +208,8 @@ pub fn get_completion(input: &str, cursor_pos: usize) -> Option<String> {
                 "tools",
                 "connect-all",
                 "disconnect-all",
+                "logout",
+                "import",
                 "test",
                 "help",
             ];
@@ -224,6 +271,26 @@ pub fn get_completion(input: &str, cursor_pos: usize) -> Option<String> {
     None
 }
 
+/// Complete a custom command one namespace at a time: `/gi` becomes `/git:`,
+/// then `/git:` becomes `/git:commit`
+fn complete_custom_command(input: &str, names: &[String]) -> Option<String> {
+    let typed = input.strip_prefix('/')?;
+    if typed.contains(char::is_whitespace) {
+        return None;
+    }
+    let mut candidates: Vec<String> = names
+        .iter()
+        .filter(|name| name.starts_with(typed))
+        .map(|name| match name[typed.len()..].find(':') {
+            Some(end) => format!("/{}", &name[..typed.len() + end + 1]),
+            None => format!("/{}", name),
+        })
+        .filter(|candidate| candidate != input)
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next()
+}
+
 /// Check for file completion with @ syntax anywhere in the input
 fn check_file_completion(input: &str, cursor_pos: usize) -> Option<String> {
     // Find the last @ symbol before the cursor position
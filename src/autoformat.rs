@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::{FormatConfig, FormatterConfig};
+
+/// Longest a formatter may take on one file
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most changed lines quoted back to the model; beyond this it's asked to
+/// read the file again instead
+const MAX_QUOTED_LINES: usize = 200;
+
+/// What a formatter changed in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reformatted {
+    /// First changed line of the formatted file, 1-based
+    pub start_line: usize,
+    /// The changed lines as they now read
+    pub lines: Vec<String>,
+}
+
+/// The formatter configured for `path`'s extension, if any
+pub fn formatter_for<'a>(config: &'a FormatConfig, path: &Path) -> Option<&'a FormatterConfig> {
+    let extension = path.extension()?.to_str()?;
+    config.formatters.iter().find(|formatter| {
+        formatter
+            .extensions
+            .iter()
+            .any(|ext| ext.eq_ignore_ascii_case(extension))
+    })
+}
+
+/// Run `formatter` on the file at `path`. Returns what it changed, or None
+/// when the file was already formatted.
+pub async fn format_file(formatter: &FormatterConfig, path: &Path) -> Result<Option<Reformatted>> {
+    let before = tokio::fs::read_to_string(path).await?;
+    let output = tokio::time::timeout(
+        FORMAT_TIMEOUT,
+        tokio::process::Command::new(&formatter.command)
+            .args(&formatter.args)
+            .arg(path)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| anyhow!("timed out after {}s", FORMAT_TIMEOUT.as_secs()))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "exited with {}: {}",
+            output.status,
+            stderr.lines().next().unwrap_or_default()
+        ));
+    }
+    let after = tokio::fs::read_to_string(path).await?;
+    Ok(changed_lines(&before, &after))
+}
+
+/// The lines of `after` that differ from `before`, between the lines the
+/// two have in common at the start and end
+fn changed_lines(before: &str, after: &str) -> Option<Reformatted> {
+    if before == after {
+        return None;
+    }
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    Some(Reformatted {
+        start_line: prefix + 1,
+        lines: new[prefix..new.len() - suffix]
+            .iter()
+            .map(|line| line.to_string())
+            .collect(),
+    })
+}
+
+/// The note added to a Write or Edit result, so the model's next edit
+/// matches the file as it is on disk
+pub fn format_note(path: &str, formatter: &str, change: &Reformatted) -> String {
+    if change.lines.is_empty() {
+        return format!(
+            "{} reformatted {}, removing lines from line {}. Read the file again before editing it.",
+            formatter, path, change.start_line
+        );
+    }
+    if change.lines.len() > MAX_QUOTED_LINES {
+        return format!(
+            "{} reformatted {} from line {}. Read the file again before editing it.",
+            formatter, path, change.start_line
+        );
+    }
+    let end_line = change.start_line + change.lines.len() - 1;
+    format!(
+        "{} reformatted {}. Lines {}-{} now read:\n```\n{}\n```",
+        formatter,
+        path,
+        change.start_line,
+        end_line,
+        change.lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_formatter_by_extension() {
+        let config = FormatConfig::default();
+        let formatter = formatter_for(&config, Path::new("src/main.rs")).unwrap();
+        assert_eq!(formatter.command, "rustfmt");
+        let formatter = formatter_for(&config, Path::new("web/App.TSX")).unwrap();
+        assert_eq!(formatter.command, "prettier");
+        assert!(formatter_for(&config, Path::new("Makefile")).is_none());
+    }
+
+    #[test]
+    fn finds_the_changed_lines() {
+        assert_eq!(changed_lines("a\nb\n", "a\nb\n"), None);
+        let change = changed_lines("fn f(){\nx\n}\n", "fn f() {\nx\n}\n").unwrap();
+        assert_eq!(change.start_line, 1);
+        assert_eq!(change.lines, vec!["fn f() {"]);
+
+        let change = changed_lines("a\nb\nc\nd\n", "a\nB\nb2\nd\n").unwrap();
+        assert_eq!(change.start_line, 2);
+        assert_eq!(change.lines, vec!["B", "b2"]);
+        let note = format_note("x.txt", "fmt", &change);
+        assert!(note.starts_with("fmt reformatted x.txt. Lines 2-3 now read:"));
+    }
+
+    #[tokio::test]
+    async fn runs_the_formatter_on_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        tokio::fs::write(&path, "keep\nchange me\n").await.unwrap();
+        let formatter = FormatterConfig {
+            command: "sed".to_string(),
+            args: vec!["-i".to_string(), "s/change me/changed/".to_string()],
+            extensions: vec!["txt".to_string()],
+        };
+        let change = format_file(&formatter, &path).await.unwrap().unwrap();
+        assert_eq!(change.start_line, 2);
+        assert_eq!(change.lines, vec!["changed"]);
+        assert!(format_file(&formatter, &path).await.unwrap().is_none());
+
+        let missing = FormatterConfig {
+            command: "flexorama-no-such-formatter".to_string(),
+            ..formatter
+        };
+        assert!(format_file(&missing, &path).await.is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Context, Result};
+use futures_util::stream::{self, StreamExt};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::agent::Agent;
+use crate::headless::{RunResult, RunStatus, ToolCallCollector, UsageSummary};
+
+/// One prompt from a batch file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum BatchEntry {
+    Prompt(String),
+    Item {
+        prompt: String,
+        /// Output file name, relative to the output directory
+        #[serde(default)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchItem {
+    pub prompt: String,
+    pub output: PathBuf,
+}
+
+/// Parse a batch file: a YAML list for .yaml/.yml files, otherwise one prompt
+/// per line with blank lines and `#` comments skipped
+pub fn parse_batch_file(path: &Path, content: &str) -> Result<Vec<BatchItem>> {
+    let is_yaml = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml" | "yml")
+    );
+    let entries: Vec<BatchEntry> = if is_yaml {
+        serde_yaml::from_str(content)
+            .with_context(|| format!("{} must be a YAML list of prompts", path.display()))?
+    } else {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| BatchEntry::Prompt(line.to_string()))
+            .collect()
+    };
+
+    let items: Vec<BatchItem> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let (prompt, output) = match entry {
+                BatchEntry::Prompt(prompt) => (prompt, None),
+                BatchEntry::Item { prompt, output } => (prompt, output),
+            };
+            let output = output
+                .map(PathBuf::from)
+                .unwrap_or_else(|| default_output_name(index, &prompt));
+            BatchItem { prompt, output }
+        })
+        .collect();
+    if items.is_empty() {
+        return Err(anyhow!("{} contains no prompts", path.display()));
+    }
+    Ok(items)
+}
+
+/// `004-review-the-parser.md` for the fourth prompt
+fn default_output_name(index: usize, prompt: &str) -> PathBuf {
+    let mut slug = String::new();
+    for c in prompt.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') && !slug.is_empty() {
+            slug.push('-');
+        }
+        if slug.len() >= 40 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        PathBuf::from(format!("{:03}.md", index + 1))
+    } else {
+        PathBuf::from(format!("{:03}-{}.md", index + 1, slug))
+    }
+}
+
+/// The outcome of one batch item, as written to `summary.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItemReport {
+    pub prompt: String,
+    pub output: PathBuf,
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub conversation_id: Option<String>,
+    pub tool_calls: usize,
+    pub usage: UsageSummary,
+    pub cost_usd: Option<f64>,
+    pub duration_ms: u128,
+}
+
+/// Run every item in its own conversation, at most `concurrency` at a time,
+/// writing each answer to the output directory. Reports are in item order;
+/// `on_done` is called as each item finishes.
+pub async fn run_batch<F, Fut>(
+    items: &[BatchItem],
+    output_dir: &Path,
+    concurrency: usize,
+    create_agent: F,
+    on_done: impl Fn(&BatchItemReport),
+) -> Result<Vec<BatchItemReport>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Agent>>,
+{
+    tokio::fs::create_dir_all(output_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+    let on_done = &on_done;
+    let mut reports: Vec<(usize, BatchItemReport)> = stream::iter(items.iter().enumerate())
+        .map(|(index, item)| {
+            let agent = create_agent();
+            async move {
+                let report = match agent.await {
+                    Ok(mut agent) => run_item(&mut agent, item, output_dir).await,
+                    Err(e) => failed_report(item, format!("Failed to start agent: {}", e)),
+                };
+                on_done(&report);
+                (index, report)
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    reports.sort_by_key(|(index, _)| *index);
+    let reports: Vec<BatchItemReport> = reports.into_iter().map(|(_, report)| report).collect();
+
+    let summary = serde_json::to_string_pretty(&reports)?;
+    tokio::fs::write(output_dir.join("summary.json"), summary + "\n").await?;
+    Ok(reports)
+}
+
+async fn run_item(agent: &mut Agent, item: &BatchItem, output_dir: &Path) -> BatchItemReport {
+    let started = Instant::now();
+    let tool_calls = ToolCallCollector::new();
+    let response = agent
+        .process_message_with_stream(
+            &item.prompt,
+            None,
+            Some(tool_calls.callback()),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await;
+    let result = RunResult::new(agent, &response, tool_calls.take(), started.elapsed());
+
+    let mut status = result.status;
+    let mut error = result.error.clone();
+    if status == RunStatus::Success {
+        let path = output_dir.join(&item.output);
+        if let Err(e) = write_output(&path, &result.result).await {
+            warn!("{:#}", e);
+            status = RunStatus::Error;
+            error = Some(format!("{:#}", e));
+        }
+    }
+
+    BatchItemReport {
+        prompt: item.prompt.clone(),
+        output: item.output.clone(),
+        status,
+        error,
+        conversation_id: result.conversation_id,
+        tool_calls: result.tool_calls.len(),
+        usage: result.usage,
+        cost_usd: result.cost_usd,
+        duration_ms: result.duration_ms,
+    }
+}
+
+async fn write_output(path: &Path, text: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, format!("{}\n", text.trim_end()))
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn failed_report(item: &BatchItem, error: String) -> BatchItemReport {
+    BatchItemReport {
+        prompt: item.prompt.clone(),
+        output: item.output.clone(),
+        status: RunStatus::Error,
+        error: Some(error),
+        conversation_id: None,
+        tool_calls: 0,
+        usage: UsageSummary {
+            requests: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            total_tokens: 0,
+        },
+        cost_usd: None,
+        duration_ms: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_based_batch_files() {
+        let content = "# Reviews\nReview src/parser.rs for bugs\n\n  Document the CLI flags  \n";
+        let items = parse_batch_file(Path::new("prompts.txt"), content).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                BatchItem {
+                    prompt: "Review src/parser.rs for bugs".to_string(),
+                    output: PathBuf::from("001-review-src-parser-rs-for-bugs.md"),
+                },
+                BatchItem {
+                    prompt: "Document the CLI flags".to_string(),
+                    output: PathBuf::from("002-document-the-cli-flags.md"),
+                },
+            ]
+        );
+
+        assert!(parse_batch_file(Path::new("empty.txt"), "# nothing\n").is_err());
+    }
+
+    #[test]
+    fn parses_yaml_batch_files() {
+        let content = "- Summarize README.md\n- prompt: |\n    Write docs for\n    src/batch.rs\n  output: docs/batch.md\n";
+        let items = parse_batch_file(Path::new("prompts.yaml"), content).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].output, PathBuf::from("001-summarize-readme-md.md"));
+        assert_eq!(items[1].prompt, "Write docs for\nsrc/batch.rs\n");
+        assert_eq!(items[1].output, PathBuf::from("docs/batch.md"));
+
+        assert!(parse_batch_file(Path::new("bad.yml"), "prompt: not a list").is_err());
+    }
+}
@@ -0,0 +1,534 @@
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+
+use crate::project_config;
+
+/// Version of the bundle file format
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// What a bundle holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleKind {
+    Skill,
+    Agent,
+}
+
+impl BundleKind {
+    /// The directory under `.flexorama` these are installed in
+    fn dir_name(self) -> &'static str {
+        match self {
+            BundleKind::Skill => "skills",
+            BundleKind::Agent => "agents",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BundleKind::Skill => "skill",
+            BundleKind::Agent => "agent",
+        }
+    }
+}
+
+/// A skill with its scripts and references, or a subagent, in one JSON file
+/// that can be shared between machines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    pub kind: BundleKind,
+    pub name: String,
+    pub exported_at: DateTime<Utc>,
+    /// Files by path: relative to the skill's directory for a skill, or
+    /// the single `<name>.md` file for an agent
+    pub files: BTreeMap<String, BundleFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleFile {
+    pub content: String,
+    /// The content is base64, for files that aren't UTF-8 text
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub executable: bool,
+}
+
+impl BundleFile {
+    pub(crate) fn from_bytes(bytes: Vec<u8>, executable: bool) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(content) => Self {
+                content,
+                base64: false,
+                executable,
+            },
+            Err(e) => Self {
+                content: STANDARD.encode(e.into_bytes()),
+                base64: true,
+                executable,
+            },
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> Result<Vec<u8>> {
+        if self.base64 {
+            STANDARD
+                .decode(&self.content)
+                .map_err(|e| anyhow!("Invalid base64 in bundle: {}", e))
+        } else {
+            Ok(self.content.as_bytes().to_vec())
+        }
+    }
+}
+
+impl Bundle {
+    fn new(kind: BundleKind, name: &str, files: BTreeMap<String, BundleFile>) -> Self {
+        Self {
+            format: BUNDLE_FORMAT,
+            kind,
+            name: name.to_string(),
+            exported_at: Utc::now(),
+            files,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        let bundle: Bundle = serde_json::from_str(content).context("Invalid bundle file")?;
+        if bundle.format > BUNDLE_FORMAT {
+            return Err(anyhow!(
+                "Bundle format {} is newer than this version of Flexorama supports",
+                bundle.format
+            ));
+        }
+        Ok(bundle)
+    }
+
+    /// The default file name for this bundle, like `review.skill.json`
+    pub fn file_name(&self) -> String {
+        format!("{}.{}.json", self.name, self.kind.label())
+    }
+}
+
+/// `~/.flexorama/skills` or `~/.flexorama/agents`
+pub fn global_dir(kind: BundleKind) -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow!("Home directory not found"))?
+        .join(".flexorama")
+        .join(kind.dir_name()))
+}
+
+/// The project's `.flexorama/skills` or `.flexorama/agents`, created in the
+/// current directory if the project doesn't have one yet
+pub fn project_dir(kind: BundleKind) -> Result<PathBuf> {
+    match project_config::project_dir(kind.dir_name()) {
+        Some(dir) => Ok(dir),
+        None => Ok(std::env::current_dir()?
+            .join(".flexorama")
+            .join(kind.dir_name())),
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(anyhow!("Invalid name '{}'", name));
+    }
+    Ok(())
+}
+
+/// Bundle paths come from other people, so they must stay inside the
+/// directory they're installed in
+pub(crate) fn validate_path(path: &str) -> Result<()> {
+    let valid = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("Bundle contains an unsafe path '{}'", path))
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+pub(crate) async fn read_file(path: &Path) -> Result<BundleFile> {
+    let metadata = fs::metadata(path).await?;
+    let bytes = fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(BundleFile::from_bytes(bytes, is_executable(&metadata)))
+}
+
+/// Every file under `dir` by relative path, skipping hidden files like `.git`
+pub(crate) async fn read_dir_files(dir: &Path) -> Result<BTreeMap<String, BundleFile>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let mut entries = fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.is_file() {
+                let relative = path.strip_prefix(dir)?.to_string_lossy().replace('\\', "/");
+                files.insert(relative, read_file(&path).await?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Where skill or agent `name` is installed, checking the project first
+pub fn find_installed(kind: BundleKind, name: &str) -> Result<Option<PathBuf>> {
+    validate_name(name)?;
+    let mut dirs = Vec::new();
+    if let Some(dir) = project_config::project_dir(kind.dir_name()) {
+        dirs.push(dir);
+    }
+    dirs.push(global_dir(kind)?);
+
+    for dir in dirs {
+        let candidates = match kind {
+            BundleKind::Skill => vec![dir.join(name), dir.join(format!("{}.md", name))],
+            BundleKind::Agent => vec![dir.join(format!("{}.md", name))],
+        };
+        if let Some(path) = candidates.into_iter().find(|path| path.exists()) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Bundle a skill from its directory, or from a legacy single-file skill
+pub async fn export_skill(path: &Path, name: &str) -> Result<Bundle> {
+    validate_name(name)?;
+    let files = if path.is_dir() {
+        if !path.join("SKILL.md").is_file() {
+            return Err(anyhow!("{} has no SKILL.md", path.display()));
+        }
+        read_dir_files(path).await?
+    } else {
+        BTreeMap::from([("SKILL.md".to_string(), read_file(path).await?)])
+    };
+    Ok(Bundle::new(BundleKind::Skill, name, files))
+}
+
+/// Bundle a subagent from its definition file
+pub async fn export_agent(path: &Path, name: &str) -> Result<Bundle> {
+    validate_name(name)?;
+    let files = BTreeMap::from([(format!("{}.md", name), read_file(path).await?)]);
+    Ok(Bundle::new(BundleKind::Agent, name, files))
+}
+
+/// Write `bundle` into `root` (a skills or agents directory) and return
+/// where it went. An existing skill or agent is only replaced with `force`.
+pub async fn install(bundle: &Bundle, root: &Path, force: bool) -> Result<PathBuf> {
+    validate_name(&bundle.name)?;
+    for path in bundle.files.keys() {
+        validate_path(path)?;
+    }
+
+    let (target, files): (PathBuf, Vec<(PathBuf, &BundleFile)>) = match bundle.kind {
+        BundleKind::Skill => {
+            if !bundle.files.contains_key("SKILL.md") {
+                return Err(anyhow!("Skill bundle '{}' has no SKILL.md", bundle.name));
+            }
+            let target = root.join(&bundle.name);
+            let files = bundle
+                .files
+                .iter()
+                .map(|(path, file)| (target.join(path), file))
+                .collect();
+            (target, files)
+        }
+        BundleKind::Agent => {
+            let mut definitions = bundle
+                .files
+                .iter()
+                .filter(|(path, _)| path.ends_with(".md"));
+            let (Some((_, file)), None) = (definitions.next(), definitions.next()) else {
+                return Err(anyhow!(
+                    "Agent bundle '{}' must contain exactly one .md file",
+                    bundle.name
+                ));
+            };
+            let target = root.join(format!("{}.md", bundle.name));
+            (target.clone(), vec![(target, file)])
+        }
+    };
+
+    let legacy_skill = root.join(format!("{}.md", bundle.name));
+    let exists = target.exists() || (bundle.kind == BundleKind::Skill && legacy_skill.is_file());
+    if exists && !force {
+        return Err(anyhow!(
+            "{} '{}' already exists (use --force to replace it)",
+            bundle.kind.label(),
+            bundle.name
+        ));
+    }
+    if bundle.kind == BundleKind::Skill {
+        if target.exists() {
+            fs::remove_dir_all(&target).await?;
+        }
+        if legacy_skill.is_file() {
+            fs::remove_file(&legacy_skill).await?;
+        }
+    }
+
+    for (path, file) in files {
+        write_file(&path, file).await?;
+    }
+    debug!(
+        "Installed {} '{}' to {}",
+        bundle.kind.label(),
+        bundle.name,
+        target.display()
+    );
+    Ok(target)
+}
+
+/// Write a bundled file to `path`, creating its directory
+pub(crate) async fn write_file(path: &Path, file: &BundleFile) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, file.bytes()?).await?;
+    #[cfg(unix)]
+    if file.executable {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+    }
+    Ok(())
+}
+
+/// The `name` field of a Markdown file's YAML frontmatter
+fn frontmatter_name(content: &str) -> Option<String> {
+    let content = content.trim_start_matches('\u{FEFF}').replace("\r\n", "\n");
+    let rest = content.strip_prefix("---\n")?;
+    let end = rest.find("\n---")?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&rest[..end]).ok()?;
+    value.get("name")?.as_str().map(str::to_string)
+}
+
+/// Build a bundle from a skill directory (one with a SKILL.md) or a
+/// directory holding a single agent definition, as found in a git checkout
+async fn bundle_from_dir(dir: &Path, kind: BundleKind) -> Result<Bundle> {
+    match kind {
+        BundleKind::Skill => {
+            let skill_file = dir.join("SKILL.md");
+            if !skill_file.is_file() {
+                return Err(anyhow!("{} has no SKILL.md", dir.display()));
+            }
+            let content = fs::read_to_string(&skill_file).await?;
+            let name = frontmatter_name(&content)
+                .ok_or_else(|| anyhow!("SKILL.md in {} has no name", dir.display()))?;
+            export_skill(dir, &name).await
+        }
+        BundleKind::Agent => {
+            let mut definitions = Vec::new();
+            let mut entries = fs::read_dir(dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("md") {
+                    continue;
+                }
+                let content = fs::read_to_string(&path).await?;
+                if let Some(name) = frontmatter_name(&content) {
+                    definitions.push((path, name));
+                }
+            }
+            match definitions.as_slice() {
+                [(path, name)] => export_agent(path, name).await,
+                [] => Err(anyhow!("{} has no agent definition", dir.display())),
+                _ => Err(anyhow!(
+                    "{} has more than one agent definition",
+                    dir.display()
+                )),
+            }
+        }
+    }
+}
+
+/// URLs that aren't a `.json` bundle are treated as git repositories
+fn is_git_source(source: &str) -> bool {
+    let is_url = source.starts_with("https://") || source.starts_with("http://");
+    source.starts_with("git@")
+        || source.starts_with("git://")
+        || source.starts_with("ssh://")
+        || source.ends_with(".git")
+        || (is_url && !source.ends_with(".json"))
+}
+
+/// Shallow-clone `url` into a new temporary directory. The caller removes
+/// it when done.
+pub async fn clone_repository(url: &str) -> Result<PathBuf> {
+    let checkout = std::env::temp_dir().join(format!("flexorama-import-{}", uuid::Uuid::new_v4()));
+    let output = tokio::process::Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", url])
+        .arg(&checkout)
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        let _ = fs::remove_dir_all(&checkout).await;
+        return Err(anyhow!(
+            "git clone {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(checkout)
+}
+
+async fn bundle_from_git(url: &str, kind: BundleKind) -> Result<Bundle> {
+    let checkout = clone_repository(url).await?;
+    let result = bundle_from_dir(&checkout, kind).await;
+    let _ = fs::remove_dir_all(&checkout).await;
+    result
+}
+
+/// Read a bundle from a bundle file, a skill or agent directory, a URL to
+/// a bundle file, or a git repository
+pub async fn read_source(source: &str, kind: BundleKind) -> Result<Bundle> {
+    let bundle = if is_git_source(source) {
+        bundle_from_git(source, kind).await?
+    } else if source.starts_with("https://") || source.starts_with("http://") {
+        let response = crate::http_client::client()
+            .get(source)
+            .send()
+            .await?
+            .error_for_status()?;
+        Bundle::from_json(&response.text().await?)?
+    } else {
+        let path = PathBuf::from(shellexpand::tilde(source).into_owned());
+        if path.is_dir() {
+            bundle_from_dir(&path, kind).await?
+        } else if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            Bundle::from_json(&fs::read_to_string(&path).await?)?
+        } else if path.is_file() {
+            let content = fs::read_to_string(&path).await?;
+            let name = frontmatter_name(&content)
+                .ok_or_else(|| anyhow!("{} has no name in its frontmatter", path.display()))?;
+            match kind {
+                BundleKind::Skill => export_skill(&path, &name).await?,
+                BundleKind::Agent => export_agent(&path, &name).await?,
+            }
+        } else {
+            return Err(anyhow!("{} not found", source));
+        }
+    };
+
+    if bundle.kind != kind {
+        return Err(anyhow!(
+            "{} is a {} bundle, not a {}",
+            source,
+            bundle.kind.label(),
+            kind.label()
+        ));
+    }
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn skill_bundles_round_trip() {
+        let source = TempDir::new().unwrap();
+        let skill = source.path().join("release");
+        std::fs::create_dir_all(skill.join("scripts")).unwrap();
+        std::fs::create_dir_all(skill.join(".git")).unwrap();
+        std::fs::write(skill.join("SKILL.md"), "---\nname: release\n---\nShip it").unwrap();
+        std::fs::write(skill.join("scripts/bump.sh"), "echo bump").unwrap();
+        std::fs::write(skill.join("logo.bin"), [0xff, 0x00, 0xfe]).unwrap();
+        std::fs::write(skill.join(".git/HEAD"), "ref").unwrap();
+
+        let bundle = export_skill(&skill, "release").await.unwrap();
+        assert_eq!(bundle.file_name(), "release.skill.json");
+        assert_eq!(
+            bundle.files.keys().collect::<Vec<_>>(),
+            vec!["SKILL.md", "logo.bin", "scripts/bump.sh"]
+        );
+        assert!(bundle.files["logo.bin"].base64);
+
+        let bundle = Bundle::from_json(&bundle.to_json().unwrap()).unwrap();
+        let dest = TempDir::new().unwrap();
+        let installed = install(&bundle, dest.path(), false).await.unwrap();
+        assert_eq!(installed, dest.path().join("release"));
+        assert_eq!(
+            std::fs::read(installed.join("logo.bin")).unwrap(),
+            vec![0xff, 0x00, 0xfe]
+        );
+        assert!(installed.join("scripts/bump.sh").is_file());
+
+        let err = install(&bundle, dest.path(), false).await.unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        install(&bundle, dest.path(), true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_paths_outside_the_install_directory() {
+        let mut bundle = Bundle::new(BundleKind::Skill, "evil", BTreeMap::new());
+        for path in ["SKILL.md", "../escape.sh"] {
+            bundle.files.insert(
+                path.to_string(),
+                BundleFile::from_bytes(b"x".to_vec(), false),
+            );
+        }
+        let dest = TempDir::new().unwrap();
+        let err = install(&bundle, dest.path(), false).await.unwrap_err();
+        assert!(err.to_string().contains("unsafe path"));
+        assert!(!dest.path().join("evil").exists());
+    }
+
+    #[tokio::test]
+    async fn reads_agents_from_directories() {
+        let repo = TempDir::new().unwrap();
+        std::fs::write(repo.path().join("README.md"), "# Reviewer").unwrap();
+        std::fs::write(
+            repo.path().join("reviewer.md"),
+            "---\nname: reviewer\n---\nReview code",
+        )
+        .unwrap();
+
+        let source = repo.path().to_string_lossy().to_string();
+        let bundle = read_source(&source, BundleKind::Agent).await.unwrap();
+        assert_eq!(bundle.name, "reviewer");
+        assert_eq!(bundle.files.keys().collect::<Vec<_>>(), vec!["reviewer.md"]);
+
+        let err = read_source(&source, BundleKind::Skill).await.unwrap_err();
+        assert!(err.to_string().contains("no SKILL.md"));
+    }
+
+    #[test]
+    fn detects_git_sources() {
+        assert!(is_git_source("https://github.com/acme/skills"));
+        assert!(is_git_source("git@github.com:acme/skills.git"));
+        assert!(!is_git_source("https://example.com/review.skill.json"));
+        assert!(!is_git_source("./review.skill.json"));
+    }
+}
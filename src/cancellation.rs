@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How often a cancellation flag is checked while waiting on something else
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Resolve once `flag` is set
+pub async fn cancelled(flag: &AtomicBool) {
+    while !flag.load(Ordering::SeqCst) {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Run `future` until it finishes or `flag` is set. On cancellation the
+/// future is dropped, which aborts its HTTP request or kills its process,
+/// and the error is "CANCELLED".
+pub async fn cancellable<T>(
+    flag: &AtomicBool,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    if flag.load(Ordering::SeqCst) {
+        return Err(anyhow!("CANCELLED"));
+    }
+    tokio::select! {
+        result = future => result,
+        _ = cancelled(flag) => Err(anyhow!("CANCELLED")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn stops_waiting_when_the_flag_is_set() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let setter = flag.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            setter.store(true, Ordering::SeqCst);
+        });
+
+        let started = std::time::Instant::now();
+        let result = cancellable(&flag, async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "CANCELLED");
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let finished = cancellable(&AtomicBool::new(false), async { Ok(7) }).await;
+        assert_eq!(finished.unwrap(), 7);
+    }
+}
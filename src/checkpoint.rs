@@ -0,0 +1,405 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, FixedOffset};
+use std::path::{Path, PathBuf};
+
+use crate::config::CheckpointConfig;
+
+/// Subject of the commits recording changes made between turns, which are
+/// the parents of checkpoints rather than checkpoints themselves
+const BASELINE_SUBJECT: &str = "Changes made outside the agent";
+
+/// Longest checkpoint subject, taken from the start of the prompt
+const SUBJECT_CHARS: usize = 72;
+
+/// Checkpoints listed by `/checkpoints`
+pub const LIST_LIMIT: usize = 20;
+
+/// A commit recording the files one agent turn changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub commit: String,
+    pub short: String,
+    pub created_at: DateTime<FixedOffset>,
+    /// The commit subject without the checkpoint prefix
+    pub summary: String,
+}
+
+/// Records agent turns as commits on a dedicated branch. The commits are
+/// built with git's plumbing and a private index, so the checked-out branch,
+/// the index and the working tree are never touched.
+#[derive(Debug, Clone)]
+pub struct Checkpointer {
+    config: CheckpointConfig,
+    repo_root: PathBuf,
+    index_file: PathBuf,
+}
+
+impl Checkpointer {
+    /// A checkpointer for the git repository containing `dir`, or None when
+    /// `dir` isn't in one
+    pub async fn open(config: CheckpointConfig, dir: &Path) -> Result<Option<Self>> {
+        let Ok(root) = git(dir, &["rev-parse", "--show-toplevel"], None).await else {
+            return Ok(None);
+        };
+        let repo_root = PathBuf::from(root);
+        let index_file = PathBuf::from(
+            git(
+                &repo_root,
+                &[
+                    "rev-parse",
+                    "--path-format=absolute",
+                    "--git-path",
+                    "flexorama-checkpoint-index",
+                ],
+                None,
+            )
+            .await?,
+        );
+        Ok(Some(Self {
+            config,
+            repo_root,
+            index_file,
+        }))
+    }
+
+    pub fn branch(&self) -> &str {
+        &self.config.branch
+    }
+
+    fn branch_ref(&self) -> String {
+        format!("refs/heads/{}", self.config.branch)
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String> {
+        git(&self.repo_root, args, None).await
+    }
+
+    /// Write the working tree, minus ignored files, as a tree object
+    pub async fn snapshot(&self) -> Result<String> {
+        if !self.index_file.exists() {
+            // Starting from the real index lets git skip hashing unchanged files
+            let real_index = PathBuf::from(
+                self.git(&["rev-parse", "--path-format=absolute", "--git-path", "index"])
+                    .await?,
+            );
+            if real_index.exists() {
+                std::fs::copy(&real_index, &self.index_file)
+                    .with_context(|| format!("Failed to create {}", self.index_file.display()))?;
+            }
+        }
+        let index = Some(self.index_file.as_path());
+        git(&self.repo_root, &["add", "--all", "."], index).await?;
+        git(&self.repo_root, &["write-tree"], index).await
+    }
+
+    async fn resolve(&self, rev: &str) -> Option<String> {
+        self.git(&["rev-parse", "--verify", "--quiet", rev])
+            .await
+            .ok()
+    }
+
+    async fn commit_tree(&self, tree: &str, parent: Option<&str>, message: &str) -> Result<String> {
+        let mut args = vec!["commit-tree", tree, "-m", message];
+        if let Some(parent) = parent {
+            args.extend(["-p", parent]);
+        }
+        self.git(&args).await
+    }
+
+    /// Record the change from `before` (a tree from [`snapshot`](Self::snapshot)
+    /// taken when the turn started) to the working tree now. Returns None
+    /// when the turn didn't change anything.
+    pub async fn create(&self, before: &str, prompt: &str) -> Result<Option<Checkpoint>> {
+        let after = self.snapshot().await?;
+        if after == before {
+            return Ok(None);
+        }
+
+        let branch_ref = self.branch_ref();
+        let tip = self.resolve(&branch_ref).await;
+        let mut parent = match &tip {
+            Some(tip) => Some(tip.clone()),
+            None => self.resolve("HEAD").await,
+        };
+        // Give the checkpoint a parent holding exactly the files as they were
+        // when the turn started, so reverting it undoes only the agent's changes
+        let parent_tree = match &parent {
+            Some(parent) => self.resolve(&format!("{}^{{tree}}", parent)).await,
+            None => None,
+        };
+        if parent_tree.as_deref() != Some(before) {
+            let message = format!("{} {}", self.config.prefix, BASELINE_SUBJECT);
+            parent = Some(
+                self.commit_tree(before, parent.as_deref(), &message)
+                    .await?,
+            );
+        }
+
+        let message = format!("{} {}", self.config.prefix, checkpoint_subject(prompt));
+        let commit = self
+            .commit_tree(&after, parent.as_deref(), &message)
+            .await?;
+        let mut update = vec!["update-ref", branch_ref.as_str(), commit.as_str()];
+        if let Some(tip) = &tip {
+            update.push(tip);
+        }
+        self.git(&update).await?;
+        self.find(&commit).await.map(Some)
+    }
+
+    /// The most recent checkpoints, newest first
+    pub async fn list(&self, limit: usize) -> Result<Vec<Checkpoint>> {
+        let branch_ref = self.branch_ref();
+        if self.resolve(&branch_ref).await.is_none() {
+            return Ok(Vec::new());
+        }
+        let log = self
+            .git(&[
+                "log",
+                "--first-parent",
+                "--format=%H%x1f%h%x1f%cI%x1f%s",
+                &branch_ref,
+            ])
+            .await?;
+        Ok(log
+            .lines()
+            .filter_map(|line| self.parse_log_line(line))
+            .take(limit)
+            .collect())
+    }
+
+    fn parse_log_line(&self, line: &str) -> Option<Checkpoint> {
+        let mut fields = line.split('\u{1f}');
+        let commit = fields.next()?.to_string();
+        let short = fields.next()?.to_string();
+        let created_at = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+        let summary = fields
+            .next()?
+            .strip_prefix(&self.config.prefix)?
+            .trim()
+            .to_string();
+        (summary != BASELINE_SUBJECT).then_some(Checkpoint {
+            commit,
+            short,
+            created_at,
+            summary,
+        })
+    }
+
+    /// The checkpoint whose commit id starts with `id`
+    pub async fn find(&self, id: &str) -> Result<Checkpoint> {
+        let commit = self
+            .resolve(&format!("{}^{{commit}}", id))
+            .await
+            .ok_or_else(|| anyhow!("No checkpoint '{}'", id))?;
+        let line = self
+            .git(&["log", "-1", "--format=%H%x1f%h%x1f%cI%x1f%s", &commit])
+            .await?;
+        self.parse_log_line(&line)
+            .ok_or_else(|| anyhow!("{} isn't a checkpoint", id))
+    }
+
+    /// Files the checkpoint changed, as `git diff --stat` shows them
+    pub async fn stat(&self, checkpoint: &Checkpoint) -> Result<String> {
+        self.git(&[
+            "diff",
+            "--stat",
+            &format!("{}^", checkpoint.commit),
+            &checkpoint.commit,
+        ])
+        .await
+    }
+
+    /// Undo the changes a checkpoint made in the working tree, or the latest
+    /// checkpoint's when `id` is None. Fails without changing anything if the
+    /// files have been edited since in a way that conflicts.
+    pub async fn revert(&self, id: Option<&str>) -> Result<Checkpoint> {
+        let checkpoint = match id {
+            Some(id) => self.find(id).await?,
+            None => self
+                .list(1)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("There are no checkpoints to revert"))?,
+        };
+        let patch = self
+            .git(&[
+                "diff",
+                "--binary",
+                &checkpoint.commit,
+                &format!("{}^", checkpoint.commit),
+            ])
+            .await?;
+        if patch.is_empty() {
+            return Ok(checkpoint);
+        }
+        apply_patch(&self.repo_root, &format!("{}\n", patch))
+            .await
+            .with_context(|| {
+                format!(
+                    "Couldn't revert checkpoint {}; the files it changed have been edited since",
+                    checkpoint.short
+                )
+            })?;
+        Ok(checkpoint)
+    }
+}
+
+/// The first line of the prompt, shortened to fit a commit subject
+fn checkpoint_subject(prompt: &str) -> String {
+    let line = prompt
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("Agent changes");
+    if line.chars().count() <= SUBJECT_CHARS {
+        return line.to_string();
+    }
+    let mut subject: String = line.chars().take(SUBJECT_CHARS - 1).collect();
+    subject.push('…');
+    subject
+}
+
+async fn git(dir: &Path, args: &[&str], index_file: Option<&Path>) -> Result<String> {
+    let mut command = tokio::process::Command::new("git");
+    command.args(args).current_dir(dir);
+    if let Some(index_file) = index_file {
+        command.env("GIT_INDEX_FILE", index_file);
+    }
+    let output = command.output().await.context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+async fn apply_patch(dir: &Path, patch: &str) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new("git")
+        .args(["apply", "--whitespace=nowarn", "-"])
+        .current_dir(dir)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("Failed to run git")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git apply failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for args in [
+            vec!["init", "--quiet"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "user.name", "Test"],
+        ] {
+            git(dir.path(), &args, None).await.unwrap();
+        }
+        std::fs::write(dir.path().join("README.md"), "hello\n").unwrap();
+        git(dir.path(), &["add", "."], None).await.unwrap();
+        git(dir.path(), &["commit", "--quiet", "-m", "Initial"], None)
+            .await
+            .unwrap();
+        dir
+    }
+
+    #[test]
+    fn subjects_come_from_the_first_line_of_the_prompt() {
+        assert_eq!(
+            checkpoint_subject("\n  Fix the build\nThen test"),
+            "Fix the build"
+        );
+        assert_eq!(checkpoint_subject(""), "Agent changes");
+        let subject = checkpoint_subject(&"x".repeat(100));
+        assert_eq!(subject.chars().count(), SUBJECT_CHARS);
+        assert!(subject.ends_with('…'));
+    }
+
+    #[tokio::test]
+    async fn checkpoints_and_reverts_a_turn() {
+        let dir = repo().await;
+        let checkpoints = Checkpointer::open(CheckpointConfig::default(), dir.path())
+            .await
+            .unwrap()
+            .expect("a git repository");
+
+        // Uncommitted changes from before the turn aren't part of it
+        std::fs::write(dir.path().join("notes.txt"), "mine\n").unwrap();
+        let before = checkpoints.snapshot().await.unwrap();
+        assert!(checkpoints
+            .create(&before, "Nothing")
+            .await
+            .unwrap()
+            .is_none());
+
+        std::fs::write(dir.path().join("README.md"), "hello, agent\n").unwrap();
+        std::fs::write(dir.path().join("new.rs"), "fn main() {}\n").unwrap();
+        let checkpoint = checkpoints
+            .create(&before, "Update the readme")
+            .await
+            .unwrap()
+            .expect("files changed");
+        assert_eq!(checkpoint.summary, "Update the readme");
+        assert!(checkpoints
+            .stat(&checkpoint)
+            .await
+            .unwrap()
+            .contains("new.rs"));
+
+        let listed = checkpoints.list(LIST_LIMIT).await.unwrap();
+        assert_eq!(listed, vec![checkpoint.clone()]);
+
+        // The user's branch and index are left alone
+        let status = git(dir.path(), &["status", "--porcelain"], None)
+            .await
+            .unwrap();
+        assert!(status.contains("?? new.rs"));
+        assert_eq!(
+            git(dir.path(), &["rev-list", "--count", "HEAD"], None)
+                .await
+                .unwrap(),
+            "1"
+        );
+
+        let reverted = checkpoints.revert(None).await.unwrap();
+        assert_eq!(reverted.commit, checkpoint.commit);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("README.md")).unwrap(),
+            "hello\n"
+        );
+        assert!(!dir.path().join("new.rs").exists());
+        assert!(dir.path().join("notes.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn outside_a_repository_there_are_no_checkpoints() {
+        let dir = TempDir::new().unwrap();
+        assert!(Checkpointer::open(CheckpointConfig::default(), dir.path())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
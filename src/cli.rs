@@ -1,4 +1,8 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::config_import::ImportTool;
+use crate::conversation_export::ExportFormat;
+use crate::headless::OutputFormat;
 
 /// Flexorama CLI - An AI-powered agent for code and task automation
 #[derive(Parser, Debug)]
@@ -6,7 +10,7 @@ use clap::Parser;
 #[clap(about = "AI-powered agent for code and task automation", long_about = None)]
 pub struct Cli {
     /// The message to send to the agent
-    #[arg(short = 'm', long)]
+    #[arg(short = 'm', long, visible_short_alias = 'p', visible_alias = "print")]
     pub message: Option<String>,
 
     /// Set the API key (overrides config file)
@@ -33,6 +37,10 @@ pub struct Cli {
     #[arg(short = 'f', long = "file", value_name = "FILE")]
     pub context_files: Vec<String>,
 
+    /// Let the agent read and write this directory too (repeatable)
+    #[arg(long = "add-dir", value_name = "DIR")]
+    pub add_dirs: Vec<std::path::PathBuf>,
+
     /// System prompt to use for the conversation
     #[arg(short = 's', long = "system", value_name = "PROMPT")]
     pub system_prompt: Option<String>,
@@ -41,6 +49,55 @@ pub struct Cli {
     #[arg(long = "no-stream")]
     pub no_stream: bool,
 
+    /// Print long responses straight to the terminal instead of a pager
+    #[arg(long = "no-pager")]
+    pub no_pager: bool,
+
+    /// Reopen the most recent conversation in this directory
+    #[arg(long = "continue", conflicts_with = "resume")]
+    pub continue_conversation: bool,
+
+    /// Reopen a conversation by ID, name or unique ID prefix
+    #[arg(long, value_name = "ID")]
+    pub resume: Option<String>,
+
+    /// Open the --continue or --resume conversation without writing to it,
+    /// e.g. while another Flexorama process has it open
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Name this run's conversation so it can be resumed with --resume <name>
+    #[arg(long, value_name = "NAME")]
+    pub session_name: Option<String>,
+
+    /// Output format for -m and stdin runs (text, json or stream-json)
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    pub output_format: OutputFormat,
+
+    /// Print each tool call and its result to stderr during -m and stdin runs
+    #[arg(long)]
+    pub verbose: bool,
+
+    /// Stop after this many model requests for a single message
+    #[arg(long, value_name = "N")]
+    pub max_turns: Option<usize>,
+
+    /// Don't reuse or store cached responses in this run (see [response_cache])
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Reuse cached responses up to this many seconds old; 0 for no limit
+    #[arg(long, value_name = "SECS", conflicts_with = "no_cache")]
+    pub cache_ttl: Option<u64>,
+
+    /// Only offer these tools, e.g. `Read,glob,Bash(git *)` (comma-separated, repeatable)
+    #[arg(long, value_name = "TOOLS")]
+    pub allowed_tools: Vec<String>,
+
+    /// Never allow these tools or calls, e.g. `Write,Bash(git push *)`
+    #[arg(long, value_name = "TOOLS")]
+    pub disallowed_tools: Vec<String>,
+
     /// Enable 'yolo' mode - bypass all permission checks for file and tool operations
     #[arg(long)]
     pub yolo: bool,
@@ -64,6 +121,236 @@ pub struct Cli {
     /// Port for the web UI
     #[arg(long, default_value = "3000")]
     pub web_port: u16,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum Commands {
+    /// Serve Flexorama's built-in tools as an MCP server (stdio by default)
+    McpServe {
+        /// Serve the Streamable HTTP transport instead of stdio
+        #[arg(long)]
+        http: bool,
+
+        /// Port for the HTTP transport
+        #[arg(long, default_value = "3333")]
+        port: u16,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Set up the provider, API key, model and permissions, and a starter AGENTS.md
+    Init,
+    /// Import settings from another coding agent (claude-code, aider or codex)
+    ImportConfig {
+        /// The tool to import from
+        tool: ImportTool,
+
+        /// Show what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Run each prompt in a file as its own conversation
+    Batch {
+        /// One prompt per line, or a YAML list of prompts
+        file: std::path::PathBuf,
+
+        /// Directory for the answers and summary.json
+        #[arg(short, long, value_name = "DIR", default_value = "batch-output")]
+        output_dir: std::path::PathBuf,
+
+        /// How many prompts to run at once
+        #[arg(short = 'j', long, default_value = "4")]
+        concurrency: usize,
+    },
+    /// Run eval tasks against one or more models and compare the results
+    Eval {
+        /// YAML list of tasks, each with a name, prompt, check and optional fixture
+        file: std::path::PathBuf,
+
+        /// Models to compare, as model or provider:model (default: the configured model)
+        #[arg(long, value_delimiter = ',')]
+        models: Vec<String>,
+
+        /// Directory for workspaces, results.json and report.md
+        #[arg(short, long, value_name = "DIR", default_value = "eval-output")]
+        output_dir: std::path::PathBuf,
+    },
+    /// Start a new conversation, set up from a template
+    New {
+        /// Template from the config or .flexorama/templates to start from
+        #[arg(long, value_name = "NAME", required_unless_present = "list")]
+        template: Option<String>,
+
+        /// List the available templates
+        #[arg(long, conflicts_with = "template")]
+        list: bool,
+    },
+    /// Browse and manage saved conversations
+    Conversations {
+        #[command(subcommand)]
+        action: ConversationsCommand,
+    },
+    /// Export a skill as a shareable bundle, or import one
+    Skill {
+        #[command(subcommand)]
+        action: BundleCommand,
+    },
+    /// Export a subagent as a shareable bundle, or import one
+    Agent {
+        #[command(subcommand)]
+        action: BundleCommand,
+    },
+    /// Move the global config, subagents, skills, custom commands and hooks to another machine
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommand,
+    },
+    /// Install a pack of commands, skills and subagents from a git repository
+    Install {
+        /// Git URL, local directory, owner/repo on GitHub, or a pack name in the registry
+        source: String,
+
+        /// Install into the project's .flexorama directory instead of ~/.flexorama
+        #[arg(long)]
+        project: bool,
+
+        /// Install without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Run custom commands on a cron schedule
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleCommand,
+    },
+    /// Install the latest release from GitHub
+    Update {
+        /// Only report whether a newer version is available
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConversationsCommand {
+    /// List recent conversations
+    List {
+        /// Maximum number of conversations to show
+        #[arg(long, default_value = "20")]
+        limit: i64,
+
+        /// Only show conversations with a message containing this text
+        #[arg(long)]
+        search: Option<String>,
+    },
+    /// Print a conversation
+    Show {
+        /// Conversation ID, or a unique prefix of one
+        id: String,
+    },
+    /// Delete a conversation and its history
+    Delete {
+        /// Conversation ID, or a unique prefix of one
+        id: String,
+    },
+    /// Export a conversation as Markdown or JSON
+    Export {
+        /// Conversation ID, or a unique prefix of one
+        id: String,
+
+        /// markdown, json or html
+        #[arg(long, default_value = "markdown")]
+        format: ExportFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum BundleCommand {
+    /// Write a bundle with everything needed to install it elsewhere
+    Export {
+        /// Name of the skill or agent
+        name: String,
+
+        /// Bundle file to write (default: <name>.skill.json or <name>.agent.json)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Install from a bundle file, a URL to one, a directory or a git repository
+    Import {
+        /// Path, URL or git repository to import from
+        source: String,
+
+        /// Install into the project's .flexorama directory instead of ~/.flexorama
+        #[arg(long)]
+        project: bool,
+
+        /// Replace an existing skill or agent with the same name
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ProfileCommand {
+    /// Write everything to one file, with secrets replaced by [REDACTED]
+    Export {
+        /// Profile file to write
+        file: std::path::PathBuf,
+    },
+    /// Install a profile exported on another machine
+    Import {
+        /// Profile file to read
+        file: std::path::PathBuf,
+
+        /// Replace files that already exist instead of keeping them
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ScheduleCommand {
+    /// Run a custom command on a cron schedule, in the current directory
+    Add {
+        /// Five-field cron expression, like "0 9 * * 1", or @hourly, @daily, @weekly or @monthly
+        cron: String,
+
+        /// The custom command and its arguments, like /weekly-report main
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// List scheduled commands and when they next run
+    List,
+    /// Stop running a scheduled command
+    Remove {
+        /// Schedule ID, or a unique prefix of one
+        id: String,
+    },
+    /// Run scheduled commands as they come due, until interrupted
+    Run {
+        /// Run whatever is due now and exit, for use from a system scheduler
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Validate the global and project config, exiting non-zero on errors
+    Check {
+        /// Treat warnings as errors
+        #[arg(long)]
+        strict: bool,
+    },
 }
 
 #[cfg(test)]
@@ -88,6 +375,379 @@ mod tests {
         assert!(!cli.plan_mode);
         assert!(!cli.web);
         assert_eq!(cli.web_port, 3000);
+        assert_eq!(cli.command, None);
+    }
+
+    #[test]
+    fn test_cli_bundle_subcommands() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "skill", "export", "review"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Skill {
+                action: BundleCommand::Export {
+                    name: "review".to_string(),
+                    output: None
+                }
+            })
+        );
+
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "agent",
+            "import",
+            "https://github.com/acme/reviewer",
+            "--project",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Agent {
+                action: BundleCommand::Import {
+                    source: "https://github.com/acme/reviewer".to_string(),
+                    project: true,
+                    force: false
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_profile_subcommands() {
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "profile",
+            "import",
+            "team.json",
+            "--force",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Profile {
+                action: ProfileCommand::Import {
+                    file: "team.json".into(),
+                    force: true
+                }
+            })
+        );
+        assert!(Cli::try_parse_from(vec!["flexorama", "profile", "export"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_install_subcommand() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "install", "acme/pack", "-y"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Install {
+                source: "acme/pack".to_string(),
+                project: false,
+                yes: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_schedule_subcommands() {
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "schedule",
+            "add",
+            "0 9 * * 1",
+            "/weekly-report",
+            "main",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Schedule {
+                action: ScheduleCommand::Add {
+                    cron: "0 9 * * 1".to_string(),
+                    command: vec!["/weekly-report".to_string(), "main".to_string()]
+                }
+            })
+        );
+
+        let cli = Cli::try_parse_from(vec!["flexorama", "schedule", "run", "--once"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Schedule {
+                action: ScheduleCommand::Run { once: true }
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_eval_subcommand() {
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "eval",
+            "evals.yaml",
+            "--models",
+            "claude-sonnet-4,openai:gpt-4o",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Eval {
+                file: std::path::PathBuf::from("evals.yaml"),
+                models: vec!["claude-sonnet-4".to_string(), "openai:gpt-4o".to_string()],
+                output_dir: std::path::PathBuf::from("eval-output"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_mcp_serve_subcommand() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "mcp-serve"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::McpServe {
+                http: false,
+                port: 3333
+            })
+        );
+
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "--yolo",
+            "mcp-serve",
+            "--http",
+            "--port",
+            "4000",
+        ])
+        .unwrap();
+        assert!(cli.yolo);
+        assert_eq!(
+            cli.command,
+            Some(Commands::McpServe {
+                http: true,
+                port: 4000
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_config_check_subcommand() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "config", "check"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                action: ConfigCommand::Check { strict: false }
+            })
+        );
+
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "--config",
+            "ci.toml",
+            "config",
+            "check",
+            "--strict",
+        ])
+        .unwrap();
+        assert_eq!(cli.config, Some("ci.toml".to_string()));
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                action: ConfigCommand::Check { strict: true }
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_new_subcommand() {
+        let cli =
+            Cli::try_parse_from(vec!["flexorama", "new", "--template", "rust-review"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::New {
+                template: Some("rust-review".to_string()),
+                list: false
+            })
+        );
+        assert!(Cli::try_parse_from(vec!["flexorama", "new", "--list"]).is_ok());
+        assert!(Cli::try_parse_from(vec!["flexorama", "new"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_cache_flags() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "-m", "hi", "--cache-ttl", "60"]).unwrap();
+        assert_eq!(cli.cache_ttl, Some(60));
+        assert!(!cli.no_cache);
+        assert!(Cli::try_parse_from(vec!["flexorama", "--no-cache", "--cache-ttl", "60"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_init_subcommand() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "init"]).unwrap();
+        assert_eq!(cli.command, Some(Commands::Init));
+    }
+
+    #[test]
+    fn test_cli_import_config_subcommand() {
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "import-config",
+            "claude-code",
+            "--dry-run",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::ImportConfig {
+                tool: ImportTool::ClaudeCode,
+                dry_run: true
+            })
+        );
+
+        assert!(Cli::try_parse_from(vec!["flexorama", "import-config", "cursor"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_output_format() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "-m", "hi"]).unwrap();
+        assert_eq!(cli.output_format, OutputFormat::Text);
+
+        let cli =
+            Cli::try_parse_from(vec!["flexorama", "-m", "hi", "--output-format", "json"]).unwrap();
+        assert_eq!(cli.output_format, OutputFormat::Json);
+
+        assert!(Cli::try_parse_from(vec!["flexorama", "--output-format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_print_verbose() {
+        let cli =
+            Cli::try_parse_from(vec!["flexorama", "-p", "run the tests", "--verbose"]).unwrap();
+        assert_eq!(cli.message.as_deref(), Some("run the tests"));
+        assert!(cli.verbose);
+
+        let cli = Cli::try_parse_from(vec!["flexorama", "--print", "hi"]).unwrap();
+        assert_eq!(cli.message.as_deref(), Some("hi"));
+        assert!(!cli.verbose);
+    }
+
+    #[test]
+    fn test_cli_max_turns() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "-m", "hi", "--max-turns", "5"]).unwrap();
+        assert_eq!(cli.max_turns, Some(5));
+        assert!(Cli::try_parse_from(vec!["flexorama", "--max-turns", "many"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_tool_restrictions() {
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "--allowed-tools",
+            "Read,glob",
+            "--allowed-tools",
+            "Bash(git *)",
+            "--disallowed-tools",
+            "Write",
+        ])
+        .unwrap();
+        assert_eq!(cli.allowed_tools, vec!["Read,glob", "Bash(git *)"]);
+        assert_eq!(cli.disallowed_tools, vec!["Write"]);
+    }
+
+    #[test]
+    fn test_cli_add_dir() {
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "--add-dir",
+            "../shared",
+            "--add-dir",
+            "/srv/docs",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.add_dirs,
+            vec![
+                std::path::PathBuf::from("../shared"),
+                std::path::PathBuf::from("/srv/docs")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cli_conversations_subcommands() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "conversations", "list"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Conversations {
+                action: ConversationsCommand::List {
+                    limit: 20,
+                    search: None
+                }
+            })
+        );
+
+        let cli = Cli::try_parse_from(vec![
+            "flexorama",
+            "conversations",
+            "export",
+            "abc123",
+            "--format",
+            "json",
+            "-o",
+            "out.json",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Conversations {
+                action: ConversationsCommand::Export {
+                    id: "abc123".to_string(),
+                    format: ExportFormat::Json,
+                    output: Some("out.json".into()),
+                }
+            })
+        );
+
+        assert!(Cli::try_parse_from(vec!["flexorama", "conversations", "delete"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_continue_and_resume() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "--continue", "-m", "and then?"]).unwrap();
+        assert!(cli.continue_conversation);
+        assert_eq!(cli.resume, None);
+
+        let cli = Cli::try_parse_from(vec!["flexorama", "--resume", "5c0e"]).unwrap();
+        assert!(!cli.continue_conversation);
+        assert_eq!(cli.resume, Some("5c0e".to_string()));
+
+        assert!(Cli::try_parse_from(vec!["flexorama", "--continue", "--resume", "5c0e"]).is_err());
+
+        let cli = Cli::try_parse_from(vec!["flexorama", "--continue", "--read-only"]).unwrap();
+        assert!(cli.read_only);
+
+        let cli =
+            Cli::try_parse_from(vec!["flexorama", "--session-name", "auth refactor"]).unwrap();
+        assert_eq!(cli.session_name.as_deref(), Some("auth refactor"));
+    }
+
+    #[test]
+    fn test_cli_update_subcommand() {
+        let cli = Cli::try_parse_from(vec!["flexorama", "update"]).unwrap();
+        assert_eq!(cli.command, Some(Commands::Update { check: false }));
+
+        let cli = Cli::try_parse_from(vec!["flexorama", "update", "--check"]).unwrap();
+        assert_eq!(cli.command, Some(Commands::Update { check: true }));
+    }
+
+    #[test]
+    fn test_cli_batch_subcommand() {
+        let cli =
+            Cli::try_parse_from(vec!["flexorama", "batch", "prompts.txt", "-j", "2"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Batch {
+                file: "prompts.txt".into(),
+                output_dir: "batch-output".into(),
+                concurrency: 2,
+            })
+        );
     }
 
     #[test]
@@ -127,6 +787,7 @@ mod tests {
             "flexorama",
             "--non-interactive",
             "--no-stream",
+            "--no-pager",
             "--yolo",
             "--plan-mode",
         ])
@@ -134,6 +795,7 @@ mod tests {
 
         assert!(cli.non_interactive);
         assert!(cli.no_stream);
+        assert!(cli.no_pager);
         assert!(cli.yolo);
         assert!(cli.plan_mode);
     }
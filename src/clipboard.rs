@@ -0,0 +1,258 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::debug;
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::config::ImagesConfig;
+
+/// Code blocks from this session's responses. Block `n` in a header is
+/// `CODE_BLOCKS[n - 1]`, so numbers stay the same for the whole session.
+static CODE_BLOCKS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Remember a complete code block and return its number
+pub fn record_code_block(code: &str) -> usize {
+    let mut blocks = CODE_BLOCKS.lock().expect("code blocks lock");
+    blocks.push(code.to_string());
+    blocks.len()
+}
+
+/// Number a code block that's still streaming in; its lines are added
+/// with [`append_code_line`]
+pub fn start_code_block() -> usize {
+    record_code_block("")
+}
+
+pub fn append_code_line(number: usize, line: &str) {
+    let mut blocks = CODE_BLOCKS.lock().expect("code blocks lock");
+    if let Some(block) = number.checked_sub(1).and_then(|i| blocks.get_mut(i)) {
+        block.push_str(line);
+        block.push('\n');
+    }
+}
+
+/// The content of code block `number`, or the latest block when `None`
+pub fn code_block(number: Option<usize>) -> Option<(usize, String)> {
+    let blocks = CODE_BLOCKS.lock().expect("code blocks lock");
+    let number = number.unwrap_or(blocks.len());
+    let block = blocks.get(number.checked_sub(1)?)?;
+    Some((number, block.clone()))
+}
+
+/// How text reached the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+    /// The local system clipboard
+    System,
+    /// An OSC 52 escape sequence, which the terminal applies to the
+    /// clipboard of the machine it runs on
+    Terminal,
+}
+
+/// Over SSH the system clipboard belongs to the remote machine, so only
+/// the terminal can reach the user's clipboard
+fn in_ssh_session() -> bool {
+    std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+}
+
+/// The OSC 52 sequence that sets the clipboard to `text`, wrapped for tmux
+/// so it's passed through to the outer terminal
+fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if in_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+fn copy_with_osc52(text: &str) -> Result<()> {
+    let sequence = osc52_sequence(text, std::env::var_os("TMUX").is_some());
+    // Written straight to the terminal: it isn't output, and the TUI would
+    // show the escape sequence as text
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Copy `text` to the clipboard: the system clipboard locally, or OSC 52
+/// over SSH and when no system clipboard is available
+pub fn copy(text: &str) -> Result<CopyMethod> {
+    if !in_ssh_session() {
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            Ok(()) => return Ok(CopyMethod::System),
+            Err(e) => debug!("System clipboard unavailable, using OSC 52: {}", e),
+        }
+    }
+    copy_with_osc52(text).map_err(|e| anyhow!("Failed to copy to the clipboard: {}", e))?;
+    Ok(CopyMethod::Terminal)
+}
+
+/// An image pasted from the clipboard, encoded as PNG
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PastedImage {
+    pub width: usize,
+    pub height: usize,
+    pub media_type: String,
+    /// Base64 of the PNG
+    pub data: String,
+    /// Its size on the clipboard, when it was scaled down to fit the
+    /// `[images]` limits
+    pub original_size: Option<(usize, usize)>,
+}
+
+impl PastedImage {
+    /// The text standing in for the image in the input, with what it costs
+    pub fn placeholder(&self) -> String {
+        let tokens = crate::image::dimension_tokens((self.width as u32, self.height as u32));
+        match self.original_size {
+            Some((width, height)) => format!(
+                "[image {}x{} attached, ~{} tokens, scaled down from {}x{}]",
+                self.width, self.height, tokens, width, height
+            ),
+            None => format!(
+                "[image {}x{} attached, ~{} tokens]",
+                self.width, self.height, tokens
+            ),
+        }
+    }
+}
+
+/// Encode RGBA pixels as a PNG that fits within `limits`
+fn encode_png(
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+    limits: &ImagesConfig,
+) -> Result<PastedImage> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    let image = crate::image::prepare_image("image/png", png, limits)?;
+    let (final_width, final_height) = image.dimensions.unwrap_or((width as u32, height as u32));
+    Ok(PastedImage {
+        width: final_width as usize,
+        height: final_height as usize,
+        media_type: image.media_type.clone(),
+        data: image.base64(),
+        original_size: image.original_dimensions.map(|_| (width, height)),
+    })
+}
+
+/// The image on the system clipboard, or None when it holds something else
+pub fn paste_image() -> Result<Option<PastedImage>> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    match clipboard.get_image() {
+        Ok(image) => encode_png(
+            image.width,
+            image.height,
+            &image.bytes,
+            &crate::image::limits(),
+        )
+        .map(Some),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The text on the system clipboard, if any
+pub fn paste_text() -> Option<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .ok()
+}
+
+/// The pasted images whose placeholders are still in the submitted `text`,
+/// in order; an image whose placeholder was deleted is dropped with it
+pub fn attached_images(pending: Vec<PastedImage>, text: &str) -> Vec<PastedImage> {
+    let mut remaining = text.to_string();
+    pending
+        .into_iter()
+        .filter(|image| {
+            let placeholder = image.placeholder();
+            match remaining.find(&placeholder) {
+                Some(start) => {
+                    remaining.replace_range(start..start + placeholder.len(), "");
+                    true
+                }
+                None => false,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_code_blocks_for_the_session() {
+        let first = record_code_block("fn main() {}\n");
+        assert_eq!(
+            code_block(Some(first)),
+            Some((first, "fn main() {}\n".to_string()))
+        );
+
+        let streamed = start_code_block();
+        append_code_line(streamed, "echo hi");
+        append_code_line(streamed, "echo bye");
+        assert!(streamed > first);
+        assert_eq!(code_block(Some(streamed)).unwrap().1, "echo hi\necho bye\n");
+        assert_eq!(code_block(Some(0)), None);
+        assert!(code_block(None).is_some());
+    }
+
+    #[test]
+    fn encodes_pasted_images_as_png() {
+        let limits = ImagesConfig::default();
+        let image = encode_png(2, 1, &[255, 0, 0, 255, 0, 0, 255, 255], &limits).unwrap();
+        assert_eq!(image.placeholder(), "[image 2x1 attached, ~1 tokens]");
+        assert_eq!(image.media_type, "image/png");
+        let png = STANDARD.decode(&image.data).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let limits = ImagesConfig {
+            max_dimension: 2,
+            ..limits
+        };
+        let image = encode_png(4, 2, &[128; 4 * 2 * 4], &limits).unwrap();
+        assert_eq!(
+            image.placeholder(),
+            "[image 2x1 attached, ~1 tokens, scaled down from 4x2]"
+        );
+    }
+
+    #[test]
+    fn attaches_only_images_still_in_the_input() {
+        let image = |width| PastedImage {
+            width,
+            height: 720,
+            media_type: "image/png".to_string(),
+            data: width.to_string(),
+            original_size: None,
+        };
+        let pending = vec![image(1280), image(1280), image(640)];
+        let attached = attached_images(
+            pending.clone(),
+            "Why is [image 1280x720 attached, ~1229 tokens] misaligned?",
+        );
+        assert_eq!(attached, vec![image(1280)]);
+        let text = "[image 640x720 attached, ~615 tokens] [image 1280x720 attached, ~1229 tokens] \
+                    [image 1280x720 attached, ~1229 tokens]";
+        assert_eq!(attached_images(pending, text).len(), 3);
+    }
+
+    #[test]
+    fn builds_osc52_sequences() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+}
@@ -2,29 +2,43 @@ use anyhow::{anyhow, Result};
 use chrono::Local;
 use colored::*;
 use crossterm::terminal;
-use dialoguer::Select;
+use dialoguer::{Input, Select};
 use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, warn};
 use serde_json;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::fs as async_fs;
 
 use crate::agent::Agent;
+use crate::checkpoint;
+use crate::clipboard::{self, CopyMethod};
+use crate::config::{settable_key, Config, SETTABLE_KEYS};
+use crate::context_usage::{self, ContextBreakdown};
+use crate::conversation_export::ConversationTranscript;
+use crate::conversation_lock::ConversationInUse;
 use crate::custom_commands;
 use crate::database::{Conversation as StoredConversation, Message as StoredMessage};
 use crate::formatter;
 use crate::help::{
-    print_agent_help, print_file_permissions_help, print_help, print_hooks_help, print_mcp_help,
-    print_permissions_help, print_skill_help,
+    print_agent_help, print_custom_commands_help, print_file_permissions_help, print_help,
+    print_hooks_help, print_mcp_help, print_permissions_help, print_skill_help,
 };
 use crate::hooks::{HookEvent, HookManager};
+use crate::learn;
 use crate::mcp::McpManager;
-use crate::processing::create_streaming_renderer;
+use crate::pager;
+use crate::processing::{create_streaming_renderer, process_input};
+use crate::project_config::{self, ConfigSource};
+use crate::pull_request;
+use crate::share;
 use crate::subagent;
 use crate::tools;
 use crate::tui;
+#[cfg(feature = "voice")]
+use crate::voice;
 
 pub async fn handle_agent_command(
     args: &[&str],
@@ -224,11 +238,7 @@ pub async fn handle_agent_command(
             }
 
             let name = args[1];
-            let file_path = dirs::home_dir()
-                .unwrap_or_else(|| std::path::PathBuf::from("."))
-                .join(".flexorama")
-                .join("agents")
-                .join(format!("{}.md", name));
+            let file_path = subagent_manager.subagent_file_path(name);
 
             if file_path.exists() {
                 app_println!(
@@ -310,7 +320,7 @@ pub async fn handle_shell_command(command: &str, _agent: &mut Agent) -> Result<(
                 app_println!("{} Command failed:", "❌".red());
                 app_println!("{}", result.content.red());
             } else {
-                app_println!("{}", result.content);
+                pager::print_paged(&format!("{}\n", result.content));
             }
         })
         .map_err(|e| {
@@ -423,8 +433,18 @@ fn format_resume_option(conversation: &StoredConversation, preview: &str) -> Str
         .format("%Y-%m-%d %H:%M")
         .to_string();
 
-    // Single line: date + preview
-    format!("{} · {}", updated_local, preview)
+    // Single line: date + name (if any) + preview
+    match &conversation.name {
+        Some(name) => format!("{} · {} · {}", updated_local, name, preview),
+        None => format!("{} · {}", updated_local, preview),
+    }
+}
+
+/// Remind the user where a resumed conversation got to
+fn print_conversation_summary(conversation: &StoredConversation) {
+    if let Some(summary) = &conversation.summary {
+        app_println!("{} {}", "Summary:".dimmed(), summary);
+    }
 }
 
 async fn build_conversation_previews(
@@ -444,13 +464,45 @@ async fn build_conversation_previews(
         if messages.is_empty() {
             continue; // Skip conversations with no messages
         }
-        let preview = build_message_preview(&messages);
+        // A generated title says more than the first message, when there is one
+        let preview = match &conversation.title {
+            Some(title) => truncate_line(title, 50),
+            None => build_message_preview(&messages),
+        };
         conversations_with_previews.push((conversation.clone(), preview));
     }
 
     Ok(conversations_with_previews)
 }
 
+/// Resume a conversation, offering to open it read-only when another
+/// Flexorama process has it. Returns false if that was declined.
+async fn resume_or_open_read_only(
+    agent: &mut Agent,
+    conversation_id: &str,
+    tui: Option<&tui::Tui>,
+) -> Result<bool> {
+    let Err(error) = agent.resume_conversation(conversation_id).await else {
+        return Ok(true);
+    };
+    let Some(in_use) = error.downcast_ref::<ConversationInUse>() else {
+        return Err(error);
+    };
+    app_println!("{} {}", "⚠".yellow(), in_use);
+    let options = vec!["Open read-only".to_string(), "Cancel".to_string()];
+    match select_conversation_index("Open it read-only?", options, "Resume cancelled.", tui).await {
+        Some(0) => {
+            agent.open_conversation_read_only(conversation_id).await?;
+            Ok(true)
+        }
+        Some(_) => {
+            app_println!("{}", "Resume cancelled.".yellow());
+            Ok(false)
+        }
+        None => Ok(false),
+    }
+}
+
 async fn select_conversation_index(
     prompt: &str,
     options: Vec<String>,
@@ -589,16 +641,582 @@ pub async fn handle_resume_command(agent: &mut Agent, tui: Option<&tui::Tui>) ->
 
     if let Some(index) = selected_index {
         if let Some((conversation, _)) = conversations_with_previews.get(index) {
-            agent.resume_conversation(&conversation.id).await?;
+            if !resume_or_open_read_only(agent, &conversation.id, tui).await? {
+                return Ok(());
+            }
+            let opened = if agent.is_read_only() {
+                format!("Opened conversation {} read-only", conversation.id)
+            } else {
+                format!("Resumed conversation {}", conversation.id)
+            };
             app_println!(
-                "{} Resumed conversation {} ({} messages loaded).",
+                "{} {} ({} messages loaded).",
                 "✓".green(),
-                conversation.id,
+                opened,
                 agent.conversation_len()
             );
+            print_conversation_summary(conversation);
+        }
+    }
+
+    Ok(())
+}
+
+/// `/checkpoints [list]` lists the commits made after file-changing turns and
+/// `/checkpoints revert [id]` undoes one, the latest by default
+pub async fn handle_checkpoints_command(args: &[&str], agent: &Agent) -> Result<()> {
+    let Some(checkpointer) = agent.checkpointer() else {
+        app_println!(
+            "{} Checkpoints are off. Set enabled = true under [checkpoints] in the config to commit each turn's changes.",
+            "💡".yellow()
+        );
+        return Ok(());
+    };
+
+    match args.first().copied().unwrap_or("list") {
+        "list" => {
+            let checkpoints = checkpointer.list(checkpoint::LIST_LIMIT).await?;
+            if checkpoints.is_empty() {
+                app_println!("No checkpoints on {} yet.", checkpointer.branch().cyan());
+                return Ok(());
+            }
+            app_println!(
+                "{}",
+                format!("Checkpoints on {}", checkpointer.branch()).bold()
+            );
+            for checkpoint in checkpoints {
+                app_println!(
+                    "  {}  {}  {}",
+                    checkpoint.short.yellow(),
+                    checkpoint
+                        .created_at
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                        .dimmed(),
+                    checkpoint.summary
+                );
+            }
+            app_println!(
+                "{} /checkpoints revert [id] undoes a checkpoint's changes",
+                "💡".yellow()
+            );
+        }
+        "revert" => match checkpointer.revert(args.get(1).copied()).await {
+            Ok(checkpoint) => {
+                app_println!(
+                    "{} Reverted checkpoint {} ({})",
+                    "✓".green(),
+                    checkpoint.short.yellow(),
+                    checkpoint.summary
+                );
+                let stat = checkpointer.stat(&checkpoint).await.unwrap_or_default();
+                for line in stat.lines() {
+                    app_println!("  {}", line);
+                }
+            }
+            Err(e) => app_println!("{} {:#}", "✗".red(), e),
+        },
+        _ => app_println!(
+            "{} Usage: /checkpoints [list] | /checkpoints revert [id]",
+            "💡".yellow()
+        ),
+    }
+    Ok(())
+}
+
+/// Push the current branch and open a pull request whose title and
+/// description the model writes from the conversation
+pub async fn handle_pr_command(args: &[&str], agent: &Agent) -> Result<()> {
+    let mut base = None;
+    let mut draft = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--draft" => draft = true,
+            "--base" => base = args.next().copied(),
+            _ => {
+                app_println!("{} Usage: /pr [--base <branch>] [--draft]", "💡".yellow());
+                return Ok(());
+            }
         }
     }
 
+    let dir = std::env::current_dir()?;
+    let target = match pull_request::Target::for_current_branch(&dir, base, draft).await {
+        Ok(target) => target,
+        Err(e) => {
+            app_println!("{} {:#}", "✗".red(), e);
+            return Ok(());
+        }
+    };
+
+    let spinner = create_spinner();
+    spinner.set_message("Writing the pull request description...");
+    let changes = pull_request::branch_changes(&dir, target.base.as_deref()).await;
+    let draft = pull_request::draft(
+        &agent.llm_client(),
+        agent.model(),
+        &agent.snapshot_conversation().messages,
+        &changes,
+    )
+    .await;
+    spinner.finish_and_clear();
+    let pull_request = match draft {
+        Ok(pull_request) => pull_request,
+        Err(e) => {
+            app_println!("{} Failed to write the pull request: {:#}", "✗".red(), e);
+            return Ok(());
+        }
+    };
+    app_println!("{}", pull_request.title.bold());
+    if !pull_request.body.is_empty() {
+        app_println!("{}", pull_request.body.dimmed());
+    }
+    app_println!();
+
+    app_println!("{} {}", "→".cyan(), target.push_command());
+    if let Err(e) = target.push(&dir).await {
+        app_println!("{} {:#}", "✗".red(), e);
+        return Ok(());
+    }
+    app_println!("{} {}", "→".cyan(), target.create_command());
+    match target.create(&dir, &pull_request).await {
+        Ok(url) => app_println!("{} Opened {}", "✓".green(), url.cyan()),
+        Err(e) => app_println!("{} {:#}", "✗".red(), e),
+    }
+    Ok(())
+}
+
+/// Propose the project conventions stated or corrected in this conversation
+/// as additions to AGENTS.md, and write them once confirmed
+pub async fn handle_learn_command(agent: &Agent, tui: Option<&tui::Tui>) -> Result<()> {
+    let messages = agent.snapshot_conversation().messages;
+    if !messages.iter().any(|message| message.role == "assistant") {
+        app_println!("{} Nothing to learn from yet.", "💡".yellow());
+        return Ok(());
+    }
+    let path = std::path::Path::new("AGENTS.md");
+    let agents_md = match tokio::fs::read_to_string(path).await {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("Failed to read AGENTS.md: {}", e)),
+    };
+
+    let spinner = create_spinner();
+    spinner.set_message("Looking for conventions worth keeping...");
+    let proposal = learn::propose(&agent.llm_client(), agent.model(), &agents_md, &messages).await;
+    spinner.finish_and_clear();
+    let additions = match proposal {
+        Ok(additions) => additions,
+        Err(e) => {
+            app_println!("{} Failed to review the conversation: {:#}", "✗".red(), e);
+            return Ok(());
+        }
+    };
+    if additions.is_empty() {
+        app_println!(
+            "{} Nothing in this conversation looks worth adding to AGENTS.md.",
+            "💡".yellow()
+        );
+        return Ok(());
+    }
+
+    let updated = learn::apply(&agents_md, &additions);
+    for line in learn::diff("AGENTS.md", &agents_md, &updated).lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            app_println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            app_println!("{}", line.green());
+        } else if line.starts_with('-') {
+            app_println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            app_println!("{}", line.cyan());
+        } else {
+            app_println!("{}", line.dimmed());
+        }
+    }
+    app_println!();
+
+    let options = vec![
+        match additions.len() {
+            1 => "Add it to AGENTS.md".to_string(),
+            count => format!("Add all {} to AGENTS.md", count),
+        },
+        "Cancel".to_string(),
+    ];
+    let Some(0) = select_conversation_index(
+        "Update AGENTS.md?",
+        options,
+        "Left AGENTS.md as it was.",
+        tui,
+    )
+    .await
+    else {
+        return Ok(());
+    };
+    tokio::fs::write(path, &updated)
+        .await
+        .map_err(|e| anyhow!("Failed to write AGENTS.md: {}", e))?;
+    app_println!(
+        "{} Updated AGENTS.md; new conversations will follow it (/clear starts one)",
+        "✓".green()
+    );
+    Ok(())
+}
+
+/// Export this conversation with secrets redacted, as an HTML file or an
+/// upload, and print the link to it
+pub async fn handle_share_command(args: &[&str], agent: &Agent) -> Result<()> {
+    let force_file = match args {
+        [] => false,
+        ["--file"] => true,
+        _ => {
+            app_println!("{} Usage: /share [--file]", "💡".yellow());
+            return Ok(());
+        }
+    };
+    let Some(database_manager) = agent.database_manager() else {
+        app_println!(
+            "{} Database is not configured; cannot share conversations.",
+            "💡".yellow()
+        );
+        return Ok(());
+    };
+    let Some(conversation_id) = agent.current_conversation_id() else {
+        app_println!("{} No active conversation to share.", "💡".yellow());
+        return Ok(());
+    };
+
+    let transcript = ConversationTranscript::load(&database_manager, &conversation_id).await?;
+    let (transcript, redactions) = share::sanitize(&transcript);
+    let path = share::save(&conversation_id, &transcript.to_html()).await?;
+    if redactions > 0 {
+        app_println!(
+            "{} Redacted {} secret{}",
+            "🔒".cyan(),
+            redactions,
+            if redactions == 1 { "" } else { "s" }
+        );
+    }
+
+    let config = agent.share_config();
+    if force_file || config.upload.is_none() {
+        app_println!(
+            "{} Saved the transcript to {}",
+            "✓".green(),
+            share::file_link(&path).cyan()
+        );
+        return Ok(());
+    }
+    let description = transcript
+        .conversation
+        .name
+        .clone()
+        .or_else(|| transcript.conversation.title.clone())
+        .unwrap_or_else(|| format!("Flexorama conversation {}", conversation_id));
+    let spinner = create_spinner();
+    spinner.set_message("Uploading the transcript...");
+    let uploaded = share::upload(config, &path, &description).await;
+    spinner.finish_and_clear();
+    match uploaded {
+        Ok(link) => app_println!("{} Shared at {}", "✓".green(), link.cyan()),
+        Err(e) => app_println!(
+            "{} {:#}. The transcript is saved at {}",
+            "✗".red(),
+            e,
+            share::file_link(&path)
+        ),
+    }
+    Ok(())
+}
+
+/// Record from the microphone until Enter, transcribe it locally with
+/// whisper.cpp and put the text in the input, ready to edit and send
+#[cfg(feature = "voice")]
+pub async fn handle_voice_command(agent: &Agent, tui: Option<&tui::Tui>) -> Result<()> {
+    let config = agent.voice_config().clone();
+    let recording = match voice::Recording::start(&config) {
+        Ok(recording) => recording,
+        Err(e) => {
+            app_println!("{} {:#}", "✗".red(), e);
+            return Ok(());
+        }
+    };
+    let prompt = format!(
+        "🎙 Recording (up to {}s); press Enter to stop",
+        config.max_seconds
+    );
+    match tui {
+        Some(tui) => {
+            let _ = tui.prompt_text(&prompt);
+        }
+        None => {
+            app_println!("{}", prompt);
+            let mut line = String::new();
+            let _ = std::io::stdin().read_line(&mut line);
+        }
+    }
+    let audio = match recording.stop().await {
+        Ok(audio) => audio,
+        Err(e) => {
+            app_println!("{} {:#}", "✗".red(), e);
+            return Ok(());
+        }
+    };
+
+    let spinner = create_spinner();
+    spinner.set_message("Transcribing...");
+    let transcript = voice::transcribe(&config, &audio).await;
+    spinner.finish_and_clear();
+    let _ = async_fs::remove_file(&audio).await;
+    match transcript {
+        Ok(text) if text.is_empty() => {
+            app_println!("{} Didn't catch anything.", "💡".yellow())
+        }
+        Ok(text) => match tui {
+            Some(tui) => {
+                tui.insert_input(&text)?;
+                app_println!(
+                    "{} Transcribed; edit it or press Enter to send.",
+                    "✓".green()
+                );
+            }
+            None => app_println!("{}", text),
+        },
+        Err(e) => app_println!("{} {:#}", "✗".red(), e),
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "voice"))]
+pub async fn handle_voice_command(_agent: &Agent, _tui: Option<&tui::Tui>) -> Result<()> {
+    app_println!(
+        "{} Voice input isn't in this build; rebuild with --features voice",
+        "💡".yellow()
+    );
+    Ok(())
+}
+
+/// Browse the prompts sent from this project in any session, and run, edit
+/// or copy one
+pub async fn handle_history_command(
+    search: &str,
+    agent: &mut Agent,
+    formatter: &formatter::CodeFormatter,
+    stream: bool,
+    tui: Option<&tui::Tui>,
+) -> Result<()> {
+    let Some(database_manager) = agent.database_manager() else {
+        app_println!(
+            "{} Database is not configured; no prompt history is kept.",
+            "💡".yellow()
+        );
+        return Ok(());
+    };
+    let search = (!search.is_empty()).then_some(search);
+    let limit = match tui.and_then(|tui| tui.terminal_height()) {
+        Some(height) => (height as usize).saturating_sub(8).clamp(5, 100),
+        None => 30,
+    };
+    let history = database_manager
+        .get_prompt_history(limit as i64, search)
+        .await?;
+    if history.is_empty() {
+        match search {
+            Some(search) => app_println!("No prompts matching '{}'.", search),
+            None => app_println!("No prompts yet."),
+        }
+        return Ok(());
+    }
+
+    let options: Vec<String> = history
+        .iter()
+        .map(|entry| {
+            let first_line = entry.prompt.lines().next().unwrap_or_default();
+            let mut preview: String = first_line.chars().take(70).collect();
+            if preview.len() < entry.prompt.trim_end().len() {
+                preview.push('…');
+            }
+            let uses = if entry.uses > 1 {
+                format!(" ×{}", entry.uses)
+            } else {
+                String::new()
+            };
+            format!(
+                "{} {}{}",
+                entry
+                    .last_used
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M"),
+                preview,
+                uses
+            )
+        })
+        .collect();
+    let Some(index) =
+        select_conversation_index("Prompt history", options, "History closed.", tui).await
+    else {
+        return Ok(());
+    };
+    let prompt = history[index].prompt.clone();
+
+    let mut actions = vec!["Run it again", "Copy it"];
+    if tui.is_some() {
+        actions.insert(1, "Edit it in the input");
+    }
+    let actions: Vec<String> = actions.into_iter().map(str::to_string).collect();
+    let Some(action) =
+        select_conversation_index("Use this prompt", actions.clone(), "History closed.", tui).await
+    else {
+        return Ok(());
+    };
+    match actions[action].as_str() {
+        "Run it again" => {
+            app_println!(
+                "> {}",
+                formatter.format_input_with_file_highlighting(&prompt)
+            );
+            process_input(
+                &prompt,
+                agent,
+                formatter,
+                stream,
+                Arc::new(AtomicBool::new(false)),
+                None,
+            )
+            .await;
+        }
+        "Edit it in the input" => {
+            if let Some(tui) = tui {
+                tui.insert_input(&prompt)?;
+            }
+        }
+        _ => match clipboard::copy(&prompt) {
+            Ok(CopyMethod::System) => app_println!("{} Copied the prompt", "✓".green()),
+            Ok(CopyMethod::Terminal) => app_println!(
+                "{} Sent the prompt to the terminal clipboard (OSC 52)",
+                "✓".green()
+            ),
+            Err(e) => app_println!("{} {}", "✗".red(), e),
+        },
+    }
+    Ok(())
+}
+
+pub async fn handle_rename_command(agent: &mut Agent, name: &str) -> Result<()> {
+    let Some(database_manager) = agent.database_manager() else {
+        app_println!(
+            "{} Database is not configured; cannot name conversations.",
+            "💡".yellow()
+        );
+        return Ok(());
+    };
+    let Some(conversation_id) = agent.current_conversation_id() else {
+        app_println!("{} No active conversation to rename.", "💡".yellow());
+        return Ok(());
+    };
+
+    if name.is_empty() {
+        let current = database_manager
+            .get_conversation(&conversation_id)
+            .await?
+            .and_then(|conversation| conversation.name);
+        match current {
+            Some(current) => app_println!("Conversation name: {}", current.cyan()),
+            None => app_println!("This conversation has no name."),
+        }
+        app_println!("{} Usage: /rename <name> | /rename --clear", "💡".yellow());
+        return Ok(());
+    }
+
+    if name == "--clear" {
+        database_manager
+            .set_conversation_name(&conversation_id, None)
+            .await?;
+        app_println!("{} Conversation name cleared.", "✓".green());
+        return Ok(());
+    }
+
+    match database_manager
+        .set_conversation_name(&conversation_id, Some(name))
+        .await
+    {
+        Ok(()) => app_println!(
+            "{} Conversation renamed to '{}'. Reopen it with --resume \"{}\".",
+            "✓".green(),
+            name,
+            name
+        ),
+        Err(e) => app_eprintln!("{} {}", "✗".red(), e),
+    }
+    Ok(())
+}
+
+/// `/diff`: toggle the TUI's diff pane, or print the session's diff
+/// without the TUI
+pub fn handle_diff_command(agent: &Agent, tui: Option<&tui::Tui>) -> Result<()> {
+    let root = std::env::current_dir()?;
+    let diff = agent
+        .session_diff_handle()
+        .lock()
+        .expect("session diff lock")
+        .render(&root);
+    match tui {
+        Some(tui) => {
+            if tui.toggle_diff_pane()? {
+                tui.set_diff(&diff)?;
+            }
+        }
+        None if diff.is_empty() => app_println!("No files changed this session."),
+        None => pager::print_paged(&diff),
+    }
+    Ok(())
+}
+
+/// `/env`: list, set or remove environment variables for this conversation's
+/// shell commands
+pub fn handle_env_command(agent: &mut Agent, args: &str) -> Result<()> {
+    let (subcommand, rest) = args.split_once(' ').unwrap_or((args, ""));
+    let rest = rest.trim();
+    match subcommand {
+        "" | "list" => {
+            let env = agent.conversation_env();
+            if env.is_empty() {
+                app_println!("No environment variables are set for this conversation.");
+            } else {
+                app_println!("{}", "Conversation environment:".cyan().bold());
+                for (name, value) in env.masked_vars() {
+                    app_println!("  {}={}", name.green(), value);
+                }
+            }
+        }
+        "set" if !rest.is_empty() => {
+            let name = agent.conversation_env_mut().set(rest)?;
+            app_println!(
+                "{} {} is set for commands in this conversation.",
+                "✓".green(),
+                name
+            );
+        }
+        "unset" if !rest.is_empty() => {
+            if agent.conversation_env_mut().unset(rest) {
+                app_println!("{} {} removed.", "✓".green(), rest);
+            } else {
+                app_println!("{} {} isn't set.", "💡".yellow(), rest);
+            }
+        }
+        "clear" => {
+            agent.conversation_env_mut().clear();
+            app_println!("{} Conversation environment cleared.", "✓".green());
+        }
+        _ => {
+            app_println!(
+                "{} Usage: /env | /env set KEY=value | /env unset KEY | /env clear",
+                "💡".yellow()
+            );
+        }
+    }
     Ok(())
 }
 
@@ -660,19 +1278,192 @@ pub async fn handle_search_command(
 
     if let Some(index) = selected_index {
         if let Some((conversation, _)) = conversations_with_previews.get(index) {
-            agent.resume_conversation(&conversation.id).await?;
+            if !resume_or_open_read_only(agent, &conversation.id, tui).await? {
+                return Ok(());
+            }
+            let opened = if agent.is_read_only() {
+                format!("Opened conversation {} read-only", conversation.id)
+            } else {
+                format!("Resumed conversation {}", conversation.id)
+            };
             app_println!(
-                "{} Resumed conversation {} ({} messages loaded).",
+                "{} {} ({} messages loaded).",
                 "✓".green(),
-                conversation.id,
+                opened,
                 agent.conversation_len()
             );
+            print_conversation_summary(conversation);
         }
     }
 
     Ok(())
 }
 
+/// `/config`, reading and saving the config file at `config_path`, or the
+/// default one
+pub async fn handle_config_command(args: &[&str], config_path: Option<&str>) -> Result<()> {
+    match args.first().copied() {
+        Some("get") => {
+            let config = Config::load(config_path).await?;
+            match args.get(1) {
+                Some(name) => match config.get_setting(name) {
+                    Ok(value) => app_println!("{} = {}", settable_key(name).unwrap_or(name), value),
+                    Err(e) => app_println!("{} {}", "✗".red(), e),
+                },
+                None => {
+                    app_println!("{}", "Settings".cyan().bold());
+                    for (key, description) in SETTABLE_KEYS {
+                        let value = config.get_setting(key)?;
+                        app_println!("  {:<18} {:<24} {}", key, value, description.dimmed());
+                    }
+                }
+            }
+        }
+        Some("set") if args.len() >= 3 => {
+            let mut config = Config::load(config_path).await?;
+            let value = args[2..].join(" ");
+            let key = match config.set_setting(args[1], &value) {
+                Ok(key) => key,
+                Err(e) => {
+                    app_println!("{} {}", "✗".red(), e);
+                    return Ok(());
+                }
+            };
+            config.save(config_path).await?;
+            app_println!(
+                "{} {} = {} (saved to {})",
+                "✓".green(),
+                key,
+                config.get_setting(key)?,
+                config_path
+                    .map(PathBuf::from)
+                    .unwrap_or_else(Config::default_config_path)
+                    .display()
+            );
+            let overridden = project_config::loaded_sources()
+                .and_then(|sources| sources.get(key))
+                .filter(|source| {
+                    matches!(source, ConfigSource::Project(_) | ConfigSource::CommandLine)
+                });
+            if let Some(source) = overridden {
+                app_println!(
+                    "{} This session's value comes from the {}, which takes precedence",
+                    "ℹ".yellow(),
+                    source
+                );
+            }
+        }
+        Some("set") => {
+            app_println!("{} Usage: /config set <key> <value>", "💡".yellow());
+        }
+        Some("sources") => {
+            let Some(sources) = project_config::loaded_sources() else {
+                app_println!("{} Configuration sources are not available", "ℹ".yellow());
+                return Ok(());
+            };
+            app_println!("{}", "Configuration Sources".cyan().bold());
+            app_println!(
+                "  Precedence (lowest first): default, global, project, environment, command line"
+            );
+            if let Some(path) = sources.global_file() {
+                app_println!("  Global config: {}", path.display());
+            }
+            match sources.project_file() {
+                Some(path) => app_println!("  Project config: {}", path.display()),
+                None => app_println!("  Project config: (none found)"),
+            }
+            app_println!();
+            for (key, source) in sources.iter() {
+                let source = match source {
+                    ConfigSource::Project(_) => source.to_string().green(),
+                    ConfigSource::Default => source.to_string().dimmed(),
+                    _ => source.to_string().normal(),
+                };
+                app_println!("  {:<28} {}", key, source);
+            }
+        }
+        _ => {
+            app_println!(
+                "{} Usage: /config get [key] | /config set <key> <value> | /config sources",
+                "💡".yellow()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ask for each argument a custom command needs but wasn't given, using the
+/// `argument-hint` as the usage line. Returns false if the user cancels or
+/// there's no terminal to ask on.
+async fn prompt_for_missing_arguments(
+    invocation: &mut custom_commands::CustomCommandInvocation,
+    tui: Option<&tui::Tui>,
+) -> bool {
+    let usage = invocation
+        .command
+        .argument_hint
+        .as_deref()
+        .map(|hint| format!("/{} {}", invocation.command.name, hint.trim()));
+    for name in invocation.missing_arguments() {
+        let label = match &usage {
+            Some(usage) => format!("{} (usage: {})", name, usage),
+            None => name.clone(),
+        };
+        let value = match tui {
+            Some(tui) => tui.prompt_text(&label),
+            None if std::io::stdin().is_terminal() => tokio::task::spawn_blocking(move || {
+                Input::<String>::new()
+                    .with_prompt(label)
+                    .allow_empty(true)
+                    .interact_text()
+                    .ok()
+            })
+            .await
+            .ok()
+            .flatten(),
+            None => None,
+        };
+        match value {
+            Some(value) if !value.trim().is_empty() => invocation.supply(&name, value.trim()),
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// `/copy [n]`: copy a numbered code block, or the latest one
+pub fn handle_copy_command(args: &[&str]) -> Result<()> {
+    let number = match args.first() {
+        Some(arg) => match arg.trim_start_matches('#').parse::<usize>() {
+            Ok(number) => Some(number),
+            Err(_) => {
+                app_println!("{} Usage: /copy [block number]", "💡".yellow());
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let Some((number, code)) = clipboard::code_block(number) else {
+        match number {
+            Some(number) => app_println!("{} No code block {}", "✗".red(), number),
+            None => app_println!("{} No code blocks yet", "ℹ".yellow()),
+        }
+        return Ok(());
+    };
+    match clipboard::copy(&code) {
+        Ok(CopyMethod::System) => {
+            app_println!("{} Copied code block {}", "✓".green(), number)
+        }
+        Ok(CopyMethod::Terminal) => app_println!(
+            "{} Sent code block {} to the terminal clipboard (OSC 52)",
+            "✓".green(),
+            number
+        ),
+        Err(e) => app_println!("{} {}", "✗".red(), e),
+    }
+    Ok(())
+}
+
 pub fn handle_hooks_command(args: &[&str]) -> Result<()> {
     if args.is_empty() || args[0] == "list" {
         // List all configured hooks
@@ -789,6 +1580,10 @@ pub async fn handle_slash_command(
     match cmd {
         "/help" => {
             print_help();
+            match custom_commands::list_custom_commands().await {
+                Ok(custom) => print_custom_commands_help(&custom),
+                Err(e) => debug!("Failed to list custom commands: {}", e),
+            }
             Ok(true) // Command was handled
         }
         "/stats" | "/usage" => {
@@ -796,7 +1591,44 @@ pub async fn handle_slash_command(
             Ok(true) // Command was handled
         }
         "/context" => {
-            agent.display_context();
+            match parts.get(1).copied() {
+                None => print_context_breakdown(&agent.context_breakdown().await),
+                Some("messages") => agent.display_context(),
+                Some("remove") => {
+                    let breakdown = agent.context_breakdown().await;
+                    let item = parts
+                        .get(2)
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|n| breakdown.items.get(n));
+                    match item {
+                        Some(item) => match item.message_index {
+                            Some(index) => {
+                                agent.remove_context_message(index)?;
+                                app_println!(
+                                    "{} Removed {} from the context",
+                                    "🗑️".blue(),
+                                    item.label
+                                );
+                            }
+                            None => app_println!(
+                                "{} The {} can't be removed from the context",
+                                "⚠️".yellow(),
+                                item.kind.label()
+                            ),
+                        },
+                        None => app_println!(
+                            "{} Usage: /context remove <n>, where n is an item number from /context",
+                            "⚠️".yellow()
+                        ),
+                    }
+                }
+                Some(other) => app_println!(
+                    "{} Unknown context command: {}. Use /context, /context messages or /context remove <n>.",
+                    "⚠️".yellow(),
+                    other
+                ),
+            }
             Ok(true) // Command was handled
         }
         "/provider" => {
@@ -834,37 +1666,43 @@ pub async fn handle_slash_command(
                     }
                 }
                 "pick" => {
-                    if available.is_empty() {
-                        app_println!(
-                            "{} No default models configured for {}",
-                            "💡".yellow(),
-                            provider
-                        );
-                        return Ok(true);
-                    }
-                    if let Some(tui) = tui {
-                        let options: Vec<String> =
-                            available.iter().map(|model| model.to_string()).collect();
-                        if let Some(index) = select_index_with_tui(
-                            tui,
-                            "Select a model",
-                            &options,
-                            "Model pick cancelled.",
-                        ) {
-                            let new_model = options[index].clone();
-                            agent.set_model(new_model.clone()).await?;
-                            app_println!("{} Active model set to {}", "✅".green(), new_model);
+                    let choices =
+                        crate::model_picker::choices(provider, agent.model(), &available_models);
+                    let selected = if let Some(tui) = tui {
+                        let selected = tui.pick_model(&choices);
+                        if selected.is_none() {
+                            app_println!("{}", "Model pick cancelled.".yellow());
                         }
+                        selected
                     } else {
-                        let selected = Select::new()
+                        let items: Vec<String> = choices
+                            .iter()
+                            .map(|choice| format!("{}  ({})", choice.label(), choice.hint()))
+                            .collect();
+                        Select::new()
                             .with_prompt("Select a model")
-                            .items(&available)
-                            .default(0)
-                            .interact_opt()?;
-                        if let Some(index) = selected {
-                            let new_model = available[index].to_string();
-                            agent.set_model(new_model.clone()).await?;
-                            app_println!("{} Active model set to {}", "✅".green(), new_model);
+                            .items(&items)
+                            .default(
+                                choices
+                                    .iter()
+                                    .position(|choice| choice.current)
+                                    .unwrap_or(0),
+                            )
+                            .interact_opt()?
+                    };
+                    if let Some(index) = selected {
+                        let choice = &choices[index];
+                        match agent
+                            .set_provider(choice.provider, choice.model.clone())
+                            .await
+                        {
+                            Ok(()) => app_println!(
+                                "{} Active model set to {} ({})",
+                                "✅".green(),
+                                choice.model,
+                                choice.provider
+                            ),
+                            Err(e) => app_eprintln!("{} {}", "✗".red(), e),
                         }
                     }
                 }
@@ -889,6 +1727,47 @@ pub async fn handle_slash_command(
             handle_resume_command(agent, tui).await?;
             Ok(true)
         }
+        "/rename" => {
+            let name = command.trim_start_matches("/rename").trim();
+            handle_rename_command(agent, name).await?;
+            Ok(true)
+        }
+        "/diff" => {
+            handle_diff_command(agent, tui)?;
+            Ok(true)
+        }
+        "/env" => {
+            let args = command.trim().trim_start_matches("/env").trim();
+            if let Err(e) = handle_env_command(agent, args) {
+                app_eprintln!("{} {}", "✗".red(), e);
+            }
+            Ok(true)
+        }
+        "/checkpoints" => {
+            handle_checkpoints_command(&parts[1..], agent).await?;
+            Ok(true)
+        }
+        "/pr" => {
+            handle_pr_command(&parts[1..], agent).await?;
+            Ok(true)
+        }
+        "/learn" => {
+            handle_learn_command(agent, tui).await?;
+            Ok(true)
+        }
+        "/share" => {
+            handle_share_command(&parts[1..], agent).await?;
+            Ok(true)
+        }
+        "/voice" => {
+            handle_voice_command(agent, tui).await?;
+            Ok(true)
+        }
+        "/history" => {
+            let search = command.trim_start_matches("/history").trim();
+            handle_history_command(search, agent, formatter, stream, tui).await?;
+            Ok(true)
+        }
         "/clear" => {
             match agent.clear_conversation_keep_agents_md().await {
                 Ok(_) => {
@@ -925,10 +1804,22 @@ pub async fn handle_slash_command(
             handle_file_permissions_command(&parts[1..], agent).await?;
             Ok(true) // Command was handled
         }
+        "/copy" => {
+            handle_copy_command(&parts[1..])?;
+            Ok(true)
+        }
         "/hooks" => {
             handle_hooks_command(&parts[1..])?;
             Ok(true) // Command was handled
         }
+        "/config" => {
+            // The file the session was started with, which --config may have changed
+            let config_path = project_config::loaded_sources()
+                .and_then(|sources| sources.global_file())
+                .and_then(|path| path.to_str());
+            handle_config_command(&parts[1..], config_path).await?;
+            Ok(true)
+        }
         "/plan" => {
             // Parse subcommand with splitn to preserve plan IDs containing whitespace
             let mut plan_parts = command.splitn(3, ' ');
@@ -1012,7 +1903,26 @@ pub async fn handle_slash_command(
             std::process::exit(0);
         }
         _ => {
-            match custom_commands::render_custom_command_input(command).await {
+            let rendered = match custom_commands::parse_custom_command_input(command).await {
+                Ok(Some(mut invocation)) => {
+                    if !prompt_for_missing_arguments(&mut invocation, tui).await {
+                        app_println!("{} {}", "⚠️".yellow(), invocation.missing_args_message());
+                        return Ok(true);
+                    }
+                    let mut rendered = invocation.render();
+                    rendered
+                        .expand(
+                            &agent.get_bash_security_manager(),
+                            agent.yolo_mode(),
+                            agent.conversation_env(),
+                        )
+                        .await
+                        .map(|()| Some(rendered))
+                }
+                Ok(None) => Ok(None),
+                Err(e) => Err(e),
+            };
+            match rendered {
                 Ok(Some(rendered)) => {
                     if let Some(model) = rendered.command.model.clone() {
                         agent.set_model(model).await?;
@@ -1207,8 +2117,11 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                 }
 
                 for (name, config, connected) in servers {
+                    let deferred = mcp_manager.is_deferred(&name).await;
                     let status = if connected {
                         "✅ Connected".green().to_string()
+                    } else if deferred {
+                        "💤 Starts on first use".blue().to_string()
                     } else if config.enabled {
                         "❌ Disconnected".red().to_string()
                     } else {
@@ -1227,7 +2140,7 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                         app_println!("  URL: {}", url);
                     }
 
-                    if connected {
+                    if connected || deferred {
                         if let Ok(tools) = mcp_manager.get_all_tools().await {
                             let server_tools: Vec<_> = tools
                                 .iter()
@@ -1333,6 +2246,88 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                 }
             }
         }
+        "logout" => {
+            if args.len() < 2 {
+                app_println!("{} Usage: /mcp logout <server_name>", "⚠️".yellow());
+                return Ok(());
+            }
+
+            match mcp_manager.logout_server(args[1]).await {
+                Ok(true) => {
+                    app_println!(
+                        "{} Removed stored OAuth token for MCP server: {}",
+                        "🔑".yellow(),
+                        args[1].cyan()
+                    );
+                }
+                Ok(false) => {
+                    app_println!(
+                        "{} No stored OAuth token for MCP server: {}",
+                        "ℹ️".blue(),
+                        args[1].cyan()
+                    );
+                }
+                Err(e) => {
+                    app_eprintln!(
+                        "{} Failed to remove OAuth token for MCP server '{}': {}",
+                        "✗".red(),
+                        args[1],
+                        e
+                    );
+                }
+            }
+        }
+        "import" => {
+            if args.len() < 2 {
+                app_println!(
+                    "{} Usage: /mcp import <claude-desktop|cursor|path/to/.mcp.json>",
+                    "⚠️".yellow()
+                );
+                return Ok(());
+            }
+
+            let source = crate::mcp_import::McpImportSource::parse(args[1]);
+            let servers = match crate::mcp_import::load_servers(&source).await {
+                Ok(servers) => servers,
+                Err(e) => {
+                    app_eprintln!(
+                        "{} Failed to import MCP servers from {}: {:#}",
+                        "✗".red(),
+                        source,
+                        e
+                    );
+                    return Ok(());
+                }
+            };
+            if servers.is_empty() {
+                app_println!("{} No MCP servers found in {}", "ℹ️".blue(), source);
+                return Ok(());
+            }
+
+            match mcp_manager.import_servers(servers).await {
+                Ok(summary) => {
+                    for name in &summary.added {
+                        app_println!("{} Imported MCP server: {}", "✓".green(), name.cyan());
+                    }
+                    for name in &summary.skipped {
+                        app_println!(
+                            "{} Skipped '{}': a server with that name already exists",
+                            "⚠️".yellow(),
+                            name
+                        );
+                    }
+                    if !summary.added.is_empty() {
+                        app_println!(
+                            "{} Use '/mcp connect <name>' or '/mcp connect-all' to start them",
+                            "💡".blue()
+                        );
+                    }
+                }
+                Err(e) => {
+                    app_eprintln!("{} Failed to save imported MCP servers: {}", "✗".red(), e);
+                }
+            }
+        }
         "reconnect" => {
             if args.len() < 2 {
                 app_println!("{} Usage: /mcp reconnect <server_name>", "⚠️".yellow());
@@ -1401,6 +2396,7 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                 );
                 app_println!("{} Usage: /mcp add <name> ws <url>", "⚠️".yellow());
                 app_println!("{} Usage: /mcp add <name> http <url>", "⚠️".yellow());
+                app_println!("{} Usage: /mcp add <name> sse <url>", "⚠️".yellow());
                 app_println!();
                 app_println!("{}", "Examples:".green().bold());
                 app_println!(
@@ -1408,6 +2404,7 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                 );
                 app_println!("  /mcp add websocket ws://localhost:8080");
                 app_println!("  /mcp add linear http https://mcp.linear.app/mcp");
+                app_println!("  /mcp add legacy sse https://example.com/sse");
                 return Ok(());
             }
 
@@ -1436,6 +2433,10 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                     env: None,
                     auth: None,
                     enabled: true,
+                    transport: None,
+                    sampling: None,
+                    lazy: false,
+                    aliases: None,
                 };
 
                 app_println!("{} Adding MCP server: {}", "🔧".blue(), name.cyan());
@@ -1470,8 +2471,18 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                 || connection_type == "websocket"
                 || connection_type == "http"
                 || connection_type == "https"
+                || connection_type == "sse"
             {
                 let url = args[3];
+                let is_sse = connection_type == "sse";
+
+                if is_sse && !url.starts_with("http://") && !url.starts_with("https://") {
+                    app_println!(
+                        "{} SSE URL must start with http:// or https://",
+                        "⚠️".yellow()
+                    );
+                    return Ok(());
+                }
 
                 // Basic URL validation
                 if !url.starts_with("ws://")
@@ -1494,10 +2505,22 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                     env: None,
                     auth: None,
                     enabled: true,
+                    // Plain http URLs auto-detect between Streamable HTTP and legacy SSE
+                    transport: if is_sse {
+                        Some(crate::mcp::McpTransport::Sse)
+                    } else {
+                        None
+                    },
+                    sampling: None,
+                    lazy: false,
+                    aliases: None,
                 };
 
                 app_println!("{} Adding MCP server: {}", "🔧".blue(), name.cyan());
                 app_println!("  URL: {}", url);
+                if is_sse {
+                    app_println!("  Transport: HTTP+SSE (legacy)");
+                }
 
                 match mcp_manager.add_server(name, server_config).await {
                     Ok(_) => {
@@ -1518,13 +2541,16 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
                 }
             } else {
                 app_println!(
-                    "{} Connection type must be 'stdio', 'ws', or 'http'",
+                    "{} Connection type must be 'stdio', 'ws', 'http', or 'sse'",
                     "⚠️".yellow()
                 );
                 app_println!("{} Available types:", "💡".blue());
                 app_println!("  - stdio: For command-line based MCP servers");
                 app_println!("  - ws: For WebSocket-based MCP servers");
-                app_println!("  - http: For HTTP-based MCP servers");
+                app_println!(
+                    "  - http: For HTTP-based MCP servers (Streamable HTTP, falls back to SSE)"
+                );
+                app_println!("  - sse: For legacy HTTP+SSE MCP servers");
             }
         }
         "remove" => {
@@ -1548,11 +2574,8 @@ pub async fn handle_mcp_command(args: &[&str], mcp_manager: &McpManager) -> Resu
             }
         }
         "connect-all" => match mcp_manager.connect_all_enabled().await {
-            Ok(_) => {
-                app_println!(
-                    "{} Attempted to connect to all enabled MCP servers",
-                    "🔄".blue()
-                );
+            Ok(summary) => {
+                app_println!("{} {}", "🔄".blue(), summary.describe());
             }
             Err(e) => {
                 app_eprintln!("{} Failed to connect to MCP servers: {}", "✗".red(), e);
@@ -1664,6 +2687,77 @@ pub fn print_usage_stats(agent: &Agent) {
     }
 }
 
+/// Show how each part of the context uses the model's window
+pub fn print_context_breakdown(breakdown: &ContextBreakdown) {
+    const BAR_WIDTH: usize = 30;
+    let total = breakdown.total_tokens();
+    let percent = breakdown.percent_of_window(total);
+    app_println!("{}", "📝 Context Usage".cyan().bold());
+    app_println!(
+        "  {} · {} token window",
+        breakdown.model,
+        context_usage::format_tokens(breakdown.window)
+    );
+    app_println!();
+    let bar = context_usage::bar(percent, BAR_WIDTH);
+    let bar = match percent {
+        p if p >= 90.0 => bar.red(),
+        p if p >= 70.0 => bar.yellow(),
+        _ => bar.green(),
+    };
+    app_println!(
+        "  {} {:.1}% ({} tokens)",
+        bar,
+        percent,
+        context_usage::format_tokens(total)
+    );
+    app_println!();
+
+    let label_width = breakdown
+        .items
+        .iter()
+        .map(|item| item.label.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(50);
+    for (i, item) in breakdown.items.iter().enumerate() {
+        let item_percent = breakdown.percent_of_window(item.tokens);
+        // Long paths keep their end, which is the part that tells them apart
+        let chars: Vec<char> = item.label.chars().collect();
+        let label: String = if chars.len() > label_width {
+            let tail: String = chars[chars.len() + 1 - label_width..].iter().collect();
+            format!("…{}", tail)
+        } else {
+            item.label.clone()
+        };
+        app_println!(
+            "  {} {:<width$} {:>7}  {} {:>5.1}%",
+            format!("{:>2}.", i + 1).dimmed(),
+            label,
+            context_usage::format_tokens(item.tokens),
+            context_usage::bar(item_percent, BAR_WIDTH / 2).cyan(),
+            item_percent,
+            width = label_width
+        );
+    }
+    app_println!();
+    if breakdown
+        .items
+        .iter()
+        .any(|item| item.message_index.is_some())
+    {
+        app_println!(
+            "{}",
+            "Use /context remove <n> to drop a context file or image.".dimmed()
+        );
+    }
+    app_println!(
+        "{}",
+        "Token counts are estimates. Use /context messages to list every message.".dimmed()
+    );
+    app_println!();
+}
+
 /// Create a progress spinner for API calls
 pub fn create_spinner() -> ProgressBar {
     if crate::output::is_tui_active() {
@@ -2168,6 +3262,9 @@ mod tests {
     fn test_format_resume_option() {
         let conversation = crate::database::Conversation {
             id: "test-id".to_string(),
+            name: None,
+            title: None,
+            summary: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
             system_prompt: None,
@@ -2223,6 +3320,20 @@ mod tests {
         assert_eq!(result, "A");
     }
 
+    #[tokio::test]
+    async fn test_config_set_saves_to_the_given_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("work.toml");
+        let path = path.to_str().unwrap();
+
+        handle_config_command(&["set", "default_model", "gpt-5"], Some(path))
+            .await
+            .unwrap();
+
+        let saved = Config::load(Some(path)).await.unwrap();
+        assert_eq!(saved.default_model, "gpt-5");
+    }
+
     #[tokio::test]
     async fn test_handle_shell_command_empty() {
         let config = crate::config::Config::default();
@@ -2247,6 +3358,30 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_handle_env_command() {
+        let config = crate::config::Config::default();
+        let mut agent =
+            crate::agent::Agent::new_with_plan_mode(config, "test-model".to_string(), false, false)
+                .await;
+
+        handle_env_command(&mut agent, "set API_URL=https://staging.example.com").unwrap();
+        handle_env_command(&mut agent, "set DEPLOY_TOKEN=abcdef123456").unwrap();
+        assert!(handle_env_command(&mut agent, "set not valid").is_err());
+        assert_eq!(
+            agent.conversation_env().masked_vars(),
+            vec![
+                ("API_URL", "https://staging.example.com"),
+                ("DEPLOY_TOKEN", "[REDACTED]"),
+            ]
+        );
+
+        handle_env_command(&mut agent, "unset API_URL").unwrap();
+        assert_eq!(agent.conversation_env().masked_vars().len(), 1);
+        agent.start_new_conversation().await.unwrap();
+        assert!(agent.conversation_env().is_empty());
+    }
+
     #[tokio::test]
     async fn test_handle_agent_command_no_args() {
         let config = crate::config::Config::default();
@@ -2335,6 +3470,40 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_handle_slash_command_context_remove() {
+        let config = crate::config::Config::default();
+        let mut agent =
+            crate::agent::Agent::new_with_plan_mode(config, "test-model".to_string(), false, false)
+                .await;
+        agent.add_context_text("file 'notes.md'", "notes");
+        let formatter = crate::formatter::CodeFormatter::new().unwrap();
+        let mcp_manager = Arc::new(crate::mcp::McpManager::new());
+
+        let breakdown = agent.context_breakdown().await;
+        let number = breakdown
+            .items
+            .iter()
+            .position(|item| item.label == "notes.md")
+            .unwrap()
+            + 1;
+        for command in [
+            "/context messages".to_string(),
+            format!("/context remove {}", number),
+        ] {
+            let result =
+                handle_slash_command(&command, &mut agent, &mcp_manager, &formatter, false, None)
+                    .await;
+            assert!(result.unwrap());
+        }
+        assert!(!agent
+            .context_breakdown()
+            .await
+            .items
+            .iter()
+            .any(|item| item.label == "notes.md"));
+    }
+
     #[tokio::test]
     async fn test_handle_slash_command_clear() {
         let config = crate::config::Config::default();
@@ -2458,6 +3627,9 @@ mod tests {
     fn test_format_resume_option_with_subagent() {
         let conversation = crate::database::Conversation {
             id: "test-id-2".to_string(),
+            name: Some("release notes".to_string()),
+            title: Some("Draft the release notes".to_string()),
+            summary: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
             system_prompt: Some("Custom prompt".to_string()),
@@ -2469,8 +3641,9 @@ mod tests {
         let preview = "Another preview";
         let result = format_resume_option(&conversation, preview);
 
-        // Should contain date and preview, single line
+        // Should contain date, name and preview, single line
         assert!(result.contains("Another preview"));
+        assert!(result.contains(" · release notes · "));
         assert!(result.contains(" · ")); // separator
         assert!(!result.contains('\n')); // single line
     }
@@ -1,26 +1,42 @@
+use crate::project_config::{
+    apply_project_config, find_project_config, load_project_config, record_global_sources,
+    ConfigSource, ConfigSources,
+};
 use crate::security::{BashSecurity, FileSecurity};
-use anyhow::Result;
-use log::info;
+use crate::webhooks::WebhookConfig;
+use anyhow::{anyhow, Result};
+use log::{info, warn, LevelFilter};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum Provider {
-    Anthropic,
-    Gemini,
-    Mistral,
-    OpenAI,
-    #[serde(rename = "z.ai")]
-    Zai,
-    Ollama,
+/// An LLM provider, by the name it's registered under in [`crate::providers`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Provider(&'static str);
+
+impl Provider {
+    pub const ANTHROPIC: Provider = Provider("anthropic");
+    pub const GEMINI: Provider = Provider("gemini");
+    pub const MISTRAL: Provider = Provider("mistral");
+    pub const OPENAI: Provider = Provider("openai");
+    pub const ZAI: Provider = Provider("z.ai");
+    pub const OLLAMA: Provider = Provider("ollama");
+    pub const OPENROUTER: Provider = Provider("openrouter");
+    pub const DEEPSEEK: Provider = Provider("deepseek");
+
+    pub(crate) const fn from_static(name: &'static str) -> Self {
+        Provider(name)
+    }
+
+    pub fn name(self) -> &'static str {
+        self.0
+    }
 }
 
 impl Default for Provider {
     fn default() -> Self {
-        Provider::Anthropic
+        Provider::ANTHROPIC
     }
 }
 
@@ -28,28 +44,26 @@ impl std::str::FromStr for Provider {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "anthropic" => Ok(Provider::Anthropic),
-            "gemini" => Ok(Provider::Gemini),
-            "mistral" => Ok(Provider::Mistral),
-            "openai" => Ok(Provider::OpenAI),
-            "z.ai" | "zai" => Ok(Provider::Zai),
-            "ollama" => Ok(Provider::Ollama),
-            other => Err(format!("Unsupported provider '{}'", other)),
-        }
+        crate::providers::lookup(s).ok_or_else(|| format!("Unsupported provider '{}'", s))
     }
 }
 
 impl std::fmt::Display for Provider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Provider::Anthropic => write!(f, "anthropic"),
-            Provider::Gemini => write!(f, "gemini"),
-            Provider::Mistral => write!(f, "mistral"),
-            Provider::OpenAI => write!(f, "openai"),
-            Provider::Zai => write!(f, "z.ai"),
-            Provider::Ollama => write!(f, "ollama"),
-        }
+        f.write_str(self.0)
+    }
+}
+
+impl Serialize for Provider {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Provider {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -63,6 +77,41 @@ pub struct McpServerConfig {
     #[serde(default)]
     pub auth: Option<McpAuthConfig>,
     pub enabled: bool,
+    /// Transport for http(s) URLs; auto-detected when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<McpTransport>,
+    /// Whether the server may request LLM completions; asks by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<McpSamplingPolicy>,
+    /// Advertise cached tool schemas and only connect when a tool is first called
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lazy: bool,
+    /// Names to expose individual tools under, keyed by the server's tool name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<HashMap<String, String>>,
+}
+
+/// Transport used to reach an http(s) MCP server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransport {
+    /// Streamable HTTP: every message is POSTed to a single endpoint
+    Http,
+    /// Legacy HTTP+SSE: a GET event stream plus a POST endpoint announced by the server
+    Sse,
+}
+
+/// How `sampling/createMessage` requests from an MCP server are handled
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum McpSamplingPolicy {
+    /// Show the requested messages and ask before calling the provider
+    #[default]
+    Ask,
+    /// Answer requests without prompting
+    Allow,
+    /// Reject requests and don't advertise the sampling capability
+    Deny,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,12 +201,507 @@ impl Default for SkillConfig {
     }
 }
 
+/// Limits applied to the web UI's HTTP API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebConfig {
+    /// Requests replenished per second for each client IP (0 disables rate limiting)
+    pub rate_limit_per_second: u64,
+    /// Requests a client IP may burst before being throttled
+    pub rate_limit_burst: u32,
+    /// Maximum request body size for most endpoints
+    pub max_body_bytes: usize,
+    /// Maximum request body size for message endpoints, which may carry images
+    pub max_message_body_bytes: usize,
+    /// Seconds a conversation's agent may sit unused before it's dropped from
+    /// memory (0 keeps agents until the conversation is closed)
+    pub agent_idle_timeout_secs: u64,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_per_second: 20,
+            rate_limit_burst: 60,
+            max_body_bytes: 2 * 1024 * 1024,
+            max_message_body_bytes: 25 * 1024 * 1024,
+            agent_idle_timeout_secs: 30 * 60,
+        }
+    }
+}
+
+/// TLS options for outgoing HTTP clients, for gateways with a private PKI
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// Extra PEM root certificates (bundles allowed) trusted alongside the system roots
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ca_certs: Vec<PathBuf>,
+    /// PEM client certificate for mutual TLS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// PKCS#8 PEM private key for `client_cert`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Accept any server certificate. Dangerous: anyone on the network path can
+    /// read and change traffic, including API keys.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// When a new log file is started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// One file per day, shared by every session that day
+    #[default]
+    Daily,
+    /// One file per run of flexorama
+    Session,
+}
+
+/// The `[checkpoints]` config table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckpointConfig {
+    /// Commit the files each turn changes to `branch`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub enabled: bool,
+    pub branch: String,
+    /// Start of every checkpoint commit's subject
+    pub prefix: String,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            branch: "flexorama/checkpoints".to_string(),
+            prefix: "[checkpoint]".to_string(),
+        }
+    }
+}
+
+impl CheckpointConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[lsp]` config table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LspConfig {
+    /// Ask a language server for diagnostics on each file the agent writes
+    /// or edits
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub enabled: bool,
+    /// Longest wait for a server's diagnostics after an edit
+    pub timeout_ms: u64,
+    pub servers: Vec<LspServerConfig>,
+}
+
+/// A language server and the files it checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LspServerConfig {
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// File extensions, without the dot
+    pub extensions: Vec<String>,
+    /// The LSP language ID of the files; guessed from the extension when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_id: Option<String>,
+}
+
+impl Default for LspConfig {
+    fn default() -> Self {
+        let server = |command: &str, args: &[&str], extensions: &[&str]| LspServerConfig {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+            language_id: None,
+        };
+        Self {
+            enabled: false,
+            timeout_ms: 5000,
+            servers: vec![
+                server("rust-analyzer", &[], &["rs"]),
+                server(
+                    "typescript-language-server",
+                    &["--stdio"],
+                    &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+                ),
+            ],
+        }
+    }
+}
+
+impl LspConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[tests]` config table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TestsConfig {
+    /// The project's test command, run through the shell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Run `command` after each turn that changed files and hand failures
+    /// back to the model to fix
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub auto_run: bool,
+    /// Most extra turns spent fixing failing tests after a change
+    pub max_fix_attempts: usize,
+    /// Longest a test run may take
+    pub timeout_secs: u64,
+}
+
+impl Default for TestsConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            auto_run: false,
+            max_fix_attempts: 3,
+            timeout_secs: 600,
+        }
+    }
+}
+
+impl TestsConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[format]` config table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// Run the matching formatter on each file the agent writes or edits
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub enabled: bool,
+    pub formatters: Vec<FormatterConfig>,
+}
+
+/// A formatter or fixing linter and the files it handles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormatterConfig {
+    pub command: String,
+    /// Arguments before the file path, which is always last
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// File extensions, without the dot
+    pub extensions: Vec<String>,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        let formatter = |command: &str, args: &[&str], extensions: &[&str]| FormatterConfig {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        };
+        Self {
+            enabled: false,
+            formatters: vec![
+                formatter("rustfmt", &["--edition", "2021"], &["rs"]),
+                formatter(
+                    "prettier",
+                    &["--write", "--log-level", "warn"],
+                    &[
+                        "js", "jsx", "mjs", "cjs", "ts", "tsx", "css", "scss", "html", "md",
+                        "json", "yaml", "yml",
+                    ],
+                ),
+                formatter("black", &["--quiet"], &["py"]),
+            ],
+        }
+    }
+}
+
+impl FormatConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[share]` config table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    /// Where `/share` uploads transcripts: `gist`, or a URL that takes the
+    /// page in a POST body and answers with a link. Unset saves a file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload: Option<String>,
+    /// Headers sent to the upload URL; values may be `env:` or `keyring:`
+    /// references
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+impl ShareConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[voice]` config table, used by `/voice` in builds with the `voice`
+/// feature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceConfig {
+    /// The whisper.cpp command line program
+    pub whisper_command: String,
+    /// The ggml model file; defaults to ~/.flexorama/models/ggml-base.en.bin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<PathBuf>,
+    /// Spoken language, or `auto` to detect it
+    pub language: String,
+    /// Longest recording
+    pub max_seconds: u64,
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            whisper_command: "whisper-cli".to_string(),
+            model: None,
+            language: "en".to_string(),
+            max_seconds: 120,
+        }
+    }
+}
+
+impl VoiceConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What to do with the files a prompt mentions without an @
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryMode {
+    #[default]
+    Off,
+    /// List them so they can be added with @
+    Suggest,
+    /// Add them to the context with a notice
+    Auto,
+}
+
+/// The `[discovery]` config table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    pub mode: DiscoveryMode,
+    /// Most files suggested or added for one prompt
+    pub max_files: usize,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            mode: DiscoveryMode::Off,
+            max_files: 3,
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[images]` config table: how images are shrunk before they're sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagesConfig {
+    /// Longest side, in pixels, larger PNGs are scaled down to
+    pub max_dimension: u32,
+    /// Largest encoded image sent; bigger PNGs are scaled down until they fit
+    pub max_bytes: usize,
+    /// Remove EXIF metadata, such as camera details and location, from
+    /// images before they're sent
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub strip_exif: bool,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            max_dimension: 1568,
+            max_bytes: 5 * 1024 * 1024,
+            strip_exif: false,
+        }
+    }
+}
+
+impl ImagesConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[response_cache]` config table: reuse replies to identical requests
+/// in `-m`, piped and batch runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseCacheConfig {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub enabled: bool,
+    /// How long a cached reply is reused; 0 keeps it until it's deleted
+    pub ttl_secs: u64,
+    /// Where cached replies are kept instead of the user cache directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 7 * 24 * 60 * 60,
+            dir: None,
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `[logging]` config table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Write a JSON log file alongside the normal output
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub json: bool,
+    /// Directory for log files; defaults to ~/.flexorama/logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    pub rotation: LogRotation,
+    /// Most verbose level written to the file: error, warn, info, debug or trace
+    pub level: String,
+    /// Older log files beyond this many are deleted
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            json: false,
+            dir: None,
+            rotation: LogRotation::Daily,
+            level: "info".to_string(),
+            max_files: 14,
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn level_filter(&self) -> Result<LevelFilter> {
+        self.level
+            .parse()
+            .map_err(|_| anyhow!("Invalid logging.level '{}'", self.level))
+    }
+
+    pub fn log_dir(&self) -> Result<PathBuf> {
+        match &self.dir {
+            Some(dir) => Ok(PathBuf::from(
+                shellexpand::tilde(&dir.to_string_lossy()).into_owned(),
+            )),
+            None => Ok(dirs::home_dir()
+                .ok_or_else(|| anyhow!("Could not determine home directory"))?
+                .join(".flexorama")
+                .join("logs")),
+        }
+    }
+}
+
+/// A provider declared in the config: any server with OpenAI's chat
+/// completions API, such as vLLM, LM Studio or llama.cpp
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomProviderConfig {
+    /// Name used by `--provider` and the config
+    pub name: String,
+    /// Where the API is served, e.g. `http://localhost:8000/v1`
+    pub base_url: String,
+    /// Environment variable holding the API key; local servers without one
+    /// don't need it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    /// Defaults to the first of `models`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// Ask the server for its models at startup (`GET /models`)
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub list_models: bool,
+    /// The models can call tools; when off they're sent none
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub tools: bool,
+    /// The models accept images; when off images are left out of requests
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub images: bool,
+    /// The server streams replies; when off each reply arrives whole
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub streaming: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+/// Register the `[[providers]]` declared in a config file. This runs before
+/// the rest of the file is parsed, so `provider` can name one of them.
+pub fn register_custom_providers(content: &str) -> Result<Vec<Provider>> {
+    #[derive(Deserialize)]
+    struct Providers {
+        #[serde(default)]
+        providers: Vec<CustomProviderConfig>,
+    }
+    let declared: Providers = toml::from_str(content)?;
+    declared
+        .providers
+        .iter()
+        .map(crate::providers::register_custom)
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(skip)]
     pub api_key: String,
     #[serde(default)]
     pub provider: Provider,
+    /// Where the API key is read from when the provider's environment
+    /// variable isn't set, as `keyring:NAME` or `env:NAME`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_secret: Option<String>,
     pub base_url: String,
     pub default_model: String,
     pub max_tokens: u32,
@@ -169,6 +713,159 @@ pub struct Config {
     pub mcp: McpConfig,
     #[serde(default)]
     pub skills: SkillConfig,
+    #[serde(default)]
+    pub web: WebConfig,
+    /// Optional cap on total tokens per session; crossing it fires a webhook
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Tools the model is never offered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_tools: Vec<String>,
+    /// Where custom commands are read from instead of ~/.flexorama/commands
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commands_dir: Option<PathBuf>,
+    /// Stream responses as they arrive; `--no-stream` turns it off for one run
+    #[serde(default = "default_stream", skip_serializing_if = "is_default_stream")]
+    pub stream: bool,
+    /// Apply file edits without asking for permission first
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_accept_edits: bool,
+    /// HTTP proxy for all outgoing requests; overrides HTTP_PROXY/HTTPS_PROXY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "TlsConfig::is_default")]
+    pub tls: TlsConfig,
+    /// Number the lines of code blocks in responses
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub code_line_numbers: bool,
+    /// Column to wrap responses at; unset follows the terminal width and 0
+    /// turns wrapping off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_width: Option<usize>,
+    /// Base URL that `flexorama install <name>` appends pack names to, such
+    /// as a GitHub organization holding one repository per pack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_url: Option<String>,
+    #[serde(default, skip_serializing_if = "LoggingConfig::is_default")]
+    pub logging: LoggingConfig,
+    /// Generate conversation titles and summaries in the background after
+    /// each turn, for /resume
+    #[serde(
+        default = "default_summarize_conversations",
+        skip_serializing_if = "is_default_summarize_conversations"
+    )]
+    pub summarize_conversations: bool,
+    /// Language for help, prompts and messages, such as `es` or `fr`; unset
+    /// follows the system locale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "CheckpointConfig::is_default")]
+    pub checkpoints: CheckpointConfig,
+    #[serde(default, skip_serializing_if = "LspConfig::is_default")]
+    pub lsp: LspConfig,
+    /// Check the syntax of each file Write or Edit changes and tell the
+    /// model when a change broke it
+    #[serde(
+        default = "default_check_syntax",
+        skip_serializing_if = "is_default_check_syntax"
+    )]
+    pub check_syntax: bool,
+    #[serde(default, skip_serializing_if = "TestsConfig::is_default")]
+    pub tests: TestsConfig,
+    #[serde(default, skip_serializing_if = "FormatConfig::is_default")]
+    pub format: FormatConfig,
+    #[serde(default, skip_serializing_if = "ShareConfig::is_default")]
+    pub share: ShareConfig,
+    #[serde(default, skip_serializing_if = "VoiceConfig::is_default")]
+    pub voice: VoiceConfig,
+    #[serde(default, skip_serializing_if = "DiscoveryConfig::is_default")]
+    pub discovery: DiscoveryConfig,
+    #[serde(default, skip_serializing_if = "ResponseCacheConfig::is_default")]
+    pub response_cache: ResponseCacheConfig,
+    #[serde(default, skip_serializing_if = "ImagesConfig::is_default")]
+    pub images: ImagesConfig,
+    /// Conversation templates for `flexorama new --template <name>`, by name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, crate::templates::TemplateConfig>,
+    /// OpenAI-compatible providers to register alongside the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<CustomProviderConfig>,
+}
+
+fn default_stream() -> bool {
+    true
+}
+
+fn is_default_stream(stream: &bool) -> bool {
+    *stream
+}
+
+fn default_summarize_conversations() -> bool {
+    true
+}
+
+fn is_default_summarize_conversations(summarize: &bool) -> bool {
+    *summarize
+}
+
+fn default_check_syntax() -> bool {
+    true
+}
+
+fn is_default_check_syntax(check: &bool) -> bool {
+    *check
+}
+
+/// Settings that `/config get` and `/config set` understand: (key, description)
+pub const SETTABLE_KEYS: &[(&str, &str)] = &[
+    ("default_model", "Model used when --model isn't given"),
+    ("temperature", "Sampling temperature"),
+    ("stream", "Stream responses as they arrive (true/false)"),
+    (
+        "auto_accept_edits",
+        "Apply file edits without asking (true/false)",
+    ),
+    ("token_budget", "Token budget per session, or 'none'"),
+    ("output_width", "Column to wrap output at, 'auto' or 'off'"),
+];
+
+/// The config key a `/config` argument refers to, allowing a few short names
+pub fn settable_key(name: &str) -> Option<&'static str> {
+    let name = match name.replace('-', "_").as_str() {
+        "model" => "default_model",
+        "auto_accept" => "auto_accept_edits",
+        "budget" => "token_budget",
+        "width" => "output_width",
+        other => {
+            return SETTABLE_KEYS
+                .iter()
+                .map(|(key, _)| *key)
+                .find(|key| *key == other)
+        }
+    };
+    Some(name)
+}
+
+/// Narrower than this and wrapped responses become unreadable
+const MIN_OUTPUT_WIDTH: usize = 20;
+
+/// `output_width` as `/config get` shows it
+pub fn describe_output_width(width: Option<usize>) -> String {
+    match width {
+        None => "auto".to_string(),
+        Some(0) => "off".to_string(),
+        Some(width) => width.to_string(),
+    }
+}
+
+fn parse_bool_setting(key: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Ok(true),
+        "false" | "off" | "no" | "0" => Ok(false),
+        _ => Err(anyhow!("{} must be true or false, got '{}'", key, value)),
+    }
 }
 const DEFAULT_SYSTEM_PROMPT: &str = r#"
 You are an expert in software development. Your job is to help the user build awesome software.
@@ -187,103 +884,43 @@ You must call the complete_todo tool call after completing an item in your tool
 "#;
 
 pub fn provider_default_api_key(provider: Provider) -> String {
-    match provider {
-        Provider::Anthropic => std::env::var("ANTHROPIC_AUTH_TOKEN").unwrap_or_default(),
-        Provider::Gemini => std::env::var("GEMINI_API_KEY")
-            .or_else(|_| std::env::var("GOOGLE_API_KEY"))
-            .unwrap_or_default(),
-        Provider::Mistral => std::env::var("MISTRAL_API_KEY").unwrap_or_default(),
-        Provider::OpenAI => std::env::var("OPENAI_API_KEY").unwrap_or_default(),
-        Provider::Zai => std::env::var("ZAI_API_KEY").unwrap_or_default(),
-        Provider::Ollama => std::env::var("OLLAMA_API_KEY").unwrap_or_default(),
-    }
+    crate::providers::spec(provider)
+        .api_key_env
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .unwrap_or_default()
+}
+
+/// Environment variable the API key for `provider` is read from
+pub fn provider_api_key_env(provider: Provider) -> String {
+    let spec = crate::providers::spec(provider);
+    spec.api_key_env
+        .iter()
+        .find(|var| std::env::var(var).is_ok())
+        .or(spec.api_key_env.last())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Whether `provider` can be used without an API key
+pub fn provider_api_key_optional(provider: Provider) -> bool {
+    crate::providers::spec(provider).api_key_optional
 }
 
 pub fn provider_default_base_url(provider: Provider) -> String {
-    match provider {
-        Provider::Anthropic => std::env::var("ANTHROPIC_BASE_URL")
-            .unwrap_or_else(|_| "https://api.anthropic.com/v1".to_string()),
-        Provider::Gemini => std::env::var("GEMINI_BASE_URL")
-            .unwrap_or_else(|_| "https://generativelanguage.googleapis.com/v1beta".to_string()),
-        Provider::Mistral => std::env::var("MISTRAL_BASE_URL")
-            .unwrap_or_else(|_| "https://api.mistral.ai/v1".to_string()),
-        Provider::OpenAI => std::env::var("OPENAI_BASE_URL")
-            .unwrap_or_else(|_| "https://api.openai.com/v1".to_string()),
-        Provider::Zai => std::env::var("ZAI_BASE_URL")
-            .unwrap_or_else(|_| "https://api.z.ai/api/anthropic".to_string()),
-        Provider::Ollama => std::env::var("OLLAMA_BASE_URL")
-            .unwrap_or_else(|_| "http://localhost:11434".to_string()),
-    }
+    let spec = crate::providers::spec(provider);
+    spec.base_url_env
+        .as_ref()
+        .and_then(|var| std::env::var(var).ok())
+        .unwrap_or_else(|| spec.default_base_url.clone())
 }
 
 pub fn provider_default_model(provider: Provider) -> String {
-    match provider {
-        Provider::Anthropic => "claude-sonnet-4-5".to_string(),
-        Provider::Gemini => "gemini-flash-latest".to_string(),
-        Provider::Mistral => "mistral-large-latest".to_string(),
-        Provider::OpenAI => "gpt-5.2".to_string(),
-        Provider::Zai => "glm-4.7".to_string(),
-        Provider::Ollama => "llama2".to_string(),
-    }
-}
-
-pub fn provider_models(provider: Provider) -> &'static [&'static str] {
-    match provider {
-        Provider::Anthropic => &[
-            "claude-opus-4-1",
-            "claude-sonnet-4-0",
-            "claude-3-7-sonnet-latest",
-            "claude-opus-4-0",
-            "claude-sonnet-4-5",
-            "claude-haiku-4-5",
-            "claude-opus-4-5",
-        ],
-        Provider::Gemini => &[
-            "gemini-flash-latest",
-            "gemini-1.5-pro",
-            "gemini-1.5-flash",
-            "gemini-2.5-flash",
-            "gemini-3-pro-preview",
-            "gemini-3-flash-preview",
-            "gemini-3-pro-image-preview",
-            "gemini-2.5-flash-lite",
-            "gemini-2.5-pro",
-        ],
-        Provider::Mistral => &[
-            "mistral-large-latest",
-            "mistral-medium-latest",
-            "mistral-small-latest",
-            "pixtral-12b-2409",
-            "open-mistral-nemo",
-        ],
-        Provider::OpenAI => &[
-            "gpt-5.2",
-            "gpt-5.1-codex-max",
-            "gpt-5.1",
-            "gpt-5.1-chat",
-            "gpt-5.1-codex",
-            "gpt-5.1-codex-mini",
-            "gpt-5-pro",
-            "gpt-5-codex",
-            "gpt-5",
-            "gpt-5-mini",
-            "gpt-5-nano",
-            "o3-pro",
-            "codex-mini",
-            "o4-mini",
-            "o3",
-            "o3-mini",
-            "o1",
-            "o1-mini",
-            "gpt-4o",
-            "gpt-4o-mini",
-            "gpt-4-turbo",
-            "gpt-4",
-            "gpt-3.5-turbo",
-        ],
-        Provider::Zai => &["glm-4.7", "glm-4.6", "glm-4.5"],
-        Provider::Ollama => &["llama2", "gemma3:1b"],
-    }
+    crate::providers::spec(provider).default_model.clone()
+}
+
+pub fn provider_models(provider: Provider) -> Vec<String> {
+    crate::providers::spec(provider).models.clone()
 }
 
 impl Default for Config {
@@ -292,6 +929,7 @@ impl Default for Config {
         Self {
             api_key: provider_default_api_key(provider),
             provider,
+            api_key_secret: None,
             base_url: provider_default_base_url(provider),
             default_model: provider_default_model(provider),
             max_tokens: 4096,
@@ -301,11 +939,115 @@ impl Default for Config {
             file_security: FileSecurity::default(),
             mcp: McpConfig::default(),
             skills: SkillConfig::default(),
+            web: WebConfig::default(),
+            token_budget: None,
+            webhooks: Vec::new(),
+            denied_tools: Vec::new(),
+            commands_dir: None,
+            stream: true,
+            auto_accept_edits: false,
+            proxy: None,
+            tls: TlsConfig::default(),
+            code_line_numbers: false,
+            output_width: None,
+            registry_url: None,
+            logging: LoggingConfig::default(),
+            summarize_conversations: true,
+            locale: None,
+            checkpoints: CheckpointConfig::default(),
+            lsp: LspConfig::default(),
+            check_syntax: true,
+            tests: TestsConfig::default(),
+            format: FormatConfig::default(),
+            share: ShareConfig::default(),
+            voice: VoiceConfig::default(),
+            discovery: DiscoveryConfig::default(),
+            response_cache: ResponseCacheConfig::default(),
+            images: ImagesConfig::default(),
+            templates: HashMap::new(),
+            providers: Vec::new(),
         }
     }
 }
 
 impl Config {
+    /// The value of a setting from [`SETTABLE_KEYS`], as `/config get` shows it
+    pub fn get_setting(&self, name: &str) -> Result<String> {
+        let key = settable_key(name).ok_or_else(|| anyhow!("Unknown setting '{}'", name))?;
+        Ok(match key {
+            "default_model" => self.default_model.clone(),
+            "temperature" => self.temperature.to_string(),
+            "stream" => self.stream.to_string(),
+            "auto_accept_edits" => self.auto_accept_edits.to_string(),
+            "token_budget" => self
+                .token_budget
+                .map(|budget| budget.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+            "output_width" => describe_output_width(self.output_width),
+            _ => unreachable!("settable key without a getter: {}", key),
+        })
+    }
+
+    /// Change a setting from [`SETTABLE_KEYS`], parsing `value` for its type.
+    /// Returns the canonical key name.
+    pub fn set_setting(&mut self, name: &str, value: &str) -> Result<&'static str> {
+        let key = settable_key(name).ok_or_else(|| anyhow!("Unknown setting '{}'", name))?;
+        let value = value.trim();
+        match key {
+            "default_model" => {
+                if value.is_empty() {
+                    return Err(anyhow!("default_model can't be empty"));
+                }
+                self.default_model = value.to_string();
+            }
+            "temperature" => {
+                let temperature: f32 = value
+                    .parse()
+                    .map_err(|_| anyhow!("temperature must be a number, got '{}'", value))?;
+                if !(0.0..=2.0).contains(&temperature) {
+                    return Err(anyhow!("temperature must be between 0 and 2"));
+                }
+                self.temperature = temperature;
+            }
+            "stream" => self.stream = parse_bool_setting(key, value)?,
+            "auto_accept_edits" => self.auto_accept_edits = parse_bool_setting(key, value)?,
+            "token_budget" => {
+                self.token_budget = match value.to_ascii_lowercase().as_str() {
+                    "none" | "off" | "" => None,
+                    _ => Some(value.parse().map_err(|_| {
+                        anyhow!(
+                            "token_budget must be a whole number or 'none', got '{}'",
+                            value
+                        )
+                    })?),
+                };
+            }
+            "output_width" => {
+                self.output_width = match value.to_ascii_lowercase().as_str() {
+                    "auto" | "" => None,
+                    "off" | "none" => Some(0),
+                    _ => {
+                        let width: usize = value.parse().map_err(|_| {
+                            anyhow!(
+                                "output_width must be a number of columns, 'auto' or 'off', got '{}'",
+                                value
+                            )
+                        })?;
+                        if width != 0 && width < MIN_OUTPUT_WIDTH {
+                            return Err(anyhow!(
+                                "output_width must be at least {} columns",
+                                MIN_OUTPUT_WIDTH
+                            ));
+                        }
+                        Some(width)
+                    }
+                };
+            }
+            _ => unreachable!("settable key without a setter: {}", key),
+        }
+        Ok(key)
+    }
+
     pub fn default_config_path() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -321,6 +1063,7 @@ impl Config {
 
         let mut config = if config_path.exists() {
             let content = fs::read_to_string(&config_path).await?;
+            register_custom_providers(&content)?;
             let mut config: Config = toml::from_str(&content)?;
 
             // Ensure API key is never loaded from config file
@@ -348,10 +1091,57 @@ impl Config {
 
         // Always prioritize environment variables for API key based on provider
         config.api_key = provider_default_api_key(config.provider);
+        if config.api_key.is_empty() {
+            if let Some(reference) = &config.api_key_secret {
+                match crate::secrets::resolve_secret(reference) {
+                    Ok(key) => config.api_key = key,
+                    Err(e) => warn!("Could not read the API key from {}: {}", reference, e),
+                }
+            }
+        }
 
         Ok(config)
     }
 
+    /// Load the global configuration, then merge the nearest project config
+    /// (`.flexorama/config.toml` or `flexorama.toml`) found from `project_dir`.
+    /// Precedence, lowest first: defaults, global file, project file,
+    /// environment variables. Command-line flags are applied by the caller.
+    pub async fn load_layered(
+        path: Option<&str>,
+        project_dir: &Path,
+    ) -> Result<(Self, ConfigSources)> {
+        let config_path = path
+            .map(PathBuf::from)
+            .unwrap_or_else(Self::default_config_path);
+        let mut config = Self::load(path).await?;
+
+        let mut sources = ConfigSources::default();
+        let global = if config_path.exists() {
+            fs::read_to_string(&config_path).await?
+        } else {
+            String::new()
+        };
+        record_global_sources(&global, &config_path, &mut sources);
+
+        if let Some(project_path) = find_project_config(project_dir) {
+            let project = load_project_config(&project_path)?;
+            apply_project_config(&mut config, project, &project_path, &mut sources);
+            info!("Merged project config from {}", project_path.display());
+        }
+
+        if !config.api_key.is_empty() {
+            let source = if provider_default_api_key(config.provider).is_empty() {
+                ConfigSource::Global(config_path.clone())
+            } else {
+                ConfigSource::Environment(provider_api_key_env(config.provider))
+            };
+            sources.set("api_key", source);
+        }
+
+        Ok((config, sources))
+    }
+
     /// Save configuration to file (without API key)
     pub async fn save(&self, path: Option<&str>) -> Result<()> {
         let config_path = path
@@ -384,3 +1174,87 @@ impl Config {
         self.api_key = provider_default_api_key(provider);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_logging_config() {
+        let config: LoggingConfig =
+            toml::from_str("json = true\nrotation = \"session\"\nlevel = \"debug\"").unwrap();
+        assert!(config.json);
+        assert_eq!(config.rotation, LogRotation::Session);
+        assert_eq!(config.level_filter().unwrap(), LevelFilter::Debug);
+        assert_eq!(config.max_files, 14);
+        assert!(LoggingConfig::default().is_default());
+
+        let bad = LoggingConfig {
+            level: "loud".to_string(),
+            ..LoggingConfig::default()
+        };
+        assert!(bad.level_filter().is_err());
+    }
+
+    #[test]
+    fn settings_round_trip_through_get_and_set() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.set_setting("model", "my-model").unwrap(),
+            "default_model"
+        );
+        assert_eq!(config.get_setting("default_model").unwrap(), "my-model");
+
+        config.set_setting("auto-accept", "on").unwrap();
+        assert!(config.auto_accept_edits);
+        config.set_setting("budget", "5000").unwrap();
+        assert_eq!(config.token_budget, Some(5000));
+        config.set_setting("budget", "none").unwrap();
+        assert_eq!(config.get_setting("budget").unwrap(), "none");
+
+        assert_eq!(config.get_setting("width").unwrap(), "auto");
+        config.set_setting("width", "100").unwrap();
+        assert_eq!(config.output_width, Some(100));
+        config.set_setting("width", "off").unwrap();
+        assert_eq!(config.get_setting("output_width").unwrap(), "off");
+        config.set_setting("width", "auto").unwrap();
+        assert_eq!(config.output_width, None);
+        assert!(config.set_setting("width", "5").is_err());
+
+        assert!(config.set_setting("stream", "maybe").is_err());
+        assert!(config.set_setting("temperature", "3").is_err());
+        assert!(config.set_setting("theme", "dark").is_err());
+    }
+
+    #[test]
+    fn stream_defaults_on_and_is_only_saved_when_off() {
+        let mut config = Config::default();
+        assert!(!toml::to_string(&config).unwrap().contains("stream"));
+
+        config.stream = false;
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("stream = false"));
+        let loaded: Config = toml::from_str(&saved).unwrap();
+        assert!(!loaded.stream);
+    }
+
+    #[test]
+    fn the_provider_can_be_one_declared_in_the_same_file() {
+        let declared = "\n[[providers]]\nname = \"test-lm-studio\"\nbase_url = \"http://localhost:1234/v1\"\nmodels = [\"qwen3\"]\nstreaming = false\n";
+        let content = toml::to_string(&Config::default()).unwrap().replacen(
+            &format!("provider = \"{}\"", Provider::default()),
+            "provider = \"test-lm-studio\"",
+            1,
+        ) + declared;
+        let registered = register_custom_providers(&content).unwrap();
+        let config: Config = toml::from_str(&content).unwrap();
+        assert_eq!(config.provider, registered[0]);
+        assert_eq!(config.provider.name(), "test-lm-studio");
+        assert!(!config.providers[0].streaming);
+        assert!(config.providers[0].tools);
+
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("streaming = false"));
+        assert!(!saved.contains("tools ="));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::config::{
+    provider_api_key_env, provider_api_key_optional, provider_default_api_key, Config,
+};
+use crate::project_config::{
+    apply_project_config, find_project_config, ConfigSources, ProjectConfig,
+};
+use crate::security::{BashSecurity, BashSecurityManager, PermissionResult};
+use glob::Pattern;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found by `flexorama config check`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", label, self.message)
+    }
+}
+
+/// Validate the global config at `path` (or the default location) and the
+/// project config found from `project_dir`
+pub fn check_config(path: Option<&str>, project_dir: &Path) -> Vec<Finding> {
+    let global_path = path
+        .map(PathBuf::from)
+        .unwrap_or_else(Config::default_config_path);
+    let mut findings = Vec::new();
+
+    let mut config = if global_path.exists() {
+        match std::fs::read_to_string(&global_path) {
+            Ok(content) => check_global_file(&content, &global_path, &mut findings),
+            Err(e) => {
+                findings.push(Finding::error(format!(
+                    "{}: failed to read: {}",
+                    global_path.display(),
+                    e
+                )));
+                None
+            }
+        }
+    } else {
+        Some(Config::default())
+    };
+
+    let project = find_project_config(project_dir).and_then(|project_path| {
+        let parsed = std::fs::read_to_string(&project_path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                toml::from_str::<ProjectConfig>(&content).map_err(|e| e.to_string())
+            });
+        match parsed {
+            Ok(project) => Some((project, project_path)),
+            Err(e) => {
+                findings.push(Finding::error(format!(
+                    "{}: {}",
+                    project_path.display(),
+                    e.trim()
+                )));
+                None
+            }
+        }
+    });
+
+    // The rest needs a config that parsed
+    let Some(config) = config.as_mut() else {
+        return findings;
+    };
+    config.api_key = provider_default_api_key(config.provider);
+    if let Some((project, project_path)) = project {
+        let mut sources = ConfigSources::default();
+        apply_project_config(config, project, &project_path, &mut sources);
+        if let Some(untrusted) = sources.untrusted_project() {
+            findings.push(Finding::warning(format!(
+                "{}: MCP servers {} aren't started until you trust this file in an interactive session",
+                untrusted.path.display(),
+                untrusted.servers.keys().cloned().collect::<Vec<_>>().join(", ")
+            )));
+        }
+    }
+
+    check_api_key(config, &mut findings);
+    if let Some(proxy) = &config.proxy {
+        if let Err(e) = crate::http_client::parse_proxy(proxy) {
+            findings.push(Finding::error(e.to_string()));
+        }
+    }
+    if let Err(e) = crate::http_client::apply_tls(reqwest::Client::builder(), &config.tls) {
+        findings.push(Finding::error(format!("tls: {:#}", e)));
+    }
+    if config.tls.danger_accept_invalid_certs {
+        findings.push(Finding::warning(
+            "tls.danger_accept_invalid_certs is on; server certificates are not verified",
+        ));
+    }
+    if let Err(e) = config.logging.level_filter() {
+        findings.push(Finding::error(e.to_string()));
+    }
+    check_mcp_servers(config, &mut findings);
+    check_bash_rules(&config.bash_security, &mut findings);
+    findings
+}
+
+fn check_global_file(content: &str, path: &Path, findings: &mut Vec<Finding>) -> Option<Config> {
+    let raw: toml::Value = match content.parse() {
+        Ok(raw) => raw,
+        Err(e) => {
+            findings.push(Finding::error(format!(
+                "{}: {}",
+                path.display(),
+                e.to_string().trim()
+            )));
+            return None;
+        }
+    };
+    if let Err(e) = crate::config::register_custom_providers(content) {
+        findings.push(Finding::error(format!("{}: {}", path.display(), e)));
+        return None;
+    }
+    let config: Config = match toml::from_str(content) {
+        Ok(config) => config,
+        Err(e) => {
+            findings.push(Finding::error(format!(
+                "{}: {}",
+                path.display(),
+                e.to_string().trim()
+            )));
+            return None;
+        }
+    };
+
+    // Whatever doesn't survive a round trip was ignored while loading
+    match toml::Value::try_from(&config) {
+        Ok(understood) => {
+            for key in unknown_keys(&raw, &understood, "") {
+                let message = if key == "api_key" {
+                    "api_key is ignored in config files; set it in the environment or with --api-key"
+                        .to_string()
+                } else {
+                    format!("unknown key '{}' is ignored", key)
+                };
+                findings.push(Finding::warning(format!("{}: {}", path.display(), message)));
+            }
+        }
+        Err(e) => findings.push(Finding::error(format!(
+            "{}: failed to re-encode config: {}",
+            path.display(),
+            e
+        ))),
+    }
+    Some(config)
+}
+
+/// Dotted paths of keys in `raw` that `understood` doesn't have
+fn unknown_keys(raw: &toml::Value, understood: &toml::Value, prefix: &str) -> Vec<String> {
+    let (Some(raw), Some(understood)) = (raw.as_table(), understood.as_table()) else {
+        return Vec::new();
+    };
+    let mut unknown = Vec::new();
+    for (key, value) in raw {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match understood.get(key) {
+            Some(known) => unknown.extend(unknown_keys(value, known, &path)),
+            None => unknown.push(path),
+        }
+    }
+    unknown
+}
+
+fn check_api_key(config: &Config, findings: &mut Vec<Finding>) {
+    if !config.api_key.is_empty() {
+        return;
+    }
+    if let Some(reference) = &config.api_key_secret {
+        if let Err(e) = crate::secrets::resolve_secret(reference) {
+            findings.push(Finding::error(format!(
+                "api_key_secret: could not read {}: {}",
+                reference, e
+            )));
+        }
+        return;
+    }
+    if !provider_api_key_optional(config.provider) {
+        findings.push(Finding::error(format!(
+            "no API key for provider '{}': set {} or pass --api-key",
+            config.provider,
+            provider_api_key_env(config.provider)
+        )));
+    }
+}
+
+fn check_mcp_servers(config: &Config, findings: &mut Vec<Finding>) {
+    let mut names: Vec<&String> = config.mcp.servers.keys().collect();
+    names.sort();
+    for name in names {
+        let server = &config.mcp.servers[name];
+        if !server.enabled {
+            continue;
+        }
+        match (&server.command, &server.url) {
+            (Some(command), _) => {
+                if which::which(command).is_err() {
+                    findings.push(Finding::error(format!(
+                        "MCP server '{}': command '{}' was not found",
+                        name, command
+                    )));
+                }
+            }
+            (None, Some(url)) => {
+                if !(url.starts_with("http://")
+                    || url.starts_with("https://")
+                    || url.starts_with("ws://")
+                    || url.starts_with("wss://"))
+                {
+                    findings.push(Finding::error(format!(
+                        "MCP server '{}': '{}' is not an http(s) or ws(s) URL",
+                        name, url
+                    )));
+                }
+            }
+            (None, None) => findings.push(Finding::error(format!(
+                "MCP server '{}' has neither a command nor a url",
+                name
+            ))),
+        }
+    }
+}
+
+fn check_bash_rules(security: &BashSecurity, findings: &mut Vec<Finding>) {
+    for pattern in security
+        .allowed_commands
+        .iter()
+        .chain(security.denied_commands.iter())
+    {
+        if let Err(e) = Pattern::new(pattern) {
+            findings.push(Finding::warning(format!(
+                "bash pattern '{}' is not a valid glob ({}) and only matches literally",
+                pattern, e
+            )));
+        }
+    }
+
+    // Denied patterns are checked first, so an allowed pattern they cover never applies
+    let deny_only = BashSecurityManager::new(BashSecurity {
+        allowed_commands: Default::default(),
+        denied_commands: security.denied_commands.clone(),
+        ask_for_permission: true,
+        enabled: true,
+    });
+    let mut allowed: Vec<&String> = security.allowed_commands.iter().collect();
+    allowed.sort();
+    for pattern in allowed {
+        let command = pattern.trim_end_matches('*').trim();
+        if deny_only.check_command_permission(command) == PermissionResult::Denied
+            || security.denied_commands.contains(pattern)
+        {
+            findings.push(Finding::warning(format!(
+                "allowed bash pattern '{}' conflicts with a denied pattern, which takes precedence",
+                pattern
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Provider;
+    use tempfile::TempDir;
+
+    fn check(content: &str) -> Vec<Finding> {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, content).unwrap();
+        check_config(path.to_str(), dir.path())
+    }
+
+    #[test]
+    fn reports_unknown_keys_and_type_errors() {
+        let findings = check(
+            r#"
+            base_url = "http://localhost:11434"
+            default_model = "llama3"
+            max_tokens = 4096
+            temperature = 0.2
+            provider = "ollama"
+            default_modle = "typo"
+            api_key = "sk-secret"
+
+            [bash_security]
+            allowed_commands = []
+            denied_commands = []
+            ask_for_permission = true
+            enabled = true
+            colour = "red"
+
+            [mcp.servers]
+            "#,
+        );
+        let messages: Vec<String> = findings.iter().map(|f| f.message.clone()).collect();
+        assert!(messages.iter().any(|m| m.contains("'default_modle'")));
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("'bash_security.colour'")));
+        assert!(messages.iter().any(|m| m.contains("api_key is ignored")));
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+
+        let findings = check("max_tokens = \"lots\"\n");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn reports_missing_mcp_commands_and_conflicting_rules() {
+        let mut config = Config {
+            provider: Provider::OLLAMA,
+            ..Config::default()
+        };
+        config.mcp.servers.insert(
+            "ghost".to_string(),
+            toml::from_str(
+                "name = \"ghost\"\ncommand = \"flexorama-no-such-binary\"\nenabled = true\n",
+            )
+            .unwrap(),
+        );
+        config.bash_security.allowed_commands = ["git push".to_string()].into();
+        config.bash_security.denied_commands = ["git push*".to_string()].into();
+
+        let findings = check(&toml::to_string(&config).unwrap());
+        assert!(findings.iter().any(
+            |f| f.severity == Severity::Error && f.message.contains("flexorama-no-such-binary")
+        ));
+        assert!(findings
+            .iter()
+            .any(|f| f.severity == Severity::Warning && f.message.contains("'git push'")));
+    }
+}
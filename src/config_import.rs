@@ -0,0 +1,579 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, McpServerConfig, Provider};
+use crate::custom_commands::{self, CustomCommand};
+use crate::mcp_import::{merge_servers, parse_mcp_servers, McpImportSummary};
+
+/// Another coding agent whose settings `flexorama import-config` can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportTool {
+    ClaudeCode,
+    Aider,
+    Codex,
+}
+
+impl std::str::FromStr for ImportTool {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "claude-code" | "claude" => Ok(Self::ClaudeCode),
+            "aider" => Ok(Self::Aider),
+            "codex" => Ok(Self::Codex),
+            other => Err(format!(
+                "Unsupported tool '{}' (expected claude-code, aider or codex)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ImportTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClaudeCode => write!(f, "Claude Code"),
+            Self::Aider => write!(f, "Aider"),
+            Self::Codex => write!(f, "Codex"),
+        }
+    }
+}
+
+/// Everything found in the other tool's settings, before anything is written
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    pub provider: Option<Provider>,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    pub allowed_commands: Vec<String>,
+    pub denied_commands: Vec<String>,
+    pub mcp_servers: Vec<(String, McpServerConfig)>,
+    pub commands: Vec<CustomCommand>,
+    /// Instruction files to copy: (source, destination AGENTS.md)
+    pub instructions: Vec<(PathBuf, PathBuf)>,
+    /// Settings that were found but can't be carried over
+    pub notes: Vec<String>,
+}
+
+/// Read the settings of `tool` from the user's home directory and the project
+pub async fn plan_import(tool: ImportTool, home: &Path, project: &Path) -> Result<ImportPlan> {
+    let mut plan = ImportPlan::default();
+    match tool {
+        ImportTool::ClaudeCode => plan_claude_code(&mut plan, home, project).await?,
+        ImportTool::Aider => plan_aider(&mut plan, home, project).await?,
+        ImportTool::Codex => plan_codex(&mut plan, home, project).await?,
+    }
+    Ok(plan)
+}
+
+async fn read_optional(path: &Path) -> Result<Option<String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+async fn read_json(path: &Path) -> Result<Option<Value>> {
+    match read_optional(path).await? {
+        Some(content) => serde_json::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        None => Ok(None),
+    }
+}
+
+/// Add MCP servers from a `{ "mcpServers": { ... } }` value, later ones winning
+fn add_mcp_servers(plan: &mut ImportPlan, servers: &Value) -> Result<()> {
+    let file = json!({ "mcpServers": servers });
+    for (name, server) in parse_mcp_servers(&file.to_string())? {
+        plan.mcp_servers.retain(|(existing, _)| existing != &name);
+        plan.mcp_servers.push((name, server));
+    }
+    Ok(())
+}
+
+/// Load the markdown commands in `dir`, which use the same format as ours
+async fn add_commands(plan: &mut ImportPlan, dir: &Path) -> Result<()> {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return Ok(());
+    };
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("md") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    for path in paths {
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let content = tokio::fs::read_to_string(&path).await?;
+        match CustomCommand::from_markdown(name.to_string(), &content) {
+            Ok(command) => {
+                plan.commands
+                    .retain(|existing| existing.name != command.name);
+                plan.commands.push(command);
+            }
+            Err(e) => plan
+                .notes
+                .push(format!("Skipped command {}: {}", path.display(), e)),
+        }
+    }
+    Ok(())
+}
+
+fn add_instructions(plan: &mut ImportPlan, source: PathBuf, destination: PathBuf) {
+    if source.is_file() {
+        plan.instructions.push((source, destination));
+    }
+}
+
+fn home_agents_md(home: &Path) -> PathBuf {
+    home.join(".flexorama").join("AGENTS.md")
+}
+
+async fn plan_claude_code(plan: &mut ImportPlan, home: &Path, project: &Path) -> Result<()> {
+    plan.provider = Some(Provider::ANTHROPIC);
+
+    // Later files override earlier ones, as in Claude Code itself
+    let settings_files = [
+        home.join(".claude").join("settings.json"),
+        project.join(".claude").join("settings.json"),
+        project.join(".claude").join("settings.local.json"),
+    ];
+    for path in &settings_files {
+        let Some(settings) = read_json(path).await? else {
+            continue;
+        };
+        if let Some(model) = settings.get("model").and_then(Value::as_str) {
+            if model.starts_with("claude-") {
+                plan.model = Some(model.to_string());
+            } else {
+                plan.notes.push(format!(
+                    "Model alias '{}' has no exact model name; set default_model yourself",
+                    model
+                ));
+            }
+        }
+        let permissions = settings.get("permissions");
+        for (key, target) in [("allow", true), ("deny", false)] {
+            let rules = permissions
+                .and_then(|p| p.get(key))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str);
+            for rule in rules {
+                match claude_bash_rule(rule) {
+                    Some(pattern) if target => plan.allowed_commands.push(pattern),
+                    Some(pattern) => plan.denied_commands.push(pattern),
+                    None => plan
+                        .notes
+                        .push(format!("Skipped permission rule '{}'", rule)),
+                }
+            }
+        }
+    }
+
+    // User-scoped servers live at the top of ~/.claude.json, local-scoped ones
+    // under the project's path, and project-scoped ones in .mcp.json
+    if let Some(state) = read_json(&home.join(".claude.json")).await? {
+        if let Some(servers) = state.get("mcpServers") {
+            add_mcp_servers(plan, servers)?;
+        }
+        let project_key = project.to_string_lossy();
+        if let Some(servers) = state
+            .get("projects")
+            .and_then(|projects| projects.get(project_key.as_ref()))
+            .and_then(|entry| entry.get("mcpServers"))
+        {
+            add_mcp_servers(plan, servers)?;
+        }
+    }
+    if let Some(file) = read_json(&project.join(".mcp.json")).await? {
+        if let Some(servers) = file.get("mcpServers") {
+            add_mcp_servers(plan, servers)?;
+        }
+    }
+
+    add_commands(plan, &home.join(".claude").join("commands")).await?;
+    add_commands(plan, &project.join(".claude").join("commands")).await?;
+
+    add_instructions(
+        plan,
+        home.join(".claude").join("CLAUDE.md"),
+        home_agents_md(home),
+    );
+    add_instructions(plan, project.join("CLAUDE.md"), project.join("AGENTS.md"));
+    Ok(())
+}
+
+/// Convert a Claude Code `Bash(...)` permission rule to a command pattern
+fn claude_bash_rule(rule: &str) -> Option<String> {
+    let inner = rule.strip_prefix("Bash(")?.strip_suffix(')')?.trim();
+    if inner.is_empty() {
+        return None;
+    }
+    Some(match inner.strip_suffix(":*") {
+        Some(prefix) => format!("{}*", prefix),
+        None => inner.to_string(),
+    })
+}
+
+/// Split a LiteLLM-style model name such as `anthropic/claude-sonnet-4-5` or
+/// `gpt-4o` into a provider and the provider's own model name
+fn provider_for_model(model: &str) -> Option<(Provider, String)> {
+    if let Some((prefix, name)) = model.split_once('/') {
+        return prefix
+            .parse::<Provider>()
+            .ok()
+            .map(|provider| (provider, name.to_string()));
+    }
+    let provider = if model.starts_with("claude-") {
+        Provider::ANTHROPIC
+    } else if model.starts_with("gpt-")
+        || model.starts_with("o1")
+        || model.starts_with("o3")
+        || model.starts_with("o4")
+    {
+        Provider::OPENAI
+    } else if model.starts_with("gemini") {
+        Provider::GEMINI
+    } else if model.starts_with("mistral") || model.starts_with("codestral") {
+        Provider::MISTRAL
+    } else if model.starts_with("deepseek") {
+        Provider::DEEPSEEK
+    } else {
+        return None;
+    };
+    Some((provider, model.to_string()))
+}
+
+async fn plan_aider(plan: &mut ImportPlan, home: &Path, project: &Path) -> Result<()> {
+    let mut read_files = Vec::new();
+    for (dir, path) in [
+        (home, home.join(".aider.conf.yml")),
+        (project, project.join(".aider.conf.yml")),
+    ] {
+        let Some(content) = read_optional(&path).await? else {
+            continue;
+        };
+        let settings: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        if let Some(model) = settings.get("model").and_then(|m| m.as_str()) {
+            match provider_for_model(model) {
+                Some((provider, name)) => {
+                    plan.provider = Some(provider);
+                    plan.model = Some(name);
+                }
+                None => plan.notes.push(format!(
+                    "Couldn't tell which provider serves model '{}'; set it yourself",
+                    model
+                )),
+            }
+        }
+        if let Some(base) = settings.get("openai-api-base").and_then(|b| b.as_str()) {
+            plan.base_url = Some(base.to_string());
+        }
+        match settings.get("read") {
+            Some(serde_yaml::Value::String(file)) => read_files.push(dir.join(file)),
+            Some(serde_yaml::Value::Sequence(files)) => {
+                read_files.extend(files.iter().filter_map(|f| f.as_str()).map(|f| dir.join(f)))
+            }
+            _ => {}
+        }
+    }
+    if plan.base_url.is_some() && plan.provider != Some(Provider::OPENAI) {
+        plan.notes
+            .push("Ignored openai-api-base because the model isn't served by OpenAI".to_string());
+        plan.base_url = None;
+    }
+
+    // Aider's conventions files are the closest thing to AGENTS.md
+    let mut read_files = read_files.into_iter().filter(|f| f.is_file());
+    if let Some(conventions) = read_files.next() {
+        add_instructions(plan, conventions, project.join("AGENTS.md"));
+    }
+    for extra in read_files {
+        plan.notes.push(format!(
+            "Only one conventions file becomes AGENTS.md; merge {} by hand",
+            extra.display()
+        ));
+    }
+    Ok(())
+}
+
+async fn plan_codex(plan: &mut ImportPlan, home: &Path, _project: &Path) -> Result<()> {
+    let codex = home.join(".codex");
+    let path = codex.join("config.toml");
+    if let Some(content) = read_optional(&path).await? {
+        let settings: toml::Value = content
+            .parse()
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        let provider_name = settings
+            .get("model_provider")
+            .and_then(|p| p.as_str())
+            .unwrap_or("openai");
+        match provider_name.parse::<Provider>() {
+            Ok(provider) => plan.provider = Some(provider),
+            Err(_) => plan.notes.push(format!(
+                "Model provider '{}' isn't supported; using OpenAI settings",
+                provider_name
+            )),
+        }
+        plan.provider.get_or_insert(Provider::OPENAI);
+        if let Some(model) = settings.get("model").and_then(|m| m.as_str()) {
+            plan.model = Some(model.to_string());
+        }
+        if let Some(base_url) = settings
+            .get("model_providers")
+            .and_then(|providers| providers.get(provider_name))
+            .and_then(|provider| provider.get("base_url"))
+            .and_then(|url| url.as_str())
+        {
+            plan.base_url = Some(base_url.to_string());
+        }
+
+        if let Some(servers) = settings.get("mcp_servers") {
+            let servers = serde_json::to_value(servers)
+                .map_err(|e| anyhow!("Failed to read mcp_servers: {}", e))?;
+            add_mcp_servers(plan, &servers)?;
+        }
+    }
+
+    // Codex's custom prompts use the same $ARGUMENTS and $1 placeholders
+    add_commands(plan, &codex.join("prompts")).await?;
+    add_instructions(plan, codex.join("AGENTS.md"), home_agents_md(home));
+    Ok(())
+}
+
+impl ImportPlan {
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_none()
+            && self.model.is_none()
+            && self.base_url.is_none()
+            && self.allowed_commands.is_empty()
+            && self.denied_commands.is_empty()
+            && self.mcp_servers.is_empty()
+            && self.commands.is_empty()
+            && self.instructions.is_empty()
+    }
+
+    /// Merge the imported settings into `config`. Existing MCP servers with
+    /// the same name are kept.
+    pub fn apply_to_config(&self, config: &mut Config) -> McpImportSummary {
+        if let Some(provider) = self.provider {
+            if provider != config.provider {
+                config.set_provider(provider);
+            }
+        }
+        if let Some(model) = &self.model {
+            config.default_model = model.clone();
+        }
+        if let Some(base_url) = &self.base_url {
+            config.base_url = base_url.clone();
+        }
+        config
+            .bash_security
+            .allowed_commands
+            .extend(self.allowed_commands.iter().cloned());
+        config
+            .bash_security
+            .denied_commands
+            .extend(self.denied_commands.iter().cloned());
+        merge_servers(&mut config.mcp, self.mcp_servers.clone())
+    }
+
+    /// Write the commands and instruction files, never replacing existing ones.
+    /// Returns a line for each file written or skipped.
+    pub async fn write_files(&self) -> Result<Vec<String>> {
+        let mut report = Vec::new();
+        for command in &self.commands {
+            if custom_commands::load_custom_command(&command.name)
+                .await?
+                .is_some()
+            {
+                report.push(format!(
+                    "Skipped command /{} (already exists)",
+                    command.name
+                ));
+                continue;
+            }
+            custom_commands::save_custom_command(command).await?;
+            report.push(format!("Added command /{}", command.name));
+        }
+
+        for (source, destination) in &self.instructions {
+            if destination.exists() {
+                report.push(format!(
+                    "Skipped {} ({} already exists)",
+                    source.display(),
+                    destination.display()
+                ));
+                continue;
+            }
+            if let Some(parent) = destination.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::copy(source, destination)
+                .await
+                .with_context(|| format!("Failed to copy {}", source.display()))?;
+            report.push(format!(
+                "Copied {} to {}",
+                source.display(),
+                destination.display()
+            ));
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn imports_claude_code_settings() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        write(
+            &home.path().join(".claude/settings.json"),
+            r#"{
+                "model": "claude-sonnet-4-5",
+                "permissions": {
+                    "allow": ["Bash(npm run test:*)", "Bash(git status)", "Read(~/notes)"],
+                    "deny": ["Bash(rm -rf:*)"]
+                }
+            }"#,
+        );
+        write(
+            &home.path().join(".claude.json"),
+            &format!(
+                r#"{{
+                    "mcpServers": {{ "docs": {{ "command": "docs-mcp" }} }},
+                    "projects": {{ "{}": {{ "mcpServers": {{ "db": {{ "command": "db-mcp" }} }} }} }}
+                }}"#,
+                project.path().display()
+            ),
+        );
+        write(
+            &project.path().join(".mcp.json"),
+            r#"{ "mcpServers": { "docs": { "type": "http", "url": "https://docs.example/mcp" } } }"#,
+        );
+        write(
+            &project.path().join(".claude/commands/review.md"),
+            "---\ndescription: Review a file\nargument-hint: <file>\n---\n\nReview $1",
+        );
+        write(&project.path().join("CLAUDE.md"), "Use tabs.");
+
+        let plan = plan_import(ImportTool::ClaudeCode, home.path(), project.path())
+            .await
+            .unwrap();
+
+        assert_eq!(plan.provider, Some(Provider::ANTHROPIC));
+        assert_eq!(plan.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(plan.allowed_commands, vec!["npm run test*", "git status"]);
+        assert_eq!(plan.denied_commands, vec!["rm -rf*"]);
+        assert!(plan.notes.iter().any(|n| n.contains("Read(~/notes)")));
+
+        let names: Vec<&str> = plan.mcp_servers.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["db", "docs"]);
+        // The project's .mcp.json overrides the user-scoped server
+        assert!(plan.mcp_servers[1].1.url.is_some());
+
+        assert_eq!(plan.commands.len(), 1);
+        assert_eq!(
+            plan.commands[0].description.as_deref(),
+            Some("Review a file")
+        );
+        assert_eq!(
+            plan.instructions,
+            vec![(
+                project.path().join("CLAUDE.md"),
+                project.path().join("AGENTS.md")
+            )]
+        );
+
+        let mut config = Config::default();
+        let summary = plan.apply_to_config(&mut config);
+        assert_eq!(config.default_model, "claude-sonnet-4-5");
+        assert!(config.bash_security.denied_commands.contains("rm -rf*"));
+        assert_eq!(summary.added.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn imports_aider_and_codex_models() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        write(
+            &project.path().join(".aider.conf.yml"),
+            "model: ollama/qwen2.5-coder\nread: CONVENTIONS.md\n",
+        );
+        write(&project.path().join("CONVENTIONS.md"), "Be brief.");
+
+        let plan = plan_import(ImportTool::Aider, home.path(), project.path())
+            .await
+            .unwrap();
+        assert_eq!(plan.provider, Some(Provider::OLLAMA));
+        assert_eq!(plan.model.as_deref(), Some("qwen2.5-coder"));
+        assert_eq!(plan.instructions.len(), 1);
+
+        write(
+            &home.path().join(".codex/config.toml"),
+            r#"
+            model = "o3"
+
+            [mcp_servers.search]
+            command = "search-mcp"
+            args = ["--fast"]
+            "#,
+        );
+        write(&home.path().join(".codex/prompts/fix.md"), "Fix $ARGUMENTS");
+
+        let plan = plan_import(ImportTool::Codex, home.path(), project.path())
+            .await
+            .unwrap();
+        assert_eq!(plan.provider, Some(Provider::OPENAI));
+        assert_eq!(plan.model.as_deref(), Some("o3"));
+        assert_eq!(plan.mcp_servers[0].1.args, Some(vec!["--fast".to_string()]));
+        assert_eq!(plan.commands[0].name, "fix");
+    }
+
+    #[test]
+    fn guesses_providers_from_model_names() {
+        assert_eq!(
+            provider_for_model("anthropic/claude-3-5-sonnet"),
+            Some((Provider::ANTHROPIC, "claude-3-5-sonnet".to_string()))
+        );
+        assert_eq!(
+            provider_for_model("gpt-4o"),
+            Some((Provider::OPENAI, "gpt-4o".to_string()))
+        );
+        assert_eq!(provider_for_model("sonnet"), None);
+        assert_eq!(
+            provider_for_model("openrouter/anthropic/claude-3.5-sonnet"),
+            Some((
+                Provider::OPENROUTER,
+                "anthropic/claude-3.5-sonnet".to_string()
+            ))
+        );
+        assert_eq!(provider_for_model("bedrock/x"), None);
+        assert_eq!(
+            provider_for_model("deepseek-reasoner"),
+            Some((Provider::DEEPSEEK, "deepseek-reasoner".to_string()))
+        );
+    }
+}
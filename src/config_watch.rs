@@ -0,0 +1,332 @@
+use crate::config::Config;
+use crate::project_config::{find_project_config, ConfigSources};
+use log::{debug, warn};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+
+/// How often the config files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One difference between the running config and the reloaded one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub description: String,
+    /// False if the setting only takes effect after a restart
+    pub applied: bool,
+}
+
+/// A changed config, ready to be applied to a running session
+#[derive(Debug, Clone)]
+pub struct ConfigReload {
+    pub previous: Config,
+    pub config: Config,
+    pub sources: ConfigSources,
+    pub changes: Vec<ConfigChange>,
+}
+
+/// Settings that are read once at startup
+const RESTART_ONLY: &[&str] = &[
+    "provider",
+    "base_url",
+    "default_system_prompt",
+    "skills",
+    "web",
+    "webhooks",
+    "commands_dir",
+    "stream",
+    "proxy",
+    "tls",
+];
+
+/// Describe what changed between two configs
+pub fn describe_changes(old: &Config, new: &Config) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    let mut applied = |description: String| {
+        changes.push(ConfigChange {
+            description,
+            applied: true,
+        })
+    };
+
+    if old.default_model != new.default_model {
+        applied(format!(
+            "default model: {} → {}",
+            old.default_model, new.default_model
+        ));
+    }
+    if old.temperature != new.temperature {
+        applied(format!(
+            "temperature: {} → {}",
+            old.temperature, new.temperature
+        ));
+    }
+    for (label, old_set, new_set) in [
+        (
+            "allowed bash commands",
+            &old.bash_security.allowed_commands,
+            &new.bash_security.allowed_commands,
+        ),
+        (
+            "denied bash commands",
+            &old.bash_security.denied_commands,
+            &new.bash_security.denied_commands,
+        ),
+    ] {
+        if let Some(diff) = describe_set_diff(old_set.iter(), new_set.iter()) {
+            applied(format!("{}: {}", label, diff));
+        }
+    }
+    if old.bash_security.ask_for_permission != new.bash_security.ask_for_permission {
+        applied(format!(
+            "ask before unknown bash commands: {}",
+            new.bash_security.ask_for_permission
+        ));
+    }
+    if old.bash_security.enabled != new.bash_security.enabled {
+        applied(format!("bash security: {}", new.bash_security.enabled));
+    }
+    if let Some(diff) = describe_set_diff(old.denied_tools.iter(), new.denied_tools.iter()) {
+        applied(format!("denied tools: {}", diff));
+    }
+    if old.auto_accept_edits != new.auto_accept_edits {
+        applied(format!("auto-accept edits: {}", new.auto_accept_edits));
+    }
+    if old.token_budget != new.token_budget {
+        applied(format!(
+            "token budget: {}",
+            new.token_budget
+                .map(|budget| budget.to_string())
+                .unwrap_or_else(|| "none".to_string())
+        ));
+    }
+    if old.output_width != new.output_width {
+        applied(format!(
+            "output width: {}",
+            crate::config::describe_output_width(new.output_width)
+        ));
+    }
+
+    let names: BTreeSet<&String> = old
+        .mcp
+        .servers
+        .keys()
+        .chain(new.mcp.servers.keys())
+        .collect();
+    for name in names {
+        let before = old.mcp.servers.get(name).map(serde_json::to_value);
+        let after = new.mcp.servers.get(name).map(serde_json::to_value);
+        let description = match (before, after) {
+            (None, Some(_)) => format!("MCP server '{}' added", name),
+            (Some(_), None) => format!("MCP server '{}' removed", name),
+            (Some(Ok(before)), Some(Ok(after))) if before == after => continue,
+            _ => format!("MCP server '{}' updated", name),
+        };
+        applied(description);
+    }
+
+    let old_value = toml::Value::try_from(old).ok();
+    let new_value = toml::Value::try_from(new).ok();
+    for key in RESTART_ONLY {
+        let before = old_value.as_ref().and_then(|v| v.get(*key));
+        let after = new_value.as_ref().and_then(|v| v.get(*key));
+        if before != after {
+            changes.push(ConfigChange {
+                description: format!("{} changed; restart to apply", key),
+                applied: false,
+            });
+        }
+    }
+
+    changes
+}
+
+/// "+added -removed", or None if the sets are equal
+fn describe_set_diff<'a>(
+    old: impl Iterator<Item = &'a String>,
+    new: impl Iterator<Item = &'a String>,
+) -> Option<String> {
+    let old: BTreeSet<&String> = old.collect();
+    let new: BTreeSet<&String> = new.collect();
+    let parts: Vec<String> = new
+        .difference(&old)
+        .map(|item| format!("+{}", item))
+        .chain(old.difference(&new).map(|item| format!("-{}", item)))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Apply the difference between two sets of patterns to `current`, keeping
+/// anything added during the session
+pub fn apply_set_diff(current: &mut HashSet<String>, old: &HashSet<String>, new: &HashSet<String>) {
+    for removed in old.difference(new) {
+        current.remove(removed);
+    }
+    for added in new.difference(old) {
+        current.insert(added.clone());
+    }
+}
+
+/// Watch the global config and the project config above `project_dir`,
+/// sending a reload whenever either changes in a way that matters. Stops when
+/// the receiver is dropped.
+pub fn spawn_config_watcher(
+    path: Option<String>,
+    project_dir: PathBuf,
+    initial: Config,
+) -> mpsc::UnboundedReceiver<ConfigReload> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let global_path = path
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(Config::default_config_path);
+
+    tokio::spawn(async move {
+        let mut current = initial;
+        let mut last_seen = fingerprint(&global_path, &project_dir);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if tx.is_closed() {
+                break;
+            }
+            let seen = fingerprint(&global_path, &project_dir);
+            if seen == last_seen {
+                continue;
+            }
+            last_seen = seen;
+
+            let (config, sources) = match Config::load_layered(path.as_deref(), &project_dir).await
+            {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    warn!("Ignoring config change that failed to load: {:#}", e);
+                    continue;
+                }
+            };
+            let changes = describe_changes(&current, &config);
+            if changes.is_empty() {
+                debug!("Config files changed without affecting any settings");
+                continue;
+            }
+            let reload = ConfigReload {
+                previous: std::mem::replace(&mut current, config.clone()),
+                config,
+                sources,
+                changes,
+            };
+            if tx.send(reload).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+/// The config files that currently apply and when they were last modified
+fn fingerprint(global_path: &Path, project_dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    std::iter::once(global_path.to_path_buf())
+        .chain(find_project_config(project_dir))
+        .map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::McpServerConfig;
+
+    #[test]
+    fn describes_applied_and_restart_only_changes() {
+        let old = Config::default();
+        let mut new = Config {
+            default_model: "other-model".to_string(),
+            base_url: "http://example.invalid".to_string(),
+            denied_tools: vec!["bash".to_string()],
+            ..Config::default()
+        };
+        new.bash_security
+            .denied_commands
+            .insert("git push*".to_string());
+        let server: McpServerConfig =
+            toml::from_str("name = \"docs\"\ncommand = \"docs-server\"\nenabled = true\n").unwrap();
+        new.mcp.servers.insert("docs".to_string(), server);
+
+        let changes = describe_changes(&old, &new);
+        let applied: Vec<&str> = changes
+            .iter()
+            .filter(|c| c.applied)
+            .map(|c| c.description.as_str())
+            .collect();
+        assert_eq!(
+            applied,
+            vec![
+                format!("default model: {} → other-model", old.default_model).as_str(),
+                "denied bash commands: +git push*",
+                "denied tools: +bash",
+                "MCP server 'docs' added",
+            ]
+        );
+        assert!(changes
+            .iter()
+            .any(|c| !c.applied && c.description == "base_url changed; restart to apply"));
+
+        assert!(describe_changes(&new, &new.clone()).is_empty());
+    }
+
+    #[test]
+    fn set_diff_keeps_session_additions() {
+        let old: HashSet<String> = ["ls".to_string(), "cat".to_string()].into();
+        let new: HashSet<String> = ["ls".to_string(), "git status".to_string()].into();
+        let mut current: HashSet<String> =
+            ["ls".to_string(), "cat".to_string(), "make".to_string()].into();
+
+        apply_set_diff(&mut current, &old, &new);
+        let expected: HashSet<String> = [
+            "ls".to_string(),
+            "git status".to_string(),
+            "make".to_string(),
+        ]
+        .into();
+        assert_eq!(current, expected);
+    }
+
+    #[tokio::test]
+    async fn watcher_sends_reload_when_the_file_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        let path = dir.path().join("config.toml");
+        let initial = Config::default();
+        std::fs::write(&path, toml::to_string(&initial).unwrap()).unwrap();
+
+        let mut rx = spawn_config_watcher(
+            Some(path.to_string_lossy().to_string()),
+            dir.path().to_path_buf(),
+            initial.clone(),
+        );
+        // Let the watcher record the initial modification time
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let updated = Config {
+            temperature: 0.1,
+            ..initial
+        };
+        std::fs::write(&path, toml::to_string(&updated).unwrap()).unwrap();
+        let file = std::fs::File::options().append(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+
+        let reload = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reload.config.temperature, 0.1);
+        assert_eq!(reload.changes.len(), 1);
+        assert!(reload.changes[0].description.starts_with("temperature:"));
+    }
+}
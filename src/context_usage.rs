@@ -0,0 +1,483 @@
+use std::path::Path;
+
+use crate::anthropic::{ContentBlock, Message};
+use crate::tools::Tool;
+
+/// Images are billed by size; this is about what a screenshot costs, for
+/// ones whose size can't be read
+const IMAGE_TOKENS: usize = 1_600;
+
+/// Assumed for models missing from [`CONTEXT_WINDOWS`]
+pub const DEFAULT_CONTEXT_WINDOW: usize = 128_000;
+
+/// Context window sizes in tokens, matched by model name prefix (most
+/// specific first)
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("claude", 200_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-5", 400_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("gemini", 1_048_576),
+    ("mistral-large", 128_000),
+    ("codestral", 256_000),
+    ("glm-4.5", 128_000),
+    ("glm-4.6", 200_000),
+    ("deepseek", 128_000),
+];
+
+/// The context window of `model`. Provider prefixes such as
+/// `anthropic/claude-sonnet-4` are ignored.
+pub fn context_window(model: &str) -> usize {
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, window)| *window)
+        .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+}
+
+/// A rough token count: about four characters per token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+fn block_tokens(block: &ContentBlock) -> usize {
+    if let Some(source) = &block.source {
+        return crate::image::estimate_tokens(&source.media_type, &source.data)
+            .map_or(IMAGE_TOKENS, |tokens| tokens as usize);
+    }
+    let mut tokens = 0;
+    for text in [&block.text, &block.name, &block.content]
+        .into_iter()
+        .flatten()
+    {
+        tokens += estimate_tokens(text);
+    }
+    if let Some(input) = &block.input {
+        tokens += estimate_tokens(&input.to_string());
+    }
+    tokens
+}
+
+fn message_tokens(message: &Message) -> usize {
+    message.content.iter().map(block_tokens).sum()
+}
+
+/// What a part of the context is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextItemKind {
+    SystemPrompt,
+    AgentsMd,
+    ContextFile,
+    Image,
+    ToolSchemas,
+    History,
+}
+
+impl ContextItemKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ContextItemKind::SystemPrompt => "system prompt",
+            ContextItemKind::AgentsMd => "AGENTS.md",
+            ContextItemKind::ContextFile => "context file",
+            ContextItemKind::Image => "image",
+            ContextItemKind::ToolSchemas => "tool schemas",
+            ContextItemKind::History => "history",
+        }
+    }
+}
+
+/// One contributor to what's sent to the model with every request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextItem {
+    pub kind: ContextItemKind,
+    pub label: String,
+    pub tokens: usize,
+    /// The conversation message holding this item, for items that can be
+    /// removed on their own
+    pub message_index: Option<usize>,
+}
+
+/// A context message added with `@file`, `/add` or AGENTS.md, rather than
+/// something said in the conversation
+fn context_message(message: &Message) -> Option<(ContextItemKind, String)> {
+    if message.role != "user" {
+        return None;
+    }
+    let text = match message.content.as_slice() {
+        [block] => block.text.as_deref()?,
+        [image, caption] if image.source.is_some() => {
+            let path = caption.text.as_deref()?.strip_prefix("Image file: ")?;
+            return Some((ContextItemKind::Image, path.to_string()));
+        }
+        _ => return None,
+    };
+    let header = text.strip_prefix("Context from ")?.split(":\n").next()?;
+    match header
+        .strip_prefix("file '")
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        Some(path)
+            if Path::new(path)
+                .file_name()
+                .is_some_and(|name| name == "AGENTS.md") =>
+        {
+            Some((ContextItemKind::AgentsMd, path.to_string()))
+        }
+        Some(path) => Some((ContextItemKind::ContextFile, path.to_string())),
+        None => Some((ContextItemKind::ContextFile, header.to_string())),
+    }
+}
+
+/// Whether the message at `index` is a context file or image that can be
+/// dropped without breaking the conversation
+pub fn is_removable(messages: &[Message], index: usize) -> bool {
+    messages.get(index).and_then(context_message).is_some()
+}
+
+/// How a model's context window is being spent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextBreakdown {
+    pub model: String,
+    pub window: usize,
+    pub items: Vec<ContextItem>,
+}
+
+impl ContextBreakdown {
+    pub fn build(
+        model: &str,
+        system_prompt: Option<&str>,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Self {
+        let mut items = Vec::new();
+        if let Some(prompt) = system_prompt.filter(|prompt| !prompt.is_empty()) {
+            items.push(ContextItem {
+                kind: ContextItemKind::SystemPrompt,
+                label: "System prompt".to_string(),
+                tokens: estimate_tokens(prompt),
+                message_index: None,
+            });
+        }
+
+        let mut history_tokens = 0;
+        let mut history_messages = 0;
+        for (index, message) in messages.iter().enumerate() {
+            match context_message(message) {
+                Some((kind, label)) => items.push(ContextItem {
+                    kind,
+                    label,
+                    tokens: message_tokens(message),
+                    message_index: Some(index),
+                }),
+                None => {
+                    history_tokens += message_tokens(message);
+                    history_messages += 1;
+                }
+            }
+        }
+
+        if !tools.is_empty() {
+            let tokens = tools
+                .iter()
+                .map(|tool| {
+                    estimate_tokens(&tool.name)
+                        + estimate_tokens(&tool.description)
+                        + estimate_tokens(&tool.input_schema.to_string())
+                })
+                .sum();
+            items.push(ContextItem {
+                kind: ContextItemKind::ToolSchemas,
+                label: format!("Tool schemas ({} tools)", tools.len()),
+                tokens,
+                message_index: None,
+            });
+        }
+
+        items.push(ContextItem {
+            kind: ContextItemKind::History,
+            label: format!("Conversation history ({} messages)", history_messages),
+            tokens: history_tokens,
+            message_index: None,
+        });
+
+        Self {
+            model: model.to_string(),
+            window: context_window(model),
+            items,
+        }
+    }
+
+    pub fn total_tokens(&self) -> usize {
+        self.items.iter().map(|item| item.tokens).sum()
+    }
+
+    /// The share of the window `tokens` takes, as a percentage
+    pub fn percent_of_window(&self, tokens: usize) -> f64 {
+        if self.window == 0 {
+            return 0.0;
+        }
+        tokens as f64 * 100.0 / self.window as f64
+    }
+}
+
+/// A `width`-character bar filled to `percent`
+pub fn bar(percent: f64, width: usize) -> String {
+    let filled = ((percent.clamp(0.0, 100.0) / 100.0) * width as f64).round() as usize;
+    // Anything at all gets at least a sliver, so small items stay visible
+    let filled = if percent > 0.0 {
+        filled.max(1).min(width)
+    } else {
+        0
+    };
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// `1234` as `1.2k`, `200000` as `200k`
+pub fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=99_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        100_000..=999_999 => format!("{}k", tokens / 1_000),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// What providers say when a request doesn't fit the model's context window
+const OVERFLOW_PHRASES: &[&str] = &[
+    "context_length_exceeded",
+    "context length",
+    "context window",
+    "prompt is too long",
+    "input is too long",
+    "maximum context",
+    "too many tokens",
+    "exceeds the maximum number of tokens",
+    "input token count",
+];
+
+/// Whether `error` is a provider rejecting a request for not fitting the
+/// model's context window
+pub fn is_context_overflow(error: &str) -> bool {
+    let error = error.to_lowercase();
+    OVERFLOW_PHRASES.iter().any(|phrase| error.contains(phrase))
+}
+
+/// Put in place of tool results dropped by [`reduce_context`]
+const ELIDED_TOOL_RESULT: &str = "[Tool result removed to fit the context window]";
+
+/// Make `messages` smaller after a context overflow: first by dropping the
+/// contents of tool results, except the latest ones the model is working
+/// from, then, if there were none, by dropping the older half of the
+/// conversation. Context files and images are kept. Returns whether anything
+/// was dropped.
+pub fn reduce_context(messages: &mut Vec<Message>) -> bool {
+    let mut elided = false;
+    let last = messages.len().saturating_sub(1);
+    for message in &mut messages[..last] {
+        for block in &mut message.content {
+            if block.block_type != "tool_result" {
+                continue;
+            }
+            if let Some(content) = &mut block.content {
+                if content.len() > ELIDED_TOOL_RESULT.len() {
+                    *content = ELIDED_TOOL_RESULT.to_string();
+                    elided = true;
+                }
+            }
+        }
+    }
+    if elided {
+        return true;
+    }
+
+    // Cut where the user asked something, so no tool result is left
+    // without the call it answers
+    let starts_exchange = |message: &Message| {
+        message.role == "user"
+            && context_message(message).is_none()
+            && !message
+                .content
+                .iter()
+                .any(|block| block.block_type == "tool_result")
+    };
+    let Some(cut) = (messages.len() / 2..messages.len())
+        .find(|&index| starts_exchange(&messages[index]))
+        .or_else(|| {
+            (1..messages.len().min(messages.len() / 2 + 1))
+                .rev()
+                .find(|&index| starts_exchange(&messages[index]))
+        })
+    else {
+        return false;
+    };
+    let dropped = messages[..cut]
+        .iter()
+        .filter(|message| context_message(message).is_none())
+        .count();
+    if dropped == 0 {
+        return false;
+    }
+    let mut index = 0;
+    messages.retain(|message| {
+        index += 1;
+        index > cut || context_message(message).is_some()
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::context_block;
+
+    fn user(text: &str) -> Message {
+        Message {
+            role: "user".to_string(),
+            content: vec![ContentBlock::text(text.to_string())],
+        }
+    }
+
+    #[test]
+    fn looks_up_context_windows() {
+        assert_eq!(context_window("claude-sonnet-4-5"), 200_000);
+        assert_eq!(context_window("anthropic/claude-opus-4"), 200_000);
+        assert_eq!(context_window("gpt-4.1-mini"), 1_047_576);
+        assert_eq!(context_window("llama3"), DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn breaks_the_context_down_by_contributor() {
+        let messages = vec![
+            user(&context_block(
+                "file '/home/me/.flexorama/AGENTS.md'",
+                "Be brief",
+            )),
+            user(&context_block("file '/work/src/main.rs'", &"x".repeat(400))),
+            user(&context_block("standard input", "diff")),
+            user("What does main do?"),
+            Message {
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::text("It starts the app.".to_string())],
+            },
+        ];
+        let breakdown =
+            ContextBreakdown::build("claude-sonnet-4", Some("You are helpful"), &messages, &[]);
+
+        let summary: Vec<(ContextItemKind, &str, Option<usize>)> = breakdown
+            .items
+            .iter()
+            .map(|item| (item.kind, item.label.as_str(), item.message_index))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ContextItemKind::SystemPrompt, "System prompt", None),
+                (
+                    ContextItemKind::AgentsMd,
+                    "/home/me/.flexorama/AGENTS.md",
+                    Some(0)
+                ),
+                (ContextItemKind::ContextFile, "/work/src/main.rs", Some(1)),
+                (ContextItemKind::ContextFile, "standard input", Some(2)),
+                (
+                    ContextItemKind::History,
+                    "Conversation history (2 messages)",
+                    None
+                ),
+            ]
+        );
+        assert!(breakdown.items[2].tokens > 100);
+        assert!(is_removable(&messages, 1));
+        assert!(!is_removable(&messages, 3));
+        assert!(!is_removable(&messages, 9));
+    }
+
+    #[test]
+    fn formats_bars_and_token_counts() {
+        assert_eq!(bar(50.0, 10), "█████░░░░░");
+        assert_eq!(bar(0.1, 10), "█░░░░░░░░░");
+        assert_eq!(bar(0.0, 4), "░░░░");
+        assert_eq!(bar(150.0, 4), "████");
+        assert_eq!(format_tokens(950), "950");
+        assert_eq!(format_tokens(1_234), "1.2k");
+        assert_eq!(format_tokens(200_000), "200k");
+        assert_eq!(format_tokens(1_047_576), "1.0M");
+    }
+
+    fn tool_exchange(id: &str, result: &str) -> Vec<Message> {
+        vec![
+            Message {
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::tool_use(
+                    id.to_string(),
+                    "read_file".to_string(),
+                    serde_json::json!({}),
+                )],
+            },
+            Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::tool_result(
+                    id.to_string(),
+                    result.to_string(),
+                    None,
+                )],
+            },
+        ]
+    }
+
+    #[test]
+    fn recognizes_context_overflow_errors() {
+        assert!(is_context_overflow(
+            "Anthropic API error 400: prompt is too long: 210000 tokens > 200000 maximum"
+        ));
+        assert!(is_context_overflow(
+            r#"OpenAI API error: {"error":{"code":"context_length_exceeded"}}"#
+        ));
+        assert!(!is_context_overflow("API error 429: rate limited"));
+    }
+
+    #[test]
+    fn drops_old_tool_results_before_messages() {
+        let mut messages = vec![user("Read both files")];
+        messages.extend(tool_exchange("a", &"a".repeat(1000)));
+        messages.extend(tool_exchange("b", &"b".repeat(1000)));
+
+        assert!(reduce_context(&mut messages));
+        assert_eq!(messages.len(), 5);
+        assert_eq!(
+            messages[2].content[0].content.as_deref(),
+            Some(ELIDED_TOOL_RESULT)
+        );
+        // The latest result is what the model is working from
+        assert_eq!(
+            messages[4].content[0].content.as_deref().unwrap().len(),
+            1000
+        );
+    }
+
+    #[test]
+    fn then_drops_the_older_half_of_the_conversation() {
+        let mut messages = vec![user(&context_block("file 'src/main.rs'", "fn main() {}"))];
+        for question in ["one", "two", "three"] {
+            messages.push(user(question));
+            messages.extend(tool_exchange(question, "ok"));
+            messages.push(Message {
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::text("done".to_string())],
+            });
+        }
+
+        assert!(reduce_context(&mut messages));
+        assert!(is_removable(&messages, 0));
+        assert_eq!(messages[1].content[0].text.as_deref(), Some("three"));
+        assert_eq!(messages.len(), 5);
+
+        let mut single = vec![user("only")];
+        assert!(!reduce_context(&mut single));
+    }
+}
@@ -1,5 +1,7 @@
 use crate::anthropic::ContentBlock;
-use crate::database::{DatabaseManager, Message as StoredMessage};
+use crate::conversation_env::ConversationEnv;
+use crate::conversation_lock::ConversationLock;
+use crate::database::{DatabaseManager, Message as StoredMessage, ToolCallRecord};
 use anyhow::Result;
 use colored::Colorize;
 use log::{debug, info};
@@ -17,6 +19,13 @@ pub struct ConversationManager {
     pub database_manager: Option<Arc<DatabaseManager>>,
     pub model: String,
     pub subagent: Option<String>,
+    /// Variables set with `/env`, dropped when another conversation starts
+    pub env: ConversationEnv,
+    /// Keeps other processes from writing to the current conversation
+    pub lock: Option<ConversationLock>,
+    /// Set when the conversation was opened while another process had it;
+    /// nothing can be added to it
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -39,11 +48,29 @@ impl ConversationManager {
             database_manager,
             model,
             subagent: None,
+            env: ConversationEnv::default(),
+            lock: None,
+            read_only: false,
         }
     }
 
+    /// Lock `conversation_id` for writing in place of the current
+    /// conversation. Without a database there's nothing to protect.
+    pub fn lock_conversation(&mut self, conversation_id: &str) -> Result<()> {
+        self.lock = match &self.database_manager {
+            Some(database_manager) => Some(crate::conversation_lock::acquire(
+                database_manager.db_path(),
+                conversation_id,
+            )?),
+            None => None,
+        };
+        self.read_only = false;
+        Ok(())
+    }
+
     /// Start a new conversation
     pub async fn start_new_conversation(&mut self) -> Result<String> {
+        self.env.clear();
         if let Some(database_manager) = &self.database_manager {
             // Create new conversation in database
             let conversation_id = database_manager
@@ -55,6 +82,7 @@ impl ConversationManager {
                 .await?;
 
             // Update current conversation tracking
+            self.lock_conversation(&conversation_id)?;
             self.current_conversation_id = Some(conversation_id.clone());
 
             info!("Started new conversation: {}", conversation_id);
@@ -63,6 +91,7 @@ impl ConversationManager {
             // Fallback: just generate a conversation ID without database
             let conversation_id = Uuid::new_v4().to_string();
             self.current_conversation_id = Some(conversation_id.clone());
+            self.read_only = false;
             Ok(conversation_id)
         }
     }
@@ -73,12 +102,31 @@ impl ConversationManager {
         role: &str,
         content: &str,
         tokens: i32,
+    ) -> Result<()> {
+        self.save_message_with_tool_calls(role, content, tokens, &[])
+            .await
+    }
+
+    /// Save a message and the tool calls it made in one write
+    pub async fn save_message_with_tool_calls(
+        &mut self,
+        role: &str,
+        content: &str,
+        tokens: i32,
+        tool_calls: &[ToolCallRecord],
     ) -> Result<()> {
         if let (Some(database_manager), Some(conversation_id)) =
             (&self.database_manager, &self.current_conversation_id)
         {
             database_manager
-                .add_message(conversation_id, role, content, &self.model, tokens)
+                .add_message_with_tool_calls(
+                    conversation_id,
+                    role,
+                    content,
+                    &self.model,
+                    tokens,
+                    tool_calls,
+                )
                 .await?;
         }
         Ok(())
@@ -107,6 +155,11 @@ impl ConversationManager {
             database_manager
                 .update_usage_stats(input_tokens, output_tokens)
                 .await?;
+            if let Some(conversation_id) = &self.current_conversation_id {
+                database_manager
+                    .add_conversation_usage(conversation_id, input_tokens, output_tokens)
+                    .await?;
+            }
         }
         Ok(())
     }
@@ -187,26 +240,45 @@ impl ConversationManager {
         use shellexpand;
         use tokio::fs;
 
+        let (file_path, symbol) = match crate::symbol_context::split_mention(file_path) {
+            Some((path, symbol)) => (path, Some(symbol)),
+            None => (file_path, None),
+        };
         let expanded_path = shellexpand::tilde(file_path);
         let absolute_path = Path::new(&*expanded_path).absolutize()?;
         let absolute_path_buf = absolute_path.to_path_buf();
 
-        if crate::image::is_image_file(&absolute_path_buf) {
+        if let Some(symbol) = symbol {
+            let content = fs::read_to_string(&absolute_path).await.map_err(|e| {
+                anyhow::anyhow!("Failed to read file '{}': {}", absolute_path.display(), e)
+            })?;
+            let Some(excerpt) =
+                crate::symbol_context::find_symbol(&absolute_path_buf, &content, symbol)
+            else {
+                anyhow::bail!(
+                    "Symbol '{}' not found in '{}'",
+                    symbol,
+                    absolute_path.display()
+                );
+            };
+            self.add_context_text(
+                &format!("file '{}#{}'", absolute_path.display(), symbol),
+                &excerpt.render(),
+            );
+            debug!(
+                "Added symbol context: {}#{} (lines {}-{})",
+                absolute_path.display(),
+                symbol,
+                excerpt.start_line,
+                excerpt.end_line
+            );
+            Ok(())
+        } else if crate::image::is_image_file(&absolute_path_buf) {
             self.add_image_context(&absolute_path_buf).await
         } else {
             match fs::read_to_string(&absolute_path).await {
                 Ok(content) => {
-                    let context_message = format!(
-                        "Context from file '{}':\n\n```\n{}\n```",
-                        absolute_path.display(),
-                        content
-                    );
-
-                    self.conversation.push(crate::anthropic::Message {
-                        role: "user".to_string(),
-                        content: vec![crate::anthropic::ContentBlock::text(context_message)],
-                    });
-
+                    self.add_context_text(&format!("file '{}'", absolute_path.display()), &content);
                     debug!("Added context file: {}", absolute_path.display());
                     Ok(())
                 }
@@ -217,13 +289,35 @@ impl ConversationManager {
         }
     }
 
+    /// Add text as a labeled context block, e.g. "file 'src/main.rs'"
+    pub fn add_context_text(&mut self, label: &str, content: &str) {
+        let context_message = context_block(label, content);
+        self.conversation.push(crate::anthropic::Message {
+            role: "user".to_string(),
+            content: vec![crate::anthropic::ContentBlock::text(context_message)],
+        });
+    }
+
+    /// Drop the context file or image at `index` in the conversation
+    pub fn remove_context_message(&mut self, index: usize) -> Result<()> {
+        if !crate::context_usage::is_removable(&self.conversation, index) {
+            anyhow::bail!("Message {} isn't a context file or image", index + 1);
+        }
+        self.conversation.remove(index);
+        Ok(())
+    }
+
     /// Add an image file as context to the conversation
     async fn add_image_context(&mut self, path: &Path) -> Result<()> {
-        let (media_type, base64_data) = crate::image::load_image_as_base64(path)?;
+        let image = crate::image::load_prepared_image(path)?;
 
         let content = vec![
-            ContentBlock::image(media_type, base64_data),
-            ContentBlock::text(format!("Image file: {}", path.display())),
+            ContentBlock::image(image.media_type.clone(), image.base64()),
+            ContentBlock::text(format!(
+                "Image file: {} ({})",
+                path.display(),
+                image.summary()
+            )),
         ];
 
         self.conversation.push(crate::anthropic::Message {
@@ -265,6 +359,7 @@ impl ConversationManager {
         tool_calls: &[crate::database::ToolCallRecord],
     ) {
         self.conversation.clear();
+        self.env.clear();
         let mut timeline: Vec<(chrono::DateTime<chrono::Utc>, i32, TimelineEntry)> = Vec::new();
 
         for message in messages {
@@ -482,6 +577,12 @@ impl ConversationManager {
     }
 }
 
+/// The text of a labeled context block, as added by
+/// [`ConversationManager::add_context_text`]
+pub fn context_block(label: &str, content: &str) -> String {
+    format!("Context from {}:\n\n```\n{}\n```", label, content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -520,6 +621,36 @@ mod tests {
         assert_eq!(manager.system_prompt, system_prompt);
     }
 
+    #[test]
+    fn test_add_context_text_labels_the_block() {
+        let mut manager = ConversationManager::new(None, None, "model".to_string());
+        manager.add_context_text("standard input", "diff --git a/x b/x");
+        assert_eq!(manager.conversation.len(), 1);
+        assert_eq!(manager.conversation[0].role, "user");
+        assert_eq!(
+            manager.conversation[0].content[0].text.as_deref(),
+            Some("Context from standard input:\n\n```\ndiff --git a/x b/x\n```")
+        );
+    }
+
+    #[test]
+    fn test_remove_context_message_only_removes_context() {
+        let mut manager = ConversationManager::new(None, None, "model".to_string());
+        manager.add_context_text("file 'notes.md'", "notes");
+        manager.conversation.push(crate::anthropic::Message {
+            role: "user".to_string(),
+            content: vec![ContentBlock::text("hello".to_string())],
+        });
+
+        assert!(manager.remove_context_message(1).is_err());
+        manager.remove_context_message(0).unwrap();
+        assert_eq!(manager.conversation.len(), 1);
+        assert_eq!(
+            manager.conversation[0].content[0].text.as_deref(),
+            Some("hello")
+        );
+    }
+
     #[tokio::test]
     async fn test_new_with_database() {
         let (db, _temp_dir) = create_test_db().await;
@@ -1038,6 +1169,30 @@ mod tests {
         assert_eq!(conv2_messages[0].content, "Message 2");
     }
 
+    #[tokio::test]
+    async fn test_add_context_file_with_symbol() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("lib.rs");
+        fs::write(
+            &file_path,
+            "fn first() {\n    1\n}\n\nfn second() {\n    2\n}\n",
+        )
+        .await
+        .unwrap();
+        let mut manager = ConversationManager::new(None, None, "test-model".to_string());
+
+        let mention = format!("{}#second", file_path.display());
+        manager.add_context_file(&mention).await.unwrap();
+        let text = manager.conversation[0].content[0].text.as_deref().unwrap();
+        assert!(text.starts_with(&format!("Context from file '{}':", mention)));
+        assert!(text.contains("fn second() {\n    2\n}"));
+        assert!(!text.contains("first"));
+
+        let missing = format!("{}#third", file_path.display());
+        let error = manager.add_context_file(&missing).await.unwrap_err();
+        assert!(error.to_string().contains("Symbol 'third' not found"));
+    }
+
     #[tokio::test]
     async fn test_default_agents_files_empty() {
         // This test will likely return empty since we don't have AGENTS.md in test environment
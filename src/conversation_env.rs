@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use tokio::process::Command;
+
+use crate::share::{is_secret_env_var, REDACTED};
+
+/// Environment variables set with `/env` for one conversation. They're added
+/// to the environment of the commands the agent runs, and the values of the
+/// ones named like secrets are masked in what those commands print.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversationEnv {
+    vars: BTreeMap<String, String>,
+}
+
+impl ConversationEnv {
+    /// Set a variable from `KEY=value`, returning its name
+    pub fn set(&mut self, assignment: &str) -> Result<String> {
+        let (name, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected KEY=value, got '{}'", assignment))?;
+        let name = name.trim();
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(anyhow!("'{}' is not a valid variable name", name));
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        self.vars.insert(name.to_string(), value.to_string());
+        Ok(name.to_string())
+    }
+
+    /// Remove a variable, returning whether it was set
+    pub fn unset(&mut self, name: &str) -> bool {
+        self.vars.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.vars.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// The variables by name, with secret values masked, for display
+    pub fn masked_vars(&self) -> Vec<(&str, &str)> {
+        self.vars
+            .iter()
+            .map(|(name, value)| {
+                let shown = if is_secret_env_var(name, value) {
+                    REDACTED
+                } else {
+                    value.as_str()
+                };
+                (name.as_str(), shown)
+            })
+            .collect()
+    }
+
+    /// Add the variables to `cmd`'s environment
+    pub fn apply(&self, cmd: &mut Command) {
+        cmd.envs(&self.vars);
+    }
+
+    /// `text` with the values of secret variables replaced
+    pub fn mask(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (name, value) in &self.vars {
+            if is_secret_env_var(name, value) {
+                text = text.replace(value.as_str(), REDACTED);
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_and_masks_variables() {
+        let mut env = ConversationEnv::default();
+        assert_eq!(
+            env.set("API_URL=https://staging.example.com").unwrap(),
+            "API_URL"
+        );
+        env.set("STAGING_TOKEN=\"tok-1234567890\"").unwrap();
+        assert!(env.set("1BAD=x").is_err());
+        assert!(env.set("NO_VALUE").is_err());
+
+        assert_eq!(
+            env.masked_vars(),
+            vec![
+                ("API_URL", "https://staging.example.com"),
+                ("STAGING_TOKEN", REDACTED),
+            ]
+        );
+        assert_eq!(
+            env.mask("curl -H 'Bearer tok-1234567890' https://staging.example.com"),
+            format!("curl -H 'Bearer {}' https://staging.example.com", REDACTED)
+        );
+
+        assert!(env.unset("API_URL"));
+        assert!(!env.unset("API_URL"));
+        env.clear();
+        assert!(env.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn variables_reach_commands() {
+        let mut env = ConversationEnv::default();
+        env.set("FLEXORAMA_TEST_STAGE=staging").unwrap();
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo $FLEXORAMA_TEST_STAGE"]);
+        env.apply(&mut cmd);
+        let output = cmd.output().await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "staging");
+    }
+}
@@ -0,0 +1,377 @@
+use chrono::Local;
+use serde_json::{json, Value};
+use std::fmt;
+
+use crate::database::{Conversation, DatabaseManager, Message, ToolCallRecord};
+
+/// File format for `flexorama conversations export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Json,
+    Html,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            "html" => Ok(Self::Html),
+            other => Err(format!(
+                "Unsupported export format '{}' (expected markdown, json or html)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Markdown => write!(f, "markdown"),
+            Self::Json => write!(f, "json"),
+            Self::Html => write!(f, "html"),
+        }
+    }
+}
+
+/// A stored conversation with everything needed to render it
+pub struct ConversationTranscript {
+    pub conversation: Conversation,
+    pub messages: Vec<Message>,
+    pub tool_calls: Vec<ToolCallRecord>,
+}
+
+enum Entry<'a> {
+    Message(&'a Message),
+    ToolCall(&'a ToolCallRecord),
+}
+
+impl ConversationTranscript {
+    pub async fn load(db: &DatabaseManager, conversation_id: &str) -> anyhow::Result<Self> {
+        let conversation = db
+            .get_conversation(conversation_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation {} not found", conversation_id))?;
+        Ok(Self {
+            messages: db.get_conversation_messages(conversation_id).await?,
+            tool_calls: db.get_conversation_tool_calls(conversation_id).await?,
+            conversation,
+        })
+    }
+
+    /// Messages and tool calls in the order they happened. Tool calls are
+    /// stored with second precision, so they sort after messages from the
+    /// same instant, as when a conversation is resumed.
+    fn timeline(&self) -> Vec<Entry<'_>> {
+        let mut timeline: Vec<_> = self
+            .messages
+            .iter()
+            .map(|m| (m.created_at, 0, Entry::Message(m)))
+            .chain(
+                self.tool_calls
+                    .iter()
+                    .map(|tc| (tc.created_at, 1, Entry::ToolCall(tc))),
+            )
+            .collect();
+        timeline.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        timeline.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&self.to_json()).unwrap_or_default() + "\n"
+            }
+            ExportFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let conversation = &self.conversation;
+        let mut out = format!("# Conversation {}\n\n", conversation.id);
+        if let Some(name) = &conversation.name {
+            out.push_str(&format!("- Name: {}\n", name));
+        }
+        out.push_str(&format!("- Model: {}\n", conversation.model));
+        if let Some(subagent) = &conversation.subagent {
+            out.push_str(&format!("- Subagent: {}\n", subagent));
+        }
+        out.push_str(&format!(
+            "- Created: {}\n- Updated: {}\n- Tokens: {}\n",
+            conversation
+                .created_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            conversation
+                .updated_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            conversation.total_tokens
+        ));
+        if let Some(prompt) = &conversation.system_prompt {
+            out.push_str(&format!("\n## System Prompt\n\n{}\n", prompt.trim()));
+        }
+
+        for entry in self.timeline() {
+            match entry {
+                Entry::Message(message) => {
+                    let heading = match message.role.as_str() {
+                        "user" => "User",
+                        "assistant" => "Assistant",
+                        other => other,
+                    };
+                    out.push_str(&format!("\n## {}\n\n{}\n", heading, message.content.trim()));
+                }
+                Entry::ToolCall(call) => {
+                    let arguments = serde_json::from_str::<Value>(&call.tool_arguments)
+                        .ok()
+                        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+                        .unwrap_or_else(|| call.tool_arguments.clone());
+                    out.push_str(&format!(
+                        "\n### Tool: {}\n\n{}\n",
+                        call.tool_name,
+                        fenced(&arguments, "json")
+                    ));
+                    if let Some(result) = &call.result_content {
+                        let label = if call.is_error { "Error" } else { "Result" };
+                        out.push_str(&format!("\n{}:\n\n{}\n", label, fenced(result, "")));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// A self-contained page, with tool calls folded away
+    pub fn to_html(&self) -> String {
+        let conversation = &self.conversation;
+        let title = conversation
+            .name
+            .clone()
+            .or_else(|| conversation.title.clone())
+            .unwrap_or_else(|| format!("Conversation {}", conversation.id));
+        let mut body = format!(
+            "<h1>{}</h1>\n<p class=\"meta\">{} &middot; {} &middot; {} tokens</p>\n",
+            escape_html(&title),
+            escape_html(&conversation.model),
+            conversation
+                .created_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            conversation.total_tokens
+        );
+        if let Some(prompt) = &conversation.system_prompt {
+            body.push_str(&format!(
+                "<details class=\"tool\"><summary>System prompt</summary><pre>{}</pre></details>\n",
+                escape_html(prompt.trim())
+            ));
+        }
+        for entry in self.timeline() {
+            match entry {
+                Entry::Message(message) => {
+                    let (class, heading) = match message.role.as_str() {
+                        "user" => ("user", "User"),
+                        "assistant" => ("assistant", "Assistant"),
+                        other => ("other", other),
+                    };
+                    body.push_str(&format!(
+                        "<section class=\"{}\"><h2>{}</h2><div class=\"text\">{}</div></section>\n",
+                        class,
+                        escape_html(heading),
+                        escape_html(message.content.trim())
+                    ));
+                }
+                Entry::ToolCall(call) => {
+                    let arguments = serde_json::from_str::<Value>(&call.tool_arguments)
+                        .ok()
+                        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+                        .unwrap_or_else(|| call.tool_arguments.clone());
+                    body.push_str(&format!(
+                        "<details class=\"tool{}\"><summary>Tool: {}</summary><pre>{}</pre>",
+                        if call.is_error { " error" } else { "" },
+                        escape_html(&call.tool_name),
+                        escape_html(&arguments)
+                    ));
+                    if let Some(result) = &call.result_content {
+                        body.push_str(&format!("<pre>{}</pre>", escape_html(result.trim_end())));
+                    }
+                    body.push_str("</details>\n");
+                }
+            }
+        }
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(&title),
+            HTML_STYLE,
+            body
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        let conversation = &self.conversation;
+        let entries: Vec<Value> = self
+            .timeline()
+            .into_iter()
+            .map(|entry| match entry {
+                Entry::Message(message) => json!({
+                    "type": "message",
+                    "role": message.role,
+                    "content": message.content,
+                    "created_at": message.created_at.to_rfc3339(),
+                }),
+                Entry::ToolCall(call) => json!({
+                    "type": "tool_call",
+                    "id": call.id,
+                    "name": call.tool_name,
+                    "input": serde_json::from_str::<Value>(&call.tool_arguments)
+                        .unwrap_or_else(|_| Value::String(call.tool_arguments.clone())),
+                    "result": call.result_content,
+                    "is_error": call.is_error,
+                    "created_at": call.created_at.to_rfc3339(),
+                }),
+            })
+            .collect();
+        json!({
+            "id": conversation.id,
+            "name": conversation.name,
+            "model": conversation.model,
+            "subagent": conversation.subagent,
+            "system_prompt": conversation.system_prompt,
+            "created_at": conversation.created_at.to_rfc3339(),
+            "updated_at": conversation.updated_at.to_rfc3339(),
+            "total_tokens": conversation.total_tokens,
+            "request_count": conversation.request_count,
+            "entries": entries,
+        })
+    }
+}
+
+const HTML_STYLE: &str = "\
+body{font:15px/1.5 system-ui,sans-serif;max-width:52rem;margin:2rem auto;padding:0 1rem;color:#1f2328;background:#fff}\
+h1{font-size:1.4rem;margin-bottom:0}h2{font-size:.8rem;text-transform:uppercase;letter-spacing:.05em;color:#59636e;margin:0 0 .3rem}\
+.meta{color:#59636e;margin-top:.2rem}section{margin:1.2rem 0;padding:.8rem 1rem;border-radius:8px}\
+.user{background:#ddf4ff}.assistant{background:#f6f8fa}.text{white-space:pre-wrap;overflow-wrap:anywhere}\
+details.tool{margin:.5rem 0 .5rem 1rem;border-left:3px solid #d1d9e0;padding-left:.8rem}details.error{border-color:#cf222e}\
+summary{cursor:pointer;color:#59636e;font-family:ui-monospace,monospace;font-size:.85rem}\
+pre{background:#f6f8fa;padding:.6rem;border-radius:6px;overflow-x:auto;font-size:.8rem}\
+@media (prefers-color-scheme:dark){body{color:#e6edf3;background:#0d1117}.user{background:#0c2d48}\
+.assistant,pre{background:#161b22}h2,.meta,summary{color:#9198a1}details.tool{border-color:#3d444d}}";
+
+/// Escape text for use in HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Wrap text in a code fence longer than any backtick run inside it
+fn fenced(text: &str, language: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language, text.trim_end(), fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn transcript() -> ConversationTranscript {
+        let start = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+        let message = |id: &str, role: &str, content: &str, offset: i64| Message {
+            id: id.to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            created_at: start + Duration::milliseconds(offset),
+        };
+        ConversationTranscript {
+            conversation: Conversation {
+                id: "conv-1".to_string(),
+                name: None,
+                title: None,
+                summary: None,
+                created_at: start,
+                updated_at: start,
+                system_prompt: Some("Be brief".to_string()),
+                model: "test-model".to_string(),
+                subagent: None,
+                total_tokens: 42,
+                request_count: 1,
+            },
+            messages: vec![
+                message("m1", "user", "What's in README?", 0),
+                message("m2", "assistant", "A heading.", 2500),
+            ],
+            tool_calls: vec![ToolCallRecord {
+                id: "t1".to_string(),
+                tool_name: "Read".to_string(),
+                tool_arguments: r#"{"file_path":"README.md"}"#.to_string(),
+                result_content: Some("```\n# Title\n```".to_string()),
+                is_error: false,
+                created_at: start + Duration::seconds(1),
+            }],
+        }
+    }
+
+    #[test]
+    fn renders_markdown_in_timeline_order() {
+        let markdown = transcript().to_markdown();
+        let user = markdown.find("## User").unwrap();
+        let tool = markdown.find("### Tool: Read").unwrap();
+        let assistant = markdown.find("## Assistant").unwrap();
+        assert!(user < tool && tool < assistant);
+        assert!(markdown.contains("## System Prompt\n\nBe brief"));
+        assert!(markdown.contains("\"file_path\": \"README.md\""));
+        // The result contains a fence, so it gets a longer one
+        assert!(markdown.contains("Result:\n\n````\n```\n# Title\n```\n````"));
+    }
+
+    #[test]
+    fn renders_escaped_html() {
+        let mut transcript = transcript();
+        transcript.messages[0].content = "Is <script> & \"quoted\" safe?".to_string();
+        let html = transcript.render(ExportFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Conversation conv-1</title>"));
+        assert!(html.contains("Is &lt;script&gt; &amp; &quot;quoted&quot; safe?"));
+        assert!(html.contains("<summary>Tool: Read</summary>"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn renders_json_entries() {
+        let value = transcript().to_json();
+        assert_eq!(value["id"], "conv-1");
+        let entries = value["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1]["type"], "tool_call");
+        assert_eq!(entries[1]["input"]["file_path"], "README.md");
+        assert_eq!(entries[2]["role"], "assistant");
+    }
+}
@@ -0,0 +1,283 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+/// The process holding a conversation's lock, as written in the lock file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub since: DateTime<Utc>,
+}
+
+/// Returned by [`acquire`] when another process has the conversation open
+#[derive(Debug, Clone)]
+pub struct ConversationInUse {
+    pub conversation_id: String,
+    /// None if the lock file couldn't be read, e.g. while it's being written
+    pub holder: Option<LockHolder>,
+}
+
+impl fmt::Display for ConversationInUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Conversation {} is in use by another Flexorama process",
+            self.conversation_id
+        )?;
+        if let Some(holder) = &self.holder {
+            write!(
+                f,
+                " (pid {}, since {})",
+                holder.pid,
+                holder.since.with_timezone(&chrono::Local).format("%H:%M")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConversationInUse {}
+
+/// Held while this process may write to a conversation. It's an advisory
+/// lock on a file, so it goes away with the last clone of it or when the
+/// process exits, even if it crashes. The last clone also removes the file.
+#[derive(Debug, Clone)]
+pub struct ConversationLock {
+    conversation_id: String,
+    _file: Arc<LockFile>,
+}
+
+/// A locked lock file, removed while it's still locked when it's dropped.
+/// Only the holder removes the file, so a process that opened it just
+/// before then can tell its lock is on a file that's gone, and starts over.
+#[derive(Debug)]
+struct LockFile {
+    path: PathBuf,
+    _file: File,
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl ConversationLock {
+    pub fn conversation_id(&self) -> &str {
+        &self.conversation_id
+    }
+}
+
+/// This process's locks by path, so its agents share a lock rather than
+/// contending for it. The web server runs one agent per conversation, and
+/// reloads a conversation in the agent that has it open.
+fn held() -> &'static Mutex<HashMap<PathBuf, Weak<LockFile>>> {
+    static HELD: OnceLock<Mutex<HashMap<PathBuf, Weak<LockFile>>>> = OnceLock::new();
+    HELD.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The lock file for a conversation in the database at `db_path`, in a
+/// `locks` directory next to it
+pub fn lock_path(db_path: &Path, conversation_id: &str) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("locks")
+        .join(format!("{}.lock", conversation_id))
+}
+
+/// Lock a conversation for writing. Fails with [`ConversationInUse`] if
+/// another process holds the lock.
+pub fn acquire(db_path: &Path, conversation_id: &str) -> Result<ConversationLock> {
+    let path = lock_path(db_path, conversation_id);
+    let mut held = held().lock().unwrap_or_else(|e| e.into_inner());
+    held.retain(|_, file| file.strong_count() > 0);
+    if let Some(file) = held.get(&path).and_then(Weak::upgrade) {
+        return Ok(ConversationLock {
+            conversation_id: conversation_id.to_string(),
+            _file: file,
+        });
+    }
+
+    let mut file = loop {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|text| serde_json::from_str(&text).ok());
+                return Err(ConversationInUse {
+                    conversation_id: conversation_id.to_string(),
+                    holder,
+                }
+                .into());
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        if is_current(&file, &path) {
+            break file;
+        }
+    };
+
+    file.set_len(0)?;
+    serde_json::to_writer(
+        &mut file,
+        &LockHolder {
+            pid: std::process::id(),
+            since: Utc::now(),
+        },
+    )?;
+    let file = Arc::new(LockFile {
+        path: path.clone(),
+        _file: file,
+    });
+    held.insert(path, Arc::downgrade(&file));
+    Ok(ConversationLock {
+        conversation_id: conversation_id.to_string(),
+        _file: file,
+    })
+}
+
+/// Whether `file` is still the one at `path`, and not one its holder
+/// removed between us opening and locking it
+fn is_current(file: &File, path: &Path) -> bool {
+    let Ok(at_path) = std::fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        file.metadata()
+            .is_ok_and(|opened| opened.dev() == at_path.dev() && opened.ino() == at_path.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (file, at_path);
+        true
+    }
+}
+
+/// Remove the lock file of a deleted conversation. If a process still has
+/// the conversation open, the file goes when it lets go of it instead.
+pub fn remove(db_path: &Path, conversation_id: &str) {
+    if lock_path(db_path, conversation_id).exists() {
+        let _ = acquire(db_path, conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn a_second_process_is_told_who_has_the_conversation() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("project.db");
+        let lock = acquire(&db_path, "conv-1").unwrap();
+        assert_eq!(lock.conversation_id(), "conv-1");
+
+        // Opening the file again stands in for another process: its lock
+        // doesn't go through this process's table of held locks
+        let path = lock_path(&db_path, "conv-1");
+        let other = File::open(&path).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+        drop(lock);
+        assert!(!path.exists());
+        let other = File::create(&path).unwrap();
+        other.try_lock().unwrap();
+
+        let error = acquire(&db_path, "conv-1").unwrap_err();
+        let in_use = error.downcast_ref::<ConversationInUse>().unwrap();
+        assert_eq!(in_use.conversation_id, "conv-1");
+        assert!(error
+            .to_string()
+            .starts_with("Conversation conv-1 is in use by another Flexorama process"));
+
+        drop(other);
+        acquire(&db_path, "conv-1").unwrap();
+    }
+
+    #[test]
+    fn the_holder_is_read_from_the_lock_file() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("project.db");
+        let path = lock_path(&db_path, "conv-2");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let holder = LockHolder {
+            pid: 4242,
+            since: Utc::now(),
+        };
+        std::fs::write(&path, serde_json::to_string(&holder).unwrap()).unwrap();
+        let other = File::open(&path).unwrap();
+        other.try_lock().unwrap();
+
+        let error = acquire(&db_path, "conv-2").unwrap_err();
+        let in_use = error.downcast_ref::<ConversationInUse>().unwrap();
+        assert_eq!(in_use.holder, Some(holder));
+        assert!(error.to_string().contains("(pid 4242, since "));
+    }
+
+    #[test]
+    fn agents_in_one_process_share_a_lock() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("project.db");
+        let first = acquire(&db_path, "conv-3").unwrap();
+        let second = acquire(&db_path, "conv-3").unwrap();
+        drop(first);
+
+        let path = lock_path(&db_path, "conv-3");
+        let other = File::open(&path).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(second);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn a_lock_on_a_removed_file_is_taken_again() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("project.db");
+        let path = lock_path(&db_path, "conv-4");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let removed = File::create(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!is_current(&removed, &path));
+
+        let lock = acquire(&db_path, "conv-4").unwrap();
+        assert!(path.exists());
+        let other = File::open(&path).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(lock);
+    }
+
+    #[test]
+    fn removing_a_deleted_conversations_lock_leaves_held_ones() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("project.db");
+        let path = lock_path(&db_path, "conv-5");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let other = File::create(&path).unwrap();
+        other.try_lock().unwrap();
+        remove(&db_path, "conv-5");
+        assert!(path.exists());
+
+        drop(other);
+        remove(&db_path, "conv-5");
+        assert!(!path.exists());
+        remove(&db_path, "conv-5");
+    }
+}
@@ -0,0 +1,352 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::agent::Agent;
+use crate::database::ToolCallRecord;
+
+/// A tool that was running, or had finished but whose result wasn't part of
+/// the conversation yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingTool {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub result: Option<String>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+/// What the interactive session was doing, kept up to date so a panic
+/// report can say how to pick it back up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub conversation_id: Option<String>,
+    pub model: String,
+    pub working_dir: PathBuf,
+    /// The prompt of the turn that was in progress
+    pub prompt: Option<String>,
+    pub pending_tools: Vec<PendingTool>,
+    pub updated_at: DateTime<Utc>,
+}
+
+static SESSION: Mutex<Option<SessionSnapshot>> = Mutex::new(None);
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+const RECENT_LOG_LINES: usize = 200;
+
+fn update_session(update: impl FnOnce(&mut SessionSnapshot)) {
+    let mut guard = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let snapshot = guard.get_or_insert_with(|| SessionSnapshot {
+        conversation_id: None,
+        model: String::new(),
+        working_dir: std::env::current_dir().unwrap_or_default(),
+        prompt: None,
+        pending_tools: Vec::new(),
+        updated_at: Utc::now(),
+    });
+    update(snapshot);
+    snapshot.updated_at = Utc::now();
+}
+
+/// A turn started in `conversation_id`
+pub fn turn_started(conversation_id: Option<String>, model: &str, prompt: &str) {
+    update_session(|snapshot| {
+        snapshot.conversation_id = conversation_id;
+        snapshot.model = model.to_string();
+        snapshot.working_dir = std::env::current_dir().unwrap_or_default();
+        snapshot.prompt = Some(prompt.to_string());
+        snapshot.pending_tools.clear();
+    });
+}
+
+pub fn tool_started(id: &str, name: &str) {
+    update_session(|snapshot| {
+        snapshot.pending_tools.push(PendingTool {
+            id: id.to_string(),
+            name: name.to_string(),
+            result: None,
+            is_error: false,
+        })
+    });
+}
+
+pub fn tool_finished(id: &str, content: &str, is_error: bool) {
+    update_session(|snapshot| {
+        if let Some(tool) = snapshot.pending_tools.iter_mut().find(|tool| tool.id == id) {
+            tool.result = Some(content.to_string());
+            tool.is_error = is_error;
+        }
+    });
+}
+
+/// The turn's messages are all saved, so there's nothing left to recover
+pub fn turn_finished() {
+    update_session(|snapshot| {
+        snapshot.prompt = None;
+        snapshot.pending_tools.clear();
+    });
+}
+
+/// Keep a log line for the diagnostics of a crash report
+pub fn remember_log_line(line: String) {
+    let mut lines = RECENT_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if lines.len() == RECENT_LOG_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// `~/.flexorama/crashes`
+pub fn crashes_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow!("Could not determine home directory"))?
+        .join(".flexorama")
+        .join("crashes"))
+}
+
+/// Write a crash report on any panic, then carry on with the usual panic
+/// output
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => info
+                .payload()
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "unknown panic".to_string()),
+        };
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()));
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        // try_lock: the panic may have happened while one of these was held
+        let session = SESSION.try_lock().ok().and_then(|guard| guard.clone());
+        let log: Vec<String> = RECENT_LOG
+            .try_lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default();
+        let panic = match &location {
+            Some(location) => format!("{} at {}", message, location),
+            None => message,
+        };
+        match crashes_dir()
+            .and_then(|dir| write_report(&dir, &panic, &backtrace, session.as_ref(), &log))
+        {
+            Ok(path) => eprintln!(
+                "Flexorama crashed. Crash report saved to {}",
+                path.display()
+            ),
+            Err(e) => eprintln!("Flexorama crashed and the crash report failed: {:#}", e),
+        }
+        previous(info);
+    }));
+}
+
+/// Write a crash report directory: `diagnostics.txt` for a bug report, and
+/// `session.json` when there's a session to recover
+pub fn write_report(
+    crashes_dir: &Path,
+    panic: &str,
+    backtrace: &str,
+    session: Option<&SessionSnapshot>,
+    log: &[String],
+) -> Result<PathBuf> {
+    let now = Utc::now();
+    let dir = crashes_dir.join(format!(
+        "{}-{}",
+        now.format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut diagnostics = format!(
+        "Flexorama {} crashed at {}\n\nPanic: {}\nOS: {} {}\nArguments: {:?}\n",
+        env!("CARGO_PKG_VERSION"),
+        now.to_rfc3339(),
+        panic,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::args().collect::<Vec<_>>(),
+    );
+    if let Some(session) = session {
+        diagnostics.push_str(&format!(
+            "Model: {}\nConversation: {}\nTools running: {}\n",
+            session.model,
+            session.conversation_id.as_deref().unwrap_or("none"),
+            session.pending_tools.len()
+        ));
+    }
+    diagnostics.push_str(&format!("\nBacktrace:\n{}\n", backtrace));
+    if !log.is_empty() {
+        diagnostics.push_str("\nRecent log:\n");
+        for line in log {
+            diagnostics.push_str(line);
+            diagnostics.push('\n');
+        }
+    }
+    std::fs::write(dir.join("diagnostics.txt"), diagnostics)?;
+
+    let report = CrashReport {
+        dir: dir.clone(),
+        crashed_at: now,
+        panic: panic.to_string(),
+        session: session.cloned(),
+    };
+    if report.is_recoverable() {
+        std::fs::write(
+            dir.join("session.json"),
+            serde_json::to_string_pretty(&report)?,
+        )?;
+    }
+    Ok(dir)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    #[serde(skip)]
+    pub dir: PathBuf,
+    pub crashed_at: DateTime<Utc>,
+    pub panic: String,
+    pub session: Option<SessionSnapshot>,
+}
+
+impl CrashReport {
+    fn is_recoverable(&self) -> bool {
+        self.session
+            .as_ref()
+            .is_some_and(|session| session.conversation_id.is_some())
+    }
+
+    /// The conversation the crashed session was in
+    pub fn conversation_id(&self) -> Option<&str> {
+        self.session.as_ref()?.conversation_id.as_deref()
+    }
+
+    /// Don't offer this report again. The diagnostics are kept.
+    pub fn dismiss(&self) -> Result<()> {
+        std::fs::remove_file(self.dir.join("session.json"))
+            .with_context(|| format!("Failed to dismiss crash report {}", self.dir.display()))
+    }
+
+    /// Reopen the crashed conversation in `agent`. Tools that finished are
+    /// recorded with their results; tools that were cut off are recorded as
+    /// failed, so the model knows to check on them.
+    pub async fn recover(&self, agent: &mut Agent) -> Result<()> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| anyhow!("Nothing to recover"))?;
+        let conversation_id = self
+            .conversation_id()
+            .ok_or_else(|| anyhow!("Nothing to recover"))?;
+        if let Some(db) = agent.database_manager() {
+            let tool_calls: Vec<ToolCallRecord> = session
+                .pending_tools
+                .iter()
+                .map(|tool| {
+                    let (content, is_error) = match &tool.result {
+                        Some(result) => (result.clone(), tool.is_error),
+                        None => (
+                            "Flexorama crashed while this tool was running; its effects are unknown"
+                                .to_string(),
+                            true,
+                        ),
+                    };
+                    ToolCallRecord {
+                        id: tool.id.clone(),
+                        tool_name: tool.name.clone(),
+                        tool_arguments: "{}".to_string(),
+                        result_content: Some(content),
+                        is_error,
+                        created_at: Utc::now(),
+                    }
+                })
+                .collect();
+            db.save_tool_calls(conversation_id, &tool_calls).await?;
+        }
+        agent.resume_conversation(conversation_id).await?;
+        self.dismiss()
+    }
+}
+
+/// The most recent crash with a session to recover that was running in
+/// `working_dir`
+pub fn find_recoverable(crashes_dir: &Path, working_dir: &Path) -> Option<CrashReport> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(crashes_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|dir| dir.join("session.json").is_file())
+        .collect();
+    dirs.sort();
+    dirs.into_iter().rev().find_map(|dir| {
+        let content = std::fs::read_to_string(dir.join("session.json")).ok()?;
+        let mut report: CrashReport = serde_json::from_str(&content).ok()?;
+        report.dir = dir;
+        let same_dir = report.session.as_ref()?.working_dir == working_dir;
+        (same_dir && report.is_recoverable()).then_some(report)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn snapshot(working_dir: &Path) -> SessionSnapshot {
+        SessionSnapshot {
+            conversation_id: Some("conv-1".to_string()),
+            model: "claude-sonnet-4".to_string(),
+            working_dir: working_dir.to_path_buf(),
+            prompt: Some("Refactor the parser".to_string()),
+            pending_tools: vec![PendingTool {
+                id: "tool-1".to_string(),
+                name: "bash".to_string(),
+                result: None,
+                is_error: false,
+            }],
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn writes_and_finds_crash_reports() {
+        let crashes = TempDir::new().unwrap();
+        let project = Path::new("/work/project");
+        let log = vec!["[INFO] Starting".to_string()];
+
+        let dir = write_report(
+            crashes.path(),
+            "index out of bounds at src/agent.rs:10",
+            "0: main",
+            Some(&snapshot(project)),
+            &log,
+        )
+        .unwrap();
+        let diagnostics = std::fs::read_to_string(dir.join("diagnostics.txt")).unwrap();
+        assert!(diagnostics.contains("Panic: index out of bounds at src/agent.rs:10"));
+        assert!(diagnostics.contains("Conversation: conv-1"));
+        assert!(diagnostics.contains("[INFO] Starting"));
+
+        let report = find_recoverable(crashes.path(), project).unwrap();
+        assert_eq!(report.conversation_id(), Some("conv-1"));
+        assert_eq!(report.session.as_ref().unwrap().pending_tools.len(), 1);
+        assert!(find_recoverable(crashes.path(), Path::new("/elsewhere")).is_none());
+
+        report.dismiss().unwrap();
+        assert!(find_recoverable(crashes.path(), project).is_none());
+        assert!(dir.join("diagnostics.txt").is_file());
+    }
+
+    #[test]
+    fn crashes_outside_a_conversation_only_get_diagnostics() {
+        let crashes = TempDir::new().unwrap();
+        let dir = write_report(crashes.path(), "boom", "", None, &[]).unwrap();
+        assert!(dir.join("diagnostics.txt").is_file());
+        assert!(!dir.join("session.json").exists());
+    }
+}
@@ -1,8 +1,19 @@
 use anyhow::{anyhow, Result};
+use log::debug;
 use regex::Regex;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tokio::fs;
+use tokio::sync::RwLock;
+
+use crate::conversation::context_block;
+use crate::conversation_env::ConversationEnv;
+use crate::project_config;
+use crate::security::BashSecurityManager;
+use crate::tools::bash;
 
 #[derive(Debug, Clone)]
 pub struct CustomCommand {
@@ -18,6 +29,127 @@ pub struct CustomCommand {
 pub struct RenderedCustomCommand {
     pub command: CustomCommand,
     pub message: String,
+    snippets: Vec<ShellSnippet>,
+}
+
+/// A `!`command`` from a command's template, and where it is in the
+/// rendered message
+#[derive(Debug)]
+struct ShellSnippet {
+    range: Range<usize>,
+    command: String,
+}
+
+impl RenderedCustomCommand {
+    /// Pull in everything the message refers to: the output of `!`command``s
+    /// first, then `@file` references. The shell snippets are taken from the
+    /// command's own text only, never from the contents of an included file.
+    pub async fn expand(
+        &mut self,
+        security_manager: &RwLock<BashSecurityManager>,
+        yolo_mode: bool,
+        env: &ConversationEnv,
+    ) -> Result<()> {
+        self.run_shell_commands(security_manager, yolo_mode, env)
+            .await?;
+        self.expand_file_references().await;
+        Ok(())
+    }
+
+    /// Turn each `@path` that names a readable text file into a plain path
+    /// and add the file's contents as a context block after the message.
+    /// Anything else that starts with `@`, like a mention or a missing file,
+    /// is left alone.
+    pub async fn expand_file_references(&mut self) {
+        let mut blocks: Vec<(String, String)> = Vec::new();
+        let mut found = Vec::new();
+        for caps in file_reference_regex().captures_iter(&self.message) {
+            let path = caps[2]
+                .trim_end_matches(['.', ',', ':', ';', ')'])
+                .to_string();
+            if found.contains(&path) {
+                continue;
+            }
+            let expanded = shellexpand::tilde(&path).into_owned();
+            if crate::image::is_image_file(Path::new(&expanded)) {
+                continue;
+            }
+            match fs::read_to_string(&expanded).await {
+                Ok(content) => {
+                    blocks.push((path.clone(), content));
+                    found.push(path);
+                }
+                Err(e) => debug!("/{}: not expanding @{}: {}", self.command.name, path, e),
+            }
+        }
+        if blocks.is_empty() {
+            return;
+        }
+
+        self.message = file_reference_regex()
+            .replace_all(&self.message, |caps: &regex::Captures| {
+                let path = caps[2].trim_end_matches(['.', ',', ':', ';', ')']);
+                if found.iter().any(|found| found == path) {
+                    format!("{}{}", &caps[1], &caps[2])
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .to_string();
+        for (path, content) in blocks {
+            self.message.push_str("\n\n");
+            self.message.push_str(&context_block(
+                &format!("file '{}'", path),
+                content.trim_end(),
+            ));
+        }
+    }
+
+    /// Run each `!`command`` from the template, under the bash security
+    /// policy, and put its output in its place. A command that isn't allowed
+    /// stops the whole custom command. The conversation's `/env` variables
+    /// are set for them. This has to run before anything else changes the
+    /// message.
+    pub async fn run_shell_commands(
+        &mut self,
+        security_manager: &RwLock<BashSecurityManager>,
+        yolo_mode: bool,
+        env: &ConversationEnv,
+    ) -> Result<()> {
+        let snippets = std::mem::take(&mut self.snippets);
+        let mut outputs = Vec::with_capacity(snippets.len());
+        for snippet in &snippets {
+            let mut manager = security_manager.write().await;
+            let output = bash::run_for_output(&snippet.command, &mut manager, yolo_mode, env)
+                .await
+                .map_err(|e| anyhow!("/{}: {}", self.command.name, e))?;
+            outputs.push(output.trim_end().to_string());
+        }
+        for (snippet, output) in snippets.into_iter().zip(outputs).rev() {
+            self.message.replace_range(snippet.range, &output);
+        }
+        Ok(())
+    }
+}
+
+/// `@path` at the start of the message or after whitespace, so email
+/// addresses aren't picked up
+fn file_reference_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(^|\s)@([^\s@`]+)").expect("valid regex"))
+}
+
+/// The `` !`command` `` snippets in a command template
+pub fn shell_commands_in(content: &str) -> Vec<String> {
+    shell_regex()
+        .captures_iter(content)
+        .map(|caps| caps[1].trim().to_string())
+        .collect()
+}
+
+fn shell_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"!`([^`\n]+)`").expect("valid regex"))
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -40,6 +172,11 @@ enum AllowedToolsDef {
 }
 
 impl CustomCommand {
+    /// The namespace of a command like `git:commit`, from its subdirectory
+    pub fn namespace(&self) -> Option<&str> {
+        self.name.rsplit_once(':').map(|(namespace, _)| namespace)
+    }
+
     pub fn from_markdown(name: String, content: &str) -> Result<Self> {
         let (frontmatter, body) = split_frontmatter(content)?;
         let mut command = CustomCommand {
@@ -100,33 +237,304 @@ impl CustomCommand {
         format!("---\n{}\n---\n\n{}", frontmatter, body)
     }
 
+    /// Fill in `$ARGUMENTS`, `$1`..`$9` and `{{name}}` placeholders
     pub fn render(&self, args_raw: &str) -> String {
-        let mut rendered = self.content.clone();
-        let arguments = args_raw.trim();
-        if rendered.contains("$ARGUMENTS") {
-            rendered = rendered.replace("$ARGUMENTS", arguments);
+        self.render_with_snippets(args_raw).0
+    }
+
+    /// [`render`](Self::render), also returning where each `!`command``
+    /// ended up in the message and the command to run for it. Values
+    /// filled in inside a snippet are shell-quoted, and snippets are only
+    /// taken from the template, so arguments can't add or change commands.
+    fn render_with_snippets(&self, args_raw: &str) -> (String, Vec<ShellSnippet>) {
+        let args = CommandArgs::parse(self, args_raw);
+        let mut message = String::new();
+        let mut snippets = Vec::new();
+        let mut last = 0;
+        for caps in shell_regex().captures_iter(&self.content) {
+            let span = caps.get(0).expect("whole match");
+            message.push_str(
+                &self.substitute(&self.content[last..span.start()], |placeholder| {
+                    args.value(self, placeholder)
+                }),
+            );
+            let command = self.substitute(caps[1].trim(), |placeholder| {
+                args.shell_value(self, placeholder)
+            });
+            let start = message.len();
+            message.push_str(&format!("!`{}`", command));
+            snippets.push(ShellSnippet {
+                range: start..message.len(),
+                command,
+            });
+            last = span.end();
         }
+        message.push_str(&self.substitute(&self.content[last..], |placeholder| {
+            args.value(self, placeholder)
+        }));
+        (message, snippets)
+    }
 
-        let args: Vec<&str> = if arguments.is_empty() {
-            Vec::new()
-        } else {
-            arguments.split_whitespace().collect()
-        };
+    /// Fill in the placeholders in `text` with `value`
+    fn substitute(&self, text: &str, value: impl Fn(&Placeholder) -> String) -> String {
+        let rendered = text.replace("$ARGUMENTS", &value(&Placeholder::All));
+        let rendered =
+            positional_regex().replace_all(&rendered, |caps: &regex::Captures| {
+                match caps[1].parse::<usize>() {
+                    Ok(index) if index > 0 => value(&Placeholder::Positional(index)),
+                    _ => String::new(),
+                }
+            });
+        named_regex()
+            .replace_all(&rendered, |caps: &regex::Captures| {
+                value(&Placeholder::Named(caps[1].to_string()))
+            })
+            .to_string()
+    }
 
-        let re = Regex::new(r"\$(\d+)").expect("valid regex");
-        re.replace_all(&rendered, |caps: &regex::Captures| {
-            let idx = caps[1].parse::<usize>().unwrap_or(0);
-            if idx == 0 {
-                ""
-            } else {
-                args.get(idx - 1).copied().unwrap_or("")
+    /// Argument names from the `argument-hint`, so `[issue] [priority]`
+    /// names `$1` "issue" and `$2` "priority"
+    fn hint_names(&self) -> Vec<String> {
+        self.argument_hint
+            .as_deref()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|word| {
+                word.trim_matches(|c| matches!(c, '[' | ']' | '<' | '>'))
+                    .trim_end_matches("...")
+                    .to_string()
+            })
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    /// The placeholders the template uses, in the order they should be asked for
+    fn placeholders(&self) -> Vec<Placeholder> {
+        let mut placeholders = Vec::new();
+        let highest = positional_regex()
+            .captures_iter(&self.content)
+            .filter_map(|caps| caps[1].parse::<usize>().ok())
+            .max()
+            .unwrap_or(0);
+        placeholders.extend((1..=highest).map(Placeholder::Positional));
+        for caps in named_regex().captures_iter(&self.content) {
+            let placeholder = Placeholder::Named(caps[1].to_string());
+            if !placeholders.contains(&placeholder) {
+                placeholders.push(placeholder);
             }
-        })
-        .to_string()
+        }
+        if self.content.contains("$ARGUMENTS") && placeholders.is_empty() {
+            placeholders.push(Placeholder::All);
+        }
+        placeholders
     }
 }
 
-pub async fn render_custom_command_input(input: &str) -> Result<Option<RenderedCustomCommand>> {
+/// Where an argument goes in a command's template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Placeholder {
+    /// `$ARGUMENTS`
+    All,
+    /// `$1`..`$9`
+    Positional(usize),
+    /// `{{name}}`
+    Named(String),
+}
+
+impl Placeholder {
+    /// The name the user gives the argument as with `name=value`, and is
+    /// asked for when it's missing
+    fn name(&self, command: &CustomCommand) -> String {
+        match self {
+            Placeholder::All => "arguments".to_string(),
+            Placeholder::Positional(index) => command
+                .hint_names()
+                .get(index - 1)
+                .cloned()
+                .unwrap_or_else(|| format!("arg{}", index)),
+            Placeholder::Named(name) => name.clone(),
+        }
+    }
+}
+
+fn positional_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\$(\d+)").expect("valid regex"))
+}
+
+fn named_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z_][\w-]*)\s*\}\}").expect("valid regex"))
+}
+
+/// A command's arguments: positional words, plus `name=value` pairs for
+/// names the command knows
+struct CommandArgs {
+    raw: String,
+    positional: Vec<String>,
+    named: HashMap<String, String>,
+}
+
+impl CommandArgs {
+    fn parse(command: &CustomCommand, args_raw: &str) -> Self {
+        let names: Vec<String> = command
+            .placeholders()
+            .iter()
+            .map(|placeholder| placeholder.name(command))
+            .chain(command.hint_names())
+            .collect();
+        let mut positional = Vec::new();
+        let mut named = HashMap::new();
+        for word in split_arguments(args_raw) {
+            match word.split_once('=') {
+                Some((name, value)) if names.iter().any(|known| known == name) => {
+                    named.insert(name.to_string(), value.to_string());
+                }
+                _ => positional.push(word),
+            }
+        }
+        Self {
+            raw: args_raw.trim().to_string(),
+            positional,
+            named,
+        }
+    }
+
+    fn value(&self, command: &CustomCommand, placeholder: &Placeholder) -> String {
+        if let Some(value) = self.named.get(&placeholder.name(command)) {
+            return value.clone();
+        }
+        match placeholder {
+            // Keep the user's spacing and quotes unless named arguments
+            // were taken out
+            Placeholder::All if self.named.is_empty() => self.raw.clone(),
+            Placeholder::All => self.positional.join(" "),
+            Placeholder::Positional(index) => {
+                self.positional.get(index - 1).cloned().unwrap_or_default()
+            }
+            Placeholder::Named(name) => command
+                .hint_names()
+                .iter()
+                .position(|hint| hint == name)
+                .and_then(|index| self.positional.get(index).cloned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// [`value`](Self::value) quoted for the shell, for use inside a
+    /// `!`command``. `$ARGUMENTS` stays one word per argument.
+    fn shell_value(&self, command: &CustomCommand, placeholder: &Placeholder) -> String {
+        match placeholder {
+            Placeholder::All if !self.named.contains_key(&placeholder.name(command)) => self
+                .positional
+                .iter()
+                .map(|word| shell_quote(word))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => shell_quote(&self.value(command, placeholder)),
+        }
+    }
+}
+
+/// Split arguments on whitespace, keeping "quoted strings" and
+/// name="quoted values" together. Unbalanced quotes, as in `don't`, are
+/// taken literally.
+fn split_arguments(raw: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some('"') if c == '\\' => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+            }
+            Some(_) => word.push(c),
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None if (c == '"' || c == '\'') && (!in_word || word.ends_with('=')) => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return raw.split_whitespace().map(str::to_string).collect();
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Quote `value` as a single shell word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quote `value` so [`split_arguments`] reads it back as one word
+fn quote_argument(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A custom command and the arguments it was run with, before rendering
+#[derive(Debug, Clone)]
+pub struct CustomCommandInvocation {
+    pub command: CustomCommand,
+    pub args: String,
+}
+
+impl CustomCommandInvocation {
+    /// Names of the arguments the template needs but weren't given, in order
+    pub fn missing_arguments(&self) -> Vec<String> {
+        let args = CommandArgs::parse(&self.command, &self.args);
+        let mut missing: Vec<String> = Vec::new();
+        for placeholder in self.command.placeholders() {
+            let name = placeholder.name(&self.command);
+            if args.value(&self.command, &placeholder).is_empty() && !missing.contains(&name) {
+                missing.push(name);
+            }
+        }
+        missing
+    }
+
+    /// Add a value for an argument that was missing
+    pub fn supply(&mut self, name: &str, value: &str) {
+        if !self.args.is_empty() {
+            self.args.push(' ');
+        }
+        self.args
+            .push_str(&format!("{}={}", name, quote_argument(value)));
+    }
+
+    pub fn missing_args_message(&self) -> String {
+        missing_args_message(&self.command)
+    }
+
+    pub fn render(self) -> RenderedCustomCommand {
+        let (message, snippets) = self.command.render_with_snippets(&self.args);
+        RenderedCustomCommand {
+            command: self.command,
+            message,
+            snippets,
+        }
+    }
+}
+
+/// Find the custom command `input` runs, without checking its arguments
+pub async fn parse_custom_command_input(input: &str) -> Result<Option<CustomCommandInvocation>> {
     let trimmed = input.trim();
     if !trimmed.starts_with('/') {
         return Ok(None);
@@ -145,65 +553,129 @@ pub async fn render_custom_command_input(input: &str) -> Result<Option<RenderedC
         Some(command) => command,
         None => return Ok(None),
     };
+    Ok(Some(CustomCommandInvocation {
+        command,
+        args: args_raw.to_string(),
+    }))
+}
 
-    let args_count = if args_raw.is_empty() {
-        0
-    } else {
-        args_raw.split_whitespace().count()
+/// Render the custom command `input` runs, failing if it's missing arguments
+pub async fn render_custom_command_input(input: &str) -> Result<Option<RenderedCustomCommand>> {
+    let Some(invocation) = parse_custom_command_input(input).await? else {
+        return Ok(None);
     };
-    if let Some(required) = required_arg_count(&command) {
-        if args_count < required {
-            let message = missing_args_message(&command);
-            return Err(anyhow!(message));
+    if !invocation.missing_arguments().is_empty() {
+        return Err(anyhow!(invocation.missing_args_message()));
+    }
+    Ok(Some(invocation.render()))
+}
+
+/// All custom commands, global and project. A project command replaces a
+/// global one with the same name.
+pub async fn list_custom_commands() -> Result<Vec<CustomCommand>> {
+    list_commands_in(&command_dirs()?).await
+}
+
+async fn list_commands_in(dirs: &[PathBuf]) -> Result<Vec<CustomCommand>> {
+    let mut commands: Vec<CustomCommand> = Vec::new();
+
+    for dir in dirs {
+        for (name, path) in command_files(dir) {
+            match load_command_from_path(&path, name).await {
+                Ok(command) => {
+                    commands.retain(|existing| existing.name != command.name);
+                    commands.push(command);
+                }
+                Err(e) => eprintln!("Failed to load command {:?}: {}", path, e),
+            }
         }
     }
 
-    let message = command.render(args_raw);
-    Ok(Some(RenderedCustomCommand { command, message }))
+    Ok(commands)
 }
 
-pub async fn list_custom_commands() -> Result<Vec<CustomCommand>> {
-    let dir = commands_dir()?;
-    if !dir.exists() {
-        return Ok(Vec::new());
+/// Commands sorted into groups by namespace, with commands that have no
+/// namespace first
+pub fn group_by_namespace(commands: &[CustomCommand]) -> Vec<(Option<&str>, Vec<&CustomCommand>)> {
+    let mut groups: Vec<(Option<&str>, Vec<&CustomCommand>)> = Vec::new();
+    for command in commands {
+        let namespace = command.namespace();
+        match groups
+            .iter_mut()
+            .find(|(existing, _)| *existing == namespace)
+        {
+            Some((_, group)) => group.push(command),
+            None => groups.push((namespace, vec![command])),
+        }
+    }
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    for (_, group) in &mut groups {
+        group.sort_by(|a, b| a.name.cmp(&b.name));
     }
+    groups
+}
 
-    let mut entries = fs::read_dir(&dir).await?;
-    let mut commands = Vec::new();
+/// Names of all custom commands, without reading them, for completion
+pub fn custom_command_names() -> Vec<String> {
+    let mut names: Vec<String> = command_dirs()
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|dir| command_files(dir))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
 
-    while let Some(entry) = entries.next_entry().await? {
+/// Every command file under `dir` with its command name. Files in
+/// subdirectories are namespaced, so `git/commit.md` is `git:commit`.
+pub fn command_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
         let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("md") {
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
             continue;
-        }
-        let name = match path.file_stem().and_then(|s| s.to_str()) {
-            Some(name) if !name.trim().is_empty() => name.to_string(),
-            _ => continue,
         };
-        match load_command_from_path(&path, name).await {
-            Ok(command) => commands.push(command),
-            Err(e) => eprintln!("Failed to load command {:?}: {}", path, e),
+        if stem.trim().is_empty() || stem.starts_with('.') || stem.contains(':') {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(
+                command_files(&path)
+                    .into_iter()
+                    .map(|(name, path)| (format!("{}:{}", stem, name), path)),
+            );
+        } else if path.extension().and_then(|s| s.to_str()) == Some("md") {
+            files.push((stem.to_string(), path));
         }
     }
-
-    Ok(commands)
+    files
 }
 
 pub async fn load_custom_command(name: &str) -> Result<Option<CustomCommand>> {
     let normalized = normalize_command_name(name)?;
-    let path = command_file_path(&normalized)?;
-    if !path.exists() {
+    let Some(path) = find_command_file(&normalized)? else {
         return Ok(None);
-    }
+    };
     let command = load_command_from_path(&path, normalized).await?;
     Ok(Some(command))
 }
 
+/// Save `command` where it already lives, or in the global directory if
+/// it's new
 pub async fn save_custom_command(command: &CustomCommand) -> Result<()> {
     let normalized = normalize_command_name(&command.name)?;
-    let dir = commands_dir()?;
-    fs::create_dir_all(&dir).await?;
-    let path = command_file_path(&normalized)?;
+    let path = match find_command_file(&normalized)? {
+        Some(path) => path,
+        None => commands_dir()?.join(command_relative_path(&normalized)),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
     let content = CustomCommand {
         name: normalized,
         ..command.clone()
@@ -215,8 +687,7 @@ pub async fn save_custom_command(command: &CustomCommand) -> Result<()> {
 
 pub async fn delete_custom_command(name: &str) -> Result<()> {
     let normalized = normalize_command_name(name)?;
-    let path = command_file_path(&normalized)?;
-    if path.exists() {
+    if let Some(path) = find_command_file(&normalized)? {
         fs::remove_file(path).await?;
     }
     Ok(())
@@ -342,28 +813,6 @@ fn strip_quotes(value: &str) -> &str {
     trimmed
 }
 
-fn required_arg_count(command: &CustomCommand) -> Option<usize> {
-    let mut required = 0usize;
-    if command.content.contains("$ARGUMENTS") {
-        required = 1;
-    }
-
-    let re = Regex::new(r"\$(\d+)").expect("valid regex");
-    for caps in re.captures_iter(&command.content) {
-        if let Ok(idx) = caps[1].parse::<usize>() {
-            if idx > required {
-                required = idx;
-            }
-        }
-    }
-
-    if required > 0 {
-        Some(required)
-    } else {
-        None
-    }
-}
-
 fn missing_args_message(command: &CustomCommand) -> String {
     let base = format!("Command '/{}' requires arguments.", command.name);
     if let Some(hint) = command
@@ -387,15 +836,41 @@ fn normalize_command_name(name: &str) -> Result<String> {
     if trimmed.contains('/') || trimmed.contains('\\') {
         return Err(anyhow!("Command name cannot contain path separators"));
     }
+    if trimmed
+        .split(':')
+        .any(|part| part.trim().is_empty() || part.starts_with('.'))
+    {
+        return Err(anyhow!("Invalid command name '{}'", trimmed));
+    }
     Ok(trimmed.to_string())
 }
 
-fn commands_dir() -> Result<PathBuf> {
+/// The file for command `name` relative to a commands directory: each
+/// namespace is a subdirectory
+fn command_relative_path(name: &str) -> PathBuf {
+    let mut parts: Vec<&str> = name.split(':').collect();
+    let file = format!("{}.md", parts.pop().unwrap_or(name));
+    parts.iter().collect::<PathBuf>().join(file)
+}
+
+static CONFIGURED_COMMANDS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` for custom commands, as set by the `commands_dir` config option.
+/// `FLEXORAMA_COMMANDS_DIR` still takes precedence.
+pub fn set_commands_dir(dir: PathBuf) {
+    let _ = CONFIGURED_COMMANDS_DIR.set(dir);
+}
+
+/// The global custom commands directory, where new commands are saved
+pub fn commands_dir() -> Result<PathBuf> {
     if let Ok(dir) = std::env::var("FLEXORAMA_COMMANDS_DIR") {
         if !dir.trim().is_empty() {
             return Ok(PathBuf::from(dir));
         }
     }
+    if let Some(dir) = CONFIGURED_COMMANDS_DIR.get() {
+        return Ok(dir.clone());
+    }
     let dir = dirs::home_dir()
         .ok_or_else(|| anyhow!("Home directory not found"))?
         .join(".flexorama")
@@ -403,8 +878,25 @@ fn commands_dir() -> Result<PathBuf> {
     Ok(dir)
 }
 
-fn command_file_path(name: &str) -> Result<PathBuf> {
-    Ok(commands_dir()?.join(format!("{}.md", name)))
+/// Directories to load commands from, lowest precedence first: the global
+/// directory, then the project's `.flexorama/commands`
+fn command_dirs() -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![commands_dir()?];
+    if let Some(project) = project_config::project_dir("commands") {
+        if !dirs.contains(&project) {
+            dirs.push(project);
+        }
+    }
+    Ok(dirs)
+}
+
+/// The file that defines command `name`, project first
+fn find_command_file(name: &str) -> Result<Option<PathBuf>> {
+    Ok(command_dirs()?
+        .into_iter()
+        .rev()
+        .map(|dir| dir.join(command_relative_path(name)))
+        .find(|path| path.is_file()))
 }
 
 async fn load_command_from_path(path: &Path, name: String) -> Result<CustomCommand> {
@@ -517,6 +1009,254 @@ Fix $ARGUMENTS"#;
         assert_eq!(rendered, "One=alpha Two=beta Rest=alpha beta gamma");
     }
 
+    #[test]
+    fn test_render_named_and_quoted_args() {
+        let cmd = CustomCommand {
+            name: "deploy".to_string(),
+            description: None,
+            argument_hint: Some("[env] [branch]".to_string()),
+            allowed_tools: Vec::new(),
+            model: None,
+            content: "Deploy {{branch}} to {{ env }} ($1)".to_string(),
+        };
+        assert_eq!(cmd.render("prod main"), "Deploy main to prod (prod)");
+        assert_eq!(
+            cmd.render("branch=\"fix/a b\" env=staging"),
+            "Deploy fix/a b to staging (staging)"
+        );
+        assert_eq!(
+            split_arguments("don't \"a b\" x='c d'"),
+            vec!["don't", "a b", "x=c d"]
+        );
+        assert_eq!(split_arguments("say \"hi"), vec!["say", "\"hi"]);
+    }
+
+    #[test]
+    fn test_missing_arguments_are_named_from_hint() {
+        let mut invocation = CustomCommandInvocation {
+            command: CustomCommand {
+                name: "ticket".to_string(),
+                description: None,
+                argument_hint: Some("<project> <title...>".to_string()),
+                allowed_tools: Vec::new(),
+                model: None,
+                content: "File $2 in $1 as {{assignee}}".to_string(),
+            },
+            args: "core".to_string(),
+        };
+        assert_eq!(invocation.missing_arguments(), vec!["title", "assignee"]);
+
+        invocation.supply("title", "Crash on \"save\"");
+        invocation.supply("assignee", "sam");
+        assert!(invocation.missing_arguments().is_empty());
+        assert_eq!(
+            invocation.render().message,
+            "File Crash on \"save\" in core as sam"
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_run_shell_commands_respects_security() {
+        let security = crate::security::BashSecurity {
+            allowed_commands: ["echo".to_string()].into_iter().collect(),
+            denied_commands: ["rm".to_string()].into_iter().collect(),
+            ask_for_permission: false,
+            enabled: true,
+        };
+        let manager = RwLock::new(BashSecurityManager::new(security));
+        let command = CustomCommand {
+            name: "commit".to_string(),
+            description: None,
+            argument_hint: None,
+            allowed_tools: Vec::new(),
+            model: None,
+            content: "Staged:\n!`echo $1`\nDone".to_string(),
+        };
+
+        let mut rendered = CustomCommandInvocation {
+            command: command.clone(),
+            args: "diff".to_string(),
+        }
+        .render();
+        rendered
+            .run_shell_commands(&manager, false, &ConversationEnv::default())
+            .await
+            .unwrap();
+        assert_eq!(rendered.message, "Staged:\ndiff\nDone");
+
+        let mut rendered = CustomCommandInvocation {
+            command: CustomCommand {
+                content: "!`rm -rf target`".to_string(),
+                ..command
+            },
+            args: String::new(),
+        }
+        .render();
+        let err = rendered
+            .run_shell_commands(&manager, false, &ConversationEnv::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not allowed"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_arguments_cannot_add_shell_commands() {
+        let manager = RwLock::new(BashSecurityManager::new(crate::security::BashSecurity {
+            allowed_commands: ["echo".to_string()].into_iter().collect(),
+            denied_commands: Default::default(),
+            ask_for_permission: false,
+            enabled: true,
+        }));
+        let mut rendered = CustomCommandInvocation {
+            command: CustomCommand {
+                name: "greet".to_string(),
+                description: None,
+                argument_hint: None,
+                allowed_tools: Vec::new(),
+                model: None,
+                content: "!`echo $1` / !`echo $ARGUMENTS` / $ARGUMENTS".to_string(),
+            },
+            args: "\"x; echo pwned $(echo pwned) it's\" !`echo pwned`".to_string(),
+        }
+        .render();
+        rendered
+            .run_shell_commands(&manager, false, &ConversationEnv::default())
+            .await
+            .unwrap();
+
+        let output = "x; echo pwned $(echo pwned) it's";
+        let args = format!("\"{}\" !`echo pwned`", output);
+        assert_eq!(
+            rendered.message,
+            format!("{} / {} !`echo pwned` / {}", output, output, args)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_project_commands_shadow_global_ones() {
+        let global = tempdir().expect("tempdir");
+        let project = tempdir().expect("tempdir");
+        std::fs::write(global.path().join("review.md"), "Global review").unwrap();
+        std::fs::write(global.path().join("deploy.md"), "Deploy").unwrap();
+        std::fs::write(project.path().join("review.md"), "Project review").unwrap();
+
+        let mut commands =
+            list_commands_in(&[global.path().to_path_buf(), project.path().to_path_buf()])
+                .await
+                .unwrap();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].name, "deploy");
+        assert_eq!(commands[1].name, "review");
+        assert_eq!(commands[1].content, "Project review");
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_commands_from_subdirectories() {
+        let dir = tempdir().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("git").join("remote")).unwrap();
+        std::fs::write(dir.path().join("deploy.md"), "Deploy").unwrap();
+        std::fs::write(dir.path().join("git").join("commit.md"), "Commit").unwrap();
+        std::fs::write(dir.path().join("git/remote/add.md"), "Add remote").unwrap();
+
+        let commands = list_commands_in(&[dir.path().to_path_buf()]).await.unwrap();
+        let groups = group_by_namespace(&commands);
+        let summary: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|(namespace, group)| (*namespace, group.iter().map(|c| c.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (None, vec!["deploy"]),
+                (Some("git"), vec!["git:commit"]),
+                (Some("git:remote"), vec!["git:remote:add"]),
+            ]
+        );
+
+        assert_eq!(
+            command_relative_path("git:remote:add"),
+            Path::new("git").join("remote").join("add.md")
+        );
+        assert!(normalize_command_name("git:").is_err());
+        assert!(normalize_command_name("git:../x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expand_file_references() {
+        let temp = tempdir().expect("tempdir");
+        let config = temp.path().join("config.toml");
+        std::fs::write(&config, "debug = true\n").unwrap();
+        let config = config.to_string_lossy().to_string();
+
+        let mut rendered = CustomCommandInvocation {
+            command: CustomCommand {
+                name: "review-config".to_string(),
+                description: None,
+                argument_hint: None,
+                allowed_tools: Vec::new(),
+                model: None,
+                content: format!("Review @{}. Ask me@example.com or @{{missing}}", config),
+            },
+            args: String::new(),
+        }
+        .render();
+        rendered.expand_file_references().await;
+
+        assert_eq!(
+            rendered.message,
+            format!(
+                "Review {}. Ask me@example.com or @{{missing}}\n\n{}",
+                config,
+                context_block(&format!("file '{}'", config), "debug = true")
+            )
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_expand_never_runs_snippets_from_included_files() {
+        let temp = tempdir().expect("tempdir");
+        let notes = temp.path().join("notes.md");
+        std::fs::write(&notes, "!`echo pwned`").unwrap();
+        let notes = notes.to_string_lossy().to_string();
+        let manager = RwLock::new(BashSecurityManager::new(crate::security::BashSecurity {
+            allowed_commands: ["echo".to_string()].into_iter().collect(),
+            denied_commands: Default::default(),
+            ask_for_permission: false,
+            enabled: true,
+        }));
+
+        let mut rendered = CustomCommandInvocation {
+            command: CustomCommand {
+                name: "summarize".to_string(),
+                description: None,
+                argument_hint: None,
+                allowed_tools: Vec::new(),
+                model: None,
+                content: format!("Summarize @{} for !`echo me`", notes),
+            },
+            args: String::new(),
+        }
+        .render();
+        rendered
+            .expand(&manager, false, &ConversationEnv::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rendered.message,
+            format!(
+                "Summarize {} for me\n\n{}",
+                notes,
+                context_block(&format!("file '{}'", notes), "!`echo pwned`")
+            )
+        );
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_render_custom_command_requires_args() {
@@ -0,0 +1,165 @@
+use crate::anthropic::ContentBlock;
+use crate::config::CustomProviderConfig;
+use crate::llm::{ContentCallback, LlmProvider, LlmRequest, LlmResponse};
+use crate::openai::OpenAIClient;
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+
+/// What a custom provider's models can do, from its `[[providers]]` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub tools: bool,
+    pub images: bool,
+    pub streaming: bool,
+}
+
+impl From<&CustomProviderConfig> for Capabilities {
+    fn from(config: &CustomProviderConfig) -> Self {
+        Self {
+            tools: config.tools,
+            images: config.images,
+            streaming: config.streaming,
+        }
+    }
+}
+
+/// A provider declared in the config. The API is OpenAI's; requests are
+/// cut down to what the server's models can handle.
+pub struct CustomProviderClient {
+    inner: OpenAIClient,
+    capabilities: Capabilities,
+}
+
+impl CustomProviderClient {
+    pub fn new(
+        name: &'static str,
+        api_key: String,
+        base_url: String,
+        capabilities: Capabilities,
+    ) -> Self {
+        Self {
+            inner: OpenAIClient::new(api_key, base_url).with_name(name),
+            capabilities,
+        }
+    }
+
+    fn adapt<'a>(&self, mut request: LlmRequest<'a>) -> LlmRequest<'a> {
+        if !self.capabilities.tools {
+            request.tools = &[];
+        }
+        if !self.capabilities.images {
+            for message in &mut request.messages {
+                for block in &mut message.content {
+                    if block.source.is_some() {
+                        *block = ContentBlock::text(
+                            "[An image was left out: this model doesn't accept images]".to_string(),
+                        );
+                    }
+                }
+            }
+        }
+        request
+    }
+}
+
+impl LlmProvider for CustomProviderClient {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>> {
+        LlmProvider::create_message(&self.inner, self.adapt(request))
+    }
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>> {
+        let request = self.adapt(request);
+        if self.capabilities.streaming {
+            return LlmProvider::create_message_stream(&self.inner, request, on_content);
+        }
+        Box::pin(async move {
+            let response = LlmProvider::create_message(&self.inner, request).await?;
+            for text in response
+                .content
+                .iter()
+                .filter_map(|block| block.text.as_ref())
+            {
+                on_content(text.clone());
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anthropic::Message;
+    use crate::llm::tests::{configure_no_proxy, spawn_server};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn leaves_out_what_the_models_cant_handle() {
+        configure_no_proxy();
+        let seen = Arc::new(Mutex::new(Vec::<Value>::new()));
+        let recorded = seen.clone();
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move |Json(body): Json<Value>| async move {
+                recorded.lock().unwrap().push(body);
+                Json(json!({
+                    "choices": [{"message": {"role": "assistant", "content": "a cat"}}]
+                }))
+            }),
+        );
+        let base_url = spawn_server(app).await;
+
+        let client = CustomProviderClient::new(
+            "llama.cpp",
+            String::new(),
+            base_url,
+            Capabilities {
+                tools: false,
+                images: false,
+                streaming: false,
+            },
+        );
+        let tools = [crate::tools::glob::create_glob_tool()];
+        let streamed = Arc::new(Mutex::new(String::new()));
+        let on_content = streamed.clone();
+        let response = LlmProvider::create_message_stream(
+            &client,
+            LlmRequest {
+                model: "qwen3",
+                messages: vec![Message {
+                    role: "user".to_string(),
+                    content: vec![
+                        ContentBlock::image("image/png".to_string(), "aGk=".to_string()),
+                        ContentBlock::text("What is this?".to_string()),
+                    ],
+                }],
+                tools: &tools,
+                max_tokens: 16,
+                temperature: 0.0,
+                system_prompt: None,
+                cancellation_flag: Arc::new(AtomicBool::new(false)),
+                on_delta: None,
+            },
+            Arc::new(move |text| on_content.lock().unwrap().push_str(&text)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.content[0].text.as_deref(), Some("a cat"));
+        assert_eq!(*streamed.lock().unwrap(), "a cat");
+        let body = &seen.lock().unwrap()[0];
+        assert_eq!(body["stream"], json!(false));
+        assert!(body.get("tools").is_none());
+        let request = body.to_string();
+        assert!(!request.contains("aGk="));
+        assert!(request.contains("doesn't accept images"));
+    }
+}
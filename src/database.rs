@@ -7,6 +7,9 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use uuid::Uuid;
 
+/// How long a connection waits for another writer before giving up
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Database manager for Flexorama
 pub struct DatabaseManager {
     pool: SqlitePool,
@@ -54,9 +57,14 @@ impl DatabaseManager {
             debug!("Database file already exists, skipping creation test");
         }
 
-        // Connect with create_if_missing option
+        // WAL lets readers carry on while a conversation is being written, and the
+        // busy timeout makes concurrent writers wait their turn instead of failing
+        // with "database is locked"
         let connect_opts = sqlx::sqlite::SqliteConnectOptions::from_str(&connection_string)?
-            .create_if_missing(true);
+            .create_if_missing(true)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
 
         let pool = SqlitePool::connect_with(connect_opts).await?;
 
@@ -108,6 +116,43 @@ impl DatabaseManager {
         .await
         .ok(); // Ignore error if column already exists
 
+        // Optional human-friendly name, unique among named conversations
+        sqlx::query("ALTER TABLE conversations ADD COLUMN name TEXT")
+            .execute(&self.pool)
+            .await
+            .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_conversations_name ON conversations(name)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Generated title and rolling summary, and how many messages the summary covers
+        for column in [
+            "title TEXT",
+            "summary TEXT",
+            "summarized_messages INTEGER NOT NULL DEFAULT 0",
+        ] {
+            sqlx::query(&format!("ALTER TABLE conversations ADD COLUMN {}", column))
+                .execute(&self.pool)
+                .await
+                .ok(); // Ignore error if column already exists
+        }
+
+        // The directory a conversation was started in, and its token usage split
+        // into input and output so its cost can be estimated
+        for column in [
+            "workspace_path TEXT",
+            "input_tokens INTEGER NOT NULL DEFAULT 0",
+            "output_tokens INTEGER NOT NULL DEFAULT 0",
+        ] {
+            sqlx::query(&format!("ALTER TABLE conversations ADD COLUMN {}", column))
+                .execute(&self.pool)
+                .await
+                .ok(); // Ignore error if column already exists
+        }
+
         // Create messages table
         sqlx::query(
             r#"
@@ -208,6 +253,35 @@ impl DatabaseManager {
         .execute(&self.pool)
         .await?;
 
+        // Create per-conversation settings table; NULL columns inherit the global setting
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversation_settings (
+                conversation_id TEXT PRIMARY KEY,
+                temperature REAL,
+                plan_mode BOOLEAN,
+                active_skills TEXT,
+                FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Create prompt history table; prompts outlive the conversations they were sent in
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS prompt_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                prompt TEXT NOT NULL,
+                conversation_id TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
         // Create indexes for better performance
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_messages_conversation_id ON messages(conversation_id)",
@@ -241,6 +315,12 @@ impl DatabaseManager {
             .execute(&self.pool)
             .await?;
 
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_prompt_history_created_at ON prompt_history(created_at)",
+        )
+        .execute(&self.pool)
+        .await?;
+
         debug!("Database migrations completed successfully");
         Ok(())
     }
@@ -250,6 +330,16 @@ impl DatabaseManager {
         &self.db_path
     }
 
+    /// Get the database size in bytes (also serves as a reachability check)
+    pub async fn size_bytes(&self) -> Result<i64> {
+        let row = sqlx::query(
+            "SELECT page_count * page_size AS size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.get("size"))
+    }
+
     /// Close the database connection pool
     pub async fn close(&self) {
         self.pool.close().await;
@@ -309,10 +399,38 @@ pub fn create_slug_from_path(path: &str) -> String {
     slug
 }
 
+/// Check a conversation name: non-empty, at most 64 characters, and without
+/// whitespace at either end or control characters
+pub fn validate_conversation_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("Conversation name cannot be empty"));
+    }
+    if name.trim() != name {
+        return Err(anyhow!(
+            "Conversation name cannot start or end with whitespace"
+        ));
+    }
+    if name.chars().count() > 64 {
+        return Err(anyhow!("Conversation name is limited to 64 characters"));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(anyhow!(
+            "Conversation name cannot contain control characters"
+        ));
+    }
+    Ok(())
+}
+
 /// Represents a conversation in the database
 #[derive(Debug, Clone)]
 pub struct Conversation {
     pub id: String,
+    /// Set with `--session-name` or `/rename`
+    pub name: Option<String>,
+    /// Generated in the background after the first turns
+    pub title: Option<String>,
+    /// Rolling summary of the conversation, also generated in the background
+    pub summary: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub system_prompt: Option<String>,
@@ -331,6 +449,32 @@ pub struct Message {
     pub created_at: DateTime<Utc>,
 }
 
+/// A page of conversation messages in ascending order
+#[derive(Debug, Clone)]
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    pub has_more: bool,
+    /// Creation time of the cursor message the page ends before
+    pub before: Option<DateTime<Utc>>,
+}
+
+/// A conversation's generated title and summary
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversationSummary {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    /// How many messages the summary covers
+    pub summarized_messages: usize,
+}
+
+/// Settings that override the global defaults for a single conversation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversationSettings {
+    pub temperature: Option<f32>,
+    pub plan_mode: Option<bool>,
+    pub active_skills: Option<Vec<String>>,
+}
+
 /// Represents a tool call tied to a conversation
 #[derive(Debug, Clone)]
 pub struct ToolCallRecord {
@@ -342,6 +486,14 @@ pub struct ToolCallRecord {
     pub created_at: DateTime<Utc>,
 }
 
+/// A prompt from the history, with how often it was sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptHistoryEntry {
+    pub prompt: String,
+    pub last_used: DateTime<Utc>,
+    pub uses: i64,
+}
+
 /// Represents a saved plan
 #[derive(Debug, Clone)]
 pub struct Plan {
@@ -372,6 +524,21 @@ pub struct ModelStats {
     pub request_count: i32,
 }
 
+/// Usage of the conversations started in one directory with one model
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectModelStats {
+    /// None for conversations from before the directory was recorded
+    pub workspace_path: Option<String>,
+    pub model: String,
+    pub total_conversations: i64,
+    pub total_tokens: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub request_count: i64,
+    pub tool_calls: i64,
+    pub tool_errors: i64,
+}
+
 /// Overview of all statistics
 #[derive(Debug, Serialize)]
 pub struct StatsOverview {
@@ -399,8 +566,8 @@ impl DatabaseManager {
 
         sqlx::query(
             r#"
-            INSERT INTO conversations (id, created_at, updated_at, system_prompt, model, subagent, total_tokens, request_count)
-            VALUES (?, ?, ?, ?, ?, ?, 0, 0)
+            INSERT INTO conversations (id, created_at, updated_at, system_prompt, model, subagent, workspace_path, total_tokens, request_count)
+            VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0)
             "#
         )
         .bind(&conversation_id)
@@ -409,6 +576,11 @@ impl DatabaseManager {
         .bind(&system_prompt)
         .bind(model)
         .bind(subagent)
+        .bind(
+            std::env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().to_string()),
+        )
         .execute(&self.pool)
         .await?;
 
@@ -424,13 +596,32 @@ impl DatabaseManager {
         content: &str,
         model: &str,
         tokens: i32,
+    ) -> Result<String> {
+        self.add_message_with_tool_calls(conversation_id, role, content, model, tokens, &[])
+            .await
+    }
+
+    /// Add a message and the tool calls it made in a single transaction, so a
+    /// turn costs one write rather than one per tool
+    pub async fn add_message_with_tool_calls(
+        &self,
+        conversation_id: &str,
+        role: &str,
+        content: &str,
+        model: &str,
+        tokens: i32,
+        tool_calls: &[ToolCallRecord],
     ) -> Result<String> {
         let message_id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
         debug!(
-            "Adding message {} to conversation {}: {} ({} tokens)",
-            message_id, conversation_id, role, tokens
+            "Adding message {} to conversation {}: {} ({} tokens, {} tool calls)",
+            message_id,
+            conversation_id,
+            role,
+            tokens,
+            tool_calls.len()
         );
 
         // Begin transaction
@@ -470,6 +661,8 @@ impl DatabaseManager {
         .execute(&mut *tx)
         .await?;
 
+        Self::upsert_tool_calls(&mut tx, conversation_id, Some(&message_id), tool_calls).await?;
+
         // Commit transaction
         tx.commit().await?;
 
@@ -613,7 +806,7 @@ impl DatabaseManager {
     pub async fn get_conversation(&self, conversation_id: &str) -> Result<Option<Conversation>> {
         let row = sqlx::query(
             r#"
-            SELECT id, created_at, updated_at, system_prompt, model, subagent, total_tokens, request_count
+            SELECT id, name, title, summary, created_at, updated_at, system_prompt, model, subagent, total_tokens, request_count
             FROM conversations
             WHERE id = ?
             "#,
@@ -625,6 +818,9 @@ impl DatabaseManager {
         if let Some(row) = row {
             Ok(Some(Conversation {
                 id: row.get("id"),
+                name: row.get("name"),
+                title: row.get("title"),
+                summary: row.get("summary"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
                 system_prompt: row.get("system_prompt"),
@@ -638,6 +834,79 @@ impl DatabaseManager {
         }
     }
 
+    /// The ID of the conversation with this name
+    pub async fn find_conversation_by_name(&self, name: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT id FROM conversations WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("id")))
+    }
+
+    /// Name a conversation, or clear its name with None. Names are unique.
+    pub async fn set_conversation_name(
+        &self,
+        conversation_id: &str,
+        name: Option<&str>,
+    ) -> Result<()> {
+        if let Some(name) = name {
+            validate_conversation_name(name)?;
+            if let Some(existing) = self.find_conversation_by_name(name).await? {
+                if existing != conversation_id {
+                    return Err(anyhow!(
+                        "Another conversation is already named '{}' ({})",
+                        name,
+                        existing
+                    ));
+                }
+            }
+        }
+        let result = sqlx::query("UPDATE conversations SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("Conversation {} not found", conversation_id));
+        }
+        Ok(())
+    }
+
+    /// IDs of conversations whose ID starts with `prefix`
+    pub async fn find_conversation_ids(&self, prefix: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT id FROM conversations WHERE id LIKE ? || '%' ORDER BY id")
+            .bind(prefix.replace(['%', '_'], ""))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
+
+    /// Delete a conversation along with its messages, tool calls and settings.
+    /// Returns false if there was no such conversation.
+    pub async fn delete_conversation(&self, conversation_id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        // Cascades only happen with foreign keys on, so clean up explicitly
+        for statement in [
+            "DELETE FROM tool_calls WHERE conversation_id = ?",
+            "DELETE FROM messages WHERE conversation_id = ?",
+            "DELETE FROM context_files WHERE conversation_id = ?",
+            "DELETE FROM conversation_settings WHERE conversation_id = ?",
+            "UPDATE plans SET conversation_id = NULL WHERE conversation_id = ?",
+        ] {
+            sqlx::query(statement)
+                .bind(conversation_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let result = sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        crate::conversation_lock::remove(&self.db_path, conversation_id);
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Get all messages for a conversation
     pub async fn get_conversation_messages(&self, conversation_id: &str) -> Result<Vec<Message>> {
         let rows = sqlx::query(
@@ -665,52 +934,176 @@ impl DatabaseManager {
         Ok(messages)
     }
 
-    /// Save a tool call for a conversation
-    pub async fn add_tool_call(
+    /// Get up to `limit` messages recorded before the `before` message (or the newest
+    /// messages when no cursor is given). Returns `None` when the cursor is unknown.
+    pub async fn get_conversation_messages_page(
         &self,
         conversation_id: &str,
-        message_id: Option<&str>,
-        tool_use_id: &str,
-        tool_name: &str,
-        tool_arguments: &str,
-    ) -> Result<()> {
-        sqlx::query(
+        before: Option<&str>,
+        limit: i64,
+    ) -> Result<Option<MessagePage>> {
+        let before_at: Option<DateTime<Utc>> = match before {
+            Some(message_id) => {
+                let created_at = sqlx::query_scalar(
+                    "SELECT created_at FROM messages WHERE id = ? AND conversation_id = ?",
+                )
+                .bind(message_id)
+                .bind(conversation_id)
+                .fetch_optional(&self.pool)
+                .await?;
+                match created_at {
+                    Some(created_at) => Some(created_at),
+                    None => return Ok(None),
+                }
+            }
+            None => None,
+        };
+
+        // Fetch one extra row to learn whether older messages remain
+        let rows = sqlx::query(
             r#"
-            INSERT INTO tool_calls (id, conversation_id, message_id, tool_name, tool_arguments, is_error)
-            VALUES (?, ?, ?, ?, ?, false)
+            SELECT id, role, content, created_at
+            FROM messages
+            WHERE conversation_id = ?
+              AND (? IS NULL OR julianday(created_at) < julianday(?))
+            ORDER BY created_at DESC
+            LIMIT ?
             "#,
         )
-        .bind(tool_use_id)
         .bind(conversation_id)
-        .bind(message_id)
-        .bind(tool_name)
-        .bind(tool_arguments)
-        .execute(&self.pool)
+        .bind(before_at)
+        .bind(before_at)
+        .bind(limit + 1)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(())
+        let has_more = rows.len() as i64 > limit;
+        let mut messages: Vec<Message> = rows
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|row| Message {
+                id: row.get("id"),
+                role: row.get("role"),
+                content: row.get("content"),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+        messages.reverse();
+
+        Ok(Some(MessagePage {
+            messages,
+            has_more,
+            before: before_at,
+        }))
     }
 
-    /// Update a tool call with its result content
-    pub async fn complete_tool_call(
+    /// Remove a message and everything recorded after it in the same conversation
+    pub async fn truncate_conversation_from(
         &self,
-        tool_use_id: &str,
-        result_content: &str,
-        is_error: bool,
+        conversation_id: &str,
+        message_id: &str,
     ) -> Result<()> {
+        let created_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT created_at FROM messages WHERE id = ? AND conversation_id = ?",
+        )
+        .bind(message_id)
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let created_at = created_at.ok_or_else(|| {
+            anyhow!(
+                "Message {} not found in conversation {}",
+                message_id,
+                conversation_id
+            )
+        })?;
+
+        debug!(
+            "Truncating conversation {} from message {} ({})",
+            conversation_id, message_id, created_at
+        );
+
+        let mut tx = self.pool.begin().await?;
+
+        // Tool calls go with the messages they belong to. Ones saved without
+        // a message are compared by time, at the same precision as messages.
         sqlx::query(
             r#"
-            UPDATE tool_calls
-            SET result_content = ?, is_error = ?, created_at = created_at
-            WHERE id = ?
+            DELETE FROM tool_calls WHERE conversation_id = ? AND (
+                message_id IN (
+                    SELECT id FROM messages
+                    WHERE conversation_id = ? AND julianday(created_at) >= julianday(?)
+                )
+                OR (message_id IS NULL AND julianday(created_at) >= julianday(?))
+            )
             "#,
         )
-        .bind(result_content)
-        .bind(is_error)
-        .bind(tool_use_id)
-        .execute(&self.pool)
+        .bind(conversation_id)
+        .bind(conversation_id)
+        .bind(created_at)
+        .bind(created_at)
+        .execute(&mut *tx)
         .await?;
 
+        sqlx::query(
+            "DELETE FROM messages WHERE conversation_id = ? AND julianday(created_at) >= julianday(?)",
+        )
+        .bind(conversation_id)
+        .bind(created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE conversations SET updated_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Save tool calls for a conversation. A call that's already recorded gets
+    /// its result updated.
+    pub async fn save_tool_calls(
+        &self,
+        conversation_id: &str,
+        tool_calls: &[ToolCallRecord],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::upsert_tool_calls(&mut tx, conversation_id, None, tool_calls).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn upsert_tool_calls(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        conversation_id: &str,
+        message_id: Option<&str>,
+        tool_calls: &[ToolCallRecord],
+    ) -> Result<()> {
+        for call in tool_calls {
+            sqlx::query(
+                r#"
+                INSERT INTO tool_calls (id, conversation_id, message_id, tool_name, tool_arguments, result_content, is_error, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(id) DO UPDATE SET
+                    result_content = excluded.result_content,
+                    is_error = excluded.is_error
+                "#,
+            )
+            .bind(&call.id)
+            .bind(conversation_id)
+            .bind(message_id)
+            .bind(&call.tool_name)
+            .bind(&call.tool_arguments)
+            .bind(&call.result_content)
+            .bind(call.is_error)
+            .bind(call.created_at)
+            .execute(&mut **tx)
+            .await?;
+        }
         Ok(())
     }
 
@@ -746,6 +1139,47 @@ impl DatabaseManager {
         Ok(calls)
     }
 
+    /// Fetch tool calls recorded in `[from, until)` for a conversation; either bound may be open
+    pub async fn get_conversation_tool_calls_between(
+        &self,
+        conversation_id: &str,
+        from: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ToolCallRecord>> {
+        // Tool calls use CURRENT_TIMESTAMP (second precision), so compare at that granularity
+        let rows = sqlx::query(
+            r#"
+            SELECT id, tool_name, tool_arguments, result_content, is_error, created_at
+            FROM tool_calls
+            WHERE conversation_id = ?
+              AND (? IS NULL OR julianday(created_at) >= julianday(datetime(?)))
+              AND (? IS NULL OR julianday(created_at) < julianday(datetime(?)))
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(from)
+        .bind(from)
+        .bind(until)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let calls = rows
+            .into_iter()
+            .map(|row| ToolCallRecord {
+                id: row.get("id"),
+                tool_name: row.get("tool_name"),
+                tool_arguments: row.get("tool_arguments"),
+                result_content: row.get("result_content"),
+                is_error: row.get("is_error"),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+
+        Ok(calls)
+    }
+
     /// Get recent conversations, optionally filtered by message content
     pub async fn get_recent_conversations(
         &self,
@@ -766,7 +1200,7 @@ impl DatabaseManager {
         // Base query is shared with /resume; optional filter narrows by message content
         let mut query = String::from(
             r#"
-            SELECT id, created_at, updated_at, system_prompt, model, subagent, total_tokens, request_count
+            SELECT id, name, title, summary, created_at, updated_at, system_prompt, model, subagent, total_tokens, request_count
             FROM conversations c
             WHERE EXISTS (
                 SELECT 1 FROM messages m WHERE m.conversation_id = c.id
@@ -801,6 +1235,9 @@ impl DatabaseManager {
             .into_iter()
             .map(|row| Conversation {
                 id: row.get("id"),
+                name: row.get("name"),
+                title: row.get("title"),
+                summary: row.get("summary"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
                 system_prompt: row.get("system_prompt"),
@@ -836,6 +1273,176 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Update the system prompt for a conversation
+    pub async fn update_conversation_system_prompt(
+        &self,
+        conversation_id: &str,
+        system_prompt: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE conversations SET system_prompt = ?, updated_at = ? WHERE id = ?")
+            .bind(system_prompt)
+            .bind(Utc::now())
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The generated title and summary of a conversation
+    pub async fn get_conversation_summary(
+        &self,
+        conversation_id: &str,
+    ) -> Result<ConversationSummary> {
+        let row = sqlx::query(
+            "SELECT title, summary, summarized_messages FROM conversations WHERE id = ?",
+        )
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Conversation {} not found", conversation_id))?;
+        Ok(ConversationSummary {
+            title: row.get("title"),
+            summary: row.get("summary"),
+            summarized_messages: row.get::<i64, _>("summarized_messages") as usize,
+        })
+    }
+
+    /// Store a generated title and summary. This leaves `updated_at` alone so
+    /// summarizing doesn't reorder the conversation list.
+    pub async fn save_conversation_summary(
+        &self,
+        conversation_id: &str,
+        summary: &ConversationSummary,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE conversations SET title = ?, summary = ?, summarized_messages = ? WHERE id = ?",
+        )
+        .bind(&summary.title)
+        .bind(&summary.summary)
+        .bind(summary.summarized_messages as i64)
+        .bind(conversation_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the settings overrides stored for a conversation
+    pub async fn get_conversation_settings(
+        &self,
+        conversation_id: &str,
+    ) -> Result<ConversationSettings> {
+        let row = sqlx::query(
+            "SELECT temperature, plan_mode, active_skills FROM conversation_settings WHERE conversation_id = ?",
+        )
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(ConversationSettings::default()),
+        };
+
+        let temperature: Option<f64> = row.get("temperature");
+        let active_skills: Option<String> = row.get("active_skills");
+        let active_skills = match active_skills {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        };
+
+        Ok(ConversationSettings {
+            temperature: temperature.map(|t| t as f32),
+            plan_mode: row.get("plan_mode"),
+            active_skills,
+        })
+    }
+
+    /// Replace the settings overrides stored for a conversation
+    pub async fn save_conversation_settings(
+        &self,
+        conversation_id: &str,
+        settings: &ConversationSettings,
+    ) -> Result<()> {
+        let active_skills = settings
+            .active_skills
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO conversation_settings (conversation_id, temperature, plan_mode, active_skills)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(conversation_id) DO UPDATE SET
+                temperature = excluded.temperature,
+                plan_mode = excluded.plan_mode,
+                active_skills = excluded.active_skills
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(settings.temperature.map(|t| t as f64))
+        .bind(settings.plan_mode)
+        .bind(active_skills)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a prompt the user sent. Sending the same prompt twice in a row
+    /// records it once.
+    pub async fn add_prompt_history(
+        &self,
+        prompt: &str,
+        conversation_id: Option<&str>,
+    ) -> Result<()> {
+        let last: Option<String> =
+            sqlx::query_scalar("SELECT prompt FROM prompt_history ORDER BY id DESC LIMIT 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        if last.as_deref() == Some(prompt) {
+            return Ok(());
+        }
+        sqlx::query(
+            "INSERT INTO prompt_history (prompt, conversation_id, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(prompt)
+        .bind(conversation_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Distinct prompts from the history, most recently used first,
+    /// optionally only those containing `search`
+    pub async fn get_prompt_history(
+        &self,
+        limit: i64,
+        search: Option<&str>,
+    ) -> Result<Vec<PromptHistoryEntry>> {
+        let mut query = String::from(
+            "SELECT prompt, MAX(created_at) AS last_used, COUNT(*) AS uses FROM prompt_history",
+        );
+        if search.is_some() {
+            query.push_str(" WHERE LOWER(prompt) LIKE LOWER(?)");
+        }
+        query.push_str(" GROUP BY prompt ORDER BY last_used DESC LIMIT ?");
+
+        let mut sql = sqlx::query(&query);
+        if let Some(search) = search {
+            sql = sql.bind(format!("%{}%", search));
+        }
+        let rows = sql.bind(limit).fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| PromptHistoryEntry {
+                prompt: row.get("prompt"),
+                last_used: row.get("last_used"),
+                uses: row.get("uses"),
+            })
+            .collect())
+    }
+
     /// Update daily usage statistics
     pub async fn update_usage_stats(&self, input_tokens: i32, output_tokens: i32) -> Result<()> {
         let today = Utc::now().date_naive();
@@ -927,6 +1534,43 @@ impl DatabaseManager {
         Ok(stats)
     }
 
+    /// Add a response's token usage to its conversation
+    pub async fn add_conversation_usage(
+        &self,
+        conversation_id: &str,
+        input_tokens: i32,
+        output_tokens: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE conversations
+            SET input_tokens = input_tokens + ?,
+                output_tokens = output_tokens + ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(input_tokens)
+        .bind(output_tokens)
+        .bind(conversation_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Usage per directory and model for the conversations in this database
+    pub async fn get_stats_by_project(
+        &self,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+    ) -> Result<Vec<ProjectModelStats>> {
+        project_stats(&self.pool, start_date, end_date).await
+    }
+
+    /// The file this database is stored in
+    pub fn db_path(&self) -> &std::path::Path {
+        &self.db_path
+    }
+
     /// Get statistics grouped by model
     pub async fn get_stats_by_model(
         &self,
@@ -1107,6 +1751,116 @@ impl DatabaseManager {
     }
 }
 
+/// Usage per directory and model in the database behind `pool`. Databases
+/// that predate the recorded directory and token split report those as
+/// unknown and zero.
+async fn project_stats(
+    pool: &SqlitePool,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Result<Vec<ProjectModelStats>> {
+    let start =
+        start_date.unwrap_or_else(|| Utc::now().naive_utc().date() - chrono::Duration::days(30));
+    let end = end_date.unwrap_or_else(|| Utc::now().naive_utc().date());
+
+    let columns: Vec<String> = sqlx::query("PRAGMA table_info(conversations)")
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+    if columns.is_empty() {
+        return Err(anyhow!("not a Flexorama database"));
+    }
+    let column_or = |name: &str, fallback: &str| {
+        if columns.iter().any(|column| column == name) {
+            format!("c.{}", name)
+        } else {
+            fallback.to_string()
+        }
+    };
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {workspace} as workspace_path,
+            c.model as model,
+            COUNT(*) as total_conversations,
+            COALESCE(SUM(c.total_tokens), 0) as total_tokens,
+            COALESCE(SUM({input}), 0) as input_tokens,
+            COALESCE(SUM({output}), 0) as output_tokens,
+            COALESCE(SUM(c.request_count), 0) as request_count,
+            COALESCE(SUM(t.tool_calls), 0) as tool_calls,
+            COALESCE(SUM(t.tool_errors), 0) as tool_errors
+        FROM conversations c
+        LEFT JOIN (
+            SELECT conversation_id,
+                   COUNT(*) as tool_calls,
+                   SUM(CASE WHEN is_error THEN 1 ELSE 0 END) as tool_errors
+            FROM tool_calls
+            GROUP BY conversation_id
+        ) t ON t.conversation_id = c.id
+        WHERE DATE(c.created_at) BETWEEN ? AND ?
+        GROUP BY 1, 2
+        ORDER BY total_tokens DESC
+        "#,
+        workspace = column_or("workspace_path", "NULL"),
+        input = column_or("input_tokens", "0"),
+        output = column_or("output_tokens", "0"),
+    ))
+    .bind(start.to_string())
+    .bind(end.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ProjectModelStats {
+            workspace_path: row.get("workspace_path"),
+            model: row.get("model"),
+            total_conversations: row.get("total_conversations"),
+            total_tokens: row.get("total_tokens"),
+            input_tokens: row.get("input_tokens"),
+            output_tokens: row.get("output_tokens"),
+            request_count: row.get("request_count"),
+            tool_calls: row.get("tool_calls"),
+            tool_errors: row.get("tool_errors"),
+        })
+        .collect())
+}
+
+/// Usage per directory and model in the database file at `db_path`, which
+/// is opened read-only so other directories' databases are left as they are
+pub async fn project_stats_at(
+    db_path: &std::path::Path,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Result<Vec<ProjectModelStats>> {
+    let connect_opts = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(db_path)
+        .read_only(true)
+        .busy_timeout(BUSY_TIMEOUT);
+    let pool = SqlitePool::connect_with(connect_opts).await?;
+    let stats = project_stats(&pool, start_date, end_date).await;
+    pool.close().await;
+    stats
+}
+
+/// The conversation databases in `dir`, one per directory Flexorama has
+/// been run in
+pub fn database_files(dir: &std::path::Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
 /// Get the database path for the current directory
 pub fn get_database_path() -> Result<PathBuf> {
     // Get current directory
@@ -1140,6 +1894,17 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    fn tool_call(id: &str, result: Option<&str>) -> ToolCallRecord {
+        ToolCallRecord {
+            id: id.to_string(),
+            tool_name: "Read".to_string(),
+            tool_arguments: "{}".to_string(),
+            result_content: result.map(str::to_string),
+            is_error: false,
+            created_at: Utc::now(),
+        }
+    }
+
     async fn create_test_db() -> Result<(DatabaseManager, TempDir)> {
         let temp_dir = TempDir::new()?;
         let db_path = temp_dir.path().join("test.db");
@@ -1247,6 +2012,163 @@ mod tests {
         assert_eq!(page4.len(), 0, "Page beyond available data should be empty");
     }
 
+    #[tokio::test]
+    async fn test_delete_conversation_removes_messages_and_tool_calls() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+        db.add_message(&conv_id, "user", "Hello", "gpt-4", 1)
+            .await
+            .unwrap();
+        db.save_tool_calls(&conv_id, &[tool_call("tool-1", None)])
+            .await
+            .unwrap();
+
+        let prefix = &conv_id[..8];
+        assert_eq!(
+            db.find_conversation_ids(prefix).await.unwrap(),
+            vec![conv_id.clone()]
+        );
+
+        assert!(db.delete_conversation(&conv_id).await.unwrap());
+        assert!(db.get_conversation(&conv_id).await.unwrap().is_none());
+        assert!(db
+            .get_conversation_messages(&conv_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(db
+            .get_conversation_tool_calls(&conv_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!db.delete_conversation(&conv_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_message_and_tool_calls_are_saved_together() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+        db.add_message_with_tool_calls(
+            &conv_id,
+            "assistant",
+            "Reading",
+            "gpt-4",
+            3,
+            &[tool_call("tool-1", Some("ok")), tool_call("tool-2", None)],
+        )
+        .await
+        .unwrap();
+        db.save_tool_calls(&conv_id, &[tool_call("tool-2", Some("done"))])
+            .await
+            .unwrap();
+
+        let calls = db.get_conversation_tool_calls(&conv_id).await.unwrap();
+        let results: Vec<(&str, Option<&str>)> = calls
+            .iter()
+            .map(|call| (call.id.as_str(), call.result_content.as_deref()))
+            .collect();
+        assert_eq!(
+            results,
+            vec![("tool-1", Some("ok")), ("tool-2", Some("done"))]
+        );
+        assert_eq!(
+            db.get_conversation_messages(&conv_id).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writers_wait_for_each_other() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+
+        let db = std::sync::Arc::new(db);
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+        let writers: Vec<_> = (0..20)
+            .map(|i| {
+                let db = db.clone();
+                let conv_id = conv_id.clone();
+                tokio::spawn(async move {
+                    db.add_message(&conv_id, "user", &format!("Message {}", i), "gpt-4", 1)
+                        .await
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+        assert_eq!(
+            db.get_conversation_messages(&conv_id).await.unwrap().len(),
+            20
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conversation_names_are_unique() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let first = db.create_conversation(None, "gpt-4", None).await.unwrap();
+        let second = db.create_conversation(None, "gpt-4", None).await.unwrap();
+
+        db.set_conversation_name(&first, Some("auth refactor"))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.find_conversation_by_name("auth refactor").await.unwrap(),
+            Some(first.clone())
+        );
+        let conversation = db.get_conversation(&first).await.unwrap().unwrap();
+        assert_eq!(conversation.name.as_deref(), Some("auth refactor"));
+
+        // Renaming to the same name is fine; taking another's name is not
+        db.set_conversation_name(&first, Some("auth refactor"))
+            .await
+            .unwrap();
+        assert!(db
+            .set_conversation_name(&second, Some("auth refactor"))
+            .await
+            .is_err());
+        assert!(db.set_conversation_name(&second, Some("  ")).await.is_err());
+
+        db.set_conversation_name(&first, None).await.unwrap();
+        assert!(db
+            .find_conversation_by_name("auth refactor")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .set_conversation_name("missing", Some("x"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conversation_summary_round_trip() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+        assert_eq!(
+            db.get_conversation_summary(&id).await.unwrap(),
+            ConversationSummary::default()
+        );
+        let before = db.get_conversation(&id).await.unwrap().unwrap().updated_at;
+
+        let summary = ConversationSummary {
+            title: Some("Fix the login bug".to_string()),
+            summary: Some("Traced the bug to token expiry.".to_string()),
+            summarized_messages: 4,
+        };
+        db.save_conversation_summary(&id, &summary).await.unwrap();
+        assert_eq!(db.get_conversation_summary(&id).await.unwrap(), summary);
+
+        let conversation = db.get_conversation(&id).await.unwrap().unwrap();
+        assert_eq!(conversation.title.as_deref(), Some("Fix the login bug"));
+        assert_eq!(conversation.updated_at, before);
+        assert!(db.get_conversation_summary("missing").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_get_recent_conversations_backwards_compatibility() {
         // Create test database
@@ -1351,4 +2273,251 @@ mod tests {
 
         assert_eq!(page3.len(), 0, "Third page with filter should be empty");
     }
+
+    #[tokio::test]
+    async fn test_truncate_conversation_from_removes_later_messages() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let role = if i % 2 == 0 { "user" } else { "assistant" };
+            let id = db
+                .add_message(&conv_id, role, &format!("Message {}", i), "gpt-4", 0)
+                .await
+                .unwrap();
+            ids.push(id);
+            tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        }
+
+        db.truncate_conversation_from(&conv_id, &ids[2])
+            .await
+            .unwrap();
+
+        let remaining = db.get_conversation_messages(&conv_id).await.unwrap();
+        let remaining_ids: Vec<String> = remaining.into_iter().map(|m| m.id).collect();
+        assert_eq!(remaining_ids, ids[..2].to_vec());
+
+        let missing = db.truncate_conversation_from(&conv_id, "missing").await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncate_keeps_tool_calls_from_the_same_second() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+
+        db.add_message(&conv_id, "user", "Read it", "gpt-4", 0)
+            .await
+            .unwrap();
+        db.add_message_with_tool_calls(
+            &conv_id,
+            "assistant",
+            "Reading",
+            "gpt-4",
+            0,
+            &[tool_call("kept", Some("done"))],
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        let retry = db
+            .add_message_with_tool_calls(
+                &conv_id,
+                "user",
+                "Read it again",
+                "gpt-4",
+                0,
+                &[tool_call("attached", None)],
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        db.save_tool_calls(&conv_id, &[tool_call("unattached", None)])
+            .await
+            .unwrap();
+
+        db.truncate_conversation_from(&conv_id, &retry)
+            .await
+            .unwrap();
+
+        let calls = db.get_conversation_tool_calls(&conv_id).await.unwrap();
+        let ids: Vec<&str> = calls.iter().map(|call| call.id.as_str()).collect();
+        assert_eq!(ids, vec!["kept"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_conversation_messages_page_walks_backwards() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let id = db
+                .add_message(&conv_id, "user", &format!("Message {}", i), "gpt-4", 0)
+                .await
+                .unwrap();
+            ids.push(id);
+            tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        }
+
+        let newest = db
+            .get_conversation_messages_page(&conv_id, None, 2)
+            .await
+            .unwrap()
+            .unwrap();
+        let newest_ids: Vec<String> = newest.messages.iter().map(|m| m.id.clone()).collect();
+        assert_eq!(newest_ids, ids[3..].to_vec());
+        assert!(newest.has_more);
+
+        let older = db
+            .get_conversation_messages_page(&conv_id, Some(&ids[3]), 3)
+            .await
+            .unwrap()
+            .unwrap();
+        let older_ids: Vec<String> = older.messages.iter().map(|m| m.id.clone()).collect();
+        assert_eq!(older_ids, ids[..3].to_vec());
+        assert!(!older.has_more);
+
+        let unknown = db
+            .get_conversation_messages_page(&conv_id, Some("missing"), 3)
+            .await
+            .unwrap();
+        assert!(unknown.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_history() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        for prompt in [
+            "fix the build",
+            "fix the build",
+            "write tests",
+            "fix the build",
+        ] {
+            db.add_prompt_history(prompt, None).await.unwrap();
+            tokio::time::sleep(tokio::time::Duration::from_millis(2)).await;
+        }
+
+        let history = db.get_prompt_history(10, None).await.unwrap();
+        let prompts: Vec<&str> = history.iter().map(|e| e.prompt.as_str()).collect();
+        assert_eq!(prompts, vec!["fix the build", "write tests"]);
+        // The repeat in a row was recorded once
+        assert_eq!(history[0].uses, 2);
+        assert!(history[0].last_used > history[1].last_used);
+
+        let found = db.get_prompt_history(10, Some("TESTS")).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].prompt, "write tests");
+    }
+
+    #[tokio::test]
+    async fn test_conversation_settings_round_trip() {
+        let (db, _temp_dir) = create_test_db().await.unwrap();
+        let conv_id = db.create_conversation(None, "gpt-4", None).await.unwrap();
+
+        assert_eq!(
+            db.get_conversation_settings(&conv_id).await.unwrap(),
+            ConversationSettings::default()
+        );
+
+        let settings = ConversationSettings {
+            temperature: Some(0.25),
+            plan_mode: Some(true),
+            active_skills: Some(vec!["rust".to_string()]),
+        };
+        db.save_conversation_settings(&conv_id, &settings)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_conversation_settings(&conv_id).await.unwrap(),
+            settings
+        );
+
+        let cleared = ConversationSettings {
+            temperature: None,
+            ..settings
+        };
+        db.save_conversation_settings(&conv_id, &cleared)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_conversation_settings(&conv_id).await.unwrap(),
+            cleared
+        );
+
+        db.update_conversation_system_prompt(&conv_id, Some("Be brief"))
+            .await
+            .unwrap();
+        let conversation = db.get_conversation(&conv_id).await.unwrap().unwrap();
+        assert_eq!(conversation.system_prompt.as_deref(), Some("Be brief"));
+    }
+
+    #[tokio::test]
+    async fn test_stats_by_project() -> Result<()> {
+        let (db, _temp_dir) = create_test_db().await?;
+        let conv_id = db.create_conversation(None, "gpt-4o", None).await?;
+        let mut failed = tool_call("call-2", Some("boom"));
+        failed.is_error = true;
+        db.add_message_with_tool_calls(
+            &conv_id,
+            "assistant",
+            "Reading",
+            "gpt-4o",
+            30,
+            &[tool_call("call-1", Some("ok")), failed],
+        )
+        .await?;
+        db.add_conversation_usage(&conv_id, 20, 10).await?;
+        db.add_conversation_usage(&conv_id, 5, 5).await?;
+
+        let stats = db.get_stats_by_project(None, None).await?;
+        assert_eq!(stats.len(), 1);
+        let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+        assert_eq!(
+            stats[0].workspace_path.as_deref(),
+            Some(current_dir.as_str())
+        );
+        assert_eq!(stats[0].model, "gpt-4o");
+        assert_eq!((stats[0].input_tokens, stats[0].output_tokens), (25, 15));
+        assert_eq!((stats[0].tool_calls, stats[0].tool_errors), (2, 1));
+        assert_eq!(stats[0].total_tokens, 30);
+
+        // Read-only, as for other directories' databases
+        let at_path = project_stats_at(db.db_path(), None, None).await?;
+        assert_eq!(at_path[0].tool_calls, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_project_stats_of_older_databases() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let old_db = temp_dir.path().join("old.db");
+        let pool = SqlitePool::connect_with(
+            sqlx::sqlite::SqliteConnectOptions::new()
+                .filename(&old_db)
+                .create_if_missing(true),
+        )
+        .await?;
+        sqlx::query(
+            "CREATE TABLE conversations (id TEXT PRIMARY KEY, created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, model TEXT NOT NULL, total_tokens INTEGER DEFAULT 0, request_count INTEGER DEFAULT 0)",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query("CREATE TABLE tool_calls (id TEXT PRIMARY KEY, conversation_id TEXT NOT NULL, is_error BOOLEAN NOT NULL DEFAULT FALSE)")
+            .execute(&pool)
+            .await?;
+        sqlx::query("INSERT INTO conversations (id, model, total_tokens, request_count) VALUES ('a', 'claude-sonnet-4-5', 100, 2)")
+            .execute(&pool)
+            .await?;
+        pool.close().await;
+        std::fs::write(temp_dir.path().join("notes.txt"), "not a database")?;
+
+        let stats = project_stats_at(&old_db, None, None).await?;
+        assert_eq!(stats[0].workspace_path, None);
+        assert_eq!(stats[0].total_tokens, 100);
+        assert_eq!(stats[0].input_tokens, 0);
+        assert_eq!(database_files(temp_dir.path()), vec![old_db]);
+        Ok(())
+    }
 }
@@ -0,0 +1,101 @@
+use crate::llm::{ContentCallback, LlmProvider, LlmRequest, LlmResponse};
+use crate::openai::OpenAIClient;
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+
+/// DeepSeek's API is OpenAI's. `deepseek-reasoner` streams its reasoning as
+/// `reasoning_content`, which is passed on as thinking rather than as part of
+/// the reply, and never sent back: DeepSeek rejects requests that include it.
+pub struct DeepSeekClient {
+    inner: OpenAIClient,
+}
+
+impl DeepSeekClient {
+    pub fn new(api_key: String, base_url: String) -> Self {
+        Self {
+            inner: OpenAIClient::new(api_key, base_url).with_name("DeepSeek"),
+        }
+    }
+}
+
+impl LlmProvider for DeepSeekClient {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>> {
+        LlmProvider::create_message(&self.inner, request)
+    }
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>> {
+        LlmProvider::create_message_stream(&self.inner, request, on_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anthropic::{ContentBlock, Message};
+    use crate::llm::tests::{configure_no_proxy, spawn_server};
+    use crate::llm::ReplyDelta;
+    use axum::http::header;
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn streams_reasoning_apart_from_the_answer() {
+        configure_no_proxy();
+        let app = Router::new().route(
+            "/chat/completions",
+            post(|| async {
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream")],
+                    concat!(
+                        "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":null,\"reasoning_content\":\"2 and 2 \"}}]}\n\n",
+                        "data: {\"choices\":[{\"delta\":{\"content\":null,\"reasoning_content\":\"make 4.\"}}]}\n\n",
+                        "data: {\"choices\":[{\"delta\":{\"content\":\"4\",\"reasoning_content\":null}}]}\n\n",
+                        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":5}}\n\n",
+                        "data: [DONE]\n\n"
+                    ),
+                )
+            }),
+        );
+        let base_url = spawn_server(app).await;
+
+        let client = DeepSeekClient::new("test-key".to_string(), base_url);
+        let thinking = Arc::new(Mutex::new(String::new()));
+        let answer = Arc::new(Mutex::new(String::new()));
+        let (seen_thinking, seen_answer) = (thinking.clone(), answer.clone());
+        let response = LlmProvider::create_message_stream(
+            &client,
+            LlmRequest {
+                model: "deepseek-reasoner",
+                messages: vec![Message {
+                    role: "user".to_string(),
+                    content: vec![ContentBlock::text("What is 2 + 2?".to_string())],
+                }],
+                tools: &[],
+                max_tokens: 64,
+                temperature: 0.0,
+                system_prompt: None,
+                cancellation_flag: Arc::new(AtomicBool::new(false)),
+                on_delta: Some(Arc::new(move |delta| {
+                    if let ReplyDelta::Thinking(text) = delta {
+                        seen_thinking.lock().unwrap().push_str(&text);
+                    }
+                })),
+            },
+            Arc::new(move |text| seen_answer.lock().unwrap().push_str(&text)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(*thinking.lock().unwrap(), "2 and 2 make 4.");
+        assert_eq!(*answer.lock().unwrap(), "4");
+        assert_eq!(response.content.len(), 1);
+        assert_eq!(response.content[0].text.as_deref(), Some("4"));
+        assert_eq!(response.usage.unwrap().output_tokens, 5);
+    }
+}
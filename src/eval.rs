@@ -0,0 +1,528 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::agent::Agent;
+use crate::config::Provider;
+use crate::headless::{RunResult, RunStatus, ToolCallCollector, UsageSummary};
+
+const DEFAULT_CHECK_TIMEOUT_SECS: u64 = 300;
+
+/// One task from an eval file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EvalTask {
+    pub name: String,
+    pub prompt: String,
+    /// Directory copied into a fresh workspace for each run; relative to the
+    /// eval file
+    #[serde(default)]
+    pub fixture: Option<PathBuf>,
+    /// Shell command run in the workspace afterwards; exit code 0 is a pass
+    pub check: String,
+    /// Seconds the check may run for
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+/// Parse an eval file: a YAML list of tasks
+pub fn parse_eval_file(path: &Path, content: &str) -> Result<Vec<EvalTask>> {
+    let mut tasks: Vec<EvalTask> = serde_yaml::from_str(content)
+        .with_context(|| format!("{} must be a YAML list of tasks", path.display()))?;
+    if tasks.is_empty() {
+        return Err(anyhow!("{} contains no tasks", path.display()));
+    }
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut names = std::collections::HashSet::new();
+    for task in &mut tasks {
+        if task.name.trim().is_empty() || task.name.contains(['/', '\\']) {
+            return Err(anyhow!("Invalid task name '{}'", task.name));
+        }
+        if !names.insert(task.name.clone()) {
+            return Err(anyhow!("Task '{}' is defined twice", task.name));
+        }
+        if let Some(fixture) = &task.fixture {
+            // Absolute, since runs change the current directory
+            let fixture = std::path::absolute(base.join(fixture))?;
+            if !fixture.is_dir() {
+                return Err(anyhow!(
+                    "Task '{}': fixture {} is not a directory",
+                    task.name,
+                    fixture.display()
+                ));
+            }
+            task.fixture = Some(fixture);
+        }
+    }
+    Ok(tasks)
+}
+
+/// A model to evaluate, optionally on a provider other than the configured one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelTarget {
+    pub provider: Option<Provider>,
+    pub model: String,
+}
+
+impl ModelTarget {
+    /// `model`, or `provider:model` such as `openai:gpt-4o`. A prefix that
+    /// isn't a provider is part of the model name, as in `llama3:8b`.
+    pub fn parse(spec: &str) -> Self {
+        if let Some((provider, model)) = spec.split_once(':') {
+            if let Ok(provider) = provider.parse::<Provider>() {
+                return Self {
+                    provider: Some(provider),
+                    model: model.to_string(),
+                };
+            }
+        }
+        Self {
+            provider: None,
+            model: spec.to_string(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.provider {
+            Some(provider) => format!("{}:{}", provider, self.model),
+            None => self.model.clone(),
+        }
+    }
+
+    /// The label as a directory name
+    fn slug(&self) -> String {
+        self.label()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+/// The outcome of one task on one model, as written to `results.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalResult {
+    pub task: String,
+    pub model: String,
+    pub passed: bool,
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The check's exit code, or None if it didn't run or timed out
+    pub check_exit_code: Option<i32>,
+    pub workspace: PathBuf,
+    pub conversation_id: Option<String>,
+    pub tool_calls: usize,
+    pub usage: UsageSummary,
+    pub cost_usd: Option<f64>,
+    pub duration_ms: u128,
+}
+
+/// Run every task against every model, one at a time since each run works
+/// in its own directory. Workspaces, answers and check output are kept under
+/// `output_dir/<model>/<task>/`; `results.json` and `report.md` summarize.
+pub async fn run_eval<F, Fut>(
+    tasks: &[EvalTask],
+    targets: &[ModelTarget],
+    output_dir: &Path,
+    create_agent: F,
+    on_done: impl Fn(&EvalResult),
+) -> Result<Vec<EvalResult>>
+where
+    F: Fn(&ModelTarget) -> Fut,
+    Fut: Future<Output = Result<Agent>>,
+{
+    tokio::fs::create_dir_all(output_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    let output_dir = std::path::absolute(output_dir)?;
+    let original_dir = std::env::current_dir()?;
+
+    let mut results = Vec::new();
+    for target in targets {
+        for task in tasks {
+            let run_dir = output_dir.join(target.slug()).join(&task.name);
+            let result = run_task(task, target, &run_dir, &create_agent).await;
+            std::env::set_current_dir(&original_dir)?;
+            on_done(&result);
+            results.push(result);
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&results)?;
+    tokio::fs::write(output_dir.join("results.json"), json + "\n").await?;
+    tokio::fs::write(output_dir.join("report.md"), render_report(&results)).await?;
+    Ok(results)
+}
+
+async fn run_task<F, Fut>(
+    task: &EvalTask,
+    target: &ModelTarget,
+    run_dir: &Path,
+    create_agent: &F,
+) -> EvalResult
+where
+    F: Fn(&ModelTarget) -> Fut,
+    Fut: Future<Output = Result<Agent>>,
+{
+    let workspace = run_dir.join("workspace");
+    let mut result = EvalResult {
+        task: task.name.clone(),
+        model: target.label(),
+        passed: false,
+        status: RunStatus::Error,
+        error: None,
+        check_exit_code: None,
+        workspace: workspace.clone(),
+        conversation_id: None,
+        tool_calls: 0,
+        usage: UsageSummary {
+            requests: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            total_tokens: 0,
+        },
+        cost_usd: None,
+        duration_ms: 0,
+    };
+
+    if let Err(e) = prepare_workspace(task.fixture.as_deref(), &workspace) {
+        result.error = Some(format!("Failed to prepare workspace: {:#}", e));
+        return result;
+    }
+    // Tools resolve relative paths against the current directory
+    if let Err(e) = std::env::set_current_dir(&workspace) {
+        result.error = Some(format!("Failed to enter workspace: {}", e));
+        return result;
+    }
+    let mut agent = match create_agent(target).await {
+        Ok(agent) => agent,
+        Err(e) => {
+            result.error = Some(format!("Failed to start agent: {:#}", e));
+            return result;
+        }
+    };
+
+    let started = Instant::now();
+    let tool_calls = ToolCallCollector::new();
+    let response = agent
+        .process_message_with_stream(
+            &task.prompt,
+            None,
+            Some(tool_calls.callback()),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await;
+    let run = RunResult::new(&agent, &response, tool_calls.take(), started.elapsed());
+    let _ = std::fs::write(run_dir.join("response.md"), &run.result);
+
+    result.status = run.status;
+    result.error = run.error;
+    result.conversation_id = run.conversation_id;
+    result.tool_calls = run.tool_calls.len();
+    result.usage = run.usage;
+    result.cost_usd = run.cost_usd;
+    result.duration_ms = run.duration_ms;
+
+    // The check decides, even when the run ended in an error: the work may
+    // already be done
+    let timeout = Duration::from_secs(task.timeout.unwrap_or(DEFAULT_CHECK_TIMEOUT_SECS));
+    match run_check(&task.check, &workspace, timeout).await {
+        Ok((exit_code, output)) => {
+            let _ = std::fs::write(run_dir.join("check.log"), output);
+            result.check_exit_code = exit_code;
+            result.passed = exit_code == Some(0);
+        }
+        Err(e) => {
+            let error = format!("Check failed to run: {:#}", e);
+            result.error = Some(match result.error.take() {
+                Some(run_error) => format!("{}; {}", run_error, error),
+                None => error,
+            });
+        }
+    }
+    result
+}
+
+/// Make `workspace` a fresh copy of `fixture`, or an empty directory
+fn prepare_workspace(fixture: Option<&Path>, workspace: &Path) -> Result<()> {
+    if workspace.exists() {
+        std::fs::remove_dir_all(workspace)?;
+    }
+    std::fs::create_dir_all(workspace)?;
+    if let Some(fixture) = fixture {
+        copy_dir(fixture, workspace)?;
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run a check command, returning its exit code (None if it timed out or
+/// was killed) and combined output
+async fn run_check(check: &str, dir: &Path, timeout: Duration) -> Result<(Option<i32>, String)> {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(check)
+        .current_dir(dir)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(timeout, child).await {
+        Ok(output) => {
+            let output = output.context("Failed to run check")?;
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            Ok((output.status.code(), text))
+        }
+        Err(_) => Ok((
+            None,
+            format!("Check timed out after {}s\n", timeout.as_secs()),
+        )),
+    }
+}
+
+/// Totals for one model across all tasks
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelSummary {
+    pub model: String,
+    pub passed: usize,
+    pub total: usize,
+    pub total_tokens: u64,
+    pub cost_usd: Option<f64>,
+    pub duration_ms: u128,
+}
+
+impl ModelSummary {
+    pub fn pass_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.passed as f64 / self.total as f64
+        }
+    }
+}
+
+/// Per-model totals, in the order the models were run
+pub fn summarize(results: &[EvalResult]) -> Vec<ModelSummary> {
+    let mut summaries: Vec<ModelSummary> = Vec::new();
+    for result in results {
+        let index = match summaries.iter().position(|s| s.model == result.model) {
+            Some(index) => index,
+            None => {
+                summaries.push(ModelSummary {
+                    model: result.model.clone(),
+                    passed: 0,
+                    total: 0,
+                    total_tokens: 0,
+                    cost_usd: Some(0.0),
+                    duration_ms: 0,
+                });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[index];
+        summary.total += 1;
+        summary.passed += usize::from(result.passed);
+        summary.total_tokens += u64::from(result.usage.total_tokens);
+        // A run without a known price makes the model's total unknown
+        summary.cost_usd = summary.cost_usd.zip(result.cost_usd).map(|(a, b)| a + b);
+        summary.duration_ms += result.duration_ms;
+    }
+    summaries
+}
+
+/// A Markdown report comparing the models, then each task's outcome per model
+pub fn render_report(results: &[EvalResult]) -> String {
+    let summaries = summarize(results);
+    let mut report = String::from("# Eval report\n\n");
+    report.push_str("| Model | Passed | Pass rate | Tokens | Cost | Time |\n");
+    report.push_str("|---|---|---|---|---|---|\n");
+    for summary in &summaries {
+        report.push_str(&format!(
+            "| {} | {}/{} | {:.0}% | {} | {} | {:.1}s |\n",
+            summary.model,
+            summary.passed,
+            summary.total,
+            summary.pass_rate() * 100.0,
+            summary.total_tokens,
+            summary
+                .cost_usd
+                .map(|cost| format!("${:.4}", cost))
+                .unwrap_or_else(|| "unknown".to_string()),
+            summary.duration_ms as f64 / 1000.0
+        ));
+    }
+
+    let mut by_task: BTreeMap<&str, BTreeMap<&str, &EvalResult>> = BTreeMap::new();
+    for result in results {
+        by_task
+            .entry(result.task.as_str())
+            .or_default()
+            .insert(result.model.as_str(), result);
+    }
+    report.push_str("\n| Task |");
+    for summary in &summaries {
+        report.push_str(&format!(" {} |", summary.model));
+    }
+    report.push_str("\n|---|");
+    report.push_str(&"---|".repeat(summaries.len()));
+    report.push('\n');
+    for (task, models) in by_task {
+        report.push_str(&format!("| {} |", task));
+        for summary in &summaries {
+            let cell = match models.get(summary.model.as_str()) {
+                Some(result) if result.passed => "pass",
+                Some(result) if result.check_exit_code.is_none() => "error",
+                Some(_) => "fail",
+                None => "",
+            };
+            report.push_str(&format!(" {} |", cell));
+        }
+        report.push('\n');
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn result(task: &str, model: &str, passed: bool, tokens: u32, cost: Option<f64>) -> EvalResult {
+        EvalResult {
+            task: task.to_string(),
+            model: model.to_string(),
+            passed,
+            status: RunStatus::Success,
+            error: None,
+            check_exit_code: Some(if passed { 0 } else { 1 }),
+            workspace: PathBuf::new(),
+            conversation_id: None,
+            tool_calls: 0,
+            usage: UsageSummary {
+                requests: 1,
+                input_tokens: tokens,
+                output_tokens: 0,
+                total_tokens: tokens,
+            },
+            cost_usd: cost,
+            duration_ms: 1000,
+        }
+    }
+
+    #[test]
+    fn parses_eval_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("fixture")).unwrap();
+        let path = dir.path().join("evals.yaml");
+        let content = "- name: fix-test\n  prompt: Make the tests pass\n  fixture: fixture\n  check: cargo test\n  timeout: 60\n- name: hello\n  prompt: Write hello.txt\n  check: test -f hello.txt\n";
+        let tasks = parse_eval_file(&path, content).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].fixture, Some(dir.path().join("fixture")));
+        assert_eq!(tasks[0].timeout, Some(60));
+        assert_eq!(tasks[1].fixture, None);
+
+        let missing = "- name: a\n  prompt: p\n  fixture: nope\n  check: 'true'\n";
+        assert!(parse_eval_file(&path, missing).is_err());
+        let duplicate =
+            "- {name: a, prompt: p, check: 'true'}\n- {name: a, prompt: q, check: 'true'}\n";
+        assert!(parse_eval_file(&path, duplicate).is_err());
+        assert!(parse_eval_file(&path, "[]").is_err());
+    }
+
+    #[test]
+    fn parses_model_targets() {
+        assert_eq!(
+            ModelTarget::parse("openai:gpt-4o"),
+            ModelTarget {
+                provider: Some(Provider::OPENAI),
+                model: "gpt-4o".to_string()
+            }
+        );
+        assert_eq!(ModelTarget::parse("llama3:8b").provider, None);
+        assert_eq!(ModelTarget::parse("llama3:8b").model, "llama3:8b");
+        assert_eq!(
+            ModelTarget::parse("ollama:llama3:8b").slug(),
+            "ollama_llama3_8b"
+        );
+    }
+
+    #[tokio::test]
+    async fn prepares_workspaces_and_runs_checks() {
+        let dir = TempDir::new().unwrap();
+        let fixture = dir.path().join("fixture");
+        std::fs::create_dir_all(fixture.join("src")).unwrap();
+        std::fs::write(fixture.join("src/lib.rs"), "// fixture").unwrap();
+
+        let workspace = dir.path().join("run/workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("stale.txt"), "left over").unwrap();
+        prepare_workspace(Some(&fixture), &workspace).unwrap();
+        assert!(workspace.join("src/lib.rs").is_file());
+        assert!(!workspace.join("stale.txt").exists());
+
+        let (code, output) = run_check(
+            "test -f src/lib.rs && echo ok",
+            &workspace,
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, Some(0));
+        assert_eq!(output.trim(), "ok");
+        let (code, _) = run_check("exit 3", &workspace, Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(code, Some(3));
+        let (code, output) = run_check("sleep 5", &workspace, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(code, None);
+        assert!(output.contains("timed out"));
+    }
+
+    #[test]
+    fn compares_models_in_the_report() {
+        let results = vec![
+            result("fix-test", "sonnet", true, 100, Some(0.01)),
+            result("hello", "sonnet", true, 50, Some(0.02)),
+            result("fix-test", "llama3", false, 200, None),
+            result("hello", "llama3", true, 80, None),
+        ];
+        let summaries = summarize(&results);
+        assert_eq!(summaries[0].model, "sonnet");
+        assert_eq!(summaries[0].passed, 2);
+        assert_eq!(summaries[0].total_tokens, 150);
+        assert!((summaries[0].cost_usd.unwrap() - 0.03).abs() < 1e-9);
+        assert_eq!(summaries[1].pass_rate(), 0.5);
+        assert_eq!(summaries[1].cost_usd, None);
+
+        let report = render_report(&results);
+        assert!(report.contains("| sonnet | 2/2 | 100% | 150 | $0.0300 | 2.0s |"));
+        assert!(report.contains("| llama3 | 1/2 | 50% | 280 | unknown | 2.0s |"));
+        assert!(report.contains("| fix-test | pass | fail |"));
+    }
+}
@@ -0,0 +1,281 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Most names or symbols looked up for one prompt
+const MAX_LOOKUPS: usize = 6;
+
+/// A name or symbol found in more files than this is too common to say
+/// which file the prompt means
+const MAX_MATCHES: usize = 3;
+
+/// Most files listed when walking the project without ripgrep
+const MAX_WALKED_FILES: usize = 20_000;
+
+/// Files larger than this aren't searched for definitions
+const MAX_SEARCHED_BYTES: u64 = 1024 * 1024;
+
+/// Directories skipped when walking the project without ripgrep
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+
+/// Extensions of files searched for definitions without ripgrep
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "jsx", "ts", "tsx", "mjs", "go", "java", "kt", "c", "h", "cc", "cpp", "hpp",
+    "cs", "rb", "php", "swift", "scala",
+];
+
+/// A file a prompt seems to be about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discovery {
+    /// Relative to the project root
+    pub path: PathBuf,
+    /// Why it was picked, e.g. "defines `Agent`"
+    pub reason: String,
+}
+
+/// A token that looks like a file name: `config.rs`, `src/agent.rs`
+fn file_name_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(?:[\w.\-]+/)*[\w\-]{2,}(?:\.[\w\-]+)*\.[A-Za-z][A-Za-z0-9]{0,7}$")
+            .expect("valid file name pattern")
+    })
+}
+
+/// Whether `word` is shaped like code rather than prose: `snake_case`,
+/// `camelCase` or `PascalCase` with more than one word
+fn is_code_identifier(word: &str) -> bool {
+    if word.len() < 4 || !word.chars().any(|c| c.is_ascii_alphabetic()) {
+        return false;
+    }
+    let snake = word.trim_matches('_').contains('_');
+    let camel = word
+        .chars()
+        .zip(word.chars().skip(1))
+        .any(|(a, b)| a.is_ascii_lowercase() && b.is_ascii_uppercase());
+    snake || camel
+}
+
+/// The file names and code identifiers in `message` that aren't already
+/// @mentions
+fn mentions(message: &str) -> (Vec<String>, Vec<String>) {
+    let mut names = Vec::new();
+    let mut symbols = Vec::new();
+    for token in message.split_whitespace() {
+        if token.starts_with('@') || token.contains("://") {
+            continue;
+        }
+        let quoted = token.starts_with('`');
+        let token = token
+            .trim_matches(|c: char| "`'\"()[]{},;:!?<>".contains(c))
+            .trim_end_matches('.');
+        if file_name_pattern().is_match(token) {
+            if !names.iter().any(|name| name == token) {
+                names.push(token.to_string());
+            }
+            continue;
+        }
+        for word in token.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+            let identifier = is_code_identifier(word)
+                || (quoted
+                    && word.len() >= 3
+                    && word.chars().all(|c| c.is_alphanumeric() || c == '_'));
+            if identifier && !symbols.iter().any(|symbol| symbol == word) {
+                symbols.push(word.to_string());
+            }
+        }
+    }
+    (names, symbols)
+}
+
+/// Whether ripgrep is on the PATH
+fn has_ripgrep() -> bool {
+    static FOUND: OnceLock<bool> = OnceLock::new();
+    *FOUND.get_or_init(|| which::which("rg").is_ok())
+}
+
+/// The project's files, relative to `root`. ripgrep lists them honoring
+/// .gitignore; without it, hidden and build directories are skipped.
+fn project_files(root: &Path) -> Vec<PathBuf> {
+    if has_ripgrep() {
+        if let Ok(output) = Command::new("rg").arg("--files").current_dir(root).output() {
+            if output.status.success() {
+                return String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(PathBuf::from)
+                    .collect();
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(root, root, &mut files);
+    files
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if files.len() >= MAX_WALKED_FILES {
+            return;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                walk(root, &path, files);
+            }
+        } else if file_type.is_file() {
+            if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+}
+
+/// The files whose path ends with `name`
+fn files_named<'a>(files: &'a [PathBuf], name: &str) -> Vec<&'a PathBuf> {
+    let name = Path::new(name);
+    files.iter().filter(|file| file.ends_with(name)).collect()
+}
+
+/// A regex matching the definition of `symbol` in the common languages
+fn definition_pattern(symbol: &str) -> String {
+    format!(
+        r"\b(?:fn|struct|enum|trait|type|union|mod|class|def|interface|function|func|const|static)\s+{}\b",
+        regex::escape(symbol)
+    )
+}
+
+/// The files that define `symbol`
+fn files_defining(root: &Path, files: &[PathBuf], symbol: &str) -> Vec<PathBuf> {
+    let pattern = definition_pattern(symbol);
+    if has_ripgrep() {
+        if let Ok(output) = Command::new("rg")
+            .args(["-l", "--no-messages", "-e", &pattern])
+            .current_dir(root)
+            .output()
+        {
+            // 1 means no matches
+            if output.status.success() || output.status.code() == Some(1) {
+                return String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(PathBuf::from)
+                    .collect();
+            }
+        }
+    }
+    let Ok(regex) = Regex::new(&pattern) else {
+        return Vec::new();
+    };
+    files
+        .iter()
+        .filter(|file| {
+            file.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+        })
+        .filter(|file| {
+            let path = root.join(file);
+            std::fs::metadata(&path).is_ok_and(|meta| meta.len() <= MAX_SEARCHED_BYTES)
+                && std::fs::read_to_string(&path).is_ok_and(|text| regex.is_match(&text))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The files under `root` that `message` names or whose types and functions
+/// it mentions, at most `max_files`. Names and symbols that match too many
+/// files to tell which is meant are ignored.
+pub fn discover(root: &Path, message: &str, max_files: usize) -> Vec<Discovery> {
+    let (names, symbols) = mentions(message);
+    if max_files == 0 || (names.is_empty() && symbols.is_empty()) {
+        return Vec::new();
+    }
+    let files = project_files(root);
+    let mut found: Vec<Discovery> = Vec::new();
+    let mut add = |path: PathBuf, reason: String| {
+        if found.len() < max_files && !found.iter().any(|d| d.path == path) {
+            found.push(Discovery { path, reason });
+        }
+    };
+
+    for name in names.iter().take(MAX_LOOKUPS) {
+        let matches = files_named(&files, name);
+        if matches.len() <= MAX_MATCHES {
+            for path in matches {
+                add(path.clone(), format!("named `{}`", name));
+            }
+        }
+    }
+    for symbol in symbols.iter().take(MAX_LOOKUPS) {
+        let mut matches = files_defining(root, &files, symbol);
+        if matches.len() <= MAX_MATCHES {
+            matches.sort();
+            for path in matches {
+                add(path, format!("defines `{}`", symbol));
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_file_names_and_identifiers_in_prose() {
+        let (names, symbols) = mentions(
+            "Why does ConversationManager call add_context_file twice? See config.rs, e.g. \
+             the `Agent` struct, and @src/main.rs. Thanks!",
+        );
+        assert_eq!(names, vec!["config.rs"]);
+        assert_eq!(
+            symbols,
+            vec!["ConversationManager", "add_context_file", "Agent"]
+        );
+
+        let (names, symbols) = mentions("Please fix the Makefile, see https://example.com/a.html");
+        assert!(names.is_empty());
+        assert!(symbols.is_empty());
+    }
+
+    #[test]
+    fn discovers_named_and_defining_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(
+            dir.path().join("src/parser.rs"),
+            "pub struct TokenStream;\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("src/lexer.rs"), "use TokenStream;\n").unwrap();
+        std::fs::write(dir.path().join("target/parser.rs"), "").unwrap();
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+
+        let found = discover(dir.path(), "TokenStream panics, compare with notes.md", 3);
+        assert_eq!(
+            found,
+            vec![
+                Discovery {
+                    path: PathBuf::from("notes.md"),
+                    reason: "named `notes.md`".to_string(),
+                },
+                Discovery {
+                    path: PathBuf::from("src/parser.rs"),
+                    reason: "defines `TokenStream`".to_string(),
+                },
+            ]
+        );
+        assert_eq!(discover(dir.path(), "TokenStream notes.md", 1).len(), 1);
+        assert!(discover(dir.path(), "how are you?", 3).is_empty());
+    }
+}
@@ -2,11 +2,66 @@ use anyhow::Result;
 use colored::*;
 use regex::Regex;
 
+use crate::clipboard;
+use crate::math;
+
 pub struct CodeFormatter {
     code_block_regex: Regex,
     file_regex: Regex,
     number_regex: Regex,
+    list_item_regex: Regex,
     cache: std::cell::RefCell<InputHighlightCache>,
+    line_numbers: bool,
+    block_numbers: bool,
+}
+
+/// What the previous lines of prose opened, since streamed responses are
+/// rendered a line at a time
+#[derive(Default)]
+struct ProseState {
+    in_diff: bool,
+    in_math: bool,
+}
+
+/// Bullets for each level of a nested list
+const LIST_BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// The info string after a code fence: ```` ```rust title=src/main.rs ````
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CodeFence {
+    lang: String,
+    title: Option<String>,
+}
+
+impl CodeFence {
+    fn parse(info: &str) -> Self {
+        let info = info.trim();
+        let (lang, rest) = match info.split_once(char::is_whitespace) {
+            Some((lang, rest)) => (lang, rest.trim()),
+            None => (info, ""),
+        };
+        // `rust title=x` or a bare `title=x` without a language
+        let (lang, rest) = if lang.contains('=') {
+            ("", info)
+        } else {
+            (lang, rest)
+        };
+        let title = rest
+            .find("title=")
+            .map(|start| {
+                let value = &rest[start + "title=".len()..];
+                match value.strip_prefix('"') {
+                    Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                    None => value.split_whitespace().next().unwrap_or_default(),
+                }
+            })
+            .filter(|title| !title.is_empty())
+            .map(str::to_string);
+        Self {
+            lang: lang.to_string(),
+            title,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -17,26 +72,49 @@ struct InputHighlightCache {
 
 impl CodeFormatter {
     pub fn new() -> Result<Self> {
-        let code_block_regex = Regex::new(r"```(\w*)\n([\s\S]*?)```")?;
+        let code_block_regex = Regex::new(r"```([^`\n]*)\n([\s\S]*?)```")?;
         let file_regex = Regex::new(r"@([^\s@]+)")?;
         let number_regex = Regex::new(r"\b\d+(\.\d+)?\b")?;
+        let list_item_regex = Regex::new(r"^(\s*)([-*+]|\d+[.)])\s+(\[[ xX]\]\s+)?(.*)$")?;
 
         Ok(Self {
             code_block_regex,
             file_regex,
             number_regex,
+            list_item_regex,
             cache: std::cell::RefCell::new(InputHighlightCache {
                 last_input: String::new(),
                 last_result: String::new(),
             }),
+            line_numbers: false,
+            block_numbers: false,
         })
     }
 
+    /// Number the lines of code blocks
+    pub fn set_line_numbers(&mut self, enabled: bool) {
+        self.line_numbers = enabled;
+    }
+
+    /// Number code blocks in their headers so `/copy <n>` can copy them
+    pub fn set_block_numbers(&mut self, enabled: bool) {
+        self.block_numbers = enabled;
+    }
+
     pub fn format_response(&self, response: &str) -> Result<String> {
         let formatted = self.format_text_with_code_blocks(response)?;
         Ok(formatted)
     }
 
+    /// A line of the model's reasoning, dimmed so it reads as an aside to
+    /// the answer
+    pub fn format_thinking_line(&self, line: &str) -> String {
+        if line.is_empty() {
+            return "│".dimmed().to_string();
+        }
+        format!("{} {}", "│".dimmed(), line.dimmed().italic())
+    }
+
     /// Format response with file highlighting (for user input display) with caching
     pub fn format_input_with_file_highlighting(&self, input: &str) -> String {
         // First do a cheap check - if no @ symbol, return as-is (fast path)
@@ -65,62 +143,190 @@ impl CodeFormatter {
 
         for caps in self.code_block_regex.captures_iter(text) {
             let full_match = caps.get(0).unwrap();
-            let lang = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+            let fence = CodeFence::parse(caps.get(1).map(|m| m.as_str()).unwrap_or(""));
             let code = caps.get(2).unwrap().as_str();
 
             // Add text before the code block
-            result.push_str(&text[last_end..full_match.start()]);
+            result.push_str(&self.format_markdown_text(&text[last_end..full_match.start()]));
 
             // Format and add the code block
-            let formatted_code = self.format_code_block(code, lang)?;
+            let formatted_code =
+                self.format_code_block(code, &fence.lang, fence.title.as_deref())?;
             result.push_str(&formatted_code);
 
             last_end = full_match.end();
         }
 
         // Add remaining text after the last code block
-        result.push_str(&text[last_end..]);
+        result.push_str(&self.format_markdown_text(&text[last_end..]));
 
         Ok(result)
     }
 
-    fn format_code_block(&self, code: &str, lang: &str) -> Result<String> {
+    fn format_markdown_text(&self, text: &str) -> String {
+        let mut state = ProseState::default();
+        text.split_inclusive('\n')
+            .map(|line| match line.strip_suffix('\n') {
+                Some(line) => format!("{}\n", self.format_prose_line(line, &mut state)),
+                None => self.format_prose_line(line, &mut state),
+            })
+            .collect()
+    }
+
+    /// Render a line outside code blocks. A unified diff pasted straight into
+    /// the text is coloured like a ```` ```diff ```` block from its first
+    /// header or hunk line until a line that can't be part of it. Lines
+    /// between `$$` (or `\[` and `\]`) delimiters are display math.
+    fn format_prose_line(&self, line: &str, state: &mut ProseState) -> String {
+        let line = line.trim_end_matches('\r');
+        if !state.in_diff {
+            let delimiter = line.trim();
+            let toggles = delimiter == "$$"
+                || (delimiter == r"\[" && !state.in_math)
+                || (delimiter == r"\]" && state.in_math);
+            if toggles {
+                state.in_math = !state.in_math;
+                return String::new();
+            }
+            if state.in_math {
+                return format!("    {}", math::to_unicode(line).italic());
+            }
+        }
+
+        if starts_raw_diff(line) {
+            state.in_diff = true;
+        } else if state.in_diff && !continues_diff(line) {
+            state.in_diff = false;
+        }
+        if state.in_diff {
+            self.highlight_diff(line)
+        } else {
+            let rendered = self.format_markdown_line(&math::render_inline(line));
+            match crate::output::output_width() {
+                Some(width) if width > 0 => wrap_words(&rendered, width),
+                _ => rendered,
+            }
+        }
+    }
+
+    /// Render one line of prose: blockquotes, nested bullet and numbered
+    /// lists, and `- [ ]` task lists. Works a line at a time so streamed and
+    /// complete responses come out the same.
+    fn format_markdown_line(&self, line: &str) -> String {
+        let line = line.trim_end_matches('\r');
+
+        // `> > text` nests quotes; the quoted text can itself be a list
+        let mut rest = line.trim_start();
+        let mut depth = 0;
+        while let Some(quoted) = rest.strip_prefix('>') {
+            depth += 1;
+            rest = quoted.strip_prefix(' ').unwrap_or(quoted);
+        }
+        if depth > 0 {
+            let bars = "│ ".repeat(depth).bright_black();
+            let item = self.format_list_item(rest);
+            if item == rest {
+                return format!("{}{}", bars, rest.italic());
+            }
+            return format!("{}{}", bars, item);
+        }
+        self.format_list_item(line)
+    }
+
+    fn format_list_item(&self, line: &str) -> String {
+        let Some(caps) = self.list_item_regex.captures(line) else {
+            return line.to_string();
+        };
+        let indent = caps.get(1).map_or("", |m| m.as_str());
+        let marker = &caps[2];
+        let text = caps.get(4).map_or("", |m| m.as_str());
+        let level = indent.replace('\t', "    ").len() / 2;
+
+        let marker = if marker.ends_with(['.', ')']) {
+            marker.cyan().to_string()
+        } else {
+            LIST_BULLETS[level % LIST_BULLETS.len()].cyan().to_string()
+        };
+        match caps.get(3).map(|m| m.as_str().trim()) {
+            Some("[ ]") => format!("{}{} {} {}", indent, marker, "☐".yellow(), text),
+            Some(_) => format!("{}{} {} {}", indent, marker, "☑".green(), text.dimmed()),
+            None => format!("{}{} {}", indent, marker, text),
+        }
+    }
+
+    fn format_code_block(&self, code: &str, lang: &str, title: Option<&str>) -> Result<String> {
         let mut result = String::new();
 
         // Normalize language name
         let normalized_lang = self.normalize_language(lang);
 
         // Add header with language info
-        result.push_str(&self.build_code_block_header(normalized_lang));
+        let number = self
+            .block_numbers
+            .then(|| clipboard::record_code_block(code));
+        result.push_str(&self.build_code_block_header(normalized_lang, title, number));
         result.push('\n');
 
         // Add code content with syntax highlighting
-        for line in code.lines() {
+        let number_width = line_number_width(code.lines().count());
+        for (index, line) in code.lines().enumerate() {
+            if self.line_numbers {
+                result.push_str(&self.line_number_gutter(index + 1, number_width));
+            }
             let highlighted_line = self.highlight_line(line, normalized_lang);
             result.push_str(&highlighted_line);
             result.push('\n');
         }
 
         // Add footer
-        result.push_str(&self.build_code_block_footer(normalized_lang));
+        result.push_str(&self.build_code_block_footer(normalized_lang, title, number));
         result.push('\n');
 
         Ok(result)
     }
 
-    fn build_code_block_header(&self, normalized_lang: &str) -> String {
+    /// `[2] RUST · src/main.rs`, or just `RUST` without a number or title
+    fn code_block_label(
+        normalized_lang: &str,
+        title: Option<&str>,
+        number: Option<usize>,
+    ) -> (String, String) {
+        let lang = match number {
+            Some(number) => format!("[{}] {}", number, normalized_lang.to_uppercase()),
+            None => normalized_lang.to_uppercase(),
+        };
+        match title {
+            Some(title) => (lang, format!(" · {}", title)),
+            None => (lang, String::new()),
+        }
+    }
+
+    fn build_code_block_header(
+        &self,
+        normalized_lang: &str,
+        title: Option<&str>,
+        number: Option<usize>,
+    ) -> String {
+        let (lang, title) = Self::code_block_label(normalized_lang, title, number);
         format!(
-            "{}{} {} {}{}",
+            "{}{} {}{} {}{}",
             "┌".bold().cyan(),
             " ".repeat(2),
-            normalized_lang.to_uppercase().bold().white(),
+            lang.bold().white(),
+            title.bright_white(),
             " ".repeat(2),
             "┐".bold().cyan()
         )
     }
 
-    fn build_code_block_footer(&self, normalized_lang: &str) -> String {
-        let footer_width = normalized_lang.len() + 6;
+    fn build_code_block_footer(
+        &self,
+        normalized_lang: &str,
+        title: Option<&str>,
+        number: Option<usize>,
+    ) -> String {
+        let (lang, title) = Self::code_block_label(normalized_lang, title, number);
+        let footer_width = lang.chars().count() + title.chars().count() + 6;
         format!(
             "{}{}{}",
             "└".bold().cyan(),
@@ -129,7 +335,15 @@ impl CodeFormatter {
         )
     }
 
-    fn highlight_line(&self, line: &str, lang: &str) -> String {
+    fn line_number_gutter(&self, number: usize, width: usize) -> String {
+        format!(
+            "{} ",
+            format!("{:>width$} │", number, width = width).dimmed()
+        )
+    }
+
+    /// Highlight one line of code written in `lang`
+    pub fn highlight_line(&self, line: &str, lang: &str) -> String {
         match lang {
             "rust" => self.highlight_rust(line),
             "python" => self.highlight_python(line),
@@ -147,6 +361,7 @@ impl CodeFormatter {
             "c" | "cpp" | "c++" => self.highlight_c_cpp(line),
             "java" => self.highlight_java(line),
             "go" => self.highlight_go(line),
+            "diff" => self.highlight_diff(line),
             _ => self.highlight_numbers(line),
         }
     }
@@ -854,6 +1069,16 @@ impl CodeFormatter {
         result
     }
 
+    fn highlight_diff(&self, line: &str) -> String {
+        match DiffLine::classify(line) {
+            DiffLine::Header => line.bold().to_string(),
+            DiffLine::Hunk => line.cyan().to_string(),
+            DiffLine::Added => line.green().to_string(),
+            DiffLine::Removed => line.red().to_string(),
+            DiffLine::Context => line.to_string(),
+        }
+    }
+
     fn highlight_numbers(&self, text: &str) -> String {
         self.number_regex
             .replace_all(text, |caps: &regex::Captures| caps[0].yellow().to_string())
@@ -874,6 +1099,7 @@ impl CodeFormatter {
             "c" => "c",
             "cpp" | "cxx" | "cc" => "cpp",
             "md" => "markdown",
+            "patch" | "udiff" => "diff",
             "" => "text",
             _ => lang,
         }
@@ -881,8 +1107,7 @@ impl CodeFormatter {
 
     pub fn print_formatted(&self, text: &str) -> Result<()> {
         let formatted = self.format_response(text)?;
-        app_print!("{}", formatted);
-        crate::output::flush();
+        crate::pager::print_paged(&formatted);
         Ok(())
     }
 
@@ -933,9 +1158,152 @@ impl Clone for CodeFormatter {
             code_block_regex: self.code_block_regex.clone(),
             file_regex: self.file_regex.clone(),
             number_regex: self.number_regex.clone(),
+            list_item_regex: self.list_item_regex.clone(),
             cache: std::cell::RefCell::new(self.cache.borrow().clone()),
+            line_numbers: self.line_numbers,
+            block_numbers: self.block_numbers,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DiffLine {
+    Header,
+    Hunk,
+    Added,
+    Removed,
+    Context,
+}
+
+impl DiffLine {
+    fn classify(line: &str) -> Self {
+        if line.starts_with("+++ ") || line.starts_with("--- ") || is_diff_header(line) {
+            Self::Header
+        } else if line.starts_with("@@") {
+            Self::Hunk
+        } else if line.starts_with('+') {
+            Self::Added
+        } else if line.starts_with('-') {
+            Self::Removed
+        } else {
+            Self::Context
+        }
+    }
+}
+
+/// Git's extended header lines, which start a diff or a file within one
+fn is_diff_header(line: &str) -> bool {
+    [
+        "diff --git ",
+        "index ",
+        "new file mode ",
+        "deleted file mode ",
+        "old mode ",
+        "new mode ",
+        "similarity index ",
+        "rename from ",
+        "rename to ",
+        "Binary files ",
+    ]
+    .iter()
+    .any(|prefix| line.starts_with(prefix))
+}
+
+/// A line that can only open a unified diff, so prose isn't mistaken for one
+fn starts_raw_diff(line: &str) -> bool {
+    line.starts_with("diff --git ")
+        || line.starts_with("--- a/")
+        || line.starts_with("--- /dev/null")
+        || (line.starts_with("@@ -") && line[3..].contains(" @@"))
+}
+
+fn continues_diff(line: &str) -> bool {
+    line.starts_with(['+', '-', ' ', '\\']) || line.starts_with("@@") || is_diff_header(line)
+}
+
+/// Word-wrap a rendered prose line at `width` columns, ignoring colour
+/// codes. Continuation lines keep the line's indent and hang under list
+/// text. Inline `code` is kept on one line, so long commands are never split.
+fn wrap_words(line: &str, width: usize) -> String {
+    // Words with their visible text, split on single spaces outside code
+    let mut words: Vec<(String, String)> = Vec::new();
+    let (mut word, mut plain) = (String::new(), String::new());
+    let mut in_code = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            word.push(c);
+            for next in chars.by_ref() {
+                word.push(next);
+                if next.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if c == ' ' && !in_code {
+            words.push((std::mem::take(&mut word), std::mem::take(&mut plain)));
+        } else {
+            in_code ^= c == '`';
+            word.push(c);
+            plain.push(c);
+        }
+    }
+    words.push((word, plain));
+
+    let width_of = |plain: &str| plain.chars().count();
+    let total = words
+        .iter()
+        .map(|(_, plain)| width_of(plain))
+        .sum::<usize>()
+        + words.len()
+        - 1;
+    if total <= width {
+        return line.to_string();
+    }
+
+    let mut indent = words
+        .iter()
+        .take_while(|(_, plain)| plain.is_empty())
+        .count();
+    if let Some((_, marker)) = words.get(indent) {
+        let numbered = marker.len() > 1
+            && marker.ends_with(['.', ')'])
+            && marker[..marker.len() - 1]
+                .chars()
+                .all(|c| c.is_ascii_digit());
+        if numbered || LIST_BULLETS.contains(&marker.as_str()) || marker == "☐" || marker == "☑"
+        {
+            indent += width_of(marker) + 1;
+        }
+    }
+    if indent * 2 > width {
+        indent = 0;
+    }
+
+    let mut wrapped = String::new();
+    let mut column = 0;
+    let mut has_word = false;
+    for (i, (word, plain)) in words.iter().enumerate() {
+        let len = width_of(plain);
+        if has_word && len > 0 && column + 1 + len > width {
+            wrapped.push('\n');
+            wrapped.push_str(&" ".repeat(indent));
+            column = indent;
+            has_word = false;
+        } else if i > 0 {
+            wrapped.push(' ');
+            column += 1;
         }
+        wrapped.push_str(word);
+        column += len;
+        has_word |= len > 0;
     }
+    wrapped
+}
+
+/// Digits needed for the last line number, at least three so short blocks
+/// line up with each other
+fn line_number_width(line_count: usize) -> usize {
+    line_count.to_string().len().max(3)
 }
 
 pub struct StreamingResponseFormatter {
@@ -943,6 +1311,12 @@ pub struct StreamingResponseFormatter {
     pending_line: String,
     in_code_block: bool,
     current_lang: String,
+    current_title: Option<String>,
+    current_number: Option<usize>,
+    code_line: usize,
+    prose: ProseState,
+    /// The reasoning line not yet ended, while the model is thinking
+    thinking_line: Option<String>,
 }
 
 impl StreamingResponseFormatter {
@@ -952,95 +1326,190 @@ impl StreamingResponseFormatter {
             pending_line: String::new(),
             in_code_block: false,
             current_lang: "text".to_string(),
+            current_title: None,
+            current_number: None,
+            code_line: 0,
+            prose: ProseState::default(),
+            thinking_line: None,
         }
     }
 
+    /// Show a piece of the model's reasoning, set apart from the answer
+    pub fn handle_thinking(&mut self, chunk: &str) -> Result<()> {
+        let rendered = self.render_thinking(chunk);
+        if !rendered.is_empty() {
+            app_print!("{}", rendered);
+        }
+        crate::output::flush();
+        Ok(())
+    }
+
+    fn render_thinking(&mut self, chunk: &str) -> String {
+        let mut rendered = String::new();
+        if self.thinking_line.is_none() {
+            // Thinking between answers, such as before a tool call
+            if !self.pending_line.is_empty() {
+                let line = std::mem::take(&mut self.pending_line);
+                rendered.push_str(&self.process_complete_line(&line));
+                rendered.push('\n');
+            }
+            rendered.push_str(&format!("{}\n", "💭 Thinking".dimmed()));
+        }
+        let pending = self.thinking_line.get_or_insert_with(String::new);
+        pending.push_str(chunk);
+        while let Some(pos) = pending.find('\n') {
+            let line: String = pending.drain(..=pos).collect();
+            rendered.push_str(
+                &self
+                    .formatter
+                    .format_thinking_line(line.trim_end_matches(['\r', '\n'])),
+            );
+            rendered.push('\n');
+        }
+        rendered
+    }
+
+    /// Close the reasoning before the answer, or the end of the response
+    fn end_thinking(&mut self) -> String {
+        let Some(line) = self.thinking_line.take() else {
+            return String::new();
+        };
+        let mut rendered = String::new();
+        if !line.is_empty() {
+            rendered.push_str(&self.formatter.format_thinking_line(&line));
+            rendered.push('\n');
+        }
+        rendered.push('\n');
+        rendered
+    }
+
     pub fn handle_chunk(&mut self, chunk: &str) -> Result<()> {
-        if chunk.is_empty() {
-            return Ok(());
+        let rendered = self.render_chunk(chunk);
+        if !rendered.is_empty() {
+            app_print!("{}", rendered);
         }
+        crate::output::flush();
+        Ok(())
+    }
 
+    /// Render every line the chunk completes; a partial line waits for the
+    /// rest so its markdown is recognised
+    fn render_chunk(&mut self, chunk: &str) -> String {
+        let mut rendered = self.end_thinking();
         self.pending_line.push_str(chunk);
 
         while let Some(pos) = self.pending_line.find('\n') {
             let line = self.pending_line[..pos].to_string();
             self.pending_line.drain(..=pos);
-            self.process_complete_line(line.trim_end_matches('\r'))?;
+            rendered.push_str(&self.process_complete_line(line.trim_end_matches('\r')));
+            rendered.push('\n');
         }
+        rendered
+    }
 
+    pub fn finish(&mut self) -> Result<()> {
+        let rendered = self.finish_rendering();
+        if !rendered.is_empty() {
+            app_print!("{}", rendered);
+        }
         crate::output::flush();
         Ok(())
     }
 
-    pub fn finish(&mut self) -> Result<()> {
+    /// Render what's left at the end of the response: an unterminated last
+    /// line, and the footer of a code block the model never closed
+    fn finish_rendering(&mut self) -> String {
+        let mut rendered = self.end_thinking();
         if !self.pending_line.is_empty() {
+            let line = std::mem::take(&mut self.pending_line);
             if self.in_code_block {
-                let highlighted = self
-                    .formatter
-                    .highlight_line(&self.pending_line, &self.current_lang);
-                app_println!("{}", highlighted);
+                rendered.push_str(&self.format_code_line(&line));
+                rendered.push('\n');
             } else {
-                app_print!("{}", self.pending_line);
+                rendered.push_str(&self.formatter.format_prose_line(&line, &mut self.prose));
             }
-            self.pending_line.clear();
         }
 
         if self.in_code_block {
-            app_println!(
-                "{}",
-                self.formatter.build_code_block_footer(&self.current_lang)
-            );
+            rendered.push_str(&self.code_block_footer());
+            rendered.push('\n');
             self.in_code_block = false;
         }
-
-        crate::output::flush();
-        Ok(())
+        rendered
     }
 
-    fn process_complete_line(&mut self, line: &str) -> Result<()> {
+    fn process_complete_line(&mut self, line: &str) -> String {
         if self.in_code_block {
-            self.handle_code_line(line);
+            self.handle_code_line(line)
         } else {
-            self.handle_plain_line(line)?;
+            self.handle_plain_line(line)
         }
-        Ok(())
     }
 
-    fn handle_plain_line(&mut self, line: &str) -> Result<()> {
+    fn handle_plain_line(&mut self, line: &str) -> String {
         let trimmed = line.trim_start();
         if trimmed.starts_with("```") {
-            let lang = trimmed.trim_start_matches("```").trim();
-            self.start_code_block(lang);
+            let fence = CodeFence::parse(trimmed.trim_start_matches("```"));
+            self.prose = ProseState::default();
+            self.start_code_block(&fence)
         } else {
-            app_println!("{}", line);
+            self.formatter.format_prose_line(line, &mut self.prose)
         }
-        Ok(())
     }
 
-    fn handle_code_line(&mut self, line: &str) {
+    fn handle_code_line(&mut self, line: &str) -> String {
         if line.trim() == "```" {
-            app_println!(
-                "{}",
-                self.formatter.build_code_block_footer(&self.current_lang)
-            );
             self.in_code_block = false;
-            return;
+            return self.code_block_footer();
         }
 
+        self.format_code_line(line)
+    }
+
+    fn format_code_line(&mut self, line: &str) -> String {
+        if let Some(number) = self.current_number {
+            clipboard::append_code_line(number, line);
+        }
         let highlighted = self.formatter.highlight_line(line, &self.current_lang);
-        app_println!("{}", highlighted);
+        if !self.formatter.line_numbers {
+            return highlighted;
+        }
+        // The block's length isn't known yet, so use the minimum width
+        self.code_line += 1;
+        let gutter = self
+            .formatter
+            .line_number_gutter(self.code_line, line_number_width(0));
+        format!("{}{}", gutter, highlighted)
+    }
+
+    fn code_block_footer(&self) -> String {
+        self.formatter.build_code_block_footer(
+            &self.current_lang,
+            self.current_title.as_deref(),
+            self.current_number,
+        )
     }
 
-    fn start_code_block(&mut self, lang: &str) {
-        let normalized = self.formatter.normalize_language(lang);
+    fn start_code_block(&mut self, fence: &CodeFence) -> String {
+        let normalized = self.formatter.normalize_language(&fence.lang);
         let language = if normalized.is_empty() {
             "text"
         } else {
             normalized
         };
         self.current_lang = language.to_string();
-        app_println!("{}", self.formatter.build_code_block_header(language));
+        self.current_title = fence.title.clone();
+        self.current_number = self
+            .formatter
+            .block_numbers
+            .then(clipboard::start_code_block);
+        self.code_line = 0;
         self.in_code_block = true;
+        self.formatter.build_code_block_header(
+            language,
+            fence.title.as_deref(),
+            self.current_number,
+        )
     }
 }
 
@@ -1306,7 +1775,7 @@ mod tests {
     fn test_format_code_block_rust() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "fn main() {\n    let x = 42;\n}";
-        let result = formatter.format_code_block(code, "rust")?;
+        let result = formatter.format_code_block(code, "rust", None)?;
         assert!(result.contains("fn main"));
         assert!(result.contains("RUST"));
         Ok(())
@@ -1316,7 +1785,7 @@ mod tests {
     fn test_format_code_block_python() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "def hello():\n    print('Hello')";
-        let result = formatter.format_code_block(code, "python")?;
+        let result = formatter.format_code_block(code, "python", None)?;
         assert!(result.contains("def hello"));
         assert!(result.contains("PYTHON"));
         Ok(())
@@ -1326,7 +1795,7 @@ mod tests {
     fn test_format_code_block_javascript() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "function test() {\n    return 42;\n}";
-        let result = formatter.format_code_block(code, "javascript")?;
+        let result = formatter.format_code_block(code, "javascript", None)?;
         assert!(result.contains("function test"));
         assert!(result.contains("JAVASCRIPT"));
         Ok(())
@@ -1336,7 +1805,7 @@ mod tests {
     fn test_format_code_block_typescript() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "interface User {\n    name: string;\n}";
-        let result = formatter.format_code_block(code, "typescript")?;
+        let result = formatter.format_code_block(code, "typescript", None)?;
         assert!(result.contains("interface User"));
         assert!(result.contains("TYPESCRIPT"));
         Ok(())
@@ -1346,7 +1815,7 @@ mod tests {
     fn test_format_code_block_json() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = r#"{"key": "value", "number": 42}"#;
-        let result = formatter.format_code_block(code, "json")?;
+        let result = formatter.format_code_block(code, "json", None)?;
         assert!(result.contains("key"));
         assert!(result.contains("JSON"));
         Ok(())
@@ -1356,7 +1825,7 @@ mod tests {
     fn test_format_code_block_yaml() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "name: test\nversion: 1.0";
-        let result = formatter.format_code_block(code, "yaml")?;
+        let result = formatter.format_code_block(code, "yaml", None)?;
         assert!(result.contains("name"));
         assert!(result.contains("YAML"));
         Ok(())
@@ -1366,7 +1835,7 @@ mod tests {
     fn test_format_code_block_bash() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "#!/bin/bash\necho 'Hello'";
-        let result = formatter.format_code_block(code, "bash")?;
+        let result = formatter.format_code_block(code, "bash", None)?;
         assert!(result.contains("echo"));
         assert!(result.contains("BASH"));
         Ok(())
@@ -1376,7 +1845,7 @@ mod tests {
     fn test_format_code_block_sql() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "SELECT * FROM users WHERE id = 1";
-        let result = formatter.format_code_block(code, "sql")?;
+        let result = formatter.format_code_block(code, "sql", None)?;
         assert!(result.contains("SELECT"));
         assert!(result.contains("SQL"));
         Ok(())
@@ -1386,7 +1855,7 @@ mod tests {
     fn test_format_code_block_empty() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "";
-        let result = formatter.format_code_block(code, "rust")?;
+        let result = formatter.format_code_block(code, "rust", None)?;
         assert!(result.contains("RUST"));
         Ok(())
     }
@@ -1395,7 +1864,7 @@ mod tests {
     fn test_format_code_block_unknown_language() -> Result<()> {
         let formatter = create_code_formatter()?;
         let code = "some code here";
-        let result = formatter.format_code_block(code, "unknown")?;
+        let result = formatter.format_code_block(code, "unknown", None)?;
         assert!(result.contains("UNKNOWN"));
         assert!(result.contains("some code"));
         Ok(())
@@ -1591,7 +2060,7 @@ mod tests {
     #[test]
     fn test_build_code_block_header() {
         let formatter = create_code_formatter().unwrap();
-        let header = formatter.build_code_block_header("rust");
+        let header = formatter.build_code_block_header("rust", None, None);
         assert!(header.contains("RUST"));
         assert!(header.contains("┌"));
         assert!(header.contains("┐"));
@@ -1600,7 +2069,7 @@ mod tests {
     #[test]
     fn test_build_code_block_footer() {
         let formatter = create_code_formatter().unwrap();
-        let footer = formatter.build_code_block_footer("rust");
+        let footer = formatter.build_code_block_footer("rust", None, None);
         assert!(footer.contains("└"));
         assert!(footer.contains("┘"));
         assert!(footer.contains("─"));
@@ -1609,18 +2078,100 @@ mod tests {
     #[test]
     fn test_build_code_block_header_long_language() {
         let formatter = create_code_formatter().unwrap();
-        let header = formatter.build_code_block_header("typescript");
+        let header = formatter.build_code_block_header("typescript", None, None);
         assert!(header.contains("TYPESCRIPT"));
     }
 
     #[test]
     fn test_build_code_block_footer_long_language() {
         let formatter = create_code_formatter().unwrap();
-        let footer = formatter.build_code_block_footer("typescript");
+        let footer = formatter.build_code_block_footer("typescript", None, None);
         // Footer width should accommodate the language name
         assert!(footer.len() > 10);
     }
 
+    #[test]
+    fn test_code_fence_title() {
+        assert_eq!(
+            CodeFence::parse("rust title=src/foo.rs"),
+            CodeFence {
+                lang: "rust".to_string(),
+                title: Some("src/foo.rs".to_string())
+            }
+        );
+        assert_eq!(
+            CodeFence::parse(r#"python title="my script.py" linenos"#).title,
+            Some("my script.py".to_string())
+        );
+        assert_eq!(
+            CodeFence::parse("title=Makefile"),
+            CodeFence {
+                lang: String::new(),
+                title: Some("Makefile".to_string())
+            }
+        );
+        assert_eq!(CodeFence::parse("go").title, None);
+    }
+
+    #[test]
+    fn test_code_block_header_with_title() -> Result<()> {
+        colored::control::set_override(false);
+        let formatter = create_code_formatter().unwrap();
+        let header = formatter.build_code_block_header("rust", Some("src/foo.rs"), None);
+        let footer = formatter.build_code_block_footer("rust", Some("src/foo.rs"), None);
+        assert_eq!(header, "┌   RUST · src/foo.rs   ┐");
+        assert_eq!(header.chars().count(), footer.chars().count());
+
+        let result = formatter.format_response("```rust title=src/foo.rs\nfn main() {}\n```")?;
+        assert!(result.starts_with("┌   RUST · src/foo.rs   ┐\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_code_block_numbers_match_copy() -> Result<()> {
+        colored::control::set_override(false);
+        let mut formatter = create_code_formatter().unwrap();
+        formatter.set_block_numbers(true);
+        let header_number = |rendered: &str| -> usize {
+            let start = rendered.find('[').unwrap() + 1;
+            let end = rendered.find(']').unwrap();
+            rendered[start..end].parse().unwrap()
+        };
+
+        let result = formatter.format_response("```sh\necho hi\n```")?;
+        let number = header_number(&result);
+        assert_eq!(clipboard::code_block(Some(number)).unwrap().1, "echo hi\n");
+
+        let mut streaming = StreamingResponseFormatter::new(formatter);
+        let mut rendered = streaming.render_chunk("```sh\necho streamed\n```\n");
+        rendered.push_str(&streaming.finish_rendering());
+        let number = header_number(&rendered);
+        assert_eq!(
+            clipboard::code_block(Some(number)).unwrap().1,
+            "echo streamed\n"
+        );
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0].chars().count(), lines[2].chars().count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_code_block_line_numbers() -> Result<()> {
+        colored::control::set_override(false);
+        let mut formatter = create_code_formatter().unwrap();
+        let code = "a\nb\n";
+        assert!(!formatter
+            .format_code_block(code, "text", None)?
+            .contains("│"));
+
+        formatter.set_line_numbers(true);
+        let result = formatter.format_code_block(code, "text", None)?;
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[1], "  1 │ a");
+        assert_eq!(lines[2], "  2 │ b");
+        Ok(())
+    }
+
     // ============================================================================
     // StreamingResponseFormatter Tests
     // ============================================================================
@@ -1714,6 +2265,127 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_markdown_lists_and_quotes() {
+        colored::control::set_override(false);
+        let formatter = create_code_formatter().unwrap();
+        let render = |line| formatter.format_markdown_line(line);
+        assert_eq!(render("- top"), "• top");
+        assert_eq!(render("  * nested"), "  ◦ nested");
+        assert_eq!(render("    + deeper"), "    ▪ deeper");
+        assert_eq!(render("2. second"), "2. second");
+        assert_eq!(render("- [ ] todo"), "• ☐ todo");
+        assert_eq!(render("  - [x] done"), "  ◦ ☑ done");
+        assert_eq!(render("> quoted"), "│ quoted");
+        assert_eq!(render("> > - quoted item"), "│ │ • quoted item");
+        assert_eq!(render("-not a list"), "-not a list");
+        assert_eq!(render("plain"), "plain");
+    }
+
+    #[test]
+    fn test_streaming_markdown_matches_complete_response() {
+        colored::control::set_override(false);
+        let formatter = create_code_formatter().unwrap();
+        let response =
+            "Plan:\n- [x] read\n  - nested\n    - deeper\n> note\n> - quoted item\n1. last";
+
+        let mut streaming = StreamingResponseFormatter::new(formatter.clone());
+        let mut streamed = String::new();
+        // Split mid-line and mid-marker, as a provider might
+        for chunk in response.as_bytes().chunks(3) {
+            streamed.push_str(&streaming.render_chunk(std::str::from_utf8(chunk).unwrap()));
+        }
+        streamed.push_str(&streaming.finish_rendering());
+
+        assert_eq!(streamed, formatter.format_response(response).unwrap());
+        assert!(streamed.contains("  ◦ nested\n"));
+        assert!(streamed.ends_with("1. last"));
+    }
+
+    #[test]
+    fn test_diff_line_classification() {
+        let formatter = create_code_formatter().unwrap();
+        assert_eq!(formatter.normalize_language("patch"), "diff");
+        assert_eq!(DiffLine::classify("diff --git a/x b/x"), DiffLine::Header);
+        assert_eq!(DiffLine::classify("--- a/x.rs"), DiffLine::Header);
+        assert_eq!(DiffLine::classify("+++ b/x.rs"), DiffLine::Header);
+        assert_eq!(
+            DiffLine::classify("@@ -1,2 +1,2 @@ fn main"),
+            DiffLine::Hunk
+        );
+        assert_eq!(DiffLine::classify("+new"), DiffLine::Added);
+        assert_eq!(DiffLine::classify("-old"), DiffLine::Removed);
+        assert_eq!(DiffLine::classify(" same"), DiffLine::Context);
+    }
+
+    #[test]
+    fn test_raw_diff_in_prose() {
+        assert!(starts_raw_diff("diff --git a/x b/x"));
+        assert!(starts_raw_diff("--- a/src/main.rs"));
+        assert!(starts_raw_diff("@@ -10,3 +10,4 @@"));
+        assert!(!starts_raw_diff("---"));
+        assert!(!starts_raw_diff("- a list item"));
+        assert!(continues_diff("+added"));
+        assert!(!continues_diff(""));
+        assert!(!continues_diff("Now run the tests."));
+
+        colored::control::set_override(false);
+        let formatter = create_code_formatter().unwrap();
+        let response = "Here is the change:\ndiff --git a/x b/x\n--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-old\n+new\n\n- a list item";
+        let formatted = formatter.format_response(response).unwrap();
+        // Diff lines aren't rendered as list items, but the text after it is
+        assert!(formatted.contains("\n-old\n+new\n\n• a list item"));
+
+        let mut streaming = StreamingResponseFormatter::new(formatter.clone());
+        let mut streamed = String::new();
+        for chunk in response.as_bytes().chunks(4) {
+            streamed.push_str(&streaming.render_chunk(std::str::from_utf8(chunk).unwrap()));
+        }
+        streamed.push_str(&streaming.finish_rendering());
+        assert_eq!(streamed, formatted);
+    }
+
+    #[test]
+    fn test_wrap_words() {
+        assert_eq!(wrap_words("short line", 20), "short line");
+        assert_eq!(
+            wrap_words("the quick brown fox jumps", 10),
+            "the quick\nbrown fox\njumps"
+        );
+        // List text hangs under the bullet, colour codes don't count
+        assert_eq!(
+            wrap_words("  \x1b[36m•\x1b[0m one two three", 12),
+            "  \x1b[36m•\x1b[0m one two\n    three"
+        );
+        assert_eq!(wrap_words("1. alpha beta", 9), "1. alpha\n   beta");
+        // Long commands stay whole so they can be copied
+        let command = "`cargo test --workspace --all-features`";
+        assert_eq!(
+            wrap_words(&format!("run {} now", command), 10),
+            format!("run\n{}\nnow", command)
+        );
+    }
+
+    #[test]
+    fn test_math_in_prose() {
+        colored::control::set_override(false);
+        let formatter = create_code_formatter().unwrap();
+        let response = "The area is $\\pi r^2$.\n$$\n\\sum_{i=1}^{n} i = \\frac{n(n+1)}{2}\n$$\n- costs $5 and $10\n`$HOME$`";
+        let formatted = formatter.format_response(response).unwrap();
+        assert_eq!(
+            formatted,
+            "The area is π r².\n\n    ∑ᵢ₌₁ⁿ i = (n(n+1))/2\n\n• costs $5 and $10\n`$HOME$`"
+        );
+
+        let mut streaming = StreamingResponseFormatter::new(formatter.clone());
+        let mut streamed = String::new();
+        for chunk in response.as_bytes().chunks(5) {
+            streamed.push_str(&streaming.render_chunk(std::str::from_utf8(chunk).unwrap()));
+        }
+        streamed.push_str(&streaming.finish_rendering());
+        assert_eq!(streamed, formatted);
+    }
+
     // ============================================================================
     // Edge Cases and Special Scenarios
     // ============================================================================
@@ -1837,6 +2509,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_streaming_formatter_sets_thinking_apart_from_the_answer() -> Result<()> {
+        let formatter = create_code_formatter()?;
+        let mut streaming = StreamingResponseFormatter::new(formatter);
+        let mut rendered = streaming.render_thinking("2 and 2\nmake ");
+        rendered.push_str(&streaming.render_thinking("4."));
+        rendered.push_str(&streaming.render_chunk("The answer "));
+        rendered.push_str(&streaming.render_chunk("is 4.\n"));
+        rendered.push_str(&streaming.finish_rendering());
+        assert_eq!(
+            rendered,
+            "💭 Thinking\n│ 2 and 2\n│ make 4.\n\nThe answer is 4.\n"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_streaming_formatter_empty_code_block() -> Result<()> {
         let formatter = create_code_formatter()?;
@@ -1929,8 +2617,8 @@ mod tests {
     #[test]
     fn test_code_block_footer_width_calculation() {
         let formatter = create_code_formatter().unwrap();
-        let footer_short = formatter.build_code_block_footer("c");
-        let footer_long = formatter.build_code_block_footer("typescript");
+        let footer_short = formatter.build_code_block_footer("c", None, None);
+        let footer_long = formatter.build_code_block_footer("typescript", None, None);
 
         // Longer language name should result in longer footer
         assert!(footer_long.len() > footer_short.len());
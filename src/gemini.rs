@@ -121,7 +121,7 @@ pub struct GeminiClient {
 impl GeminiClient {
     pub fn new(api_key: String, base_url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http_client::client(),
             api_key,
             base_url: base_url.trim_end_matches('/').to_string(),
         }
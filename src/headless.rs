@@ -0,0 +1,570 @@
+use colored::*;
+use futures_util::FutureExt;
+use log::warn;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use crate::agent::{Agent, LlmRequestError, StreamToolEvent, STREAM_SCHEMA_VERSION};
+use crate::pricing;
+use crate::security::{PermissionHandler, PermissionKind, PermissionPrompt};
+
+/// How the result of a `-m` or stdin run is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Formatted text for people
+    #[default]
+    Text,
+    /// A single JSON object for scripts, with all other output on stderr
+    Json,
+    /// Newline-delimited JSON events as the run progresses
+    StreamJson,
+}
+
+impl OutputFormat {
+    /// Whether stdout is reserved for machine-readable output
+    pub fn is_json(self) -> bool {
+        matches!(self, Self::Json | Self::StreamJson)
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "stream-json" => Ok(Self::StreamJson),
+            other => Err(format!(
+                "Unsupported output format '{}' (expected text, json or stream-json)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+            Self::StreamJson => write!(f, "stream-json"),
+        }
+    }
+}
+
+/// Process exit codes for non-interactive runs. 2 is left to clap for
+/// command line usage errors.
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    /// Anything else: bad configuration, I/O failures, tool errors
+    pub const ERROR: i32 = 1;
+    /// The LLM provider request failed
+    pub const API_ERROR: i32 = 3;
+    /// A tool call was blocked by the security policy or denied permission
+    pub const PERMISSION_DENIED: i32 = 4;
+    /// The configured token budget was used up
+    pub const BUDGET_EXCEEDED: i32 = 5;
+    /// The run stopped after --max-turns model requests
+    pub const MAX_TURNS: i32 = 6;
+    /// Interrupted with Ctrl+C
+    pub const CANCELLED: i32 = 130;
+}
+
+/// Pick the exit code for a finished run. Failures take precedence over
+/// limits, and limits over denied tool calls the model worked around.
+pub fn exit_code_for(
+    agent: &Agent,
+    response: &anyhow::Result<String>,
+    tool_calls: &[ToolCallRecord],
+) -> i32 {
+    match response {
+        Err(e) if e.to_string().contains("CANCELLED") => exit_code::CANCELLED,
+        Err(e) if e.downcast_ref::<LlmRequestError>().is_some() => exit_code::API_ERROR,
+        Err(_) => exit_code::ERROR,
+        Ok(_) if agent.max_turns_reached() => exit_code::MAX_TURNS,
+        Ok(_) if agent.token_budget_exceeded() => exit_code::BUDGET_EXCEEDED,
+        Ok(_) if tool_calls.iter().any(ToolCallRecord::was_denied) => exit_code::PERMISSION_DENIED,
+        Ok(_) => exit_code::SUCCESS,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Success,
+    Error,
+    Cancelled,
+}
+
+/// A tool the agent called while answering, with its result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCallRecord {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    pub result: Option<String>,
+    pub is_error: Option<bool>,
+}
+
+impl ToolCallRecord {
+    /// Whether the call was blocked by the security policy or a denied prompt
+    pub fn was_denied(&self) -> bool {
+        self.is_error == Some(true)
+            && self
+                .result
+                .as_deref()
+                .is_some_and(|result| result.starts_with("🔒 Security:"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageSummary {
+    pub requests: u32,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// The machine-readable result of a non-interactive run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunResult {
+    pub status: RunStatus,
+    pub result: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub conversation_id: Option<String>,
+    pub model: String,
+    pub tool_calls: Vec<ToolCallRecord>,
+    pub usage: UsageSummary,
+    /// Estimated from list prices; null for models without a known price
+    pub cost_usd: Option<f64>,
+    pub duration_ms: u128,
+    /// The process exit code, see [`exit_code`]
+    pub exit_code: i32,
+}
+
+impl RunResult {
+    /// Summarize a finished run of `agent`
+    pub fn new(
+        agent: &Agent,
+        response: &anyhow::Result<String>,
+        tool_calls: Vec<ToolCallRecord>,
+        duration: std::time::Duration,
+    ) -> Self {
+        let usage = agent.get_token_usage();
+        let exit_code = exit_code_for(agent, response, &tool_calls);
+        let (status, result, error) = match response {
+            Ok(text) => (RunStatus::Success, text.clone(), None),
+            Err(e) if e.to_string().contains("CANCELLED") => {
+                (RunStatus::Cancelled, String::new(), Some(e.to_string()))
+            }
+            Err(e) => (RunStatus::Error, String::new(), Some(e.to_string())),
+        };
+        Self {
+            status,
+            result,
+            error,
+            conversation_id: agent.current_conversation_id(),
+            model: agent.model().to_string(),
+            tool_calls,
+            usage: UsageSummary {
+                requests: usage.request_count,
+                input_tokens: usage.total_input_tokens,
+                output_tokens: usage.total_output_tokens,
+                total_tokens: usage.total_tokens(),
+            },
+            cost_usd: pricing::estimate_cost(
+                agent.provider(),
+                agent.model(),
+                usage.total_input_tokens,
+                usage.total_output_tokens,
+            ),
+            duration_ms: duration.as_millis(),
+            exit_code,
+        }
+    }
+}
+
+/// One line of `--output-format stream-json` output
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Start {
+        /// See [`STREAM_SCHEMA_VERSION`]
+        schema_version: u32,
+        conversation_id: Option<String>,
+        model: String,
+    },
+    Text {
+        text: String,
+    },
+    ToolStart {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolEnd {
+        id: String,
+        name: String,
+        result: Option<String>,
+        is_error: Option<bool>,
+    },
+    /// A tool needed permission; there is nobody to ask, so it was denied
+    Permission {
+        kind: &'static str,
+        summary: String,
+        detail: String,
+        decision: &'static str,
+    },
+    Result(RunResult),
+    /// Thinking, tool argument deltas, usage and the other turn events
+    #[serde(untagged)]
+    Turn(StreamToolEvent),
+}
+
+impl StreamEvent {
+    /// The start of a run's output
+    pub fn start(conversation_id: Option<String>, model: String) -> Self {
+        Self::Start {
+            schema_version: STREAM_SCHEMA_VERSION,
+            conversation_id,
+            model,
+        }
+    }
+}
+
+/// Print an event as a single line on stdout
+pub fn emit(event: &StreamEvent) {
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+/// A permission handler for unattended runs. Anything the allow lists, hooks
+/// or --yolo didn't already permit is denied and reported as an event.
+pub fn deny_permission_handler() -> PermissionHandler {
+    Arc::new(|prompt: PermissionPrompt| {
+        emit(&StreamEvent::Permission {
+            kind: match prompt.kind {
+                PermissionKind::Bash => "bash",
+                PermissionKind::File => "file",
+                PermissionKind::Sampling => "sampling",
+                PermissionKind::Question => "question",
+            },
+            summary: prompt.summary,
+            detail: prompt.detail,
+            decision: "denied",
+        });
+        async { None }.boxed()
+    })
+}
+
+/// Like [`deny_permission_handler`], but only logs what was denied
+pub fn warn_and_deny_permission_handler() -> PermissionHandler {
+    Arc::new(|prompt: PermissionPrompt| {
+        warn!(
+            "Denied without asking: {} ({})",
+            prompt.summary,
+            prompt.detail.replace('\n', ", ")
+        );
+        async { None }.boxed()
+    })
+}
+
+/// Collects tool calls and their results from the agent's tool events
+#[derive(Clone, Default)]
+pub struct ToolCallCollector {
+    calls: Arc<Mutex<Vec<ToolCallRecord>>>,
+    /// Also emit tool_start and tool_end events
+    emit_events: bool,
+    /// Also print each call and result to stderr (`--verbose`)
+    trace: bool,
+}
+
+impl ToolCallCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn streaming() -> Self {
+        Self {
+            emit_events: true,
+            ..Self::default()
+        }
+    }
+
+    /// Print calls and results to stderr as they happen
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn record(&self, event: StreamToolEvent) {
+        if self.trace {
+            if let Some(line) = trace_line(&event) {
+                eprintln!("{}", line);
+            }
+        }
+        if self.emit_events {
+            emit(&match &event {
+                StreamToolEvent::ToolCall {
+                    tool_use_id,
+                    name,
+                    input,
+                } => StreamEvent::ToolStart {
+                    id: tool_use_id.clone(),
+                    name: name.clone(),
+                    input: input.clone(),
+                },
+                StreamToolEvent::ToolResult {
+                    tool_use_id,
+                    name,
+                    content,
+                    is_error,
+                } => StreamEvent::ToolEnd {
+                    id: tool_use_id.clone(),
+                    name: name.clone(),
+                    result: Some(content.clone()),
+                    is_error: Some(*is_error),
+                },
+                other => StreamEvent::Turn(other.clone()),
+            });
+        }
+
+        let Ok(mut calls) = self.calls.lock() else {
+            return;
+        };
+        match event {
+            StreamToolEvent::ToolCall {
+                tool_use_id,
+                name,
+                input,
+            } => calls.push(ToolCallRecord {
+                id: tool_use_id,
+                name,
+                input,
+                result: None,
+                is_error: None,
+            }),
+            StreamToolEvent::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+                ..
+            } => {
+                if let Some(call) = calls.iter_mut().rev().find(|c| c.id == tool_use_id) {
+                    call.result = Some(content);
+                    call.is_error = Some(is_error);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn callback(&self) -> Arc<dyn Fn(StreamToolEvent) + Send + Sync + 'static> {
+        let collector = self.clone();
+        Arc::new(move |event| collector.record(event))
+    }
+
+    pub fn take(&self) -> Vec<ToolCallRecord> {
+        self.calls
+            .lock()
+            .map(|mut calls| std::mem::take(&mut *calls))
+            .unwrap_or_default()
+    }
+}
+
+/// Longest argument or result text shown in a trace line
+const TRACE_WIDTH: usize = 200;
+
+/// A one-line description of a tool call or result for `--verbose`
+fn trace_line(event: &StreamToolEvent) -> Option<String> {
+    match event {
+        StreamToolEvent::ToolCall { name, input, .. } => {
+            let input = serde_json::to_string(input).unwrap_or_default();
+            Some(format!(
+                "{} {} {}",
+                "→".cyan(),
+                name.bold(),
+                truncate_for_trace(&input).dimmed()
+            ))
+        }
+        StreamToolEvent::ToolResult {
+            name,
+            content,
+            is_error,
+            ..
+        } => {
+            let lines = content.lines().count();
+            let marker = if *is_error {
+                "✗".red()
+            } else {
+                "✓".green()
+            };
+            Some(format!(
+                "{} {} ({} line{}) {}",
+                marker,
+                name.bold(),
+                lines,
+                if lines == 1 { "" } else { "s" },
+                truncate_for_trace(content).dimmed()
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Collapse whitespace onto one line and cut it to TRACE_WIDTH characters
+fn truncate_for_trace(text: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= TRACE_WIDTH {
+        flat
+    } else {
+        let truncated: String = flat.chars().take(TRACE_WIDTH).collect();
+        format!("{}…", truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(kind: &str, id: &str) -> StreamToolEvent {
+        match kind {
+            "tool_call" => StreamToolEvent::ToolCall {
+                tool_use_id: id.to_string(),
+                name: "Read".to_string(),
+                input: json!({"file_path": "a.txt"}),
+            },
+            _ => result_event(id, "contents", false),
+        }
+    }
+
+    fn result_event(id: &str, content: &str, is_error: bool) -> StreamToolEvent {
+        StreamToolEvent::ToolResult {
+            tool_use_id: id.to_string(),
+            name: "Read".to_string(),
+            content: content.to_string(),
+            is_error,
+        }
+    }
+
+    #[test]
+    fn collector_pairs_calls_with_results() {
+        let collector = ToolCallCollector::new();
+        let callback = collector.callback();
+        callback(event("tool_call", "1"));
+        callback(event("tool_call", "2"));
+        callback(event("tool_result", "1"));
+
+        let calls = collector.take();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].input, json!({"file_path": "a.txt"}));
+        assert_eq!(calls[0].result.as_deref(), Some("contents"));
+        assert_eq!(calls[0].is_error, Some(false));
+        assert_eq!(calls[1].result, None);
+        assert!(collector.take().is_empty());
+    }
+
+    #[test]
+    fn trace_lines_are_single_truncated_lines() {
+        colored::control::set_override(false);
+        let call = trace_line(&event("tool_call", "1")).unwrap();
+        assert_eq!(call, r#"→ Read {"file_path":"a.txt"}"#);
+
+        let content = format!("first\nsecond\n{}", "x".repeat(300));
+        let line = trace_line(&result_event("1", &content, false)).unwrap();
+        assert!(line.starts_with("✓ Read (3 lines) first second xxx"));
+        assert!(line.ends_with('…'));
+        assert!(!line.contains('\n'));
+
+        let failed = trace_line(&result_event("1", &content, true)).unwrap();
+        assert!(failed.starts_with("✗ Read"));
+    }
+
+    #[test]
+    fn parses_output_formats() {
+        assert_eq!("json".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert_eq!("TEXT".parse::<OutputFormat>(), Ok(OutputFormat::Text));
+        assert_eq!(
+            "stream-json".parse::<OutputFormat>(),
+            Ok(OutputFormat::StreamJson)
+        );
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn stream_events_are_tagged_by_type() {
+        let event = StreamEvent::ToolEnd {
+            id: "1".to_string(),
+            name: "Bash".to_string(),
+            result: Some("ok".to_string()),
+            is_error: Some(false),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({"type": "tool_end", "id": "1", "name": "Bash", "result": "ok", "is_error": false})
+        );
+        assert_eq!(
+            serde_json::to_value(StreamEvent::start(None, "m".to_string())).unwrap(),
+            json!({"type": "start", "schema_version": STREAM_SCHEMA_VERSION, "conversation_id": null, "model": "m"})
+        );
+        let usage = StreamEvent::Turn(StreamToolEvent::Usage {
+            input_tokens: 2,
+            output_tokens: 3,
+            total_tokens: 5,
+        });
+        assert_eq!(
+            serde_json::to_value(&usage).unwrap(),
+            json!({"type": "usage", "input_tokens": 2, "output_tokens": 3, "total_tokens": 5})
+        );
+
+        let result = RunResult {
+            status: RunStatus::Success,
+            result: "done".to_string(),
+            error: None,
+            conversation_id: None,
+            model: "m".to_string(),
+            tool_calls: Vec::new(),
+            usage: UsageSummary {
+                requests: 1,
+                input_tokens: 2,
+                output_tokens: 3,
+                total_tokens: 5,
+            },
+            cost_usd: None,
+            duration_ms: 7,
+            exit_code: 0,
+        };
+        let value = serde_json::to_value(StreamEvent::Result(result)).unwrap();
+        assert_eq!(value["type"], "result");
+        assert_eq!(value["status"], "success");
+        assert_eq!(value["usage"]["total_tokens"], 5);
+        assert_eq!(value["exit_code"], 0);
+    }
+
+    #[test]
+    fn detects_denied_tool_calls() {
+        let mut call = ToolCallRecord {
+            id: "1".to_string(),
+            name: "Bash".to_string(),
+            input: json!({"command": "rm -rf build"}),
+            result: Some(
+                "🔒 Security: Command 'rm -rf build' is not allowed by security policy".to_string(),
+            ),
+            is_error: Some(true),
+        };
+        assert!(call.was_denied());
+
+        call.result = Some("rm: build: No such file or directory".to_string());
+        assert!(!call.was_denied());
+    }
+}
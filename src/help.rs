@@ -1,3 +1,4 @@
+use crate::i18n::{tr, tr_args};
 use colored::*;
 use std::thread;
 
@@ -35,12 +36,15 @@ pub fn print_mcp_help() {
     app_println!("  /mcp add <name> stdio <cmd>  - Add a stdio MCP server");
     app_println!("  /mcp add <name> ws <url>     - Add a WebSocket MCP server");
     app_println!("  /mcp add <name> http <url>   - Add an HTTP MCP server");
+    app_println!("  /mcp add <name> sse <url>    - Add a legacy HTTP+SSE MCP server");
     app_println!("  /mcp remove <name>           - Remove an MCP server");
+    app_println!("  /mcp import <source>         - Import servers from claude-desktop, cursor, or a .mcp.json file");
     app_println!("  /mcp connect <name>          - Connect to a specific server");
     app_println!("  /mcp disconnect <name>       - Disconnect from a specific server");
     app_println!("  /mcp reconnect <name>        - Reconnect to a specific server");
     app_println!("  /mcp connect-all             - Connect to all enabled servers");
     app_println!("  /mcp disconnect-all          - Disconnect from all servers");
+    app_println!("  /mcp logout <name>           - Forget the stored OAuth token for a server");
     app_println!();
     app_println!("{}", "Testing & Debugging:".green().bold());
     app_println!("  /mcp test <command>          - Test if a command is available");
@@ -58,44 +62,69 @@ pub fn print_mcp_help() {
 
 /// Print file permissions help information
 pub fn print_file_permissions_help() {
-    app_println!("{}", "🔒 File Permissions Commands".cyan().bold());
-    app_println!();
-    app_println!("{}", "View File Permissions:".green().bold());
+    let operations = "write_file, edit_file, delete_file, create_directory";
+    title("help-file-permissions-title");
+    app_println!();
+    heading("help-file-permissions-view");
+    entry(32, "/file-permissions", "help-file-permissions-show");
+    entry(32, "/file-permissions show", "help-file-permissions-alias");
+    entry(32, "/file-permissions list", "help-file-permissions-alias");
+    entry(32, "/file-permissions help", "help-permissions-help");
+    app_println!();
+    heading("help-permissions-testing");
+    entry(
+        32,
+        "/file-permissions test <op> <path>",
+        "help-file-permissions-test",
+    );
     app_println!(
-        "  /file-permissions                - Show current file permissions and security settings"
+        "    {}",
+        tr_args(
+            "help-file-permissions-operations",
+            &[("operations", operations)]
+        )
+    );
+    app_println!();
+    heading("help-permissions-settings");
+    entry(
+        32,
+        "/file-permissions enable",
+        "help-file-permissions-enable",
+    );
+    entry(
+        32,
+        "/file-permissions disable",
+        "help-file-permissions-disable",
     );
-    app_println!("  /file-permissions show          - Alias for /file-permissions");
-    app_println!("  /file-permissions list          - Alias for /file-permissions");
-    app_println!("  /file-permissions help          - Show this help message");
-    app_println!();
-    app_println!("{}", "Testing:".green().bold());
-    app_println!("  /file-permissions test <op> <path> - Test if file operation is allowed");
-    app_println!("    Operations: write_file, edit_file, delete_file, create_directory");
-    app_println!();
-    app_println!("{}", "Security Settings:".green().bold());
-    app_println!("  /file-permissions enable        - Enable file security");
-    app_println!("  /file-permissions disable       - Disable file security");
-    app_println!("  /file-permissions ask-on        - Enable asking for permission");
-    app_println!("  /file-permissions ask-off       - Disable asking for permission");
-    app_println!("  /file-permissions reset-session - Reset session permissions");
-    app_println!();
-    app_println!("{}", "Permission Options:".green().bold());
-    app_println!("  When a file operation requires permission, you can choose:");
-    app_println!("  • Allow this operation only - One-time permission");
-    app_println!("  • Allow all file operations this session - Session-wide permission");
-    app_println!("  • Deny this operation - Block the operation");
-    app_println!();
-    app_println!("{}", "Security Tips:".yellow().bold());
-    app_println!("  • Enable 'ask for permission' for better security");
-    app_println!("  • Use 'Allow this operation only' for one-off edits");
-    app_println!("  • Use 'Allow all file operations this session' for trusted sessions");
+    entry(32, "/file-permissions ask-on", "help-permissions-ask-on");
+    entry(32, "/file-permissions ask-off", "help-permissions-ask-off");
+    entry(
+        32,
+        "/file-permissions reset-session",
+        "help-file-permissions-reset",
+    );
+    app_println!();
+    heading("help-file-permissions-options");
+    line("help-file-permissions-options-intro");
+    line("help-file-permissions-option-once");
+    line("help-file-permissions-option-session");
+    line("help-file-permissions-option-deny");
+    app_println!();
+    app_println!("{}", tr("help-permissions-tips").yellow().bold());
+    line("help-file-permissions-tip-ask");
+    line("help-file-permissions-tip-once");
+    line("help-file-permissions-tip-session");
     app_println!(
-        "  • File operations include: write_file, edit_file, create_directory, delete_file"
+        "  {}",
+        tr_args(
+            "help-file-permissions-tip-operations",
+            &[("operations", operations)]
+        )
     );
-    app_println!("  • Read operations (read_file, list_directory) are always allowed");
-    app_println!("  • Session permissions are reset when you restart the agent");
+    line("help-file-permissions-tip-reads");
+    line("help-file-permissions-tip-reset");
     app_println!();
-    app_println!("{}", "Examples:".green().bold());
+    heading("help-examples");
     app_println!("  /file-permissions test write_file /tmp/test.txt");
     app_println!("  /file-permissions enable");
     app_println!("  /file-permissions ask-on");
@@ -181,138 +210,219 @@ pub fn print_skill_help() {
     app_println!();
 }
 
+/// Print the custom commands, grouped by namespace, after the built-in ones
+pub fn print_custom_commands_help(commands: &[crate::custom_commands::CustomCommand]) {
+    if commands.is_empty() {
+        return;
+    }
+    app_println!("{}", "Custom commands:".green().bold());
+    for (namespace, group) in crate::custom_commands::group_by_namespace(commands) {
+        let indent = match namespace {
+            Some(namespace) => {
+                app_println!("  {}", format!("{}:", namespace).cyan());
+                "    "
+            }
+            None => "  ",
+        };
+        for command in group {
+            let usage = match &command.argument_hint {
+                Some(hint) => format!("/{} {}", command.name, hint),
+                None => format!("/{}", command.name),
+            };
+            match &command.description {
+                Some(description) => app_println!("{}{} - {}", indent, usage, description),
+                None => app_println!("{}{}", indent, usage),
+            }
+        }
+    }
+    app_println!();
+}
+
 /// Print permissions help information
 pub fn print_permissions_help() {
-    app_println!("{}", "🔒 Permissions Commands".cyan().bold());
-    app_println!();
-    app_println!("{}", "View Permissions:".green().bold());
-    app_println!("  /permissions                - Show current permissions and security settings");
-    app_println!("  /permissions show          - Alias for /permissions");
-    app_println!("  /permissions list          - Alias for /permissions");
-    app_println!("  /permissions help          - Show this help message");
-    app_println!();
-    app_println!("{}", "Manage Allowlist:".green().bold());
-    app_println!("  /permissions allow <cmd>    - Add command to allowlist");
-    app_println!("  /permissions remove-allow <cmd> - Remove from allowlist");
-    app_println!();
-    app_println!("{}", "Manage Denylist:".green().bold());
-    app_println!("  /permissions deny <cmd>     - Add command to denylist");
-    app_println!("  /permissions remove-deny <cmd> - Remove from denylist");
-    app_println!();
-    app_println!("{}", "Security Settings:".green().bold());
-    app_println!("  /permissions enable         - Enable bash security");
-    app_println!("  /permissions disable        - Disable bash security");
-    app_println!("  /permissions ask-on         - Enable asking for permission");
-    app_println!("  /permissions ask-off        - Disable asking for permission");
+    title("help-permissions-title");
+    app_println!();
+    heading("help-permissions-view");
+    entry(27, "/permissions", "help-permissions-show");
+    entry(27, "/permissions show", "help-permissions-alias");
+    entry(27, "/permissions list", "help-permissions-alias");
+    entry(27, "/permissions help", "help-permissions-help");
+    app_println!();
+    heading("help-permissions-allowlist");
+    entry(27, "/permissions allow <cmd>", "help-permissions-allow");
+    entry(
+        27,
+        "/permissions remove-allow <cmd>",
+        "help-permissions-remove-allow",
+    );
     app_println!();
-    app_println!("{}", "Testing:".green().bold());
-    app_println!("  /permissions test <cmd>     - Test if a command is allowed");
+    heading("help-permissions-denylist");
+    entry(27, "/permissions deny <cmd>", "help-permissions-deny");
+    entry(
+        27,
+        "/permissions remove-deny <cmd>",
+        "help-permissions-remove-deny",
+    );
     app_println!();
-    app_println!("{}", "Pattern Matching:".green().bold());
-    app_println!("  • Use wildcards: 'git *' allows all git commands");
-    app_println!("  • Use exact match: 'cargo test' allows only that command");
-    app_println!("  • Prefix matching: 'git' matches 'git status', 'git log', etc.");
+    heading("help-permissions-settings");
+    entry(27, "/permissions enable", "help-permissions-enable");
+    entry(27, "/permissions disable", "help-permissions-disable");
+    entry(27, "/permissions ask-on", "help-permissions-ask-on");
+    entry(27, "/permissions ask-off", "help-permissions-ask-off");
+    app_println!();
+    heading("help-permissions-testing");
+    entry(27, "/permissions test <cmd>", "help-permissions-test");
+    app_println!();
+    heading("help-permissions-patterns");
+    line("help-permissions-pattern-wildcard");
+    line("help-permissions-pattern-exact");
+    line("help-permissions-pattern-prefix");
+    app_println!();
+    heading("help-examples");
+    entry(
+        27,
+        "/permissions allow 'git *'",
+        "help-permissions-example-allow",
+    );
+    entry(
+        27,
+        "/permissions deny 'rm *'",
+        "help-permissions-example-deny",
+    );
+    entry(
+        27,
+        "/permissions test 'ls -la'",
+        "help-permissions-example-test",
+    );
+    entry(27, "/permissions enable", "help-permissions-example-enable");
+    entry(27, "/permissions ask-on", "help-permissions-example-ask");
     app_println!();
-    app_println!("{}", "Examples:".green().bold());
-    app_println!("  /permissions allow 'git *'  - Allow all git commands");
-    app_println!("  /permissions deny 'rm *'    - Deny dangerous rm commands");
-    app_println!("  /permissions test 'ls -la'  - Test if ls -la is allowed");
-    app_println!("  /permissions enable         - Turn security on");
-    app_println!("  /permissions ask-on         - Ask for unknown commands");
-    app_println!();
-    app_println!("{}", "Security Tips:".yellow().bold());
-    app_println!("  • Be specific with allowlist entries for better security");
-    app_println!("  • Use denylist for dangerous command patterns");
-    app_println!("  • Enable 'ask for permission' for unknown commands");
-    app_println!("  • Changes are automatically saved to config file");
+    app_println!("{}", tr("help-permissions-tips").yellow().bold());
+    line("help-permissions-tip-specific");
+    line("help-permissions-tip-denylist");
+    line("help-permissions-tip-ask");
+    line("help-permissions-tip-saved");
     app_println!();
 }
 
 /// Print the main help message
 pub fn print_help() {
-    app_println!("{}", "🤖 Flexorama - Slash Commands".cyan().bold());
-    app_println!();
-    app_println!("{}", "Available commands:".green().bold());
-    app_println!("  /help         - Show this help message");
-    app_println!("  /stats        - Show token usage statistics");
-    app_println!("  /usage        - Show token usage statistics (alias for /stats)");
-    app_println!("  /context      - Show current conversation context");
-    app_println!("  /provider     - Show active LLM provider, model, and base URL");
-    app_println!("  /model        - Show or set the active model");
-    app_println!("  /search <q>   - Search previous conversations");
-    app_println!("  /resume       - Resume a previous conversation");
-    app_println!("  /clear        - Clear all conversation context (keeps AGENTS.md if it exists)");
-    app_println!("  /reset-stats  - Reset token usage statistics");
-    app_println!("  /permissions  - Manage bash command security permissions");
-    app_println!("  /file-permissions  - Manage file operation security permissions");
-    app_println!("  /hooks        - View and manage hooks configuration");
-    app_println!("  /mcp          - Manage MCP (Model Context Protocol) servers");
-    app_println!("  /skill        - Manage skills (list, create, update, delete, deactivate)");
-    app_println!("  /exit         - Exit the program");
-    app_println!("  /quit         - Exit the program");
-    app_println!();
-    app_println!("{}", "Navigation:".green().bold());
-    app_println!("  ↑ / ↓ Arrow   - Navigate through input history");
-    app_println!("  ← / → Arrow   - Move cursor left/right in current input");
-    app_println!("  Tab           - Auto-complete file paths and commands");
-    app_println!("  Ctrl+R        - Start reverse history search (like readline)");
-    app_println!("  ESC           - Cancel current AI conversation (during processing)");
-    app_println!("  Ctrl+C        - Exit the program immediately");
-    app_println!();
-    app_println!("{}", "Reverse Search (Ctrl+R):".green().bold());
-    app_println!("  Ctrl+R        - Start reverse search mode");
-    app_println!("  Type text     - Search for matching history entries");
-    app_println!("  Ctrl+R / r    - Find next match");
-    app_println!("  ↑ / ↓ Arrow   - Navigate between matches");
-    app_println!("  Enter         - Accept current match");
-    app_println!("  ESC           - Cancel search and restore original input");
-    app_println!("  Backspace     - Remove last character from search query");
-    app_println!();
-    app_println!("{}", "Shell Commands:".green().bold());
-    app_println!("  !<command>    - Execute a shell command directly (bypasses all security)");
-    app_println!("  Examples: !dir, !ls -la, !git status, !cargo test");
-    app_println!("  Note: ! commands execute immediately without permission checks");
-    app_println!();
-    app_println!("{}", "Security Commands:".green().bold());
-    app_println!("  /permissions              - Show current bash security settings");
-    app_println!("  /file-permissions        - Show current file security settings");
-    app_println!("  /permissions allow <cmd>  - Add command to allowlist");
-    app_println!("  /permissions deny <cmd>   - Add command to denylist");
-    app_println!("  /permissions test <cmd>  - Test if command is allowed");
-    app_println!("  /file-permissions test <op> <path> - Test if file operation is allowed");
-    app_println!("  /plan on|off             - Toggle plan mode at runtime");
-    app_println!("  /plan run <id>           - Load and execute a saved plan by ID");
-    app_println!();
-    app_println!("{}", "MCP Commands:".green().bold());
-    app_println!("  /mcp list                    - List MCP servers");
-    app_println!("  /mcp add <name> stdio <cmd>  - Add stdio server");
-    app_println!("  /mcp add <name> ws <url>     - Add WebSocket server");
-    app_println!("  /mcp add <name> http <url>   - Add HTTP server");
-    app_println!("  /mcp test <command>          - Test command availability");
-    app_println!("  /mcp connect <name>          - Connect to server");
-    app_println!("  /mcp tools                   - List available tools");
-    app_println!("  /mcp help                    - Show MCP help");
-    app_println!();
-    app_println!("{}", "Context Files:".green().bold());
-    app_println!("  Use -f or --file to include files as context");
-    app_println!("  Use @path/to/file syntax in messages to auto-include files");
-    app_println!("  AGENTS.md is automatically included from ~/.flexorama/AGENTS.md (priority)");
-    app_println!("  Falls back to ./AGENTS.md if home directory version doesn't exist");
-    app_println!("  Messages with only @file references will NOT make API calls");
-    app_println!();
-    app_println!("{}", "System Prompts:".green().bold());
-    app_println!("  Use -s or --system to set a custom system prompt");
-    app_println!("  System prompts set the behavior and personality of the AI");
-    app_println!();
-    app_println!("{}", "Streaming:".green().bold());
-    app_println!("  Streaming is the default for all CLI modes");
-    app_println!("  Use --no-stream to disable streaming (spinner + formatted response)");
-    app_println!();
-    app_println!("{}", "Plan Mode:".green().bold());
-    app_println!("  Use --plan-mode to generate a read-only plan in Markdown");
-    app_println!("  Plan mode disables mutating tools and saves the plan to the database");
-    app_println!();
-    app_println!("{}", "Examples:".green().bold());
+    title("help-title");
+    app_println!();
+    heading("help-available-commands");
+    entry(13, "/help", "help-cmd-help");
+    entry(13, "/stats", "help-cmd-stats");
+    entry(13, "/usage", "help-cmd-usage");
+    entry(13, "/context", "help-cmd-context");
+    entry(13, "/context remove <n>", "help-cmd-context-remove");
+    entry(13, "/context messages", "help-cmd-context-messages");
+    entry(13, "/provider", "help-cmd-provider");
+    entry(13, "/model", "help-cmd-model");
+    entry(13, "/search <q>", "help-cmd-search");
+    entry(13, "/resume", "help-cmd-resume");
+    entry(13, "/rename <name>", "help-cmd-rename");
+    entry(13, "/env set KEY=value", "help-cmd-env");
+    entry(13, "/history [q]", "help-cmd-history");
+    entry(13, "/diff", "help-cmd-diff");
+    entry(13, "/checkpoints", "help-cmd-checkpoints");
+    entry(
+        13,
+        "/checkpoints revert [id]",
+        "help-cmd-checkpoints-revert",
+    );
+    entry(13, "/pr [--base <branch>] [--draft]", "help-cmd-pr");
+    entry(13, "/share [--file]", "help-cmd-share");
+    entry(13, "/learn", "help-cmd-learn");
+    entry(13, "/voice", "help-cmd-voice");
+    entry(13, "/clear", "help-cmd-clear");
+    entry(13, "/reset-stats", "help-cmd-reset-stats");
+    entry(13, "/permissions", "help-cmd-permissions");
+    entry(13, "/file-permissions", "help-cmd-file-permissions");
+    entry(13, "/hooks", "help-cmd-hooks");
+    entry(13, "/config sources", "help-cmd-config-sources");
+    entry(13, "/config get [key]", "help-cmd-config-get");
+    entry(13, "/config set <key> <value>", "help-cmd-config-set");
+    entry(13, "/copy [n]", "help-cmd-copy");
+    entry(13, "/mcp", "help-cmd-mcp");
+    entry(13, "/skill", "help-cmd-skill");
+    entry(13, "/exit", "help-cmd-exit");
+    entry(13, "/quit", "help-cmd-exit");
+    app_println!();
+    heading("help-navigation");
+    entry(13, "↑ / ↓ Arrow", "help-nav-history");
+    entry(13, "← / → Arrow", "help-nav-cursor");
+    entry(13, "Tab", "help-nav-tab");
+    entry(13, "Ctrl+R", "help-nav-search");
+    entry(13, "Ctrl+Y", "help-nav-copy");
+    entry(13, "Ctrl+P", "help-nav-model");
+    entry(13, "ESC", "help-nav-cancel");
+    entry(13, "Ctrl+C", "help-nav-exit");
+    app_println!();
+    heading("help-reverse-search");
+    entry(13, "Ctrl+R", "help-search-start");
+    entry(13, &tr("help-search-type"), "help-search-type-desc");
+    entry(13, "Ctrl+R / r", "help-search-next");
+    entry(13, "↑ / ↓ Arrow", "help-search-navigate");
+    entry(13, "Enter", "help-search-accept");
+    entry(13, "ESC", "help-search-cancel");
+    entry(13, "Backspace", "help-search-backspace");
+    app_println!();
+    heading("help-shell-commands");
+    entry(13, "!<command>", "help-shell-run");
+    app_println!(
+        "  {}",
+        tr_args(
+            "help-shell-examples",
+            &[("examples", "!dir, !ls -la, !git status, !cargo test")]
+        )
+    );
+    line("help-shell-note");
+    app_println!();
+    heading("help-security-commands");
+    entry(25, "/permissions", "help-sec-permissions");
+    entry(25, "/file-permissions", "help-sec-file-permissions");
+    entry(25, "/permissions allow <cmd>", "help-sec-allow");
+    entry(25, "/permissions deny <cmd>", "help-sec-deny");
+    entry(25, "/permissions test <cmd>", "help-sec-test");
+    entry(
+        25,
+        "/file-permissions test <op> <path>",
+        "help-sec-file-test",
+    );
+    entry(25, "/plan on|off", "help-sec-plan");
+    entry(25, "/plan run <id>", "help-sec-plan-run");
+    app_println!();
+    heading("help-mcp-commands");
+    entry(28, "/mcp list", "help-mcp-list");
+    entry(28, "/mcp add <name> stdio <cmd>", "help-mcp-add-stdio");
+    entry(28, "/mcp add <name> ws <url>", "help-mcp-add-ws");
+    entry(28, "/mcp add <name> http <url>", "help-mcp-add-http");
+    entry(28, "/mcp add <name> sse <url>", "help-mcp-add-sse");
+    entry(28, "/mcp test <command>", "help-mcp-test");
+    entry(28, "/mcp connect <name>", "help-mcp-connect");
+    entry(28, "/mcp tools", "help-mcp-tools");
+    entry(28, "/mcp help", "help-mcp-help");
+    app_println!();
+    heading("help-context-files");
+    line("help-context-flag");
+    line("help-context-mention");
+    line("help-context-agents");
+    line("help-context-agents-fallback");
+    line("help-context-only");
+    app_println!();
+    heading("help-system-prompts");
+    line("help-system-flag");
+    line("help-system-purpose");
+    app_println!();
+    heading("help-streaming");
+    line("help-streaming-default");
+    line("help-streaming-disable");
+    app_println!();
+    heading("help-plan-mode");
+    line("help-plan-flag");
+    line("help-plan-readonly");
+    app_println!();
+    heading("help-examples");
     app_println!("  flexorama -f config.toml \"Explain this configuration\"");
     app_println!("  flexorama \"What does @Cargo.toml contain?\"");
     app_println!("  flexorama \"Compare @file1.rs and @file2.rs\"");
@@ -327,125 +437,80 @@ pub fn print_help() {
     app_println!("  !cargo build            # Build the project");
     app_println!("  ESC                     # Cancel AI conversation during processing");
     app_println!();
-    app_println!("{}", "History Navigation:".green().bold());
-    app_println!("  • Press UP arrow to cycle through previous commands");
-    app_println!("  • Press DOWN arrow to cycle through more recent commands");
-    app_println!("  • Press Ctrl+R to start reverse history search");
-    app_println!("  • Start typing to exit history navigation mode");
-    app_println!("  • History is preserved across the entire session");
-    app_println!("  • Duplicate and empty commands are not stored");
+    heading("help-history");
+    line("help-history-up");
+    line("help-history-down");
+    line("help-history-search");
+    line("help-history-typing");
+    line("help-history-session");
+    line("help-history-duplicates");
     app_println!();
-    app_println!(
-        "{}",
-        "Any other input will be sent to the Flexorama for processing.".dimmed()
-    );
+    app_println!("{}", tr("help-footer").dimmed());
     app_println!();
 }
 
 /// Display a large red warning for yolo mode
 pub fn display_yolo_warning() {
     app_println!();
-    app_println!(
-        "{}",
-        "⚠️  WARNING: YOLO MODE ENABLED  ⚠️".red().bold().blink()
-    );
-    app_println!(
-        "{}",
-        " ALL SECURITY PERMISSIONS ARE BYPASSED - USE WITH EXTREME CAUTION "
-            .red()
-            .bold()
-    );
+    app_println!("{}", tr("yolo-title").red().bold().blink());
+    app_println!("{}", banner("yolo-subtitle").red().bold());
     app_println!();
-    app_println!(
-        "{}",
-        " • File operations (read/write/delete) will execute WITHOUT prompts ".red()
-    );
-    app_println!(
-        "{}",
-        " • Bash commands will execute WITHOUT permission checks ".red()
-    );
-    app_println!(
-        "{}",
-        " • MCP tools will execute WITHOUT security validation ".red()
-    );
-    app_println!(
-        "{}",
-        " • No allowlist/denylist filtering will be applied ".red()
-    );
-    app_println!("{}", " • All tool calls are automatically approved ".red());
+    app_println!("{}", banner("yolo-files").red());
+    app_println!("{}", banner("yolo-bash").red());
+    app_println!("{}", banner("yolo-mcp").red());
+    app_println!("{}", banner("yolo-lists").red());
+    app_println!("{}", banner("yolo-auto-approve").red());
     app_println!();
-    app_println!(
-        "{}",
-        " 🚨 This mode can cause irreversible damage to your system! "
-            .red()
-            .bold()
-    );
+    app_println!("{}", banner("yolo-damage").red().bold());
     app_println!();
-    app_println!(
-        "{}",
-        " Press Ctrl+C NOW to cancel if this was not intended! "
-            .red()
-            .bold()
-    );
+    app_println!("{}", banner("yolo-cancel").red().bold());
     app_println!();
 
     // Add a dramatic pause for effect
     thread::sleep(std::time::Duration::from_millis(2000));
-    app_println!(
-        "{}",
-        "🔥 Proceeding in YOLO mode... You have been warned! 🔥"
-            .red()
-            .bold()
-    );
+    app_println!("{}", tr("yolo-proceeding").red().bold());
     app_println!();
 }
 
 /// Display YOLO mode warning after MCP configuration is complete
 pub fn display_mcp_yolo_warning() {
     app_println!();
-    app_println!(
-        "{}",
-        "🔌 MCP Configuration Complete - YOLO Mode Active 🔌"
-            .red()
-            .bold()
-    );
+    app_println!("{}", tr("yolo-mcp-title").red().bold());
     app_println!();
-    app_println!(
-        "{}",
-        " ⚠️  MCP TOOLS WILL EXECUTE WITHOUT SECURITY VALIDATION ⚠️ "
-            .red()
-            .bold()
-    );
+    app_println!("{}", banner("yolo-mcp-subtitle").red().bold());
     app_println!();
-    app_println!(
-        "{}",
-        " • MCP server tools are now available and will execute WITHOUT prompts ".red()
-    );
-    app_println!(
-        "{}",
-        " • No permission checks will be applied to MCP tool calls ".red()
-    );
-    app_println!(
-        "{}",
-        " • All MCP operations (file access, commands, etc.) are auto-approved ".red()
-    );
-    app_println!(
-        "{}",
-        " • External MCP server connections have unrestricted access ".red()
-    );
+    app_println!("{}", banner("yolo-mcp-prompts").red());
+    app_println!("{}", banner("yolo-mcp-checks").red());
+    app_println!("{}", banner("yolo-mcp-auto-approve").red());
+    app_println!("{}", banner("yolo-mcp-access").red());
     app_println!();
-    app_println!(
-        "{}",
-        " 🚨 MCP tools can potentially access and modify your system! "
-            .red()
-            .bold()
-    );
+    app_println!("{}", banner("yolo-mcp-modify").red().bold());
     app_println!();
-    app_println!(
-        "{}",
-        " 🔥 All MCP servers and their tools are operating in YOLO mode! 🔥"
-            .red()
-            .bold()
-    );
+    app_println!("{}", banner("yolo-mcp-active").red().bold());
     app_println!();
 }
+
+/// A section title, in the current language
+fn title(key: &str) {
+    app_println!("{}", tr(key).cyan().bold());
+}
+
+/// A section heading, in the current language
+fn heading(key: &str) {
+    app_println!("{}", tr(key).green().bold());
+}
+
+/// An indented line of help text, in the current language
+fn line(key: &str) {
+    app_println!("  {}", tr(key));
+}
+
+/// A `usage - description` line with the usage padded to `width`
+fn entry(width: usize, usage: &str, key: &str) {
+    app_println!("  {:<width$} - {}", usage, tr(key), width = width);
+}
+
+/// A line of the YOLO warnings, padded with a space on each side
+fn banner(key: &str) -> String {
+    format!(" {} ", tr(key))
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Used for anything a translation is missing
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// The bundled translations: (locale, catalog in Fluent syntax)
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+/// The locales Flexorama has translations for
+pub fn available_locales() -> Vec<&'static str> {
+    LOCALES.iter().map(|(locale, _)| *locale).collect()
+}
+
+/// Messages for one locale, parsed from the simple subset of Fluent the
+/// bundled catalogs use: `key = value` lines with `{ $name }` placeholders,
+/// indented continuation lines and `#` comments
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn parse(source: &str) -> Self {
+        let mut messages = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+        for line in source.lines() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            if line.starts_with(' ') && !line.trim().is_empty() {
+                if let Some((_, value)) = current.as_mut() {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((key, value)) = current.take() {
+                messages.insert(key, value);
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+                if !key.is_empty() {
+                    current = Some((key.to_string(), value.trim().to_string()));
+                }
+            }
+        }
+        if let Some((key, value)) = current {
+            messages.insert(key, value);
+        }
+        Self { messages }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+}
+
+/// Looks up messages in one locale, falling back to English
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    locale: String,
+    primary: Option<Catalog>,
+    fallback: Catalog,
+}
+
+impl Localizer {
+    /// A localizer for the closest bundled match to `locale`, e.g. `es` for
+    /// `es_MX.UTF-8`
+    pub fn new(locale: &str) -> Self {
+        let locale = match_locale(locale).unwrap_or(DEFAULT_LOCALE);
+        let primary = (locale != DEFAULT_LOCALE).then(|| Catalog::parse(catalog_source(locale)));
+        Self {
+            locale: locale.to_string(),
+            primary,
+            fallback: Catalog::parse(catalog_source(DEFAULT_LOCALE)),
+        }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The message for `key` with its `{ $name }` placeholders filled in from
+    /// `args`. Unknown keys come back as the key itself, so a missing message
+    /// is visible rather than blank.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let message = self
+            .primary
+            .as_ref()
+            .and_then(|catalog| catalog.get(key))
+            .or_else(|| self.fallback.get(key));
+        match message {
+            Some(message) => substitute(message, args),
+            None => key.to_string(),
+        }
+    }
+}
+
+fn catalog_source(locale: &str) -> &'static str {
+    LOCALES
+        .iter()
+        .find(|(name, _)| *name == locale)
+        .map(|(_, source)| *source)
+        .unwrap_or("")
+}
+
+/// The bundled locale for a POSIX or BCP 47 locale name such as `fr_CA.UTF-8`
+/// or `fr-CA`, if there is one
+fn match_locale(requested: &str) -> Option<&'static str> {
+    let name = requested
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-")
+        .to_ascii_lowercase();
+    let language = name.split('-').next().unwrap_or_default();
+    LOCALES
+        .iter()
+        .map(|(locale, _)| *locale)
+        .find(|locale| *locale == name)
+        .or_else(|| {
+            LOCALES
+                .iter()
+                .map(|(locale, _)| *locale)
+                .find(|locale| *locale == language)
+        })
+}
+
+fn substitute(message: &str, args: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let placeholder = rest[start + 1..start + end].trim();
+        match placeholder.strip_prefix('$') {
+            Some(name) => {
+                let value = args
+                    .iter()
+                    .find(|(arg, _)| *arg == name)
+                    .map(|(_, value)| *value)
+                    .unwrap_or(placeholder);
+                result.push_str(value);
+            }
+            None => result.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The locale from the environment, as the C library would pick it
+pub fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+static LOCALIZER: OnceLock<RwLock<Arc<Localizer>>> = OnceLock::new();
+
+fn localizer_cell() -> &'static RwLock<Arc<Localizer>> {
+    LOCALIZER.get_or_init(|| RwLock::new(Arc::new(Localizer::new(DEFAULT_LOCALE))))
+}
+
+/// Pick the language for user-facing text: `locale` from the config, or the
+/// system locale when it isn't set
+pub fn set_locale(locale: Option<&str>) {
+    let locale = locale
+        .map(str::to_string)
+        .or_else(system_locale)
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+    let localizer = Arc::new(Localizer::new(&locale));
+    *localizer_cell().write().expect("localizer lock") = localizer;
+}
+
+/// The locale user-facing text is shown in
+pub fn current_locale() -> String {
+    localizer().locale().to_string()
+}
+
+fn localizer() -> Arc<Localizer> {
+    localizer_cell().read().expect("localizer lock").clone()
+}
+
+/// The message for `key` in the current locale
+pub fn tr(key: &str) -> String {
+    localizer().format(key, &[])
+}
+
+/// The message for `key` in the current locale, with placeholders filled in
+pub fn tr_args(key: &str, args: &[(&str, &str)]) -> String {
+    localizer().format(key, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fluent_messages() {
+        let catalog = Catalog::parse(
+            "# Comment\nhello = Hello, { $name }!\n\nlong = First line\n    second line\n",
+        );
+        assert_eq!(catalog.get("hello"), Some("Hello, { $name }!"));
+        assert_eq!(catalog.get("long"), Some("First line\nsecond line"));
+        assert_eq!(
+            substitute(catalog.get("hello").unwrap(), &[("name", "Ana")]),
+            "Hello, Ana!"
+        );
+        assert_eq!(substitute("{ $missing } {x}", &[]), "$missing {x}");
+    }
+
+    #[test]
+    fn matches_system_locale_names() {
+        assert_eq!(match_locale("es_MX.UTF-8"), Some("es"));
+        assert_eq!(match_locale("fr-CA"), Some("fr"));
+        assert_eq!(match_locale("en"), Some("en"));
+        assert_eq!(match_locale("ja_JP.UTF-8"), None);
+        assert_eq!(Localizer::new("ja_JP.UTF-8").locale(), "en");
+    }
+
+    #[test]
+    fn falls_back_to_english_and_then_the_key() {
+        let spanish = Localizer::new("es");
+        assert_eq!(
+            spanish.format("permission-select-option", &[]),
+            "Selecciona una opción"
+        );
+        assert_eq!(spanish.format("no-such-message", &[]), "no-such-message");
+        let mut partial = spanish.clone();
+        partial.primary = Some(Catalog::default());
+        assert_eq!(
+            partial.format("permission-select-option", &[]),
+            "Select an option"
+        );
+    }
+
+    #[test]
+    fn translations_use_the_same_placeholders() {
+        let english = Catalog::parse(catalog_source(DEFAULT_LOCALE));
+        for locale in available_locales() {
+            let catalog = Catalog::parse(catalog_source(locale));
+            for key in catalog.keys() {
+                let english_message = english.get(key).unwrap_or_else(|| {
+                    panic!("{}: '{}' isn't in the English catalog", locale, key)
+                });
+                let placeholders = |message: &str| {
+                    let mut names: Vec<String> = message
+                        .split("{ $")
+                        .skip(1)
+                        .filter_map(|part| part.split(' ').next().map(str::to_string))
+                        .collect();
+                    names.sort();
+                    names
+                };
+                assert_eq!(
+                    placeholders(catalog.get(key).unwrap()),
+                    placeholders(english_message),
+                    "{}: placeholders of '{}' differ",
+                    locale,
+                    key
+                );
+            }
+        }
+    }
+}
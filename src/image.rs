@@ -1,11 +1,359 @@
+use ::image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use ::image::imageops::FilterType;
+use ::image::{DynamicImage, ImageFormat};
 use anyhow::{bail, Result};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use log::debug;
 use std::path::Path;
+use std::sync::RwLock;
+
+use crate::config::ImagesConfig;
 
 const SUPPORTED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
 const MAX_IMAGE_SIZE_MB: u64 = 20;
 
+/// Pixels per input token, from the estimate providers publish for images
+const PIXELS_PER_TOKEN: u64 = 750;
+/// How much more a scaled-down PNG is shrunk each time it's still too big
+const SHRINK_STEP: f64 = 0.75;
+/// Smallest side a PNG is shrunk to when trying to get under `max_bytes`
+const MIN_DIMENSION: u32 = 64;
+
+/// How much of a base64 image is decoded to find its size
+const HEADER_BASE64_CHARS: usize = 96 * 1024;
+
+/// The `[images]` settings from the config, as passed to [`set_limits`]
+static LIMITS: RwLock<Option<ImagesConfig>> = RwLock::new(None);
+
+/// Use `config` for the images added from now on
+pub fn set_limits(config: ImagesConfig) {
+    *LIMITS.write().expect("image limits lock") = Some(config);
+}
+
+/// The limits images are fitted within
+pub fn limits() -> ImagesConfig {
+    LIMITS
+        .read()
+        .expect("image limits lock")
+        .clone()
+        .unwrap_or_default()
+}
+
+/// An image ready to send, and what was done to get it there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedImage {
+    pub media_type: String,
+    pub data: Vec<u8>,
+    /// Width and height, when the format's header could be read
+    pub dimensions: Option<(u32, u32)>,
+    /// Width and height before it was scaled down, if it was
+    pub original_dimensions: Option<(u32, u32)>,
+    pub exif_stripped: bool,
+}
+
+impl PreparedImage {
+    /// Roughly how many input tokens the image costs
+    pub fn estimated_tokens(&self) -> Option<u64> {
+        self.dimensions.map(dimension_tokens)
+    }
+
+    /// Its size and cost, and what was changed, e.g.
+    /// "1568x882, ~1844 tokens, scaled down from 3840x2160"
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((width, height)) = self.dimensions {
+            parts.push(format!("{}x{}", width, height));
+        }
+        if let Some(tokens) = self.estimated_tokens() {
+            parts.push(format!("~{} tokens", tokens));
+        }
+        if let Some((width, height)) = self.original_dimensions {
+            parts.push(format!("scaled down from {}x{}", width, height));
+        }
+        if self.exif_stripped {
+            parts.push("EXIF removed".to_string());
+        }
+        if parts.is_empty() {
+            parts.push(format!("{} bytes", self.data.len()));
+        }
+        parts.join(", ")
+    }
+
+    pub fn base64(&self) -> String {
+        STANDARD.encode(&self.data)
+    }
+}
+
+/// Fit an image within `config`'s limits: PNGs larger than `max_dimension`
+/// or `max_bytes` are scaled down and re-encoded, and EXIF is removed from
+/// PNGs and JPEGs when `strip_exif` is set. Other formats can't be scaled
+/// down, so one over `max_bytes` is an error.
+pub fn prepare_image(
+    media_type: &str,
+    data: Vec<u8>,
+    config: &ImagesConfig,
+) -> Result<PreparedImage> {
+    if media_type == "image/png" {
+        return prepare_png(data, config);
+    }
+
+    let (data, exif_stripped) = if media_type == "image/jpeg" && config.strip_exif {
+        let stripped = strip_jpeg_exif(&data);
+        let changed = stripped.len() != data.len();
+        (stripped, changed)
+    } else {
+        (data, false)
+    };
+    if data.len() > config.max_bytes {
+        bail!(
+            "Image is {:.1}MB, over the {:.1}MB limit, and only PNGs can be scaled down",
+            megabytes(data.len()),
+            megabytes(config.max_bytes)
+        );
+    }
+    let dimensions = match media_type {
+        "image/jpeg" => jpeg_dimensions(&data),
+        "image/gif" => gif_dimensions(&data),
+        "image/webp" => webp_dimensions(&data),
+        _ => None,
+    };
+    Ok(PreparedImage {
+        media_type: media_type.to_string(),
+        data,
+        dimensions,
+        original_dimensions: None,
+        exif_stripped,
+    })
+}
+
+/// Like [`prepare_image`] with the configured limits, for base64 data as
+/// it arrives from the clipboard or the web UI
+pub fn prepare_base64(media_type: &str, base64_data: &str) -> Result<PreparedImage> {
+    let data = STANDARD.decode(base64_data.trim())?;
+    prepare_image(media_type, data, &limits())
+}
+
+/// Roughly how many input tokens a base64 image costs, from the size in
+/// its header
+pub fn estimate_tokens(media_type: &str, base64_data: &str) -> Option<u64> {
+    // The header is near the start, except for JPEGs with large metadata
+    let prefix = &base64_data[..base64_data.len().min(HEADER_BASE64_CHARS)];
+    let data = STANDARD.decode(&prefix[..prefix.len() / 4 * 4]).ok()?;
+    let dimensions = match media_type {
+        "image/png" => png_dimensions(&data),
+        "image/jpeg" => jpeg_dimensions(&data),
+        "image/gif" => gif_dimensions(&data),
+        "image/webp" => webp_dimensions(&data),
+        _ => None,
+    };
+    dimensions.map(dimension_tokens)
+}
+
+/// Roughly how many input tokens an image of this width and height costs
+pub fn dimension_tokens((width, height): (u32, u32)) -> u64 {
+    (u64::from(width) * u64::from(height)).div_ceil(PIXELS_PER_TOKEN)
+}
+
+fn megabytes(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+fn prepare_png(data: Vec<u8>, config: &ImagesConfig) -> Result<PreparedImage> {
+    let had_exif = png_has_exif(&data);
+    let dimensions = png_dimensions(&data);
+    let oversized =
+        dimensions.is_some_and(|(width, height)| width.max(height) > config.max_dimension);
+    if !oversized && data.len() <= config.max_bytes {
+        let (data, exif_stripped) = if config.strip_exif && had_exif {
+            (strip_png_exif(&data), true)
+        } else {
+            (data, false)
+        };
+        return Ok(PreparedImage {
+            media_type: "image/png".to_string(),
+            data,
+            dimensions,
+            original_dimensions: None,
+            exif_stripped,
+        });
+    }
+
+    let decoded = ::image::load_from_memory_with_format(&data, ImageFormat::Png)?;
+    let (width, height) = (decoded.width(), decoded.height());
+    let mut scale = (f64::from(config.max_dimension) / f64::from(width.max(height))).min(1.0);
+    loop {
+        let size = (
+            ((f64::from(width) * scale).round() as u32).max(1),
+            ((f64::from(height) * scale).round() as u32).max(1),
+        );
+        let resized = if size == (width, height) {
+            decoded.clone()
+        } else {
+            decoded.resize_exact(size.0, size.1, FilterType::Triangle)
+        };
+        let encoded = encode_png(&resized)?;
+        if encoded.len() <= config.max_bytes || size.0.max(size.1) <= MIN_DIMENSION {
+            debug!(
+                "Re-encoded {}x{} PNG as {}x{} ({} -> {} bytes)",
+                width,
+                height,
+                size.0,
+                size.1,
+                data.len(),
+                encoded.len()
+            );
+            return Ok(PreparedImage {
+                media_type: "image/png".to_string(),
+                data: encoded,
+                dimensions: Some(size),
+                original_dimensions: (size != (width, height)).then_some((width, height)),
+                // Re-encoding keeps only the pixels
+                exif_stripped: had_exif,
+            });
+        }
+        scale *= SHRINK_STEP;
+    }
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut encoded = Vec::new();
+    image.write_with_encoder(PngEncoder::new_with_quality(
+        &mut encoded,
+        CompressionType::Best,
+        PngFilter::Adaptive,
+    ))?;
+    Ok(encoded)
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(PNG_SIGNATURE) || data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((be_u32(data.get(16..20)?), be_u32(data.get(20..24)?)))
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The chunks of a PNG after its signature, as (type, whole chunk) pairs
+fn png_chunks(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    while let Some(header) = data.get(pos..pos + 8) {
+        let end = pos + 12 + be_u32(&header[..4]) as usize;
+        let Some(chunk) = data.get(pos..end) else {
+            break;
+        };
+        chunks.push((&header[4..8], chunk));
+        pos = end;
+    }
+    chunks
+}
+
+fn png_has_exif(data: &[u8]) -> bool {
+    data.starts_with(PNG_SIGNATURE) && png_chunks(data).iter().any(|(kind, _)| *kind == b"eXIf")
+}
+
+fn strip_png_exif(data: &[u8]) -> Vec<u8> {
+    let mut stripped = PNG_SIGNATURE.to_vec();
+    for (kind, chunk) in png_chunks(data) {
+        if kind != b"eXIf" {
+            stripped.extend_from_slice(chunk);
+        }
+    }
+    stripped
+}
+
+/// A JPEG segment's marker and its bytes, marker included
+type JpegSegment<'a> = (u8, &'a [u8]);
+
+/// The segments of a JPEG before its image data, and where the rest starts
+fn jpeg_segments(data: &[u8]) -> Option<(Vec<JpegSegment<'_>>, usize)> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut segments = Vec::new();
+    let mut pos = 2;
+    while let Some(&[0xFF, marker]) = data.get(pos..pos + 2) {
+        // Start of scan: entropy-coded data follows
+        if marker == 0xDA {
+            break;
+        }
+        let length = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+        segments.push((marker, data.get(pos..pos + 2 + length)?));
+        pos += 2 + length;
+    }
+    Some((segments, pos))
+}
+
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let (segments, _) = jpeg_segments(data)?;
+    segments.iter().find_map(|(marker, segment)| {
+        // Start-of-frame markers; C4, C8 and CC are other tables
+        let is_frame = matches!(marker, 0xC0..=0xCF) && ![0xC4, 0xC8, 0xCC].contains(marker);
+        if !is_frame {
+            return None;
+        }
+        let height = u16::from_be_bytes([*segment.get(5)?, *segment.get(6)?]);
+        let width = u16::from_be_bytes([*segment.get(7)?, *segment.get(8)?]);
+        Some((u32::from(width), u32::from(height)))
+    })
+}
+
+fn strip_jpeg_exif(data: &[u8]) -> Vec<u8> {
+    let Some((segments, rest)) = jpeg_segments(data) else {
+        return data.to_vec();
+    };
+    let mut stripped = vec![0xFF, 0xD8];
+    for (marker, segment) in segments {
+        let is_exif = marker == 0xE1 && segment.get(4..10) == Some(b"Exif\0\0".as_slice());
+        if !is_exif {
+            stripped.extend_from_slice(segment);
+        }
+    }
+    stripped.extend_from_slice(&data[rest..]);
+    stripped
+}
+
+fn gif_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(b"GIF8") {
+        return None;
+    }
+    let width = u16::from_le_bytes([*data.get(6)?, *data.get(7)?]);
+    let height = u16::from_le_bytes([*data.get(8)?, *data.get(9)?]);
+    Some((u32::from(width), u32::from(height)))
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(..4)? != b"RIFF" || data.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let le24 = |at: usize| -> Option<u32> {
+        Some(u32::from_le_bytes([
+            *data.get(at)?,
+            *data.get(at + 1)?,
+            *data.get(at + 2)?,
+            0,
+        ]))
+    };
+    match data.get(12..16)? {
+        b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8 " => {
+            let width = u16::from_le_bytes([*data.get(26)?, *data.get(27)?]) & 0x3FFF;
+            let height = u16::from_le_bytes([*data.get(28)?, *data.get(29)?]) & 0x3FFF;
+            Some((u32::from(width), u32::from(height)))
+        }
+        _ => None,
+    }
+}
+
 /// Check if a file path points to a supported image format
 pub fn is_image_file(path: &Path) -> bool {
     path.extension()
@@ -36,9 +384,21 @@ pub fn get_media_type(path: &Path) -> Result<String> {
     }
 }
 
+/// Load an image file, fit it within the configured limits and encode it
+/// as base64
+pub fn load_prepared_image(path: &Path) -> Result<PreparedImage> {
+    let (media_type, data) = read_image(path)?;
+    prepare_image(&media_type, data, &limits())
+}
+
 /// Load an image file and encode it as base64
 /// Returns (media_type, base64_data)
 pub fn load_image_as_base64(path: &Path) -> Result<(String, String)> {
+    let (media_type, data) = read_image(path)?;
+    Ok((media_type, STANDARD.encode(&data)))
+}
+
+fn read_image(path: &Path) -> Result<(String, Vec<u8>)> {
     let metadata = std::fs::metadata(path)?;
     let size_mb = metadata.len() / (1024 * 1024);
 
@@ -52,7 +412,6 @@ pub fn load_image_as_base64(path: &Path) -> Result<(String, String)> {
 
     let media_type = get_media_type(path)?;
     let data = std::fs::read(path)?;
-    let base64_data = STANDARD.encode(&data);
 
     debug!(
         "Loaded image: {} ({}, {} bytes)",
@@ -61,7 +420,7 @@ pub fn load_image_as_base64(path: &Path) -> Result<(String, String)> {
         data.len()
     );
 
-    Ok((media_type, base64_data))
+    Ok((media_type, data))
 }
 
 #[cfg(test)]
@@ -152,6 +511,87 @@ mod tests {
         assert_eq!(decoded, png_data);
     }
 
+    fn png_of(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(::image::RgbaImage::from_pixel(
+            width,
+            height,
+            ::image::Rgba([200, 40, 40, 255]),
+        ));
+        encode_png(&image).unwrap()
+    }
+
+    #[test]
+    fn test_prepare_image_scales_down_large_pngs() {
+        let config = ImagesConfig {
+            max_dimension: 100,
+            ..ImagesConfig::default()
+        };
+        let prepared = prepare_image("image/png", png_of(400, 200), &config).unwrap();
+        assert_eq!(prepared.dimensions, Some((100, 50)));
+        assert_eq!(prepared.original_dimensions, Some((400, 200)));
+        assert_eq!(png_dimensions(&prepared.data), Some((100, 50)));
+        assert_eq!(
+            prepared.summary(),
+            "100x50, ~7 tokens, scaled down from 400x200"
+        );
+
+        let small = png_of(80, 40);
+        let prepared = prepare_image("image/png", small.clone(), &config).unwrap();
+        assert_eq!(prepared.data, small);
+        assert_eq!(prepared.original_dimensions, None);
+
+        let tight = ImagesConfig {
+            max_bytes: 200,
+            ..config
+        };
+        let prepared = prepare_image("image/png", png_of(100, 100), &tight).unwrap();
+        assert!(prepared.data.len() <= 200);
+        assert_eq!(prepared.original_dimensions, Some((100, 100)));
+    }
+
+    #[test]
+    fn test_prepare_image_strips_jpeg_exif() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        // APP1 with EXIF, then a baseline frame header of 640x480
+        jpeg.extend_from_slice(&[0xFF, 0xE1, 0x00, 0x0A]);
+        jpeg.extend_from_slice(b"Exif\0\0GP");
+        jpeg.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x01, 0xE0, 0x02, 0x80, 0x01]);
+        jpeg.extend_from_slice(&[0x11, 0x00, 0x00]);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x01, 0x02, 0xFF, 0xD9]);
+
+        let kept = prepare_image("image/jpeg", jpeg.clone(), &ImagesConfig::default()).unwrap();
+        assert_eq!(kept.data, jpeg);
+        assert_eq!(kept.dimensions, Some((640, 480)));
+
+        let config = ImagesConfig {
+            strip_exif: true,
+            ..ImagesConfig::default()
+        };
+        let stripped = prepare_image("image/jpeg", jpeg.clone(), &config).unwrap();
+        assert!(stripped.exif_stripped);
+        assert_eq!(stripped.data.len(), jpeg.len() - 12);
+        assert!(!stripped.data.windows(4).any(|w| w == b"Exif"));
+        assert_eq!(stripped.dimensions, Some((640, 480)));
+        assert_eq!(stripped.summary(), "640x480, ~410 tokens, EXIF removed");
+
+        let too_big = ImagesConfig {
+            max_bytes: 10,
+            ..ImagesConfig::default()
+        };
+        assert!(prepare_image("image/jpeg", jpeg, &too_big).is_err());
+    }
+
+    #[test]
+    fn test_estimate_tokens_reads_headers() {
+        let gif = b"GIF89a\x20\x03\x58\x02rest";
+        assert_eq!(gif_dimensions(gif), Some((800, 600)));
+        assert_eq!(
+            estimate_tokens("image/png", &STANDARD.encode(png_of(1500, 750))),
+            Some(1500)
+        );
+        assert_eq!(estimate_tokens("image/png", "bm90IGFuIGltYWdl"), None);
+    }
+
     #[test]
     fn test_load_image_nonexistent_file() {
         let result = load_image_as_base64(Path::new("nonexistent.png"));
@@ -0,0 +1,333 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::config::{Config, Provider};
+use crate::security::BashSecurity;
+
+/// Directories left out of the layout in a generated AGENTS.md
+const IGNORED_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "out",
+    "vendor",
+    "venv",
+    "__pycache__",
+];
+
+/// Most directories listed under Layout
+const MAX_LAYOUT_DIRS: usize = 12;
+
+/// How much the agent may do without asking, as offered by `flexorama init`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionProfile {
+    /// Ask before every shell command and every file edit
+    Cautious,
+    /// Run read-only commands like `ls` and `git status` without asking,
+    /// and ask before anything else
+    Standard,
+    /// Like `Standard`, but apply file edits without asking
+    AcceptEdits,
+}
+
+impl PermissionProfile {
+    pub const ALL: [PermissionProfile; 3] = [Self::Cautious, Self::Standard, Self::AcceptEdits];
+
+    /// The profile `config` already matches, if any
+    pub fn of(config: &Config) -> Option<Self> {
+        let default_commands = BashSecurity::default().allowed_commands;
+        let security = &config.bash_security;
+        if !security.enabled || !security.ask_for_permission {
+            return None;
+        }
+        if security.allowed_commands.is_empty() && !config.auto_accept_edits {
+            Some(Self::Cautious)
+        } else if security.allowed_commands == default_commands {
+            Some(if config.auto_accept_edits {
+                Self::AcceptEdits
+            } else {
+                Self::Standard
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Change the permission settings in `config` to this profile's,
+    /// keeping its denied commands
+    pub fn apply(self, config: &mut Config) {
+        config.bash_security.enabled = true;
+        config.bash_security.ask_for_permission = true;
+        config.bash_security.allowed_commands = match self {
+            Self::Cautious => Default::default(),
+            Self::Standard | Self::AcceptEdits => BashSecurity::default().allowed_commands,
+        };
+        config.auto_accept_edits = self == Self::AcceptEdits;
+    }
+}
+
+impl fmt::Display for PermissionProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cautious => write!(f, "Cautious - ask before every command and edit"),
+            Self::Standard => write!(
+                f,
+                "Standard - run read-only commands freely, ask before the rest"
+            ),
+            Self::AcceptEdits => write!(
+                f,
+                "Accept edits - like Standard, but apply file edits without asking"
+            ),
+        }
+    }
+}
+
+/// Keyring account the API key for `provider` is stored under
+pub fn keyring_account(provider: Provider) -> String {
+    format!("{}-api-key", provider.name())
+}
+
+/// What a look around the project found, for a starter AGENTS.md
+#[derive(Debug, Default, PartialEq)]
+pub struct ProjectScan {
+    pub name: String,
+    pub build_system: Option<String>,
+    pub build_command: Option<String>,
+    pub test_command: Option<String>,
+    pub lint_command: Option<String>,
+    /// Top-level directories, with what they usually hold when it's known
+    pub layout: Vec<(String, Option<&'static str>)>,
+}
+
+/// Work out the build system, its commands and the layout of the project
+/// in `root`
+pub fn scan_project(root: &Path) -> ProjectScan {
+    let mut scan = ProjectScan {
+        name: root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Project".to_string()),
+        ..Default::default()
+    };
+    let has = |file: &str| root.join(file).is_file();
+    let read = |file: &str| std::fs::read_to_string(root.join(file)).unwrap_or_default();
+
+    if has("Cargo.toml") {
+        scan.set_commands(
+            "Cargo",
+            Some("cargo build"),
+            Some("cargo test"),
+            Some("cargo clippy --all-targets -- -D warnings"),
+        );
+    } else if has("package.json") {
+        let manager = if has("pnpm-lock.yaml") {
+            "pnpm"
+        } else if has("yarn.lock") {
+            "yarn"
+        } else if has("bun.lockb") || has("bun.lock") {
+            "bun"
+        } else {
+            "npm"
+        };
+        let package: serde_json::Value =
+            serde_json::from_str(&read("package.json")).unwrap_or_default();
+        let script = |name: &str| package["scripts"].get(name).is_some();
+        scan.build_system = Some(manager.to_string());
+        scan.build_command = script("build").then(|| format!("{} run build", manager));
+        scan.test_command = script("test").then(|| format!("{} test", manager));
+        scan.lint_command = script("lint").then(|| format!("{} run lint", manager));
+    } else if has("go.mod") {
+        scan.set_commands(
+            "Go",
+            Some("go build ./..."),
+            Some("go test ./..."),
+            Some("go vet ./..."),
+        );
+    } else if has("pyproject.toml") || has("setup.py") || has("requirements.txt") {
+        let pyproject = read("pyproject.toml");
+        let lint = pyproject.contains("ruff").then_some("ruff check .");
+        scan.set_commands("Python", None, Some("pytest"), lint);
+    } else if has("pom.xml") {
+        scan.set_commands("Maven", Some("mvn compile"), Some("mvn test"), None);
+    } else if has("build.gradle") || has("build.gradle.kts") {
+        let gradle = if has("gradlew") {
+            "./gradlew"
+        } else {
+            "gradle"
+        };
+        scan.build_system = Some("Gradle".to_string());
+        scan.build_command = Some(format!("{} build", gradle));
+        scan.test_command = Some(format!("{} test", gradle));
+    } else if has("CMakeLists.txt") {
+        scan.set_commands(
+            "CMake",
+            Some("cmake -B build && cmake --build build"),
+            Some("ctest --test-dir build"),
+            None,
+        );
+    } else if has("Makefile") {
+        let makefile = read("Makefile");
+        let test = makefile
+            .lines()
+            .any(|line| line.starts_with("test:"))
+            .then_some("make test");
+        scan.set_commands("Make", Some("make"), test, None);
+    }
+
+    let mut dirs: Vec<String> = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    scan.layout = dirs
+        .into_iter()
+        .take(MAX_LAYOUT_DIRS)
+        .map(|dir| {
+            let purpose = describe_dir(&dir);
+            (dir, purpose)
+        })
+        .collect();
+    scan
+}
+
+impl ProjectScan {
+    fn set_commands(
+        &mut self,
+        build_system: &str,
+        build: Option<&str>,
+        test: Option<&str>,
+        lint: Option<&str>,
+    ) {
+        self.build_system = Some(build_system.to_string());
+        self.build_command = build.map(str::to_string);
+        self.test_command = test.map(str::to_string);
+        self.lint_command = lint.map(str::to_string);
+    }
+}
+
+fn describe_dir(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "src" | "lib" => "source code",
+        "tests" | "test" | "spec" => "tests",
+        "docs" | "doc" => "documentation",
+        "examples" => "examples",
+        "benches" | "benchmarks" => "benchmarks",
+        "scripts" => "helper scripts",
+        "cmd" => "entry points",
+        "pkg" | "internal" => "packages",
+        "migrations" => "database migrations",
+        "assets" | "static" | "public" => "static assets",
+        _ => return None,
+    })
+}
+
+/// A starter AGENTS.md from what `scan` found, for the developer to fill in
+pub fn render_agents_md(scan: &ProjectScan) -> String {
+    let mut out = format!("# {}\n\n## Build and Test\n\n", scan.name);
+    let commands = [
+        ("Build", &scan.build_command),
+        ("Test", &scan.test_command),
+        ("Lint", &scan.lint_command),
+    ];
+    if commands.iter().all(|(_, command)| command.is_none()) {
+        out.push_str("- TODO: the commands to build and test the project\n");
+    }
+    if let Some(build_system) = &scan.build_system {
+        out.push_str(&format!("- Build system: {}\n", build_system));
+    }
+    for (label, command) in commands {
+        if let Some(command) = command {
+            out.push_str(&format!("- {}: `{}`\n", label, command));
+        }
+    }
+    if !scan.layout.is_empty() {
+        out.push_str("\n## Layout\n\n");
+        for (dir, purpose) in &scan.layout {
+            match purpose {
+                Some(purpose) => out.push_str(&format!("- `{}/` - {}\n", dir, purpose)),
+                None => out.push_str(&format!("- `{}/`\n", dir)),
+            }
+        }
+    }
+    out.push_str("\n## Conventions\n\n- Match the style of the surrounding code.\n");
+    if scan.test_command.is_some() {
+        out.push_str("- Run the tests before calling a change done.\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_a_cargo_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("widget");
+        for sub in ["src", "tests", "target", ".git", "tools"] {
+            std::fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"widget\"\n").unwrap();
+
+        let scan = scan_project(&root);
+        assert_eq!(scan.name, "widget");
+        assert_eq!(scan.build_system.as_deref(), Some("Cargo"));
+        assert_eq!(scan.test_command.as_deref(), Some("cargo test"));
+        assert_eq!(
+            scan.layout,
+            vec![
+                ("src".to_string(), Some("source code")),
+                ("tests".to_string(), Some("tests")),
+                ("tools".to_string(), None),
+            ]
+        );
+
+        let agents_md = render_agents_md(&scan);
+        assert!(agents_md.starts_with("# widget\n\n## Build and Test\n\n- Build system: Cargo\n"));
+        assert!(agents_md.contains("- Test: `cargo test`\n"));
+        assert!(agents_md.contains("- `tools/`\n"));
+        assert!(agents_md.contains("- Run the tests before calling a change done.\n"));
+    }
+
+    #[test]
+    fn reads_package_json_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"test": "vitest", "lint": "eslint ."}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+
+        let scan = scan_project(dir.path());
+        assert_eq!(scan.build_system.as_deref(), Some("pnpm"));
+        assert_eq!(scan.build_command, None);
+        assert_eq!(scan.test_command.as_deref(), Some("pnpm test"));
+        assert_eq!(scan.lint_command.as_deref(), Some("pnpm run lint"));
+    }
+
+    #[test]
+    fn profiles_round_trip_through_the_config() {
+        let mut config = Config::default();
+        assert_eq!(
+            PermissionProfile::of(&config),
+            Some(PermissionProfile::Standard)
+        );
+        for profile in PermissionProfile::ALL {
+            profile.apply(&mut config);
+            assert_eq!(PermissionProfile::of(&config), Some(profile));
+        }
+        config
+            .bash_security
+            .allowed_commands
+            .insert("make".to_string());
+        assert_eq!(PermissionProfile::of(&config), None);
+    }
+}
@@ -8,14 +8,29 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
 use crate::agent::{self, Agent};
+use crate::clipboard::PastedImage;
 use crate::commands::{handle_shell_command, handle_slash_command};
+use crate::config_watch::ConfigReload;
+use crate::context_usage;
 use crate::formatter;
 use crate::logo;
 use crate::mcp::McpManager;
 use crate::processing::process_input;
+use crate::session_diff::SessionDiff;
 use crate::tui;
 use crate::utils::{get_home_agents_md_path, print_usage_stats};
 
+/// A submitted input waiting its turn
+struct QueuedInput {
+    text: String,
+    images: Vec<PastedImage>,
+}
+
+/// The queued texts, as the TUI shows them
+fn queued_texts(queue: &VecDeque<QueuedInput>) -> VecDeque<String> {
+    queue.iter().map(|queued| queued.text.clone()).collect()
+}
+
 /// Run the TUI interactive mode
 pub async fn run_tui_interactive(
     tui: Arc<tui::Tui>,
@@ -24,6 +39,7 @@ pub async fn run_tui_interactive(
     formatter: &formatter::CodeFormatter,
     stream: bool,
     plan_mode: bool,
+    mut config_reloads: Option<mpsc::UnboundedReceiver<ConfigReload>>,
 ) -> Result<()> {
     enum InputEvent {
         Queued,
@@ -31,6 +47,18 @@ pub async fn run_tui_interactive(
         Exit,
     }
 
+    let tui_for_usage = Arc::clone(&tui);
+    agent.set_usage_listener(Some(Arc::new(move |usage: &agent::TokenUsage| {
+        let text = (usage.total_tokens() > 0).then(|| {
+            format!(
+                "{} in · {} out",
+                context_usage::format_tokens(usage.total_input_tokens as usize),
+                context_usage::format_tokens(usage.total_output_tokens as usize)
+            )
+        });
+        let _ = tui_for_usage.set_usage(text.as_deref());
+    })));
+
     let tui_for_permissions = Arc::clone(&tui);
     agent
         .set_permission_handler(Some(Arc::new(move |prompt| {
@@ -58,21 +86,32 @@ pub async fn run_tui_interactive(
     app_println!("{}", "Type '/help' for commands.".dimmed());
     app_println!();
 
+    if let Some(database_manager) = agent.database_manager() {
+        match database_manager.get_prompt_history(500, None).await {
+            Ok(history) => {
+                let prompts: Vec<String> = history.into_iter().rev().map(|e| e.prompt).collect();
+                tui.load_history(&prompts);
+            }
+            Err(e) => debug!("Failed to load prompt history: {}", e),
+        }
+    }
+
     let queued_inputs = Arc::new(Mutex::new(VecDeque::new()));
     tui.set_queue(&VecDeque::new())?;
     tui.set_todos(&agent.get_todos().await)?;
 
     let todo_handle = agent.todos_handle();
     let tui_for_todos = Arc::clone(&tui);
+    let session_diff = agent.session_diff_handle();
     let on_tool_event: Arc<dyn Fn(agent::StreamToolEvent) + Send + Sync> =
         Arc::new(move |event: agent::StreamToolEvent| {
-            if event.event != "tool_result" {
+            let agent::StreamToolEvent::ToolResult { name, .. } = event else {
                 return;
+            };
+            if tui_for_todos.diff_pane_open() {
+                refresh_diff_pane(&tui_for_todos, &session_diff);
             }
-            if event.name != "create_todo"
-                && event.name != "complete_todo"
-                && event.name != "list_todos"
-            {
+            if name != "create_todo" && name != "complete_todo" && name != "list_todos" {
                 return;
             }
             let todo_handle = Arc::clone(&todo_handle);
@@ -96,14 +135,17 @@ pub async fn run_tui_interactive(
     let exit_for_input = Arc::clone(&exit_requested);
     let input_thread = tokio::task::spawn_blocking(move || loop {
         match tui_for_input.read_input() {
-            Ok(tui::InputResult::Submitted(value)) => {
+            Ok(tui::InputResult::Submitted(value, images)) => {
                 if value.trim().is_empty() {
                     continue;
                 }
                 let snapshot = {
                     let mut guard = queue_for_input.lock().expect("queue lock");
-                    guard.push_back(value);
-                    guard.clone()
+                    guard.push_back(QueuedInput {
+                        text: value,
+                        images,
+                    });
+                    queued_texts(&guard)
                 };
                 let _ = tui_for_input.set_queue(&snapshot);
                 if input_tx.send(InputEvent::Queued).is_err() {
@@ -140,13 +182,9 @@ pub async fn run_tui_interactive(
         }
     });
 
-    let clear_queue = |queue: &Arc<Mutex<VecDeque<String>>>| -> Result<()> {
-        let snapshot = {
-            let mut guard = queue.lock().expect("queue lock");
-            guard.clear();
-            guard.clone()
-        };
-        tui.set_queue(&snapshot)?;
+    let clear_queue = |queue: &Arc<Mutex<VecDeque<QueuedInput>>>| -> Result<()> {
+        queue.lock().expect("queue lock").clear();
+        tui.set_queue(&VecDeque::new())?;
         Ok(())
     };
 
@@ -161,7 +199,7 @@ pub async fn run_tui_interactive(
         let next_input = {
             let mut guard = queued_inputs.lock().expect("queue lock");
             let next = guard.pop_front();
-            let snapshot = guard.clone();
+            let snapshot = queued_texts(&guard);
             drop(guard);
             if next.is_some() {
                 let _ = tui.set_queue(&snapshot);
@@ -169,14 +207,23 @@ pub async fn run_tui_interactive(
             next
         };
 
-        let input = if let Some(value) = next_input {
-            value
+        let QueuedInput {
+            text: input,
+            images,
+        } = if let Some(queued) = next_input {
+            queued
         } else {
-            match input_rx.recv().await {
-                Some(InputEvent::Queued) => continue,
-                Some(InputEvent::Cancelled) => continue,
-                Some(InputEvent::Exit) => continue,
-                None => break,
+            tokio::select! {
+                event = input_rx.recv() => match event {
+                    Some(InputEvent::Queued) => continue,
+                    Some(InputEvent::Cancelled) => continue,
+                    Some(InputEvent::Exit) => continue,
+                    None => break,
+                },
+                Some(reload) = next_config_reload(&mut config_reloads) => {
+                    apply_config_reload(agent, mcp_manager, reload).await;
+                    continue;
+                }
             }
         };
 
@@ -226,6 +273,11 @@ pub async fn run_tui_interactive(
             }
         }
 
+        // Like images sent from the web UI, pasted images go in just before the message
+        for image in images {
+            agent.add_image(image.media_type, image.data, None);
+        }
+
         let cancellation_flag_for_processing = Arc::new(AtomicBool::new(false));
         {
             let mut guard = current_cancel_flag.lock().expect("cancel lock");
@@ -275,6 +327,10 @@ pub async fn run_tui_interactive(
             agent.clear_todos_for_current_conversation().await;
             let _ = tui.set_todos(&[]);
         }
+        // Commands the model ran may have changed the files too
+        if tui.diff_pane_open() {
+            refresh_diff_pane(&tui, &agent.session_diff_handle());
+        }
         {
             let mut guard = current_cancel_flag.lock().expect("cancel lock");
             *guard = None;
@@ -285,6 +341,60 @@ pub async fn run_tui_interactive(
     Ok(())
 }
 
+/// Wait for the next config reload, or forever if the config isn't watched
+/// Show the session's current diff in the TUI's diff pane
+fn refresh_diff_pane(tui: &tui::Tui, session_diff: &Mutex<SessionDiff>) {
+    let Ok(root) = std::env::current_dir() else {
+        return;
+    };
+    let diff = session_diff
+        .lock()
+        .expect("session diff lock")
+        .render(&root);
+    let _ = tui.set_diff(&diff);
+}
+
+async fn next_config_reload(
+    reloads: &mut Option<mpsc::UnboundedReceiver<ConfigReload>>,
+) -> Option<ConfigReload> {
+    match reloads {
+        Some(reloads) => reloads.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Apply a changed config between prompts and say what changed
+async fn apply_config_reload(
+    agent: &mut Agent,
+    mcp_manager: &Arc<McpManager>,
+    reload: ConfigReload,
+) {
+    app_println!("{} Configuration reloaded:", "⟳".cyan());
+    for change in &reload.changes {
+        if change.applied {
+            app_println!("  • {}", change.description);
+        } else {
+            app_println!("  • {}", change.description.yellow());
+        }
+    }
+
+    crate::output::set_output_width(reload.config.output_width);
+    crate::image::set_limits(reload.config.images.clone());
+    if let Err(e) = agent
+        .apply_config_reload(&reload.previous, &reload.config)
+        .await
+    {
+        app_eprintln!("{} Failed to apply reloaded config: {}", "✗".red(), e);
+    }
+    mcp_manager
+        .set_project_servers(reload.sources.project_mcp_servers())
+        .await;
+    mcp_manager.reload_servers(reload.config.mcp.clone()).await;
+    if let Err(e) = agent.force_refresh_mcp_tools().await {
+        app_eprintln!("{} Failed to refresh MCP tools: {}", "✗".red(), e);
+    }
+}
+
 /// Check for and add context files
 pub async fn add_context_files(
     agent: &mut Agent,
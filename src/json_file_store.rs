@@ -0,0 +1,105 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// A JSON file of entries keyed by name, read and written whole. Backs the
+/// MCP token store and tool cache.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    /// Make the file readable only by the current user
+    private: bool,
+}
+
+impl JsonFileStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            private: false,
+        }
+    }
+
+    /// A store whose file only the current user can read, for credentials
+    pub fn private(path: PathBuf) -> Self {
+        Self {
+            path,
+            private: true,
+        }
+    }
+
+    /// `file_name` in Flexorama's directory under the user's config directory
+    pub fn default_path(file_name: &str) -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("flexorama")
+            .join(file_name)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every entry, or none if the file doesn't exist yet
+    pub async fn read_all<T: DeserializeOwned>(&self) -> Result<HashMap<String, T>> {
+        if !self.path.exists() {
+            return Ok(HashMap::new());
+        }
+        let content = fs::read_to_string(&self.path).await?;
+        if content.trim().is_empty() {
+            return Ok(HashMap::new());
+        }
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Replace the file's entries with `entries`
+    pub async fn write_all<T: Serialize>(&self, entries: &HashMap<String, T>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(entries)?;
+        if !self.private {
+            fs::write(&self.path, content).await?;
+            return Ok(());
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&self.path).await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, content.as_bytes()).await?;
+        tokio::io::AsyncWriteExt::flush(&mut file).await?;
+
+        // Tighten permissions on files created before the mode was applied
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn missing_or_empty_files_have_no_entries() {
+        let dir = TempDir::new().unwrap();
+        let store = JsonFileStore::new(dir.path().join("nested").join("store.json"));
+        assert!(store.read_all::<u32>().await.unwrap().is_empty());
+
+        std::fs::create_dir_all(dir.path().join("nested")).unwrap();
+        std::fs::write(store.path(), "  \n").unwrap();
+        assert!(store.read_all::<u32>().await.unwrap().is_empty());
+
+        let entries = HashMap::from([("one".to_string(), 1u32)]);
+        store.write_all(&entries).await.unwrap();
+        assert_eq!(store.read_all::<u32>().await.unwrap(), entries);
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use log::{LevelFilter, Record};
+use serde_json::{json, Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::{LogRotation, LoggingConfig};
+
+const FILE_PREFIX: &str = "flexorama-";
+const FILE_SUFFIX: &str = ".jsonl";
+
+struct JsonLog {
+    dir: PathBuf,
+    rotation: LogRotation,
+    level: LevelFilter,
+    max_files: usize,
+    session_id: String,
+    started: DateTime<Local>,
+    /// The day the open file is for, with daily rotation
+    day: Option<NaiveDate>,
+    file: Option<File>,
+}
+
+impl JsonLog {
+    fn file_name(&self, now: DateTime<Local>) -> String {
+        match self.rotation {
+            LogRotation::Daily => {
+                format!("{}{}{}", FILE_PREFIX, now.format("%Y-%m-%d"), FILE_SUFFIX)
+            }
+            LogRotation::Session => format!(
+                "{}{}-{}{}",
+                FILE_PREFIX,
+                self.started.format("%Y-%m-%d-%H%M%S"),
+                &self.session_id[..8],
+                FILE_SUFFIX
+            ),
+        }
+    }
+
+    fn write(&mut self, mut entry: Map<String, Value>) -> Result<()> {
+        let now = Local::now();
+        let today = now.date_naive();
+        let rotate = self.rotation == LogRotation::Daily && self.day != Some(today);
+        if self.file.is_none() || rotate {
+            std::fs::create_dir_all(&self.dir)?;
+            let path = self.dir.join(self.file_name(now));
+            self.file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("Failed to open {}", path.display()))?,
+            );
+            self.day = Some(today);
+            prune_log_files(&self.dir, self.max_files)?;
+        }
+
+        entry.insert("ts".to_string(), json!(now.to_rfc3339()));
+        entry.insert("session".to_string(), json!(self.session_id));
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Delete the oldest log files so at most `keep` remain. Names sort by date,
+/// so the oldest come first.
+fn prune_log_files(dir: &Path, keep: usize) -> Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for path in files.into_iter().take(excess) {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+static JSON_LOG: OnceLock<Mutex<Option<JsonLog>>> = OnceLock::new();
+
+fn log_cell() -> &'static Mutex<Option<JsonLog>> {
+    JSON_LOG.get_or_init(|| Mutex::new(None))
+}
+
+/// Start writing the JSON log described by `config`, if it's turned on.
+/// Returns the most verbose level the file takes, so the logger can let
+/// those records through.
+pub fn enable(config: &LoggingConfig) -> Result<Option<LevelFilter>> {
+    if !config.json {
+        return Ok(None);
+    }
+    let level = config.level_filter()?;
+    let log = JsonLog {
+        dir: config.log_dir()?,
+        rotation: config.rotation,
+        level,
+        max_files: config.max_files,
+        session_id: uuid::Uuid::new_v4().to_string(),
+        started: Local::now(),
+        day: None,
+        file: None,
+    };
+    *log_cell().lock().expect("json log lock") = Some(log);
+    Ok(Some(level))
+}
+
+pub fn is_enabled() -> bool {
+    log_cell().lock().expect("json log lock").is_some()
+}
+
+fn write_entry(level: Option<LevelFilter>, entry: Map<String, Value>) {
+    let mut guard = log_cell().lock().expect("json log lock");
+    let Some(log) = guard.as_mut() else {
+        return;
+    };
+    if level.is_some_and(|level| level > log.level) {
+        return;
+    }
+    // A log that can't be written mustn't take the session down; there is
+    // nowhere better to report it than stderr
+    if let Err(e) = log.write(entry) {
+        eprintln!("Failed to write JSON log: {:#}", e);
+        *guard = None;
+    }
+}
+
+/// Write a `log` record
+pub fn record(record: &Record) {
+    let mut entry = Map::new();
+    entry.insert("level".to_string(), json!(record.level().as_str()));
+    entry.insert("target".to_string(), json!(record.target()));
+    entry.insert("message".to_string(), json!(record.args().to_string()));
+    write_entry(Some(record.level().to_level_filter()), entry);
+}
+
+/// Write a structured event, such as a model request or a tool call. Events
+/// are written at any level.
+pub fn event(name: &str, fields: Value) {
+    let mut entry = match fields {
+        Value::Object(fields) => fields,
+        other => {
+            let mut entry = Map::new();
+            entry.insert("data".to_string(), other);
+            entry
+        }
+    };
+    entry.insert("event".to_string(), json!(name));
+    write_entry(None, entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_log(dir: &Path, rotation: LogRotation) -> JsonLog {
+        JsonLog {
+            dir: dir.to_path_buf(),
+            rotation,
+            level: LevelFilter::Info,
+            max_files: 3,
+            session_id: "0123456789abcdef".to_string(),
+            started: Local::now(),
+            day: None,
+            file: None,
+        }
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        let dir = TempDir::new().unwrap();
+        let mut log = test_log(dir.path(), LogRotation::Session);
+        let mut entry = Map::new();
+        entry.insert("event".to_string(), json!("tool_call"));
+        entry.insert("request_id".to_string(), json!("req-1"));
+        log.write(entry).unwrap();
+
+        let name = log.file_name(Local::now());
+        assert!(name.starts_with("flexorama-") && name.ends_with("-01234567.jsonl"));
+        let content = std::fs::read_to_string(dir.path().join(name)).unwrap();
+        let line: Value = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(line["event"], "tool_call");
+        assert_eq!(line["request_id"], "req-1");
+        assert_eq!(line["session"], "0123456789abcdef");
+        assert!(line["ts"].is_string());
+    }
+
+    #[test]
+    fn prunes_old_files() {
+        let dir = TempDir::new().unwrap();
+        for day in 1..=5 {
+            let name = format!("flexorama-2024-01-0{}.jsonl", day);
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        prune_log_files(dir.path(), 3).unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "flexorama-2024-01-03.jsonl",
+                "flexorama-2024-01-04.jsonl",
+                "flexorama-2024-01-05.jsonl",
+                "notes.txt"
+            ]
+        );
+    }
+}
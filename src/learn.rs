@@ -0,0 +1,244 @@
+use anyhow::Result;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use crate::agent::SnapshotMessage;
+use crate::anthropic::{ContentBlock, Message};
+use crate::llm::{create_response_content, LlmClient};
+
+const LEARN_SYSTEM_PROMPT: &str = "You keep a project's AGENTS.md, the instructions a coding assistant reads at the start of every session. From a conversation between a developer and the assistant, pick out durable project conventions the developer stated or corrected the assistant on: build and test commands, code style, naming, architecture rules, things to avoid. Leave out anything specific to the task at hand, anything AGENTS.md already says, and anything you'd be guessing at. Reply with one Markdown bullet per convention, each a short imperative sentence, and nothing else. If nothing is worth keeping, reply with NONE.";
+const LEARN_MAX_TOKENS: u32 = 1000;
+/// Longest excerpt of each message sent for review
+const LEARN_EXCERPT_CHARS: usize = 2000;
+
+/// Heading of the section new conventions are added to
+pub const LEARNED_HEADING: &str = "## Learned Conventions";
+
+/// Lines of unchanged context shown around an addition
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// The request to review `messages` for conventions missing from the
+/// current AGENTS.md
+pub fn build_prompt(agents_md: &str, messages: &[SnapshotMessage]) -> String {
+    let mut prompt = String::from("The current AGENTS.md:\n\n");
+    if agents_md.trim().is_empty() {
+        prompt.push_str("(empty)\n\n");
+    } else {
+        prompt.push_str(agents_md.trim());
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str("The conversation:\n\n");
+    for message in messages {
+        let text = message
+            .content
+            .iter()
+            .filter_map(|block| block.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n");
+        // Files added as context, AGENTS.md among them, aren't conversation
+        if text.trim().is_empty() || text.starts_with("Context from ") {
+            continue;
+        }
+        let excerpt: String = text.chars().take(LEARN_EXCERPT_CHARS).collect();
+        prompt.push_str(&format!(
+            "{}: {}\n\n",
+            message.role.to_uppercase(),
+            excerpt.trim()
+        ));
+    }
+    prompt
+}
+
+/// The bullets in a reply, without any AGENTS.md already has
+pub fn parse_additions(text: &str, agents_md: &str) -> Vec<String> {
+    let existing: Vec<String> = agents_md
+        .lines()
+        .map(|line| normalize(line.trim_start_matches(['-', '*']).trim()))
+        .collect();
+    let mut additions: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        let Some(item) = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .map(str::trim)
+        else {
+            continue;
+        };
+        let key = normalize(item);
+        if item.is_empty()
+            || existing.contains(&key)
+            || additions.iter().any(|added| normalize(&added[2..]) == key)
+        {
+            continue;
+        }
+        additions.push(format!("- {}", item));
+    }
+    additions
+}
+
+fn normalize(text: &str) -> String {
+    text.trim_end_matches('.').to_lowercase()
+}
+
+/// AGENTS.md with `additions` at the end of its learned conventions section,
+/// which is started at the end of the file if it has none yet
+pub fn apply(agents_md: &str, additions: &[String]) -> String {
+    let mut lines: Vec<String> = agents_md.lines().map(str::to_string).collect();
+    match lines.iter().position(|line| line.trim() == LEARNED_HEADING) {
+        Some(heading) => {
+            let section_end = lines[heading + 1..]
+                .iter()
+                .position(|line| line.starts_with("# ") || line.starts_with("## "))
+                .map_or(lines.len(), |offset| heading + 1 + offset);
+            let mut insert_at = section_end;
+            while insert_at > heading + 1 && lines[insert_at - 1].trim().is_empty() {
+                insert_at -= 1;
+            }
+            lines.splice(insert_at..insert_at, additions.iter().cloned());
+        }
+        None => {
+            while lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(LEARNED_HEADING.to_string());
+            lines.push(String::new());
+            lines.extend(additions.iter().cloned());
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// A unified diff of the lines `after` adds or changes in `before`
+pub fn diff(path: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let start = prefix.saturating_sub(DIFF_CONTEXT_LINES);
+    let old_end = (old.len() - suffix + DIFF_CONTEXT_LINES).min(old.len());
+    let new_end = (new.len() - suffix + DIFF_CONTEXT_LINES).min(new.len());
+
+    let mut out = format!("--- {}\n+++ {}\n", path, path);
+    out.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        start + 1,
+        old_end - start,
+        start + 1,
+        new_end - start
+    ));
+    for line in &old[start..prefix] {
+        out.push_str(&format!(" {}\n", line));
+    }
+    for line in &old[prefix..old.len() - suffix] {
+        out.push_str(&format!("-{}\n", line));
+    }
+    for line in &new[prefix..new.len() - suffix] {
+        out.push_str(&format!("+{}\n", line));
+    }
+    for line in &new[new.len() - suffix..new_end] {
+        out.push_str(&format!(" {}\n", line));
+    }
+    out
+}
+
+/// Ask the model which conventions from the conversation AGENTS.md is missing
+pub async fn propose(
+    client: &LlmClient,
+    model: &str,
+    agents_md: &str,
+    messages: &[SnapshotMessage],
+) -> Result<Vec<String>> {
+    let response = client
+        .create_message(
+            model,
+            vec![Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text(build_prompt(agents_md, messages))],
+            }],
+            &[],
+            LEARN_MAX_TOKENS,
+            0.2,
+            Some(&LEARN_SYSTEM_PROMPT.to_string()),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await?;
+    Ok(parse_additions(
+        &create_response_content(&response.content),
+        agents_md,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_new_bullets_only() {
+        let agents_md = "# Project\n\n- Run tests with `cargo test`.\n";
+        let additions = parse_additions(
+            "Here you go:\n- Use anyhow for errors\n* run tests with `cargo test`\n- Use anyhow for errors.\n",
+            agents_md,
+        );
+        assert_eq!(additions, vec!["- Use anyhow for errors"]);
+        assert!(parse_additions("NONE", agents_md).is_empty());
+    }
+
+    #[test]
+    fn adds_to_the_learned_section() {
+        let additions = vec!["- Keep mod lists sorted".to_string()];
+        assert_eq!(
+            apply("# Project\n\nNotes.\n\n", &additions),
+            "# Project\n\nNotes.\n\n## Learned Conventions\n\n- Keep mod lists sorted\n"
+        );
+        assert_eq!(
+            apply("", &additions),
+            "## Learned Conventions\n\n- Keep mod lists sorted\n"
+        );
+
+        let agents_md =
+            "# Project\n\n## Learned Conventions\n\n- Use anyhow\n\n## Layout\n\nsrc/\n";
+        let updated = apply(agents_md, &additions);
+        assert_eq!(
+            updated,
+            "# Project\n\n## Learned Conventions\n\n- Use anyhow\n- Keep mod lists sorted\n\n## Layout\n\nsrc/\n"
+        );
+        let diff = diff("AGENTS.md", agents_md, &updated);
+        assert_eq!(
+            diff,
+            "--- AGENTS.md\n+++ AGENTS.md\n@@ -3,6 +3,7 @@\n ## Learned Conventions\n \n - Use anyhow\n+- Keep mod lists sorted\n \n ## Layout\n \n"
+        );
+    }
+
+    #[test]
+    fn prompt_leaves_out_context_files() {
+        let messages = vec![
+            SnapshotMessage {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text(
+                    "Context from file 'AGENTS.md':\n\n```\n# Project\n```".to_string(),
+                )],
+            },
+            SnapshotMessage {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text(
+                    "No, we never use unwrap here".to_string(),
+                )],
+            },
+        ];
+        let prompt = build_prompt("", &messages);
+        assert!(prompt.starts_with("The current AGENTS.md:\n\n(empty)"));
+        assert!(prompt.contains("USER: No, we never use unwrap here"));
+        assert!(!prompt.contains("Context from"));
+    }
+}
@@ -6,31 +6,89 @@ pub mod acp;
 pub mod agent;
 pub mod anthropic;
 pub mod autocomplete;
+pub mod autoformat;
+pub mod batch;
+pub mod bundle;
+pub mod cancellation;
+pub mod checkpoint;
 pub mod cli;
+pub mod clipboard;
 pub mod commands;
 pub mod config;
+pub mod config_check;
+pub mod config_import;
+pub mod config_watch;
+pub mod context_usage;
 pub mod conversation;
+pub mod conversation_env;
+pub mod conversation_export;
+pub mod conversation_lock;
+pub mod crash;
 pub mod csrf;
 pub mod custom_commands;
+pub mod custom_provider;
 pub mod database;
+pub mod deepseek;
+pub mod eval;
+pub mod file_discovery;
 pub mod formatter;
 pub mod gemini;
+pub mod headless;
 pub mod help;
 pub mod hooks;
+pub mod http_client;
+pub mod i18n;
 pub mod image;
+pub mod init_wizard;
 pub mod input;
 pub mod interactive;
+pub mod json_file_store;
+pub mod json_log;
+pub mod learn;
 pub mod llm;
 pub mod logo;
+pub mod lsp;
+pub mod math;
 pub mod mcp;
+pub mod mcp_catalog;
+pub mod mcp_import;
+pub mod mcp_sampling;
+pub mod mcp_schema;
+pub mod mcp_server;
+pub mod mcp_token_store;
+pub mod mcp_tool_cache;
 pub mod mistral;
+pub mod model_picker;
 pub mod ollama;
 pub mod openai;
+pub mod openrouter;
+pub mod packs;
+pub mod pager;
+pub mod pricing;
 pub mod processing;
+pub mod profile;
+pub mod project_config;
+pub mod providers;
+pub mod pull_request;
+pub mod response_cache;
+pub mod schedule;
+pub mod script_preview;
+pub mod secrets;
 pub mod security;
+pub mod session_diff;
+pub mod share;
 pub mod skill;
 pub mod subagent;
+pub mod symbol_context;
+pub mod syntax_check;
+pub mod templates;
+pub mod test_runner;
+pub mod tool_filter;
 pub mod tools;
 pub mod tui;
+pub mod update;
 pub mod utils;
+#[cfg(feature = "voice")]
+pub mod voice;
 pub mod web;
+pub mod webhooks;
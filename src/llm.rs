@@ -1,155 +1,210 @@
 use crate::anthropic::{AnthropicClient, AnthropicResponse, ContentBlock, Message};
+use crate::cancellation::cancellable;
 use crate::config::Provider;
 use crate::gemini::GeminiClient;
 use crate::mistral::MistralClient;
 use crate::ollama::OllamaClient;
 use crate::openai::OpenAIClient;
+use crate::response_cache::{CachingProvider, ResponseCache};
 use crate::tools::{Tool, ToolCall};
 use anyhow::Result;
+use futures_util::future::BoxFuture;
 use serde_json::Value;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
-/// Macro to dispatch method calls to the appropriate provider client
-macro_rules! dispatch_to_provider {
-    ($self:expr, $method:ident, $($arg:expr),*) => {
-        match $self.provider {
-            Provider::Anthropic => {
-                $self.anthropic
-                    .as_ref()
-                    .expect("Anthropic client should be initialized")
-                    .$method($($arg),*)
-                    .await
-            }
-            Provider::Gemini => {
-                $self.gemini
-                    .as_ref()
-                    .expect("Gemini client should be initialized")
-                    .$method($($arg),*)
-                    .await
-            }
-            Provider::Mistral => {
-                $self.mistral
-                    .as_ref()
-                    .expect("Mistral client should be initialized")
-                    .$method($($arg),*)
-                    .await
-            }
-            Provider::OpenAI => {
-                $self.openai
-                    .as_ref()
-                    .expect("OpenAI client should be initialized")
-                    .$method($($arg),*)
-                    .await
-            }
-            Provider::Zai => {
-                $self.anthropic
-                    .as_ref()
-                    .expect("Z.ai client should be initialized")
-                    .$method($($arg),*)
-                    .await
-            }
-            Provider::Ollama => {
-                $self.ollama
-                    .as_ref()
-                    .expect("Ollama client should be initialized")
-                    .$method($($arg),*)
-                    .await
+pub type LlmResponse = AnthropicResponse;
+
+/// Receives each piece of text as a streamed reply arrives
+pub type ContentCallback = Arc<dyn Fn(String) + Send + Sync + 'static>;
+
+/// Part of a streamed reply that isn't text meant for the user
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplyDelta {
+    /// The model's reasoning
+    Thinking(String),
+    /// A piece of a tool call's JSON arguments
+    ToolInput {
+        tool_use_id: String,
+        name: String,
+        delta: String,
+    },
+}
+
+/// Receives thinking and tool argument deltas, from providers that stream them
+pub type DeltaCallback = Arc<dyn Fn(ReplyDelta) + Send + Sync + 'static>;
+
+/// One request for a reply from the model
+pub struct LlmRequest<'a> {
+    pub model: &'a str,
+    pub messages: Vec<Message>,
+    pub tools: &'a [Tool],
+    pub max_tokens: u32,
+    pub temperature: f32,
+    pub system_prompt: Option<&'a String>,
+    pub cancellation_flag: Arc<AtomicBool>,
+    /// Only used by streamed requests
+    pub on_delta: Option<DeltaCallback>,
+}
+
+/// A client for one provider's API. Implementations are registered with
+/// [`crate::providers::register_provider`] and picked by [`LlmClient`].
+pub trait LlmProvider: Send + Sync {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>>;
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>>;
+}
+
+/// Implement [`LlmProvider`] for clients whose inherent `create_message` and
+/// `create_message_stream` take the request's fields as arguments
+macro_rules! impl_llm_provider {
+    ($($client:ty),*) => {
+        $(
+            impl LlmProvider for $client {
+                fn create_message<'a>(
+                    &'a self,
+                    request: LlmRequest<'a>,
+                ) -> BoxFuture<'a, Result<LlmResponse>> {
+                    Box::pin(<$client>::create_message(
+                        self,
+                        request.model,
+                        request.messages,
+                        request.tools,
+                        request.max_tokens,
+                        request.temperature,
+                        request.system_prompt,
+                        request.cancellation_flag,
+                    ))
+                }
+
+                fn create_message_stream<'a>(
+                    &'a self,
+                    request: LlmRequest<'a>,
+                    on_content: ContentCallback,
+                ) -> BoxFuture<'a, Result<LlmResponse>> {
+                    Box::pin(<$client>::create_message_stream(
+                        self,
+                        request.model,
+                        request.messages,
+                        request.tools,
+                        request.max_tokens,
+                        request.temperature,
+                        request.system_prompt,
+                        on_content,
+                        request.cancellation_flag,
+                    ))
+                }
             }
-        }
+        )*
     };
 }
 
-pub type LlmResponse = AnthropicResponse;
+impl_llm_provider!(GeminiClient, MistralClient, OllamaClient);
+
+impl LlmProvider for OpenAIClient {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>> {
+        Box::pin(OpenAIClient::create_message(
+            self,
+            request.model,
+            request.messages,
+            request.tools,
+            request.max_tokens,
+            request.temperature,
+            request.system_prompt,
+            request.cancellation_flag,
+        ))
+    }
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>> {
+        Box::pin(OpenAIClient::create_message_stream(
+            self,
+            request.model,
+            request.messages,
+            request.tools,
+            request.max_tokens,
+            request.temperature,
+            request.system_prompt,
+            on_content,
+            request.on_delta,
+            request.cancellation_flag,
+        ))
+    }
+}
+
+impl LlmProvider for AnthropicClient {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>> {
+        Box::pin(AnthropicClient::create_message(
+            self,
+            request.model,
+            request.messages,
+            request.tools,
+            request.max_tokens,
+            request.temperature,
+            request.system_prompt,
+            request.cancellation_flag,
+        ))
+    }
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>> {
+        Box::pin(AnthropicClient::create_message_stream(
+            self,
+            request.model,
+            request.messages,
+            request.tools,
+            request.max_tokens,
+            request.temperature,
+            request.system_prompt,
+            on_content,
+            request.on_delta,
+            request.cancellation_flag,
+        ))
+    }
+}
 
 pub struct LlmClient {
     provider: Provider,
-    anthropic: Option<AnthropicClient>,
-    gemini: Option<GeminiClient>,
-    mistral: Option<MistralClient>,
-    openai: Option<OpenAIClient>,
-    ollama: Option<OllamaClient>,
+    client: Arc<dyn LlmProvider>,
 }
 
 impl LlmClient {
     pub fn new(provider: Provider, api_key: String, base_url: String) -> Self {
-        match provider {
-            Provider::Anthropic => Self {
-                provider,
-                anthropic: Some(AnthropicClient::new(api_key, base_url)),
-                gemini: None,
-                mistral: None,
-                openai: None,
-                ollama: None,
-            },
-            Provider::Gemini => Self {
-                provider,
-                anthropic: None,
-                gemini: Some(GeminiClient::new(api_key, base_url)),
-                mistral: None,
-                openai: None,
-                ollama: None,
-            },
-            Provider::Mistral => Self {
-                provider,
-                anthropic: None,
-                gemini: None,
-                mistral: Some(MistralClient::new(api_key, base_url)),
-                openai: None,
-                ollama: None,
-            },
-            Provider::OpenAI => Self {
-                provider,
-                anthropic: None,
-                gemini: None,
-                mistral: None,
-                openai: Some(OpenAIClient::new(api_key, base_url)),
-                ollama: None,
-            },
-            Provider::Zai => Self {
-                provider,
-                anthropic: Some(AnthropicClient::new(api_key, base_url)),
-                gemini: None,
-                mistral: None,
-                openai: None,
-                ollama: None,
-            },
-            Provider::Ollama => Self {
-                provider,
-                anthropic: None,
-                gemini: None,
-                mistral: None,
-                openai: None,
-                ollama: Some(OllamaClient::new(api_key, base_url)),
-            },
+        let spec = crate::providers::spec(provider);
+        Self {
+            provider,
+            client: (spec.factory)(api_key, base_url),
         }
     }
 
-    #[cfg(test)]
-    pub(crate) fn provider(&self) -> Provider {
+    pub fn provider(&self) -> Provider {
         self.provider
     }
 
-    #[cfg(test)]
-    pub(crate) fn has_anthropic_client(&self) -> bool {
-        self.anthropic.is_some()
-    }
-
-    #[cfg(test)]
-    pub(crate) fn has_gemini_client(&self) -> bool {
-        self.gemini.is_some()
-    }
-
-    #[cfg(test)]
-    pub(crate) fn has_mistral_client(&self) -> bool {
-        self.mistral.is_some()
-    }
-
-    #[cfg(test)]
-    pub(crate) fn has_openai_client(&self) -> bool {
-        self.openai.is_some()
+    /// This client, answering requests it has seen before from `cache`
+    pub fn with_response_cache(&self, cache: ResponseCache) -> Self {
+        Self {
+            provider: self.provider,
+            client: Arc::new(CachingProvider::new(
+                self.provider,
+                self.client.clone(),
+                cache,
+            )),
+        }
     }
 
+    /// Ask for a reply, dropping the request as soon as `cancellation_flag`
+    /// is set
     pub async fn create_message(
         &self,
         model: &str,
@@ -160,19 +215,21 @@ impl LlmClient {
         system_prompt: Option<&String>,
         cancellation_flag: Arc<AtomicBool>,
     ) -> Result<LlmResponse> {
-        dispatch_to_provider!(
-            self,
-            create_message,
+        let request = self.client.create_message(LlmRequest {
             model,
             messages,
             tools,
             max_tokens,
             temperature,
             system_prompt,
-            cancellation_flag
-        )
+            cancellation_flag: cancellation_flag.clone(),
+            on_delta: None,
+        });
+        cancellable(&cancellation_flag, request).await
     }
 
+    /// Ask for a streamed reply, dropping the stream as soon as
+    /// `cancellation_flag` is set
     pub async fn create_message_stream(
         &self,
         model: &str,
@@ -181,21 +238,24 @@ impl LlmClient {
         max_tokens: u32,
         temperature: f32,
         system_prompt: Option<&String>,
-        on_content: Arc<dyn Fn(String) + Send + Sync + 'static>,
+        on_content: ContentCallback,
+        on_delta: Option<DeltaCallback>,
         cancellation_flag: Arc<AtomicBool>,
     ) -> Result<LlmResponse> {
-        dispatch_to_provider!(
-            self,
-            create_message_stream,
-            model,
-            messages,
-            tools,
-            max_tokens,
-            temperature,
-            system_prompt,
+        let request = self.client.create_message_stream(
+            LlmRequest {
+                model,
+                messages,
+                tools,
+                max_tokens,
+                temperature,
+                system_prompt,
+                cancellation_flag: cancellation_flag.clone(),
+                on_delta,
+            },
             on_content,
-            cancellation_flag
-        )
+        );
+        cancellable(&cancellation_flag, request).await
     }
 
     pub fn convert_tool_calls(&self, content_blocks: &[ContentBlock]) -> Vec<ToolCall> {
@@ -240,7 +300,7 @@ pub fn create_response_content(content_blocks: &[ContentBlock]) -> String {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use axum::extract::{OriginalUri, State};
     use axum::http::header;
@@ -249,7 +309,7 @@ mod tests {
     use axum::{Json, Router};
     use serde_json::json;
     use std::sync::atomic::{AtomicUsize, Ordering};
-    use std::sync::{Arc, Mutex};
+    use std::sync::{Arc, Mutex, Once};
     use tokio::net::TcpListener;
 
     #[derive(Clone, Default)]
@@ -273,7 +333,8 @@ mod tests {
         }
     }
 
-    async fn spawn_server(app: Router) -> String {
+    /// Serve `app` on a free local port and return its base URL
+    pub(crate) async fn spawn_server(app: Router) -> String {
         let listener = TcpListener::bind("127.0.0.1:0")
             .await
             .expect("bind test server");
@@ -285,9 +346,15 @@ mod tests {
         base_url
     }
 
-    fn configure_no_proxy() {
-        std::env::set_var("NO_PROXY", "127.0.0.1,localhost");
-        std::env::set_var("no_proxy", "127.0.0.1,localhost");
+    /// Keep requests to test servers away from any proxy in the environment.
+    /// The variables are set once, so tests running in parallel don't
+    /// change them under each other.
+    pub(crate) fn configure_no_proxy() {
+        static NO_PROXY: Once = Once::new();
+        NO_PROXY.call_once(|| {
+            std::env::set_var("NO_PROXY", "127.0.0.1,localhost");
+            std::env::set_var("no_proxy", "127.0.0.1,localhost");
+        });
     }
 
     async fn anthropic_handler(
@@ -348,66 +415,62 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn provider_returns_expected_variant() {
-        configure_no_proxy();
+    async fn routes_registered_providers() {
+        struct EchoProvider;
+
+        impl LlmProvider for EchoProvider {
+            fn create_message<'a>(
+                &'a self,
+                request: LlmRequest<'a>,
+            ) -> BoxFuture<'a, Result<LlmResponse>> {
+                Box::pin(async move {
+                    Ok(LlmResponse {
+                        content: vec![ContentBlock::text(format!("echo from {}", request.model))],
+                        usage: None,
+                    })
+                })
+            }
 
-        // Test Anthropic provider
-        let client = LlmClient::new(
-            Provider::Anthropic,
-            "test-key".to_string(),
-            "http://localhost".to_string(),
-        );
-        assert_eq!(client.provider(), Provider::Anthropic);
-        assert!(client.has_anthropic_client());
-        assert!(!client.has_gemini_client());
-        assert!(!client.has_openai_client());
-
-        // Test Gemini provider
-        let client = LlmClient::new(
-            Provider::Gemini,
-            "test-key".to_string(),
-            "http://localhost".to_string(),
-        );
-        assert_eq!(client.provider(), Provider::Gemini);
-        assert!(!client.has_anthropic_client());
-        assert!(client.has_gemini_client());
-        assert!(!client.has_openai_client());
-
-        // Test Mistral provider
-        let client = LlmClient::new(
-            Provider::Mistral,
-            "test-key".to_string(),
-            "http://localhost".to_string(),
-        );
-        assert_eq!(client.provider(), Provider::Mistral);
-        assert!(!client.has_anthropic_client());
-        assert!(!client.has_gemini_client());
-        assert!(client.has_mistral_client());
-        assert!(!client.has_openai_client());
-
-        // Test OpenAI provider
-        let client = LlmClient::new(
-            Provider::OpenAI,
-            "test-key".to_string(),
-            "http://localhost".to_string(),
-        );
-        assert_eq!(client.provider(), Provider::OpenAI);
-        assert!(!client.has_anthropic_client());
-        assert!(!client.has_gemini_client());
-        assert!(!client.has_mistral_client());
-        assert!(client.has_openai_client());
-
-        // Test Zai provider (uses Anthropic client)
-        let client = LlmClient::new(
-            Provider::Zai,
-            "test-key".to_string(),
-            "http://localhost".to_string(),
+            fn create_message_stream<'a>(
+                &'a self,
+                request: LlmRequest<'a>,
+                on_content: ContentCallback,
+            ) -> BoxFuture<'a, Result<LlmResponse>> {
+                on_content("echo".to_string());
+                self.create_message(request)
+            }
+        }
+
+        let provider = crate::providers::register_provider(crate::providers::ProviderSpec::new(
+            "test-echo",
+            "http://localhost",
+            "echo-1",
+            Arc::new(|_api_key, _base_url| Arc::new(EchoProvider) as Arc<dyn LlmProvider>),
+        ));
+        let client = LlmClient::new(provider, String::new(), "http://localhost".to_string());
+        assert_eq!(client.provider(), provider);
+
+        let streamed = Arc::new(Mutex::new(Vec::new()));
+        let sink = streamed.clone();
+        let response = client
+            .create_message_stream(
+                "echo-1",
+                Vec::new(),
+                &[],
+                16,
+                0.0,
+                None,
+                Arc::new(move |chunk| sink.lock().expect("chunks lock").push(chunk)),
+                None,
+                Arc::new(AtomicBool::new(false)),
+            )
+            .await
+            .expect("create_message_stream");
+        assert_eq!(
+            create_response_content(&response.content),
+            "echo from echo-1"
         );
-        assert_eq!(client.provider(), Provider::Zai);
-        assert!(client.has_anthropic_client());
-        assert!(!client.has_gemini_client());
-        assert!(!client.has_mistral_client());
-        assert!(!client.has_openai_client());
+        assert_eq!(*streamed.lock().expect("chunks lock"), vec!["echo"]);
     }
 
     #[tokio::test]
@@ -419,7 +482,7 @@ mod tests {
         configure_no_proxy();
         let base_url = spawn_server(app).await;
 
-        let providers = [Provider::Anthropic, Provider::Zai];
+        let providers = [Provider::ANTHROPIC, Provider::ZAI];
         for provider in providers {
             let client = LlmClient::new(provider, "test-key".to_string(), base_url.clone());
             let messages = vec![Message {
@@ -450,6 +513,7 @@ mod tests {
                     0.0,
                     None,
                     Arc::new(|_chunk| {}),
+                    None,
                     cancellation_flag.clone(),
                 )
                 .await
@@ -472,7 +536,7 @@ mod tests {
         configure_no_proxy();
         let base_url = spawn_server(app).await;
 
-        let client = LlmClient::new(Provider::Mistral, "test-key".to_string(), base_url);
+        let client = LlmClient::new(Provider::MISTRAL, "test-key".to_string(), base_url);
         let messages = vec![Message {
             role: "user".to_string(),
             content: vec![ContentBlock::text("ping".to_string())],
@@ -501,6 +565,7 @@ mod tests {
                 0.0,
                 None,
                 Arc::new(|_chunk| {}),
+                None,
                 cancellation_flag,
             )
             .await
@@ -521,7 +586,7 @@ mod tests {
         configure_no_proxy();
         let base_url = spawn_server(app).await;
 
-        let client = LlmClient::new(Provider::OpenAI, "test-key".to_string(), base_url);
+        let client = LlmClient::new(Provider::OPENAI, "test-key".to_string(), base_url);
         let messages = vec![Message {
             role: "user".to_string(),
             content: vec![ContentBlock::text("ping".to_string())],
@@ -550,6 +615,7 @@ mod tests {
                 0.0,
                 None,
                 Arc::new(|_chunk| {}),
+                None,
                 cancellation_flag,
             )
             .await
@@ -570,7 +636,7 @@ mod tests {
         configure_no_proxy();
         let base_url = spawn_server(app).await;
 
-        let client = LlmClient::new(Provider::Gemini, "test-key".to_string(), base_url);
+        let client = LlmClient::new(Provider::GEMINI, "test-key".to_string(), base_url);
         let messages = vec![Message {
             role: "user".to_string(),
             content: vec![ContentBlock::text("ping".to_string())],
@@ -599,6 +665,7 @@ mod tests {
                 0.0,
                 None,
                 Arc::new(|_chunk| {}),
+                None,
                 cancellation_flag,
             )
             .await
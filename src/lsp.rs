@@ -0,0 +1,628 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, watch, Mutex};
+
+use crate::config::{LspConfig, LspServerConfig};
+
+/// The LSP severity of errors, the only diagnostics reported back
+const SEVERITY_ERROR: u64 = 1;
+/// Servers often publish diagnostics in several batches; once one arrives,
+/// wait this long for more
+const SETTLE: Duration = Duration::from_millis(300);
+/// Longest wait for a server to start up and answer `initialize`
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Most errors reported for one file
+const MAX_DIAGNOSTICS: usize = 20;
+
+/// An error a language server found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based, unlike LSP positions
+    pub line: u64,
+    pub column: u64,
+    pub message: String,
+    pub source: Option<String>,
+}
+
+impl Diagnostic {
+    fn from_lsp(value: &Value) -> Option<Self> {
+        let start = value.get("range")?.get("start")?;
+        Some(Self {
+            line: start.get("line")?.as_u64()? + 1,
+            column: start.get("character")?.as_u64()? + 1,
+            message: value.get("message")?.as_str()?.to_string(),
+            source: value
+                .get("source")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        })
+    }
+}
+
+/// The errors in `value`, the params of a `textDocument/publishDiagnostics`
+fn parse_published_errors(value: &Value) -> Option<(String, Vec<Diagnostic>)> {
+    let uri = value.get("uri")?.as_str()?.to_string();
+    let errors = value
+        .get("diagnostics")?
+        .as_array()?
+        .iter()
+        .filter(|diagnostic| {
+            // Severity is optional and then up to the client; count it as an error
+            diagnostic
+                .get("severity")
+                .and_then(Value::as_u64)
+                .unwrap_or(SEVERITY_ERROR)
+                == SEVERITY_ERROR
+        })
+        .filter_map(Diagnostic::from_lsp)
+        .collect();
+    Some((uri, errors))
+}
+
+/// The report appended to a Write or Edit result when the file has errors
+pub fn format_diagnostics(path: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut report = format!(
+        "The language server reports {} error{} in {} after this change:",
+        diagnostics.len(),
+        if diagnostics.len() == 1 { "" } else { "s" },
+        path
+    );
+    for diagnostic in diagnostics.iter().take(MAX_DIAGNOSTICS) {
+        report.push_str(&format!(
+            "\n{}:{}:{}: {}",
+            path, diagnostic.line, diagnostic.column, diagnostic.message
+        ));
+        if let Some(source) = &diagnostic.source {
+            report.push_str(&format!(" [{}]", source));
+        }
+    }
+    if diagnostics.len() > MAX_DIAGNOSTICS {
+        report.push_str(&format!(
+            "\n... and {} more",
+            diagnostics.len() - MAX_DIAGNOSTICS
+        ));
+    }
+    report
+}
+
+/// A `file://` URI for an absolute path
+pub fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        // Windows drive paths
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// The LSP language ID for files with `extension`
+fn language_id(server: &LspServerConfig, extension: &str) -> String {
+    if let Some(language_id) = &server.language_id {
+        return language_id.clone();
+    }
+    match extension {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "py" => "python",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Frame a JSON-RPC message with the Content-Length header LSP uses
+pub fn encode_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut framed = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    framed.extend_from_slice(body.as_bytes());
+    framed
+}
+
+/// Read one framed JSON-RPC message, or None at the end of the stream
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+    let length = content_length.ok_or_else(|| anyhow!("LSP message without Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+type Writer = Arc<Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+type Pending = Arc<std::sync::Mutex<HashMap<i64, oneshot::Sender<Value>>>>;
+/// Errors per document URI, with a count of the times each was published
+type Published = HashMap<String, (u64, Vec<Diagnostic>)>;
+
+async fn write_message(writer: &Writer, message: &Value) -> Result<()> {
+    let mut writer = writer.lock().await;
+    writer.write_all(&encode_message(message)).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// A connection to one running language server
+pub struct LspClient {
+    writer: Writer,
+    next_id: AtomicI64,
+    pending: Pending,
+    published: watch::Receiver<Published>,
+    /// Open documents and their versions
+    versions: Mutex<HashMap<String, i64>>,
+    _child: Option<Child>,
+}
+
+impl LspClient {
+    /// Start `server` for the project in `root`
+    pub async fn spawn(server: &LspServerConfig, root: &Path) -> Result<Self> {
+        let mut child = Command::new(&server.command)
+            .args(&server.args)
+            .current_dir(root)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", server.command))?;
+        let stdin = child.stdin.take().context("language server stdin")?;
+        let stdout = child.stdout.take().context("language server stdout")?;
+        let mut client = Self::connect(tokio::io::BufReader::new(stdout), stdin, root).await?;
+        client._child = Some(child);
+        Ok(client)
+    }
+
+    /// Talk LSP over `reader` and `writer`, and initialize the server for
+    /// the project in `root`
+    pub async fn connect<R, W>(reader: R, writer: W, root: &Path) -> Result<Self>
+    where
+        R: AsyncBufRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let writer: Writer = Arc::new(Mutex::new(Box::new(writer)));
+        let pending: Pending = Arc::default();
+        let (published_tx, published) = watch::channel(Published::new());
+        tokio::spawn(Self::read_loop(
+            reader,
+            writer.clone(),
+            pending.clone(),
+            published_tx,
+        ));
+
+        let client = Self {
+            writer,
+            next_id: AtomicI64::new(1),
+            pending,
+            published,
+            versions: Mutex::default(),
+            _child: None,
+        };
+        let root_uri = file_uri(root);
+        let initialize = client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri,
+                "workspaceFolders": [{ "uri": root_uri, "name": "workspace" }],
+                "capabilities": {
+                    "textDocument": {
+                        "synchronization": { "didSave": true },
+                        "publishDiagnostics": { "versionSupport": true }
+                    },
+                    "workspace": { "workspaceFolders": true, "configuration": true }
+                },
+            }),
+        );
+        tokio::time::timeout(INITIALIZE_TIMEOUT, initialize)
+            .await
+            .map_err(|_| anyhow!("The language server didn't answer initialize"))??;
+        client.notify("initialized", json!({})).await?;
+        Ok(client)
+    }
+
+    async fn read_loop<R: AsyncBufRead + Unpin>(
+        mut reader: R,
+        writer: Writer,
+        pending: Pending,
+        published: watch::Sender<Published>,
+    ) {
+        loop {
+            let message = match read_message(&mut reader).await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(e) => {
+                    debug!("Failed to read from the language server: {}", e);
+                    break;
+                }
+            };
+            let id = message.get("id").cloned();
+            match (message.get("method").and_then(Value::as_str), id) {
+                (Some("textDocument/publishDiagnostics"), None) => {
+                    if let Some((uri, errors)) =
+                        message.get("params").and_then(parse_published_errors)
+                    {
+                        published.send_modify(|published| {
+                            let entry = published.entry(uri).or_default();
+                            entry.0 += 1;
+                            entry.1 = errors;
+                        });
+                    }
+                }
+                (Some(method), Some(id)) => {
+                    // Requests from the server, e.g. for configuration. Answering
+                    // with nothing keeps the server from waiting on us.
+                    let result = match method {
+                        "workspace/configuration" => {
+                            let items = message
+                                .pointer("/params/items")
+                                .and_then(Value::as_array)
+                                .map_or(0, Vec::len);
+                            Value::Array(vec![Value::Null; items])
+                        }
+                        _ => Value::Null,
+                    };
+                    let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                    if write_message(&writer, &reply).await.is_err() {
+                        break;
+                    }
+                }
+                (None, Some(id)) => {
+                    let sender = id
+                        .as_i64()
+                        .and_then(|id| pending.lock().expect("pending lock").remove(&id));
+                    if let Some(sender) = sender {
+                        let _ = sender.send(message);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Waking the waiters lets them see the server is gone
+        pending.lock().expect("pending lock").clear();
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        self.pending
+            .lock()
+            .expect("pending lock")
+            .insert(id, sender);
+        write_message(
+            &self.writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+        )
+        .await?;
+        let response = receiver
+            .await
+            .map_err(|_| anyhow!("The language server exited"))?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        write_message(
+            &self.writer,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+        .await
+    }
+
+    /// Send the server the new `text` of the file at `path` and return the
+    /// errors it finds, waiting at most `timeout` for them
+    pub async fn check(
+        &self,
+        path: &Path,
+        language_id: &str,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<Vec<Diagnostic>> {
+        let uri = file_uri(path);
+        let mut published = self.published.clone();
+        let seen = published.borrow_and_update().get(&uri).map_or(0, |p| p.0);
+
+        {
+            let mut versions = self.versions.lock().await;
+            match versions.get_mut(&uri) {
+                Some(version) => {
+                    *version += 1;
+                    self.notify(
+                        "textDocument/didChange",
+                        json!({
+                            "textDocument": { "uri": uri, "version": *version },
+                            "contentChanges": [{ "text": text }],
+                        }),
+                    )
+                    .await?;
+                }
+                None => {
+                    versions.insert(uri.clone(), 1);
+                    self.notify(
+                        "textDocument/didOpen",
+                        json!({
+                            "textDocument": {
+                                "uri": uri,
+                                "languageId": language_id,
+                                "version": 1,
+                                "text": text,
+                            },
+                        }),
+                    )
+                    .await?;
+                }
+            }
+            self.notify(
+                "textDocument/didSave",
+                json!({ "textDocument": { "uri": uri }, "text": text }),
+            )
+            .await?;
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let first = tokio::time::timeout_at(deadline, async {
+            loop {
+                if published.borrow_and_update().get(&uri).map_or(0, |p| p.0) > seen {
+                    return true;
+                }
+                if published.changed().await.is_err() {
+                    return false;
+                }
+            }
+        })
+        .await;
+        if !matches!(first, Ok(true)) {
+            debug!("No diagnostics for {} before the timeout", uri);
+            return Ok(Vec::new());
+        }
+        while tokio::time::Instant::now() < deadline {
+            match tokio::time::timeout(SETTLE, published.changed()).await {
+                Ok(Ok(())) => continue,
+                _ => break,
+            }
+        }
+        let errors = published
+            .borrow()
+            .get(&uri)
+            .map(|p| p.1.clone())
+            .unwrap_or_default();
+        Ok(errors)
+    }
+}
+
+/// Starts the configured language servers on demand and asks them about
+/// files the agent changes
+pub struct LspManager {
+    config: LspConfig,
+    root: PathBuf,
+    /// Servers by their index in the config; None once one failed to start
+    clients: Mutex<HashMap<usize, Option<Arc<LspClient>>>>,
+}
+
+impl LspManager {
+    pub fn new(config: LspConfig, root: PathBuf) -> Self {
+        Self {
+            config,
+            root,
+            clients: Mutex::default(),
+        }
+    }
+
+    fn server_for(&self, path: &Path) -> Option<(usize, &LspServerConfig, String)> {
+        let extension = path.extension()?.to_str()?;
+        self.config
+            .servers
+            .iter()
+            .enumerate()
+            .find(|(_, server)| server.extensions.iter().any(|ext| ext == extension))
+            .map(|(index, server)| (index, server, language_id(server, extension)))
+    }
+
+    async fn client(&self, index: usize, server: &LspServerConfig) -> Option<Arc<LspClient>> {
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&index) {
+            return client.clone();
+        }
+        let client = match LspClient::spawn(server, &self.root).await {
+            Ok(client) => Some(Arc::new(client)),
+            Err(e) => {
+                warn!(
+                    "Language server {} isn't available: {:#}",
+                    server.command, e
+                );
+                None
+            }
+        };
+        clients.insert(index, client.clone());
+        client
+    }
+
+    /// The errors a language server finds in the file at `path`, or None when
+    /// no server handles it
+    pub async fn diagnostics(&self, path: &Path) -> Option<Vec<Diagnostic>> {
+        let (index, server, language_id) = self.server_for(path)?;
+        let client = self.client(index, server).await?;
+        let text = tokio::fs::read_to_string(path).await.ok()?;
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        match client.check(path, &language_id, &text, timeout).await {
+            Ok(diagnostics) => Some(diagnostics),
+            Err(e) => {
+                warn!(
+                    "{} failed to check {}: {:#}",
+                    server.command,
+                    path.display(),
+                    e
+                );
+                // Start it again next time
+                self.clients.lock().await.remove(&index);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::BufReader;
+
+    #[test]
+    fn formats_errors_with_positions() {
+        let diagnostics = vec![
+            Diagnostic {
+                line: 3,
+                column: 5,
+                message: "expected `;`".to_string(),
+                source: Some("rustc".to_string()),
+            },
+            Diagnostic {
+                line: 9,
+                column: 1,
+                message: "unresolved name".to_string(),
+                source: None,
+            },
+        ];
+        assert_eq!(
+            format_diagnostics("src/lib.rs", &diagnostics),
+            "The language server reports 2 errors in src/lib.rs after this change:\n\
+             src/lib.rs:3:5: expected `;` [rustc]\n\
+             src/lib.rs:9:1: unresolved name"
+        );
+    }
+
+    #[test]
+    fn file_uris_escape_paths() {
+        assert_eq!(
+            file_uri(Path::new("/home/me/my project/a.rs")),
+            "file:///home/me/my%20project/a.rs"
+        );
+    }
+
+    #[test]
+    fn keeps_only_errors() {
+        let params = json!({
+            "uri": "file:///a.rs",
+            "diagnostics": [
+                { "range": { "start": { "line": 0, "character": 4 } }, "severity": 1, "message": "bad" },
+                { "range": { "start": { "line": 2, "character": 0 } }, "severity": 2, "message": "unused" },
+            ]
+        });
+        let (uri, errors) = parse_published_errors(&params).unwrap();
+        assert_eq!(uri, "file:///a.rs");
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (1, 5));
+    }
+
+    #[tokio::test]
+    async fn reads_framed_messages() {
+        let mut bytes = encode_message(&json!({ "id": 1 }));
+        bytes.extend(encode_message(&json!({ "id": 2 })));
+        let mut reader = BufReader::new(bytes.as_slice());
+        assert_eq!(
+            read_message(&mut reader).await.unwrap(),
+            Some(json!({ "id": 1 }))
+        );
+        assert_eq!(
+            read_message(&mut reader).await.unwrap(),
+            Some(json!({ "id": 2 }))
+        );
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    /// A server that answers initialize and reports an error in every
+    /// document it's sent
+    async fn fake_server(stream: tokio::io::DuplexStream) {
+        let (reader, writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        let writer: Writer = Arc::new(Mutex::new(Box::new(writer)));
+        while let Ok(Some(message)) = read_message(&mut reader).await {
+            match message.get("method").and_then(Value::as_str) {
+                Some("initialize") => {
+                    let reply = json!({ "jsonrpc": "2.0", "id": message["id"], "result": { "capabilities": {} } });
+                    write_message(&writer, &reply).await.unwrap();
+                }
+                Some("textDocument/didOpen") | Some("textDocument/didChange") => {
+                    let uri = message["params"]["textDocument"]["uri"].clone();
+                    let publish = json!({
+                        "jsonrpc": "2.0",
+                        "method": "textDocument/publishDiagnostics",
+                        "params": {
+                            "uri": uri,
+                            "diagnostics": [{
+                                "range": { "start": { "line": 1, "character": 2 } },
+                                "severity": 1,
+                                "message": "mismatched types",
+                            }],
+                        },
+                    });
+                    write_message(&writer, &publish).await.unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_documents_with_a_server() {
+        let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(fake_server(server_end));
+        let (reader, writer) = tokio::io::split(client_end);
+        let client = LspClient::connect(BufReader::new(reader), writer, Path::new("/project"))
+            .await
+            .unwrap();
+
+        let path = Path::new("/project/src/main.rs");
+        for _ in 0..2 {
+            let errors = client
+                .check(path, "rust", "fn main() {}", Duration::from_secs(5))
+                .await
+                .unwrap();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].message, "mismatched types");
+            assert_eq!((errors[0].line, errors[0].column), (2, 3));
+        }
+        assert_eq!(
+            client
+                .versions
+                .lock()
+                .await
+                .get("file:///project/src/main.rs"),
+            Some(&2)
+        );
+    }
+}
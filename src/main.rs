@@ -9,18 +9,23 @@ use std::sync::Arc;
 use tokio::sync::{Mutex as AsyncMutex, RwLock};
 
 use flexorama::*;
-use flexorama::{app_eprintln, app_println};
+use flexorama::{app_eprintln, app_print, app_println};
 
 use agent::Agent;
-use cli::Cli;
-use config::{Config, Provider};
+use cli::{
+    BundleCommand, Cli, Commands, ConfigCommand, ConversationsCommand, ProfileCommand,
+    ScheduleCommand,
+};
+use config::Config;
 use csrf::CsrfManager;
 use database::{get_database_path, DatabaseManager};
 use formatter::create_code_formatter;
+use headless::{OutputFormat, RunResult, RunStatus, StreamEvent, ToolCallCollector};
 use help::{display_mcp_yolo_warning, display_yolo_warning};
 use interactive::{add_context_files, run_tui_interactive};
 use mcp::McpManager;
-use processing::create_streaming_renderer;
+use processing::{create_streaming_renderer, render_thinking};
+use project_config::ConfigSource;
 use subagent::SubagentManager;
 use utils::{create_spinner, print_usage_stats};
 
@@ -28,11 +33,27 @@ use utils::{create_spinner, print_usage_stats};
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    let is_interactive = cli.message.is_none() && !cli.non_interactive && !cli.web && !cli.acp;
-    let stream = !cli.no_stream;
+    let serving_mcp = cli
+        .command
+        .as_ref()
+        .is_some_and(|command| !matches!(command, Commands::New { .. }));
+    let json_output = cli.output_format.is_json();
+    let is_interactive = cli.message.is_none()
+        && !cli.non_interactive
+        && !json_output
+        && !cli.web
+        && !cli.acp
+        && !serving_mcp;
+    if json_output {
+        // Keep stdout for the JSON result
+        output::redirect_stdout_to_stderr();
+    }
+    if cli.no_pager {
+        pager::disable();
+    }
 
     // Create code formatter early so TUI can render input/output immediately
-    let formatter = create_code_formatter()?;
+    let mut formatter = create_code_formatter()?;
     let _tui_guard = if is_interactive {
         Some(Arc::new(tui::init_tui_output(&formatter)?))
     } else {
@@ -40,43 +61,177 @@ async fn main() -> Result<()> {
     };
 
     // Initialize logger - in ACP mode, all logs go to stderr to keep stdout clean for JSON-RPC
-    output::init_logger(log::LevelFilter::Info, cli.acp);
+    output::init_logger(log::LevelFilter::Info, cli.acp || serving_mcp);
+    crash::install_panic_hook();
     debug!("Starting Flexorama");
 
+    if let Some(Commands::Config {
+        action: ConfigCommand::Check { strict },
+    }) = cli.command
+    {
+        run_config_check(cli.config.as_deref(), strict)?;
+    }
+    if let Some(Commands::Init) = cli.command {
+        return run_init_wizard(cli.config.as_deref()).await;
+    }
+    if let Some(Commands::ImportConfig { tool, dry_run }) = cli.command {
+        return run_import_config(cli.config.as_deref(), tool, dry_run).await;
+    }
+    if let Some(Commands::Conversations { action }) = cli.command {
+        return run_conversations_command(action).await;
+    }
+    if let Some(Commands::New { list: true, .. }) = cli.command {
+        let (config, _) =
+            Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+        list_templates(&config);
+        return Ok(());
+    }
+    if let Some(Commands::Schedule { action }) = &cli.command {
+        if !matches!(action, ScheduleCommand::Run { .. }) {
+            let (config, _) =
+                Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+            if let Some(dir) = &config.commands_dir {
+                custom_commands::set_commands_dir(dir.clone());
+            }
+            return run_schedule_command(action).await;
+        }
+    }
+    if let Some(Commands::Skill { action } | Commands::Agent { action }) = &cli.command {
+        let kind = match cli.command {
+            Some(Commands::Agent { .. }) => bundle::BundleKind::Agent,
+            _ => bundle::BundleKind::Skill,
+        };
+        let (config, _) =
+            Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+        http_client::configure(&config);
+        return run_bundle_command(kind, action).await;
+    }
+    if let Some(Commands::Profile { action }) = &cli.command {
+        let (config, _) =
+            Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+        if let Some(dir) = &config.commands_dir {
+            custom_commands::set_commands_dir(dir.clone());
+        }
+        return run_profile_command(cli.config.as_deref(), action).await;
+    }
+    if let Some(Commands::Install {
+        source,
+        project,
+        yes,
+    }) = &cli.command
+    {
+        let (config, _) =
+            Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+        http_client::configure(&config);
+        if let Some(dir) = &config.commands_dir {
+            custom_commands::set_commands_dir(dir.clone());
+        }
+        return run_install_command(source, config.registry_url.as_deref(), *project, *yes).await;
+    }
+    if let Some(Commands::Update { check }) = cli.command {
+        let (config, _) =
+            Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+        http_client::configure(&config);
+        return run_update_command(check).await;
+    }
+
+    if let Some(Commands::McpServe { http, port }) = cli.command {
+        let (config, _) =
+            Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+        http_client::configure(&config);
+        return run_mcp_serve_mode(config, cli.yolo, http, port).await;
+    }
+
     // Display large red warning if yolo mode is enabled
     if cli.yolo {
         display_yolo_warning();
     }
 
-    // Load configuration
-    let mut config = Config::load(cli.config.as_deref()).await?;
+    // Load configuration, with the current project's config layered on top
+    let (mut config, mut config_sources) =
+        Config::load_layered(cli.config.as_deref(), &std::env::current_dir()?).await?;
+    if config_sources.untrusted_project().is_some() {
+        confirm_project_mcp_servers(&mut config, &mut config_sources, _tui_guard.as_deref());
+    }
+    // Watch for edits in long-running modes; command line overrides aren't reloaded
+    let config_reloads = if is_interactive || cli.web {
+        Some(config_watch::spawn_config_watcher(
+            cli.config.clone(),
+            std::env::current_dir()?,
+            config.clone(),
+        ))
+    } else {
+        None
+    };
+
+    let stream = config.stream && !cli.no_stream;
+    formatter.set_line_numbers(config.code_line_numbers);
+    formatter.set_block_numbers(is_interactive);
+    output::set_output_width(config.output_width);
+    image::set_limits(config.images.clone());
+    i18n::set_locale(config.locale.as_deref());
+    match json_log::enable(&config.logging) {
+        Ok(Some(level)) => log::set_max_level(level.max(log::max_level())),
+        Ok(None) => {}
+        Err(e) => warn!("JSON logging is off: {:#}", e),
+    }
+    http_client::configure(&config);
+    if config.tls.danger_accept_invalid_certs {
+        app_eprintln!(
+            "{}",
+            "⚠ TLS certificate verification is disabled (tls.danger_accept_invalid_certs)"
+                .yellow()
+                .bold()
+        );
+    }
 
     // If provider is specified on command line, always apply its defaults
     if let Some(provider) = cli.provider {
         config.set_provider(provider);
+        for key in ["provider", "base_url", "default_model"] {
+            config_sources.set(key, ConfigSource::CommandLine);
+        }
     }
 
-    // Initialize database
-    info!("Initializing database...");
-    let db_path = get_database_path()?;
-    let database_manager = DatabaseManager::new(db_path).await?;
-    info!(
-        "Database initialized at: {}",
-        database_manager.path().display()
-    );
-
     // Override API key if provided via command line (highest priority)
     if let Some(api_key) = cli.api_key.clone() {
         config.api_key = api_key;
+        config_sources.set("api_key", ConfigSource::CommandLine);
     } else if config.api_key.is_empty() {
         // If no API key from config, try environment variable for the selected provider
         config.api_key = config::provider_default_api_key(config.provider);
     }
 
+    let template = match &cli.command {
+        Some(Commands::New {
+            template: Some(name),
+            ..
+        }) => Some(templates::find_template(&config.templates, name)?),
+        _ => None,
+    };
+
     let model = cli
         .model
         .clone()
+        .or_else(|| template.as_ref().and_then(|t| t.config.model.clone()))
         .unwrap_or_else(|| config.default_model.clone());
+    if cli.model.is_some() {
+        config_sources.set("default_model", ConfigSource::CommandLine);
+    }
+    if let Some(dir) = &config.commands_dir {
+        custom_commands::set_commands_dir(dir.clone());
+    }
+    json_log::event(
+        "session_start",
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "provider": config.provider.to_string(),
+            "model": model,
+            "cwd": std::env::current_dir().ok(),
+        }),
+    );
+    let added_dirs = resolve_added_dirs(&cli.add_dirs)?;
+    tools::path::set_additional_roots(added_dirs.clone());
 
     // In ACP mode, all logging must go to stderr, never stdout
     if !cli.acp {
@@ -84,6 +239,9 @@ async fn main() -> Result<()> {
         app_println!("  Provider: {}", config.provider);
         app_println!("  Base URL: {}", config.base_url);
         app_println!("  Model: {}", model);
+        for dir in &added_dirs {
+            app_println!("  Added directory: {}", dir.display());
+        }
 
         // Show yolo mode status
         if cli.yolo {
@@ -95,15 +253,8 @@ async fn main() -> Result<()> {
     }
 
     // Validate API key without exposing it
-    if config.api_key.is_empty() && config.provider != Provider::Ollama {
-        let env_hint = match config.provider {
-            Provider::Anthropic => "ANTHROPIC_AUTH_TOKEN",
-            Provider::Gemini => "GEMINI_API_KEY or GOOGLE_API_KEY",
-            Provider::Mistral => "MISTRAL_API_KEY",
-            Provider::OpenAI => "OPENAI_API_KEY",
-            Provider::Zai => "ZAI_API_KEY",
-            Provider::Ollama => "OLLAMA_API_KEY (optional for local instances)",
-        };
+    if config.api_key.is_empty() && !config::provider_api_key_optional(config.provider) {
+        let env_hint = providers::spec(config.provider).api_key_env.join(" or ");
         app_eprintln!(
             "{}",
             format!(
@@ -113,7 +264,7 @@ async fn main() -> Result<()> {
             .red()
         );
         app_eprintln!(
-            "Create a config file at {} or set {}",
+            "Run `flexorama init`, create a config file at {} or set {}",
             Config::default_config_path().display(),
             env_hint
         );
@@ -138,26 +289,48 @@ async fn main() -> Result<()> {
     let mut agent =
         Agent::new_with_plan_mode(config.clone(), model.clone(), cli.yolo, cli.plan_mode).await;
 
-    // Initialize MCP manager
+    // The database, MCP configuration and skills don't depend on each other,
+    // so they're loaded at the same time
+    info!("Initializing database, MCP manager and skills...");
+    let db_path = get_database_path()?;
     let mcp_manager = Arc::new(McpManager::new());
-    mcp_manager.initialize(config.mcp.clone()).await?;
+    let config_arc = Arc::new(RwLock::new(config.clone()));
+    let skill_manager = Arc::new(AsyncMutex::new(crate::skill::SkillManager::new(
+        config_arc.clone(),
+    )?));
+    let (database_manager, mcp_initialized, skills_loaded) = tokio::join!(
+        DatabaseManager::new(db_path),
+        mcp_manager.initialize(config.mcp.clone()),
+        async { skill_manager.lock().await.load_all_skills().await },
+    );
+    let database_manager = database_manager?;
+    mcp_initialized?;
+    skills_loaded?;
+    info!(
+        "Database initialized at: {}",
+        database_manager.path().display()
+    );
+
+    mcp_manager
+        .set_project_servers(config_sources.project_mcp_servers())
+        .await;
+    project_config::record_sources(config_sources);
     agent = agent.with_mcp_manager(mcp_manager.clone());
+    mcp_manager
+        .set_sampling_handler(Some(agent.mcp_sampling_handler()))
+        .await;
+
+    // Slow MCP servers mustn't hold up startup; interactive sessions don't
+    // wait for them at all
+    let mcp_connect = spawn_mcp_connect(mcp_manager.clone(), _tui_guard.clone());
 
     // Set database manager in agent
     let database_manager = Arc::new(database_manager);
     agent = agent.with_database_manager(database_manager.clone());
-
-    // Initialize SkillManager
-    info!("Initializing skill manager...");
-    let config_arc = Arc::new(RwLock::new(config.clone()));
-    let skill_manager = Arc::new(AsyncMutex::new(crate::skill::SkillManager::new(
-        config_arc.clone(),
-    )?));
     agent = agent.with_skill_manager(skill_manager.clone());
 
     let (deactivated, skill_names) = {
-        let mut manager = skill_manager.lock().await;
-        manager.load_all_skills().await?;
+        let manager = skill_manager.lock().await;
 
         // Activate all skills not explicitly deactivated in config
         let config_read = config_arc.read().await;
@@ -191,62 +364,39 @@ async fn main() -> Result<()> {
 
     info!("Skill manager initialized");
 
-    // Connect to all enabled MCP servers
-    info!("Connecting to MCP servers...");
-    let mcp_connect_result = tokio::time::timeout(
-        std::time::Duration::from_secs(30),
-        mcp_manager.connect_all_enabled(),
-    )
-    .await;
-
-    match mcp_connect_result {
-        Ok(Ok(_)) => {
-            info!("MCP servers connected successfully");
-        }
-        Ok(Err(e)) => {
-            warn!("Failed to connect to MCP servers: {}", e);
-            error!("MCP Server Connection Issues:");
-            error!("  - Check that MCP servers are configured correctly: /mcp list");
-            error!("  - Verify server commands/URLs are valid");
-            error!("  - Ensure all dependencies are installed");
-            error!("  - Use '/mcp test <command>' to verify command availability");
-            error!("  - Tool calls to unavailable MCP servers will fail");
-        }
-        Err(_) => {
-            warn!("MCP server connection timed out after 30 seconds");
-            error!("MCP Server Connection Timeout:");
-            error!("  - MCP servers are taking too long to respond");
-            error!("  - Check if servers are running and accessible");
-            error!("  - Use '/mcp reconnect <server>' to try connecting manually");
-        }
-    }
-
-    // Force initial refresh of MCP tools after connecting
-    info!("Refreshing MCP tools...");
-    let mcp_refresh_result = tokio::time::timeout(
-        std::time::Duration::from_secs(15),
-        agent.force_refresh_mcp_tools(),
-    )
-    .await;
+    // Everything but the interactive session needs its MCP tools from the start.
+    // The interactive agent picks them up before the first request after they
+    // arrive.
+    if !is_interactive {
+        let _ = mcp_connect.await;
+
+        // Force initial refresh of MCP tools after connecting
+        info!("Refreshing MCP tools...");
+        let mcp_refresh_result = tokio::time::timeout(
+            std::time::Duration::from_secs(15),
+            agent.force_refresh_mcp_tools(),
+        )
+        .await;
 
-    match mcp_refresh_result {
-        Ok(Ok(_)) => {
-            info!("MCP tools loaded successfully");
-        }
-        Ok(Err(e)) => {
-            warn!("Failed to refresh MCP tools on startup: {}", e);
-            error!("MCP Tools Loading Failed:");
-            error!("  - Connected MCP servers may not be responding properly");
-            error!("  - Tools may have invalid schemas or descriptions");
-            error!("  - Use '/mcp tools' to check available tools");
-            error!("  - Use '/mcp reconnect <server>' to fix connection issues");
-        }
-        Err(_) => {
-            warn!("MCP tools refresh timed out after 15 seconds");
-            error!("MCP Tools Refresh Timeout:");
-            error!("  - MCP servers are taking too long to provide tools");
-            error!("  - Some tools may not be available initially");
-            error!("  - Tools will be refreshed on demand during use");
+        match mcp_refresh_result {
+            Ok(Ok(_)) => {
+                info!("MCP tools loaded successfully");
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to refresh MCP tools on startup: {}", e);
+                error!("MCP Tools Loading Failed:");
+                error!("  - Connected MCP servers may not be responding properly");
+                error!("  - Tools may have invalid schemas or descriptions");
+                error!("  - Use '/mcp tools' to check available tools");
+                error!("  - Use '/mcp reconnect <server>' to fix connection issues");
+            }
+            Err(_) => {
+                warn!("MCP tools refresh timed out after 15 seconds");
+                error!("MCP Tools Refresh Timeout:");
+                error!("  - MCP servers are taking too long to provide tools");
+                error!("  - Some tools may not be available initially");
+                error!("  - Tools will be refreshed on demand during use");
+            }
         }
     }
 
@@ -255,6 +405,51 @@ async fn main() -> Result<()> {
         display_mcp_yolo_warning();
     }
 
+    if let Some(max_turns) = cli.max_turns {
+        agent.set_max_turns(max_turns);
+    }
+    agent.set_tool_filter(tool_filter::ToolFilter::from_args(
+        &cli.allowed_tools,
+        &cli.disallowed_tools,
+    )?);
+
+    if let Some(name) = &cli.session_name {
+        database::validate_conversation_name(name).map_err(|e| anyhow!("--session-name: {}", e))?;
+    }
+
+    // Reopen an earlier conversation before applying this run's prompt settings
+    let mut resumed = resume_requested_conversation(&cli, &mut agent, &database_manager).await?;
+    if let (false, None, Some(tui)) = (resumed, &template, _tui_guard.as_ref()) {
+        resumed = offer_crash_recovery(tui, &mut agent).await;
+    }
+    agent.set_crash_recovery(is_interactive);
+    if is_interactive && config.summarize_conversations {
+        agent.set_summary_worker(Some(processing::SummaryWorker::spawn(
+            agent.llm_client(),
+            database_manager.clone(),
+        )));
+    }
+    if config.lsp.enabled {
+        agent.set_lsp(Some(Arc::new(lsp::LspManager::new(
+            config.lsp.clone(),
+            std::env::current_dir()?,
+        ))));
+    }
+    if config.checkpoints.enabled && !cli.web && !cli.acp {
+        let checkpointer =
+            checkpoint::Checkpointer::open(config.checkpoints.clone(), &std::env::current_dir()?)
+                .await?;
+        if checkpointer.is_none() {
+            warn!("Checkpoints are enabled but the current directory isn't in a git repository");
+        }
+        agent.set_checkpointer(checkpointer);
+    }
+    if resumed {
+        if let Some(model) = &cli.model {
+            agent.set_model(model.clone()).await?;
+        }
+    }
+
     // Set system prompt - use command line prompt if provided, otherwise use config default
     match &cli.system_prompt {
         Some(system_prompt) => {
@@ -278,6 +473,19 @@ async fn main() -> Result<()> {
         }
     }
 
+    if let Some(template) = &template {
+        apply_template_settings(&mut agent, template, cli.system_prompt.is_none()).await?;
+    }
+
+    if !added_dirs.is_empty() {
+        let note = added_dirs_prompt(&added_dirs);
+        let prompt = match agent.get_system_prompt() {
+            Some(prompt) => format!("{}\n\n{}", prompt, note),
+            None => note,
+        };
+        agent.set_system_prompt(prompt);
+    }
+
     if cli.plan_mode {
         agent.apply_plan_mode_prompt();
         if !cli.acp {
@@ -290,14 +498,43 @@ async fn main() -> Result<()> {
 
     // Add context files (silent in ACP mode to avoid stdout pollution)
     add_context_files(&mut agent, &cli.context_files, cli.acp).await?;
+    if let Some(template) = &template {
+        let files: Vec<String> = template
+            .context_files(&std::env::current_dir()?)
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        add_context_files(&mut agent, &files, cli.acp).await?;
+    }
+
+    // With -m, anything piped in (e.g. `git diff | flexorama -m "review this"`)
+    // is attached as context rather than being the prompt
+    if cli.message.is_some() {
+        if let Some(piped) = read_piped_stdin()? {
+            agent.add_context_text("standard input", &piped);
+            app_println!(
+                "{} Added piped input as context ({} lines)",
+                "✓".green(),
+                piped.lines().count()
+            );
+        }
+    }
 
     // Create initial conversation in database
-    match agent.start_new_conversation().await {
-        Ok(conversation_id) => {
-            info!("Started initial conversation: {}", conversation_id);
+    if !resumed {
+        match agent.start_new_conversation().await {
+            Ok(conversation_id) => {
+                info!("Started initial conversation: {}", conversation_id);
+            }
+            Err(e) => {
+                warn!("Failed to create initial conversation: {}", e);
+            }
         }
-        Err(e) => {
-            warn!("Failed to create initial conversation: {}", e);
+    }
+    if let Some(name) = &cli.session_name {
+        name_current_conversation(&agent, &database_manager, name).await?;
+        if !cli.acp {
+            app_println!("{} Conversation named '{}'", "✓".green(), name);
         }
     }
 
@@ -308,6 +545,7 @@ async fn main() -> Result<()> {
     }
 
     // Run the appropriate mode
+    let mut exit_code = headless::exit_code::SUCCESS;
     if cli.acp {
         run_acp_mode(agent, config, model, cli.acp_debug).await?;
     } else if cli.web {
@@ -318,13 +556,65 @@ async fn main() -> Result<()> {
             database_manager.clone(),
             mcp_manager.clone(),
             skill_manager.clone(),
+            config_reloads,
+        )
+        .await?;
+    } else if let Some(Commands::Batch {
+        file,
+        output_dir,
+        concurrency,
+    }) = &cli.command
+    {
+        let services = (database_manager.clone(), mcp_manager.clone(), skill_manager);
+        run_batch_mode(
+            &cli,
+            &agent,
+            &config,
+            services,
+            file,
+            output_dir,
+            *concurrency,
+        )
+        .await?;
+    } else if let Some(Commands::Eval {
+        file,
+        models,
+        output_dir,
+    }) = &cli.command
+    {
+        let services = (database_manager.clone(), mcp_manager.clone(), skill_manager);
+        run_eval_mode(&cli, &agent, &config, services, file, models, output_dir).await?;
+    } else if let Some(Commands::Schedule {
+        action: ScheduleCommand::Run { once },
+    }) = &cli.command
+    {
+        let services = (database_manager.clone(), mcp_manager.clone(), skill_manager);
+        run_scheduler(&cli, &agent, &config, services, *once).await?;
+    } else if let Some(message) = cli.message.clone() {
+        enable_response_cache(&cli, &config, &mut agent);
+        exit_code = run_single_message_mode(
+            message,
+            &mut agent,
+            &formatter,
+            stream,
+            cli.output_format,
+            cli.verbose,
+        )
+        .await?;
+    } else if cli.non_interactive || json_output {
+        enable_response_cache(&cli, &config, &mut agent);
+        exit_code = run_non_interactive_mode(
+            &mut agent,
+            &formatter,
+            stream,
+            cli.output_format,
+            cli.verbose,
         )
         .await?;
-    } else if let Some(message) = cli.message {
-        run_single_message_mode(message, &mut agent, &formatter, stream).await?;
-    } else if cli.non_interactive {
-        run_non_interactive_mode(&mut agent, &formatter, stream).await?;
     } else {
+        if let Some(notice) = update::new_version_notice() {
+            app_println!("{} {}", "⬆".cyan(), notice);
+        }
         run_interactive_mode(
             _tui_guard,
             &mut agent,
@@ -332,6 +622,7 @@ async fn main() -> Result<()> {
             &formatter,
             stream,
             cli.plan_mode,
+            config_reloads,
         )
         .await?;
     }
@@ -342,6 +633,58 @@ async fn main() -> Result<()> {
     }
     database_manager.close().await;
 
+    if exit_code != headless::exit_code::SUCCESS {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+/// Reuse replies to identical requests in this run, when the response
+/// cache is on
+fn enable_response_cache(cli: &Cli, config: &Config, agent: &mut Agent) {
+    if let Some(cache) =
+        response_cache::ResponseCache::for_run(&config.response_cache, cli.no_cache, cli.cache_ttl)
+    {
+        debug!("Caching responses in {}", cache.dir().display());
+        agent.set_response_cache(cache);
+    }
+}
+
+/// Print the templates `flexorama new --template` can start from
+fn list_templates(config: &Config) {
+    let templates = templates::load_templates(&config.templates);
+    if templates.is_empty() {
+        app_println!(
+            "No templates yet. Add a [templates.<name>] table to the config or a <name>.md file to .flexorama/templates."
+        );
+        return;
+    }
+    for template in templates {
+        match &template.config.description {
+            Some(description) => app_println!("{}  {}", template.name.cyan(), description),
+            None => app_println!("{}", template.name.cyan()),
+        }
+    }
+}
+
+/// Set up the agent with a template's system prompt and skills. Its model
+/// is picked before the agent is created, and its context files once the
+/// usual ones are added.
+async fn apply_template_settings(
+    agent: &mut Agent,
+    template: &templates::ConversationTemplate,
+    use_system_prompt: bool,
+) -> Result<()> {
+    if let (true, Some(prompt)) = (use_system_prompt, &template.config.system_prompt) {
+        agent.set_system_prompt(prompt.clone());
+    }
+    if !template.config.skills.is_empty() {
+        agent
+            .set_conversation_skills(Some(template.config.skills.clone()))
+            .await
+            .map_err(|e| anyhow!("Template '{}': {}", template.name, e))?;
+    }
+    app_println!("{} Started from template '{}'", "✓".green(), template.name);
     Ok(())
 }
 
@@ -353,6 +696,7 @@ async fn run_web_mode(
     database_manager: Arc<DatabaseManager>,
     mcp_manager: Arc<McpManager>,
     skill_manager: Arc<AsyncMutex<skill::SkillManager>>,
+    config_reloads: Option<tokio::sync::mpsc::UnboundedReceiver<config_watch::ConfigReload>>,
 ) -> Result<()> {
     if cli.message.is_some() || cli.non_interactive {
         app_println!(
@@ -377,9 +721,22 @@ async fn run_web_mode(
         skill_manager,
         conversation_agents: Arc::new(AsyncMutex::new(HashMap::new())),
         csrf_manager: Arc::new(CsrfManager::new()),
-        config: Arc::new(config),
+        config: Arc::new(RwLock::new(config)),
+        started_at: std::time::Instant::now(),
     };
 
+    if let Some(mut reloads) = config_reloads {
+        let state = state.clone();
+        tokio::spawn(async move {
+            while let Some(reload) = reloads.recv().await {
+                for change in &reload.changes {
+                    info!("Config reloaded: {}", change.description);
+                }
+                web::apply_config_reload(&state, &reload).await;
+            }
+        });
+    }
+
     web::launch_web_ui(state, cli.web_port).await?;
     Ok(())
 }
@@ -401,25 +758,1305 @@ async fn run_acp_mode(agent: Agent, config: Config, model: String, debug: bool)
     Ok(())
 }
 
-/// Helper function to process and format a message with optional streaming
+/// Validate the configuration and exit, non-zero if anything is wrong
+fn run_config_check(path: Option<&str>, strict: bool) -> Result<()> {
+    let findings = config_check::check_config(path, &std::env::current_dir()?);
+    for finding in &findings {
+        match finding.severity {
+            config_check::Severity::Error => app_eprintln!("{}", finding.to_string().red()),
+            config_check::Severity::Warning => app_eprintln!("{}", finding.to_string().yellow()),
+        }
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == config_check::Severity::Error)
+        .count();
+    let warnings = findings.len() - errors;
+    if findings.is_empty() {
+        app_println!("{} Configuration is valid", "✓".green());
+    } else {
+        app_eprintln!("{} error(s), {} warning(s)", errors, warnings);
+    }
+
+    let failed = errors > 0 || (strict && warnings > 0);
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// Ask for one of `items` on the terminal
+async fn prompt_select(prompt: &str, items: Vec<String>, default: usize) -> Result<usize> {
+    let prompt = prompt.to_string();
+    Ok(tokio::task::spawn_blocking(move || {
+        dialoguer::Select::new()
+            .with_prompt(prompt)
+            .items(&items)
+            .default(default)
+            .interact()
+    })
+    .await??)
+}
+
+/// Walk through the provider, API key, default model and permissions, save
+/// them to the global config, and offer a starter AGENTS.md for the project
+async fn run_init_wizard(path: Option<&str>) -> Result<()> {
+    use init_wizard::PermissionProfile;
+    use std::io::IsTerminal;
+
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "flexorama init needs a terminal to ask questions in"
+        ));
+    }
+    let config_path = path
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(Config::default_config_path);
+    let mut config = Config::load(path).await?;
+    app_println!("{}", "Flexorama setup".bold());
+    app_println!("Settings are saved to {}", config_path.display());
+    app_println!();
+
+    let all_providers = providers::all();
+    let current = all_providers
+        .iter()
+        .position(|provider| *provider == config.provider)
+        .unwrap_or(0);
+    let names = all_providers.iter().map(|p| p.to_string()).collect();
+    let provider = all_providers[prompt_select("Provider", names, current).await?];
+    if provider != config.provider {
+        config.set_provider(provider);
+        config.api_key_secret = None;
+    }
+
+    let env_var = config::provider_api_key_env(provider);
+    if !config::provider_default_api_key(provider).is_empty() {
+        app_println!("{} Using the API key in {}", "✓".green(), env_var);
+    } else {
+        let optional = config::provider_api_key_optional(provider);
+        let prompt = if optional {
+            "API key, if the server needs one (stored in the keyring; empty to skip)"
+        } else {
+            "API key (stored in the keyring; empty to skip)"
+        };
+        let key = tokio::task::spawn_blocking(move || {
+            dialoguer::Password::new()
+                .with_prompt(prompt)
+                .allow_empty_password(true)
+                .interact()
+        })
+        .await??;
+        let key = key.trim();
+        if !key.is_empty() {
+            let account = init_wizard::keyring_account(provider);
+            match secrets::store_keyring_secret(&account, key) {
+                Ok(()) => {
+                    config.api_key_secret = Some(format!("keyring:{}", account));
+                    app_println!("{} Stored the API key in the keyring", "✓".green());
+                }
+                Err(e) => app_eprintln!(
+                    "{} Could not store the API key in the keyring ({}); set {} instead",
+                    "!".yellow(),
+                    e,
+                    env_var
+                ),
+            }
+        } else if !optional && config.api_key_secret.is_none() {
+            app_println!(
+                "{} No API key saved; set {} before starting Flexorama",
+                "!".yellow(),
+                env_var
+            );
+        }
+    }
+
+    let mut models = config::provider_models(provider);
+    if !models.contains(&config.default_model) {
+        models.insert(0, config.default_model.clone());
+    }
+    let current = models
+        .iter()
+        .position(|model| *model == config.default_model)
+        .unwrap_or(0);
+    let mut items = models.clone();
+    items.push("Other...".to_string());
+    let index = prompt_select("Default model", items, current).await?;
+    if let Some(model) = models.get(index) {
+        config.default_model = model.clone();
+    } else {
+        let model = tokio::task::spawn_blocking(|| {
+            dialoguer::Input::<String>::new()
+                .with_prompt("Model name")
+                .interact_text()
+        })
+        .await??;
+        if !model.trim().is_empty() {
+            config.default_model = model.trim().to_string();
+        }
+    }
+
+    let current = PermissionProfile::of(&config)
+        .and_then(|profile| PermissionProfile::ALL.iter().position(|p| *p == profile))
+        .unwrap_or(1);
+    let labels = PermissionProfile::ALL
+        .iter()
+        .map(|profile| profile.to_string())
+        .collect();
+    let index = prompt_select("Permissions", labels, current).await?;
+    PermissionProfile::ALL[index].apply(&mut config);
+
+    config.save(path).await?;
+    let errors: Vec<_> = config_check::check_config(path, &std::env::current_dir()?)
+        .into_iter()
+        .filter(|finding| finding.severity == config_check::Severity::Error)
+        .collect();
+    for finding in &errors {
+        app_eprintln!("{}", finding.to_string().red());
+    }
+    if errors.is_empty() {
+        app_println!("{} Saved to {}", "✓".green(), config_path.display());
+    } else {
+        app_eprintln!(
+            "Saved to {}, but fix the errors above before starting",
+            config_path.display()
+        );
+    }
+
+    let agents_md = std::path::Path::new("AGENTS.md");
+    if agents_md.exists() {
+        app_println!("AGENTS.md already exists; left it as it is");
+        return Ok(());
+    }
+    let generate = tokio::task::spawn_blocking(|| {
+        dialoguer::Confirm::new()
+            .with_prompt("Generate a starter AGENTS.md for this project?")
+            .default(true)
+            .interact()
+    })
+    .await??;
+    if generate {
+        let scan = init_wizard::scan_project(&std::env::current_dir()?);
+        tokio::fs::write(agents_md, init_wizard::render_agents_md(&scan)).await?;
+        app_println!(
+            "{} Wrote AGENTS.md; fill in anything the scan missed",
+            "✓".green()
+        );
+    }
+    Ok(())
+}
+
+/// Import another tool's settings into the global config
+async fn run_import_config(
+    path: Option<&str>,
+    tool: config_import::ImportTool,
+    dry_run: bool,
+) -> Result<()> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    let project = std::env::current_dir()?;
+    let plan = config_import::plan_import(tool, &home, &project).await?;
+    if plan.is_empty() {
+        app_println!("No {} settings found to import", tool);
+        for note in &plan.notes {
+            app_println!("  {} {}", "!".yellow(), note);
+        }
+        return Ok(());
+    }
+
+    app_println!("Importing from {}:", tool);
+    if let Some(provider) = plan.provider {
+        app_println!("  Provider: {}", provider);
+    }
+    if let Some(model) = &plan.model {
+        app_println!("  Model: {}", model);
+    }
+    if let Some(base_url) = &plan.base_url {
+        app_println!("  Base URL: {}", base_url);
+    }
+    for pattern in &plan.allowed_commands {
+        app_println!("  Allow bash: {}", pattern);
+    }
+    for pattern in &plan.denied_commands {
+        app_println!("  Deny bash: {}", pattern);
+    }
+    for (name, _) in &plan.mcp_servers {
+        app_println!("  MCP server: {}", name);
+    }
+    for command in &plan.commands {
+        app_println!("  Command: /{}", command.name);
+    }
+    for (source, destination) in &plan.instructions {
+        app_println!("  {} → {}", source.display(), destination.display());
+    }
+    for note in &plan.notes {
+        app_println!("  {} {}", "!".yellow(), note);
+    }
+
+    if dry_run {
+        app_println!("Dry run; nothing was written");
+        return Ok(());
+    }
+
+    let mut config = Config::load(path).await?;
+    if let Some(dir) = &config.commands_dir {
+        custom_commands::set_commands_dir(dir.clone());
+    }
+    let summary = plan.apply_to_config(&mut config);
+    config.save(path).await?;
+    for name in &summary.skipped {
+        app_println!("Skipped MCP server '{}' (already configured)", name);
+    }
+    for line in plan.write_files().await? {
+        app_println!("{}", line);
+    }
+    app_println!(
+        "{} Saved to {}",
+        "✓".green(),
+        path.map(std::path::PathBuf::from)
+            .unwrap_or_else(Config::default_config_path)
+            .display()
+    );
+    Ok(())
+}
+
+/// Run every prompt in a batch file in its own conversation
+type AgentServices = (
+    Arc<DatabaseManager>,
+    Arc<McpManager>,
+    Arc<AsyncMutex<skill::SkillManager>>,
+);
+
+/// How to set up the fresh, unattended agents that batch items and scheduled
+/// commands each run in: like the one this run would have used, with any
+/// permission request denied
+struct HeadlessAgentTemplate<'a> {
+    cli: &'a Cli,
+    config: &'a Config,
+    model: String,
+    plan_mode: bool,
+    system_prompt: Option<String>,
+    tool_filter: tool_filter::ToolFilter,
+    /// Set for batch runs when the response cache is on
+    response_cache: Option<response_cache::ResponseCache>,
+}
+
+impl<'a> HeadlessAgentTemplate<'a> {
+    fn new(cli: &'a Cli, template: &Agent, config: &'a Config) -> Result<Self> {
+        Ok(Self {
+            cli,
+            config,
+            model: template.model().to_string(),
+            plan_mode: template.plan_mode(),
+            system_prompt: template.get_system_prompt().cloned(),
+            tool_filter: tool_filter::ToolFilter::from_args(
+                &cli.allowed_tools,
+                &cli.disallowed_tools,
+            )?,
+            response_cache: None,
+        })
+    }
+
+    async fn create(
+        &self,
+        database: Arc<DatabaseManager>,
+        mcp_manager: Arc<McpManager>,
+        skill_manager: Arc<AsyncMutex<skill::SkillManager>>,
+    ) -> Result<Agent> {
+        let services = (database, mcp_manager, skill_manager);
+        self.create_with(self.config.clone(), self.model.clone(), services)
+            .await
+    }
+
+    /// Like [`Self::create`], for another provider or model
+    async fn create_with(
+        &self,
+        config: Config,
+        model: String,
+        (database, mcp_manager, skill_manager): AgentServices,
+    ) -> Result<Agent> {
+        let mut agent = Agent::new_with_plan_mode(config, model, self.cli.yolo, self.plan_mode)
+            .await
+            .with_mcp_manager(mcp_manager)
+            .with_database_manager(database)
+            .with_skill_manager(skill_manager);
+        agent.set_suppress_output(true);
+        agent.set_tool_filter(self.tool_filter.clone());
+        if let Some(max_turns) = self.cli.max_turns {
+            agent.set_max_turns(max_turns);
+        }
+        if let Some(cache) = &self.response_cache {
+            agent.set_response_cache(cache.clone());
+        }
+        agent
+            .set_permission_handler(Some(headless::warn_and_deny_permission_handler()))
+            .await;
+        if let Some(prompt) = self.system_prompt.clone() {
+            agent.set_system_prompt(prompt);
+        }
+        if let Err(e) = agent.force_refresh_mcp_tools().await {
+            warn!("Failed to load MCP tools for headless agent: {}", e);
+        }
+        add_context_files(&mut agent, &self.cli.context_files, true).await?;
+        agent.start_new_conversation().await?;
+        Ok(agent)
+    }
+}
+
+async fn run_batch_mode(
+    cli: &Cli,
+    template: &Agent,
+    config: &Config,
+    (database, mcp_manager, skill_manager): AgentServices,
+    file: &std::path::Path,
+    output_dir: &std::path::Path,
+    concurrency: usize,
+) -> Result<()> {
+    let content = tokio::fs::read_to_string(file)
+        .await
+        .map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let items = batch::parse_batch_file(file, &content)?;
+    app_println!(
+        "Running {} prompts, {} at a time, into {}",
+        items.len(),
+        concurrency.max(1),
+        output_dir.display()
+    );
+
+    // Each item gets a fresh agent set up like the one this run would have used
+    let mut template = HeadlessAgentTemplate::new(cli, template, config)?;
+    template.response_cache =
+        response_cache::ResponseCache::for_run(&config.response_cache, cli.no_cache, cli.cache_ttl);
+    let create_agent =
+        || template.create(database.clone(), mcp_manager.clone(), skill_manager.clone());
+
+    let total = items.len();
+    let finished = std::cell::Cell::new(0);
+    let reports = batch::run_batch(&items, output_dir, concurrency, create_agent, |report| {
+        finished.set(finished.get() + 1);
+        let progress = format!("[{}/{}]", finished.get(), total);
+        match &report.error {
+            None => app_println!(
+                "{} {} {} ({} tokens, {:.1}s)",
+                "✓".green(),
+                progress,
+                report.output.display(),
+                report.usage.total_tokens,
+                report.duration_ms as f64 / 1000.0
+            ),
+            Some(error) => app_println!("{} {} {}: {}", "✗".red(), progress, report.prompt, error),
+        }
+    })
+    .await?;
+
+    let failed = reports
+        .iter()
+        .filter(|r| r.status != RunStatus::Success)
+        .count();
+    let tokens: u32 = reports.iter().map(|r| r.usage.total_tokens).sum();
+    let cost: Option<f64> = reports.iter().map(|r| r.cost_usd).sum();
+    app_println!();
+    app_println!(
+        "{} succeeded, {} failed, {} tokens{}",
+        total - failed,
+        failed,
+        tokens,
+        cost.map(|c| format!(", ~${:.4}", c)).unwrap_or_default()
+    );
+    app_println!("Summary: {}", output_dir.join("summary.json").display());
+    if failed > 0 {
+        return Err(anyhow!("{} of {} prompts failed", failed, total));
+    }
+    Ok(())
+}
+
+/// Run a `conversations` subcommand against the conversation database
+async fn run_eval_mode(
+    cli: &Cli,
+    template: &Agent,
+    config: &Config,
+    (database, mcp_manager, skill_manager): AgentServices,
+    file: &std::path::Path,
+    models: &[String],
+    output_dir: &std::path::Path,
+) -> Result<()> {
+    let content = tokio::fs::read_to_string(file)
+        .await
+        .map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let tasks = eval::parse_eval_file(file, &content)?;
+    let targets: Vec<eval::ModelTarget> = if models.is_empty() {
+        vec![eval::ModelTarget::parse(template.model())]
+    } else {
+        models
+            .iter()
+            .map(|spec| eval::ModelTarget::parse(spec))
+            .collect()
+    };
+    app_println!(
+        "Running {} tasks on {} into {}",
+        tasks.len(),
+        targets
+            .iter()
+            .map(|target| target.label())
+            .collect::<Vec<_>>()
+            .join(", "),
+        output_dir.display()
+    );
+    if !cli.yolo {
+        app_println!(
+            "{} Without --yolo, commands and edits that need permission are denied",
+            "💡".yellow()
+        );
+    }
+
+    let template = HeadlessAgentTemplate::new(cli, template, config)?;
+    let create_agent = |target: &eval::ModelTarget| {
+        let mut config = config.clone();
+        if let Some(provider) = target.provider {
+            config.set_provider(provider);
+        }
+        let services = (database.clone(), mcp_manager.clone(), skill_manager.clone());
+        template.create_with(config, target.model.clone(), services)
+    };
+
+    let total = tasks.len() * targets.len();
+    let finished = std::cell::Cell::new(0);
+    let results = eval::run_eval(&tasks, &targets, output_dir, create_agent, |result| {
+        finished.set(finished.get() + 1);
+        let progress = format!("[{}/{}]", finished.get(), total);
+        let mark = if result.passed {
+            "✓".green()
+        } else {
+            "✗".red()
+        };
+        match &result.error {
+            None => app_println!(
+                "{} {} {} on {} ({} tokens, {:.1}s)",
+                mark,
+                progress,
+                result.task,
+                result.model,
+                result.usage.total_tokens,
+                result.duration_ms as f64 / 1000.0
+            ),
+            Some(error) => app_println!(
+                "{} {} {} on {}: {}",
+                mark,
+                progress,
+                result.task,
+                result.model,
+                error
+            ),
+        }
+    })
+    .await?;
+
+    app_println!();
+    for summary in eval::summarize(&results) {
+        app_println!(
+            "{}: {}/{} passed ({:.0}%), {} tokens, {}",
+            summary.model.bold(),
+            summary.passed,
+            summary.total,
+            summary.pass_rate() * 100.0,
+            summary.total_tokens,
+            summary
+                .cost_usd
+                .map(|cost| format!("${:.4}", cost))
+                .unwrap_or_else(|| "cost unknown".to_string())
+        );
+    }
+    app_println!(
+        "Report written to {}",
+        output_dir.join("report.md").display()
+    );
+    Ok(())
+}
+
+/// Run a `schedule` subcommand other than `run`
+async fn run_schedule_command(action: &ScheduleCommand) -> Result<()> {
+    let path = schedule::schedules_path()?;
+    match action {
+        ScheduleCommand::Add { cron, command } => {
+            let scheduled = schedule::ScheduledCommand::new(
+                cron,
+                &command.join(" "),
+                std::env::current_dir()?,
+            )?;
+            let name = command[0].trim_start_matches('/');
+            if custom_commands::load_custom_command(name).await?.is_none() {
+                return Err(anyhow!("No custom command /{} here", name));
+            }
+            let mut schedules = schedule::load_schedules(&path).await?;
+            schedules.push(scheduled.clone());
+            schedule::save_schedules(&path, &schedules).await?;
+            app_println!(
+                "{} Scheduled {} ({}) as {}",
+                "✓".green(),
+                scheduled.command.cyan(),
+                scheduled.cron,
+                scheduled.id
+            );
+            if let Some(next) = scheduled.next_run() {
+                app_println!("Next run: {}", next.format("%Y-%m-%d %H:%M"));
+            }
+            app_println!("Scheduled commands run while `flexorama schedule run` is running");
+        }
+        ScheduleCommand::List => {
+            let schedules = schedule::load_schedules(&path).await?;
+            if schedules.is_empty() {
+                app_println!("No scheduled commands");
+                return Ok(());
+            }
+            for scheduled in schedules {
+                let next = scheduled
+                    .next_run()
+                    .map(|next| next.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "never".to_string());
+                app_println!(
+                    "{}  {:<14} {}  next {}",
+                    scheduled.id.dimmed(),
+                    scheduled.cron,
+                    scheduled.command.cyan(),
+                    next
+                );
+                app_println!("          in {}", scheduled.working_dir.display());
+                if let Some(last_run) = scheduled.last_run {
+                    let last_run = last_run
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M");
+                    match (&scheduled.last_error, &scheduled.last_conversation_id) {
+                        (Some(error), _) => {
+                            app_println!(
+                                "          last run {} {}: {}",
+                                last_run,
+                                "failed".red(),
+                                error
+                            )
+                        }
+                        (None, Some(id)) => {
+                            app_println!("          last run {}, conversation {}", last_run, id)
+                        }
+                        (None, None) => app_println!("          last run {}", last_run),
+                    }
+                }
+            }
+        }
+        ScheduleCommand::Remove { id } => {
+            let removed = schedule::remove_schedule(&path, id).await?;
+            app_println!(
+                "{} Removed {} ({})",
+                "✓".green(),
+                removed.command.cyan(),
+                removed.cron
+            );
+        }
+        ScheduleCommand::Run { .. } => unreachable!("the scheduler runs with a full agent"),
+    }
+    Ok(())
+}
+
+/// Run scheduled commands as they come due, checking once a minute. Each
+/// runs in its own conversation so its output can be read back later.
+async fn run_scheduler(
+    cli: &Cli,
+    template: &Agent,
+    config: &Config,
+    (database, mcp_manager, skill_manager): AgentServices,
+    once: bool,
+) -> Result<()> {
+    use chrono::Timelike;
+
+    let path = schedule::schedules_path()?;
+    let template = HeadlessAgentTemplate::new(cli, template, config)?;
+    if !once {
+        app_println!(
+            "Running scheduled commands from {} (Ctrl+C to stop)",
+            path.display()
+        );
+    }
+    loop {
+        let now = chrono::Local::now();
+        // Re-read every time so schedules added or removed meanwhile apply
+        for scheduled in schedule::load_schedules(&path).await? {
+            if !scheduled.is_due(now) {
+                continue;
+            }
+            let started = chrono::Utc::now();
+            let outcome = run_scheduled_command(
+                &template,
+                &scheduled,
+                (database.clone(), mcp_manager.clone(), skill_manager.clone()),
+            )
+            .await;
+            let (conversation_id, error) = match outcome {
+                Ok(conversation_id) => {
+                    app_println!(
+                        "{} {} {} → conversation {}",
+                        "✓".green(),
+                        now.format("%Y-%m-%d %H:%M"),
+                        scheduled.command,
+                        conversation_id.as_deref().unwrap_or("(not saved)")
+                    );
+                    (conversation_id, None)
+                }
+                Err(e) => {
+                    app_println!(
+                        "{} {} {}: {:#}",
+                        "✗".red(),
+                        now.format("%Y-%m-%d %H:%M"),
+                        scheduled.command,
+                        e
+                    );
+                    (None, Some(format!("{:#}", e)))
+                }
+            };
+            schedule::record_run(&path, &scheduled.id, started, conversation_id, error).await?;
+        }
+        if once {
+            return Ok(());
+        }
+
+        let wait = 60 - u64::from(chrono::Local::now().second()).min(59);
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_secs(wait)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Run one scheduled command in its working directory, returning the ID of
+/// the conversation holding its output
+async fn run_scheduled_command(
+    template: &HeadlessAgentTemplate<'_>,
+    scheduled: &schedule::ScheduledCommand,
+    (database, mcp_manager, skill_manager): AgentServices,
+) -> Result<Option<String>> {
+    // Commands run one at a time, so the process directory can follow them
+    std::env::set_current_dir(&scheduled.working_dir)
+        .map_err(|e| anyhow!("Can't run in {}: {}", scheduled.working_dir.display(), e))?;
+    let mut agent = template
+        .create(database, mcp_manager, skill_manager)
+        .await?;
+    let mut rendered = custom_commands::render_custom_command_input(&scheduled.command)
+        .await?
+        .ok_or_else(|| anyhow!("No custom command {}", scheduled.command))?;
+    rendered
+        .expand(
+            &agent.get_bash_security_manager(),
+            agent.yolo_mode(),
+            agent.conversation_env(),
+        )
+        .await?;
+    if let Some(model) = rendered.command.model.clone() {
+        agent.set_model(model).await?;
+    }
+    agent
+        .process_message_with_stream(
+            &rendered.message,
+            None,
+            None,
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await?;
+    Ok(agent.current_conversation_id())
+}
+
+async fn run_conversations_command(action: ConversationsCommand) -> Result<()> {
+    let db = DatabaseManager::new(get_database_path()?).await?;
+    let result = match action {
+        ConversationsCommand::List { limit, search } => {
+            list_conversations(&db, limit, search.as_deref()).await
+        }
+        ConversationsCommand::Show { id } => {
+            let id = resolve_conversation_id(&db, &id).await?;
+            let transcript = conversation_export::ConversationTranscript::load(&db, &id).await?;
+            app_print!("{}", transcript.to_markdown());
+            Ok(())
+        }
+        ConversationsCommand::Delete { id } => {
+            let id = resolve_conversation_id(&db, &id).await?;
+            db.delete_conversation(&id).await?;
+            app_println!("{} Deleted conversation {}", "✓".green(), id);
+            Ok(())
+        }
+        ConversationsCommand::Export { id, format, output } => {
+            let id = resolve_conversation_id(&db, &id).await?;
+            let transcript = conversation_export::ConversationTranscript::load(&db, &id).await?;
+            let rendered = transcript.render(format);
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, rendered).await?;
+                    app_eprintln!("{} Exported {} to {}", "✓".green(), id, path.display());
+                }
+                None => app_print!("{}", rendered),
+            }
+            Ok(())
+        }
+    };
+    db.close().await;
+    result
+}
+
+async fn list_conversations(db: &DatabaseManager, limit: i64, search: Option<&str>) -> Result<()> {
+    let conversations = db.get_recent_conversations(limit, search).await?;
+    if conversations.is_empty() {
+        app_println!("No conversations found");
+        return Ok(());
+    }
+    for conversation in conversations {
+        let messages = db.get_conversation_messages(&conversation.id).await?;
+        let first_line = messages
+            .iter()
+            .find(|m| m.role == "user" && !m.content.trim().is_empty())
+            .and_then(|m| m.content.lines().find(|line| !line.trim().is_empty()))
+            .unwrap_or("")
+            .trim();
+        let preview: String = first_line.chars().take(60).collect();
+        let preview = match &conversation.name {
+            Some(name) => format!("[{}] {}", name.bold(), preview),
+            None => preview,
+        };
+        app_println!(
+            "{}  {}  {:>3} msgs  {}  {}",
+            conversation.id.cyan(),
+            conversation
+                .updated_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M"),
+            messages.len(),
+            conversation.model.dimmed(),
+            preview
+        );
+    }
+    Ok(())
+}
+
+/// If the last interactive session here crashed, offer to pick it back up.
+/// Returns true if it was recovered.
+async fn offer_crash_recovery(tui: &tui::Tui, agent: &mut Agent) -> bool {
+    let Ok(crashes_dir) = crash::crashes_dir() else {
+        return false;
+    };
+    let Some(report) = std::env::current_dir()
+        .ok()
+        .and_then(|dir| crash::find_recoverable(&crashes_dir, &dir))
+    else {
+        return false;
+    };
+    let pending = report
+        .session
+        .as_ref()
+        .map(|session| session.pending_tools.len())
+        .unwrap_or(0);
+    let crashed_at = report
+        .crashed_at
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M");
+    let prompt = tui::SelectionPrompt {
+        title: format!(
+            "Flexorama crashed at {} ({}). Diagnostics are in {}",
+            crashed_at,
+            report.panic,
+            report.dir.display()
+        ),
+        options: vec![
+            format!(
+                "Recover last crashed session ({} tool calls in progress)",
+                pending
+            ),
+            "Start a new conversation".to_string(),
+        ],
+    };
+    if tui.select_option(&prompt) != Some(0) {
+        if let Err(e) = report.dismiss() {
+            warn!("{:#}", e);
+        }
+        return false;
+    }
+    match report.recover(agent).await {
+        Ok(()) => {
+            app_println!(
+                "{} Recovered conversation {} ({} messages loaded)",
+                "✓".green(),
+                report.conversation_id().unwrap_or_default(),
+                agent.conversation_len()
+            );
+            true
+        }
+        Err(e) => {
+            app_println!(
+                "{} Couldn't recover the crashed session: {:#}",
+                "✗".red(),
+                e
+            );
+            false
+        }
+    }
+}
+
+/// Ask before starting the MCP servers of a project config the user hasn't
+/// trusted yet: they run commands from whatever repository is checked out.
+/// Without a terminal to ask in they're left out.
+fn confirm_project_mcp_servers(
+    config: &mut Config,
+    sources: &mut project_config::ConfigSources,
+    tui: Option<&tui::Tui>,
+) {
+    let Some(untrusted) = sources.untrusted_project() else {
+        return;
+    };
+    let Some(tui) = tui else {
+        warn!(
+            "Not starting MCP servers from {}: run flexorama interactively in this project to trust it",
+            untrusted.path.display()
+        );
+        return;
+    };
+
+    app_println!(
+        "{} {} defines MCP servers that would run on this machine:",
+        "⚠".yellow().bold(),
+        untrusted.path.display()
+    );
+    for (name, server) in &untrusted.servers {
+        let target = match (&server.command, &server.url) {
+            (Some(command), _) => std::iter::once(command.as_str())
+                .chain(server.args.iter().flatten().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" "),
+            (None, Some(url)) => url.clone(),
+            (None, None) => String::new(),
+        };
+        app_println!("  {}: {}", name.cyan(), target);
+        if server.sampling == Some(config::McpSamplingPolicy::Allow) {
+            app_println!("    may call the model without asking (sampling = \"allow\")");
+        }
+    }
+    let prompt = tui::SelectionPrompt {
+        title: "Start these servers?".to_string(),
+        options: vec![
+            "Trust this file and start them".to_string(),
+            "Don't start them".to_string(),
+        ],
+    };
+    if tui.select_option(&prompt) != Some(0) {
+        app_println!("Project MCP servers not started");
+        return;
+    }
+    match project_config::trust_project(config, sources) {
+        Ok(()) => app_println!("{} Trusted; changing the file will ask again", "✓".green()),
+        Err(e) => app_eprintln!("{} Failed to trust project config: {}", "⚠".yellow(), e),
+    }
+}
+
+/// Connect to the enabled MCP servers in the background, showing progress
+/// and the outcome in the TUI's status bar
+fn spawn_mcp_connect(
+    mcp_manager: Arc<McpManager>,
+    tui: Option<Arc<tui::Tui>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let set_status = |status: Option<&str>| {
+            if let Some(tui) = &tui {
+                let _ = tui.set_status(status);
+            }
+        };
+        info!("Connecting to MCP servers...");
+        set_status(Some("Connecting to MCP servers..."));
+        let mcp_connect_result = tokio::time::timeout(
+            std::time::Duration::from_secs(30),
+            mcp_manager.connect_all_enabled(),
+        )
+        .await;
+
+        let status = match mcp_connect_result {
+            Ok(Ok(summary)) => {
+                info!("MCP servers connected successfully");
+                let servers = summary.connected + summary.deferred + summary.failed.len();
+                (servers > 0).then(|| summary.describe())
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to connect to MCP servers: {}", e);
+                error!("MCP Server Connection Issues:");
+                error!("  - Check that MCP servers are configured correctly: /mcp list");
+                error!("  - Verify server commands/URLs are valid");
+                error!("  - Ensure all dependencies are installed");
+                error!("  - Use '/mcp test <command>' to verify command availability");
+                error!("  - Tool calls to unavailable MCP servers will fail");
+                Some("MCP: connection failed".to_string())
+            }
+            Err(_) => {
+                warn!("MCP server connection timed out after 30 seconds");
+                error!("MCP Server Connection Timeout:");
+                error!("  - MCP servers are taking too long to respond");
+                error!("  - Check if servers are running and accessible");
+                error!("  - Use '/mcp reconnect <server>' to try connecting manually");
+                Some("MCP: connection timed out".to_string())
+            }
+        };
+
+        // Leave the outcome up long enough to be noticed
+        set_status(status.as_deref());
+        if status.is_some() && tui.is_some() {
+            tokio::time::sleep(std::time::Duration::from_secs(8)).await;
+            set_status(None);
+        }
+    })
+}
+
+/// Reopen the conversation picked by --continue or --resume. Returns false
+/// if a new conversation should be started instead.
+async fn resume_requested_conversation(
+    cli: &Cli,
+    agent: &mut Agent,
+    db: &DatabaseManager,
+) -> Result<bool> {
+    let id = if let Some(id) = &cli.resume {
+        resolve_conversation_id(db, id).await?
+    } else if cli.continue_conversation {
+        match db
+            .get_recent_conversations(1, None)
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(conversation) => conversation.id,
+            None => {
+                app_println!(
+                    "{} No earlier conversation in this directory; starting a new one",
+                    "💡".yellow()
+                );
+                return Ok(false);
+            }
+        }
+    } else {
+        return Ok(false);
+    };
+
+    if cli.read_only {
+        agent.open_conversation_read_only(&id).await?;
+    } else if let Err(error) = agent.resume_conversation(&id).await {
+        if error
+            .downcast_ref::<conversation_lock::ConversationInUse>()
+            .is_some()
+        {
+            return Err(anyhow!(
+                "{}. Pass --read-only to open it without writing to it.",
+                error
+            ));
+        }
+        return Err(error);
+    }
+    if !cli.acp {
+        let opened = if cli.read_only {
+            format!("Opened conversation {} read-only", id)
+        } else {
+            format!("Resumed conversation {}", id)
+        };
+        app_println!(
+            "{} {} ({} messages loaded)",
+            "✓".green(),
+            opened,
+            agent.conversation_len()
+        );
+    }
+    Ok(true)
+}
+
+/// Apply --session-name to the conversation this run uses
+async fn name_current_conversation(agent: &Agent, db: &DatabaseManager, name: &str) -> Result<()> {
+    let conversation_id = agent
+        .current_conversation_id()
+        .ok_or_else(|| anyhow!("No conversation to name"))?;
+    db.set_conversation_name(&conversation_id, Some(name))
+        .await
+        .map_err(|e| anyhow!("--session-name: {}", e))
+}
+
+/// Find the conversation an ID, name or unique ID prefix refers to
+async fn resolve_conversation_id(db: &DatabaseManager, id: &str) -> Result<String> {
+    let matches = db.find_conversation_ids(id).await?;
+    if matches.iter().any(|m| m == id) {
+        return Ok(id.to_string());
+    }
+    if let Some(named) = db.find_conversation_by_name(id).await? {
+        return Ok(named);
+    }
+    match matches.as_slice() {
+        [] => Err(anyhow!("No conversation matches '{}'", id)),
+        [only] => Ok(only.clone()),
+        _ => Err(anyhow!(
+            "'{}' matches {} conversations; use more of the ID",
+            id,
+            matches.len()
+        )),
+    }
+}
+
+/// Check for a newer release and install it unless `check` is set
+async fn run_update_command(check: bool) -> Result<()> {
+    app_println!("Current version: {}", update::CURRENT_VERSION);
+    match update::run_update(check).await? {
+        update::UpdateOutcome::UpToDate => {
+            app_println!("{} Flexorama is up to date", "✓".green());
+        }
+        update::UpdateOutcome::Available(release) => {
+            app_println!(
+                "Version {} is available: {}",
+                release.version().cyan(),
+                release.html_url
+            );
+            app_println!("Run `flexorama update` to install it");
+        }
+        update::UpdateOutcome::Installed { release, path } => {
+            app_println!(
+                "{} Installed {} at {} (checksum verified)",
+                "✓".green(),
+                release.version().cyan(),
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Run a `skill` or `agent` subcommand: export a bundle or import one
+async fn run_bundle_command(kind: bundle::BundleKind, action: &BundleCommand) -> Result<()> {
+    match action {
+        BundleCommand::Export { name, output } => {
+            let path = bundle::find_installed(kind, name)?
+                .ok_or_else(|| anyhow!("No {} named '{}'", kind.label(), name))?;
+            let exported = match kind {
+                bundle::BundleKind::Skill => bundle::export_skill(&path, name).await?,
+                bundle::BundleKind::Agent => bundle::export_agent(&path, name).await?,
+            };
+            let output = output
+                .clone()
+                .unwrap_or_else(|| std::path::PathBuf::from(exported.file_name()));
+            tokio::fs::write(&output, exported.to_json()?).await?;
+            app_println!(
+                "{} Exported {} '{}' ({} files) to {}",
+                "✓".green(),
+                kind.label(),
+                name,
+                exported.files.len(),
+                output.display()
+            );
+        }
+        BundleCommand::Import {
+            source,
+            project,
+            force,
+        } => {
+            let imported = bundle::read_source(source, kind).await?;
+            let root = if *project {
+                bundle::project_dir(kind)?
+            } else {
+                bundle::global_dir(kind)?
+            };
+            let path = bundle::install(&imported, &root, *force).await?;
+            app_println!(
+                "{} Imported {} '{}' to {}",
+                "✓".green(),
+                kind.label(),
+                imported.name,
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Run a `profile` subcommand: export the global setup or import one
+async fn run_profile_command(config_path: Option<&str>, action: &ProfileCommand) -> Result<()> {
+    let paths = profile::ProfilePaths::global(config_path)?;
+    match action {
+        ProfileCommand::Export { file } => {
+            let (exported, redacted) = profile::export(&paths).await?;
+            if exported.files.is_empty() {
+                return Err(anyhow!(
+                    "Nothing to export: no config, subagents, skills, commands or hooks found"
+                ));
+            }
+            tokio::fs::write(file, exported.to_json()?).await?;
+            app_println!(
+                "{} Exported {} files to {}",
+                "✓".green(),
+                exported.files.len(),
+                file.display()
+            );
+            if redacted > 0 {
+                app_println!(
+                    "{} Replaced {} secrets with {}; fill them in after importing",
+                    "💡".yellow(),
+                    redacted,
+                    share::REDACTED
+                );
+            }
+        }
+        ProfileCommand::Import { file, force } => {
+            let imported = profile::read(file).await?;
+            let summary = profile::import(&imported, &paths, *force).await?;
+            app_println!(
+                "{} Imported {} files from {}",
+                "✓".green(),
+                summary.written.len(),
+                file.display()
+            );
+            if !summary.skipped.is_empty() {
+                app_println!(
+                    "{} Kept {} existing files (use --force to replace them):",
+                    "💡".yellow(),
+                    summary.skipped.len()
+                );
+                for path in &summary.skipped {
+                    app_println!("  {}", path.display());
+                }
+            }
+            for path in &summary.redacted {
+                app_println!(
+                    "{} {} has {} values to fill in",
+                    "⚠".yellow(),
+                    path.display(),
+                    share::REDACTED
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run `install`: fetch a pack, show what it contains and install it once confirmed
+async fn run_install_command(
+    source: &str,
+    registry_url: Option<&str>,
+    project: bool,
+    yes: bool,
+) -> Result<()> {
+    let resolved = packs::resolve_source(source, registry_url)?;
+    let local = std::path::Path::new(&resolved).is_dir();
+    let root = if local {
+        std::path::PathBuf::from(&resolved)
+    } else {
+        app_println!("Fetching {}...", resolved);
+        bundle::clone_repository(&resolved).await?
+    };
+    let result = install_pack(&root, &resolved, project, yes).await;
+    if !local {
+        let _ = tokio::fs::remove_dir_all(&root).await;
+    }
+    result
+}
+
+async fn install_pack(
+    root: &std::path::Path,
+    source: &str,
+    project: bool,
+    yes: bool,
+) -> Result<()> {
+    let dirs = if project {
+        packs::InstallDirs::project()?
+    } else {
+        packs::InstallDirs::global()?
+    };
+    let items = packs::plan(root, &dirs).await?;
+
+    app_println!("{} will install:", source.cyan());
+    for item in &items {
+        let status = if item.replaces {
+            "replaces".yellow()
+        } else {
+            "new".green()
+        };
+        app_println!(
+            "  {:<8} {:<24} {} → {}",
+            item.kind.label(),
+            item.name.bold(),
+            status,
+            item.target.display()
+        );
+        for runs in &item.runs {
+            let label = match item.kind {
+                packs::PackItemKind::Skill => "script",
+                _ => "runs",
+            };
+            app_println!("           {} {}", format!("{}:", label).red(), runs);
+        }
+    }
+    if items.iter().any(|item| !item.runs.is_empty()) {
+        app_println!(
+            "{} Commands run shell snippets when used, and skill scripts run without asking once the skill is loaded",
+            "Warning:".yellow()
+        );
+    }
+
+    if !yes {
+        use std::io::IsTerminal;
+        if !io::stdin().is_terminal() {
+            return Err(anyhow!(
+                "Pass --yes to install without a terminal to confirm in"
+            ));
+        }
+        let confirmed = tokio::task::spawn_blocking(|| {
+            dialoguer::Confirm::new()
+                .with_prompt("Install?")
+                .default(false)
+                .interact()
+        })
+        .await??;
+        if !confirmed {
+            app_println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    packs::install(&items).await?;
+    app_println!("{} Installed {} items", "✓".green(), items.len());
+    Ok(())
+}
+
+/// Run MCP server mode, exposing the built-in tools to other MCP clients
+async fn run_mcp_serve_mode(config: Config, yolo: bool, http: bool, port: u16) -> Result<()> {
+    // Stdout carries the protocol on stdio, so warnings go to the log (stderr)
+    if yolo {
+        warn!("YOLO mode enabled: MCP clients can run any command and edit any file");
+    }
+
+    let server = mcp_server::McpToolServer::new(&config, yolo);
+    if http {
+        server.run_http(port).await
+    } else {
+        server.run_stdio().await
+    }
+}
+
+/// Helper function to process and format a message with optional streaming.
+/// Returns the process exit code for the run.
 async fn run_message_with_formatting(
     message: &str,
     agent: &mut Agent,
     formatter: &formatter::CodeFormatter,
     stream: bool,
-) -> Result<()> {
+    output_format: OutputFormat,
+    verbose: bool,
+) -> Result<i32> {
+    if output_format.is_json() {
+        return run_message_as_json(message, agent, output_format, stream, verbose).await;
+    }
+
     let highlighted_message = formatter.format_input_with_file_highlighting(message);
     app_println!("> {}", highlighted_message);
 
     let cancellation_flag = Arc::new(AtomicBool::new(false));
+    let tool_calls = ToolCallCollector::new().with_trace(verbose);
 
-    if stream {
+    let response = if stream {
         let (streaming_state, stream_callback) = create_streaming_renderer(formatter);
         let response = agent
             .process_message_with_stream(
                 message,
                 Some(Arc::clone(&stream_callback)),
-                None,
+                Some(render_thinking(
+                    &streaming_state,
+                    Some(tool_calls.callback()),
+                )),
                 cancellation_flag,
             )
             .await;
@@ -428,16 +2065,82 @@ async fn run_message_with_formatting(
                 app_eprintln!("{} Streaming formatter error: {}", "Error".red(), e);
             }
         }
-        response?;
+        response
     } else {
         let spinner = create_spinner();
-        let response = agent.process_message(message, cancellation_flag).await?;
+        let response = agent
+            .process_message_with_stream(
+                message,
+                None,
+                Some(tool_calls.callback()),
+                cancellation_flag,
+            )
+            .await;
         spinner.finish_and_clear();
-        formatter.print_formatted(&response)?;
+        if let Ok(text) = &response {
+            formatter.print_formatted(text)?;
+        }
+        response
+    };
+
+    let code = headless::exit_code_for(agent, &response, &tool_calls.take());
+    match &response {
+        Ok(_) => print_usage_stats(agent),
+        Err(e) => app_eprintln!("{} {}", "Error:".red(), e),
     }
+    Ok(code)
+}
 
-    print_usage_stats(agent);
-    Ok(())
+/// Run a message and print the result as JSON on stdout: a single object, or
+/// with stream-json one event per line as the run progresses
+async fn run_message_as_json(
+    message: &str,
+    agent: &mut Agent,
+    output_format: OutputFormat,
+    stream: bool,
+    verbose: bool,
+) -> Result<i32> {
+    let started = std::time::Instant::now();
+    let streaming = output_format == OutputFormat::StreamJson;
+    let tool_calls = if streaming {
+        ToolCallCollector::streaming()
+    } else {
+        ToolCallCollector::new()
+    }
+    .with_trace(verbose);
+    let mut on_text: Option<Arc<dyn Fn(String) + Send + Sync>> = None;
+    if streaming {
+        agent
+            .set_permission_handler(Some(headless::deny_permission_handler()))
+            .await;
+        headless::emit(&StreamEvent::start(
+            agent.current_conversation_id(),
+            agent.model().to_string(),
+        ));
+        if stream {
+            on_text = Some(Arc::new(|text: String| {
+                if !text.is_empty() {
+                    headless::emit(&StreamEvent::Text { text });
+                }
+            }));
+        }
+    }
+
+    let response = agent
+        .process_message_with_stream(
+            message,
+            on_text,
+            Some(tool_calls.callback()),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await;
+    let result = RunResult::new(agent, &response, tool_calls.take(), started.elapsed());
+    if streaming {
+        headless::emit(&StreamEvent::Result(result.clone()));
+    } else {
+        println!("{}", serde_json::to_string(&result)?);
+    }
+    Ok(result.exit_code)
 }
 
 /// Run single message mode
@@ -446,8 +2149,47 @@ async fn run_single_message_mode(
     agent: &mut Agent,
     formatter: &formatter::CodeFormatter,
     stream: bool,
-) -> Result<()> {
-    run_message_with_formatting(&message, agent, formatter, stream).await
+    output_format: OutputFormat,
+    verbose: bool,
+) -> Result<i32> {
+    run_message_with_formatting(&message, agent, formatter, stream, output_format, verbose).await
+}
+
+/// Check the `--add-dir` directories and make them absolute
+fn resolve_added_dirs(dirs: &[std::path::PathBuf]) -> Result<Vec<std::path::PathBuf>> {
+    dirs.iter()
+        .map(|dir| {
+            let expanded = shellexpand::tilde(&dir.to_string_lossy()).to_string();
+            std::fs::canonicalize(&expanded)
+                .ok()
+                .filter(|path| path.is_dir())
+                .ok_or_else(|| anyhow!("--add-dir {} is not a directory", dir.display()))
+        })
+        .collect()
+}
+
+/// Tell the model where else it may work, since relative paths resolve
+/// against the current directory only
+fn added_dirs_prompt(dirs: &[std::path::PathBuf]) -> String {
+    let mut prompt = String::from(
+        "Besides the current directory, you may read and write files in these directories. \
+         Use absolute paths for them:\n",
+    );
+    for dir in dirs {
+        prompt.push_str(&format!("- {}\n", dir.display()));
+    }
+    prompt
+}
+
+/// Read stdin if something is piped in, or None for a terminal or empty input
+fn read_piped_stdin() -> Result<Option<String>> {
+    use std::io::IsTerminal;
+    if io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    Ok((!input.trim().is_empty()).then_some(input))
 }
 
 /// Run non-interactive mode (read from stdin)
@@ -455,12 +2197,22 @@ async fn run_non_interactive_mode(
     agent: &mut Agent,
     formatter: &formatter::CodeFormatter,
     stream: bool,
-) -> Result<()> {
+    output_format: OutputFormat,
+    verbose: bool,
+) -> Result<i32> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
     let trimmed_input = input.trim();
 
-    run_message_with_formatting(trimmed_input, agent, formatter, stream).await
+    run_message_with_formatting(
+        trimmed_input,
+        agent,
+        formatter,
+        stream,
+        output_format,
+        verbose,
+    )
+    .await
 }
 
 /// Run interactive mode
@@ -471,6 +2223,7 @@ async fn run_interactive_mode(
     formatter: &formatter::CodeFormatter,
     stream: bool,
     plan_mode: bool,
+    config_reloads: Option<tokio::sync::mpsc::UnboundedReceiver<config_watch::ConfigReload>>,
 ) -> Result<()> {
     if let Some(tui) = tui_guard.as_ref() {
         run_tui_interactive(
@@ -480,6 +2233,7 @@ async fn run_interactive_mode(
             formatter,
             stream,
             plan_mode,
+            config_reloads,
         )
         .await?;
         print_usage_stats(agent);
@@ -492,6 +2246,7 @@ async fn run_interactive_mode(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use config::Provider;
 
     #[test]
     fn test_cli_parsing_basic() {
@@ -615,7 +2370,7 @@ mod tests {
         let result = Cli::try_parse_from(args);
         assert!(result.is_ok());
         let cli = result.unwrap();
-        assert_eq!(cli.provider, Some(Provider::Anthropic));
+        assert_eq!(cli.provider, Some(Provider::ANTHROPIC));
     }
 
     #[test]
@@ -0,0 +1,542 @@
+//! LaTeX math in model responses: finding `$...$` and `$$...$$` spans, and
+//! approximating them with Unicode for the terminal. The web UI gets the raw
+//! TeX and renders it with KaTeX.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A piece of text split out by [`split_math`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathSegment<'a> {
+    Text(&'a str),
+    /// TeX source without the dollar signs; `display` for `$$...$$`
+    Math {
+        tex: &'a str,
+        display: bool,
+    },
+}
+
+/// Split text into prose and math. Inline math follows pandoc's rules so
+/// prices don't count: the opening `$` must be followed by a non-space, the
+/// closing one preceded by a non-space and not followed by a digit. Nothing
+/// inside backticks or code fences is treated as math.
+pub fn split_math(text: &str) -> Vec<MathSegment<'_>> {
+    let bytes = text.as_bytes();
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                // Skip a code span or fence, matching the run of backticks
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let fence = &text[i..i + run];
+                i = match text[i + run..].find(fence) {
+                    Some(end) => i + run + end + run,
+                    None => bytes.len(),
+                };
+            }
+            b'$' => {
+                let display = bytes.get(i + 1) == Some(&b'$');
+                let found = if display {
+                    find_display_end(text, i + 2).map(|end| (i + 2, end, end + 2))
+                } else {
+                    find_inline_end(text, i + 1).map(|end| (i + 1, end, end + 1))
+                };
+                match found {
+                    Some((tex_start, tex_end, next)) => {
+                        if text_start < i {
+                            segments.push(MathSegment::Text(&text[text_start..i]));
+                        }
+                        segments.push(MathSegment::Math {
+                            tex: text[tex_start..tex_end].trim(),
+                            display,
+                        });
+                        i = next;
+                        text_start = next;
+                    }
+                    None => i += if display { 2 } else { 1 },
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    if text_start < text.len() {
+        segments.push(MathSegment::Text(&text[text_start..]));
+    }
+    segments
+}
+
+fn find_display_end(text: &str, start: usize) -> Option<usize> {
+    let end = start + text.get(start..)?.find("$$")?;
+    (!text[start..end].trim().is_empty()).then_some(end)
+}
+
+fn find_inline_end(text: &str, start: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if bytes.get(start).is_none_or(|b| b.is_ascii_whitespace()) {
+        return None;
+    }
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            // Inline math doesn't span lines
+            b'\n' => return None,
+            b'$' => {
+                let closes = !bytes[i - 1].is_ascii_whitespace()
+                    && !bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit());
+                if closes {
+                    return Some(i);
+                }
+                return None;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Convert TeX to readable Unicode, e.g. `\frac{\pi}{2}` to `π/2` and `x^2`
+/// to `x²`. Anything it doesn't know is passed through without the backslash.
+pub fn to_unicode(tex: &str) -> String {
+    let mut chars = tex.chars().peekable();
+    let mut out = String::new();
+    while chars.peek().is_some() {
+        convert_token(&mut chars, &mut out);
+    }
+    // Collapse the runs of spaces left by dropped commands
+    out.split(' ')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Convert the next argument: a `{group}` or a single token
+fn convert_argument(chars: &mut Peekable<Chars>) -> String {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    let mut out = String::new();
+    if chars.peek() == Some(&'{') {
+        chars.next();
+        let mut depth = 0;
+        let mut group = String::new();
+        for c in chars.by_ref() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            group.push(c);
+        }
+        out = to_unicode(&group);
+    } else {
+        convert_token(chars, &mut out);
+    }
+    out
+}
+
+/// The raw text of a `{group}`, for commands like `\text` whose argument
+/// isn't math
+fn raw_argument(chars: &mut Peekable<Chars>) -> String {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if chars.peek() != Some(&'{') {
+        return chars.next().map(String::from).unwrap_or_default();
+    }
+    chars.next();
+    let mut depth = 0;
+    let mut group = String::new();
+    for c in chars.by_ref() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => break,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        group.push(c);
+    }
+    group
+}
+
+fn convert_token(chars: &mut Peekable<Chars>, out: &mut String) {
+    let Some(c) = chars.next() else {
+        return;
+    };
+    match c {
+        '\\' => convert_command(chars, out),
+        '^' => {
+            let argument = convert_argument(chars);
+            out.push_str(&script(&argument, superscript, '^'));
+        }
+        '_' => {
+            let argument = convert_argument(chars);
+            out.push_str(&script(&argument, subscript, '_'));
+        }
+        '{' | '}' => {}
+        '~' => out.push(' '),
+        '&' => out.push(' '),
+        c => out.push(c),
+    }
+}
+
+fn convert_command(chars: &mut Peekable<Chars>, out: &mut String) {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_alphabetic() {
+            name.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    if name.is_empty() {
+        // A control symbol like `\,`, `\{` or `\\`
+        match chars.next() {
+            Some(',') | Some(';') | Some(':') | Some(' ') => out.push(' '),
+            Some('!') | None => {}
+            Some('\\') => out.push('\n'),
+            Some(c) => out.push(c),
+        }
+        return;
+    }
+
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = convert_argument(chars);
+            let denominator = convert_argument(chars);
+            out.push_str(&format!(
+                "{}/{}",
+                parenthesize(&numerator),
+                parenthesize(&denominator)
+            ));
+        }
+        "sqrt" => {
+            let mut index = String::new();
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    index.push(c);
+                }
+            }
+            let radicand = convert_argument(chars);
+            let root = match index.trim() {
+                "" => "√".to_string(),
+                "3" => "∛".to_string(),
+                "4" => "∜".to_string(),
+                index => format!("{}√", script(index, superscript, '^')),
+            };
+            out.push_str(&format!("{}{}", root, parenthesize(&radicand)));
+        }
+        "text" | "textrm" | "textit" | "textbf" | "mbox" | "operatorname" => {
+            out.push_str(&raw_argument(chars));
+        }
+        "mathrm" | "mathit" | "mathbf" | "mathsf" | "mathtt" | "boldsymbol" | "vec" | "hat"
+        | "bar" | "overline" | "tilde" | "dot" => {
+            let argument = convert_argument(chars);
+            out.push_str(&argument);
+            match name.as_str() {
+                "vec" => out.push('\u{20D7}'),
+                "hat" => out.push('\u{0302}'),
+                "bar" | "overline" => out.push('\u{0305}'),
+                "tilde" => out.push('\u{0303}'),
+                "dot" => out.push('\u{0307}'),
+                _ => {}
+            }
+        }
+        "mathbb" => {
+            let argument = raw_argument(chars);
+            for c in argument.chars() {
+                out.push(match c {
+                    'R' => 'ℝ',
+                    'N' => 'ℕ',
+                    'Z' => 'ℤ',
+                    'Q' => 'ℚ',
+                    'C' => 'ℂ',
+                    'P' => 'ℙ',
+                    'E' => '𝔼',
+                    c => c,
+                });
+            }
+        }
+        "begin" | "end" => {
+            raw_argument(chars);
+        }
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" | "limits" => {
+            // Sizing only; `\left.` is an invisible delimiter
+            if chars.peek() == Some(&'.') {
+                chars.next();
+            }
+        }
+        "quad" | "qquad" => out.push(' '),
+        name => match symbol(name) {
+            Some(symbol) => out.push_str(symbol),
+            None => out.push_str(name),
+        },
+    }
+}
+
+/// Wrap a fraction part in parentheses unless it's a single term
+fn parenthesize(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= 1 || text.chars().all(|c| c.is_alphanumeric() || c == '.') {
+        text.to_string()
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// Superscript or subscript every character, or fall back to `^(...)`
+fn script(text: &str, map: fn(char) -> Option<char>, marker: char) -> String {
+    let text = text.trim();
+    match text.chars().map(map).collect::<Option<String>>() {
+        Some(mapped) if !mapped.is_empty() => mapped,
+        _ if text.chars().count() == 1 => format!("{}{}", marker, text),
+        _ => format!("{}({})", marker, text),
+    }
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'n' => 'ⁿ',
+        'i' => 'ⁱ',
+        'T' => 'ᵀ',
+        '′' => '′',
+        '*' => '*',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        // Greek
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        // Operators
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "iint" => "∬",
+        "oint" => "∮",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "infty" => "∞",
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "·",
+        "circ" => "∘",
+        "ast" => "∗",
+        "star" => "⋆",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        // Relations
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "ll" => "≪",
+        "gg" => "≫",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "mid" => "∣",
+        // Arrows
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        // Misc
+        "ldots" | "dots" | "cdots" => "…",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "prime" => "′",
+        "degree" => "°",
+        "angle" => "∠",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" => "‖",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        _ => return None,
+    })
+}
+
+/// Replace the math in a line of prose with its Unicode form
+pub fn render_inline(line: &str) -> String {
+    split_math(line)
+        .into_iter()
+        .map(|segment| match segment {
+            MathSegment::Text(text) => text.to_string(),
+            MathSegment::Math { tex, .. } => to_unicode(tex),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_common_tex_to_unicode() {
+        assert_eq!(to_unicode(r"x^2 + y^2 = z^2"), "x² + y² = z²");
+        assert_eq!(to_unicode(r"\frac{\pi}{2}"), "π/2");
+        assert_eq!(to_unicode(r"\frac{a+b}{2c}"), "(a+b)/2c");
+        assert_eq!(to_unicode(r"\sqrt{x+1}"), "√(x+1)");
+        assert_eq!(to_unicode(r"\sum_{i=1}^{n} x_i"), "∑ᵢ₌₁ⁿ xᵢ");
+        assert_eq!(to_unicode(r"\alpha \leq \beta"), "α ≤ β");
+        assert_eq!(to_unicode(r"x \in \mathbb{R}"), "x ∈ ℝ");
+        assert_eq!(to_unicode(r"e^{i\pi}"), "e^(iπ)");
+        assert_eq!(to_unicode(r"\text{if } x > 0"), "if x > 0");
+        assert_eq!(to_unicode(r"\int_0^\infty e^{-x}\,dx"), "∫₀^∞ e^(-x) dx");
+    }
+
+    #[test]
+    fn splits_inline_and_display_math() {
+        assert_eq!(
+            split_math("Area is $\\pi r^2$, see $$E = mc^2$$ done"),
+            vec![
+                MathSegment::Text("Area is "),
+                MathSegment::Math {
+                    tex: "\\pi r^2",
+                    display: false
+                },
+                MathSegment::Text(", see "),
+                MathSegment::Math {
+                    tex: "E = mc^2",
+                    display: true
+                },
+                MathSegment::Text(" done"),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_prices_and_code_alone() {
+        let text = "It costs $5 and $10 today";
+        assert_eq!(split_math(text), vec![MathSegment::Text(text)]);
+        let text = "Run `echo $HOME$` first";
+        assert_eq!(split_math(text), vec![MathSegment::Text(text)]);
+        let text = "```bash\necho $a$b\n```";
+        assert_eq!(split_math(text), vec![MathSegment::Text(text)]);
+        assert_eq!(render_inline("a $x$ b"), "a x b");
+    }
+}
@@ -13,6 +13,7 @@ use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -21,12 +22,18 @@ use tokio::process::{Child, Command as TokioCommand};
 use tokio::sync::{Mutex, RwLock};
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{client_async, connect_async, tungstenite::Message, MaybeTlsStream};
+
+use crate::mcp_import::{merge_servers, McpImportSummary};
+use crate::mcp_sampling::{McpSamplingHandler, McpSamplingRequest};
+use crate::mcp_schema::sanitize_input_schema;
+use crate::mcp_token_store::{McpTokenStore, StoredMcpToken};
+use crate::mcp_tool_cache::McpToolCache;
 
 // Re-export from config module to maintain compatibility
 pub use crate::config::{
     McpAuthConfig, McpConfig, McpOAuthClientAuth, McpOAuthConfig, McpOAuthGrantType,
-    McpServerConfig,
+    McpSamplingPolicy, McpServerConfig, McpTransport,
 };
 
 // PKCE (Proof Key for Code Exchange) support for OAuth 2.0
@@ -49,13 +56,16 @@ fn generate_state() -> String {
     URL_SAFE_NO_PAD.encode(bytes)
 }
 
+/// MCP protocol revision requested from Streamable HTTP servers
+const STREAMABLE_HTTP_PROTOCOL_VERSION: &str = "2025-03-26";
+
 /// Create a reqwest HTTP client with a proper User-Agent header.
 /// Some services (like GoDaddy) block requests without a User-Agent.
 fn create_http_client() -> reqwest::Client {
-    reqwest::Client::builder()
+    crate::http_client::client_builder()
         .user_agent("flexorama/0.1.0")
         .build()
-        .unwrap_or_else(|_| create_http_client())
+        .unwrap_or_else(|_| reqwest::Client::new())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,8 +122,14 @@ pub struct McpClientCapabilities {
     pub tools: Option<McpToolsCapability>,
     pub resources: Option<McpResourcesCapability>,
     pub prompts: Option<McpPromptsCapability>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<McpSamplingCapability>,
 }
 
+/// Advertised when the client answers `sampling/createMessage` requests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpSamplingCapability {}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct McpToolsCapability {
@@ -165,6 +181,90 @@ pub struct McpTool {
     pub input_schema: Value,
 }
 
+/// An MCP tool together with the name it is exposed to the model under
+#[derive(Debug, Clone)]
+pub struct NamedMcpTool {
+    pub name: String,
+    pub server_name: String,
+    pub tool: McpTool,
+}
+
+/// Pick the name each MCP tool is exposed under. Tools are named
+/// `mcp_<server>_<tool>` unless the server config aliases them to
+/// `mcp_<alias>`. When two tools would get the same name, both are
+/// namespaced as `mcp_<server>__<tool>` instead of one replacing the other.
+pub fn assign_mcp_tool_names(
+    mut tools: Vec<(String, McpTool)>,
+    servers: &HashMap<String, McpServerConfig>,
+) -> Vec<NamedMcpTool> {
+    tools.sort_by(|a, b| (&a.0, &a.1.name).cmp(&(&b.0, &b.1.name)));
+
+    let alias_for = |server: &str, tool: &str| {
+        servers
+            .get(server)
+            .and_then(|config| config.aliases.as_ref())
+            .and_then(|aliases| aliases.get(tool))
+            .map(|alias| format!("mcp_{}", alias))
+    };
+
+    let mut default_counts: HashMap<String, usize> = HashMap::new();
+    for (server, tool) in &tools {
+        if alias_for(server, &tool.name).is_none() {
+            *default_counts
+                .entry(format!("mcp_{}_{}", server, tool.name))
+                .or_default() += 1;
+        }
+    }
+
+    // Aliases are claimed first so they always win over generated names
+    let mut taken = HashSet::new();
+    let mut named = Vec::new();
+    let mut unaliased = Vec::new();
+    for (server, tool) in tools {
+        match alias_for(&server, &tool.name) {
+            Some(name) => {
+                if taken.insert(name.clone()) {
+                    named.push(NamedMcpTool {
+                        name,
+                        server_name: server,
+                        tool,
+                    });
+                } else {
+                    warn!(
+                        "Alias '{}' for MCP tool '{}' on server '{}' is already in use; skipping the tool",
+                        name, tool.name, server
+                    );
+                }
+            }
+            None => unaliased.push((server, tool)),
+        }
+    }
+
+    for (server, tool) in unaliased {
+        let default_name = format!("mcp_{}_{}", server, tool.name);
+        let name = if default_counts[&default_name] > 1 || taken.contains(&default_name) {
+            format!("mcp_{}__{}", server, tool.name)
+        } else {
+            default_name
+        };
+        if taken.insert(name.clone()) {
+            named.push(NamedMcpTool {
+                name,
+                server_name: server,
+                tool,
+            });
+        } else {
+            warn!(
+                "MCP tool '{}' on server '{}' conflicts with another tool named '{}'; add an alias to expose it",
+                tool.name, server, name
+            );
+        }
+    }
+
+    named.sort_by(|a, b| a.name.cmp(&b.name));
+    named
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpResource {
     pub uri: String,
@@ -192,12 +292,16 @@ struct OAuthTokenResponse {
     token_type: Option<String>,
     #[serde(default)]
     expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 struct OAuthTokenCacheEntry {
     header_value: String,
     expires_at: Option<Instant>,
+    /// Refreshable credentials from an authorization code grant, for the token store
+    credentials: Option<Box<StoredMcpToken>>,
 }
 
 /// OAuth metadata discovered from well-known endpoints (RFC 8414 / MCP OAuth spec)
@@ -365,6 +469,23 @@ async fn register_oauth_client(
     Ok(reg_response.client_id)
 }
 
+/// Repair a tool's input schema, or drop the tool with a warning if it can't be used
+fn sanitize_tool(server_name: &str, mut tool: McpTool) -> Option<McpTool> {
+    match sanitize_input_schema(&tool.input_schema) {
+        Ok(schema) => {
+            tool.input_schema = schema;
+            Some(tool)
+        }
+        Err(e) => {
+            warn!(
+                "Skipping MCP tool '{}' from server '{}': invalid input schema: {}",
+                tool.name, server_name, e
+            );
+            None
+        }
+    }
+}
+
 async fn handle_mcp_response(
     name: &str,
     response: McpResponse,
@@ -393,7 +514,7 @@ async fn handle_mcp_response(
                         match serde_json::from_value::<McpTool>(raw_tool.clone()) {
                             Ok(tool) => {
                                 debug!("Successfully parsed tool: {} from {}", tool.name, name);
-                                parsed_tools.push(tool);
+                                parsed_tools.extend(sanitize_tool(name, tool));
                             }
                             Err(e) => {
                                 warn!("Failed to parse tool {} from server '{}' (index: {}): {}. Tool data: {}", 
@@ -843,11 +964,132 @@ async fn exchange_code_for_token(
     Ok(token_response)
 }
 
+/// Use a stored refresh token to obtain a new access token. The returned credentials
+/// keep the previous refresh token when the server does not rotate it.
+async fn refresh_oauth_token(stored: &StoredMcpToken) -> Result<StoredMcpToken> {
+    let refresh_token = stored
+        .refresh_token
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("No refresh token available"))?;
+
+    let mut form = HashMap::<String, String>::new();
+    form.insert("grant_type".to_string(), "refresh_token".to_string());
+    form.insert("refresh_token".to_string(), refresh_token.to_string());
+    form.insert("client_id".to_string(), stored.client_id.clone());
+    if let Some(client_secret) = &stored.client_secret {
+        form.insert("client_secret".to_string(), client_secret.clone());
+    }
+
+    let response = create_http_client()
+        .post(&stored.token_endpoint)
+        .header("accept", "application/json")
+        .form(&form)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "Token refresh failed (HTTP {}): {}",
+            status,
+            truncate_for_log(&body, 500)
+        ));
+    }
+
+    let token_response: OAuthTokenResponse = response.json().await?;
+    Ok(StoredMcpToken {
+        server_url: stored.server_url.clone(),
+        access_token: token_response.access_token,
+        token_type: token_response
+            .token_type
+            .or_else(|| stored.token_type.clone()),
+        refresh_token: token_response
+            .refresh_token
+            .or_else(|| stored.refresh_token.clone()),
+        expires_at: token_response
+            .expires_in
+            .map(|secs| chrono::Utc::now().timestamp() + secs as i64),
+        token_endpoint: stored.token_endpoint.clone(),
+        client_id: stored.client_id.clone(),
+        client_secret: stored.client_secret.clone(),
+    })
+}
+
+/// Load a usable token from the store, refreshing it when it has expired (or when
+/// `force_refresh` is set because the server rejected it). Tokens that can no
+/// longer be refreshed are removed so the next connection starts a new flow.
+async fn load_stored_oauth_token(
+    store: &McpTokenStore,
+    name: &str,
+    server_url: &str,
+    force_refresh: bool,
+) -> Option<StoredMcpToken> {
+    let stored = match store.load(name, server_url).await {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return None,
+        Err(e) => {
+            warn!(
+                "Failed to read OAuth token store {}: {}",
+                store.path().display(),
+                e
+            );
+            return None;
+        }
+    };
+
+    if !force_refresh && !stored.is_expired() {
+        debug!("Using stored OAuth token for MCP server '{}'", name);
+        return Some(stored);
+    }
+
+    if stored.refresh_token.is_none() {
+        debug!(
+            "Stored OAuth token for MCP server '{}' cannot be refreshed",
+            name
+        );
+        let _ = store.remove(name).await;
+        return None;
+    }
+
+    match refresh_oauth_token(&stored).await {
+        Ok(refreshed) => {
+            info!("Refreshed OAuth token for MCP server '{}'", name);
+            if let Err(e) = store.save(name, &refreshed).await {
+                warn!("Failed to save refreshed OAuth token for '{}': {}", name, e);
+            }
+            Some(refreshed)
+        }
+        Err(e) => {
+            warn!(
+                "Failed to refresh OAuth token for MCP server '{}': {}",
+                name, e
+            );
+            let _ = store.remove(name).await;
+            None
+        }
+    }
+}
+
+/// Persist credentials from a completed authorization flow
+async fn save_oauth_credentials(store: &McpTokenStore, name: &str, entry: &OAuthTokenCacheEntry) {
+    if let Some(credentials) = &entry.credentials {
+        match store.save(name, credentials).await {
+            Ok(()) => debug!("Saved OAuth token for MCP server '{}'", name),
+            Err(e) => warn!(
+                "Failed to save OAuth token for MCP server '{}': {}",
+                name, e
+            ),
+        }
+    }
+}
+
 /// Perform the full OAuth authorization code flow with PKCE
 /// If client_id is None but registration_endpoint is provided, dynamic client registration
 /// will be performed first (RFC 7591).
 async fn perform_oauth_authorization_flow(
     name: &str,
+    server_url: Option<&str>,
     authorization_url: &str,
     token_url: &str,
     client_id: Option<&str>,
@@ -950,10 +1192,25 @@ async fn perform_oauth_authorization_flow(
     let expires_at = token_response
         .expires_in
         .map(|secs| Instant::now() + Duration::from_secs(secs.saturating_sub(30)));
+    let credentials = server_url.map(|server_url| {
+        Box::new(StoredMcpToken {
+            server_url: server_url.to_string(),
+            access_token: token_response.access_token.clone(),
+            token_type: token_response.token_type.clone(),
+            refresh_token: token_response.refresh_token.clone(),
+            expires_at: token_response
+                .expires_in
+                .map(|secs| chrono::Utc::now().timestamp() + secs as i64),
+            token_endpoint: token_url.to_string(),
+            client_id: resolved_client_id.clone(),
+            client_secret: Some(client_secret.to_string()).filter(|secret| !secret.is_empty()),
+        })
+    });
 
     Ok(OAuthTokenCacheEntry {
         header_value,
         expires_at,
+        credentials,
     })
 }
 
@@ -1185,6 +1442,7 @@ async fn maybe_handle_oauth_required(
 
             match perform_oauth_authorization_flow(
                 name,
+                server_url,
                 &auth_url,
                 resolved_token_url.as_ref().unwrap(),
                 resolved_client_id.as_deref(),
@@ -1257,10 +1515,145 @@ async fn maybe_handle_oauth_required(
     OAuthHandleResult::NoToken
 }
 
+/// Sampling handler shared by the manager and its connections, so it can be
+/// installed after servers have connected
+#[derive(Clone, Default)]
+struct SamplingHandlerSlot(Arc<RwLock<Option<McpSamplingHandler>>>);
+
+impl std::fmt::Debug for SamplingHandlerSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SamplingHandlerSlot")
+    }
+}
+
+/// Where replies to server-initiated requests are written
+#[derive(Clone)]
+enum McpReplySink {
+    Stdio(Arc<Mutex<tokio::process::ChildStdin>>),
+    Http {
+        client: reqwest::Client,
+        url: String,
+        headers: Vec<(&'static str, String)>,
+    },
+}
+
+/// Everything needed to answer requests a server sends to the client
+#[derive(Clone)]
+struct ServerRequestContext {
+    name: String,
+    sampling_policy: McpSamplingPolicy,
+    sampling_handler: SamplingHandlerSlot,
+    reply: Option<McpReplySink>,
+    /// Server requests still being answered; request timeouts wait for these
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl ServerRequestContext {
+    async fn send_reply(&self, reply: &Value) -> Result<()> {
+        let body = serde_json::to_string(reply)?;
+        debug!("Replying to MCP server {}: {}", self.name, body);
+        match &self.reply {
+            Some(McpReplySink::Stdio(writer)) => {
+                let mut writer = writer.lock().await;
+                writer.write_all(body.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+            Some(McpReplySink::Http {
+                client,
+                url,
+                headers,
+            }) => {
+                let mut request = client
+                    .post(url)
+                    .header("content-type", "application/json")
+                    .header("accept", "application/json, text/event-stream")
+                    .body(body);
+                for (name, value) in headers {
+                    request = request.header(*name, value);
+                }
+                let response = request.send().await?;
+                if !response.status().is_success() {
+                    return Err(anyhow::anyhow!("HTTP {}", response.status()));
+                }
+            }
+            None => return Err(anyhow::anyhow!("No connection available")),
+        }
+        Ok(())
+    }
+}
+
+/// A request from the server carries both a method and an id; notifications have no id
+fn is_server_request(value: &Value) -> bool {
+    value.get("method").is_some() && value.get("id").is_some_and(|id| !id.is_null())
+}
+
+/// Answer a server-initiated request in the background so the reader keeps
+/// dispatching replies to our own requests meanwhile
+fn spawn_server_request(context: &ServerRequestContext, request: Value) {
+    let context = context.clone();
+    context.in_flight.fetch_add(1, Ordering::SeqCst);
+    tokio::spawn(async move {
+        let reply = answer_server_request(&context, request).await;
+        if let Err(e) = context.send_reply(&reply).await {
+            warn!("Failed to reply to MCP server {}: {}", context.name, e);
+        }
+        context.in_flight.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+async fn answer_server_request(context: &ServerRequestContext, request: Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request
+        .get("method")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default();
+    debug!("MCP server {} sent request '{}'", context.name, method);
+
+    let outcome = match method {
+        "ping" => Ok(json!({})),
+        "sampling/createMessage" => handle_sampling_request(context, request.get("params")).await,
+        other => Err((-32601, format!("Method not found: {}", other))),
+    };
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message }
+        }),
+    }
+}
+
+/// Apply the server's sampling policy and forward the request to the handler.
+/// Errors are JSON-RPC (code, message) pairs; -1 means the user rejected it.
+async fn handle_sampling_request(
+    context: &ServerRequestContext,
+    params: Option<&Value>,
+) -> std::result::Result<Value, (i32, String)> {
+    if context.sampling_policy == McpSamplingPolicy::Deny {
+        return Err((-1, "Sampling is disabled for this server".to_string()));
+    }
+    let handler = match context.sampling_handler.0.read().await.clone() {
+        Some(handler) => handler,
+        None => return Err((-32601, "Sampling is not available".to_string())),
+    };
+    let mut request: McpSamplingRequest =
+        serde_json::from_value(params.cloned().unwrap_or(Value::Null))
+            .map_err(|e| (-32602, format!("Invalid sampling request: {}", e)))?;
+    request.server_name = context.name.clone();
+    request.needs_approval = context.sampling_policy == McpSamplingPolicy::Ask;
+
+    match handler(request).await {
+        Ok(result) => serde_json::to_value(result).map_err(|e| (-32603, e.to_string())),
+        Err(e) => Err((-1, e.to_string())),
+    }
+}
+
 /// Helper function to handle the SSE stream after a successful connection
 async fn handle_sse_stream(
     response: reqwest::Response,
-    name: String,
+    context: ServerRequestContext,
     pending_requests: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<McpResponse>>>>,
     tools: Arc<RwLock<Vec<McpTool>>>,
     tools_version: Arc<RwLock<u64>>,
@@ -1273,16 +1666,38 @@ async fn handle_sse_stream(
     if !content_type.contains("text/event-stream") {
         warn!(
             "MCP SSE stream for {} returned unexpected content-type '{}'",
-            name, content_type
+            context.name, content_type
         );
         return Ok(None);
     }
 
+    Ok(Some(spawn_sse_reader(
+        response.bytes_stream(),
+        String::new(),
+        context,
+        pending_requests,
+        tools,
+        tools_version,
+    )))
+}
+
+/// Dispatch JSON-RPC messages from an SSE stream in the background until cancelled.
+/// `buffer` holds any text already read from the stream.
+fn spawn_sse_reader<S>(
+    mut stream: S,
+    mut buffer: String,
+    context: ServerRequestContext,
+    pending_requests: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<McpResponse>>>>,
+    tools: Arc<RwLock<Vec<McpTool>>>,
+    tools_version: Arc<RwLock<u64>>,
+) -> tokio::sync::oneshot::Sender<()>
+where
+    S: futures_util::Stream<Item = reqwest::Result<bytes::Bytes>> + Send + Unpin + 'static,
+{
     let (cancel_tx, mut cancel_rx) = tokio::sync::oneshot::channel();
-    let mut stream = response.bytes_stream();
+    let name = context.name.clone();
 
     tokio::spawn(async move {
-        let mut buffer = String::new();
         loop {
             tokio::select! {
                 _ = &mut cancel_rx => {
@@ -1305,6 +1720,12 @@ async fn handle_sse_stream(
                                     if data.trim().is_empty() {
                                         continue;
                                     }
+                                    if let Ok(value) = serde_json::from_str::<Value>(data.trim()) {
+                                        if is_server_request(&value) {
+                                            spawn_server_request(&context, value);
+                                            continue;
+                                        }
+                                    }
                                     match serde_json::from_str::<McpResponse>(data.trim()) {
                                         Ok(response) => {
                                             handle_mcp_response(
@@ -1339,7 +1760,7 @@ async fn handle_sse_stream(
         }
     });
 
-    Ok(Some(cancel_tx))
+    cancel_tx
 }
 
 /// Result of attempting to start an HTTP SSE connection
@@ -1354,7 +1775,8 @@ async fn start_http_sse(
     url: &str,
     client: &reqwest::Client,
     auth_header: Option<&str>,
-    name: String,
+    session_id: Option<&str>,
+    context: ServerRequestContext,
     pending_requests: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<McpResponse>>>>,
     tools: Arc<RwLock<Vec<McpTool>>>,
     tools_version: Arc<RwLock<u64>>,
@@ -1364,10 +1786,14 @@ async fn start_http_sse(
     oauth_audience: Option<&str>,
     oauth_extra_params: Option<&HashMap<String, String>>,
 ) -> Result<HttpSseResult> {
+    let name = context.name.clone();
     let mut request = client.get(url).header("accept", "text/event-stream, application/json");
     if let Some(header_value) = auth_header {
         request = request.header("authorization", header_value);
     }
+    if let Some(session_id) = session_id {
+        request = request.header("mcp-session-id", session_id);
+    }
 
     let response = match request.send().await {
         Ok(response) => response,
@@ -1377,6 +1803,17 @@ async fn start_http_sse(
         }
     };
 
+    if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+        debug!(
+            "MCP server {} does not offer a server-to-client SSE stream",
+            name
+        );
+        return Ok(HttpSseResult {
+            sse_cancel: None,
+            oauth_token: None,
+        });
+    }
+
     if !response.status().is_success() {
         let status = response.status();
         let headers = response.headers().clone();
@@ -1401,17 +1838,20 @@ async fn start_http_sse(
         // return the token for use with POST-based HTTP transport
         if let OAuthHandleResult::Token(token_entry) = oauth_result {
             info!("Obtained OAuth token for {}, trying SSE connection...", name);
-            let retry_request = client
+            let mut retry_request = client
                 .get(url)
                 .header("accept", "text/event-stream, application/json")
                 .header("authorization", &token_entry.header_value);
+            if let Some(session_id) = session_id {
+                retry_request = retry_request.header("mcp-session-id", session_id);
+            }
 
             match retry_request.send().await {
                 Ok(retry_response) if retry_response.status().is_success() => {
                     // SSE GET worked, continue with SSE stream
                     let sse_result = handle_sse_stream(
                         retry_response,
-                        name,
+                        context,
                         pending_requests,
                         tools,
                         tools_version,
@@ -1454,8 +1894,78 @@ async fn start_http_sse(
     }
 
     // Handle the successful SSE stream
-    let sse_result = handle_sse_stream(response, name, pending_requests, tools, tools_version).await?;
-    Ok(HttpSseResult { sse_cancel: sse_result, oauth_token: None })
+    let sse_result =
+        handle_sse_stream(response, context, pending_requests, tools, tools_version).await?;
+    Ok(HttpSseResult {
+        sse_cancel: sse_result,
+        oauth_token: None,
+    })
+}
+
+/// Error status returned by a Streamable HTTP MCP endpoint
+#[derive(Debug)]
+struct McpHttpStatusError {
+    name: String,
+    status: StatusCode,
+    body: String,
+}
+
+impl std::fmt::Display for McpHttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MCP HTTP request failed for '{}' (HTTP {}): {}",
+            self.name, self.status, self.body
+        )
+    }
+}
+
+impl std::error::Error for McpHttpStatusError {}
+
+/// Whether a failed Streamable HTTP connection points at a legacy HTTP+SSE server.
+/// Per the MCP backwards-compatibility guidance, a 4xx reply to the initialize POST
+/// (other than an auth failure) means the client should try the older transport.
+fn should_fallback_to_sse(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<McpHttpStatusError>()
+        .map(|e| {
+            e.status.is_client_error()
+                && e.status != StatusCode::UNAUTHORIZED
+                && e.status != StatusCode::FORBIDDEN
+        })
+        .unwrap_or(false)
+}
+
+/// Read SSE events until the legacy transport's `endpoint` event says where to POST messages
+async fn read_sse_endpoint<S>(stream: &mut S, buffer: &mut String) -> Result<String>
+where
+    S: futures_util::Stream<Item = reqwest::Result<bytes::Bytes>> + Unpin,
+{
+    while let Some(chunk) = stream.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk?));
+        if buffer.contains("\r\n") {
+            *buffer = buffer.replace("\r\n", "\n");
+        }
+        while let Some(idx) = buffer.find("\n\n") {
+            let event = buffer[..idx].to_string();
+            *buffer = buffer[idx + 2..].to_string();
+            if extract_sse_event_type(&event) == Some("endpoint") {
+                if let Some(data) = extract_sse_data(&event) {
+                    return Ok(data.trim().to_string());
+                }
+            }
+        }
+    }
+    Err(anyhow::anyhow!(
+        "SSE stream closed before the server announced a message endpoint"
+    ))
+}
+
+fn extract_sse_event_type(event: &str) -> Option<&str> {
+    event
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("event:"))
+        .map(|event_type| event_type.trim())
 }
 
 fn extract_sse_data(event: &str) -> Option<String> {
@@ -1486,15 +1996,24 @@ pub struct McpConnection {
     pub http_client: Option<reqwest::Client>,
     pub http_auth_header: Option<String>,
     pub http_session_id: Option<String>,
+    pub http_protocol_version: Option<String>,
+    http_reinitializing: bool,
+    /// POST endpoint announced by a legacy HTTP+SSE server
+    pub legacy_sse_endpoint: Option<String>,
     pub oauth_authorization_url: Option<String>,
     pub oauth_client_id: Option<String>,
     pub oauth_scope: Option<String>,
     pub oauth_audience: Option<String>,
     pub oauth_extra_params: Option<HashMap<String, String>>,
+    /// Where OAuth tokens obtained for this connection are persisted
+    pub token_store: Option<McpTokenStore>,
+    pub sampling_policy: McpSamplingPolicy,
+    sampling_handler: SamplingHandlerSlot,
+    server_requests_in_flight: Arc<AtomicUsize>,
     pub sse_enabled: bool,
     pub sse_cancel: Option<tokio::sync::oneshot::Sender<()>>,
     pub reader: Option<BufReader<tokio::process::ChildStdout>>,
-    pub writer: Option<tokio::process::ChildStdin>,
+    pub writer: Option<Arc<Mutex<tokio::process::ChildStdin>>>,
     pub request_id: u64,
     pub pending_requests: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<McpResponse>>>>,
     pub tools: Arc<RwLock<Vec<McpTool>>>,
@@ -1511,11 +2030,18 @@ impl McpConnection {
             http_client: None,
             http_auth_header: None,
             http_session_id: None,
+            http_protocol_version: None,
+            http_reinitializing: false,
+            legacy_sse_endpoint: None,
             oauth_authorization_url: None,
             oauth_client_id: None,
             oauth_scope: None,
             oauth_audience: None,
             oauth_extra_params: None,
+            token_store: None,
+            sampling_policy: McpSamplingPolicy::default(),
+            sampling_handler: SamplingHandlerSlot::default(),
+            server_requests_in_flight: Arc::new(AtomicUsize::new(0)),
             sse_enabled: false,
             sse_cancel: None,
             reader: None,
@@ -1638,10 +2164,11 @@ impl McpConnection {
             .ok_or_else(|| anyhow::anyhow!("Failed to get stdout from child process"))?;
 
         self.reader = Some(BufReader::new(stdout));
-        self.writer = Some(stdin);
+        self.writer = Some(Arc::new(Mutex::new(stdin)));
         self.process = Some(child);
 
         // Start message handling loop
+        let server_requests = self.server_request_context();
         let pending_requests = self.pending_requests.clone();
         let tools = self.tools.clone();
         let tools_version = self.tools_version.clone();
@@ -1664,6 +2191,14 @@ impl McpConnection {
 
                         debug!("Received from MCP server {}: {}", name, buffer.trim());
 
+                        if let Ok(value) = serde_json::from_str::<Value>(buffer.trim()) {
+                            if is_server_request(&value) {
+                                spawn_server_request(&server_requests, value);
+                                buffer.clear();
+                                continue;
+                            }
+                        }
+
                         match serde_json::from_str::<McpResponse>(&buffer.trim()) {
                             Ok(response) => {
                                 handle_mcp_response(
@@ -1820,7 +2355,26 @@ impl McpConnection {
             request.headers_mut().insert(AUTHORIZATION, parsed_header);
         }
 
-        let (ws_stream, _) = connect_async(request).await?;
+        let ws_stream = match crate::http_client::websocket_proxy(&Url::parse(url)?) {
+            Some(proxy) => {
+                let uri = request.uri();
+                if uri.scheme_str() == Some("wss") {
+                    return Err(anyhow::anyhow!(
+                        "wss:// MCP servers can't be reached through a proxy; use ws:// or an HTTP transport"
+                    ));
+                }
+                let host = uri
+                    .host()
+                    .ok_or_else(|| anyhow::anyhow!("WebSocket URL has no host: {}", url))?
+                    .to_string();
+                let port = uri.port_u16().unwrap_or(80);
+                let stream = crate::http_client::connect_through_proxy(&proxy, &host, port).await?;
+                client_async(request, MaybeTlsStream::Plain(stream))
+                    .await?
+                    .0
+            }
+            None => connect_async(request).await?.0,
+        };
         self.websocket = Some(ws_stream);
 
         // Initialize connection
@@ -1834,13 +2388,18 @@ impl McpConnection {
         let client = create_http_client();
         self.http_url = Some(url.to_string());
         self.http_client = Some(client.clone());
-        self.http_auth_header = auth_header.clone();
+        self.http_auth_header = auth_header;
 
+        // Streamable HTTP: the session starts with a POSTed initialize request
+        self.initialize().await?;
+
+        // Optionally open the GET stream for server-initiated messages within the session
         let sse_result = start_http_sse(
             url,
             &client,
-            auth_header.as_deref(),
-            self.name.clone(),
+            self.http_auth_header.as_deref(),
+            self.http_session_id.as_deref(),
+            self.server_request_context(),
             self.pending_requests.clone(),
             self.tools.clone(),
             self.tools_version.clone(),
@@ -1865,17 +2424,177 @@ impl McpConnection {
             self.sse_enabled = false;
         }
 
-        self.initialize().await?;
+        Ok(())
+    }
+
+    /// Connect over Streamable HTTP, falling back to the legacy HTTP+SSE transport
+    /// when the server rejects the initialize POST
+    pub async fn connect_http_auto(
+        &mut self,
+        url: &str,
+        auth_header: Option<String>,
+    ) -> Result<()> {
+        match self.connect_http(url, auth_header.clone()).await {
+            Ok(()) => Ok(()),
+            Err(e) if should_fallback_to_sse(&e) => {
+                info!(
+                    "MCP server '{}' does not accept Streamable HTTP ({}), trying the HTTP+SSE transport",
+                    self.name, e
+                );
+                // Keep any OAuth token obtained during the first attempt
+                let auth_header = self.http_auth_header.take().or(auth_header);
+                self.http_url = None;
+                self.http_session_id = None;
+                self.http_protocol_version = None;
+                self.connect_sse(url, auth_header).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Connect using the legacy HTTP+SSE transport (MCP 2024-11-05): responses arrive on a
+    /// GET event stream, and requests are POSTed to the endpoint the server announces on it
+    pub async fn connect_sse(&mut self, url: &str, auth_header: Option<String>) -> Result<()> {
+        debug!("Connecting to MCP server via HTTP+SSE: {}", url);
+
+        let client = create_http_client();
+        let mut auth_header = auth_header;
+        let mut retried = false;
+        let response = loop {
+            let mut request = client.get(url).header("accept", "text/event-stream");
+            if let Some(header_value) = &auth_header {
+                request = request.header("authorization", header_value);
+            }
+            let response = request.send().await?;
+            let status = response.status();
+            if status.is_success() {
+                break response;
+            }
+
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            if !retried {
+                let oauth_result = maybe_handle_oauth_required(
+                    &self.name,
+                    status,
+                    &headers,
+                    &body,
+                    self.oauth_authorization_url.as_deref(),
+                    Some(url),
+                    Some(&client),
+                    self.oauth_client_id.as_deref(),
+                    self.oauth_scope.as_deref(),
+                    self.oauth_audience.as_deref(),
+                    self.oauth_extra_params.as_ref(),
+                    None, // token_url - will be derived from server_url
+                )
+                .await;
+                if let OAuthHandleResult::Token(token_entry) = oauth_result {
+                    self.save_oauth_credentials(&token_entry).await;
+                    retried = true;
+                    auth_header = Some(token_entry.header_value);
+                    continue;
+                }
+            }
+            return Err(anyhow::anyhow!(
+                "MCP SSE stream for '{}' returned HTTP {}: {}",
+                self.name,
+                status,
+                body
+            ));
+        };
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        if !content_type.contains("text/event-stream") {
+            return Err(anyhow::anyhow!(
+                "MCP server '{}' did not return an event stream (content-type '{}')",
+                self.name,
+                content_type
+            ));
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+        let endpoint = tokio::time::timeout(
+            Duration::from_secs(10),
+            read_sse_endpoint(&mut stream, &mut buffer),
+        )
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "MCP server '{}' did not announce a message endpoint within 10 seconds",
+                self.name
+            )
+        })??;
+        // The endpoint is usually relative to the SSE URL
+        let endpoint = Url::parse(url)?.join(&endpoint)?;
+        debug!("MCP server '{}' message endpoint: {}", self.name, endpoint);
+
+        self.http_client = Some(client);
+        self.http_auth_header = auth_header;
+        self.legacy_sse_endpoint = Some(endpoint.to_string());
+        self.sse_cancel = Some(spawn_sse_reader(
+            stream,
+            buffer,
+            self.server_request_context(),
+            self.pending_requests.clone(),
+            self.tools.clone(),
+            self.tools_version.clone(),
+        ));
+        self.sse_enabled = true;
+
+        self.initialize().await
+    }
+
+    /// POST a message to a legacy HTTP+SSE server; the server only acknowledges it here
+    async fn post_sse_message(&self, body: &str) -> Result<()> {
+        let endpoint = self
+            .legacy_sse_endpoint
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("SSE message endpoint not initialized"))?;
+        let client = self
+            .http_client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("HTTP client not initialized"))?;
+        let mut request = client
+            .post(endpoint)
+            .header("content-type", "application/json")
+            .body(body.to_string());
+        if let Some(auth_header) = &self.http_auth_header {
+            request = request.header("authorization", auth_header);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "MCP SSE message failed for '{}' (HTTP {}): {}",
+                self.name,
+                status,
+                body
+            ));
+        }
         Ok(())
     }
 
     async fn initialize(&mut self) -> Result<()> {
         info!("Initializing MCP server '{}' (sse_enabled={})...", self.name, self.sse_enabled);
+        // Streamable HTTP was introduced in 2025-03-26; stdio and WebSocket servers keep the older revision
+        let protocol_version = if self.http_url.is_some() {
+            STREAMABLE_HTTP_PROTOCOL_VERSION
+        } else {
+            "2024-11-05"
+        };
         let init_request = McpRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(self.next_id()),
             method: McpMethod::Initialize {
-                protocol_version: "2024-11-05".to_string(),
+                protocol_version: protocol_version.to_string(),
                 capabilities: McpClientCapabilities {
                     tools: Some(McpToolsCapability {
                         list_changed: Some(true),
@@ -1886,6 +2605,8 @@ impl McpConnection {
                     prompts: Some(McpPromptsCapability {
                         list_changed: Some(true),
                     }),
+                    sampling: (self.sampling_policy != McpSamplingPolicy::Deny)
+                        .then(McpSamplingCapability::default),
                 },
                 client_info: McpClientInfo {
                     name: "flexorama".to_string(),
@@ -1905,6 +2626,16 @@ impl McpConnection {
             ));
         }
 
+        if self.http_url.is_some() {
+            // Later requests must carry the negotiated version in the MCP-Protocol-Version header
+            self.http_protocol_version = response
+                .result
+                .as_ref()
+                .and_then(|result| result.get("protocolVersion"))
+                .and_then(|version| version.as_str())
+                .map(|version| version.to_string());
+        }
+
         info!("MCP server '{}' initialized, sending notification...", self.name);
         // Send initialized notification
         let initialized = McpRequest {
@@ -2037,8 +2768,10 @@ impl McpConnection {
                                         "✓ Loaded tool: {} from server '{}'",
                                         tool.name, self.name
                                     );
-                                    self.log_tool_details(&tool);
-                                    parsed_tools.push(tool);
+                                    if let Some(tool) = sanitize_tool(&self.name, tool) {
+                                        self.log_tool_details(&tool);
+                                        parsed_tools.push(tool);
+                                    }
                                 }
                                 Err(e) => {
                                     warn!("Failed to parse tool {} from server '{}' (index: {}): {}. Tool data: {}", 
@@ -2290,170 +3023,50 @@ impl McpConnection {
 
         debug!("Sending MCP request to {}: {}", self.name, request_json);
 
-        if let Some(http_url) = &self.http_url {
-            let client = self
-                .http_client
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("HTTP client not initialized"))?;
-            let mut http_request = client
-                .post(http_url)
-                .header("content-type", "application/json")
-                .header("accept", "application/json, text/event-stream")
-                .body(request_json.clone());
-            if let Some(auth_header) = &self.http_auth_header {
-                http_request = http_request.header("authorization", auth_header);
-            }
-            if let Some(session_id) = &self.http_session_id {
-                http_request = http_request.header("mcp-session-id", session_id);
-            }
-
-            // For Streamable HTTP (used by Notion and others), responses come in the POST
-            // response body, not through a separate SSE stream. Always read the response body.
-            {
-                info!("Sending HTTP POST to '{}' (non-SSE mode), url={}", self.name, http_url);
-                let response = http_request.send().await?;
-                info!("Received HTTP response from '{}': status={}, content-type={:?}",
-                    self.name,
-                    response.status(),
-                    response.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()));
-                if !response.status().is_success() {
-                    let status = response.status();
-                    let headers = response.headers().clone();
-                    let body = response.text().await.unwrap_or_default();
-                    let _ = maybe_handle_oauth_required(
-                        &self.name,
-                        status,
-                        &headers,
-                        &body,
-                        self.oauth_authorization_url.as_deref(),
-                        self.http_url.as_deref(),
-                        self.http_client.as_ref(),
-                        self.oauth_client_id.as_deref(),
-                        self.oauth_scope.as_deref(),
-                        self.oauth_audience.as_deref(),
-                        self.oauth_extra_params.as_ref(),
-                        None, // token_url - will be derived from server_url
-                    )
-                    .await;
-                    return Err(anyhow::anyhow!(
-                        "MCP HTTP request failed for '{}' (HTTP {}): {}",
-                        self.name,
-                        status,
-                        body
-                    ));
-                }
-
-                // Extract session ID from response headers if present
-                if let Some(session_id) = response
-                    .headers()
-                    .get("mcp-session-id")
-                    .and_then(|v| v.to_str().ok())
-                {
-                    debug!("Received MCP session ID for '{}': {}", self.name, session_id);
-                    self.http_session_id = Some(session_id.to_string());
-                }
-
-                // Check Content-Type to determine how to parse the response
-                let content_type = response
-                    .headers()
-                    .get(reqwest::header::CONTENT_TYPE)
-                    .and_then(|v| v.to_str().ok())
-                    .unwrap_or("");
-
-                if content_type.contains("text/event-stream") {
-                    // Server returned SSE stream - read incrementally until we find the response
-                    // Don't use .text().await as that waits for the stream to close (which may never happen)
-                    let mut stream = response.bytes_stream();
-                    let mut buffer = String::new();
-                    let timeout_duration = std::time::Duration::from_secs(30);
-                    let start = std::time::Instant::now();
-
-                    while start.elapsed() < timeout_duration {
-                        match tokio::time::timeout(
-                            std::time::Duration::from_secs(5),
-                            stream.next()
-                        ).await {
-                            Ok(Some(Ok(bytes))) => {
-                                let text = String::from_utf8_lossy(&bytes);
-                                buffer.push_str(&text);
-
-                                // Normalize line endings
-                                if buffer.contains("\r\n") {
-                                    buffer = buffer.replace("\r\n", "\n");
-                                }
+        if self.http_url.is_some() {
+            // Streamable HTTP: the reply comes back in the POST response body, either as
+            // plain JSON or as an SSE stream that may carry other server messages first
+            let response = self
+                .post_http_message(&request_json, &request.method)
+                .await?;
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+            info!(
+                "Received HTTP response from '{}': status={}, content-type={}",
+                self.name,
+                response.status(),
+                content_type
+            );
 
-                                // Process complete SSE events (separated by double newline)
-                                while let Some(idx) = buffer.find("\n\n") {
-                                    let event = buffer[..idx].to_string();
-                                    buffer = buffer[idx + 2..].to_string();
-
-                                    // Extract data from SSE event
-                                    for line in event.lines() {
-                                        if let Some(data) = line.strip_prefix("data:") {
-                                            let data = data.trim();
-                                            if data.is_empty() || data == "[DONE]" {
-                                                continue;
-                                            }
-                                            debug!(
-                                                "MCP SSE data from '{}': {}",
-                                                self.name,
-                                                truncate_for_log(data, 500)
-                                            );
-                                            if let Ok(response) = serde_json::from_str::<McpResponse>(data) {
-                                                return Ok(response);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Ok(Some(Err(e))) => {
-                                return Err(anyhow::anyhow!(
-                                    "Error reading SSE stream from '{}': {}",
-                                    self.name,
-                                    e
-                                ));
-                            }
-                            Ok(None) => {
-                                // Stream closed without finding response
-                                break;
-                            }
-                            Err(_) => {
-                                // Timeout on chunk, continue if overall timeout not exceeded
-                                continue;
-                            }
-                        }
-                    }
+            if content_type.contains("text/event-stream") {
+                return self.read_sse_response(response, &id).await;
+            }
 
-                    // If no valid response found in SSE, return error
-                    return Err(anyhow::anyhow!(
-                        "MCP server '{}' returned SSE stream but no valid JSON-RPC response found. Buffer: {}",
-                        self.name,
-                        truncate_for_log(&buffer, 200)
-                    ));
-                } else {
-                    // Regular JSON response
-                    let response_json = response.text().await?;
-                    if response_json.trim().is_empty() {
-                        return Err(anyhow::anyhow!(
-                            "MCP server '{}' returned empty response",
-                            self.name
-                        ));
-                    }
-                    debug!(
-                        "MCP JSON response from '{}': {}",
-                        self.name,
-                        truncate_for_log(&response_json, 500)
-                    );
-                    let response: McpResponse = serde_json::from_str(&response_json)
-                        .map_err(|e| anyhow::anyhow!(
-                            "Failed to parse MCP response from '{}': {}. Response: {}",
-                            self.name,
-                            e,
-                            truncate_for_log(&response_json, 200)
-                        ))?;
-                    return Ok(response);
-                }
+            let response_json = response.text().await?;
+            if response_json.trim().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "MCP server '{}' returned empty response",
+                    self.name
+                ));
             }
+            debug!(
+                "MCP JSON response from '{}': {}",
+                self.name,
+                truncate_for_log(&response_json, 500)
+            );
+            let response: McpResponse = serde_json::from_str(&response_json).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to parse MCP response from '{}': {}. Response: {}",
+                    self.name,
+                    e,
+                    truncate_for_log(&response_json, 200)
+                )
+            })?;
+            return Ok(response);
         }
 
         // Create response channel
@@ -2461,7 +3074,14 @@ impl McpConnection {
         self.pending_requests.lock().await.insert(id.clone(), tx);
 
         // Send request
-        if let Some(writer) = &mut self.writer {
+        if self.legacy_sse_endpoint.is_some() {
+            // The reply arrives on the event stream and is routed through pending_requests
+            if let Err(e) = self.post_sse_message(&request_json).await {
+                self.pending_requests.lock().await.remove(&id);
+                return Err(e);
+            }
+        } else if let Some(writer) = &self.writer {
+            let mut writer = writer.lock().await;
             writer.write_all(request_json.as_bytes()).await?;
             writer.write_all(b"\n").await?;
             writer.flush().await?;
@@ -2471,13 +3091,22 @@ impl McpConnection {
             return Err(anyhow::anyhow!("No connection available"));
         }
 
-        // Wait for response with timeout
-        let response = tokio::time::timeout(
-            std::time::Duration::from_secs(30), // 30 second timeout
-            rx,
-        )
-        .await
-        .map_err(|_| anyhow::anyhow!("MCP server '{}' timed out after 30 seconds", self.name))?
+        // Wait for response with timeout; the server may be waiting on our answer to one
+        // of its own requests (e.g. a sampling prompt), so keep waiting while one is open
+        let mut rx = rx;
+        let response = loop {
+            match tokio::time::timeout(std::time::Duration::from_secs(30), &mut rx).await {
+                Ok(response) => break response,
+                Err(_) if self.server_requests_in_flight.load(Ordering::SeqCst) > 0 => continue,
+                Err(_) => {
+                    self.pending_requests.lock().await.remove(&id);
+                    return Err(anyhow::anyhow!(
+                        "MCP server '{}' timed out after 30 seconds",
+                        self.name
+                    ));
+                }
+            }
+        }
         .map_err(|_| anyhow::anyhow!("MCP server '{}' response channel was dropped", self.name))?;
 
         Ok(response)
@@ -2491,53 +3120,19 @@ impl McpConnection {
             self.name, notification_json
         );
 
-        if let Some(http_url) = &self.http_url {
-            let client = self
-                .http_client
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("HTTP client not initialized"))?;
-            let mut http_request = client
-                .post(http_url)
-                .header("content-type", "application/json")
-                .header("accept", "application/json, text/event-stream")
-                .body(notification_json);
-            if let Some(auth_header) = &self.http_auth_header {
-                http_request = http_request.header("authorization", auth_header);
-            }
-            if let Some(session_id) = &self.http_session_id {
-                http_request = http_request.header("mcp-session-id", session_id);
-            }
-            let response = http_request.send().await?;
-            if !response.status().is_success() {
-                let status = response.status();
-                let headers = response.headers().clone();
-                let body = response.text().await.unwrap_or_default();
-                let _ = maybe_handle_oauth_required(
-                    &self.name,
-                    status,
-                    &headers,
-                    &body,
-                    self.oauth_authorization_url.as_deref(),
-                    self.http_url.as_deref(),
-                    self.http_client.as_ref(),
-                    self.oauth_client_id.as_deref(),
-                    self.oauth_scope.as_deref(),
-                    self.oauth_audience.as_deref(),
-                    self.oauth_extra_params.as_ref(),
-                    None, // token_url - will be derived from server_url
-                )
-                .await;
-                return Err(anyhow::anyhow!(
-                    "MCP HTTP notification failed for '{}' (HTTP {}): {}",
-                    self.name,
-                    status,
-                    body
-                ));
-            }
+        if self.http_url.is_some() {
+            // Servers acknowledge notifications with 202 Accepted and no body
+            self.post_http_message(&notification_json, &notification.method)
+                .await?;
             return Ok(());
         }
 
-        if let Some(writer) = &mut self.writer {
+        if self.legacy_sse_endpoint.is_some() {
+            return self.post_sse_message(&notification_json).await;
+        }
+
+        if let Some(writer) = &self.writer {
+            let mut writer = writer.lock().await;
             writer.write_all(notification_json.as_bytes()).await?;
             writer.write_all(b"\n").await?;
             writer.flush().await?;
@@ -2550,6 +3145,299 @@ impl McpConnection {
         Ok(())
     }
 
+    /// Build a POST to the Streamable HTTP endpoint with auth and session headers attached
+    fn http_post(&self, body: &str) -> Result<reqwest::RequestBuilder> {
+        let http_url = self
+            .http_url
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("HTTP URL not initialized"))?;
+        let client = self
+            .http_client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("HTTP client not initialized"))?;
+        let mut request = client
+            .post(http_url)
+            .header("content-type", "application/json")
+            .header("accept", "application/json, text/event-stream")
+            .body(body.to_string());
+        if let Some(auth_header) = &self.http_auth_header {
+            request = request.header("authorization", auth_header);
+        }
+        if let Some(session_id) = &self.http_session_id {
+            request = request.header("mcp-session-id", session_id);
+        }
+        if let Some(protocol_version) = &self.http_protocol_version {
+            request = request.header("mcp-protocol-version", protocol_version);
+        }
+        Ok(request)
+    }
+
+    /// Context for answering requests the server sends, replying over the same transport
+    fn server_request_context(&self) -> ServerRequestContext {
+        let http_reply = |url: &String, headers: Vec<(&'static str, String)>| {
+            self.http_client.as_ref().map(|client| McpReplySink::Http {
+                client: client.clone(),
+                url: url.clone(),
+                headers,
+            })
+        };
+        let auth_headers = || {
+            self.http_auth_header
+                .iter()
+                .map(|value| ("authorization", value.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let reply = if let Some(writer) = &self.writer {
+            Some(McpReplySink::Stdio(writer.clone()))
+        } else if let Some(endpoint) = &self.legacy_sse_endpoint {
+            http_reply(endpoint, auth_headers())
+        } else if let Some(url) = &self.http_url {
+            let mut headers = auth_headers();
+            if let Some(session_id) = &self.http_session_id {
+                headers.push(("mcp-session-id", session_id.clone()));
+            }
+            if let Some(protocol_version) = &self.http_protocol_version {
+                headers.push(("mcp-protocol-version", protocol_version.clone()));
+            }
+            http_reply(url, headers)
+        } else {
+            None
+        };
+
+        ServerRequestContext {
+            name: self.name.clone(),
+            sampling_policy: self.sampling_policy,
+            sampling_handler: self.sampling_handler.clone(),
+            reply,
+            in_flight: self.server_requests_in_flight.clone(),
+        }
+    }
+
+    /// Refresh the stored token after the server rejected the current one
+    async fn refresh_stored_oauth_token(&self) -> Option<String> {
+        let store = self.token_store.as_ref()?;
+        let server_url = self.http_url.as_deref()?;
+        load_stored_oauth_token(store, &self.name, server_url, true)
+            .await
+            .map(|token| token.header_value())
+    }
+
+    async fn save_oauth_credentials(&self, entry: &OAuthTokenCacheEntry) {
+        if let Some(store) = &self.token_store {
+            save_oauth_credentials(store, &self.name, entry).await;
+        }
+    }
+
+    /// POST a JSON-RPC message to the Streamable HTTP endpoint.
+    ///
+    /// Retries once when an OAuth challenge yields a token, and re-initializes the
+    /// session when the server reports it expired (HTTP 404 with a session ID).
+    async fn post_http_message(
+        &mut self,
+        body: &str,
+        method: &McpMethod,
+    ) -> Result<reqwest::Response> {
+        let mut retried = false;
+        loop {
+            info!(
+                "Sending HTTP POST to '{}', url={}",
+                self.name,
+                self.http_url.as_deref().unwrap_or_default()
+            );
+            let response = self.http_post(body)?.send().await?;
+            let status = response.status();
+
+            if status.is_success() {
+                if let Some(session_id) = response
+                    .headers()
+                    .get("mcp-session-id")
+                    .and_then(|v| v.to_str().ok())
+                {
+                    if self.http_session_id.as_deref() != Some(session_id) {
+                        debug!(
+                            "Received MCP session ID for '{}': {}",
+                            self.name, session_id
+                        );
+                        self.http_session_id = Some(session_id.to_string());
+                    }
+                }
+                return Ok(response);
+            }
+
+            let can_reinitialize = !retried
+                && !self.http_reinitializing
+                && !matches!(method, McpMethod::Initialize { .. });
+            if status == StatusCode::NOT_FOUND && self.http_session_id.is_some() && can_reinitialize
+            {
+                warn!(
+                    "MCP session for '{}' expired, starting a new session",
+                    self.name
+                );
+                retried = true;
+                self.http_session_id = None;
+                self.http_protocol_version = None;
+                self.http_reinitializing = true;
+                let result = Box::pin(self.initialize()).await;
+                self.http_reinitializing = false;
+                result?;
+                continue;
+            }
+
+            let headers = response.headers().clone();
+            let response_body = response.text().await.unwrap_or_default();
+            if status == StatusCode::UNAUTHORIZED && !retried {
+                if let Some(header_value) = self.refresh_stored_oauth_token().await {
+                    retried = true;
+                    self.http_auth_header = Some(header_value);
+                    continue;
+                }
+            }
+            let oauth_result = maybe_handle_oauth_required(
+                &self.name,
+                status,
+                &headers,
+                &response_body,
+                self.oauth_authorization_url.as_deref(),
+                self.http_url.as_deref(),
+                self.http_client.as_ref(),
+                self.oauth_client_id.as_deref(),
+                self.oauth_scope.as_deref(),
+                self.oauth_audience.as_deref(),
+                self.oauth_extra_params.as_ref(),
+                None, // token_url - will be derived from server_url
+            )
+            .await;
+            if let OAuthHandleResult::Token(token_entry) = oauth_result {
+                if !retried {
+                    debug!("Using OAuth token for HTTP requests to {}", self.name);
+                    self.save_oauth_credentials(&token_entry).await;
+                    retried = true;
+                    self.http_auth_header = Some(token_entry.header_value);
+                    continue;
+                }
+            }
+
+            return Err(McpHttpStatusError {
+                name: self.name.clone(),
+                status,
+                body: response_body,
+            }
+            .into());
+        }
+    }
+
+    /// Read an SSE response stream until the reply to request `id` arrives.
+    /// Don't use .text().await as that waits for the stream to close (which may never happen).
+    async fn read_sse_response(
+        &self,
+        response: reqwest::Response,
+        id: &str,
+    ) -> Result<McpResponse> {
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+        let timeout_duration = std::time::Duration::from_secs(30);
+        let start = std::time::Instant::now();
+
+        while start.elapsed() < timeout_duration
+            || self.server_requests_in_flight.load(Ordering::SeqCst) > 0
+        {
+            match tokio::time::timeout(std::time::Duration::from_secs(5), stream.next()).await {
+                Ok(Some(Ok(bytes))) => {
+                    buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+                    // Normalize line endings
+                    if buffer.contains("\r\n") {
+                        buffer = buffer.replace("\r\n", "\n");
+                    }
+
+                    // Process complete SSE events (separated by double newline)
+                    while let Some(idx) = buffer.find("\n\n") {
+                        let event = buffer[..idx].to_string();
+                        buffer = buffer[idx + 2..].to_string();
+
+                        let data = match extract_sse_data(&event) {
+                            Some(data) => data,
+                            None => continue,
+                        };
+                        let data = data.trim();
+                        if data.is_empty() || data == "[DONE]" {
+                            continue;
+                        }
+                        debug!(
+                            "MCP SSE data from '{}': {}",
+                            self.name,
+                            truncate_for_log(data, 500)
+                        );
+                        if let Some(reply) = self.dispatch_http_message(data, id).await {
+                            return Ok(reply);
+                        }
+                    }
+                }
+                Ok(Some(Err(e))) => {
+                    return Err(anyhow::anyhow!(
+                        "Error reading SSE stream from '{}': {}",
+                        self.name,
+                        e
+                    ));
+                }
+                // Stream closed without finding response
+                Ok(None) => break,
+                // Timeout on chunk, continue if overall timeout not exceeded
+                Err(_) => continue,
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "MCP server '{}' returned SSE stream but no valid JSON-RPC response found. Buffer: {}",
+            self.name,
+            truncate_for_log(&buffer, 200)
+        ))
+    }
+
+    /// Handle one JSON-RPC message from a response stream, returning it if it is the
+    /// reply to request `id`. Servers may interleave notifications and requests of
+    /// their own before the reply.
+    async fn dispatch_http_message(&self, data: &str, id: &str) -> Option<McpResponse> {
+        let value: Value = match serde_json::from_str(data) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Failed to parse MCP SSE message from {}: {}", self.name, e);
+                return None;
+            }
+        };
+        if is_server_request(&value) {
+            spawn_server_request(&self.server_request_context(), value);
+            return None;
+        }
+        if let Some(method) = value.get("method").and_then(|m| m.as_str()) {
+            debug!(
+                "MCP server '{}' sent '{}' on the response stream",
+                self.name, method
+            );
+            return None;
+        }
+        let response: McpResponse = match serde_json::from_value(value) {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Unexpected MCP message from {}: {}", self.name, e);
+                return None;
+            }
+        };
+        if response.id.as_deref() == Some(id) {
+            return Some(response);
+        }
+        handle_mcp_response(
+            &self.name,
+            response,
+            self.pending_requests.clone(),
+            self.tools.clone(),
+            self.tools_version.clone(),
+        )
+        .await;
+        None
+    }
+
     pub async fn get_tools(&self) -> Vec<McpTool> {
         self.tools.read().await.clone()
     }
@@ -2573,10 +3461,25 @@ impl McpConnection {
             let _ = cancel.send(());
         }
 
+        if let (Some(client), Some(url), Some(session_id)) =
+            (&self.http_client, &self.http_url, &self.http_session_id)
+        {
+            // Explicitly end the Streamable HTTP session; servers may answer 405 if unsupported
+            let mut request = client.delete(url).header("mcp-session-id", session_id);
+            if let Some(auth_header) = &self.http_auth_header {
+                request = request.header("authorization", auth_header);
+            }
+            if let Err(e) = request.send().await {
+                debug!("Failed to end MCP session for {}: {}", self.name, e);
+            }
+        }
+
         self.http_url = None;
         self.http_client = None;
         self.http_auth_header = None;
         self.http_session_id = None;
+        self.http_protocol_version = None;
+        self.legacy_sse_endpoint = None;
         self.sse_enabled = false;
 
         debug!("Disconnected from MCP server {}", self.name);
@@ -2590,6 +3493,15 @@ pub struct McpManager {
     config: Arc<RwLock<McpConfig>>,
     config_path: Option<PathBuf>,
     oauth_tokens: Arc<Mutex<HashMap<String, OAuthTokenCacheEntry>>>,
+    token_store: McpTokenStore,
+    sampling_handler: SamplingHandlerSlot,
+    tool_cache: McpToolCache,
+    /// Cached tools of lazy servers that have not been connected yet
+    lazy_tools: Arc<RwLock<HashMap<String, Vec<McpTool>>>>,
+    lazy_tools_version: Arc<AtomicU64>,
+    lazy_connect_lock: Arc<Mutex<()>>,
+    /// Servers defined by the project config, which are never saved globally
+    project_servers: Arc<RwLock<HashSet<String>>>,
 }
 
 impl McpManager {
@@ -2599,6 +3511,13 @@ impl McpManager {
             config: Arc::new(RwLock::new(McpConfig::default())),
             config_path: None,
             oauth_tokens: Arc::new(Mutex::new(HashMap::new())),
+            token_store: McpTokenStore::default(),
+            sampling_handler: SamplingHandlerSlot::default(),
+            tool_cache: McpToolCache::default(),
+            lazy_tools: Arc::new(RwLock::new(HashMap::new())),
+            lazy_tools_version: Arc::new(AtomicU64::new(0)),
+            lazy_connect_lock: Arc::new(Mutex::new(())),
+            project_servers: Arc::new(RwLock::new(HashSet::new())),
         }
     }
 
@@ -2607,8 +3526,15 @@ impl McpManager {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
             config: Arc::new(RwLock::new(McpConfig::default())),
+            token_store: McpTokenStore::new(config_path.with_file_name("mcp_tokens.json")),
+            tool_cache: McpToolCache::new(config_path.with_file_name("mcp_tool_cache.json")),
             config_path: Some(config_path),
             oauth_tokens: Arc::new(Mutex::new(HashMap::new())),
+            sampling_handler: SamplingHandlerSlot::default(),
+            lazy_tools: Arc::new(RwLock::new(HashMap::new())),
+            lazy_tools_version: Arc::new(AtomicU64::new(0)),
+            lazy_connect_lock: Arc::new(Mutex::new(())),
+            project_servers: Arc::new(RwLock::new(HashSet::new())),
         }
     }
 
@@ -2622,6 +3548,18 @@ impl McpManager {
         Ok(())
     }
 
+    /// Mark servers that came from the project config so saving leaves the
+    /// global config's own definitions alone
+    pub async fn set_project_servers(&self, names: Vec<String>) {
+        *self.project_servers.write().await = names.into_iter().collect();
+    }
+
+    /// Install the handler that answers servers' sampling requests. Connected
+    /// servers pick it up immediately.
+    pub async fn set_sampling_handler(&self, handler: Option<McpSamplingHandler>) {
+        *self.sampling_handler.0.write().await = handler;
+    }
+
     /// Save current MCP configuration to unified config file
     pub async fn save_to_config_file(&self) -> Result<()> {
         use crate::config::Config;
@@ -2634,8 +3572,20 @@ impl McpManager {
         // Load existing unified config to preserve other settings
         let mut unified_config = Config::load(config_path.as_deref()).await?;
 
-        // Update MCP configuration
-        let current_mcp_config = self.config.read().await.clone();
+        // Update MCP configuration, keeping project servers out of the global file
+        let mut current_mcp_config = self.config.read().await.clone();
+        for name in self.project_servers.read().await.iter() {
+            match unified_config.mcp.servers.get(name) {
+                Some(global) => {
+                    current_mcp_config
+                        .servers
+                        .insert(name.clone(), global.clone());
+                }
+                None => {
+                    current_mcp_config.servers.remove(name);
+                }
+            }
+        }
         unified_config.mcp = current_mcp_config;
 
         // Save unified config
@@ -2661,6 +3611,21 @@ impl McpManager {
         Ok(())
     }
 
+    /// Merge imported servers into the configuration and save it. Servers whose
+    /// name is already configured are left as they are.
+    pub async fn import_servers(
+        &self,
+        servers: Vec<(String, McpServerConfig)>,
+    ) -> Result<McpImportSummary> {
+        let mut config = self.config.write().await;
+        let summary = merge_servers(&mut config, servers);
+        drop(config);
+        if !summary.added.is_empty() {
+            self.save_to_config_file().await?;
+        }
+        Ok(summary)
+    }
+
     pub async fn remove_server(&self, name: &str) -> Result<()> {
         let mut config = self.config.write().await;
         config.servers.remove(name);
@@ -2673,10 +3638,47 @@ impl McpManager {
             let _ = connection.disconnect().await;
         }
         self.oauth_tokens.lock().await.remove(name);
+        if let Err(e) = self.token_store.remove(name).await {
+            warn!("Failed to remove stored OAuth token for '{}': {}", name, e);
+        }
+        self.clear_lazy_tools(name).await;
+        if let Err(e) = self.tool_cache.remove(name).await {
+            warn!("Failed to remove cached tools for '{}': {}", name, e);
+        }
 
         Ok(())
     }
 
+    /// Stop advertising the cached tools of a lazy server
+    async fn clear_lazy_tools(&self, name: &str) {
+        if self.lazy_tools.write().await.remove(name).is_some() {
+            self.lazy_tools_version.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Connect a lazy server the first time one of its tools is used
+    async fn ensure_connected(&self, name: &str) -> Result<()> {
+        if self.is_connected(name).await || !self.lazy_tools.read().await.contains_key(name) {
+            return Ok(());
+        }
+
+        let _guard = self.lazy_connect_lock.lock().await;
+        if self.is_connected(name).await {
+            return Ok(());
+        }
+        info!("Starting lazy MCP server '{}' on first use", name);
+        self.connect_server(name).await
+    }
+
+    /// Forget all OAuth tokens for a server so the next connection re-authorizes.
+    /// Returns whether a persisted token was removed.
+    pub async fn logout_server(&self, name: &str) -> Result<bool> {
+        self.oauth_tokens.lock().await.remove(name);
+        let removed = self.token_store.remove(name).await?;
+        let _ = self.disconnect_server(name).await;
+        Ok(removed)
+    }
+
     pub async fn upsert_server(&self, name: &str, server_config: McpServerConfig) -> Result<()> {
         {
             let mut config = self.config.write().await;
@@ -2714,7 +3716,12 @@ impl McpManager {
             if url.starts_with("ws://") || url.starts_with("wss://") {
                 debug!("     Type: WebSocket");
             } else if url.starts_with("http://") || url.starts_with("https://") {
-                debug!("     Type: HTTP");
+                let transport = match server_config.transport {
+                    Some(McpTransport::Http) => "Streamable HTTP",
+                    Some(McpTransport::Sse) => "HTTP+SSE",
+                    None => "HTTP (auto-detect)",
+                };
+                debug!("     Type: {}", transport);
             } else {
                 debug!("     Type: URL");
             }
@@ -2753,6 +3760,9 @@ impl McpManager {
         debug!("     Enabled: {}", server_config.enabled);
 
         let mut connection = McpConnection::new(name.to_string());
+        connection.token_store = Some(self.token_store.clone());
+        connection.sampling_policy = server_config.sampling.unwrap_or_default();
+        connection.sampling_handler = self.sampling_handler.clone();
         if let Some(McpAuthConfig::OAuth(oauth)) = &server_config.auth {
             connection.oauth_authorization_url = oauth.authorization_url.clone();
             connection.oauth_client_id = Some(oauth.client_id.clone());
@@ -2771,6 +3781,11 @@ impl McpManager {
             } else {
                 self.oauth_header_for_server(name, server_config).await?
             }
+        } else if let Some(url) = &server_config.url {
+            // Reuse a token from an earlier browser authorization, if any
+            load_stored_oauth_token(&self.token_store, name, url, false)
+                .await
+                .map(|token| token.header_value())
         } else {
             None
         };
@@ -2779,7 +3794,11 @@ impl McpManager {
             if url.starts_with("ws://") || url.starts_with("wss://") {
                 connection.connect_websocket(url, auth_header).await?;
             } else if url.starts_with("http://") || url.starts_with("https://") {
-                connection.connect_http(url, auth_header).await?;
+                match server_config.transport {
+                    Some(McpTransport::Http) => connection.connect_http(url, auth_header).await?,
+                    Some(McpTransport::Sse) => connection.connect_sse(url, auth_header).await?,
+                    None => connection.connect_http_auto(url, auth_header).await?,
+                }
             } else {
                 return Err(anyhow::anyhow!(
                     "Server '{}' URL must start with ws://, wss://, http://, or https://",
@@ -2789,7 +3808,13 @@ impl McpManager {
         } else if let Some(command) = &server_config.command {
             // Connect via stdio
             let args = server_config.args.as_deref().unwrap_or(&[]);
-            let env_vars = server_config.env.as_ref().cloned().unwrap_or_default();
+            // Resolve env:/keyring: references only now, so secrets never sit in the config
+            let configured_env = server_config.env.as_ref().cloned().unwrap_or_default();
+            let env_vars = tokio::task::spawn_blocking(move || {
+                crate::secrets::resolve_env_secrets(&configured_env)
+            })
+            .await?
+            .map_err(|e| anyhow::anyhow!("Server '{}': {}", name, e))?;
             connection.connect_stdio(command, args, &env_vars).await?;
         } else {
             return Err(anyhow::anyhow!(
@@ -2798,12 +3823,20 @@ impl McpManager {
             ));
         }
 
+        let tools = connection.get_tools().await;
         self.connections
             .write()
             .await
             .insert(name.to_string(), connection);
         info!("✅ Successfully connected to MCP server: {}", name);
 
+        self.clear_lazy_tools(name).await;
+        if server_config.lazy {
+            if let Err(e) = self.tool_cache.save(name, server_config, tools).await {
+                warn!("Failed to cache tools for MCP server '{}': {}", name, e);
+            }
+        }
+
         // Log summary of available tools (debug level)
         if let Some(connection) = self.connections.read().await.get(name) {
             let tools = connection.get_tools().await;
@@ -2847,7 +3880,16 @@ impl McpManager {
             }
         }
 
+        if oauth.grant_type == McpOAuthGrantType::AuthorizationCode {
+            if let Some(stored) =
+                load_stored_oauth_token(&self.token_store, server_name, server_url, false).await
+            {
+                return Ok(Some(stored.header_value()));
+            }
+        }
+
         let entry = self.fetch_oauth_token(server_name, server_url, oauth).await?;
+        save_oauth_credentials(&self.token_store, server_name, &entry).await;
         let mut cache = self.oauth_tokens.lock().await;
         cache.insert(server_name.to_string(), entry.clone());
         Ok(Some(entry.header_value))
@@ -2875,6 +3917,7 @@ impl McpManager {
 
                 perform_oauth_authorization_flow(
                     server_name,
+                    Some(server_url),
                     authorization_url,
                     &token_url,
                     Some(&oauth.client_id),
@@ -2951,12 +3994,15 @@ impl McpManager {
                 Ok(OAuthTokenCacheEntry {
                     header_value,
                     expires_at,
+                    credentials: None,
                 })
             }
         }
     }
 
     pub async fn disconnect_server(&self, name: &str) -> Result<()> {
+        self.clear_lazy_tools(name).await;
+
         // First, try to remove and disconnect the connection
         let mut connections = self.connections.write().await;
         if let Some(mut connection) = connections.remove(name) {
@@ -2974,6 +4020,48 @@ impl McpManager {
         }
     }
 
+    /// Replace the server definitions with ones from a reloaded config without
+    /// saving them. Removed servers are disconnected, and new or changed ones
+    /// are (re)connected; untouched servers keep their connections.
+    pub async fn reload_servers(&self, mcp_config: McpConfig) {
+        let previous = std::mem::replace(&mut *self.config.write().await, mcp_config.clone());
+
+        for name in previous.servers.keys() {
+            if !mcp_config.servers.contains_key(name) {
+                self.clear_lazy_tools(name).await;
+                if let Some(mut connection) = self.connections.write().await.remove(name) {
+                    let _ = connection.disconnect().await;
+                }
+            }
+        }
+
+        for (name, server_config) in &mcp_config.servers {
+            let unchanged = previous.servers.get(name).is_some_and(|old| {
+                serde_json::to_value(old).ok() == serde_json::to_value(server_config).ok()
+            });
+            if unchanged {
+                continue;
+            }
+            let _ = self.disconnect_server(name).await;
+            if !server_config.enabled {
+                continue;
+            }
+            match tokio::time::timeout(
+                std::time::Duration::from_secs(10),
+                self.connect_server(name),
+            )
+            .await
+            {
+                Ok(Ok(())) => debug!("Connected to reloaded MCP server: {}", name),
+                Ok(Err(e)) => warn!("Failed to connect to MCP server '{}': {}", name, e),
+                Err(_) => warn!(
+                    "MCP server '{}' connection timed out after 10 seconds",
+                    name
+                ),
+            }
+        }
+    }
+
     pub async fn reconnect_server(&self, name: &str) -> Result<()> {
         self.disconnect_server(name).await?;
         self.connect_server(name).await?;
@@ -3004,7 +4092,33 @@ impl McpManager {
             }
         }
 
-        Ok(all_tools)
+        for (name, tools) in self.lazy_tools.read().await.iter() {
+            if connections.contains_key(name) {
+                continue;
+            }
+            for tool in tools {
+                all_tools.push((name.clone(), tool.clone()));
+            }
+        }
+
+        Ok(all_tools)
+    }
+
+    /// All tools paired with the names they are exposed to the model under
+    pub async fn get_named_tools(&self) -> Result<Vec<NamedMcpTool>> {
+        let tools = self.get_all_tools().await?;
+        let config = self.config.read().await;
+        Ok(assign_mcp_tool_names(tools, &config.servers))
+    }
+
+    /// Server and tool name behind an exposed MCP tool name
+    pub async fn resolve_tool_name(&self, exposed_name: &str) -> Result<Option<(String, String)>> {
+        Ok(self
+            .get_named_tools()
+            .await?
+            .into_iter()
+            .find(|named| named.name == exposed_name)
+            .map(|named| (named.server_name, named.tool.name)))
     }
 
     pub async fn disconnect_all(&self) -> Result<()> {
@@ -3024,10 +4138,15 @@ impl McpManager {
         connections.contains_key(name)
     }
 
+    /// Whether a lazy server is advertising cached tools and waiting for first use
+    pub async fn is_deferred(&self, name: &str) -> bool {
+        self.lazy_tools.read().await.contains_key(name)
+    }
+
     /// Get the global tools version (sum of all connection versions)
     pub async fn get_tools_version(&self) -> u64 {
         let connections = self.connections.read().await;
-        let mut total_version = 0u64;
+        let mut total_version = self.lazy_tools_version.load(Ordering::SeqCst);
 
         for connection in connections.values() {
             total_version = total_version.wrapping_add(connection.get_tools_version().await);
@@ -3042,6 +4161,7 @@ impl McpManager {
         tool_name: &str,
         arguments: Option<Value>,
     ) -> Result<Value> {
+        self.ensure_connected(server_name).await?;
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(server_name) {
             connection.call_tool(tool_name, arguments).await
@@ -3051,6 +4171,7 @@ impl McpManager {
     }
 
     pub async fn list_resources(&self, server_name: &str) -> Result<Vec<McpResource>> {
+        self.ensure_connected(server_name).await?;
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(server_name) {
             connection.list_resources().await
@@ -3060,6 +4181,7 @@ impl McpManager {
     }
 
     pub async fn read_resource(&self, server_name: &str, uri: &str) -> Result<Value> {
+        self.ensure_connected(server_name).await?;
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(server_name) {
             connection.read_resource(uri).await
@@ -3069,6 +4191,7 @@ impl McpManager {
     }
 
     pub async fn list_prompts(&self, server_name: &str) -> Result<Vec<McpPrompt>> {
+        self.ensure_connected(server_name).await?;
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(server_name) {
             connection.list_prompts().await
@@ -3083,6 +4206,7 @@ impl McpManager {
         name: &str,
         arguments: Option<Value>,
     ) -> Result<Value> {
+        self.ensure_connected(server_name).await?;
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(server_name) {
             connection.get_prompt(name, arguments).await
@@ -3091,58 +4215,94 @@ impl McpManager {
         }
     }
 
-    pub async fn connect_all_enabled(&self) -> Result<()> {
-        let config = self.config.read().await;
-        let mut connected_count = 0;
-        let mut failed_count = 0;
-
-        debug!("🌐 Connecting to all enabled MCP servers...");
-        debug!("   Total servers configured: {}", config.servers.len());
-
-        for (name, server_config) in config.servers.iter() {
-            if server_config.enabled {
-                debug!("   Attempting to connect to: {}", name);
-
-                // Add timeout for individual server connections
-                let connect_result = tokio::time::timeout(
-                    std::time::Duration::from_secs(10), // 10 second timeout per server
-                    self.connect_server(name),
-                )
-                .await;
+    /// Connect to every enabled server at once. Lazy servers with cached
+    /// tools are left until they're used.
+    pub async fn connect_all_enabled(&self) -> Result<McpConnectSummary> {
+        let servers: Vec<(String, McpServerConfig)> = {
+            let config = self.config.read().await;
+            debug!("🌐 Connecting to all enabled MCP servers...");
+            debug!("   Total servers configured: {}", config.servers.len());
+            config
+                .servers
+                .iter()
+                .map(|(name, server)| (name.clone(), server.clone()))
+                .collect()
+        };
 
-                match connect_result {
-                    Ok(Ok(_)) => {
-                        connected_count += 1;
-                        debug!("✅ Connected to MCP server: {}", name);
-                    }
-                    Ok(Err(e)) => {
-                        failed_count += 1;
-                        warn!("❌ Failed to connect to MCP server '{}': {}", name, e);
+        let mut summary = McpConnectSummary::default();
+        let mut to_connect = Vec::new();
+        for (name, server_config) in &servers {
+            if !server_config.enabled {
+                debug!("⏭️  Skipping disabled server: {}", name);
+                continue;
+            }
+            if server_config.lazy {
+                match self.tool_cache.load(name, server_config).await {
+                    Ok(Some(tools)) => {
+                        debug!(
+                            "💤 Deferring MCP server '{}' until first use ({} cached tools)",
+                            name,
+                            tools.len()
+                        );
+                        self.lazy_tools.write().await.insert(name.clone(), tools);
+                        self.lazy_tools_version.fetch_add(1, Ordering::SeqCst);
+                        summary.deferred += 1;
+                        continue;
                     }
-                    Err(_) => {
-                        failed_count += 1;
-                        warn!(
-                            "⏰ MCP server '{}' connection timed out after 10 seconds",
+                    Ok(None) => {
+                        debug!(
+                            "   No cached tools for lazy server '{}', connecting now",
                             name
                         );
                     }
+                    Err(e) => {
+                        warn!("Failed to read cached tools for '{}': {}", name, e);
+                    }
+                }
+            }
+            to_connect.push(name.as_str());
+        }
+
+        // Connect to all servers at once so one slow server doesn't hold up the rest
+        let results = futures_util::future::join_all(to_connect.iter().map(|name| async move {
+            debug!("   Attempting to connect to: {}", name);
+            let result = tokio::time::timeout(
+                std::time::Duration::from_secs(10), // 10 second timeout per server
+                self.connect_server(name),
+            )
+            .await;
+            (*name, result)
+        }))
+        .await;
+        for (name, result) in results {
+            match result {
+                Ok(Ok(_)) => {
+                    summary.connected += 1;
+                    debug!("✅ Connected to MCP server: {}", name);
+                }
+                Ok(Err(e)) => {
+                    summary.failed.push(name.to_string());
+                    warn!("❌ Failed to connect to MCP server '{}': {}", name, e);
+                }
+                Err(_) => {
+                    summary.failed.push(name.to_string());
+                    warn!(
+                        "⏰ MCP server '{}' connection timed out after 10 seconds",
+                        name
+                    );
                 }
-            } else {
-                debug!("⏭️  Skipping disabled server: {}", name);
             }
         }
 
         debug!("📊 MCP Connection Summary:");
-        debug!("   Successfully connected: {}", connected_count);
-        debug!("   Failed connections: {}", failed_count);
-        debug!(
-            "   Skipped (disabled): {}",
-            config.servers.len() - connected_count - failed_count
-        );
+        debug!("   Successfully connected: {}", summary.connected);
+        debug!("   Failed connections: {}", summary.failed.len());
+        debug!("   Deferred (lazy): {}", summary.deferred);
 
-        if connected_count > 0 {
+        if summary.connected + summary.deferred > 0 {
             // Log total tools available across all servers (debug level)
             let all_tools = self.get_all_tools().await?;
+            summary.tools = all_tools.len();
             debug!(
                 "🛠️  Total tools available across all MCP servers: {}",
                 all_tools.len()
@@ -3162,7 +4322,41 @@ impl McpManager {
             }
         }
 
-        Ok(())
+        Ok(summary)
+    }
+}
+
+/// What [`McpManager::connect_all_enabled`] managed to do
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct McpConnectSummary {
+    pub connected: usize,
+    /// Lazy servers left unconnected until one of their cached tools is used
+    pub deferred: usize,
+    /// Servers that failed or timed out
+    pub failed: Vec<String>,
+    /// Tools available across connected and deferred servers
+    pub tools: usize,
+}
+
+impl McpConnectSummary {
+    /// One line for the status bar, e.g. "MCP: 3 servers, 42 tools (1 failed: github)"
+    pub fn describe(&self) -> String {
+        let servers = self.connected + self.deferred;
+        let mut line = format!(
+            "MCP: {} server{}, {} tool{}",
+            servers,
+            if servers == 1 { "" } else { "s" },
+            self.tools,
+            if self.tools == 1 { "" } else { "s" }
+        );
+        if !self.failed.is_empty() {
+            line.push_str(&format!(
+                " ({} failed: {})",
+                self.failed.len(),
+                self.failed.join(", ")
+            ));
+        }
+        line
     }
 }
 
@@ -3179,6 +4373,26 @@ mod tests {
     use std::collections::HashMap;
     use tempfile::TempDir;
 
+    #[test]
+    fn describes_connect_summary() {
+        let summary = McpConnectSummary {
+            connected: 2,
+            deferred: 1,
+            failed: vec!["github".to_string()],
+            tools: 42,
+        };
+        assert_eq!(
+            summary.describe(),
+            "MCP: 3 servers, 42 tools (1 failed: github)"
+        );
+        let single = McpConnectSummary {
+            connected: 1,
+            tools: 1,
+            ..Default::default()
+        };
+        assert_eq!(single.describe(), "MCP: 1 server, 1 tool");
+    }
+
     // Helper function to create a temporary config directory
     fn temp_config_dir() -> TempDir {
         tempfile::tempdir().expect("Failed to create temp dir")
@@ -3194,6 +4408,10 @@ mod tests {
             url: None,
             auth: None,
             enabled: true,
+            transport: None,
+            sampling: None,
+            lazy: false,
+            aliases: None,
         }
     }
 
@@ -3211,6 +4429,7 @@ mod tests {
                     }),
                     resources: None,
                     prompts: None,
+                    sampling: None,
                 },
                 client_info: McpClientInfo {
                     name: "flexorama".to_string(),
@@ -3245,6 +4464,7 @@ mod tests {
                     prompts: Some(McpPromptsCapability {
                         list_changed: Some(true),
                     }),
+                    sampling: None,
                 },
                 client_info: McpClientInfo {
                     name: "flexorama".to_string(),
@@ -3873,6 +5093,36 @@ mod tests {
         assert!(!manager.is_connected("server2").await);
     }
 
+    #[tokio::test]
+    async fn test_mcp_manager_reload_servers_keeps_unchanged_connections() {
+        let manager = McpManager::new();
+        let mut config = McpConfig::default();
+        for name in ["kept", "removed"] {
+            let mut server = test_server_config();
+            server.name = name.to_string();
+            config.servers.insert(name.to_string(), server);
+        }
+        manager.initialize(config.clone()).await.unwrap();
+        {
+            let mut connections = manager.connections.write().await;
+            for name in ["kept", "removed"] {
+                connections.insert(name.to_string(), McpConnection::new(name.to_string()));
+            }
+        }
+
+        config.servers.remove("removed");
+        let mut added = test_server_config();
+        added.name = "added".to_string();
+        added.enabled = false;
+        config.servers.insert("added".to_string(), added);
+        manager.reload_servers(config).await;
+
+        assert!(manager.is_connected("kept").await);
+        assert!(!manager.is_connected("removed").await);
+        assert!(manager.get_server("removed").await.is_none());
+        assert!(manager.get_server("added").await.is_some());
+    }
+
     #[tokio::test]
     async fn test_mcp_manager_call_tool_not_connected() {
         let manager = McpManager::new();
@@ -3896,6 +5146,7 @@ mod tests {
             prompts: Some(McpPromptsCapability {
                 list_changed: Some(true),
             }),
+            sampling: None,
         };
 
         let serialized = serde_json::to_value(&capabilities).unwrap();
@@ -3910,6 +5161,7 @@ mod tests {
             tools: None,
             resources: None,
             prompts: None,
+            sampling: None,
         };
 
         let serialized = serde_json::to_value(&capabilities).unwrap();
@@ -4067,4 +5319,747 @@ mod tests {
         let result = connection.disconnect().await;
         assert!(result.is_ok());
     }
+
+    /// Minimal Streamable HTTP MCP server: JSON replies, an SSE reply for tools/list with a
+    /// notification ahead of the response, 202 for notifications, and 404 for stale sessions
+    /// (method, mcp-session-id, mcp-protocol-version) for each request the server saw
+    type RequestLog = Vec<(String, Option<String>, Option<String>)>;
+
+    #[derive(Clone, Default)]
+    struct FakeHttpServer {
+        session: Arc<std::sync::Mutex<u32>>,
+        log: Arc<std::sync::Mutex<RequestLog>>,
+    }
+
+    async fn spawn_fake_http_server() -> (String, FakeHttpServer) {
+        use axum::http::{HeaderMap as AxumHeaderMap, StatusCode as AxumStatus};
+        use axum::response::IntoResponse;
+
+        let server = FakeHttpServer::default();
+        let post_state = server.clone();
+        let delete_state = server.clone();
+        let app = axum::Router::new().route(
+            "/mcp",
+            axum::routing::post(
+                move |headers: AxumHeaderMap, axum::Json(body): axum::Json<Value>| {
+                    let server = post_state.clone();
+                    async move {
+                        let method = body["method"].as_str().unwrap_or_default().to_string();
+                        let header = |name: &str| {
+                            headers
+                                .get(name)
+                                .and_then(|v| v.to_str().ok())
+                                .map(|v| v.to_string())
+                        };
+                        let session_header = header("mcp-session-id");
+                        server.log.lock().unwrap().push((
+                            method.clone(),
+                            session_header.clone(),
+                            header("mcp-protocol-version"),
+                        ));
+
+                        let current = format!("session-{}", server.session.lock().unwrap());
+                        if method == "initialize" {
+                            let mut session = server.session.lock().unwrap();
+                            *session += 1;
+                            let body = json!({
+                                "jsonrpc": "2.0",
+                                "id": body["id"],
+                                "result": {
+                                    "protocolVersion": "2025-03-26",
+                                    "capabilities": { "tools": {} },
+                                    "serverInfo": { "name": "fake", "version": "1.0" }
+                                }
+                            });
+                            return (
+                                [("mcp-session-id", format!("session-{}", *session))],
+                                axum::Json(body),
+                            )
+                                .into_response();
+                        }
+                        if session_header.as_deref() != Some(current.as_str()) {
+                            return AxumStatus::NOT_FOUND.into_response();
+                        }
+                        match method.as_str() {
+                            "notifications/initialized" => AxumStatus::ACCEPTED.into_response(),
+                            "tools/list" => {
+                                let notification = json!({
+                                    "jsonrpc": "2.0",
+                                    "method": "notifications/message",
+                                    "params": { "level": "info", "data": "listing" }
+                                });
+                                let reply = json!({
+                                    "jsonrpc": "2.0",
+                                    "id": body["id"],
+                                    "result": { "tools": [{
+                                        "name": "echo",
+                                        "description": "Echo input",
+                                        "inputSchema": { "type": "object", "properties": {} }
+                                    }]}
+                                });
+                                let stream =
+                                    format!("data: {}\n\ndata: {}\n\n", notification, reply);
+                                ([("content-type", "text/event-stream")], stream).into_response()
+                            }
+                            _ => axum::Json(json!({
+                                "jsonrpc": "2.0",
+                                "id": body["id"],
+                                "result": { "content": [{ "type": "text", "text": "hello" }] }
+                            }))
+                            .into_response(),
+                        }
+                    }
+                },
+            )
+            .get(|| async { AxumStatus::METHOD_NOT_ALLOWED })
+            .delete(move |headers: AxumHeaderMap| {
+                let server = delete_state.clone();
+                async move {
+                    let session = headers
+                        .get("mcp-session-id")
+                        .and_then(|v| v.to_str().ok())
+                        .map(|v| v.to_string());
+                    server
+                        .log
+                        .lock()
+                        .unwrap()
+                        .push(("DELETE".to_string(), session, None));
+                    AxumStatus::OK
+                }
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{}/mcp", addr), server)
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_session_lifecycle() {
+        let (url, server) = spawn_fake_http_server().await;
+        let mut connection = McpConnection::new("fake".to_string());
+        connection.connect_http(&url, None).await.unwrap();
+
+        assert_eq!(connection.http_session_id.as_deref(), Some("session-1"));
+        assert_eq!(
+            connection.http_protocol_version.as_deref(),
+            Some("2025-03-26")
+        );
+        assert!(!connection.sse_enabled);
+        let tools = connection.get_tools().await;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+
+        let result = connection.call_tool("echo", Some(json!({}))).await.unwrap();
+        assert_eq!(result["content"][0]["text"], "hello");
+
+        connection.disconnect().await.unwrap();
+        let log = server.log.lock().unwrap().clone();
+        let methods: Vec<&str> = log.iter().map(|(method, _, _)| method.as_str()).collect();
+        assert_eq!(
+            methods,
+            vec![
+                "initialize",
+                "notifications/initialized",
+                "tools/list",
+                "tools/call",
+                "DELETE"
+            ]
+        );
+        // Every message after initialize carries the session and negotiated protocol version
+        for (_, session, _) in &log[1..] {
+            assert_eq!(session.as_deref(), Some("session-1"));
+        }
+        for (_, _, version) in &log[1..4] {
+            assert_eq!(version.as_deref(), Some("2025-03-26"));
+        }
+    }
+
+    fn named_tool(name: &str) -> McpTool {
+        McpTool {
+            name: name.to_string(),
+            description: None,
+            input_schema: json!({ "type": "object" }),
+        }
+    }
+
+    #[test]
+    fn test_tool_names_namespace_collisions() {
+        let tools = vec![
+            ("git".to_string(), named_tool("hub_status")),
+            ("git_hub".to_string(), named_tool("status")),
+            ("files".to_string(), named_tool("read")),
+        ];
+
+        let names: Vec<String> = assign_mcp_tool_names(tools, &HashMap::new())
+            .into_iter()
+            .map(|named| named.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "mcp_files_read",
+                "mcp_git__hub_status",
+                "mcp_git_hub__status"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_aliases_take_priority() {
+        let mut linear = test_server_config();
+        linear.aliases = Some(HashMap::from([
+            ("search_issues".to_string(), "files_read".to_string()),
+            ("create_issue".to_string(), "new_ticket".to_string()),
+        ]));
+        let servers = HashMap::from([("linear".to_string(), linear)]);
+        let tools = vec![
+            ("files".to_string(), named_tool("read")),
+            ("linear".to_string(), named_tool("search_issues")),
+            ("linear".to_string(), named_tool("create_issue")),
+        ];
+
+        let named = assign_mcp_tool_names(tools, &servers);
+        let pairs: Vec<(&str, &str, &str)> = named
+            .iter()
+            .map(|n| {
+                (
+                    n.name.as_str(),
+                    n.server_name.as_str(),
+                    n.tool.name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("mcp_files__read", "files", "read"),
+                ("mcp_files_read", "linear", "search_issues"),
+                ("mcp_new_ticket", "linear", "create_issue"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_aliases_keep_first_tool() {
+        let mut server = test_server_config();
+        server.aliases = Some(HashMap::from([
+            ("a".to_string(), "same".to_string()),
+            ("b".to_string(), "same".to_string()),
+        ]));
+        let servers = HashMap::from([("s".to_string(), server)]);
+        let tools = vec![
+            ("s".to_string(), named_tool("b")),
+            ("s".to_string(), named_tool("a")),
+        ];
+
+        let named = assign_mcp_tool_names(tools, &servers);
+        assert_eq!(named.len(), 1);
+        assert_eq!(named[0].name, "mcp_same");
+        assert_eq!(named[0].tool.name, "a");
+    }
+
+    #[tokio::test]
+    async fn test_manager_resolves_aliased_tool_names() {
+        let manager = McpManager::new();
+        let mut server = test_server_config();
+        server.aliases = Some(HashMap::from([(
+            "search_issues".to_string(),
+            "find".to_string(),
+        )]));
+        let mut config = McpConfig::default();
+        config.servers.insert("my_tracker".to_string(), server);
+        manager.initialize(config).await.unwrap();
+        manager.lazy_tools.write().await.insert(
+            "my_tracker".to_string(),
+            vec![named_tool("search_issues"), named_tool("get_issue")],
+        );
+
+        assert_eq!(
+            manager.resolve_tool_name("mcp_find").await.unwrap(),
+            Some(("my_tracker".to_string(), "search_issues".to_string()))
+        );
+        assert_eq!(
+            manager
+                .resolve_tool_name("mcp_my_tracker_get_issue")
+                .await
+                .unwrap(),
+            Some(("my_tracker".to_string(), "get_issue".to_string()))
+        );
+        assert_eq!(
+            manager
+                .resolve_tool_name("mcp_my_tracker_search_issues")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_env_secret_fails_before_spawning() {
+        let manager = McpManager::new();
+        let mut server = test_server_config();
+        server.env = Some(HashMap::from([(
+            "API_TOKEN".to_string(),
+            "env:FLEXORAMA_MCP_TEST_UNSET_TOKEN".to_string(),
+        )]));
+        let mut config = McpConfig::default();
+        config.servers.insert("test-server".to_string(), server);
+        manager.initialize(config).await.unwrap();
+
+        let err = manager.connect_server("test-server").await.unwrap_err();
+        assert!(err.to_string().contains("API_TOKEN"));
+        assert!(!manager.is_connected("test-server").await);
+    }
+
+    fn lazy_http_server_config(url: &str) -> McpServerConfig {
+        McpServerConfig {
+            name: "fake".to_string(),
+            command: None,
+            args: None,
+            env: None,
+            url: Some(url.to_string()),
+            auth: None,
+            enabled: true,
+            transport: Some(McpTransport::Http),
+            sampling: None,
+            lazy: true,
+            aliases: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lazy_server_connects_on_first_tool_call() {
+        let (url, server) = spawn_fake_http_server().await;
+        let temp_dir = TempDir::new().unwrap();
+        let manager = McpManager::new_with_config_path(temp_dir.path().join("config.toml"));
+        let server_config = lazy_http_server_config(&url);
+        manager
+            .tool_cache
+            .save(
+                "fake",
+                &server_config,
+                vec![McpTool {
+                    name: "echo".to_string(),
+                    description: None,
+                    input_schema: json!({ "type": "object" }),
+                }],
+            )
+            .await
+            .unwrap();
+        let mut config = McpConfig::default();
+        config.servers.insert("fake".to_string(), server_config);
+        manager.initialize(config).await.unwrap();
+
+        manager.connect_all_enabled().await.unwrap();
+        assert!(!manager.is_connected("fake").await);
+        assert!(manager.is_deferred("fake").await);
+        assert!(server.log.lock().unwrap().is_empty());
+        let tools = manager.get_all_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].1.name, "echo");
+        let deferred_version = manager.get_tools_version().await;
+        assert!(deferred_version > 0);
+
+        let result = manager
+            .call_tool("fake", "echo", Some(json!({})))
+            .await
+            .unwrap();
+        assert_eq!(result["content"][0]["text"], "hello");
+        assert!(manager.is_connected("fake").await);
+        assert!(!manager.is_deferred("fake").await);
+        assert_eq!(manager.get_all_tools().await.unwrap().len(), 1);
+        assert_ne!(manager.get_tools_version().await, deferred_version);
+
+        manager.disconnect_all().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lazy_server_without_cache_connects_and_caches_tools() {
+        let (url, _server) = spawn_fake_http_server().await;
+        let temp_dir = TempDir::new().unwrap();
+        let manager = McpManager::new_with_config_path(temp_dir.path().join("config.toml"));
+        let server_config = lazy_http_server_config(&url);
+        let mut config = McpConfig::default();
+        config
+            .servers
+            .insert("fake".to_string(), server_config.clone());
+        manager.initialize(config).await.unwrap();
+
+        manager.connect_all_enabled().await.unwrap();
+        assert!(manager.is_connected("fake").await);
+        let cached = manager
+            .tool_cache
+            .load("fake", &server_config)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached[0].name, "echo");
+
+        manager.disconnect_all().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_reinitializes_expired_session() {
+        let (url, server) = spawn_fake_http_server().await;
+        let mut connection = McpConnection::new("fake".to_string());
+        connection.connect_http(&url, None).await.unwrap();
+
+        // Server restarts and forgets the session
+        *server.session.lock().unwrap() += 1;
+
+        let result = connection.call_tool("echo", None).await.unwrap();
+        assert_eq!(result["content"][0]["text"], "hello");
+        assert_eq!(connection.http_session_id.as_deref(), Some("session-3"));
+        let initializations = server
+            .log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(method, _, _)| method == "initialize")
+            .count();
+        assert_eq!(initializations, 2);
+    }
+
+    /// Minimal legacy HTTP+SSE MCP server: GET /sse announces /messages, and replies to
+    /// POSTed requests are pushed onto the event stream
+    async fn spawn_fake_sse_server() -> String {
+        use axum::http::StatusCode as AxumStatus;
+        use axum::response::IntoResponse;
+
+        let events: Arc<std::sync::Mutex<Option<tokio::sync::mpsc::Sender<String>>>> =
+            Arc::new(std::sync::Mutex::new(None));
+        let get_events = events.clone();
+        let app = axum::Router::new()
+            .route(
+                "/sse",
+                axum::routing::get(move || {
+                    let events = get_events.clone();
+                    async move {
+                        let (tx, rx) = tokio::sync::mpsc::channel::<String>(16);
+                        tx.send("event: endpoint\ndata: /messages?session=abc\n\n".to_string())
+                            .await
+                            .unwrap();
+                        *events.lock().unwrap() = Some(tx);
+                        let stream = tokio_stream::wrappers::ReceiverStream::new(rx)
+                            .map(Ok::<_, std::convert::Infallible>);
+                        (
+                            [("content-type", "text/event-stream")],
+                            axum::body::Body::from_stream(stream),
+                        )
+                            .into_response()
+                    }
+                }),
+            )
+            .route(
+                "/messages",
+                axum::routing::post(move |axum::Json(body): axum::Json<Value>| {
+                    let events = events.clone();
+                    async move {
+                        let result = match body["method"].as_str().unwrap_or_default() {
+                            "initialize" => json!({
+                                "protocolVersion": "2024-11-05",
+                                "capabilities": { "tools": {} },
+                                "serverInfo": { "name": "legacy", "version": "1.0" }
+                            }),
+                            "tools/list" => json!({ "tools": [{
+                                "name": "echo",
+                                "inputSchema": { "type": "object", "properties": {} }
+                            }]}),
+                            _ => json!({ "content": [{ "type": "text", "text": "from sse" }] }),
+                        };
+                        if !body["id"].is_null() {
+                            let reply =
+                                json!({ "jsonrpc": "2.0", "id": body["id"], "result": result });
+                            let sender = events.lock().unwrap().clone().unwrap();
+                            sender
+                                .send(format!("event: message\ndata: {}\n\n", reply))
+                                .await
+                                .unwrap();
+                        }
+                        AxumStatus::ACCEPTED
+                    }
+                }),
+            );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/sse", addr)
+    }
+
+    #[tokio::test]
+    async fn test_http_auto_detect_falls_back_to_legacy_sse() {
+        let url = spawn_fake_sse_server().await;
+        let mut connection = McpConnection::new("legacy".to_string());
+        connection.connect_http_auto(&url, None).await.unwrap();
+
+        assert!(connection.http_url.is_none());
+        assert!(connection.sse_enabled);
+        assert!(connection
+            .legacy_sse_endpoint
+            .as_deref()
+            .unwrap()
+            .ends_with("/messages?session=abc"));
+        assert_eq!(connection.get_tools().await.len(), 1);
+
+        let result = connection.call_tool("echo", None).await.unwrap();
+        assert_eq!(result["content"][0]["text"], "from sse");
+        connection.disconnect().await.unwrap();
+    }
+
+    #[test]
+    fn test_fallback_only_for_client_errors() {
+        let error = |status| {
+            anyhow::Error::from(McpHttpStatusError {
+                name: "test".to_string(),
+                status,
+                body: String::new(),
+            })
+        };
+        assert!(should_fallback_to_sse(&error(
+            StatusCode::METHOD_NOT_ALLOWED
+        )));
+        assert!(should_fallback_to_sse(&error(StatusCode::NOT_FOUND)));
+        assert!(!should_fallback_to_sse(&error(StatusCode::UNAUTHORIZED)));
+        assert!(!should_fallback_to_sse(&error(
+            StatusCode::INTERNAL_SERVER_ERROR
+        )));
+        assert!(!should_fallback_to_sse(&anyhow::anyhow!(
+            "connection refused"
+        )));
+    }
+
+    #[test]
+    fn test_mcp_server_config_transport_round_trip() {
+        let config: McpServerConfig = toml::from_str(
+            r#"
+            name = "legacy"
+            url = "https://example.com/sse"
+            enabled = true
+            transport = "sse"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.transport, Some(McpTransport::Sse));
+        let serialized = toml::to_string(&test_server_config()).unwrap();
+        assert!(!serialized.contains("transport"));
+    }
+
+    async fn spawn_fake_token_server(status: StatusCode) -> String {
+        use axum::http::StatusCode as AxumStatus;
+
+        let status = AxumStatus::from_u16(status.as_u16()).unwrap();
+        let app = axum::Router::new().route(
+            "/token",
+            axum::routing::post(
+                move |axum::Form(form): axum::Form<HashMap<String, String>>| async move {
+                    assert_eq!(form["grant_type"], "refresh_token");
+                    assert_eq!(form["refresh_token"], "old-refresh");
+                    assert_eq!(form["client_id"], "registered-client");
+                    (
+                        status,
+                        axum::Json(json!({
+                            "access_token": "new-access",
+                            "token_type": "bearer",
+                            "expires_in": 3600
+                        })),
+                    )
+                },
+            ),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/token", addr)
+    }
+
+    fn expired_stored_token(token_endpoint: String) -> StoredMcpToken {
+        StoredMcpToken {
+            server_url: "https://mcp.example.com/mcp".to_string(),
+            access_token: "old-access".to_string(),
+            token_type: Some("Bearer".to_string()),
+            refresh_token: Some("old-refresh".to_string()),
+            expires_at: Some(chrono::Utc::now().timestamp() - 60),
+            token_endpoint,
+            client_id: "registered-client".to_string(),
+            client_secret: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_stored_token_is_refreshed() {
+        let token_endpoint = spawn_fake_token_server(StatusCode::OK).await;
+        let temp_dir = TempDir::new().unwrap();
+        let store = McpTokenStore::new(temp_dir.path().join("mcp_tokens.json"));
+        store
+            .save("linear", &expired_stored_token(token_endpoint))
+            .await
+            .unwrap();
+
+        let token = load_stored_oauth_token(&store, "linear", "https://mcp.example.com/mcp", false)
+            .await
+            .unwrap();
+        assert_eq!(token.header_value(), "Bearer new-access");
+        // The server did not rotate the refresh token, so the old one is kept
+        assert_eq!(token.refresh_token.as_deref(), Some("old-refresh"));
+        assert!(!token.is_expired());
+
+        let saved = store
+            .load("linear", "https://mcp.example.com/mcp")
+            .await
+            .unwrap();
+        assert_eq!(saved, Some(token));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_refresh_discards_stored_token() {
+        let token_endpoint = spawn_fake_token_server(StatusCode::BAD_REQUEST).await;
+        let temp_dir = TempDir::new().unwrap();
+        let store = McpTokenStore::new(temp_dir.path().join("mcp_tokens.json"));
+        store
+            .save("linear", &expired_stored_token(token_endpoint))
+            .await
+            .unwrap();
+
+        assert!(
+            load_stored_oauth_token(&store, "linear", "https://mcp.example.com/mcp", false)
+                .await
+                .is_none()
+        );
+        assert!(store
+            .load("linear", "https://mcp.example.com/mcp")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    fn sampling_context(
+        policy: McpSamplingPolicy,
+        handler: Option<McpSamplingHandler>,
+    ) -> ServerRequestContext {
+        ServerRequestContext {
+            name: "tracker".to_string(),
+            sampling_policy: policy,
+            sampling_handler: SamplingHandlerSlot(Arc::new(RwLock::new(handler))),
+            reply: None,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn echo_sampling_handler() -> McpSamplingHandler {
+        Arc::new(|request: McpSamplingRequest| {
+            Box::pin(async move {
+                Ok(crate::mcp_sampling::McpSamplingResult {
+                    role: "assistant".to_string(),
+                    content: crate::mcp_sampling::SamplingContent::Text {
+                        text: format!(
+                            "{} approval={}",
+                            request.server_name, request.needs_approval
+                        ),
+                    },
+                    model: "test-model".to_string(),
+                    stop_reason: "endTurn".to_string(),
+                })
+            })
+        })
+    }
+
+    fn create_message_request() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "sampling/createMessage",
+            "params": {
+                "messages": [{ "role": "user", "content": { "type": "text", "text": "hi" } }],
+                "maxTokens": 50
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sampling_request_follows_server_policy() {
+        let ask = sampling_context(McpSamplingPolicy::Ask, Some(echo_sampling_handler()));
+        let reply = answer_server_request(&ask, create_message_request()).await;
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["result"]["content"]["text"], "tracker approval=true");
+        assert_eq!(reply["result"]["stopReason"], "endTurn");
+
+        let allow = sampling_context(McpSamplingPolicy::Allow, Some(echo_sampling_handler()));
+        let reply = answer_server_request(&allow, create_message_request()).await;
+        assert_eq!(reply["result"]["content"]["text"], "tracker approval=false");
+
+        let deny = sampling_context(McpSamplingPolicy::Deny, Some(echo_sampling_handler()));
+        let reply = answer_server_request(&deny, create_message_request()).await;
+        assert_eq!(reply["error"]["code"], -1);
+        assert!(reply.get("result").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_server_requests_without_handler_or_unknown_method() {
+        let context = sampling_context(McpSamplingPolicy::Ask, None);
+        let reply = answer_server_request(&context, create_message_request()).await;
+        assert_eq!(reply["error"]["code"], -32601);
+
+        let reply = answer_server_request(
+            &context,
+            json!({ "jsonrpc": "2.0", "id": "a", "method": "ping" }),
+        )
+        .await;
+        assert_eq!(reply["id"], "a");
+        assert_eq!(reply["result"], json!({}));
+
+        let reply = answer_server_request(
+            &context,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "roots/list" }),
+        )
+        .await;
+        assert_eq!(reply["error"]["code"], -32601);
+    }
+
+    #[test]
+    fn test_server_request_detection() {
+        assert!(is_server_request(&create_message_request()));
+        assert!(!is_server_request(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/tools/list_changed"
+        })));
+        assert!(!is_server_request(
+            &json!({ "jsonrpc": "2.0", "id": "1", "result": {} })
+        ));
+    }
+
+    #[test]
+    fn test_mcp_server_config_sampling_policy() {
+        let config: McpServerConfig = toml::from_str(
+            r#"
+            name = "tracker"
+            command = "tracker-mcp"
+            enabled = true
+            sampling = "deny"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.sampling, Some(McpSamplingPolicy::Deny));
+        assert_eq!(
+            test_server_config().sampling.unwrap_or_default(),
+            McpSamplingPolicy::Ask
+        );
+        let capabilities = serde_json::to_value(McpClientCapabilities {
+            tools: None,
+            resources: None,
+            prompts: None,
+            sampling: Some(McpSamplingCapability::default()),
+        })
+        .unwrap();
+        assert_eq!(capabilities["sampling"], json!({}));
+    }
 }
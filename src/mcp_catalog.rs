@@ -0,0 +1,309 @@
+use crate::config::{McpServerConfig, McpTransport};
+
+/// How a catalog server is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogTransport {
+    /// A local process started with a command and arguments
+    Stdio {
+        command: &'static str,
+        args: &'static [&'static str],
+    },
+    /// A hosted server; auth, if any, happens through OAuth on first connect
+    Remote {
+        url: &'static str,
+        transport: Option<McpTransport>,
+    },
+}
+
+/// A well-known MCP server the agent can suggest and set up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalogEntry {
+    /// Also the name the server is configured under
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Words a request for this integration is likely to contain
+    pub keywords: &'static [&'static str],
+    pub transport: CatalogTransport,
+    /// Environment variables a stdio server reads its credentials from
+    pub required_env: &'static [&'static str],
+}
+
+/// The servers Flexorama knows how to set up without further instructions
+pub const CATALOG: &[CatalogEntry] = &[
+    CatalogEntry {
+        name: "github",
+        description: "GitHub repositories, issues, pull requests and Actions runs",
+        keywords: &[
+            "github",
+            "issue",
+            "pull request",
+            "pr",
+            "repository",
+            "actions",
+        ],
+        transport: CatalogTransport::Remote {
+            url: "https://api.githubcopilot.com/mcp/",
+            transport: Some(McpTransport::Http),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "linear",
+        description: "Linear issues, projects and cycles",
+        keywords: &["linear", "issue", "ticket", "project", "cycle", "backlog"],
+        transport: CatalogTransport::Remote {
+            url: "https://mcp.linear.app/mcp",
+            transport: Some(McpTransport::Http),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "atlassian",
+        description: "Jira issues and Confluence pages",
+        keywords: &["jira", "confluence", "atlassian", "issue", "ticket", "wiki"],
+        transport: CatalogTransport::Remote {
+            url: "https://mcp.atlassian.com/v1/sse",
+            transport: Some(McpTransport::Sse),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "sentry",
+        description: "Sentry errors, issues and releases",
+        keywords: &[
+            "sentry",
+            "error",
+            "exception",
+            "crash",
+            "stack trace",
+            "release",
+        ],
+        transport: CatalogTransport::Remote {
+            url: "https://mcp.sentry.dev/mcp",
+            transport: Some(McpTransport::Http),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "notion",
+        description: "Notion pages and databases",
+        keywords: &["notion", "notes", "wiki", "knowledge base"],
+        transport: CatalogTransport::Remote {
+            url: "https://mcp.notion.com/mcp",
+            transport: Some(McpTransport::Http),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "stripe",
+        description: "Stripe customers, payments, invoices and subscriptions",
+        keywords: &[
+            "stripe",
+            "payment",
+            "invoice",
+            "subscription",
+            "customer",
+            "billing",
+        ],
+        transport: CatalogTransport::Remote {
+            url: "https://mcp.stripe.com",
+            transport: Some(McpTransport::Http),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "context7",
+        description: "Up-to-date documentation and code examples for libraries",
+        keywords: &[
+            "docs",
+            "documentation",
+            "library",
+            "api reference",
+            "framework",
+        ],
+        transport: CatalogTransport::Remote {
+            url: "https://mcp.context7.com/mcp",
+            transport: Some(McpTransport::Http),
+        },
+        required_env: &[],
+    },
+    CatalogEntry {
+        name: "slack",
+        description: "Slack channels, threads and messages",
+        keywords: &["slack", "channel", "message", "thread", "chat"],
+        transport: CatalogTransport::Stdio {
+            command: "npx",
+            args: &["-y", "@modelcontextprotocol/server-slack"],
+        },
+        required_env: &["SLACK_BOT_TOKEN", "SLACK_TEAM_ID"],
+    },
+    CatalogEntry {
+        name: "playwright",
+        description: "Drive a browser: open pages, click, fill forms and take screenshots",
+        keywords: &[
+            "browser",
+            "web page",
+            "website",
+            "screenshot",
+            "playwright",
+            "click",
+        ],
+        transport: CatalogTransport::Stdio {
+            command: "npx",
+            args: &["-y", "@playwright/mcp@latest"],
+        },
+        required_env: &[],
+    },
+];
+
+impl CatalogEntry {
+    /// The configuration `/mcp add` would write for this server
+    pub fn server_config(&self) -> McpServerConfig {
+        let (command, args, url, transport) = match self.transport {
+            CatalogTransport::Stdio { command, args } => (
+                Some(command.to_string()),
+                Some(args.iter().map(|arg| arg.to_string()).collect()),
+                None,
+                None,
+            ),
+            CatalogTransport::Remote { url, transport } => {
+                (None, None, Some(url.to_string()), transport)
+            }
+        };
+        McpServerConfig {
+            name: self.name.to_string(),
+            command,
+            args,
+            url,
+            env: None,
+            auth: None,
+            enabled: true,
+            transport,
+            sampling: None,
+            lazy: false,
+            aliases: None,
+        }
+    }
+
+    /// The command or URL, for showing the user what will be added
+    pub fn location(&self) -> String {
+        match self.transport {
+            CatalogTransport::Stdio { command, args } => {
+                format!("{} {}", command, args.join(" "))
+            }
+            CatalogTransport::Remote { url, .. } => url.to_string(),
+        }
+    }
+
+    /// Required environment variables that aren't set
+    pub fn missing_env(&self) -> Vec<&'static str> {
+        self.required_env
+            .iter()
+            .copied()
+            .filter(|var| std::env::var(var).map_or(true, |value| value.is_empty()))
+            .collect()
+    }
+
+    /// How well `query` matches this entry; 0 means not at all
+    fn score(&self, query: &str) -> usize {
+        let query = query.to_lowercase();
+        let mut score = 0;
+        if query
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word == self.name)
+        {
+            score += 10;
+        }
+        for keyword in self.keywords {
+            if contains_phrase(&query, keyword) {
+                score += 1;
+            }
+        }
+        score
+    }
+}
+
+/// Whether `phrase` appears in `text` as whole words, allowing a plural
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    text.match_indices(phrase).any(|(start, _)| {
+        let rest = &text[start + phrase.len()..];
+        let before = text[..start].chars().next_back();
+        let after = rest.strip_prefix('s').unwrap_or(rest).chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// The catalog entry called `name`
+pub fn find(name: &str) -> Option<&'static CatalogEntry> {
+    CATALOG
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Catalog entries matching `query`, best first. An empty query lists them all.
+pub fn search(query: &str) -> Vec<&'static CatalogEntry> {
+    if query.trim().is_empty() {
+        return CATALOG.iter().collect();
+    }
+    let mut matches: Vec<_> = CATALOG
+        .iter()
+        .map(|entry| (entry.score(query), entry))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_integrations_rank_first() {
+        let matches = search("Check my Linear issues");
+        assert_eq!(matches[0].name, "linear");
+        assert!(matches.iter().any(|entry| entry.name == "github"));
+
+        assert_eq!(search("open the page in a browser")[0].name, "playwright");
+        assert!(search("refactor this function").is_empty());
+        assert_eq!(search("").len(), CATALOG.len());
+    }
+
+    #[test]
+    fn keywords_match_whole_words() {
+        assert!(contains_phrase("review my pr please", "pr"));
+        assert!(contains_phrase("my open issues", "issue"));
+        assert!(!contains_phrase("improve the prompt", "pr"));
+        assert!(contains_phrase("a stack trace.", "stack trace"));
+    }
+
+    #[test]
+    fn builds_configs_like_mcp_add() {
+        let linear = find("Linear").unwrap().server_config();
+        assert_eq!(linear.name, "linear");
+        assert_eq!(linear.url.as_deref(), Some("https://mcp.linear.app/mcp"));
+        assert!(linear.command.is_none());
+        assert!(linear.enabled);
+
+        let slack = find("slack").unwrap();
+        let config = slack.server_config();
+        assert_eq!(config.command.as_deref(), Some("npx"));
+        assert_eq!(
+            config.args.unwrap(),
+            vec!["-y", "@modelcontextprotocol/server-slack"]
+        );
+        assert_eq!(
+            slack.location(),
+            "npx -y @modelcontextprotocol/server-slack"
+        );
+        assert!(find("nope").is_none());
+    }
+
+    #[test]
+    fn catalog_names_are_unique() {
+        let mut names: Vec<_> = CATALOG.iter().map(|entry| entry.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), CATALOG.len());
+    }
+}
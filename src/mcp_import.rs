@@ -0,0 +1,282 @@
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::{McpConfig, McpServerConfig, McpTransport};
+
+/// Where to import MCP server definitions from
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpImportSource {
+    /// Claude Desktop's `claude_desktop_config.json`
+    ClaudeDesktop,
+    /// Cursor's global `~/.cursor/mcp.json`
+    Cursor,
+    /// A `.mcp.json`-style file, such as the project-level one in the current directory
+    File(PathBuf),
+}
+
+impl McpImportSource {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "claude-desktop" | "claude" => Self::ClaudeDesktop,
+            "cursor" => Self::Cursor,
+            path => Self::File(PathBuf::from(path)),
+        }
+    }
+
+    pub fn path(&self) -> Result<PathBuf> {
+        match self {
+            Self::ClaudeDesktop => claude_desktop_config_path()
+                .ok_or_else(|| anyhow!("Could not determine the Claude Desktop config directory")),
+            Self::Cursor => dirs::home_dir()
+                .map(|home| home.join(".cursor").join("mcp.json"))
+                .ok_or_else(|| anyhow!("Could not determine the home directory")),
+            Self::File(path) => Ok(path.clone()),
+        }
+    }
+}
+
+impl fmt::Display for McpImportSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClaudeDesktop => write!(f, "Claude Desktop"),
+            Self::Cursor => write!(f, "Cursor"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+fn claude_desktop_config_path() -> Option<PathBuf> {
+    // Claude Desktop keeps its config under ~/Library/Application Support on
+    // macOS, %APPDATA% on Windows and ~/.config elsewhere, which is what
+    // dirs::config_dir resolves to on each platform
+    dirs::config_dir().map(|dir| dir.join("Claude").join("claude_desktop_config.json"))
+}
+
+/// Top level of the JSON format shared by Claude Desktop, Cursor and `.mcp.json`
+#[derive(Debug, Deserialize)]
+struct ExternalMcpFile {
+    #[serde(default, rename = "mcpServers", alias = "servers")]
+    servers: BTreeMap<String, ExternalMcpServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalMcpServer {
+    command: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    url: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+impl ExternalMcpServer {
+    fn into_config(self, name: &str) -> Result<McpServerConfig> {
+        if self.command.is_none() && self.url.is_none() {
+            return Err(anyhow!("server '{}' has neither a command nor a url", name));
+        }
+        if !self.headers.is_empty() {
+            warn!(
+                "Ignoring custom headers for imported MCP server '{}'; configure auth instead",
+                name
+            );
+        }
+
+        let transport = match self.kind.as_deref() {
+            Some("http") | Some("streamable-http") | Some("streamableHttp") => {
+                Some(McpTransport::Http)
+            }
+            Some("sse") => Some(McpTransport::Sse),
+            _ => None,
+        };
+
+        Ok(McpServerConfig {
+            name: name.to_string(),
+            command: self.command,
+            args: if self.args.is_empty() {
+                None
+            } else {
+                Some(self.args)
+            },
+            url: self.url,
+            env: if self.env.is_empty() {
+                None
+            } else {
+                Some(self.env)
+            },
+            auth: None,
+            enabled: !self.disabled,
+            transport,
+            sampling: None,
+            lazy: false,
+            aliases: None,
+        })
+    }
+}
+
+/// Parse the `mcpServers` map of an external config file. Entries that can't
+/// be converted are skipped with a warning.
+pub fn parse_mcp_servers(content: &str) -> Result<Vec<(String, McpServerConfig)>> {
+    let file: ExternalMcpFile = serde_json::from_str(content)?;
+    let mut servers = Vec::new();
+    for (name, entry) in file.servers {
+        match entry.into_config(&name) {
+            Ok(config) => servers.push((name, config)),
+            Err(e) => warn!("Skipping MCP server during import: {}", e),
+        }
+    }
+    Ok(servers)
+}
+
+/// Read the servers defined in `source`
+pub async fn load_servers(source: &McpImportSource) -> Result<Vec<(String, McpServerConfig)>> {
+    let path = source.path()?;
+    read_servers_file(&path).await
+}
+
+async fn read_servers_file(path: &Path) -> Result<Vec<(String, McpServerConfig)>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_mcp_servers(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Outcome of merging imported servers into the configuration
+#[derive(Debug, Default, PartialEq)]
+pub struct McpImportSummary {
+    pub added: Vec<String>,
+    /// Servers left untouched because the name is already configured
+    pub skipped: Vec<String>,
+}
+
+/// Add imported servers to `config`, keeping any existing server with the same name
+pub fn merge_servers(
+    config: &mut McpConfig,
+    servers: Vec<(String, McpServerConfig)>,
+) -> McpImportSummary {
+    let mut summary = McpImportSummary::default();
+    for (name, server) in servers {
+        if config.servers.contains_key(&name) {
+            summary.skipped.push(name);
+        } else {
+            config.servers.insert(name.clone(), server);
+            summary.added.push(name);
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CLAUDE_DESKTOP_CONFIG: &str = r#"{
+        "mcpServers": {
+            "filesystem": {
+                "command": "npx",
+                "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"],
+                "env": { "DEBUG": "1" }
+            },
+            "linear": { "type": "http", "url": "https://mcp.linear.app/mcp" },
+            "legacy": { "type": "sse", "url": "https://example.com/sse", "disabled": true },
+            "broken": { "args": ["nothing to run"] }
+        },
+        "globalShortcut": "Ctrl+Space"
+    }"#;
+
+    #[test]
+    fn parses_stdio_and_remote_servers() {
+        let servers = parse_mcp_servers(CLAUDE_DESKTOP_CONFIG).unwrap();
+        let names: Vec<&str> = servers.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["filesystem", "legacy", "linear"]);
+
+        let filesystem = &servers[0].1;
+        assert_eq!(filesystem.command.as_deref(), Some("npx"));
+        assert_eq!(filesystem.args.as_ref().unwrap().len(), 3);
+        assert_eq!(
+            filesystem
+                .env
+                .as_ref()
+                .unwrap()
+                .get("DEBUG")
+                .map(String::as_str),
+            Some("1")
+        );
+        assert!(filesystem.enabled);
+
+        let legacy = &servers[1].1;
+        assert_eq!(legacy.transport, Some(McpTransport::Sse));
+        assert!(!legacy.enabled);
+
+        let linear = &servers[2].1;
+        assert_eq!(linear.url.as_deref(), Some("https://mcp.linear.app/mcp"));
+        assert_eq!(linear.transport, Some(McpTransport::Http));
+        assert!(linear.args.is_none());
+    }
+
+    #[test]
+    fn accepts_servers_key_and_empty_files() {
+        let servers =
+            parse_mcp_servers(r#"{ "servers": { "git": { "command": "git-mcp" } } }"#).unwrap();
+        assert_eq!(servers.len(), 1);
+        assert!(parse_mcp_servers("{}").unwrap().is_empty());
+        assert!(parse_mcp_servers("not json").is_err());
+    }
+
+    #[test]
+    fn merge_keeps_existing_servers() {
+        let mut config = McpConfig::default();
+        let mut servers = parse_mcp_servers(CLAUDE_DESKTOP_CONFIG).unwrap();
+        let (_, mut existing) = servers.remove(0);
+        existing.command = Some("my-own-filesystem".to_string());
+        config.servers.insert("filesystem".to_string(), existing);
+
+        let summary = merge_servers(
+            &mut config,
+            parse_mcp_servers(CLAUDE_DESKTOP_CONFIG).unwrap(),
+        );
+        assert_eq!(summary.added, vec!["legacy", "linear"]);
+        assert_eq!(summary.skipped, vec!["filesystem"]);
+        assert_eq!(
+            config.servers["filesystem"].command.as_deref(),
+            Some("my-own-filesystem")
+        );
+    }
+
+    #[tokio::test]
+    async fn loads_project_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".mcp.json");
+        std::fs::write(&path, CLAUDE_DESKTOP_CONFIG).unwrap();
+
+        let source = McpImportSource::parse(path.to_str().unwrap());
+        assert_eq!(source, McpImportSource::File(path.clone()));
+        assert_eq!(load_servers(&source).await.unwrap().len(), 3);
+
+        let missing = McpImportSource::File(dir.path().join("missing.json"));
+        assert!(load_servers(&missing).await.is_err());
+    }
+
+    #[test]
+    fn parses_named_sources() {
+        assert_eq!(
+            McpImportSource::parse("claude-desktop"),
+            McpImportSource::ClaudeDesktop
+        );
+        assert_eq!(McpImportSource::parse("cursor"), McpImportSource::Cursor);
+        assert!(McpImportSource::Cursor
+            .path()
+            .unwrap()
+            .ends_with(".cursor/mcp.json"));
+    }
+}
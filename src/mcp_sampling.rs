@@ -0,0 +1,364 @@
+use anyhow::Result;
+use colored::Colorize;
+use dialoguer::Select;
+use futures_util::future::BoxFuture;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::anthropic::{ContentBlock, Message};
+use crate::i18n::{tr, tr_args};
+use crate::llm::{create_response_content, LlmClient};
+use crate::security::{BashSecurityManager, PermissionKind, PermissionPrompt};
+
+/// Longest message excerpt shown in the permission prompt
+const PROMPT_EXCERPT_CHARS: usize = 400;
+
+/// Answers a server's `sampling/createMessage` request using the configured provider
+pub type McpSamplingHandler =
+    Arc<dyn Fn(McpSamplingRequest) -> BoxFuture<'static, Result<McpSamplingResult>> + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SamplingContent {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SamplingMessage {
+    pub role: String,
+    pub content: SamplingContent,
+}
+
+/// Parameters of a `sampling/createMessage` request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpSamplingRequest {
+    /// Server that sent the request
+    #[serde(skip)]
+    pub server_name: String,
+    /// Whether the user must approve the request before it is sent
+    #[serde(skip)]
+    pub needs_approval: bool,
+    pub messages: Vec<SamplingMessage>,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    pub max_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpSamplingResult {
+    pub role: String,
+    pub content: SamplingContent,
+    pub model: String,
+    pub stop_reason: String,
+}
+
+impl McpSamplingRequest {
+    /// Convert the requested conversation into provider messages
+    pub fn to_llm_messages(&self) -> Result<Vec<Message>> {
+        self.messages
+            .iter()
+            .map(|message| {
+                let block = match &message.content {
+                    SamplingContent::Text { text } => ContentBlock::text(text.clone()),
+                    SamplingContent::Image { data, mime_type } => {
+                        ContentBlock::image(mime_type.clone(), data.clone())
+                    }
+                    SamplingContent::Audio { .. } => {
+                        return Err(anyhow::anyhow!(
+                            "Audio content is not supported in sampling requests"
+                        ))
+                    }
+                };
+                Ok(Message {
+                    role: message.role.clone(),
+                    content: vec![block],
+                })
+            })
+            .collect()
+    }
+
+    /// Human readable summary of what will be sent to the model
+    pub fn describe(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(system_prompt) = &self.system_prompt {
+            lines.push(format!(
+                "[system] {}",
+                excerpt(system_prompt, PROMPT_EXCERPT_CHARS)
+            ));
+        }
+        for message in &self.messages {
+            let content = match &message.content {
+                SamplingContent::Text { text } => excerpt(text, PROMPT_EXCERPT_CHARS),
+                SamplingContent::Image { mime_type, .. } => format!("<image {}>", mime_type),
+                SamplingContent::Audio { mime_type, .. } => format!("<audio {}>", mime_type),
+            };
+            lines.push(format!("[{}] {}", message.role, content));
+        }
+        lines.push(format!("(max {} tokens)", self.max_tokens));
+        lines.join("\n")
+    }
+}
+
+fn excerpt(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(max_chars).collect();
+        format!("{}…", truncated)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SamplingDecision {
+    AllowOnce,
+    AllowForSession,
+    Deny,
+}
+
+fn decision_from_selection(selection: Option<usize>) -> SamplingDecision {
+    match selection {
+        Some(0) => SamplingDecision::AllowOnce,
+        Some(1) => SamplingDecision::AllowForSession,
+        _ => SamplingDecision::Deny,
+    }
+}
+
+async fn ask_sampling_permission(
+    request: &McpSamplingRequest,
+    security_manager: &Arc<RwLock<BashSecurityManager>>,
+) -> SamplingDecision {
+    let options = vec![
+        tr("permission-sampling-allow-once"),
+        tr_args(
+            "permission-sampling-allow-session",
+            &[("server", &request.server_name)],
+        ),
+        tr("permission-sampling-deny"),
+    ];
+    let detail = request.describe();
+
+    let permission_handler = security_manager.read().await.permission_handler();
+    if let Some(handler) = permission_handler {
+        let prompt = PermissionPrompt {
+            kind: PermissionKind::Sampling,
+            summary: tr_args(
+                "permission-sampling-summary",
+                &[("server", &request.server_name)],
+            ),
+            detail,
+            options,
+        };
+        return decision_from_selection((handler)(prompt).await);
+    }
+
+    app_println!();
+    app_println!("{}", tr("permission-sampling-title").yellow().bold());
+    app_println!(
+        "{}",
+        tr_args(
+            "permission-sampling-intro",
+            &[("server", &request.server_name.cyan().to_string())]
+        )
+    );
+    for line in detail.lines() {
+        app_println!("  {}", line);
+    }
+    app_println!();
+
+    let result = tokio::task::spawn_blocking(move || {
+        Select::new()
+            .with_prompt(tr("permission-select-option"))
+            .items(&options)
+            .default(0)
+            .interact()
+    })
+    .await;
+
+    match result {
+        Ok(Ok(selection)) => decision_from_selection(Some(selection)),
+        Ok(Err(e)) => {
+            error!("Failed to get user input: {}", e);
+            SamplingDecision::Deny
+        }
+        Err(e) => {
+            error!("Task join error: {}", e);
+            SamplingDecision::Deny
+        }
+    }
+}
+
+/// Build a sampling handler that prompts through the agent's permission UI and
+/// answers with the given provider and model
+pub fn create_sampling_handler(
+    client: Arc<LlmClient>,
+    model: String,
+    temperature: f32,
+    security_manager: Arc<RwLock<BashSecurityManager>>,
+) -> McpSamplingHandler {
+    let allowed_servers = Arc::new(Mutex::new(HashSet::<String>::new()));
+
+    Arc::new(move |request: McpSamplingRequest| {
+        let client = client.clone();
+        let model = model.clone();
+        let security_manager = security_manager.clone();
+        let allowed_servers = allowed_servers.clone();
+
+        Box::pin(async move {
+            let preapproved = allowed_servers.lock().await.contains(&request.server_name);
+            if request.needs_approval && !preapproved {
+                match ask_sampling_permission(&request, &security_manager).await {
+                    SamplingDecision::AllowOnce => {}
+                    SamplingDecision::AllowForSession => {
+                        allowed_servers
+                            .lock()
+                            .await
+                            .insert(request.server_name.clone());
+                    }
+                    SamplingDecision::Deny => {
+                        info!(
+                            "User declined sampling request from MCP server '{}'",
+                            request.server_name
+                        );
+                        return Err(anyhow::anyhow!("User declined the sampling request"));
+                    }
+                }
+            }
+
+            let messages = request.to_llm_messages()?;
+            info!(
+                "Answering sampling request from MCP server '{}' with {}",
+                request.server_name, model
+            );
+            let response = client
+                .create_message(
+                    &model,
+                    messages,
+                    &[],
+                    request.max_tokens,
+                    request.temperature.unwrap_or(temperature),
+                    request.system_prompt.as_ref(),
+                    Arc::new(AtomicBool::new(false)),
+                )
+                .await?;
+
+            Ok(McpSamplingResult {
+                role: "assistant".to_string(),
+                content: SamplingContent::Text {
+                    text: create_response_content(&response.content),
+                },
+                model,
+                stop_reason: "endTurn".to_string(),
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_request() -> McpSamplingRequest {
+        serde_json::from_value(json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": "Summarize the issue" } },
+                { "role": "user", "content": { "type": "image", "data": "aGk=", "mimeType": "image/png" } }
+            ],
+            "systemPrompt": "You are terse",
+            "maxTokens": 200,
+            "modelPreferences": { "hints": [{ "name": "claude" }] }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_create_message_params() {
+        let request = sample_request();
+        assert_eq!(request.max_tokens, 200);
+        assert_eq!(request.system_prompt.as_deref(), Some("You are terse"));
+        assert!(request.temperature.is_none());
+
+        let messages = request.to_llm_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].content[0].text.as_deref(),
+            Some("Summarize the issue")
+        );
+        assert!(messages[1].content[0].is_image());
+    }
+
+    #[test]
+    fn describe_lists_messages_for_the_prompt() {
+        assert_eq!(
+            sample_request().describe(),
+            "[system] You are terse\n[user] Summarize the issue\n[user] <image image/png>\n(max 200 tokens)"
+        );
+    }
+
+    #[test]
+    fn audio_content_is_rejected() {
+        let request: McpSamplingRequest = serde_json::from_value(json!({
+            "messages": [
+                { "role": "user", "content": { "type": "audio", "data": "", "mimeType": "audio/wav" } }
+            ],
+            "maxTokens": 10
+        }))
+        .unwrap();
+        assert!(request.to_llm_messages().is_err());
+    }
+
+    #[test]
+    fn result_serializes_in_protocol_shape() {
+        let result = McpSamplingResult {
+            role: "assistant".to_string(),
+            content: SamplingContent::Text {
+                text: "done".to_string(),
+            },
+            model: "claude-sonnet".to_string(),
+            stop_reason: "endTurn".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            json!({
+                "role": "assistant",
+                "content": { "type": "text", "text": "done" },
+                "model": "claude-sonnet",
+                "stopReason": "endTurn"
+            })
+        );
+    }
+
+    #[test]
+    fn only_the_first_two_options_allow() {
+        assert_eq!(
+            decision_from_selection(Some(0)),
+            SamplingDecision::AllowOnce
+        );
+        assert_eq!(
+            decision_from_selection(Some(1)),
+            SamplingDecision::AllowForSession
+        );
+        assert_eq!(decision_from_selection(Some(2)), SamplingDecision::Deny);
+        assert_eq!(decision_from_selection(None), SamplingDecision::Deny);
+    }
+}
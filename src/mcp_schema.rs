@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+
+/// Types a JSON Schema `type` keyword may name
+const SCHEMA_TYPES: &[&str] = &[
+    "string", "number", "integer", "boolean", "object", "array", "null",
+];
+
+/// Keywords that carry no meaning for the model and that Gemini rejects outright
+const DROPPED_KEYWORDS: &[&str] = &["$schema", "$id", "$comment"];
+
+/// `format` values every provider accepts; anything else is removed
+const SUPPORTED_FORMATS: &[&str] = &["date-time", "enum", "int32", "int64", "float", "double"];
+
+/// Validate an MCP tool's input schema and repair the problems providers
+/// commonly reject. Schemas that can't be repaired are reported as errors so
+/// the tool can be skipped instead of breaking the whole request.
+pub fn sanitize_input_schema(schema: &Value) -> Result<Value> {
+    if schema.is_null() {
+        return Ok(json!({ "type": "object", "properties": {}, "required": [] }));
+    }
+
+    let mut schema = schema.clone();
+    let root = schema
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("input schema must be a JSON object"))?;
+    match root.get("type") {
+        None => {
+            root.insert("type".to_string(), json!("object"));
+        }
+        Some(Value::String(kind)) if kind == "object" => {}
+        Some(other) => {
+            return Err(anyhow!(
+                "input schema must describe an object, not {}",
+                other
+            ))
+        }
+    }
+    root.entry("properties").or_insert_with(|| json!({}));
+
+    sanitize_node(&mut schema, "inputSchema")?;
+    Ok(schema)
+}
+
+fn sanitize_node(node: &mut Value, path: &str) -> Result<()> {
+    // `true` is the "anything goes" schema
+    if node.as_bool() == Some(true) {
+        *node = json!({});
+        return Ok(());
+    }
+    let map = node
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("{} is not a schema object", path))?;
+
+    for keyword in DROPPED_KEYWORDS {
+        map.remove(*keyword);
+    }
+    repair_type(map, path)?;
+
+    let unsupported_format = map
+        .get("format")
+        .and_then(|f| f.as_str())
+        .is_some_and(|format| !SUPPORTED_FORMATS.contains(&format));
+    if unsupported_format {
+        map.remove("format");
+    }
+
+    if map.get("type").and_then(|t| t.as_str()) == Some("array") {
+        // OpenAI refuses array schemas without `items`
+        map.entry("items").or_insert_with(|| json!({}));
+    }
+
+    if let Some(properties) = map.get_mut("properties") {
+        let properties = properties
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("{}.properties must be an object", path))?;
+        properties.retain(|_, property| property.as_bool() != Some(false));
+        for (name, property) in properties.iter_mut() {
+            sanitize_node(property, &format!("{}.properties.{}", path, name))?;
+        }
+    }
+    repair_required(map);
+
+    if let Some(items) = map.get_mut("items") {
+        // Tuple-style `items` arrays aren't understood by the providers, so
+        // describe every element with the first entry
+        if let Some(tuple) = items.as_array() {
+            *items = tuple.first().cloned().unwrap_or_else(|| json!({}));
+        }
+        sanitize_node(items, &format!("{}.items", path))?;
+    }
+
+    if let Some(additional) = map.get_mut("additionalProperties") {
+        if additional.is_object() {
+            sanitize_node(additional, &format!("{}.additionalProperties", path))?;
+        }
+    }
+
+    for keyword in ["anyOf", "oneOf", "allOf"] {
+        if let Some(variants) = map.get_mut(keyword) {
+            let variants = variants
+                .as_array_mut()
+                .ok_or_else(|| anyhow!("{}.{} must be an array", path, keyword))?;
+            for (i, variant) in variants.iter_mut().enumerate() {
+                sanitize_node(variant, &format!("{}.{}[{}]", path, keyword, i))?;
+            }
+        }
+    }
+
+    for keyword in ["$defs", "definitions"] {
+        if let Some(Value::Object(definitions)) = map.get_mut(keyword) {
+            for (name, definition) in definitions.iter_mut() {
+                sanitize_node(definition, &format!("{}.{}.{}", path, keyword, name))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn repair_type(map: &mut Map<String, Value>, path: &str) -> Result<()> {
+    let kind = match map.get("type").cloned() {
+        Some(kind) => kind,
+        None => {
+            if map.contains_key("properties") {
+                map.insert("type".to_string(), json!("object"));
+            } else if map.contains_key("items") {
+                map.insert("type".to_string(), json!("array"));
+            }
+            return Ok(());
+        }
+    };
+
+    let kind = match kind {
+        Value::String(kind) => kind,
+        // `["string", "null"]` isn't accepted by Gemini; use the nullable flag instead
+        Value::Array(kinds) => {
+            let kinds: Vec<&str> = kinds.iter().filter_map(|k| k.as_str()).collect();
+            let non_null: Vec<&str> = kinds.iter().copied().filter(|k| *k != "null").collect();
+            let kind = match non_null.as_slice() {
+                [] => "null",
+                [kind] => *kind,
+                _ => {
+                    return Err(anyhow!(
+                        "{} has multiple types ({}); use anyOf instead",
+                        path,
+                        non_null.join(", ")
+                    ))
+                }
+            };
+            if kinds.contains(&"null") && kind != "null" {
+                map.insert("nullable".to_string(), json!(true));
+            }
+            kind.to_string()
+        }
+        other => return Err(anyhow!("{}.type must be a string, not {}", path, other)),
+    };
+
+    if !SCHEMA_TYPES.contains(&kind.as_str()) {
+        return Err(anyhow!("{} has unknown type '{}'", path, kind));
+    }
+    map.insert("type".to_string(), Value::String(kind));
+    Ok(())
+}
+
+/// Keep only required entries that name a declared property
+fn repair_required(map: &mut Map<String, Value>) {
+    let declared: Vec<String> = map
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .map(|p| p.keys().cloned().collect())
+        .unwrap_or_default();
+
+    match map.get_mut("required") {
+        Some(Value::Array(required)) => {
+            required.retain(|name| {
+                name.as_str()
+                    .is_some_and(|name| declared.iter().any(|d| d == name))
+            });
+        }
+        Some(_) => {
+            map.remove("required");
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_missing_types_and_defaults() {
+        assert_eq!(
+            sanitize_input_schema(&Value::Null).unwrap(),
+            json!({ "type": "object", "properties": {}, "required": [] })
+        );
+
+        let schema = sanitize_input_schema(&json!({
+            "properties": {
+                "tags": { "items": { "type": "string" } },
+                "ids": { "type": "array" },
+                "options": { "properties": { "verbose": { "type": "boolean" } } }
+            }
+        }))
+        .unwrap();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["tags"]["type"], "array");
+        assert_eq!(schema["properties"]["ids"]["items"], json!({}));
+        assert_eq!(schema["properties"]["options"]["type"], "object");
+    }
+
+    #[test]
+    fn strips_keywords_providers_reject() {
+        let schema = sanitize_input_schema(&json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "format": "uri" },
+                "since": { "type": "string", "format": "date-time" },
+                "note": { "type": ["string", "null"] },
+                "pair": { "type": "array", "items": [{ "type": "number" }, { "type": "number" }] }
+            },
+            "required": ["url", "missing", 3]
+        }))
+        .unwrap();
+
+        assert!(schema.get("$schema").is_none());
+        assert!(schema["properties"]["url"].get("format").is_none());
+        assert_eq!(schema["properties"]["since"]["format"], "date-time");
+        assert_eq!(
+            schema["properties"]["note"],
+            json!({ "type": "string", "nullable": true })
+        );
+        assert_eq!(
+            schema["properties"]["pair"]["items"],
+            json!({ "type": "number" })
+        );
+        assert_eq!(schema["required"], json!(["url"]));
+    }
+
+    #[test]
+    fn rejects_schemas_that_cannot_be_repaired() {
+        assert!(sanitize_input_schema(&json!("object")).is_err());
+        assert!(sanitize_input_schema(&json!({ "type": "string" })).is_err());
+        assert!(sanitize_input_schema(&json!({ "properties": [] })).is_err());
+
+        let err = sanitize_input_schema(&json!({
+            "type": "object",
+            "properties": { "count": { "type": "int" } }
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("inputSchema.properties.count"));
+    }
+}
@@ -0,0 +1,446 @@
+use anyhow::Result;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use futures_util::future::BoxFuture;
+use log::{debug, error, info, warn};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+
+use crate::config::Config;
+use crate::security::{
+    BashSecurityManager, FileSecurityManager, PermissionHandler, PermissionPrompt,
+};
+use crate::tools::{
+    create_bash_tool, create_create_directory_tool, create_create_pr_tool, create_delete_file_tool,
+    create_edit_file_tool, create_replace_in_files_tool, create_write_file_tool, get_builtin_tools,
+    Tool, ToolCall,
+};
+
+/// Protocol revision reported when the client does not ask for one
+const DEFAULT_PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Built-in tools that only make sense inside an agent conversation
+const AGENT_ONLY_TOOLS: &[&str] = &["create_todo", "complete_todo", "list_todos"];
+
+/// Exposes Flexorama's built-in tools to other MCP clients, enforcing the
+/// same bash and file security policies the agent uses
+pub struct McpToolServer {
+    tools: Vec<Tool>,
+}
+
+/// Nobody can answer a permission prompt while stdin carries the protocol, so
+/// anything outside the configured allowlists is refused
+fn deny_permission_handler() -> PermissionHandler {
+    Arc::new(
+        |prompt: PermissionPrompt| -> BoxFuture<'static, Option<usize>> {
+            warn!(
+                "Denied {} in MCP server mode: {}",
+                prompt.summary.to_lowercase(),
+                prompt.detail
+            );
+            Box::pin(async { None })
+        },
+    )
+}
+
+impl McpToolServer {
+    pub fn new(config: &Config, yolo_mode: bool) -> Self {
+        let mut bash_security_manager = BashSecurityManager::new(config.bash_security.clone());
+        bash_security_manager.set_permission_handler(Some(deny_permission_handler()));
+        let mut file_security_manager = FileSecurityManager::new(config.file_security.clone());
+        file_security_manager.set_permission_handler(Some(deny_permission_handler()));
+
+        let bash_security_manager = Arc::new(RwLock::new(bash_security_manager));
+        let file_security_manager = Arc::new(RwLock::new(file_security_manager));
+
+        let secured_tools = vec![
+            create_write_file_tool(file_security_manager.clone(), yolo_mode),
+            create_edit_file_tool(file_security_manager.clone(), yolo_mode),
+            create_replace_in_files_tool(file_security_manager.clone(), yolo_mode),
+            create_delete_file_tool(file_security_manager.clone(), yolo_mode),
+            create_create_directory_tool(file_security_manager, yolo_mode),
+            create_create_pr_tool(bash_security_manager.clone(), yolo_mode),
+            create_bash_tool(bash_security_manager, yolo_mode),
+        ];
+
+        // The builtin list carries unsecured fallbacks for these, so swap them out
+        let mut tools: Vec<Tool> = get_builtin_tools()
+            .into_iter()
+            .filter(|tool| !AGENT_ONLY_TOOLS.contains(&tool.name.as_str()))
+            .filter(|tool| {
+                !secured_tools
+                    .iter()
+                    .any(|secured| secured.name == tool.name)
+            })
+            .collect();
+        tools.extend(secured_tools);
+
+        Self { tools }
+    }
+
+    pub fn tool_names(&self) -> Vec<&str> {
+        self.tools.iter().map(|tool| tool.name.as_str()).collect()
+    }
+
+    /// Handle one JSON-RPC message, returning the response to send back.
+    /// Notifications and stray responses produce no reply.
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        let method = match message.get("method").and_then(|m| m.as_str()) {
+            Some(method) => method.to_string(),
+            None => {
+                debug!("Ignoring MCP message without a method");
+                return None;
+            }
+        };
+        let id = match message.get("id") {
+            Some(id) if !id.is_null() => id.clone(),
+            _ => {
+                debug!("Received MCP notification: {}", method);
+                return None;
+            }
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let outcome = match method.as_str() {
+            "initialize" => Ok(self.initialize_result(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool(&id, &params).await,
+            _ => Err((-32601, format!("Method not found: {}", method))),
+        };
+
+        Some(match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        })
+    }
+
+    fn initialize_result(&self, params: &Value) -> Value {
+        let protocol_version = params
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .unwrap_or(DEFAULT_PROTOCOL_VERSION);
+        json!({
+            "protocolVersion": protocol_version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": {
+                "name": "flexorama",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })
+    }
+
+    fn list_tools(&self) -> Value {
+        let tools: Vec<Value> = self
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema
+                })
+            })
+            .collect();
+        json!({ "tools": tools })
+    }
+
+    async fn call_tool(
+        &self,
+        id: &Value,
+        params: &Value,
+    ) -> std::result::Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| (-32602, "Missing tool name".to_string()))?;
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .ok_or_else(|| (-32602, format!("Unknown tool: {}", name)))?;
+
+        let call = ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments: params
+                .get("arguments")
+                .cloned()
+                .unwrap_or_else(|| json!({})),
+        };
+        info!("MCP client called tool '{}'", name);
+
+        // Tool failures are reported in the result so the calling model can see them
+        let (text, is_error) = match (tool.handler)(call).await {
+            Ok(result) => (result.content, result.is_error),
+            Err(e) => (e.to_string(), true),
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error
+        }))
+    }
+
+    /// Serve newline-delimited JSON-RPC on stdin/stdout until stdin closes
+    pub async fn run_stdio(self) -> Result<()> {
+        info!(
+            "MCP server ready on stdio with tools: {}",
+            self.tool_names().join(", ")
+        );
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+
+        while let Some(line) = lines.next_line().await? {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Value>(line) {
+                Ok(message) => self.handle_message(message).await,
+                Err(e) => {
+                    error!("Failed to parse MCP message: {}", e);
+                    Some(json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": { "code": -32700, "message": format!("Parse error: {}", e) }
+                    }))
+                }
+            };
+            if let Some(reply) = reply {
+                stdout.write_all(reply.to_string().as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+        }
+
+        info!("MCP client disconnected, shutting down");
+        Ok(())
+    }
+
+    /// Serve the Streamable HTTP transport on `127.0.0.1:<port>/mcp`
+    pub async fn run_http(self, port: u16) -> Result<()> {
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        info!(
+            "MCP server listening on http://{}/mcp with tools: {}",
+            addr,
+            self.tool_names().join(", ")
+        );
+        axum::serve(
+            tokio::net::TcpListener::bind(addr).await?,
+            http_router(Arc::new(self), port),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn http_router(server: Arc<McpToolServer>, port: u16) -> Router {
+    Router::new()
+        .route("/mcp", post(handle_http_message))
+        .with_state(server)
+        .layer(middleware::from_fn_with_state(port, local_requests_only))
+}
+
+/// Reject requests a web page could make through DNS rebinding or from
+/// another origin: the `Host` must be this server and any `Origin` local
+async fn local_requests_only(
+    State(port): State<u16>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let headers = request.headers();
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok());
+    let host_ok = host.is_some_and(|host| {
+        host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port)
+    });
+    let origin_ok = match headers.get(header::ORIGIN) {
+        None => true,
+        Some(origin) => origin
+            .to_str()
+            .ok()
+            .and_then(|origin| reqwest::Url::parse(origin).ok())
+            .is_some_and(|url| matches!(url.host_str(), Some("127.0.0.1" | "localhost"))),
+    };
+    if host_ok && origin_ok {
+        Ok(next.run(request).await)
+    } else {
+        warn!(
+            "Rejected MCP request with Host {:?} and Origin {:?}",
+            host,
+            headers.get(header::ORIGIN)
+        );
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+async fn handle_http_message(
+    State(server): State<Arc<McpToolServer>>,
+    Json(message): Json<Value>,
+) -> Response {
+    match server.handle_message(message).await {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_server() -> McpToolServer {
+        McpToolServer::new(&Config::default(), false)
+    }
+
+    #[tokio::test]
+    async fn initialize_advertises_tools() {
+        let reply = test_server()
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "protocolVersion": "2024-11-05", "capabilities": {} }
+            }))
+            .await
+            .unwrap();
+
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["protocolVersion"], "2024-11-05");
+        assert!(reply["result"]["capabilities"]["tools"].is_object());
+        assert_eq!(reply["result"]["serverInfo"]["name"], "flexorama");
+    }
+
+    #[tokio::test]
+    async fn lists_file_and_bash_tools_but_not_todos() {
+        let reply = test_server()
+            .handle_message(json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }))
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = reply["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        for expected in ["Read", "Edit", "Write", "search_in_files", "glob", "Bash"] {
+            assert!(names.contains(&expected), "missing {}", expected);
+        }
+        assert!(!names.contains(&"create_todo"));
+        assert!(reply["result"]["tools"][0]["inputSchema"].is_object());
+    }
+
+    #[tokio::test]
+    async fn calls_read_tool() {
+        let reply = test_server()
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": { "name": "Read", "arguments": { "path": "Cargo.toml" } }
+            }))
+            .await
+            .unwrap();
+
+        assert_eq!(reply["result"]["isError"], false);
+        assert!(reply["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("name = \"flexorama\""));
+    }
+
+    #[tokio::test]
+    async fn bash_outside_allowlist_is_denied() {
+        let reply = test_server()
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "tools/call",
+                "params": { "name": "Bash", "arguments": { "command": "touch /tmp/flexorama-mcp-serve" } }
+            }))
+            .await
+            .unwrap();
+
+        assert_eq!(reply["result"]["isError"], true);
+        assert!(reply["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Permission denied"));
+    }
+
+    #[tokio::test]
+    async fn http_rejects_foreign_hosts_and_origins() {
+        use tower::util::ServiceExt;
+
+        let router = http_router(Arc::new(test_server()), 8123);
+        let request = |host: &str, origin: Option<&str>| {
+            let mut request = Request::post("/mcp")
+                .header(header::HOST, host)
+                .header(header::CONTENT_TYPE, "application/json");
+            if let Some(origin) = origin {
+                request = request.header(header::ORIGIN, origin);
+            }
+            request
+                .body(Body::from(
+                    json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }).to_string(),
+                ))
+                .unwrap()
+        };
+
+        for (host, origin, expected) in [
+            ("127.0.0.1:8123", None, StatusCode::OK),
+            (
+                "localhost:8123",
+                Some("http://localhost:3000"),
+                StatusCode::OK,
+            ),
+            ("evil.example:8123", None, StatusCode::FORBIDDEN),
+            ("127.0.0.1:9999", None, StatusCode::FORBIDDEN),
+            (
+                "127.0.0.1:8123",
+                Some("https://evil.example"),
+                StatusCode::FORBIDDEN,
+            ),
+        ] {
+            let response = router.clone().oneshot(request(host, origin)).await.unwrap();
+            assert_eq!(response.status(), expected, "{} {:?}", host, origin);
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_methods_and_tools_are_errors() {
+        let server = test_server();
+        let reply = server
+            .handle_message(json!({ "jsonrpc": "2.0", "id": 5, "method": "resources/list" }))
+            .await
+            .unwrap();
+        assert_eq!(reply["error"]["code"], -32601);
+
+        let reply = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 6,
+                "method": "tools/call",
+                "params": { "name": "nope" }
+            }))
+            .await
+            .unwrap();
+        assert_eq!(reply["error"]["code"], -32602);
+
+        assert!(server
+            .handle_message(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::Result;
+use chrono::Utc;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::json_file_store::JsonFileStore;
+
+/// Seconds before the recorded expiry at which a token is treated as expired
+const EXPIRY_SKEW_SECS: i64 = 30;
+
+/// OAuth credentials obtained for a remote MCP server, persisted so the
+/// browser flow only has to run once per server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredMcpToken {
+    /// URL of the MCP server the token was issued for
+    pub server_url: String,
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Unix timestamp (seconds) at which the access token expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    pub token_endpoint: String,
+    /// Client ID used for the grant, possibly obtained via dynamic registration
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+impl StoredMcpToken {
+    pub fn is_expired(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Utc::now().timestamp() + EXPIRY_SKEW_SECS >= expires_at,
+            None => false,
+        }
+    }
+
+    /// Value for the `Authorization` header
+    pub fn header_value(&self) -> String {
+        let token_type = self.token_type.as_deref().unwrap_or("Bearer");
+        let normalized = if token_type.eq_ignore_ascii_case("bearer") {
+            "Bearer"
+        } else {
+            token_type
+        };
+        format!("{} {}", normalized, self.access_token)
+    }
+}
+
+/// JSON file of OAuth tokens keyed by MCP server name, readable only by the
+/// current user
+#[derive(Debug, Clone)]
+pub struct McpTokenStore {
+    file: JsonFileStore,
+}
+
+impl McpTokenStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            file: JsonFileStore::private(path),
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        JsonFileStore::default_path("mcp_tokens.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    async fn read_all(&self) -> Result<HashMap<String, StoredMcpToken>> {
+        self.file.read_all().await
+    }
+
+    async fn write_all(&self, tokens: &HashMap<String, StoredMcpToken>) -> Result<()> {
+        self.file.write_all(tokens).await
+    }
+
+    /// Token for `server_name`, provided it was issued for `server_url`
+    pub async fn load(
+        &self,
+        server_name: &str,
+        server_url: &str,
+    ) -> Result<Option<StoredMcpToken>> {
+        let tokens = self.read_all().await?;
+        Ok(tokens.get(server_name).and_then(|token| {
+            if token.server_url == server_url {
+                Some(token.clone())
+            } else {
+                debug!(
+                    "Ignoring stored OAuth token for MCP server '{}': issued for {}",
+                    server_name, token.server_url
+                );
+                None
+            }
+        }))
+    }
+
+    pub async fn save(&self, server_name: &str, token: &StoredMcpToken) -> Result<()> {
+        let mut tokens = self.read_all().await?;
+        tokens.insert(server_name.to_string(), token.clone());
+        self.write_all(&tokens).await
+    }
+
+    /// Remove the token for `server_name`, returning whether one was stored
+    pub async fn remove(&self, server_name: &str) -> Result<bool> {
+        let mut tokens = self.read_all().await?;
+        if tokens.remove(server_name).is_none() {
+            return Ok(false);
+        }
+        self.write_all(&tokens).await?;
+        Ok(true)
+    }
+}
+
+impl Default for McpTokenStore {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_token(server_url: &str) -> StoredMcpToken {
+        StoredMcpToken {
+            server_url: server_url.to_string(),
+            access_token: "access".to_string(),
+            token_type: Some("bearer".to_string()),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: Some(Utc::now().timestamp() + 3600),
+            token_endpoint: "https://auth.example.com/token".to_string(),
+            client_id: "client".to_string(),
+            client_secret: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trips_tokens_per_server() {
+        let dir = TempDir::new().unwrap();
+        let store = McpTokenStore::new(dir.path().join("nested").join("mcp_tokens.json"));
+        let token = sample_token("https://mcp.example.com/mcp");
+
+        store.save("linear", &token).await.unwrap();
+        assert_eq!(
+            store
+                .load("linear", "https://mcp.example.com/mcp")
+                .await
+                .unwrap(),
+            Some(token)
+        );
+        assert_eq!(
+            store
+                .load("github", "https://mcp.example.com/mcp")
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert!(store.remove("linear").await.unwrap());
+        assert!(!store.remove("linear").await.unwrap());
+        assert_eq!(
+            store
+                .load("linear", "https://mcp.example.com/mcp")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_tokens_issued_for_another_url() {
+        let dir = TempDir::new().unwrap();
+        let store = McpTokenStore::new(dir.path().join("mcp_tokens.json"));
+        store
+            .save("linear", &sample_token("https://old.example.com/mcp"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store
+                .load("linear", "https://new.example.com/mcp")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn token_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let store = McpTokenStore::new(dir.path().join("mcp_tokens.json"));
+        store
+            .save("linear", &sample_token("https://mcp.example.com/mcp"))
+            .await
+            .unwrap();
+
+        let mode = std::fs::metadata(store.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn expiry_includes_skew() {
+        let mut token = sample_token("https://mcp.example.com/mcp");
+        assert!(!token.is_expired());
+        token.expires_at = Some(Utc::now().timestamp() + 10);
+        assert!(token.is_expired());
+        token.expires_at = None;
+        assert!(!token.is_expired());
+        assert_eq!(token.header_value(), "Bearer access");
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::McpServerConfig;
+use crate::json_file_store::JsonFileStore;
+use crate::mcp::McpTool;
+
+/// Tool schemas last reported by an MCP server, recorded so lazy servers can
+/// advertise their tools without being started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMcpTools {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub tools: Vec<McpTool>,
+}
+
+impl CachedMcpTools {
+    pub fn new(server_config: &McpServerConfig, tools: Vec<McpTool>) -> Self {
+        Self {
+            command: server_config.command.clone(),
+            args: server_config.args.clone(),
+            url: server_config.url.clone(),
+            tools,
+        }
+    }
+
+    /// Whether the schemas were recorded for the server as it is configured now
+    pub fn matches(&self, server_config: &McpServerConfig) -> bool {
+        self.command == server_config.command
+            && self.args == server_config.args
+            && self.url == server_config.url
+    }
+}
+
+/// JSON file of cached tool schemas keyed by MCP server name
+#[derive(Debug, Clone)]
+pub struct McpToolCache {
+    file: JsonFileStore,
+}
+
+impl McpToolCache {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            file: JsonFileStore::new(path),
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        JsonFileStore::default_path("mcp_tool_cache.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    async fn read_all(&self) -> Result<HashMap<String, CachedMcpTools>> {
+        self.file.read_all().await
+    }
+
+    async fn write_all(&self, entries: &HashMap<String, CachedMcpTools>) -> Result<()> {
+        self.file.write_all(entries).await
+    }
+
+    /// Cached tools for `server_name`, provided its command, args and URL are unchanged
+    pub async fn load(
+        &self,
+        server_name: &str,
+        server_config: &McpServerConfig,
+    ) -> Result<Option<Vec<McpTool>>> {
+        let mut entries = self.read_all().await?;
+        Ok(match entries.remove(server_name) {
+            Some(entry) if entry.matches(server_config) => Some(entry.tools),
+            Some(_) => {
+                debug!(
+                    "Ignoring cached tools for MCP server '{}': configuration changed",
+                    server_name
+                );
+                None
+            }
+            None => None,
+        })
+    }
+
+    pub async fn save(
+        &self,
+        server_name: &str,
+        server_config: &McpServerConfig,
+        tools: Vec<McpTool>,
+    ) -> Result<()> {
+        let mut entries = self.read_all().await?;
+        entries.insert(
+            server_name.to_string(),
+            CachedMcpTools::new(server_config, tools),
+        );
+        self.write_all(&entries).await
+    }
+
+    pub async fn remove(&self, server_name: &str) -> Result<()> {
+        let mut entries = self.read_all().await?;
+        if entries.remove(server_name).is_some() {
+            self.write_all(&entries).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for McpToolCache {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn server_config(command: &str) -> McpServerConfig {
+        McpServerConfig {
+            name: "files".to_string(),
+            command: Some(command.to_string()),
+            args: Some(vec!["--root".to_string(), "/tmp".to_string()]),
+            url: None,
+            env: None,
+            auth: None,
+            enabled: true,
+            transport: None,
+            sampling: None,
+            lazy: true,
+            aliases: None,
+        }
+    }
+
+    fn sample_tools() -> Vec<McpTool> {
+        vec![McpTool {
+            name: "read".to_string(),
+            description: Some("Read a file".to_string()),
+            input_schema: json!({ "type": "object" }),
+        }]
+    }
+
+    #[tokio::test]
+    async fn round_trips_tools_per_server() {
+        let dir = TempDir::new().unwrap();
+        let cache = McpToolCache::new(dir.path().join("mcp_tool_cache.json"));
+        let config = server_config("files-mcp");
+
+        assert!(cache.load("files", &config).await.unwrap().is_none());
+        cache.save("files", &config, sample_tools()).await.unwrap();
+
+        let tools = cache.load("files", &config).await.unwrap().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "read");
+
+        cache.remove("files").await.unwrap();
+        assert!(cache.load("files", &config).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn changed_command_invalidates_entry() {
+        let dir = TempDir::new().unwrap();
+        let cache = McpToolCache::new(dir.path().join("mcp_tool_cache.json"));
+        cache
+            .save("files", &server_config("files-mcp"), sample_tools())
+            .await
+            .unwrap();
+
+        assert!(cache
+            .load("files", &server_config("other-mcp"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::config::Provider;
+use crate::pricing::ModelPrice;
+
+/// A provider and model the model picker offers
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelChoice {
+    pub provider: Provider,
+    pub model: String,
+    pub price: Option<ModelPrice>,
+    pub context_window: usize,
+    /// Whether this is the model in use
+    pub current: bool,
+}
+
+impl ModelChoice {
+    fn new(provider: Provider, model: String, current: bool) -> Self {
+        // OpenRouter names models "vendor/model"; prices are by model
+        let name = model.rsplit('/').next().unwrap_or(&model);
+        Self {
+            provider,
+            price: crate::pricing::model_price(provider, name),
+            context_window: crate::context_usage::context_window(&model),
+            model,
+            current,
+        }
+    }
+
+    /// What the query is matched against
+    pub fn label(&self) -> String {
+        format!("{} {}", self.provider, self.model)
+    }
+
+    /// Price per million input/output tokens and the context window, e.g.
+    /// `$3/$15 per Mtok · 200k context`
+    pub fn hint(&self) -> String {
+        let price = match self.price {
+            Some(price) if price.input == 0.0 && price.output == 0.0 => "free".to_string(),
+            Some(price) => format!("${}/${} per Mtok", price.input, price.output),
+            None => "price unknown".to_string(),
+        };
+        format!(
+            "{} · {} context",
+            price,
+            crate::context_usage::format_tokens(self.context_window)
+        )
+    }
+}
+
+/// Every provider's models, the current provider first. `available` is the
+/// current provider's list, which for Ollama is fetched from the server.
+pub fn choices(current: Provider, current_model: &str, available: &[String]) -> Vec<ModelChoice> {
+    let mut providers = crate::providers::all();
+    providers.sort_by_key(|provider| *provider != current);
+
+    let mut choices = Vec::new();
+    for provider in providers {
+        let mut models = if provider == current {
+            available.to_vec()
+        } else {
+            crate::providers::spec(provider).models.clone()
+        };
+        if provider == current && !models.iter().any(|model| model == current_model) {
+            models.insert(0, current_model.to_string());
+        }
+        for model in models {
+            let is_current = provider == current && model == current_model;
+            choices.push(ModelChoice::new(provider, model, is_current));
+        }
+    }
+    choices
+}
+
+/// How well `query` fuzzy-matches `text`: None unless the query's characters
+/// all appear in order. Runs of consecutive characters and matches at the
+/// start of words score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0i64;
+    let mut position = 0usize;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars() {
+        let found = position + text[position..].iter().position(|c| *c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - position) as i64 / 4;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// Indexes of the choices matching `query`, best first. Each word of the
+/// query has to match, so `openai mini` finds OpenAI's mini models.
+pub fn filter(choices: &[ModelChoice], query: &str) -> Vec<usize> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let mut matches: Vec<(i64, usize)> = choices
+        .iter()
+        .enumerate()
+        .filter_map(|(index, choice)| {
+            let label = choice.label();
+            let mut total = 0;
+            for word in &words {
+                total += fuzzy_score(word, &label)?;
+            }
+            Some((total, index))
+        })
+        .collect();
+    matches.sort_by_key(|(score, index)| (std::cmp::Reverse(*score), *index));
+    matches.into_iter().map(|(_, index)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_need_every_character_in_order() {
+        assert!(fuzzy_score("snt", "anthropic claude-sonnet-4-5").is_some());
+        assert!(fuzzy_score("son45", "anthropic claude-sonnet-4-5").is_some());
+        assert!(fuzzy_score("45son", "anthropic claude-sonnet-4-5").is_none());
+        assert!(fuzzy_score("xyz", "anthropic claude-sonnet-4-5").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(
+            fuzzy_score("son", "anthropic claude-sonnet-4-5")
+                > fuzzy_score("son", "openai gpt-5-mini-snapshot-ordered")
+        );
+    }
+
+    #[test]
+    fn every_word_of_the_query_has_to_match() {
+        let choices = vec![
+            ModelChoice::new(Provider::OPENAI, "gpt-5".to_string(), false),
+            ModelChoice::new(Provider::OPENAI, "gpt-5-mini".to_string(), false),
+            ModelChoice::new(Provider::GEMINI, "gemini-2.5-flash".to_string(), false),
+        ];
+        assert_eq!(filter(&choices, "openai mini"), vec![1]);
+        assert_eq!(filter(&choices, ""), vec![0, 1, 2]);
+        assert_eq!(filter(&choices, "flash")[0], 2);
+        assert!(filter(&choices, "claude").is_empty());
+    }
+
+    #[test]
+    fn lists_the_current_provider_first_with_its_model() {
+        let available = vec!["llama3".to_string()];
+        let choices = choices(Provider::OLLAMA, "qwen3:8b", &available);
+        assert_eq!(choices[0].provider, Provider::OLLAMA);
+        assert_eq!(choices[0].model, "qwen3:8b");
+        assert!(choices[0].current);
+        assert_eq!(choices[1].model, "llama3");
+        assert_eq!(choices.iter().filter(|choice| choice.current).count(), 1);
+        assert!(choices
+            .iter()
+            .any(|choice| choice.provider == Provider::ANTHROPIC));
+    }
+
+    #[test]
+    fn hints_show_price_and_context_window() {
+        let sonnet = ModelChoice::new(
+            Provider::OPENROUTER,
+            "anthropic/claude-sonnet-4.5".to_string(),
+            false,
+        );
+        assert_eq!(sonnet.hint(), "$3/$15 per Mtok · 200k context");
+        let local = ModelChoice::new(Provider::OLLAMA, "llama3".to_string(), false);
+        assert_eq!(local.hint(), "free · 128k context");
+    }
+}
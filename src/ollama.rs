@@ -115,7 +115,7 @@ pub struct OllamaClient {
 impl OllamaClient {
     pub fn new(api_key: String, base_url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http_client::client(),
             api_key,
             base_url: base_url.trim_end_matches('/').to_string(),
         }
@@ -1,11 +1,12 @@
 use crate::anthropic::{AnthropicResponse, ContentBlock, Message, Usage};
+use crate::llm::{DeltaCallback, ReplyDelta};
 use crate::tools::Tool;
 use anyhow::Result;
 use futures_util::StreamExt;
 use log::{debug, error};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::{Map, Value};
+use serde_json::{json, Map, Value};
 use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -24,6 +25,11 @@ struct OpenAIRequest {
     tools: Option<Vec<OpenAITool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+    /// Fields particular to a compatible API, such as OpenRouter's routing
+    #[serde(flatten)]
+    extra: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -119,6 +125,10 @@ struct OpenAIStreamChoice {
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamDelta {
     content: Option<String>,
+    /// The reasoning of models that think before answering, such as
+    /// DeepSeek's reasoner; OpenRouter calls it `reasoning`
+    #[serde(alias = "reasoning")]
+    reasoning_content: Option<String>,
     tool_calls: Option<Vec<OpenAIStreamToolCallDelta>>,
 }
 
@@ -137,6 +147,17 @@ struct OpenAIStreamFunctionDelta {
     arguments: Option<String>,
 }
 
+/// `GET /models`
+#[derive(Debug, Deserialize)]
+struct OpenAIModelList {
+    data: Vec<OpenAIModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIModel {
+    id: String,
+}
+
 #[derive(Default)]
 struct ToolCallBuilder {
     id: Option<String>,
@@ -148,17 +169,79 @@ pub struct OpenAIClient {
     client: Client,
     api_key: String,
     base_url: String,
+    /// Which API this is, for logs and errors
+    name: &'static str,
+    headers: Vec<(&'static str, String)>,
+    extra_body: Map<String, Value>,
 }
 
 impl OpenAIClient {
     pub fn new(api_key: String, base_url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http_client::client(),
             api_key,
             base_url: base_url.trim_end_matches('/').to_string(),
+            name: "OpenAI",
+            headers: Vec::new(),
+            extra_body: Map::new(),
         }
     }
 
+    /// Name the API in logs and errors, for services that speak OpenAI's
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Send `name: value` with every request
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Add a top-level field to every request body
+    pub fn with_body_field(mut self, key: &str, value: Value) -> Self {
+        self.extra_body.insert(key.to_string(), value);
+        self
+    }
+
+    fn post(&self, endpoint: &str) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .post(endpoint)
+            .header("authorization", format!("Bearer {}", self.api_key))
+            .header("content-type", "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(*name, value);
+        }
+        request
+    }
+
+    /// The models the server offers, by ID
+    pub async fn fetch_models(&self) -> Result<Vec<String>> {
+        let endpoint = format!("{}/models", self.base_url);
+        debug!("Fetching {} models from {}", self.name, endpoint);
+        let mut request = self
+            .client
+            .get(&endpoint)
+            .header("authorization", format!("Bearer {}", self.api_key));
+        for (name, value) in &self.headers {
+            request = request.header(*name, value);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "{} /models returned {}: {}",
+                self.name,
+                status,
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        let list: OpenAIModelList = response.json().await?;
+        Ok(list.data.into_iter().map(|model| model.id).collect())
+    }
+
     pub async fn create_message(
         &self,
         model: &str,
@@ -184,32 +267,26 @@ impl OpenAIClient {
         );
         let endpoint = format!("{}/chat/completions", self.base_url);
 
-        debug!("Sending OpenAI request to {}", endpoint);
-        let response = self
-            .client
-            .post(&endpoint)
-            .header("authorization", format!("Bearer {}", self.api_key))
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        debug!("Sending {} request to {}", self.name, endpoint);
+        let response = self.post(&endpoint).json(&request).send().await?;
 
         let status = response.status();
         let response_text = response.text().await?;
 
         if !status.is_success() {
-            error!("OpenAI request failed: {}", status);
+            error!("{} request failed: {}", self.name, status);
             error!("Response: {}", response_text);
             return Err(anyhow::anyhow!(
-                "OpenAI API error: {} - {}",
+                "{} API error: {} - {}",
+                self.name,
                 status,
                 response_text
             ));
         }
 
-        debug!("OpenAI raw response: {}", response_text);
+        debug!("{} raw response: {}", self.name, response_text);
         let parsed: OpenAIResponse = serde_json::from_str(&response_text).map_err(|e| {
-            error!("Failed to parse OpenAI response: {}", e);
+            error!("Failed to parse {} response: {}", self.name, e);
             e
         })?;
 
@@ -225,6 +302,7 @@ impl OpenAIClient {
         temperature: f32,
         system_prompt: Option<&String>,
         on_content: Arc<dyn Fn(String) + Send + Sync + 'static>,
+        on_delta: Option<DeltaCallback>,
         cancellation_flag: Arc<AtomicBool>,
     ) -> Result<AnthropicResponse> {
         if cancellation_flag.load(Ordering::SeqCst) {
@@ -242,23 +320,17 @@ impl OpenAIClient {
         );
         let endpoint = format!("{}/chat/completions", self.base_url);
 
-        debug!("Sending OpenAI streaming request to {}", endpoint);
-        let response = self
-            .client
-            .post(&endpoint)
-            .header("authorization", format!("Bearer {}", self.api_key))
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        debug!("Sending {} streaming request to {}", self.name, endpoint);
+        let response = self.post(&endpoint).json(&request).send().await?;
 
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await?;
-            error!("OpenAI streaming request failed: {}", status);
+            error!("{} streaming request failed: {}", self.name, status);
             error!("Response: {}", error_text);
             return Err(anyhow::anyhow!(
-                "OpenAI API error: {} - {}",
+                "{} API error: {} - {}",
+                self.name,
                 status,
                 error_text
             ));
@@ -296,6 +368,15 @@ impl OpenAIClient {
                                     serde_json::from_str::<OpenAIStreamResponse>(event_data)
                                 {
                                     if let Some(choice) = event.choices.first() {
+                                        // Kept out of the reply, which is
+                                        // only the answer
+                                        if let (Some(reasoning), Some(on_delta)) =
+                                            (&choice.delta.reasoning_content, &on_delta)
+                                        {
+                                            if !reasoning.is_empty() {
+                                                on_delta(ReplyDelta::Thinking(reasoning.clone()));
+                                            }
+                                        }
                                         if let Some(text) = &choice.delta.content {
                                             content.push_str(text);
                                             on_content(text.clone());
@@ -408,6 +489,9 @@ impl OpenAIClient {
             temperature: Some(temperature),
             tools: tool_defs,
             stream: Some(stream),
+            // Without this, streamed replies don't report their token usage
+            stream_options: stream.then(|| json!({ "include_usage": true })),
+            extra: self.extra_body.clone(),
         }
     }
 
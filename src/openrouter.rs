@@ -0,0 +1,139 @@
+use crate::llm::{ContentCallback, LlmProvider, LlmRequest, LlmResponse};
+use crate::openai::OpenAIClient;
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use serde_json::json;
+
+/// Sent so requests are attributed to Flexorama in OpenRouter's rankings
+const APP_URL: &str = "https://github.com/martydill/flexorama";
+const APP_TITLE: &str = "Flexorama";
+
+/// Comma-separated models OpenRouter falls back to, in order, when the
+/// requested one is down, rate limited or refuses the request
+pub const FALLBACK_MODELS_ENV: &str = "OPENROUTER_FALLBACK_MODELS";
+
+/// OpenRouter's API is OpenAI's, so this is an [`OpenAIClient`] with
+/// OpenRouter's attribution headers and model routing
+pub struct OpenRouterClient {
+    inner: OpenAIClient,
+}
+
+impl OpenRouterClient {
+    pub fn new(api_key: String, base_url: String) -> Self {
+        let fallback_models = std::env::var(FALLBACK_MODELS_ENV)
+            .map(|value| parse_models(&value))
+            .unwrap_or_default();
+        Self::with_fallback_models(api_key, base_url, fallback_models)
+    }
+
+    pub fn with_fallback_models(
+        api_key: String,
+        base_url: String,
+        fallback_models: Vec<String>,
+    ) -> Self {
+        let mut inner = OpenAIClient::new(api_key, base_url)
+            .with_name("OpenRouter")
+            .with_header("HTTP-Referer", APP_URL)
+            .with_header("X-Title", APP_TITLE);
+        if !fallback_models.is_empty() {
+            inner = inner.with_body_field("models", json!(fallback_models));
+        }
+        Self { inner }
+    }
+}
+
+fn parse_models(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|model| !model.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl LlmProvider for OpenRouterClient {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>> {
+        LlmProvider::create_message(&self.inner, request)
+    }
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>> {
+        LlmProvider::create_message_stream(&self.inner, request, on_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anthropic::{ContentBlock, Message};
+    use crate::llm::tests::{configure_no_proxy, spawn_server};
+    use axum::extract::State;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::Value;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    type Seen = Arc<Mutex<Vec<(HeaderMap, Value)>>>;
+
+    async fn handler(
+        State(seen): State<Seen>,
+        headers: HeaderMap,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        seen.lock().unwrap().push((headers, body));
+        Json(json!({
+            "choices": [{"message": {"role": "assistant", "content": "ok"}}]
+        }))
+    }
+
+    #[tokio::test]
+    async fn sends_attribution_headers_and_fallback_models() {
+        configure_no_proxy();
+        let seen = Seen::default();
+        let app = Router::new()
+            .route("/chat/completions", post(handler))
+            .with_state(seen.clone());
+        let base_url = spawn_server(app).await;
+
+        let client = OpenRouterClient::with_fallback_models(
+            "test-key".to_string(),
+            base_url,
+            parse_models("openai/gpt-5, ,google/gemini-2.5-pro"),
+        );
+        let response = LlmProvider::create_message(
+            &client,
+            LlmRequest {
+                model: "anthropic/claude-sonnet-4.5",
+                messages: vec![Message {
+                    role: "user".to_string(),
+                    content: vec![ContentBlock::text("ping".to_string())],
+                }],
+                tools: &[],
+                max_tokens: 16,
+                temperature: 0.0,
+                system_prompt: None,
+                cancellation_flag: Arc::new(AtomicBool::new(false)),
+                on_delta: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.content[0].text.as_deref(), Some("ok"));
+
+        let seen = seen.lock().unwrap();
+        let (headers, body) = &seen[0];
+        assert_eq!(headers["authorization"], "Bearer test-key");
+        assert_eq!(headers["http-referer"], APP_URL);
+        assert_eq!(headers["x-title"], APP_TITLE);
+        assert_eq!(body["model"], "anthropic/claude-sonnet-4.5");
+        assert_eq!(
+            body["models"],
+            json!(["openai/gpt-5", "google/gemini-2.5-pro"])
+        );
+    }
+}
@@ -1,10 +1,15 @@
 use log::{Level, LevelFilter, Metadata, Record};
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 
 pub trait OutputSink: Send + Sync {
     fn write(&self, text: &str, is_err: bool);
     fn flush(&self);
+
+    /// Show text that doesn't fit on screen in a pager view before it's
+    /// written. Sinks without one do nothing.
+    fn page(&self, _text: &str) {}
 }
 
 static OUTPUT_SINK: OnceLock<Mutex<Option<Arc<dyn OutputSink>>>> = OnceLock::new();
@@ -23,6 +28,55 @@ pub fn clear_output_sink() {
     *guard = None;
 }
 
+/// When set, regular output goes to stderr so stdout carries only a
+/// machine-readable result
+static STDOUT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+pub fn redirect_stdout_to_stderr() {
+    STDOUT_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+fn to_stderr(is_err: bool) -> bool {
+    is_err || STDOUT_TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// Whether regular output goes straight to a terminal
+pub fn stdout_is_terminal() -> bool {
+    !STDOUT_TO_STDERR.load(Ordering::Relaxed) && ::std::io::stdout().is_terminal()
+}
+
+/// `output_width` from the config, with `WIDTH_AUTO` standing for unset
+static OUTPUT_WIDTH: AtomicUsize = AtomicUsize::new(WIDTH_AUTO);
+const WIDTH_AUTO: usize = usize::MAX;
+
+/// Set the column output wraps at: `None` follows the terminal width and
+/// `Some(0)` turns wrapping off
+pub fn set_output_width(width: Option<usize>) {
+    OUTPUT_WIDTH.store(width.unwrap_or(WIDTH_AUTO), Ordering::Relaxed);
+}
+
+/// The configured output width, as passed to [`set_output_width`]
+pub fn output_width() -> Option<usize> {
+    match OUTPUT_WIDTH.load(Ordering::Relaxed) {
+        WIDTH_AUTO => None,
+        width => Some(width),
+    }
+}
+
+/// The column to wrap output at on a terminal `terminal_width` wide, or
+/// `None` when wrapping is off
+pub fn wrap_width(terminal_width: usize) -> Option<usize> {
+    wrap_columns(output_width(), terminal_width)
+}
+
+fn wrap_columns(setting: Option<usize>, terminal_width: usize) -> Option<usize> {
+    match setting {
+        None => Some(terminal_width),
+        Some(0) => None,
+        Some(width) => Some(width.min(terminal_width)),
+    }
+}
+
 pub fn is_tui_active() -> bool {
     let guard = sink_cell().lock().expect("output sink lock");
     guard.is_some()
@@ -32,7 +86,7 @@ pub fn write(text: &str, is_err: bool) {
     let guard = sink_cell().lock().expect("output sink lock");
     if let Some(sink) = guard.as_ref() {
         sink.write(text, is_err);
-    } else if is_err {
+    } else if to_stderr(is_err) {
         ::std::eprint!("{}", text);
     } else {
         ::std::print!("{}", text);
@@ -44,13 +98,22 @@ pub fn write_line(text: &str, is_err: bool) {
     if let Some(sink) = guard.as_ref() {
         sink.write(text, is_err);
         sink.write("\n", is_err);
-    } else if is_err {
+    } else if to_stderr(is_err) {
         ::std::eprintln!("{}", text);
     } else {
         ::std::println!("{}", text);
     }
 }
 
+pub fn page(text: &str) {
+    // Release the lock while the pager waits for keys, so output from other
+    // tasks isn't blocked behind it
+    let sink = sink_cell().lock().expect("output sink lock").clone();
+    if let Some(sink) = sink {
+        sink.page(text);
+    }
+}
+
 pub fn flush() {
     let guard = sink_cell().lock().expect("output sink lock");
     if let Some(sink) = guard.as_ref() {
@@ -75,17 +138,21 @@ impl OutputLogger {
 
 impl log::Log for OutputLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.level
+        // The JSON log file may take more detail than is shown
+        metadata.level() <= log::max_level()
     }
 
     fn log(&self, record: &Record) {
-        if !self.enabled(record.metadata()) {
+        crate::json_log::record(record);
+        if record.level() > self.level {
             return;
         }
+        let line = format!("[{}] {}", record.level(), record.args());
+        crate::crash::remember_log_line(line.clone());
         // In stderr_only mode (ACP), send ALL logs to stderr
         // Otherwise, only send ERROR/WARN to stderr
         let is_err = self.stderr_only || matches!(record.level(), Level::Error | Level::Warn);
-        write_line(&format!("[{}] {}", record.level(), record.args()), is_err);
+        write_line(&line, is_err);
     }
 
     fn flush(&self) {
@@ -0,0 +1,310 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::bundle::{self, Bundle, BundleKind};
+use crate::custom_commands;
+use crate::project_config;
+
+/// What a pack item is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackItemKind {
+    Command,
+    Skill,
+    Agent,
+}
+
+impl PackItemKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            PackItemKind::Command => "command",
+            PackItemKind::Skill => "skill",
+            PackItemKind::Agent => "agent",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PackContent {
+    Command(Vec<u8>),
+    Bundle(Bundle),
+}
+
+/// One command, skill or subagent from a pack
+#[derive(Debug)]
+pub struct PackItem {
+    pub kind: PackItemKind,
+    pub name: String,
+    /// Where it will be installed
+    pub target: PathBuf,
+    /// Something with the same name is installed there already
+    pub replaces: bool,
+    /// What it can run: a command's `` !`command` `` snippets, or a skill's
+    /// scripts, which run without asking once the skill is loaded
+    pub runs: Vec<String>,
+    content: PackContent,
+}
+
+/// The directories a pack installs into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallDirs {
+    pub commands: PathBuf,
+    pub skills: PathBuf,
+    pub agents: PathBuf,
+}
+
+impl InstallDirs {
+    /// `~/.flexorama`, or the configured commands directory for commands
+    pub fn global() -> Result<Self> {
+        Ok(Self {
+            commands: custom_commands::commands_dir()?,
+            skills: bundle::global_dir(BundleKind::Skill)?,
+            agents: bundle::global_dir(BundleKind::Agent)?,
+        })
+    }
+
+    /// The project's `.flexorama`
+    pub fn project() -> Result<Self> {
+        let commands = match project_config::project_dir("commands") {
+            Some(dir) => dir,
+            None => std::env::current_dir()?.join(".flexorama").join("commands"),
+        };
+        Ok(Self {
+            commands,
+            skills: bundle::project_dir(BundleKind::Skill)?,
+            agents: bundle::project_dir(BundleKind::Agent)?,
+        })
+    }
+}
+
+/// Turn what was passed to `flexorama install` into something to clone:
+/// a URL or local directory as is, `owner/repo` as a GitHub repository, and
+/// any other name as a pack in the configured registry
+pub fn resolve_source(spec: &str, registry_url: Option<&str>) -> Result<String> {
+    let spec = spec.trim();
+    if spec.contains("://") || spec.starts_with("git@") || Path::new(spec).is_dir() {
+        return Ok(spec.to_string());
+    }
+    if spec.is_empty() || spec.contains(char::is_whitespace) || spec.starts_with('.') {
+        return Err(anyhow!("Invalid pack '{}'", spec));
+    }
+    match spec.split('/').collect::<Vec<_>>().as_slice() {
+        [owner, repo] if !owner.is_empty() && !repo.is_empty() => {
+            Ok(format!("https://github.com/{}/{}", owner, repo))
+        }
+        [name] => match registry_url {
+            Some(registry) => Ok(format!("{}/{}", registry.trim_end_matches('/'), name)),
+            None => Err(anyhow!(
+                "'{}' isn't a git URL or owner/repo, and no registry_url is configured",
+                name
+            )),
+        },
+        _ => Err(anyhow!("Invalid pack '{}'", spec)),
+    }
+}
+
+/// What installing the pack checked out at `root` would do. A pack has
+/// `commands/`, `skills/` and `agents/` directories laid out the same as
+/// `~/.flexorama`.
+pub async fn plan(root: &Path, dirs: &InstallDirs) -> Result<Vec<PackItem>> {
+    let mut items = Vec::new();
+
+    let commands_root = root.join("commands");
+    let mut commands = custom_commands::command_files(&commands_root);
+    commands.sort();
+    for (name, path) in commands {
+        let relative = path.strip_prefix(&commands_root)?;
+        let target = dirs.commands.join(relative);
+        let content = fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        items.push(PackItem {
+            kind: PackItemKind::Command,
+            replaces: target.exists(),
+            runs: custom_commands::shell_commands_in(&String::from_utf8_lossy(&content)),
+            name,
+            target,
+            content: PackContent::Command(content),
+        });
+    }
+
+    for (name, path) in pack_entries(&root.join("skills"), true).await? {
+        let exported = bundle::export_skill(&path, &name).await?;
+        let target = dirs.skills.join(&name);
+        items.push(PackItem {
+            kind: PackItemKind::Skill,
+            replaces: target.exists() || dirs.skills.join(format!("{}.md", name)).is_file(),
+            runs: exported
+                .files
+                .keys()
+                .filter(|path| path.starts_with("scripts/"))
+                .cloned()
+                .collect(),
+            name,
+            target,
+            content: PackContent::Bundle(exported),
+        });
+    }
+
+    for (name, path) in pack_entries(&root.join("agents"), false).await? {
+        let exported = bundle::export_agent(&path, &name).await?;
+        let target = dirs.agents.join(format!("{}.md", name));
+        items.push(PackItem {
+            kind: PackItemKind::Agent,
+            replaces: target.exists(),
+            runs: Vec::new(),
+            name,
+            target,
+            content: PackContent::Bundle(exported),
+        });
+    }
+
+    if items.is_empty() {
+        return Err(anyhow!(
+            "No commands, skills or agents found in {}: a pack has commands/, skills/ or agents/ directories",
+            root.display()
+        ));
+    }
+    Ok(items)
+}
+
+/// The `.md` files in `dir` by name, plus skill directories when `skills`
+async fn pack_entries(dir: &Path, skills: bool) -> Result<Vec<(String, PathBuf)>> {
+    let mut entries = Vec::new();
+    if !dir.is_dir() {
+        return Ok(entries);
+    }
+    let mut read = fs::read_dir(dir).await?;
+    while let Some(entry) = read.next_entry().await? {
+        let path = entry.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if stem.starts_with('.') {
+            continue;
+        }
+        let is_skill_dir = skills && path.join("SKILL.md").is_file();
+        let is_markdown = path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md");
+        if is_skill_dir || is_markdown {
+            entries.push((stem.to_string(), path));
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Install every item, replacing anything with the same name
+pub async fn install(items: &[PackItem]) -> Result<()> {
+    for item in items {
+        match &item.content {
+            PackContent::Command(content) => {
+                if let Some(parent) = item.target.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                fs::write(&item.target, content).await?;
+            }
+            PackContent::Bundle(exported) => {
+                let root = item
+                    .target
+                    .parent()
+                    .ok_or_else(|| anyhow!("Invalid target {}", item.target.display()))?;
+                bundle::install(exported, root, true).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn resolves_pack_sources() {
+        assert_eq!(
+            resolve_source("acme/flexorama-pack", None).unwrap(),
+            "https://github.com/acme/flexorama-pack"
+        );
+        assert_eq!(
+            resolve_source("git@github.com:acme/pack.git", None).unwrap(),
+            "git@github.com:acme/pack.git"
+        );
+        assert_eq!(
+            resolve_source("rust-tools", Some("https://github.com/flexorama-packs/")).unwrap(),
+            "https://github.com/flexorama-packs/rust-tools"
+        );
+        assert!(resolve_source("rust-tools", None).is_err());
+        assert!(resolve_source("a/b/c", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn plans_and_installs_a_pack() {
+        let pack = TempDir::new().unwrap();
+        let root = pack.path();
+        std::fs::create_dir_all(root.join("commands/git")).unwrap();
+        std::fs::create_dir_all(root.join("skills/release/scripts")).unwrap();
+        std::fs::create_dir_all(root.join("agents")).unwrap();
+        std::fs::write(
+            root.join("commands/git/commit.md"),
+            "Write a commit message for:\n!`git diff --staged`",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("skills/release/SKILL.md"),
+            "---\nname: release\ndescription: Releases\n---\nShip it",
+        )
+        .unwrap();
+        std::fs::write(root.join("skills/release/scripts/bump.sh"), "echo bump").unwrap();
+        std::fs::write(
+            root.join("agents/reviewer.md"),
+            "---\nname: reviewer\n---\nReview",
+        )
+        .unwrap();
+
+        let home = TempDir::new().unwrap();
+        let dirs = InstallDirs {
+            commands: home.path().join("commands"),
+            skills: home.path().join("skills"),
+            agents: home.path().join("agents"),
+        };
+        std::fs::create_dir_all(&dirs.agents).unwrap();
+        std::fs::write(dirs.agents.join("reviewer.md"), "old").unwrap();
+
+        let items = plan(root, &dirs).await.unwrap();
+        let summary: Vec<(PackItemKind, &str, bool)> = items
+            .iter()
+            .map(|item| (item.kind, item.name.as_str(), item.replaces))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (PackItemKind::Command, "git:commit", false),
+                (PackItemKind::Skill, "release", false),
+                (PackItemKind::Agent, "reviewer", true),
+            ]
+        );
+        assert_eq!(items[0].runs, vec!["git diff --staged"]);
+        assert_eq!(items[1].runs, vec!["scripts/bump.sh"]);
+
+        install(&items).await.unwrap();
+        assert!(dirs.commands.join("git/commit.md").is_file());
+        assert!(dirs.skills.join("release/scripts/bump.sh").is_file());
+        assert!(std::fs::read_to_string(dirs.agents.join("reviewer.md"))
+            .unwrap()
+            .contains("Review"));
+    }
+
+    #[tokio::test]
+    async fn rejects_directories_that_are_not_packs() {
+        let empty = TempDir::new().unwrap();
+        let home = TempDir::new().unwrap();
+        let dirs = InstallDirs {
+            commands: home.path().join("commands"),
+            skills: home.path().join("skills"),
+            agents: home.path().join("agents"),
+        };
+        let err = plan(empty.path(), &dirs).await.unwrap_err();
+        assert!(err.to_string().contains("No commands, skills or agents"));
+    }
+}
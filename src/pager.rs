@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::output;
+
+/// Set by `--no-pager`
+static PAGER_DISABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn disable() {
+    PAGER_DISABLED.store(true, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    !PAGER_DISABLED.load(Ordering::Relaxed)
+}
+
+/// How many terminal rows `text` takes up at `width` columns, counting
+/// wrapped lines and ignoring ANSI colour codes
+pub fn rows_needed(text: &str, width: usize) -> usize {
+    let width = width.max(1);
+    text.trim_end_matches('\n')
+        .split('\n')
+        .map(|line| visible_len(line).div_ceil(width).max(1))
+        .sum()
+}
+
+fn visible_len(line: &str) -> usize {
+    let mut len = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip a CSI sequence like `\x1b[1;32m`
+            for next in chars.by_ref() {
+                if next.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if c != '\r' {
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Whether `text` is too long to read without scrolling in a terminal of
+/// `height` rows. A couple of rows are left for the prompt.
+pub fn exceeds_screen(text: &str, width: usize, height: usize) -> bool {
+    rows_needed(text, width) > height.saturating_sub(2)
+}
+
+/// `less` isn't usually installed on Windows
+const DEFAULT_PAGER: &str = if cfg!(windows) { "more" } else { "less" };
+
+/// The pager to run: `$PAGER`, or `less` when it isn't set. An empty
+/// `$PAGER` or `cat` turns paging off.
+fn pager_command(pager_var: Option<String>) -> Option<String> {
+    let command = pager_var.unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let command = command.trim();
+    if command.is_empty() || command == "cat" {
+        None
+    } else {
+        Some(command.to_string())
+    }
+}
+
+fn run_external_pager(command: &str, text: &str) -> Result<()> {
+    #[cfg(windows)]
+    let mut child = Command::new("cmd");
+    #[cfg(windows)]
+    child.args(["/C", command]);
+    #[cfg(not(windows))]
+    let mut child = Command::new("sh");
+    #[cfg(not(windows))]
+    child.args(["-c", command]);
+
+    // Like git: keep colours, and leave the text on screen after quitting
+    if std::env::var_os("LESS").is_none() {
+        child.env("LESS", "FRX");
+    }
+    let mut child = child.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit before reading everything, closing the pipe
+        if let Err(e) = stdin.write_all(text.as_bytes()) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    let status = child.wait()?;
+    if !status.success() && status.code() == Some(127) {
+        return Err(anyhow!("'{}' not found", command));
+    }
+    Ok(())
+}
+
+/// Print a complete (non-streamed) response or tool output. When it won't fit
+/// on screen it's shown in a pager first: the TUI's own pager view in
+/// interactive mode, otherwise `$PAGER` if stdout is a terminal.
+pub fn print_paged(text: &str) {
+    if enabled() && !text.is_empty() {
+        if output::is_tui_active() {
+            // The TUI pages long text itself and keeps it in the scrollback
+            output::page(text);
+        } else if output::stdout_is_terminal() {
+            let (width, height) = crossterm::terminal::size()
+                .map(|(w, h)| (w as usize, h as usize))
+                .unwrap_or((80, 24));
+            if exceeds_screen(text, width, height) {
+                if let Some(command) = pager_command(std::env::var("PAGER").ok()) {
+                    match run_external_pager(&command, text) {
+                        Ok(()) => return,
+                        Err(e) => debug!("Pager {} failed, printing instead: {}", command, e),
+                    }
+                }
+            }
+        }
+    }
+    app_print!("{}", text);
+    output::flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_wrapped_rows_without_colour_codes() {
+        assert_eq!(rows_needed("one\ntwo\n", 80), 2);
+        assert_eq!(rows_needed("\x1b[1;32mgreen\x1b[0m", 5), 1);
+        assert_eq!(rows_needed(&"x".repeat(25), 10), 3);
+        assert_eq!(rows_needed("a\n\nb", 10), 3);
+
+        let long = "line\n".repeat(30);
+        assert!(exceeds_screen(&long, 80, 24));
+        assert!(!exceeds_screen(&long, 80, 40));
+    }
+
+    #[test]
+    fn picks_pager_from_environment() {
+        assert_eq!(pager_command(None), Some(DEFAULT_PAGER.to_string()));
+        assert_eq!(
+            pager_command(Some("most -s".to_string())),
+            Some("most -s".to_string())
+        );
+        assert_eq!(pager_command(Some(String::new())), None);
+        assert_eq!(pager_command(Some("cat".to_string())), None);
+    }
+}
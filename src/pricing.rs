@@ -0,0 +1,291 @@
+use crate::config::Provider;
+
+/// Price in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Published list prices, matched by model name prefix (most specific first)
+const PRICES: &[(&str, ModelPrice)] = &[
+    (
+        "claude-opus-4-5",
+        ModelPrice {
+            input: 5.0,
+            output: 25.0,
+        },
+    ),
+    (
+        "claude-opus-4",
+        ModelPrice {
+            input: 15.0,
+            output: 75.0,
+        },
+    ),
+    (
+        "claude-3-opus",
+        ModelPrice {
+            input: 15.0,
+            output: 75.0,
+        },
+    ),
+    (
+        "claude-sonnet-4",
+        ModelPrice {
+            input: 3.0,
+            output: 15.0,
+        },
+    ),
+    (
+        "claude-3-7-sonnet",
+        ModelPrice {
+            input: 3.0,
+            output: 15.0,
+        },
+    ),
+    (
+        "claude-3-5-sonnet",
+        ModelPrice {
+            input: 3.0,
+            output: 15.0,
+        },
+    ),
+    (
+        "claude-haiku-4-5",
+        ModelPrice {
+            input: 1.0,
+            output: 5.0,
+        },
+    ),
+    (
+        "claude-3-5-haiku",
+        ModelPrice {
+            input: 0.8,
+            output: 4.0,
+        },
+    ),
+    (
+        "claude-3-haiku",
+        ModelPrice {
+            input: 0.25,
+            output: 1.25,
+        },
+    ),
+    (
+        "gpt-5-nano",
+        ModelPrice {
+            input: 0.05,
+            output: 0.4,
+        },
+    ),
+    (
+        "gpt-5-mini",
+        ModelPrice {
+            input: 0.25,
+            output: 2.0,
+        },
+    ),
+    (
+        "gpt-5",
+        ModelPrice {
+            input: 1.25,
+            output: 10.0,
+        },
+    ),
+    (
+        "gpt-4.1-nano",
+        ModelPrice {
+            input: 0.1,
+            output: 0.4,
+        },
+    ),
+    (
+        "gpt-4.1-mini",
+        ModelPrice {
+            input: 0.4,
+            output: 1.6,
+        },
+    ),
+    (
+        "gpt-4.1",
+        ModelPrice {
+            input: 2.0,
+            output: 8.0,
+        },
+    ),
+    (
+        "gpt-4o-mini",
+        ModelPrice {
+            input: 0.15,
+            output: 0.6,
+        },
+    ),
+    (
+        "gpt-4o",
+        ModelPrice {
+            input: 2.5,
+            output: 10.0,
+        },
+    ),
+    (
+        "o4-mini",
+        ModelPrice {
+            input: 1.1,
+            output: 4.4,
+        },
+    ),
+    (
+        "o3-mini",
+        ModelPrice {
+            input: 1.1,
+            output: 4.4,
+        },
+    ),
+    (
+        "o3",
+        ModelPrice {
+            input: 2.0,
+            output: 8.0,
+        },
+    ),
+    (
+        "gemini-2.5-pro",
+        ModelPrice {
+            input: 1.25,
+            output: 10.0,
+        },
+    ),
+    (
+        "gemini-2.5-flash-lite",
+        ModelPrice {
+            input: 0.1,
+            output: 0.4,
+        },
+    ),
+    (
+        "gemini-2.5-flash",
+        ModelPrice {
+            input: 0.3,
+            output: 2.5,
+        },
+    ),
+    (
+        "gemini-2.0-flash",
+        ModelPrice {
+            input: 0.1,
+            output: 0.4,
+        },
+    ),
+    (
+        "mistral-large",
+        ModelPrice {
+            input: 2.0,
+            output: 6.0,
+        },
+    ),
+    (
+        "mistral-medium",
+        ModelPrice {
+            input: 0.4,
+            output: 2.0,
+        },
+    ),
+    (
+        "mistral-small",
+        ModelPrice {
+            input: 0.1,
+            output: 0.3,
+        },
+    ),
+    (
+        "codestral",
+        ModelPrice {
+            input: 0.3,
+            output: 0.9,
+        },
+    ),
+    (
+        "devstral",
+        ModelPrice {
+            input: 0.1,
+            output: 0.3,
+        },
+    ),
+    (
+        "glm-4.6",
+        ModelPrice {
+            input: 0.6,
+            output: 2.2,
+        },
+    ),
+    (
+        "glm-4.5-air",
+        ModelPrice {
+            input: 0.2,
+            output: 1.1,
+        },
+    ),
+    (
+        "glm-4.5",
+        ModelPrice {
+            input: 0.6,
+            output: 2.2,
+        },
+    ),
+    // deepseek-chat and deepseek-reasoner are the same model, with and
+    // without thinking
+    (
+        "deepseek",
+        ModelPrice {
+            input: 0.28,
+            output: 0.42,
+        },
+    ),
+];
+
+/// The list price of a model, if known. Local Ollama models are free.
+pub fn model_price(provider: Provider, model: &str) -> Option<ModelPrice> {
+    if provider == Provider::OLLAMA {
+        return Some(ModelPrice {
+            input: 0.0,
+            output: 0.0,
+        });
+    }
+    let model = model.to_lowercase();
+    PRICES
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, price)| *price)
+}
+
+/// Estimated cost in US dollars, or None for models without a known price
+pub fn estimate_cost(
+    provider: Provider,
+    model: &str,
+    input_tokens: u32,
+    output_tokens: u32,
+) -> Option<f64> {
+    let price = model_price(provider, model)?;
+    Some((input_tokens as f64 * price.input + output_tokens as f64 * price.output) / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_cost_from_the_most_specific_prefix() {
+        let cost = estimate_cost(Provider::OPENAI, "gpt-4o-mini-2024-07-18", 1_000_000, 0).unwrap();
+        assert!((cost - 0.15).abs() < 1e-9);
+
+        let cost = estimate_cost(Provider::ANTHROPIC, "claude-sonnet-4-5", 1000, 1000).unwrap();
+        assert!((cost - 0.018).abs() < 1e-9);
+
+        assert_eq!(
+            estimate_cost(Provider::OLLAMA, "llama3", 5000, 5000),
+            Some(0.0)
+        );
+        assert_eq!(estimate_cost(Provider::OPENAI, "my-finetune", 10, 10), None);
+    }
+}
@@ -1,9 +1,170 @@
 use crate::agent::{self, Agent};
+use crate::anthropic::{ContentBlock, Message};
+use crate::database::{ConversationSummary, DatabaseManager, Message as StoredMessage};
 use crate::formatter;
+use crate::llm::{create_response_content, LlmClient};
 use crate::utils::create_spinner;
+use anyhow::Result;
 use colored::*;
+use log::debug;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You write titles and summaries of conversations between a user and a coding assistant. Reply in exactly this format:
+Title: <a title of at most eight words>
+Summary: <what the conversation is about and where it got to, in at most three sentences>";
+/// Refresh a summary once this many messages have been added since the last one
+const SUMMARY_INTERVAL: usize = 6;
+const SUMMARY_MAX_TOKENS: u32 = 300;
+/// Longest excerpt of each message sent for summarizing
+const SUMMARY_EXCERPT_CHARS: usize = 2000;
+
+struct SummaryJob {
+    conversation_id: String,
+    model: String,
+}
+
+/// Generates conversation titles and rolling summaries in the background, so
+/// finishing a turn never waits on them
+#[derive(Clone)]
+pub struct SummaryWorker {
+    sender: mpsc::UnboundedSender<SummaryJob>,
+}
+
+impl SummaryWorker {
+    pub fn spawn(client: Arc<LlmClient>, database: Arc<DatabaseManager>) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<SummaryJob>();
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                // Only the latest job for each conversation is worth running
+                let mut jobs = vec![job];
+                while let Ok(job) = receiver.try_recv() {
+                    jobs.retain(|queued| queued.conversation_id != job.conversation_id);
+                    jobs.push(job);
+                }
+                for job in jobs {
+                    if let Err(e) = summarize_conversation(&client, &database, &job).await {
+                        debug!(
+                            "Failed to summarize conversation {}: {}",
+                            job.conversation_id, e
+                        );
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// Update the title and summary of a conversation with `model`, if it
+    /// has moved on enough since the last summary
+    pub fn enqueue(&self, conversation_id: &str, model: &str) {
+        let _ = self.sender.send(SummaryJob {
+            conversation_id: conversation_id.to_string(),
+            model: model.to_string(),
+        });
+    }
+}
+
+/// Whether a conversation with `message_count` messages is due a new title
+/// or summary
+pub fn needs_summary(summary: &ConversationSummary, message_count: usize) -> bool {
+    message_count > 0
+        && (summary.title.is_none()
+            || message_count >= summary.summarized_messages + SUMMARY_INTERVAL)
+}
+
+/// The request for a summary that folds `messages` into the `previous` one
+pub fn build_summary_prompt(previous: Option<&str>, messages: &[StoredMessage]) -> String {
+    let mut prompt = String::new();
+    if let Some(previous) = previous {
+        prompt.push_str(&format!(
+            "Summary of the conversation so far:\n{}\n\nWhat was said since:\n\n",
+            previous
+        ));
+    } else {
+        prompt.push_str("The conversation:\n\n");
+    }
+    for message in messages {
+        let content: String = message
+            .content
+            .chars()
+            .take(SUMMARY_EXCERPT_CHARS)
+            .collect();
+        prompt.push_str(&format!(
+            "{}: {}\n\n",
+            message.role.to_uppercase(),
+            content.trim()
+        ));
+    }
+    prompt
+}
+
+/// The title and summary from a reply in the format asked for
+pub fn parse_summary_response(text: &str) -> (Option<String>, Option<String>) {
+    let mut title = None;
+    let mut summary: Option<String> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("Title:") {
+            let rest = rest.trim().trim_matches(|c| c == '"' || c == '*').trim();
+            title = (!rest.is_empty()).then(|| rest.to_string());
+        } else if let Some(rest) = line.strip_prefix("Summary:") {
+            summary = Some(rest.trim().to_string());
+        } else if let Some(summary) = summary.as_mut().filter(|_| !line.is_empty()) {
+            // Summaries that run over several lines
+            summary.push(' ');
+            summary.push_str(line);
+        }
+    }
+    (title, summary.filter(|summary| !summary.is_empty()))
+}
+
+async fn summarize_conversation(
+    client: &LlmClient,
+    database: &DatabaseManager,
+    job: &SummaryJob,
+) -> Result<()> {
+    let mut summary = database
+        .get_conversation_summary(&job.conversation_id)
+        .await?;
+    let messages = database
+        .get_conversation_messages(&job.conversation_id)
+        .await?;
+    if !needs_summary(&summary, messages.len()) {
+        return Ok(());
+    }
+
+    let new_messages = &messages[summary.summarized_messages.min(messages.len())..];
+    let prompt = build_summary_prompt(summary.summary.as_deref(), new_messages);
+    let response = client
+        .create_message(
+            &job.model,
+            vec![Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text(prompt)],
+            }],
+            &[],
+            SUMMARY_MAX_TOKENS,
+            0.2,
+            Some(&SUMMARY_SYSTEM_PROMPT.to_string()),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await?;
+    let (title, text) = parse_summary_response(&create_response_content(&response.content));
+
+    // Titles stay put once set, so the conversation is easy to find again
+    if summary.title.is_none() {
+        summary.title = title;
+    }
+    if text.is_some() {
+        summary.summary = text;
+    }
+    summary.summarized_messages = messages.len();
+    database
+        .save_conversation_summary(&job.conversation_id, &summary)
+        .await
+}
 
 /// Create a streaming renderer
 pub fn create_streaming_renderer(
@@ -29,6 +190,27 @@ pub fn create_streaming_renderer(
     (state, callback)
 }
 
+/// Show the model's reasoning with `renderer` as it streams, and pass every
+/// event on to `on_tool_event`
+pub fn render_thinking(
+    renderer: &Arc<Mutex<formatter::StreamingResponseFormatter>>,
+    on_tool_event: Option<Arc<dyn Fn(agent::StreamToolEvent) + Send + Sync>>,
+) -> Arc<dyn Fn(agent::StreamToolEvent) + Send + Sync> {
+    let renderer = Arc::clone(renderer);
+    Arc::new(move |event: agent::StreamToolEvent| {
+        if let agent::StreamToolEvent::ThinkingDelta { text } = &event {
+            if let Ok(mut renderer) = renderer.lock() {
+                if let Err(e) = renderer.handle_thinking(text) {
+                    app_eprintln!("{} Streaming formatter error: {}", "Error".red(), e);
+                }
+            }
+        }
+        if let Some(on_tool_event) = &on_tool_event {
+            on_tool_event(event);
+        }
+    })
+}
+
 /// Process input and handle streaming/non-streaming response
 pub async fn process_input(
     input: &str,
@@ -38,6 +220,16 @@ pub async fn process_input(
     cancellation_flag: Arc<AtomicBool>,
     on_tool_event: Option<Arc<dyn Fn(agent::StreamToolEvent) + Send + Sync>>,
 ) {
+    if let Some(database_manager) = agent.database_manager() {
+        let conversation_id = agent.current_conversation_id();
+        if let Err(e) = database_manager
+            .add_prompt_history(input.trim(), conversation_id.as_deref())
+            .await
+        {
+            debug!("Failed to save prompt history: {}", e);
+        }
+    }
+
     // Show spinner while processing (only for non-streaming)
     if stream {
         let (streaming_state, stream_callback) = create_streaming_renderer(formatter);
@@ -45,7 +237,7 @@ pub async fn process_input(
             .process_message_with_stream(
                 &input,
                 Some(Arc::clone(&stream_callback)),
-                on_tool_event,
+                Some(render_thinking(&streaming_state, on_tool_event)),
                 cancellation_flag.clone(),
             )
             .await;
@@ -58,6 +250,7 @@ pub async fn process_input(
 
         match result {
             Ok(_response) => {
+                queue_summary(agent);
                 app_println!();
             }
             Err(e) => {
@@ -81,6 +274,7 @@ pub async fn process_input(
 
         match result {
             Ok(response) => {
+                queue_summary(agent);
                 // Only print response if it's not empty (i.e., not just @file references)
                 if !response.is_empty() {
                     if let Err(e) = formatter.print_formatted(&response) {
@@ -101,6 +295,15 @@ pub async fn process_input(
     }
 }
 
+/// Hand the finished turn's conversation to the agent's summary worker
+fn queue_summary(agent: &Agent) {
+    if let (Some(worker), Some(conversation_id)) =
+        (agent.summary_worker(), agent.current_conversation_id())
+    {
+        worker.enqueue(&conversation_id, agent.model());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,6 +342,59 @@ mod tests {
         callback("".to_string());
     }
 
+    fn stored(role: &str, content: &str) -> StoredMessage {
+        StoredMessage {
+            id: "m".to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_needs_summary() {
+        let untitled = ConversationSummary::default();
+        assert!(!needs_summary(&untitled, 0));
+        assert!(needs_summary(&untitled, 2));
+
+        let titled = ConversationSummary {
+            title: Some("Title".to_string()),
+            summary: Some("Summary".to_string()),
+            summarized_messages: 2,
+        };
+        assert!(!needs_summary(&titled, 2 + SUMMARY_INTERVAL - 1));
+        assert!(needs_summary(&titled, 2 + SUMMARY_INTERVAL));
+    }
+
+    #[test]
+    fn test_build_summary_prompt_folds_in_the_previous_summary() {
+        let messages = vec![
+            stored("user", "Add a --json flag"),
+            stored("assistant", "Done"),
+        ];
+        let prompt = build_summary_prompt(Some("Worked on the CLI."), &messages);
+        assert!(prompt.starts_with("Summary of the conversation so far:\nWorked on the CLI."));
+        assert!(prompt.contains("USER: Add a --json flag"));
+        assert!(prompt.contains("ASSISTANT: Done"));
+
+        let long = stored("user", &"x".repeat(SUMMARY_EXCERPT_CHARS * 2));
+        let prompt = build_summary_prompt(None, &[long]);
+        assert!(prompt.len() < SUMMARY_EXCERPT_CHARS + 100);
+    }
+
+    #[test]
+    fn test_parse_summary_response() {
+        let (title, summary) = parse_summary_response(
+            "Title: \"Fix flaky login test\"\nSummary: Found a race in the session setup.\nA fix is in review.",
+        );
+        assert_eq!(title.as_deref(), Some("Fix flaky login test"));
+        assert_eq!(
+            summary.as_deref(),
+            Some("Found a race in the session setup. A fix is in review.")
+        );
+        assert_eq!(parse_summary_response("I can't help"), (None, None));
+    }
+
     #[test]
     fn test_streaming_renderer_state_is_accessible() {
         let formatter = CodeFormatter::new().unwrap();
@@ -0,0 +1,454 @@
+use crate::anthropic::AnthropicClient;
+use crate::config::{CustomProviderConfig, Provider};
+use crate::custom_provider::{Capabilities, CustomProviderClient};
+use crate::deepseek::DeepSeekClient;
+use crate::gemini::GeminiClient;
+use crate::llm::LlmProvider;
+use crate::mistral::MistralClient;
+use crate::ollama::OllamaClient;
+use crate::openai::OpenAIClient;
+use crate::openrouter::OpenRouterClient;
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+/// Builds a provider's client from an API key and base URL
+pub type ProviderFactory = Arc<dyn Fn(String, String) -> Arc<dyn LlmProvider> + Send + Sync>;
+
+/// Everything flexorama needs to know about an LLM provider: how to reach
+/// it, where its credentials come from and which models it offers
+#[derive(Clone)]
+pub struct ProviderSpec {
+    /// Name used by `--provider` and the config, e.g. "openai"
+    pub name: String,
+    /// Other names accepted for it
+    pub aliases: Vec<String>,
+    /// Environment variables the API key is read from, first set wins
+    pub api_key_env: Vec<String>,
+    /// Whether it can be used without an API key, like a local server
+    pub api_key_optional: bool,
+    /// Environment variable that overrides `default_base_url`
+    pub base_url_env: Option<String>,
+    pub default_base_url: String,
+    pub default_model: String,
+    /// Models offered by `/model` and the web UI
+    pub models: Vec<String>,
+    /// Whether the server lists its models at `GET /models`, to be offered
+    /// in place of `models`
+    pub lists_models: bool,
+    pub factory: ProviderFactory,
+}
+
+impl ProviderSpec {
+    /// A spec with no alternative names, models or environment variables
+    pub fn new(
+        name: impl Into<String>,
+        default_base_url: impl Into<String>,
+        default_model: impl Into<String>,
+        factory: ProviderFactory,
+    ) -> Self {
+        let default_model = default_model.into();
+        Self {
+            name: name.into(),
+            aliases: Vec::new(),
+            api_key_env: Vec::new(),
+            api_key_optional: false,
+            base_url_env: None,
+            default_base_url: default_base_url.into(),
+            models: vec![default_model.clone()],
+            lists_models: false,
+            default_model,
+            factory,
+        }
+    }
+
+    fn answers_to(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+}
+
+struct RegisteredProvider {
+    provider: Provider,
+    spec: Arc<ProviderSpec>,
+}
+
+fn registry() -> &'static RwLock<Vec<RegisteredProvider>> {
+    static REGISTRY: OnceLock<RwLock<Vec<RegisteredProvider>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        RwLock::new(
+            builtin_specs()
+                .into_iter()
+                .map(|spec| RegisteredProvider {
+                    provider: Provider::from_static(intern(&spec.name)),
+                    spec: Arc::new(spec),
+                })
+                .collect(),
+        )
+    })
+}
+
+/// A `'static` copy of `name`, for provider handles and display names.
+/// Each name is leaked once and reused after that, so registering the
+/// same providers again on every config reload doesn't leak more.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES
+        .get_or_init(Default::default)
+        .lock()
+        .expect("interned names lock");
+    if let Some(interned) = names.get(name) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+/// Add a provider, or replace the one registered under the same name.
+/// Returns the handle it's selected by.
+pub fn register_provider(spec: ProviderSpec) -> Provider {
+    let mut providers = registry().write().expect("provider registry lock");
+    if let Some(existing) = providers
+        .iter_mut()
+        .find(|p| p.provider.name() == spec.name)
+    {
+        existing.spec = Arc::new(spec);
+        return existing.provider;
+    }
+    let provider = Provider::from_static(intern(&spec.name));
+    providers.push(RegisteredProvider {
+        provider,
+        spec: Arc::new(spec),
+    });
+    provider
+}
+
+/// The provider registered under `name` or one of its aliases
+pub fn lookup(name: &str) -> Option<Provider> {
+    registry()
+        .read()
+        .expect("provider registry lock")
+        .iter()
+        .find(|p| p.spec.answers_to(name))
+        .map(|p| p.provider)
+}
+
+/// The spec of a registered provider
+pub fn spec(provider: Provider) -> Arc<ProviderSpec> {
+    registry()
+        .read()
+        .expect("provider registry lock")
+        .iter()
+        .find(|p| p.provider == provider)
+        .map(|p| Arc::clone(&p.spec))
+        .unwrap_or_else(|| panic!("provider '{}' is not registered", provider.name()))
+}
+
+/// Every registered provider, built-in ones first
+pub fn all() -> Vec<Provider> {
+    registry()
+        .read()
+        .expect("provider registry lock")
+        .iter()
+        .map(|p| p.provider)
+        .collect()
+}
+
+/// Register a provider declared in the config's `[[providers]]`
+pub fn register_custom(config: &CustomProviderConfig) -> Result<Provider> {
+    let name = config.name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("A [[providers]] entry has no name"));
+    }
+    if builtin_specs().iter().any(|spec| spec.answers_to(name)) {
+        return Err(anyhow!(
+            "Provider '{}' is built in; give the [[providers]] entry another name",
+            name
+        ));
+    }
+    let default_model = config
+        .default_model
+        .clone()
+        .or_else(|| config.models.first().cloned())
+        .ok_or_else(|| anyhow!("Provider '{}' needs `models` or a `default_model`", name))?;
+
+    let display_name = intern(name);
+    let capabilities = Capabilities::from(config);
+    let mut spec = ProviderSpec::new(
+        name,
+        config.base_url.trim_end_matches('/'),
+        default_model.clone(),
+        Arc::new(move |api_key, base_url| {
+            Arc::new(CustomProviderClient::new(
+                display_name,
+                api_key,
+                base_url,
+                capabilities,
+            )) as Arc<dyn LlmProvider>
+        }),
+    );
+    spec.api_key_env = config.api_key_env.iter().cloned().collect();
+    spec.api_key_optional = config.api_key_env.is_none();
+    spec.lists_models = config.list_models;
+    if !config.models.is_empty() {
+        spec.models = config.models.clone();
+        if !spec.models.contains(&default_model) {
+            spec.models.insert(0, default_model);
+        }
+    }
+    Ok(register_provider(spec))
+}
+
+fn factory<C: LlmProvider + 'static>(new: fn(String, String) -> C) -> ProviderFactory {
+    Arc::new(move |api_key, base_url| Arc::new(new(api_key, base_url)) as Arc<dyn LlmProvider>)
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn builtin_specs() -> Vec<ProviderSpec> {
+    vec![
+        ProviderSpec {
+            api_key_env: strings(&["ANTHROPIC_AUTH_TOKEN"]),
+            base_url_env: Some("ANTHROPIC_BASE_URL".to_string()),
+            models: strings(&[
+                "claude-opus-4-1",
+                "claude-sonnet-4-0",
+                "claude-3-7-sonnet-latest",
+                "claude-opus-4-0",
+                "claude-sonnet-4-5",
+                "claude-haiku-4-5",
+                "claude-opus-4-5",
+            ]),
+            ..ProviderSpec::new(
+                Provider::ANTHROPIC.name(),
+                "https://api.anthropic.com/v1",
+                "claude-sonnet-4-5",
+                factory(AnthropicClient::new),
+            )
+        },
+        ProviderSpec {
+            api_key_env: strings(&["GEMINI_API_KEY", "GOOGLE_API_KEY"]),
+            base_url_env: Some("GEMINI_BASE_URL".to_string()),
+            models: strings(&[
+                "gemini-flash-latest",
+                "gemini-1.5-pro",
+                "gemini-1.5-flash",
+                "gemini-2.5-flash",
+                "gemini-3-pro-preview",
+                "gemini-3-flash-preview",
+                "gemini-3-pro-image-preview",
+                "gemini-2.5-flash-lite",
+                "gemini-2.5-pro",
+            ]),
+            ..ProviderSpec::new(
+                Provider::GEMINI.name(),
+                "https://generativelanguage.googleapis.com/v1beta",
+                "gemini-flash-latest",
+                factory(GeminiClient::new),
+            )
+        },
+        ProviderSpec {
+            api_key_env: strings(&["MISTRAL_API_KEY"]),
+            base_url_env: Some("MISTRAL_BASE_URL".to_string()),
+            models: strings(&[
+                "mistral-large-latest",
+                "mistral-medium-latest",
+                "mistral-small-latest",
+                "pixtral-12b-2409",
+                "open-mistral-nemo",
+            ]),
+            ..ProviderSpec::new(
+                Provider::MISTRAL.name(),
+                "https://api.mistral.ai/v1",
+                "mistral-large-latest",
+                factory(MistralClient::new),
+            )
+        },
+        ProviderSpec {
+            api_key_env: strings(&["OPENAI_API_KEY"]),
+            base_url_env: Some("OPENAI_BASE_URL".to_string()),
+            models: strings(&[
+                "gpt-5.2",
+                "gpt-5.1-codex-max",
+                "gpt-5.1",
+                "gpt-5.1-chat",
+                "gpt-5.1-codex",
+                "gpt-5.1-codex-mini",
+                "gpt-5-pro",
+                "gpt-5-codex",
+                "gpt-5",
+                "gpt-5-mini",
+                "gpt-5-nano",
+                "o3-pro",
+                "codex-mini",
+                "o4-mini",
+                "o3",
+                "o3-mini",
+                "o1",
+                "o1-mini",
+                "gpt-4o",
+                "gpt-4o-mini",
+                "gpt-4-turbo",
+                "gpt-4",
+                "gpt-3.5-turbo",
+            ]),
+            ..ProviderSpec::new(
+                Provider::OPENAI.name(),
+                "https://api.openai.com/v1",
+                "gpt-5.2",
+                factory(OpenAIClient::new),
+            )
+        },
+        // Z.ai serves an Anthropic-compatible API
+        ProviderSpec {
+            aliases: strings(&["zai"]),
+            api_key_env: strings(&["ZAI_API_KEY"]),
+            base_url_env: Some("ZAI_BASE_URL".to_string()),
+            models: strings(&["glm-4.7", "glm-4.6", "glm-4.5"]),
+            ..ProviderSpec::new(
+                Provider::ZAI.name(),
+                "https://api.z.ai/api/anthropic",
+                "glm-4.7",
+                factory(AnthropicClient::new),
+            )
+        },
+        // One API key for models from many providers, named "vendor/model"
+        ProviderSpec {
+            api_key_env: strings(&["OPENROUTER_API_KEY"]),
+            base_url_env: Some("OPENROUTER_BASE_URL".to_string()),
+            models: strings(&[
+                "anthropic/claude-sonnet-4.5",
+                "anthropic/claude-opus-4.5",
+                "openai/gpt-5",
+                "openai/gpt-5-mini",
+                "google/gemini-2.5-pro",
+                "google/gemini-2.5-flash",
+                "deepseek/deepseek-chat",
+                "qwen/qwen3-coder",
+                "x-ai/grok-4",
+                "meta-llama/llama-3.3-70b-instruct",
+                "openrouter/auto",
+            ]),
+            ..ProviderSpec::new(
+                Provider::OPENROUTER.name(),
+                "https://openrouter.ai/api/v1",
+                "anthropic/claude-sonnet-4.5",
+                factory(OpenRouterClient::new),
+            )
+        },
+        // deepseek-reasoner thinks before answering, and streams its reasoning
+        ProviderSpec {
+            api_key_env: strings(&["DEEPSEEK_API_KEY"]),
+            base_url_env: Some("DEEPSEEK_BASE_URL".to_string()),
+            models: strings(&["deepseek-chat", "deepseek-reasoner"]),
+            ..ProviderSpec::new(
+                Provider::DEEPSEEK.name(),
+                "https://api.deepseek.com",
+                "deepseek-chat",
+                factory(DeepSeekClient::new),
+            )
+        },
+        ProviderSpec {
+            api_key_env: strings(&["OLLAMA_API_KEY"]),
+            api_key_optional: true,
+            base_url_env: Some("OLLAMA_BASE_URL".to_string()),
+            models: strings(&["llama2", "gemma3:1b"]),
+            ..ProviderSpec::new(
+                Provider::OLLAMA.name(),
+                "http://localhost:11434",
+                "llama2",
+                factory(OllamaClient::new),
+            )
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LlmClient;
+
+    #[test]
+    fn builtin_providers_are_registered() {
+        assert_eq!(lookup("OpenAI"), Some(Provider::OPENAI));
+        assert_eq!(lookup("zai"), Some(Provider::ZAI));
+        assert_eq!(lookup("OpenRouter"), Some(Provider::OPENROUTER));
+        assert_eq!(lookup("deepseek"), Some(Provider::DEEPSEEK));
+        assert_eq!(lookup("nonexistent"), None);
+        assert_eq!(spec(Provider::GEMINI).api_key_env[1], "GOOGLE_API_KEY");
+        assert!(spec(Provider::OLLAMA).api_key_optional);
+        assert_eq!(&all()[..2], &[Provider::ANTHROPIC, Provider::GEMINI]);
+    }
+
+    #[test]
+    fn registers_and_replaces_providers() {
+        let provider = register_provider(ProviderSpec {
+            aliases: strings(&["local-llm"]),
+            ..ProviderSpec::new(
+                "test-registry-provider",
+                "http://localhost:8000/v1",
+                "first-model",
+                factory(OpenAIClient::new),
+            )
+        });
+        assert_eq!(lookup("local-llm"), Some(provider));
+        assert_eq!(provider.to_string(), "test-registry-provider");
+        assert_eq!(spec(provider).models, vec!["first-model"]);
+
+        let replaced = register_provider(ProviderSpec::new(
+            "test-registry-provider",
+            "http://localhost:8000/v1",
+            "second-model",
+            factory(OpenAIClient::new),
+        ));
+        assert_eq!(replaced, provider);
+        assert_eq!(spec(provider).default_model, "second-model");
+        assert_eq!(lookup("local-llm"), None);
+
+        let client = LlmClient::new(provider, String::new(), "http://localhost".to_string());
+        assert_eq!(client.provider(), provider);
+    }
+
+    #[test]
+    fn registers_providers_from_the_config() {
+        let config: CustomProviderConfig = toml::from_str(
+            "name = \"test-vllm\"\nbase_url = \"http://localhost:8000/v1/\"\napi_key_env = \"VLLM_API_KEY\"\nmodels = [\"qwen3-coder\", \"llama-3.3\"]\nimages = false\n",
+        )
+        .unwrap();
+        let provider = register_custom(&config).unwrap();
+        assert_eq!(lookup("test-vllm"), Some(provider));
+        let spec = spec(provider);
+        assert_eq!(spec.default_base_url, "http://localhost:8000/v1");
+        assert_eq!(spec.default_model, "qwen3-coder");
+        assert_eq!(spec.models, vec!["qwen3-coder", "llama-3.3"]);
+        assert_eq!(spec.api_key_env, vec!["VLLM_API_KEY"]);
+        assert!(!spec.api_key_optional);
+        assert!(spec.lists_models);
+
+        let builtin = CustomProviderConfig {
+            name: "OpenAI".to_string(),
+            ..config.clone()
+        };
+        assert!(register_custom(&builtin).is_err());
+        let no_models = CustomProviderConfig {
+            name: "test-no-models".to_string(),
+            models: Vec::new(),
+            ..config
+        };
+        assert!(register_custom(&no_models).is_err());
+    }
+
+    #[test]
+    fn names_are_leaked_once() {
+        let first = intern("test-interned-provider");
+        let again = intern(&String::from("test-interned-provider"));
+        assert!(std::ptr::eq(first, again));
+        assert!(!std::ptr::eq(first, intern("test-other-provider")));
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::process::Command;
+
+use crate::agent::SnapshotMessage;
+use crate::anthropic::{ContentBlock, Message};
+use crate::llm::{create_response_content, LlmClient};
+
+const DRAFT_SYSTEM_PROMPT: &str = "You write pull request titles and descriptions from a conversation between a developer and a coding assistant and the commits on the branch. Reply in exactly this format:
+Title: <an imperative title of at most 72 characters>
+
+<a Markdown description: what changed and why, then how it was tested if the conversation says>";
+const DRAFT_MAX_TOKENS: u32 = 1000;
+/// Longest excerpt of each message sent for drafting
+const DRAFT_EXCERPT_CHARS: usize = 2000;
+
+/// Title and description of a pull request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub title: String,
+    pub body: String,
+}
+
+async fn run(dir: &Path, program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .with_context(|| match program {
+            "gh" => "The GitHub CLI (gh) is needed to open pull requests: https://cli.github.com"
+                .to_string(),
+            _ => format!("Failed to run {}", program),
+        })?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} {} failed: {}",
+            program,
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The branch checked out in `dir`
+pub async fn current_branch(dir: &Path) -> Result<String> {
+    run(dir, "git", &["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .await
+        .map_err(|_| anyhow!("HEAD is detached; check out a branch to open a pull request from"))
+}
+
+/// The remote `branch` pushes to: its upstream's remote, or origin
+async fn push_remote(dir: &Path, branch: &str) -> String {
+    run(
+        dir,
+        "git",
+        &["config", "--get", &format!("branch.{}.remote", branch)],
+    )
+    .await
+    .unwrap_or_else(|_| "origin".to_string())
+}
+
+/// The branch `origin/HEAD` points to, which is the one pull requests target
+/// unless told otherwise
+pub async fn default_base(dir: &Path) -> Option<String> {
+    let head = run(
+        dir,
+        "git",
+        &[
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            "refs/remotes/origin/HEAD",
+        ],
+    )
+    .await
+    .ok()?;
+    head.strip_prefix("origin/").map(str::to_string)
+}
+
+/// The commits on the branch since `base` and the files they change, for
+/// drafting the description
+pub async fn branch_changes(dir: &Path, base: Option<&str>) -> String {
+    let range = base.map(|base| format!("origin/{}...HEAD", base));
+    let mut log_args = vec!["log", "--format=- %s%n%b", "--no-merges"];
+    let mut stat_args = vec!["diff", "--stat"];
+    match &range {
+        Some(range) => {
+            log_args.push(range);
+            stat_args.push(range);
+        }
+        None => {
+            log_args.extend(["-10", "HEAD"]);
+            stat_args.push("HEAD~1");
+        }
+    }
+    let log = run(dir, "git", &log_args).await.unwrap_or_default();
+    let stat = run(dir, "git", &stat_args).await.unwrap_or_default();
+    format!("Commits:\n{}\n\nFiles changed:\n{}", log.trim(), stat)
+}
+
+/// The git command that pushes `branch`, as shown when asking permission
+pub fn push_command(remote: &str, branch: &str) -> String {
+    format!("git push --set-upstream {} {}", remote, branch)
+}
+
+/// The gh command that opens the pull request, as shown when asking permission
+pub fn create_command(branch: &str, base: Option<&str>, draft: bool) -> String {
+    let mut command = format!("gh pr create --head {}", branch);
+    if let Some(base) = base {
+        command.push_str(&format!(" --base {}", base));
+    }
+    if draft {
+        command.push_str(" --draft");
+    }
+    command
+}
+
+/// Where the pull request for `branch` is pushed and opened
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub remote: String,
+    pub branch: String,
+    pub base: Option<String>,
+    pub draft: bool,
+}
+
+impl Target {
+    /// The pull request from the branch checked out in `dir`
+    pub async fn for_current_branch(dir: &Path, base: Option<&str>, draft: bool) -> Result<Self> {
+        let branch = current_branch(dir).await?;
+        let base = match base {
+            Some(base) => Some(base.to_string()),
+            None => default_base(dir).await,
+        };
+        if base.as_deref() == Some(branch.as_str()) {
+            return Err(anyhow!(
+                "{} is the base branch; create a branch for the changes first",
+                branch
+            ));
+        }
+        Ok(Self {
+            remote: push_remote(dir, &branch).await,
+            branch,
+            base,
+            draft,
+        })
+    }
+
+    pub fn push_command(&self) -> String {
+        push_command(&self.remote, &self.branch)
+    }
+
+    pub fn create_command(&self) -> String {
+        create_command(&self.branch, self.base.as_deref(), self.draft)
+    }
+
+    /// Push the branch, setting its upstream
+    pub async fn push(&self, dir: &Path) -> Result<()> {
+        run(
+            dir,
+            "git",
+            &["push", "--set-upstream", &self.remote, &self.branch],
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Open the pull request with gh and return its URL
+    pub async fn create(&self, dir: &Path, pull_request: &PullRequest) -> Result<String> {
+        let mut args = vec![
+            "pr",
+            "create",
+            "--head",
+            &self.branch,
+            "--title",
+            &pull_request.title,
+            "--body",
+            &pull_request.body,
+        ];
+        if let Some(base) = &self.base {
+            args.extend(["--base", base]);
+        }
+        if self.draft {
+            args.push("--draft");
+        }
+        let output = run(dir, "gh", &args).await?;
+        Ok(output.lines().last().unwrap_or_default().to_string())
+    }
+}
+
+/// The request for a title and description covering `messages` and the
+/// branch's `changes`
+pub fn build_draft_prompt(messages: &[SnapshotMessage], changes: &str) -> String {
+    let mut prompt = String::from("The conversation:\n\n");
+    for message in messages {
+        let text = message
+            .content
+            .iter()
+            .filter_map(|block| block.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.trim().is_empty() {
+            continue;
+        }
+        let excerpt: String = text.chars().take(DRAFT_EXCERPT_CHARS).collect();
+        prompt.push_str(&format!(
+            "{}: {}\n\n",
+            message.role.to_uppercase(),
+            excerpt.trim()
+        ));
+    }
+    prompt.push_str(changes);
+    prompt
+}
+
+/// The pull request in a reply in the format asked for
+pub fn parse_draft(text: &str) -> Option<PullRequest> {
+    let text = text.trim();
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let first = first.trim().trim_start_matches('#').trim();
+    let title = first
+        .strip_prefix("Title:")
+        .unwrap_or(first)
+        .trim()
+        .trim_matches(|c| c == '"' || c == '*')
+        .trim();
+    (!title.is_empty()).then(|| PullRequest {
+        title: title.to_string(),
+        body: rest.trim().to_string(),
+    })
+}
+
+/// Ask the model for a title and description of the work in the conversation
+pub async fn draft(
+    client: &LlmClient,
+    model: &str,
+    messages: &[SnapshotMessage],
+    changes: &str,
+) -> Result<PullRequest> {
+    let response = client
+        .create_message(
+            model,
+            vec![Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text(build_draft_prompt(messages, changes))],
+            }],
+            &[],
+            DRAFT_MAX_TOKENS,
+            0.2,
+            Some(&DRAFT_SYSTEM_PROMPT.to_string()),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await?;
+    parse_draft(&create_response_content(&response.content))
+        .ok_or_else(|| anyhow!("The model didn't write a pull request title"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn parses_drafts() {
+        let draft =
+            parse_draft("Title: Add a /pr command\n\nPushes the branch.\n\n- Opens a PR").unwrap();
+        assert_eq!(draft.title, "Add a /pr command");
+        assert_eq!(draft.body, "Pushes the branch.\n\n- Opens a PR");
+
+        let draft = parse_draft("# **Fix the parser**").unwrap();
+        assert_eq!(draft.title, "Fix the parser");
+        assert_eq!(draft.body, "");
+        assert_eq!(parse_draft("  \n"), None);
+    }
+
+    #[test]
+    fn draft_prompt_skips_tool_only_messages() {
+        let messages = vec![
+            SnapshotMessage {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text("Fix the login bug".to_string())],
+            },
+            SnapshotMessage {
+                role: "assistant".to_string(),
+                content: vec![ContentBlock::tool_use(
+                    "1".to_string(),
+                    "Read".to_string(),
+                    serde_json::json!({}),
+                )],
+            },
+        ];
+        let prompt = build_draft_prompt(&messages, "Commits:\n- Fix login");
+        assert!(prompt.contains("USER: Fix the login bug"));
+        assert!(!prompt.contains("ASSISTANT"));
+        assert!(prompt.ends_with("Commits:\n- Fix login"));
+    }
+
+    #[test]
+    fn commands_name_the_branch_and_base() {
+        assert_eq!(
+            push_command("origin", "fix-login"),
+            "git push --set-upstream origin fix-login"
+        );
+        assert_eq!(
+            create_command("fix-login", Some("main"), true),
+            "gh pr create --head fix-login --base main --draft"
+        );
+        assert_eq!(
+            create_command("fix-login", None, false),
+            "gh pr create --head fix-login"
+        );
+    }
+
+    #[tokio::test]
+    async fn pushes_the_current_branch() {
+        let remote = TempDir::new().unwrap();
+        let work = TempDir::new().unwrap();
+        git(remote.path(), &["init", "--quiet", "--bare"]);
+        git(work.path(), &["init", "--quiet", "-b", "main"]);
+        git(work.path(), &["config", "user.email", "dev@example.com"]);
+        git(work.path(), &["config", "user.name", "Dev"]);
+        git(
+            work.path(),
+            &["remote", "add", "origin", remote.path().to_str().unwrap()],
+        );
+        std::fs::write(work.path().join("a.txt"), "a").unwrap();
+        git(work.path(), &["add", "."]);
+        git(work.path(), &["commit", "--quiet", "-m", "First"]);
+        git(work.path(), &["push", "--quiet", "origin", "main"]);
+
+        assert!(
+            Target::for_current_branch(work.path(), Some("main"), false)
+                .await
+                .is_err(),
+            "opening a pull request from the base branch"
+        );
+
+        git(work.path(), &["checkout", "--quiet", "-b", "fix-login"]);
+        std::fs::write(work.path().join("b.txt"), "b").unwrap();
+        git(work.path(), &["add", "."]);
+        git(work.path(), &["commit", "--quiet", "-m", "Fix login"]);
+
+        let target = Target::for_current_branch(work.path(), Some("main"), false)
+            .await
+            .unwrap();
+        assert_eq!(target.remote, "origin");
+        assert_eq!(target.branch, "fix-login");
+        target.push(work.path()).await.unwrap();
+        git(
+            remote.path(),
+            &["rev-parse", "--verify", "--quiet", "refs/heads/fix-login"],
+        );
+
+        let changes = branch_changes(work.path(), Some("main")).await;
+        assert!(changes.contains("- Fix login"), "{}", changes);
+        assert!(changes.contains("b.txt"), "{}", changes);
+    }
+}
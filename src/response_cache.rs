@@ -0,0 +1,324 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::anthropic::ContentBlock;
+use crate::config::{Provider, ResponseCacheConfig};
+use crate::llm::{create_response_content, ContentCallback, LlmProvider, LlmRequest, LlmResponse};
+
+/// A reply as it's kept on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    /// Seconds since the Unix epoch
+    created_at: u64,
+    content: Vec<ContentBlock>,
+}
+
+/// Replies to earlier requests, one JSON file per request, keyed by a hash of
+/// the provider, model, messages, system prompt and tools
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl ResponseCache {
+    /// A cache in `dir` whose replies are reused for `ttl`, or for as long
+    /// as they're kept with no TTL
+    pub fn new(dir: PathBuf, ttl: Option<Duration>) -> Self {
+        Self { dir, ttl }
+    }
+
+    pub fn default_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("flexorama")
+            .join("responses")
+    }
+
+    /// The cache a run should use: none when it's off in the config or
+    /// `no_cache` is set, with `ttl_secs` replacing the configured TTL
+    pub fn for_run(
+        config: &ResponseCacheConfig,
+        no_cache: bool,
+        ttl_secs: Option<u64>,
+    ) -> Option<Self> {
+        if !config.enabled || no_cache {
+            return None;
+        }
+        let ttl_secs = ttl_secs.unwrap_or(config.ttl_secs);
+        Some(Self::new(
+            config.dir.clone().unwrap_or_else(Self::default_dir),
+            (ttl_secs > 0).then(|| Duration::from_secs(ttl_secs)),
+        ))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The key of `request` to `provider`
+    pub fn key(provider: Provider, request: &LlmRequest<'_>) -> String {
+        let tools: Vec<_> = request
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "input_schema": tool.input_schema,
+                })
+            })
+            .collect();
+        let fingerprint = json!({
+            "provider": provider.name(),
+            "model": request.model,
+            "system_prompt": request.system_prompt,
+            "messages": request.messages,
+            "tools": tools,
+            "max_tokens": request.max_tokens,
+            "temperature": request.temperature,
+        });
+        let digest = Sha256::digest(fingerprint.to_string().as_bytes());
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// The reply stored under `key`, unless there's none or it has expired
+    pub async fn get(&self, key: &str) -> Option<Vec<ContentBlock>> {
+        let path = self.path(key);
+        let content = tokio::fs::read_to_string(&path).await.ok()?;
+        let cached: CachedResponse = match serde_json::from_str(&content) {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable cached response {}: {}",
+                    path.display(),
+                    e
+                );
+                return None;
+            }
+        };
+        let expired = self
+            .ttl
+            .is_some_and(|ttl| now_secs().saturating_sub(cached.created_at) >= ttl.as_secs());
+        if expired {
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+        Some(cached.content)
+    }
+
+    pub async fn put(&self, key: &str, content: &[ContentBlock]) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let cached = CachedResponse {
+            created_at: now_secs(),
+            content: content.to_vec(),
+        };
+        tokio::fs::write(self.path(key), serde_json::to_string(&cached)?).await?;
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// A provider that answers requests it has seen before from a
+/// [`ResponseCache`] and passes the rest on
+pub struct CachingProvider {
+    provider: Provider,
+    inner: Arc<dyn LlmProvider>,
+    cache: ResponseCache,
+}
+
+impl CachingProvider {
+    pub fn new(provider: Provider, inner: Arc<dyn LlmProvider>, cache: ResponseCache) -> Self {
+        Self {
+            provider,
+            inner,
+            cache,
+        }
+    }
+
+    async fn respond(
+        &self,
+        request: LlmRequest<'_>,
+        on_content: Option<ContentCallback>,
+    ) -> Result<LlmResponse> {
+        let key = ResponseCache::key(self.provider, &request);
+        if let Some(content) = self.cache.get(&key).await {
+            debug!("Using cached response {}", key);
+            if let Some(on_content) = on_content {
+                let text = create_response_content(&content);
+                if !text.is_empty() {
+                    on_content(text);
+                }
+            }
+            return Ok(LlmResponse {
+                content,
+                usage: None,
+            });
+        }
+
+        let response = match on_content {
+            Some(on_content) => {
+                self.inner
+                    .create_message_stream(request, on_content)
+                    .await?
+            }
+            None => self.inner.create_message(request).await?,
+        };
+        if let Err(e) = self.cache.put(&key, &response.content).await {
+            warn!("Failed to cache response: {}", e);
+        }
+        Ok(response)
+    }
+}
+
+impl LlmProvider for CachingProvider {
+    fn create_message<'a>(&'a self, request: LlmRequest<'a>) -> BoxFuture<'a, Result<LlmResponse>> {
+        Box::pin(self.respond(request, None))
+    }
+
+    fn create_message_stream<'a>(
+        &'a self,
+        request: LlmRequest<'a>,
+        on_content: ContentCallback,
+    ) -> BoxFuture<'a, Result<LlmResponse>> {
+        Box::pin(self.respond(request, Some(on_content)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anthropic::Message;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    impl LlmProvider for CountingProvider {
+        fn create_message<'a>(
+            &'a self,
+            _request: LlmRequest<'a>,
+        ) -> BoxFuture<'a, Result<LlmResponse>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move {
+                Ok(LlmResponse {
+                    content: vec![ContentBlock::text(format!("reply {}", call))],
+                    usage: None,
+                })
+            })
+        }
+
+        fn create_message_stream<'a>(
+            &'a self,
+            request: LlmRequest<'a>,
+            _on_content: ContentCallback,
+        ) -> BoxFuture<'a, Result<LlmResponse>> {
+            self.create_message(request)
+        }
+    }
+
+    fn request(prompt: &str) -> LlmRequest<'static> {
+        LlmRequest {
+            model: "test-model",
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::text(prompt.to_string())],
+            }],
+            tools: &[],
+            max_tokens: 100,
+            temperature: 0.0,
+            system_prompt: None,
+            cancellation_flag: Arc::new(AtomicBool::new(false)),
+            on_delta: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reuses_replies_to_identical_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let provider = CachingProvider::new(
+            Provider::ANTHROPIC,
+            inner.clone(),
+            ResponseCache::new(dir.path().to_path_buf(), None),
+        );
+
+        let first = provider
+            .create_message(request("Document lib.rs"))
+            .await
+            .unwrap();
+        let streamed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = streamed.clone();
+        let second = provider
+            .create_message_stream(
+                request("Document lib.rs"),
+                Arc::new(move |text| sink.lock().unwrap().push(text)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(create_response_content(&first.content), "reply 1");
+        assert_eq!(create_response_content(&second.content), "reply 1");
+        assert_eq!(*streamed.lock().unwrap(), vec!["reply 1"]);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        provider
+            .create_message(request("Document main.rs"))
+            .await
+            .unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+        assert_ne!(
+            ResponseCache::key(Provider::ANTHROPIC, &request("x")),
+            ResponseCache::key(Provider::OPENAI, &request("x"))
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_replies_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(dir.path().to_path_buf(), Some(Duration::from_secs(60)));
+        let key = ResponseCache::key(Provider::ANTHROPIC, &request("x"));
+        cache
+            .put(&key, &[ContentBlock::text("old".to_string())])
+            .await
+            .unwrap();
+        assert!(cache.get(&key).await.is_some());
+
+        let stale = CachedResponse {
+            created_at: now_secs() - 120,
+            content: vec![ContentBlock::text("old".to_string())],
+        };
+        std::fs::write(cache.path(&key), serde_json::to_string(&stale).unwrap()).unwrap();
+        assert!(cache.get(&key).await.is_none());
+        assert!(!cache.path(&key).exists());
+
+        let config = ResponseCacheConfig::default();
+        assert!(ResponseCache::for_run(&config, false, None).is_none());
+        let enabled = ResponseCacheConfig {
+            enabled: true,
+            ..config
+        };
+        assert!(ResponseCache::for_run(&enabled, true, None).is_none());
+        let cache = ResponseCache::for_run(&enabled, false, Some(0)).unwrap();
+        assert_eq!(cache.ttl, None);
+    }
+}
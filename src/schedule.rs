@@ -0,0 +1,362 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A five-field cron expression: minute, hour, day of month, month and day
+/// of week, evaluated in local time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days_of_month: Vec<u32>,
+    months: Vec<u32>,
+    days_of_week: Vec<u32>,
+    /// Day of month and day of week were both restricted, so either matching
+    /// is enough, as in cron
+    either_day: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+            return Err(anyhow!(
+                "'{}' must have five fields: minute hour day-of-month month day-of-week",
+                expression
+            ));
+        };
+        let mut days_of_week = parse_field(day_of_week, 0, 7, "day of week")?;
+        // Both 0 and 7 are Sunday
+        for day in days_of_week.iter_mut() {
+            if *day == 7 {
+                *day = 0;
+            }
+        }
+        days_of_week.sort_unstable();
+        days_of_week.dedup();
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days_of_month: parse_field(day_of_month, 1, 31, "day of month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            days_of_week,
+            either_day: !day_of_month.starts_with('*') && !day_of_week.starts_with('*'),
+        })
+    }
+
+    pub fn matches(&self, time: &DateTime<Local>) -> bool {
+        let day_of_month = self.days_of_month.contains(&time.day());
+        let day_of_week = self
+            .days_of_week
+            .contains(&time.weekday().num_days_from_sunday());
+        let day = if self.either_day {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        };
+        day && self.minutes.contains(&time.minute())
+            && self.hours.contains(&time.hour())
+            && self.months.contains(&time.month())
+    }
+
+    /// The first matching minute after `after`, looking up to four years ahead
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut time = after
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(Duration::minutes(1))?;
+        let limit = after + Duration::days(4 * 366);
+        while time <= limit {
+            if !self.months.contains(&time.month()) {
+                // Skip to the first minute of the next month
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = time
+                    .with_day(1)?
+                    .with_hour(0)?
+                    .with_minute(0)?
+                    .with_month(month)?
+                    .with_year(year)?;
+                continue;
+            }
+            if self.matches(&time) {
+                return Some(time);
+            }
+            time += Duration::minutes(1);
+        }
+        None
+    }
+}
+
+/// Parse one field: `*`, `*/n`, `a`, `a-b` and `a-b/n`, separated by commas
+fn parse_field(field: &str, min: u32, max: u32, label: &str) -> Result<Vec<u32>> {
+    let invalid = || anyhow!("Invalid {} '{}'", label, field);
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value: u32 = range.parse().map_err(|_| invalid())?;
+            // `5/15` means every 15 starting at 5
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(anyhow!(
+                "{} in '{}' must be between {} and {}",
+                label,
+                field,
+                min,
+                max
+            ));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+/// A custom command to run on a cron schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledCommand {
+    pub id: String,
+    pub cron: String,
+    /// The command line, as typed in interactive mode: `/weekly-report main`
+    pub command: String,
+    /// Where the command runs, so project commands and files resolve
+    pub working_dir: PathBuf,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_conversation_id: Option<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl ScheduledCommand {
+    pub fn new(cron: &str, command: &str, working_dir: PathBuf) -> Result<Self> {
+        CronSchedule::parse(cron)?;
+        let command = command.trim();
+        if !command.starts_with('/') || command.len() < 2 {
+            return Err(anyhow!(
+                "'{}' isn't a custom command: it must start with /",
+                command
+            ));
+        }
+        Ok(Self {
+            id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            cron: cron.trim().to_string(),
+            command: command.to_string(),
+            working_dir,
+            created_at: Utc::now(),
+            last_run: None,
+            last_conversation_id: None,
+            last_error: None,
+        })
+    }
+
+    /// When this should next run. A run missed while no scheduler was
+    /// running is due straight away, once.
+    pub fn next_run(&self) -> Option<DateTime<Local>> {
+        let schedule = CronSchedule::parse(&self.cron).ok()?;
+        let since = self.last_run.unwrap_or(self.created_at);
+        schedule.next_after(since.with_timezone(&Local))
+    }
+
+    pub fn is_due(&self, now: DateTime<Local>) -> bool {
+        self.next_run().is_some_and(|next| next <= now)
+    }
+}
+
+/// `~/.flexorama/schedules.json`
+pub fn schedules_path() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow!("Could not determine home directory"))?
+        .join(".flexorama")
+        .join("schedules.json"))
+}
+
+pub async fn load_schedules(path: &Path) -> Result<Vec<ScheduledCommand>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+pub async fn save_schedules(path: &Path, schedules: &[ScheduledCommand]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let content = serde_json::to_string_pretty(schedules)?;
+    tokio::fs::write(path, content + "\n")
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Remove the schedule whose ID starts with `id`
+pub async fn remove_schedule(path: &Path, id: &str) -> Result<ScheduledCommand> {
+    let mut schedules = load_schedules(path).await?;
+    let matching: Vec<usize> = schedules
+        .iter()
+        .enumerate()
+        .filter(|(_, schedule)| schedule.id.starts_with(id))
+        .map(|(index, _)| index)
+        .collect();
+    let index = match matching.as_slice() {
+        [index] => *index,
+        [] => return Err(anyhow!("No schedule with ID '{}'", id)),
+        _ => return Err(anyhow!("'{}' matches more than one schedule", id)),
+    };
+    let removed = schedules.remove(index);
+    save_schedules(path, &schedules).await?;
+    Ok(removed)
+}
+
+/// Record the outcome of a run, re-reading the file so schedules added or
+/// removed while the command ran are kept
+pub async fn record_run(
+    path: &Path,
+    id: &str,
+    started: DateTime<Utc>,
+    conversation_id: Option<String>,
+    error: Option<String>,
+) -> Result<()> {
+    let mut schedules = load_schedules(path).await?;
+    if let Some(schedule) = schedules.iter_mut().find(|schedule| schedule.id == id) {
+        schedule.last_run = Some(started);
+        schedule.last_conversation_id = conversation_id;
+        schedule.last_error = error;
+        save_schedules(path, &schedules).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_cron_fields() {
+        let schedule = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        assert_eq!(schedule.minutes, vec![0, 15, 30, 45]);
+        assert_eq!(schedule.hours, (9..=17).collect::<Vec<_>>());
+        assert_eq!(schedule.days_of_week, vec![1, 2, 3, 4, 5]);
+
+        let schedule = CronSchedule::parse("5/20 0 1,15 * 7").unwrap();
+        assert_eq!(schedule.minutes, vec![5, 25, 45]);
+        assert_eq!(schedule.days_of_month, vec![1, 15]);
+        assert_eq!(schedule.days_of_week, vec![0]);
+
+        assert_eq!(
+            CronSchedule::parse("@weekly").unwrap(),
+            CronSchedule::parse("0 0 * * 0").unwrap()
+        );
+        assert!(CronSchedule::parse("0 9 * *").is_err());
+        assert!(CronSchedule::parse("60 9 * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 9 * * mon").is_err());
+    }
+
+    #[test]
+    fn finds_the_next_run() {
+        // 2024-01-03 is a Wednesday
+        let schedule = CronSchedule::parse("0 9 * * 1").unwrap();
+        assert_eq!(
+            schedule.next_after(local(2024, 1, 3, 12, 0)),
+            Some(local(2024, 1, 8, 9, 0))
+        );
+        assert_eq!(
+            schedule.next_after(local(2024, 1, 8, 9, 0)),
+            Some(local(2024, 1, 15, 9, 0))
+        );
+
+        let schedule = CronSchedule::parse("30 6 29 2 *").unwrap();
+        assert_eq!(
+            schedule.next_after(local(2024, 3, 1, 0, 0)),
+            Some(local(2028, 2, 29, 6, 30))
+        );
+
+        // With both day fields restricted, either one matching is enough
+        let schedule = CronSchedule::parse("0 0 1 * 5").unwrap();
+        assert_eq!(
+            schedule.next_after(local(2024, 1, 1, 12, 0)),
+            Some(local(2024, 1, 5, 0, 0))
+        );
+    }
+
+    #[test]
+    fn scheduled_commands_are_due_once_their_time_passes() {
+        let mut schedule =
+            ScheduledCommand::new("0 9 * * *", "/daily-report", PathBuf::from("/tmp")).unwrap();
+        schedule.created_at = local(2024, 1, 3, 8, 0).with_timezone(&Utc);
+        assert!(!schedule.is_due(local(2024, 1, 3, 8, 59)));
+        assert!(schedule.is_due(local(2024, 1, 3, 9, 0)));
+        // A missed run is due, but only once
+        assert!(schedule.is_due(local(2024, 1, 5, 12, 0)));
+        schedule.last_run = Some(local(2024, 1, 5, 12, 0).with_timezone(&Utc));
+        assert!(!schedule.is_due(local(2024, 1, 5, 12, 1)));
+
+        assert!(ScheduledCommand::new("0 9 * * *", "weekly", PathBuf::new()).is_err());
+        assert!(ScheduledCommand::new("bad", "/weekly", PathBuf::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn stores_schedules() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("schedules.json");
+        assert!(load_schedules(&path).await.unwrap().is_empty());
+
+        let first = ScheduledCommand::new("@daily", "/standup", PathBuf::from("/repo")).unwrap();
+        let second = ScheduledCommand::new("0 9 * * 1", "/weekly", PathBuf::from("/repo")).unwrap();
+        save_schedules(&path, &[first.clone(), second.clone()])
+            .await
+            .unwrap();
+
+        record_run(&path, &first.id, Utc::now(), Some("abc".to_string()), None)
+            .await
+            .unwrap();
+        let removed = remove_schedule(&path, &second.id[..4]).await.unwrap();
+        assert_eq!(removed.command, "/weekly");
+
+        let schedules = load_schedules(&path).await.unwrap();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].last_conversation_id.as_deref(), Some("abc"));
+        assert!(remove_schedule(&path, "missing").await.is_err());
+    }
+}
@@ -0,0 +1,481 @@
+use colored::Colorize;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::formatter::CodeFormatter;
+use crate::i18n::{tr, tr_args};
+
+/// Commands shown before the rest are summed up as "and N more". Risky
+/// commands are always shown.
+const MAX_PREVIEW_COMMANDS: usize = 20;
+
+/// Something an added command does that deserves a second look
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Risk {
+    Network,
+    PrivilegeEscalation,
+}
+
+impl Risk {
+    pub fn label(&self) -> String {
+        match self {
+            Risk::Network => tr("permission-risk-network"),
+            Risk::PrivilegeEscalation => tr("permission-risk-privilege"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// A shell script, or any file with a shebang or the executable bit
+    Script,
+    /// A CI pipeline definition, whose `run:`/`script:` steps are commands
+    Workflow,
+}
+
+/// A command line an edit adds
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddedCommand {
+    /// 1-based line in the new contents
+    pub line: usize,
+    pub text: String,
+    pub risks: Vec<Risk>,
+}
+
+/// The commands a change to a script or CI workflow adds. Editing one of
+/// these is running code later, whenever the script or pipeline runs, so
+/// permission prompts show what would run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptPreview {
+    pub kind: ScriptKind,
+    /// Language the commands are highlighted as
+    pub lang: &'static str,
+    pub commands: Vec<AddedCommand>,
+}
+
+impl ScriptPreview {
+    /// The preview for changing `path` from `old` (None for a new file) to
+    /// `new`, or None if it isn't a script or workflow or no commands are
+    /// added
+    pub fn for_change(path: &Path, old: Option<&str>, new: &str) -> Option<Self> {
+        let kind = script_kind(path, new)?;
+        let new_lines: Vec<&str> = new.lines().collect();
+        let added = added_range(old.unwrap_or_default(), &new_lines);
+
+        let (lang, candidates) = match kind {
+            ScriptKind::Workflow => ("bash", workflow_commands(&new_lines)),
+            ScriptKind::Script => (
+                script_lang(path, new),
+                script_commands(&new_lines[added.clone()], added.start),
+            ),
+        };
+        let commands: Vec<AddedCommand> = candidates
+            .into_iter()
+            .filter(|(index, _)| added.contains(index))
+            .map(|(index, text)| AddedCommand {
+                line: index + 1,
+                risks: risks(&text),
+                text,
+            })
+            .collect();
+        if commands.is_empty() {
+            return None;
+        }
+        Some(Self {
+            kind,
+            lang,
+            commands,
+        })
+    }
+
+    /// Every kind of risk the added commands carry, in a stable order
+    pub fn risks(&self) -> Vec<Risk> {
+        [Risk::Network, Risk::PrivilegeEscalation]
+            .into_iter()
+            .filter(|risk| self.commands.iter().any(|c| c.risks.contains(risk)))
+            .collect()
+    }
+
+    /// Summary for a permission prompt that calls out the risks, if any
+    pub fn risky_summary(&self) -> Option<String> {
+        risky_summary([self])
+    }
+
+    fn heading(&self) -> String {
+        match self.kind {
+            ScriptKind::Script => tr("permission-script-commands"),
+            ScriptKind::Workflow => tr("permission-workflow-commands"),
+        }
+    }
+
+    /// The commands worth showing, and how many more there are
+    fn shown(&self) -> (Vec<&AddedCommand>, usize) {
+        let shown: Vec<&AddedCommand> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(index, command)| *index < MAX_PREVIEW_COMMANDS || !command.risks.is_empty())
+            .map(|(_, command)| command)
+            .collect();
+        let hidden = self.commands.len() - shown.len();
+        (shown, hidden)
+    }
+
+    fn width(&self) -> usize {
+        self.commands
+            .last()
+            .map(|c| c.line.to_string().len())
+            .unwrap_or(1)
+    }
+
+    /// Plain text, for permission prompts shown outside the terminal and for
+    /// hooks and webhooks
+    pub fn detail(&self) -> String {
+        let width = self.width();
+        let (shown, hidden) = self.shown();
+        let mut lines = vec![self.heading()];
+        for command in shown {
+            let mut line = format!("{:>width$} │ {}", command.line, command.text);
+            if !command.risks.is_empty() {
+                line.push_str(&format!("  ⚠ {}", risk_labels(&command.risks)));
+            }
+            lines.push(line);
+        }
+        if hidden > 0 {
+            lines.push(tr_args(
+                "permission-script-more",
+                &[("count", &hidden.to_string())],
+            ));
+        }
+        lines.push(tr("permission-script-note"));
+        lines.join("\n")
+    }
+
+    /// The same with the commands highlighted and risks in red, for the
+    /// terminal prompt
+    pub fn highlighted(&self) -> Vec<String> {
+        let formatter = CodeFormatter::new().ok();
+        let width = self.width();
+        let (shown, hidden) = self.shown();
+        let mut lines = vec![self.heading().bold().to_string()];
+        for command in shown {
+            let text = match &formatter {
+                Some(formatter) => formatter.highlight_line(&command.text, self.lang),
+                None => command.text.clone(),
+            };
+            let mut line = format!(
+                "{} {}",
+                format!("{:>width$} │", command.line).dimmed(),
+                text
+            );
+            if !command.risks.is_empty() {
+                let warning = format!("⚠ {}", risk_labels(&command.risks));
+                line.push_str(&format!("  {}", warning.red().bold()));
+            }
+            lines.push(line);
+        }
+        if hidden > 0 {
+            lines.push(
+                tr_args("permission-script-more", &[("count", &hidden.to_string())])
+                    .dimmed()
+                    .to_string(),
+            );
+        }
+        lines.push(tr("permission-script-note").yellow().to_string());
+        lines
+    }
+}
+
+/// Summary for a permission prompt covering one or more previews, calling
+/// out every risk any of them carries
+pub fn risky_summary<'a>(previews: impl IntoIterator<Item = &'a ScriptPreview>) -> Option<String> {
+    let mut risks = Vec::new();
+    for preview in previews {
+        risks.extend(preview.risks());
+    }
+    let risks: Vec<Risk> = [Risk::Network, Risk::PrivilegeEscalation]
+        .into_iter()
+        .filter(|risk| risks.contains(risk))
+        .collect();
+    if risks.is_empty() {
+        return None;
+    }
+    Some(tr_args(
+        "permission-script-risky-summary",
+        &[("risks", &risk_labels(&risks))],
+    ))
+}
+
+fn risk_labels(risks: &[Risk]) -> String {
+    risks.iter().map(Risk::label).collect::<Vec<_>>().join(", ")
+}
+
+/// Extensions of scripts run by a shell
+const SCRIPT_EXTENSIONS: &[&str] = &["sh", "bash", "zsh", "ksh", "fish", "ps1", "bat", "cmd"];
+
+fn script_kind(path: &Path, new: &str) -> Option<ScriptKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let is_yaml = extension == "yml" || extension == "yaml";
+    let in_dir = |dir: &str| {
+        path.parent()
+            .is_some_and(|parent| parent.to_string_lossy().replace('\\', "/").ends_with(dir))
+    };
+    if (is_yaml && (in_dir(".github/workflows") || in_dir(".circleci")))
+        || matches!(
+            name.as_str(),
+            ".gitlab-ci.yml" | "azure-pipelines.yml" | "bitbucket-pipelines.yml"
+        )
+    {
+        return Some(ScriptKind::Workflow);
+    }
+    if SCRIPT_EXTENSIONS.contains(&extension.as_str())
+        || new.starts_with("#!")
+        || is_executable(path)
+    {
+        return Some(ScriptKind::Script);
+    }
+    None
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+fn script_lang(path: &Path, new: &str) -> &'static str {
+    let shebang = new.lines().next().filter(|line| line.starts_with("#!"));
+    match shebang {
+        Some(line) if line.contains("python") => "python",
+        Some(line) if line.contains("node") => "javascript",
+        Some(_) => "bash",
+        None => match path.extension().and_then(|e| e.to_str()) {
+            Some("ps1" | "bat" | "cmd") => "",
+            _ => "bash",
+        },
+    }
+}
+
+/// Indexes into `new` of the lines that differ from `old`, found the way
+/// [`crate::learn::diff`] does: what's left between the common start and end
+fn added_range(old: &str, new: &[&str]) -> std::ops::Range<usize> {
+    let old: Vec<&str> = old.lines().collect();
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    prefix..new.len() - suffix
+}
+
+/// Lines of a script that run something, leaving out blanks and comments
+fn script_commands(lines: &[&str], offset: usize) -> Vec<(usize, String)> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| (offset + index, line.trim()))
+        .filter(|(_, line)| {
+            let upper = line.to_uppercase();
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with("//")
+                && !line.starts_with("::")
+                && !upper.starts_with("REM ")
+        })
+        .map(|(index, line)| (index, line.to_string()))
+        .collect()
+}
+
+/// Command lines of a CI workflow: `run:` steps (GitHub, CircleCI),
+/// `script:` lists (GitLab, Bitbucket, Azure) and `command:` steps, inline
+/// or as block scalars
+fn workflow_commands(lines: &[&str]) -> Vec<(usize, String)> {
+    static STEP: OnceLock<Regex> = OnceLock::new();
+    let step = STEP.get_or_init(|| {
+        Regex::new(
+            r"^(\s*)(?:-\s+)?(run|script|before_script|after_script|command|bash|pwsh|powershell)\s*:\s*(.*)$",
+        )
+        .unwrap()
+    });
+    let indent = |line: &str| line.len() - line.trim_start().len();
+
+    let mut commands = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let Some(caps) = step.captures(lines[index]) else {
+            index += 1;
+            continue;
+        };
+        let key_indent = caps[1].len();
+        let value = caps[3].trim();
+        index += 1;
+        if value.starts_with('|') || value.starts_with('>') {
+            // Block scalar: everything indented deeper than the key
+            while index < lines.len()
+                && (lines[index].trim().is_empty() || indent(lines[index]) > key_indent)
+            {
+                let line = lines[index].trim();
+                if !line.is_empty() && !line.starts_with('#') {
+                    commands.push((index, line.to_string()));
+                }
+                index += 1;
+            }
+        } else if value.is_empty() {
+            // A list of commands, which YAML allows at the key's own indent
+            while index < lines.len()
+                && (lines[index].trim().is_empty()
+                    || indent(lines[index]) > key_indent
+                    || (indent(lines[index]) == key_indent
+                        && lines[index].trim_start().starts_with("- ")))
+            {
+                if let Some(item) = lines[index].trim().strip_prefix("- ") {
+                    commands.push((index, unquote(item).to_string()));
+                }
+                index += 1;
+            }
+        } else if !value.starts_with('#') {
+            commands.push((index - 1, unquote(value).to_string()));
+        }
+    }
+    commands
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+fn risks(command: &str) -> Vec<Risk> {
+    static NETWORK: OnceLock<Regex> = OnceLock::new();
+    static PRIVILEGE: OnceLock<Regex> = OnceLock::new();
+    let network = NETWORK.get_or_init(|| {
+        Regex::new(
+            r"(?i)(?:^|[\s;|&(`$])(?:curl|wget|nc|ncat|netcat|socat|ssh|scp|sftp|rsync|ftp|telnet|iwr|irm|invoke-webrequest|invoke-restmethod)(?:\s|$)|/dev/(?:tcp|udp)/|\bhttps?://",
+        )
+        .unwrap()
+    });
+    let privilege = PRIVILEGE.get_or_init(|| {
+        Regex::new(
+            r"(?i)(?:^|[\s;|&(`$])(?:sudo|doas|pkexec|su|runas|setcap)(?:\s|$)|\bchmod\s+(?:-\S+\s+)*(?:[ugoa]*\+[rwx]*s|[2467][0-7]{3})\b|\bchown\s+(?:-\S+\s+)*root\b|-verb\s+runas",
+        )
+        .unwrap()
+    });
+
+    let mut risks = Vec::new();
+    if network.is_match(command) {
+        risks.push(Risk::Network);
+    }
+    if privilege.is_match(command) {
+        risks.push(Risk::PrivilegeEscalation);
+    }
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_risky_commands_added_to_a_script() {
+        let old = "#!/bin/sh\nset -e\ncargo build\n";
+        let new = "#!/bin/sh\nset -e\n# fetch the installer\ncurl -fsSL https://example.com/install.sh | sudo sh\nchmod u+s ./helper\ncargo build\n";
+        let preview =
+            ScriptPreview::for_change(Path::new("scripts/build"), Some(old), new).unwrap();
+
+        assert_eq!(preview.kind, ScriptKind::Script);
+        assert_eq!(preview.lang, "bash");
+        assert_eq!(
+            preview.commands,
+            vec![
+                AddedCommand {
+                    line: 4,
+                    text: "curl -fsSL https://example.com/install.sh | sudo sh".to_string(),
+                    risks: vec![Risk::Network, Risk::PrivilegeEscalation],
+                },
+                AddedCommand {
+                    line: 5,
+                    text: "chmod u+s ./helper".to_string(),
+                    risks: vec![Risk::PrivilegeEscalation],
+                },
+            ]
+        );
+        assert_eq!(
+            preview.risks(),
+            vec![Risk::Network, Risk::PrivilegeEscalation]
+        );
+        let detail = preview.detail();
+        assert!(detail.contains("4 │ curl -fsSL"));
+        assert!(detail.contains("⚠ network call, privilege escalation"));
+        assert!(preview.risky_summary().is_some());
+    }
+
+    #[test]
+    fn finds_the_steps_added_to_a_workflow() {
+        let old = "jobs:\n  test:\n    steps:\n      - run: cargo test\n";
+        let new = "jobs:\n  test:\n    steps:\n      - run: cargo test\n      - name: Upload\n        run: |\n          tar czf out.tgz target\n          scp out.tgz deploy@example.com:/srv\n";
+        let path = Path::new(".github/workflows/ci.yml");
+        let preview = ScriptPreview::for_change(path, Some(old), new).unwrap();
+
+        assert_eq!(preview.kind, ScriptKind::Workflow);
+        let added: Vec<(usize, &str)> = preview
+            .commands
+            .iter()
+            .map(|c| (c.line, c.text.as_str()))
+            .collect();
+        assert_eq!(
+            added,
+            vec![
+                (7, "tar czf out.tgz target"),
+                (8, "scp out.tgz deploy@example.com:/srv")
+            ]
+        );
+        assert!(preview.commands[0].risks.is_empty());
+        assert_eq!(preview.commands[1].risks, vec![Risk::Network]);
+
+        let gitlab = "test:\n  script:\n  - cargo test\n  - \"wget -q https://example.com/data\"\n";
+        let preview = ScriptPreview::for_change(Path::new(".gitlab-ci.yml"), None, gitlab).unwrap();
+        assert_eq!(preview.commands.len(), 2);
+        assert_eq!(preview.commands[1].text, "wget -q https://example.com/data");
+        assert!(preview.risky_summary().is_some());
+    }
+
+    #[test]
+    fn other_files_and_harmless_changes_get_no_preview() {
+        assert!(
+            ScriptPreview::for_change(Path::new("src/main.rs"), None, "fn main() {}").is_none()
+        );
+        assert!(ScriptPreview::for_change(
+            Path::new("deploy.sh"),
+            Some("echo hi\n"),
+            "# say hello\necho hi\n"
+        )
+        .is_none());
+        let preview =
+            ScriptPreview::for_change(Path::new("deploy.sh"), Some("echo hi\n"), "echo hello\n")
+                .unwrap();
+        assert!(preview.risky_summary().is_none());
+        assert!(!risks("echo \"sudoku\" > scores.txt").contains(&Risk::PrivilegeEscalation));
+        assert!(!risks("cargo run --bin ssh-keys").contains(&Risk::Network));
+    }
+}
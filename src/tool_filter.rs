@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use glob::Pattern;
+use serde_json::Value;
+use std::fmt;
+
+/// One entry of `--allowed-tools` or `--disallowed-tools`: a tool name,
+/// optionally with a pattern for its main argument, like `Bash(git *)` or
+/// `Write(docs/*)`. Tool names are case-insensitive and may use `*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolRule {
+    pub tool: String,
+    pub pattern: Option<String>,
+}
+
+impl std::str::FromStr for ToolRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (tool, pattern) = match s.find('(') {
+            Some(open) => {
+                let pattern = s[open + 1..]
+                    .strip_suffix(')')
+                    .ok_or_else(|| anyhow!("Missing ')' in tool rule '{}'", s))?;
+                (&s[..open], Some(pattern.trim().to_string()))
+            }
+            None => (s, None),
+        };
+        let tool = tool.trim();
+        if tool.is_empty() {
+            return Err(anyhow!("Missing tool name in tool rule '{}'", s));
+        }
+        Ok(Self {
+            tool: tool.to_string(),
+            pattern: pattern.filter(|p| !p.is_empty()),
+        })
+    }
+}
+
+impl fmt::Display for ToolRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.pattern {
+            Some(pattern) => write!(f, "{}({})", self.tool, pattern),
+            None => write!(f, "{}", self.tool),
+        }
+    }
+}
+
+impl ToolRule {
+    fn matches_name(&self, name: &str) -> bool {
+        let (rule, name) = (self.tool.to_lowercase(), name.to_lowercase());
+        rule == name
+            || (rule.contains('*')
+                && Pattern::new(&rule).is_ok_and(|pattern| pattern.matches(&name)))
+    }
+
+    /// Whether the rule covers a call; a rule without a pattern covers every
+    /// call of the tool
+    fn matches_call(&self, name: &str, input: &Value) -> bool {
+        if !self.matches_name(name) {
+            return false;
+        }
+        let Some(pattern) = &self.pattern else {
+            return true;
+        };
+        primary_argument(input).is_some_and(|argument| matches_argument(argument, pattern))
+    }
+}
+
+/// The argument a rule's pattern is matched against: the command for Bash,
+/// the path for file tools
+fn primary_argument(input: &Value) -> Option<&str> {
+    ["command", "file_path", "path", "pattern"]
+        .iter()
+        .find_map(|key| input.get(*key).and_then(Value::as_str))
+}
+
+/// Exact match, glob match, or a command prefix (`git` matches `git status`)
+fn matches_argument(argument: &str, pattern: &str) -> bool {
+    let argument = argument.trim();
+    argument == pattern
+        || argument.starts_with(&format!("{} ", pattern))
+        || ((pattern.contains('*') || pattern.contains('?'))
+            && Pattern::new(pattern).is_ok_and(|glob| glob.matches(argument)))
+}
+
+/// Shell syntax that would let a command allowed by a pattern run others
+fn chains_commands(command: &str) -> bool {
+    ["&&", "||", ";", "|", "`", "$(", ">", "<", "\n", "&"]
+        .iter()
+        .any(|op| command.contains(op))
+}
+
+/// Per-invocation tool restrictions from `--allowed-tools` and
+/// `--disallowed-tools`. They only ever narrow what the configuration
+/// permits: a tool still goes through the usual permission checks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolFilter {
+    pub allowed: Vec<ToolRule>,
+    pub disallowed: Vec<ToolRule>,
+}
+
+impl ToolFilter {
+    /// Build a filter from flag values, each a comma-separated list of rules
+    pub fn from_args(allowed: &[String], disallowed: &[String]) -> Result<Self> {
+        Ok(Self {
+            allowed: parse_rules(allowed)?,
+            disallowed: parse_rules(disallowed)?,
+        })
+    }
+
+    /// Whether the model should be offered a tool at all
+    pub fn offers(&self, name: &str) -> bool {
+        let blocked = self
+            .disallowed
+            .iter()
+            .any(|rule| rule.pattern.is_none() && rule.matches_name(name));
+        let allowed =
+            self.allowed.is_empty() || self.allowed.iter().any(|rule| rule.matches_name(name));
+        allowed && !blocked
+    }
+
+    /// Check a tool call, returning the reason it isn't allowed
+    pub fn check_call(&self, name: &str, input: &Value) -> std::result::Result<(), String> {
+        if let Some(rule) = self
+            .disallowed
+            .iter()
+            .find(|rule| rule.matches_call(name, input))
+        {
+            return Err(format!("matches --disallowed-tools rule '{}'", rule));
+        }
+        if self.allowed.is_empty() {
+            return Ok(());
+        }
+        let allowed = self.allowed.iter().any(|rule| {
+            rule.matches_call(name, input)
+                && (rule.pattern.is_none() || !primary_argument(input).is_some_and(chains_commands))
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err("is not covered by --allowed-tools".to_string())
+        }
+    }
+}
+
+/// Split flag values on commas outside parentheses, so `Bash(git log, diff)`
+/// stays one rule
+fn parse_rules(values: &[String]) -> Result<Vec<ToolRule>> {
+    let mut rules = Vec::new();
+    for value in values {
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in value.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    push_rule(&mut rules, &value[start..i])?;
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        push_rule(&mut rules, &value[start..])?;
+    }
+    Ok(rules)
+}
+
+fn push_rule(rules: &mut Vec<ToolRule>, text: &str) -> Result<()> {
+    if !text.trim().is_empty() {
+        rules.push(text.parse()?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter(allowed: &[&str], disallowed: &[&str]) -> ToolFilter {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        ToolFilter::from_args(&strings(allowed), &strings(disallowed)).unwrap()
+    }
+
+    #[test]
+    fn parses_rules_with_patterns() {
+        let filter = filter(&["Read,glob", "Bash(git log, git diff)"], &[]);
+        assert_eq!(
+            filter.allowed,
+            vec![
+                ToolRule {
+                    tool: "Read".to_string(),
+                    pattern: None
+                },
+                ToolRule {
+                    tool: "glob".to_string(),
+                    pattern: None
+                },
+                ToolRule {
+                    tool: "Bash".to_string(),
+                    pattern: Some("git log, git diff".to_string())
+                },
+            ]
+        );
+        assert!("Bash(git *".parse::<ToolRule>().is_err());
+        assert!("(git *)".parse::<ToolRule>().is_err());
+    }
+
+    #[test]
+    fn allowed_tools_limit_what_is_offered_and_called() {
+        let filter = filter(&["read,Bash(git *)", "mcp_github_*"], &[]);
+        assert!(filter.offers("Read"));
+        assert!(filter.offers("Bash"));
+        assert!(filter.offers("mcp_github_search"));
+        assert!(!filter.offers("Write"));
+
+        assert!(filter
+            .check_call("Read", &json!({"file_path": "src/main.rs"}))
+            .is_ok());
+        assert!(filter
+            .check_call("Bash", &json!({"command": "git status"}))
+            .is_ok());
+        assert!(filter
+            .check_call("Bash", &json!({"command": "cargo build"}))
+            .is_err());
+        assert!(filter
+            .check_call("Bash", &json!({"command": "git status && rm -rf ."}))
+            .is_err());
+        assert!(filter
+            .check_call("Write", &json!({"file_path": "a.txt"}))
+            .is_err());
+    }
+
+    #[test]
+    fn disallowed_tools_win_over_allowed_tools() {
+        let filter = filter(&["Bash"], &["Bash(git push)", "delete_file"]);
+        assert!(filter.offers("Bash"));
+        assert!(!filter.offers("delete_file"));
+        assert!(filter
+            .check_call("Bash", &json!({"command": "git status"}))
+            .is_ok());
+        assert!(filter
+            .check_call("Bash", &json!({"command": "git push origin main"}))
+            .is_err());
+        assert!(ToolFilter::default()
+            .check_call("Write", &json!({"file_path": "a.txt"}))
+            .is_ok());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::i18n::tr;
+use crate::security::{PermissionHandler, PermissionKind, PermissionPrompt};
+use crate::tools::types::{Tool, ToolCall, ToolResult};
+use anyhow::Result;
+use colored::Colorize;
+use dialoguer::Select;
+use log::debug;
+use serde_json::json;
+
+/// Most answers the model may offer; more than this is a sign the question
+/// should be narrowed
+const MAX_OPTIONS: usize = 8;
+
+const DESCRIPTION: &str = "Ask the user a clarifying question when the request is ambiguous and \
+a wrong guess would produce the wrong code. Offer the likely answers in `options` so they can \
+pick one; they can always answer in their own words instead. Don't ask about things you can find \
+out by reading the code.";
+
+/// Ask the user the question in `call` through the permission prompt, or on
+/// the terminal when there's no handler, and describe their answer for the
+/// model
+pub async fn ask_user(call: &ToolCall, handler: Option<PermissionHandler>) -> Result<ToolResult> {
+    let question = extract_string_arg!(call, "question").trim().to_string();
+    let options: Vec<String> = call
+        .arguments
+        .get("options")
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|option| option.trim().to_string())
+                .filter(|option| !option.is_empty())
+                .take(MAX_OPTIONS)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    debug!("TOOL CALL: ask_user('{}', {:?})", question, options);
+
+    let mut choices = options.clone();
+    choices.push(if options.is_empty() {
+        tr("permission-question-reply")
+    } else {
+        tr("permission-question-other")
+    });
+
+    let selection = match handler {
+        Some(handler) => {
+            (handler)(PermissionPrompt {
+                kind: PermissionKind::Question,
+                summary: tr("permission-question-summary"),
+                detail: question.clone(),
+                options: choices,
+            })
+            .await
+        }
+        None => {
+            app_println!();
+            app_println!("{}", tr("permission-question-title").yellow().bold());
+            app_println!("{}", question);
+            app_println!();
+            tokio::task::spawn_blocking(move || {
+                Select::new()
+                    .with_prompt(tr("permission-select-option"))
+                    .items(&choices)
+                    .default(0)
+                    .interact()
+                    .ok()
+            })
+            .await
+            .ok()
+            .flatten()
+        }
+    };
+
+    Ok(ToolResult {
+        tool_use_id: call.id.clone(),
+        content: describe_answer(&options, selection),
+        is_error: false,
+    })
+}
+
+/// What the model is told about the user's choice among `options`, where
+/// the index one past the end means they want to answer in their own words
+fn describe_answer(options: &[String], selection: Option<usize>) -> String {
+    match selection {
+        Some(index) if index < options.len() => {
+            format!("The user answered: {}", options[index])
+        }
+        Some(_) => "The user wants to answer in their own words. End your turn by asking the \
+                    question in your reply, and wait for their answer before going on."
+            .to_string(),
+        None => "The user dismissed the question without answering. Don't guess at anything \
+                 that matters: either ask again in your reply, or go on and say which assumption \
+                 you made."
+            .to_string(),
+    }
+}
+
+pub fn create_ask_user_tool() -> Tool {
+    Tool {
+        name: "ask_user".to_string(),
+        description: DESCRIPTION.to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "question": {
+                    "type": "string",
+                    "description": "The question, with enough context to answer it without scrolling back"
+                },
+                "options": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Likely answers for the user to pick from (optional, at most 8)"
+                }
+            },
+            "required": ["question"]
+        }),
+        handler: Box::new(|_call| {
+            Box::pin(async move {
+                Ok(ToolResult {
+                    tool_use_id: String::new(),
+                    content: "ask_user is handled internally by the Agent".to_string(),
+                    is_error: false,
+                })
+            })
+        }),
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn asks_through_the_permission_prompt() {
+        let seen = Arc::new(Mutex::new(None));
+        let seen_by_handler = seen.clone();
+        let handler: PermissionHandler = Arc::new(move |prompt: PermissionPrompt| {
+            *seen_by_handler.lock().unwrap() = Some(prompt);
+            Box::pin(async { Some(1) })
+        });
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            name: "ask_user".to_string(),
+            arguments: json!({
+                "question": "Which database?",
+                "options": ["SQLite", "Postgres", " "]
+            }),
+        };
+
+        let result = ask_user(&call, Some(handler)).await.unwrap();
+        assert_eq!(result.content, "The user answered: Postgres");
+        assert!(!result.is_error);
+        let prompt = seen.lock().unwrap().take().unwrap();
+        assert!(matches!(prompt.kind, PermissionKind::Question));
+        assert_eq!(prompt.detail, "Which database?");
+        assert_eq!(prompt.options.len(), 3);
+    }
+
+    #[test]
+    fn describes_each_kind_of_answer() {
+        let options = vec!["Yes".to_string(), "No".to_string()];
+        assert_eq!(describe_answer(&options, Some(0)), "The user answered: Yes");
+        assert!(describe_answer(&options, Some(2)).contains("in their own words"));
+        assert!(describe_answer(&[], Some(0)).contains("in their own words"));
+        assert!(describe_answer(&options, None).contains("dismissed"));
+    }
+}
@@ -1,10 +1,11 @@
+use crate::conversation_env::ConversationEnv;
 use crate::security::BashSecurityManager;
 use crate::tools::types::{Tool, ToolCall, ToolResult};
 use anyhow::Result;
 use log::{debug, info};
 use serde_json::json;
-use std::process::Command;
-use tokio::task;
+use std::process::{Output, Stdio};
+use tokio::process::Command;
 
 // Convert Unix command separators to Windows PowerShell equivalents
 // This function properly handles quoted strings, escape sequences, and complex command structures
@@ -76,108 +77,187 @@ fn convert_unix_separators_to_windows(command: &str) -> String {
     result.trim().to_string()
 }
 
-pub async fn bash(
-    call: &ToolCall,
+/// Check `command` against the security policy, asking the user when it
+/// needs permission. Returns whether the allowlist was updated, or the
+/// message to report when the command may not run.
+pub(crate) async fn authorize(
+    command: &str,
     security_manager: &mut BashSecurityManager,
     yolo_mode: bool,
-) -> Result<ToolResult> {
-    let command = extract_string_arg!(call, "command").to_string();
-
-    debug!("TOOL CALL: bash('{}')", command);
-
-    let tool_use_id = call.id.clone();
-    let mut permissions_updated = false;
-
-    // Check security permissions
+) -> std::result::Result<bool, String> {
     if yolo_mode {
         debug!("YOLO MODE: Bypassing security for command '{}'", command);
-    } else {
-        match security_manager.check_command_permission(&command) {
-            crate::security::PermissionResult::Allowed => {
-                debug!("Command '{}' is allowed by security policy", command);
-            }
-            crate::security::PermissionResult::Denied => {
-                return Ok(ToolResult {
-                    tool_use_id,
-                    content: format!("🔒 Security: Command '{}' is not allowed by security policy. Use /permissions to manage allowed commands.", command),
-                    is_error: true,
-                });
-            }
-            crate::security::PermissionResult::RequiresPermission => {
-                // Ask user for permission
-                match security_manager.ask_permission(&command).await {
-                    Ok(Some(true)) => {
-                        // User granted permission and wants to add to allowlist
-                        info!(
-                            "User granted permission for command: {} (added to allowlist)",
-                            command
-                        );
-                        permissions_updated = true;
-                    }
-                    Ok(Some(false)) => {
-                        // User granted permission for this time only
-                        info!("User granted one-time permission for command: {}", command);
-                    }
-                    Ok(None) => {
-                        return Ok(ToolResult {
-                            tool_use_id,
-                            content: format!(
-                                "🔒 Security: Permission denied for command '{}'",
-                                command
-                            ),
-                            is_error: true,
-                        });
-                    }
-                    Err(e) => {
-                        return Ok(ToolResult {
-                            tool_use_id,
-                            content: format!(
-                                "🔒 Security: Error checking permission for command '{}': {}",
-                                command, e
-                            ),
-                            is_error: true,
-                        });
-                    }
+        return Ok(false);
+    }
+    match security_manager.check_command_permission(command) {
+        crate::security::PermissionResult::Allowed => {
+            debug!("Command '{}' is allowed by security policy", command);
+            Ok(false)
+        }
+        crate::security::PermissionResult::Denied => Err(format!("🔒 Security: Command '{}' is not allowed by security policy. Use /permissions to manage allowed commands.", command)),
+        crate::security::PermissionResult::RequiresPermission => {
+            // Ask user for permission
+            match security_manager.ask_permission(command).await {
+                Ok(Some(true)) => {
+                    // User granted permission and wants to add to allowlist
+                    info!(
+                        "User granted permission for command: {} (added to allowlist)",
+                        command
+                    );
+                    Ok(true)
+                }
+                Ok(Some(false)) => {
+                    // User granted permission for this time only
+                    info!("User granted one-time permission for command: {}", command);
+                    Ok(false)
                 }
+                Ok(None) => Err(format!(
+                    "🔒 Security: Permission denied for command '{}'",
+                    command
+                )),
+                Err(e) => Err(format!(
+                    "🔒 Security: Error checking permission for command '{}': {}",
+                    command, e
+                )),
             }
         }
     }
+}
 
+/// Run `command` under the same security policy as the Bash tool and return
+/// what it printed, for `!`command`` in custom command templates
+pub async fn run_for_output(
+    command: &str,
+    security_manager: &mut BashSecurityManager,
+    yolo_mode: bool,
+    env: &ConversationEnv,
+) -> Result<String> {
+    authorize(command, security_manager, yolo_mode)
+        .await
+        .map_err(anyhow::Error::msg)?;
+    let mut cmd = shell_command(command);
+    env.apply(&mut cmd);
+    let output = run_command(cmd).await?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        text.push_str(&stderr);
+    }
+    Ok(env.mask(&text))
+}
+
+/// A shell running `command`: bash, or PowerShell on Windows. kill_on_drop
+/// means a cancelled tool call takes the running command down with it, and
+/// on Unix the shell leads its own process group so [`run_command`] can take
+/// down everything it started too.
+pub(crate) fn shell_command(command: &str) -> Command {
     // Convert command separators for Windows compatibility
     let processed_command = if cfg!(target_os = "windows") {
         // Convert Unix-style separators to PowerShell-compatible syntax
-        convert_unix_separators_to_windows(&command)
+        convert_unix_separators_to_windows(command)
     } else {
-        command.clone()
+        command.to_string()
     };
 
-    // Execute the command using tokio::task to spawn blocking operation
-    let command_clone = processed_command.clone();
-    match task::spawn_blocking(move || {
-        #[cfg(target_os = "windows")]
-        {
-            // Use PowerShell for better command handling on Windows
-            // For multiple commands, we need to use -Command with proper PowerShell syntax
-            let mut cmd = Command::new("powershell");
-
-            // If the command contains semicolons, it's likely multiple commands
-            if command_clone.contains(';') {
-                cmd.args(["-Command", &command_clone]);
-            } else {
-                // For single commands, we can use -Command as well for consistency
-                cmd.args(["-Command", &command_clone]);
-            }
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        // Use PowerShell for better command handling on Windows
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-Command", &processed_command]);
+        cmd
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut cmd = Command::new("bash");
+        cmd.args(["-c", &processed_command]);
+        cmd.process_group(0);
+        cmd
+    };
+    cmd.kill_on_drop(true);
+    cmd
+}
 
-            cmd.output()
-        }
-        #[cfg(not(target_os = "windows"))]
-        {
-            Command::new("bash").args(["-c", &command_clone]).output()
+/// Kills a process group when dropped, unless it was disarmed because the
+/// command finished
+#[cfg(unix)]
+struct ProcessGroupGuard {
+    pgid: Option<u32>,
+}
+
+#[cfg(unix)]
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.pgid {
+            debug!("Killing process group {}", pgid);
+            // SAFETY: killpg has no memory-safety preconditions
+            unsafe {
+                libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+            }
         }
-    })
-    .await
+    }
+}
+
+/// Run `cmd` and collect its output, like `Command::output`. Dropping the
+/// future, as cancelling a tool call does, kills the command and, on Unix,
+/// every process in its group, so a cancelled `cargo build` doesn't leave
+/// compilers running.
+pub(crate) async fn run_command(mut cmd: Command) -> std::io::Result<Output> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let child = cmd.spawn()?;
+    #[cfg(unix)]
+    let mut guard = ProcessGroupGuard { pgid: child.id() };
+    let output = child.wait_with_output().await;
+    #[cfg(unix)]
     {
-        Ok(Ok(output)) => {
+        guard.pgid = None;
+    }
+    output
+}
+
+pub async fn bash(
+    call: &ToolCall,
+    security_manager: &mut BashSecurityManager,
+    yolo_mode: bool,
+) -> Result<ToolResult> {
+    bash_with_env(
+        call,
+        security_manager,
+        yolo_mode,
+        &ConversationEnv::default(),
+    )
+    .await
+}
+
+/// Run a Bash tool call with the conversation's `/env` variables set, masking
+/// the secret ones in its output
+pub async fn bash_with_env(
+    call: &ToolCall,
+    security_manager: &mut BashSecurityManager,
+    yolo_mode: bool,
+    env: &ConversationEnv,
+) -> Result<ToolResult> {
+    let command = extract_string_arg!(call, "command").to_string();
+
+    debug!("TOOL CALL: bash('{}')", command);
+
+    let tool_use_id = call.id.clone();
+    let permissions_updated = match authorize(&command, security_manager, yolo_mode).await {
+        Ok(updated) => updated,
+        Err(message) => {
+            return Ok(ToolResult {
+                tool_use_id,
+                content: message,
+                is_error: true,
+            })
+        }
+    };
+
+    let mut cmd = shell_command(&command);
+    env.apply(&mut cmd);
+    match run_command(cmd).await {
+        Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
 
@@ -205,18 +285,13 @@ pub async fn bash(
 
             Ok(ToolResult {
                 tool_use_id,
-                content: final_content,
+                content: env.mask(&final_content),
                 is_error: !output.status.success(),
             })
         }
-        Ok(Err(e)) => Ok(ToolResult {
-            tool_use_id,
-            content: format!("Error executing command '{}': {}", command, e),
-            is_error: true,
-        }),
         Err(e) => Ok(ToolResult {
             tool_use_id,
-            content: format!("Task join error: {}", e),
+            content: format!("Error executing command '{}': {}", command, e),
             is_error: true,
         }),
     }
@@ -326,4 +401,44 @@ mod tests {
         let error = result.expect_err("expected missing command error");
         assert!(error.to_string().contains("Missing 'command' argument"));
     }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn dropping_bash_call_kills_the_command() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let marker = dir.path().join("finished");
+        let call = make_call(json!({
+            "command": format!("sleep 1 && touch '{}'", marker.display())
+        }));
+        let mut security_manager =
+            BashSecurityManager::new(crate::security::BashSecurity::default());
+
+        let outcome = tokio::time::timeout(
+            std::time::Duration::from_millis(200),
+            bash(&call, &mut security_manager, true),
+        )
+        .await;
+        assert!(outcome.is_err(), "command should still be running");
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn dropping_bash_call_kills_the_whole_process_group() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let marker = dir.path().join("finished");
+        let command = format!("(sleep 1 && touch '{}') & wait", marker.display());
+
+        let outcome = tokio::time::timeout(
+            std::time::Duration::from_millis(200),
+            run_command(shell_command(&command)),
+        )
+        .await;
+        assert!(outcome.is_err(), "command should still be running");
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!marker.exists(), "background child should have been killed");
+    }
 }
@@ -1,8 +1,10 @@
 use crate::tools::types::{Tool, ToolResult};
 use crate::tools::{
-    bash::bash_sync, create_directory::create_directory_sync, delete_file::delete_file_sync,
+    bash::bash_sync, create_directory::create_directory_sync,
+    create_pr::create_create_pr_placeholder_tool, delete_file::delete_file_sync,
     edit_file::edit_file_sync, glob::create_glob_tool, list_directory::create_list_directory_tool,
     multi_read_files::create_multi_read_files_tool, read_file::create_read_file_tool,
+    replace_in_files::create_replace_in_files_placeholder_tool,
     search_in_files::create_search_in_files_tool, write_file::write_file_sync,
 };
 use serde_json::json;
@@ -130,6 +132,7 @@ pub fn get_builtin_tools() -> Vec<Tool> {
             handler: Box::new(edit_file_sync),
             metadata: None, // TODO: Add proper metadata
         },
+        create_replace_in_files_placeholder_tool(),
         Tool {
             name: "delete_file".to_string(),
             description: "Delete a file or directory".to_string(),
@@ -180,5 +183,6 @@ pub fn get_builtin_tools() -> Vec<Tool> {
             handler: Box::new(bash_sync),
             metadata: None, // TODO: Add proper metadata
         },
+        create_create_pr_placeholder_tool(),
     ]
 }
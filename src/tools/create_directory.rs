@@ -36,6 +36,7 @@ pub async fn create_directory(
         tool_use_id.clone(),
         file_security_manager,
         yolo_mode,
+        None,
     )
     .await?
     {
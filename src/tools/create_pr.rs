@@ -0,0 +1,136 @@
+use crate::pull_request::{PullRequest, Target};
+use crate::security::BashSecurityManager;
+use crate::tools::bash::authorize;
+use crate::tools::types::{Tool, ToolCall, ToolResult};
+use anyhow::Result;
+use log::debug;
+use serde_json::json;
+
+/// Push the current branch and open a pull request for it. The push and
+/// the `gh pr create` go through the bash security policy, so they can be
+/// allowlisted like any other command.
+pub async fn create_pr(
+    call: &ToolCall,
+    security_manager: &mut BashSecurityManager,
+    yolo_mode: bool,
+) -> Result<ToolResult> {
+    let title = extract_string_arg!(call, "title").to_string();
+    let body = call
+        .arguments
+        .get("body")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let base = call.arguments.get("base").and_then(|v| v.as_str());
+    let draft = call
+        .arguments
+        .get("draft")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    debug!("TOOL CALL: create_pr(title='{}', base={:?})", title, base);
+
+    let tool_use_id = call.id.clone();
+    let error = |content: String| {
+        Ok(ToolResult {
+            tool_use_id: tool_use_id.clone(),
+            content,
+            is_error: true,
+        })
+    };
+
+    let dir = std::env::current_dir()?;
+    let target = match Target::for_current_branch(&dir, base, draft).await {
+        Ok(target) => target,
+        Err(e) => return error(format!("Can't open a pull request: {}", e)),
+    };
+    for command in [target.push_command(), target.create_command()] {
+        if let Err(message) = authorize(&command, security_manager, yolo_mode).await {
+            return error(message);
+        }
+    }
+
+    if let Err(e) = target.push(&dir).await {
+        return error(format!("Failed to push {}: {}", target.branch, e));
+    }
+    let pull_request = PullRequest { title, body };
+    match target.create(&dir, &pull_request).await {
+        Ok(url) => Ok(ToolResult {
+            tool_use_id,
+            content: format!("Pushed {} and opened {}", target.branch, url),
+            is_error: false,
+        }),
+        Err(e) => error(format!(
+            "Pushed {} but couldn't open the pull request: {}",
+            target.branch, e
+        )),
+    }
+}
+
+pub fn create_pr_sync(
+    call: ToolCall,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<ToolResult>> + Send>> {
+    Box::pin(async move {
+        // Like the Bash placeholder, the Agent runs this with its own security manager
+        let mut bash_security_manager =
+            crate::security::BashSecurityManager::new(crate::security::BashSecurity::default());
+        create_pr(&call, &mut bash_security_manager, false).await
+    })
+}
+
+fn input_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "title": {
+                "type": "string",
+                "description": "Pull request title, in the imperative mood"
+            },
+            "body": {
+                "type": "string",
+                "description": "Pull request description in Markdown: what changed, why, and how it was tested"
+            },
+            "base": {
+                "type": "string",
+                "description": "Branch to merge into (defaults to the repository's default branch)"
+            },
+            "draft": {
+                "type": "boolean",
+                "description": "Open the pull request as a draft"
+            }
+        },
+        "required": ["title", "body"]
+    })
+}
+
+const DESCRIPTION: &str = "Push the current git branch and open a GitHub pull request for it with the gh CLI. Commit the changes first. Returns the pull request URL.";
+
+/// The placeholder in the builtin tool list; the Agent runs the real one
+pub fn create_create_pr_placeholder_tool() -> Tool {
+    Tool {
+        name: "create_pr".to_string(),
+        description: DESCRIPTION.to_string(),
+        input_schema: input_schema(),
+        handler: Box::new(create_pr_sync),
+        metadata: None,
+    }
+}
+
+pub fn create_create_pr_tool(
+    security_manager: std::sync::Arc<tokio::sync::RwLock<BashSecurityManager>>,
+    yolo_mode: bool,
+) -> Tool {
+    Tool {
+        name: "create_pr".to_string(),
+        description: DESCRIPTION.to_string(),
+        input_schema: input_schema(),
+        handler: Box::new(move |call: ToolCall| {
+            let security_manager = security_manager.clone();
+            Box::pin(async move {
+                let mut manager = security_manager.write().await;
+                create_pr(&call, &mut manager, yolo_mode).await
+            })
+        }),
+        metadata: None,
+    }
+}
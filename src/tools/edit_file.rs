@@ -1,4 +1,5 @@
-use crate::security::FileSecurityManager;
+use crate::script_preview::ScriptPreview;
+use crate::security::{FileSecurityManager, FileWrite};
 use crate::tools::path::resolve_project_path;
 use crate::tools::security_utils::check_file_security;
 use crate::tools::types::{Tool, ToolCall, ToolResult};
@@ -50,6 +51,30 @@ pub async fn edit_file(
         }
     };
 
+    // Work out the edit first, so a change to a script can be previewed
+    // when asking for permission
+    let edit = fs::read_to_string(&absolute_path).await.map(|content| {
+        // Detect the line ending type used in the file
+        let file_line_ending = detect_line_ending(&content);
+
+        // Normalize old_text to use the file's line endings for matching
+        let normalized_old_text = normalize_line_endings(old_text, file_line_ending);
+        if !content.contains(&normalized_old_text) {
+            return Err(normalized_old_text);
+        }
+
+        // Normalize new_text to use the file's line endings
+        let normalized_new_text = normalize_line_endings(new_text, file_line_ending);
+        let edited = content.replace(&normalized_old_text, &normalized_new_text);
+        Ok((content, edited))
+    });
+    let preview = match &edit {
+        Ok(Ok((content, edited))) => {
+            ScriptPreview::for_change(&absolute_path, Some(content), edited)
+        }
+        _ => None,
+    };
+
     // Check file security permissions
     if let Some(result) = check_file_security(
         "Edit",
@@ -57,39 +82,28 @@ pub async fn edit_file(
         tool_use_id.clone(),
         file_security_manager,
         yolo_mode,
+        preview.as_ref(),
     )
     .await?
     {
         return Ok(result);
     }
 
-    // Read existing file
-    match fs::read_to_string(&absolute_path).await {
-        Ok(mut content) => {
-            // Detect the line ending type used in the file
-            let file_line_ending = detect_line_ending(&content);
-
-            // Normalize old_text to use the file's line endings for matching
-            let normalized_old_text = normalize_line_endings(old_text, file_line_ending);
-
-            if !content.contains(&normalized_old_text) {
-                return Ok(ToolResult {
-                    tool_use_id,
-                    content: format!(
-                        "Text not found in file '{}': {}",
-                        absolute_path.display(),
-                        normalized_old_text
-                    ),
-                    is_error: true,
-                });
-            }
-
-            // Normalize new_text to use the file's line endings
-            let normalized_new_text = normalize_line_endings(new_text, file_line_ending);
-
-            content = content.replace(&normalized_old_text, &normalized_new_text);
+    match edit {
+        Ok(Ok((content, edited))) => {
+            let written = match file_security_manager.write_handler() {
+                Some(handler) => {
+                    handler(FileWrite {
+                        path: absolute_path.clone(),
+                        old_content: Some(content),
+                        new_content: edited,
+                    })
+                    .await
+                }
+                None => fs::write(&absolute_path, edited).await.map_err(Into::into),
+            };
 
-            match fs::write(&absolute_path, content).await {
+            match written {
                 Ok(_) => Ok(ToolResult {
                     tool_use_id,
                     content: format!("Successfully edited file: {}", absolute_path.display()),
@@ -102,6 +116,15 @@ pub async fn edit_file(
                 }),
             }
         }
+        Ok(Err(normalized_old_text)) => Ok(ToolResult {
+            tool_use_id,
+            content: format!(
+                "Text not found in file '{}': {}",
+                absolute_path.display(),
+                normalized_old_text
+            ),
+            is_error: true,
+        }),
         Err(e) => Ok(ToolResult {
             tool_use_id,
             content: format!("Error reading file '{}': {}", absolute_path.display(), e),
@@ -266,4 +289,95 @@ mod tests {
         assert!(result.is_error);
         assert!(result.content.contains("Text not found in file"));
     }
+
+    #[tokio::test]
+    async fn edit_hands_changes_to_write_handler() {
+        let (_temp_dir, path) = temp_file_path("edit_handler");
+        tokio::fs::write(&path, "alpha\nbeta\n")
+            .await
+            .expect("write temp file");
+
+        let call = ToolCall {
+            id: "test_handler".to_string(),
+            name: "Edit".to_string(),
+            arguments: json!({
+                "path": path.to_string_lossy(),
+                "old_text": "beta",
+                "new_text": "gamma"
+            }),
+        };
+
+        let captured = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let sink = captured.clone();
+        let mut file_security_manager =
+            FileSecurityManager::new(crate::security::FileSecurity::default());
+        file_security_manager.set_write_handler(Some(std::sync::Arc::new(
+            move |write: FileWrite| {
+                *sink.lock().unwrap() = Some(write);
+                Box::pin(async { Ok(()) })
+            },
+        )));
+
+        let result = edit_file(&call, &mut file_security_manager, true)
+            .await
+            .expect("edit file result");
+        assert!(!result.is_error);
+
+        let write = captured.lock().unwrap().take().expect("handler called");
+        assert_eq!(write.old_content.as_deref(), Some("alpha\nbeta\n"));
+        assert_eq!(write.new_content, "alpha\ngamma\n");
+
+        // The file on disk is left for the handler to change
+        let on_disk = tokio::fs::read_to_string(&path).await.expect("read file");
+        assert_eq!(on_disk, "alpha\nbeta\n");
+    }
+
+    #[tokio::test]
+    async fn edit_prompt_previews_commands_added_to_a_script() {
+        let temp_dir =
+            tempfile::tempdir_in(std::env::current_dir().expect("current dir")).expect("temp dir");
+        let path = temp_dir.path().join("deploy.sh");
+        tokio::fs::write(&path, "set -e\nmake\n")
+            .await
+            .expect("write temp file");
+
+        let call = ToolCall {
+            id: "test_preview".to_string(),
+            name: "Edit".to_string(),
+            arguments: json!({
+                "path": path.to_string_lossy(),
+                "old_text": "make\n",
+                "new_text": "make\nsudo curl -o /usr/local/bin/tool https://example.com/tool\n"
+            }),
+        };
+
+        let prompts = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = prompts.clone();
+        let mut file_security_manager = FileSecurityManager::new(crate::security::FileSecurity {
+            enabled: true,
+            ask_for_permission: true,
+            allow_all_session: false,
+        });
+        file_security_manager.set_permission_handler(Some(std::sync::Arc::new(
+            move |prompt: crate::security::PermissionPrompt| {
+                seen.lock().unwrap().push(prompt);
+                Box::pin(async { Some(2) })
+            },
+        )));
+
+        let result = edit_file(&call, &mut file_security_manager, false)
+            .await
+            .expect("edit file result");
+        assert!(result.is_error);
+
+        let prompt = prompts.lock().unwrap().pop().expect("asked for permission");
+        assert!(prompt
+            .summary
+            .contains("network call, privilege escalation"));
+        assert!(prompt
+            .detail
+            .contains("3 │ sudo curl -o /usr/local/bin/tool https://example.com/tool"));
+        let on_disk = tokio::fs::read_to_string(&path).await.expect("read file");
+        assert_eq!(on_disk, "set -e\nmake\n");
+    }
 }
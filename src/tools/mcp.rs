@@ -1,11 +1,25 @@
-use crate::mcp::{McpManager, McpTool};
+use crate::mcp::{McpManager, McpTool, NamedMcpTool};
 use crate::tools::types::{Tool, ToolCall, ToolResult};
 use log;
 use serde_json::{json, Value};
 use std::sync::Arc;
 
 pub fn create_mcp_tool(server_name: &str, mcp_tool: McpTool, mcp_manager: Arc<McpManager>) -> Tool {
-    let tool_name = format!("mcp_{}_{}", server_name, mcp_tool.name);
+    create_named_mcp_tool(
+        NamedMcpTool {
+            name: format!("mcp_{}_{}", server_name, mcp_tool.name),
+            server_name: server_name.to_string(),
+            tool: mcp_tool,
+        },
+        mcp_manager,
+    )
+}
+
+/// Wrap an MCP tool under the name chosen by `assign_mcp_tool_names`
+pub fn create_named_mcp_tool(named: NamedMcpTool, mcp_manager: Arc<McpManager>) -> Tool {
+    let tool_name = named.name;
+    let server_name = named.server_name.as_str();
+    let mcp_tool = named.tool;
     let description = mcp_tool
         .description
         .unwrap_or_else(|| format!("MCP tool from server: {}", server_name));
@@ -91,16 +105,8 @@ pub fn create_mcp_tool(server_name: &str, mcp_tool: McpTool, mcp_manager: Arc<Mc
                     log::debug!("   Arguments: <No arguments>");
                 }
 
-                // Extract the actual tool name from the mcp_ prefix
-                let actual_tool_name = call
-                    .name
-                    .strip_prefix(&format!("mcp_{}_", server_name))
-                    .unwrap_or(&tool_name_original);
-
-                log::debug!("   Resolved tool name: {}", actual_tool_name);
-
                 match mcp_manager
-                    .call_tool(&server_name, actual_tool_name, Some(call.arguments))
+                    .call_tool(&server_name, &tool_name_original, Some(call.arguments))
                     .await
                 {
                     Ok(result) => {
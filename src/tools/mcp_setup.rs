@@ -0,0 +1,337 @@
+use crate::i18n::{tr, tr_args};
+use crate::mcp::McpManager;
+use crate::mcp_catalog::{self, CatalogEntry};
+use crate::security::{PermissionHandler, PermissionKind, PermissionPrompt};
+use crate::tools::types::{Tool, ToolCall, ToolResult};
+use anyhow::Result;
+use colored::Colorize;
+use dialoguer::Select;
+use log::debug;
+use serde_json::json;
+
+const FIND_DESCRIPTION: &str = "Look up MCP servers that connect Flexorama to an external \
+service. Use this when the user asks for something you can't do with your current tools, such as \
+reading their Linear or Jira issues, Sentry errors or Slack messages. Then propose the best match \
+with add_mcp_server.";
+
+const ADD_DESCRIPTION: &str = "Add and connect an MCP server from the catalog that \
+find_mcp_server searches. The user is asked to confirm first. Its tools are available from your \
+next request on.";
+
+/// The tools that are only offered when MCP is available
+pub const MCP_SETUP_TOOLS: &[&str] = &["find_mcp_server", "add_mcp_server"];
+
+/// Search the catalog for the integration described in `call`
+pub async fn find_mcp_server(call: &ToolCall, mcp_manager: &McpManager) -> Result<ToolResult> {
+    let query = call
+        .arguments
+        .get("query")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    debug!("TOOL CALL: find_mcp_server('{}')", query);
+
+    let matches = mcp_catalog::search(query);
+    if matches.is_empty() {
+        return Ok(ToolResult {
+            tool_use_id: call.id.clone(),
+            content: format!(
+                "No catalog server matches '{}'. The user can add any MCP server with /mcp add.",
+                query
+            ),
+            is_error: false,
+        });
+    }
+
+    let configured = mcp_manager.load_config().await?.servers;
+    let mut lines = Vec::new();
+    for entry in matches {
+        let status = if mcp_manager.is_connected(entry.name).await {
+            " [connected]"
+        } else if configured.contains_key(entry.name) {
+            " [configured, not connected]"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "- {}{}: {} ({})",
+            entry.name,
+            status,
+            entry.description,
+            entry.location()
+        ));
+        if !entry.required_env.is_empty() {
+            lines.push(format!("  needs: {}", entry.required_env.join(", ")));
+        }
+    }
+    Ok(ToolResult {
+        tool_use_id: call.id.clone(),
+        content: lines.join("\n"),
+        is_error: false,
+    })
+}
+
+/// Add the catalog server named in `call` once the user agrees, then connect it
+pub async fn add_mcp_server(
+    call: &ToolCall,
+    mcp_manager: &McpManager,
+    handler: Option<PermissionHandler>,
+) -> Result<ToolResult> {
+    let name = extract_string_arg!(call, "name");
+    let reason = call
+        .arguments
+        .get("reason")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    debug!("TOOL CALL: add_mcp_server('{}')", name);
+
+    let result = |content: String, is_error: bool| {
+        Ok(ToolResult {
+            tool_use_id: call.id.clone(),
+            content,
+            is_error,
+        })
+    };
+
+    let Some(entry) = mcp_catalog::find(name) else {
+        return result(
+            format!(
+                "'{}' isn't in the catalog. Use find_mcp_server to see what is.",
+                name
+            ),
+            true,
+        );
+    };
+    if mcp_manager.is_connected(entry.name).await {
+        return result(
+            format!("MCP server '{}' is already connected.", entry.name),
+            false,
+        );
+    }
+    let configured = mcp_manager.get_server(entry.name).await.is_some();
+
+    if !confirm(entry, reason, handler).await {
+        return result(
+            format!(
+                "The user declined adding the '{}' MCP server. Don't offer it again this turn.",
+                entry.name
+            ),
+            false,
+        );
+    }
+
+    // Same steps as /mcp add followed by /mcp connect
+    if !configured {
+        if let Err(e) = mcp_manager
+            .add_server(entry.name, entry.server_config())
+            .await
+        {
+            return result(
+                format!("Failed to add MCP server '{}': {}", entry.name, e),
+                true,
+            );
+        }
+    }
+    let missing_env = entry.missing_env();
+    if let Err(e) = mcp_manager.connect_server(entry.name).await {
+        let mut content = format!(
+            "Added MCP server '{}' but couldn't connect: {}",
+            entry.name, e
+        );
+        if !missing_env.is_empty() {
+            content.push_str(&format!(
+                "\nIt needs these environment variables set: {}. Ask the user to set them and \
+                 run /mcp connect {}.",
+                missing_env.join(", "),
+                entry.name
+            ));
+        }
+        return result(content, true);
+    }
+
+    result(
+        format!(
+            "Added and connected MCP server '{}'. Its tools are available from your next request.",
+            entry.name
+        ),
+        false,
+    )
+}
+
+/// Ask the user whether to set up `entry`
+async fn confirm(entry: &CatalogEntry, reason: &str, handler: Option<PermissionHandler>) -> bool {
+    let mut detail = format!("{}\n{}", entry.description, entry.location());
+    if !reason.is_empty() {
+        detail = format!("{}\n\n{}", reason, detail);
+    }
+    let options = vec![
+        tr("permission-mcp-add-allow"),
+        tr("permission-mcp-add-deny"),
+    ];
+    let summary = tr_args("permission-mcp-add-summary", &[("server", entry.name)]);
+    let selection = match handler {
+        Some(handler) => {
+            (handler)(PermissionPrompt {
+                kind: PermissionKind::Question,
+                summary,
+                detail,
+                options,
+            })
+            .await
+        }
+        None => {
+            app_println!();
+            app_println!("{}", summary.yellow().bold());
+            app_println!("{}", detail);
+            app_println!();
+            tokio::task::spawn_blocking(move || {
+                Select::new()
+                    .with_prompt(tr("permission-select-option"))
+                    .items(&options)
+                    .default(0)
+                    .interact()
+                    .ok()
+            })
+            .await
+            .ok()
+            .flatten()
+        }
+    };
+    selection == Some(0)
+}
+
+/// Placeholders for the builtin tool list; the Agent runs the real ones with
+/// its MCP manager
+pub fn create_mcp_setup_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "find_mcp_server".to_string(),
+            description: FIND_DESCRIPTION.to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The service or what the user wants to do with it, e.g. \"check my Linear issues\""
+                    }
+                },
+                "required": ["query"]
+            }),
+            handler: placeholder_handler(),
+            metadata: None,
+        },
+        Tool {
+            name: "add_mcp_server".to_string(),
+            description: ADD_DESCRIPTION.to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "The catalog name from find_mcp_server"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "One sentence telling the user what the server is needed for"
+                    }
+                },
+                "required": ["name"]
+            }),
+            handler: placeholder_handler(),
+            metadata: None,
+        },
+    ]
+}
+
+fn placeholder_handler() -> crate::tools::types::AsyncToolHandler {
+    Box::new(|call: ToolCall| {
+        Box::pin(async move {
+            Ok(ToolResult {
+                tool_use_id: call.id,
+                content: "MCP server setup is handled internally by the Agent".to_string(),
+                is_error: false,
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    #[tokio::test]
+    async fn declining_leaves_the_configuration_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = McpManager::new_with_config_path(dir.path().join("mcp.json"));
+        let seen = Arc::new(Mutex::new(None));
+        let seen_by_handler = seen.clone();
+        let handler: PermissionHandler = Arc::new(move |prompt: PermissionPrompt| {
+            *seen_by_handler.lock().unwrap() = Some(prompt);
+            Box::pin(async { Some(1) })
+        });
+
+        let result = add_mcp_server(
+            &call(
+                "add_mcp_server",
+                json!({"name": "linear", "reason": "To read your Linear issues"}),
+            ),
+            &manager,
+            Some(handler),
+        )
+        .await
+        .unwrap();
+        assert!(result.content.contains("declined"));
+        assert!(manager.get_server("linear").await.is_none());
+        let prompt = seen.lock().unwrap().take().unwrap();
+        assert!(prompt.detail.starts_with("To read your Linear issues"));
+        assert!(prompt.detail.contains("https://mcp.linear.app/mcp"));
+        assert_eq!(prompt.options.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_servers_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = McpManager::new_with_config_path(dir.path().join("mcp.json"));
+        let result = add_mcp_server(
+            &call("add_mcp_server", json!({"name": "nope"})),
+            &manager,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(result.is_error);
+    }
+
+    #[tokio::test]
+    async fn find_lists_matches_and_their_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = McpManager::new_with_config_path(dir.path().join("mcp.json"));
+        manager
+            .add_server("slack", mcp_catalog::find("slack").unwrap().server_config())
+            .await
+            .unwrap();
+
+        let result = find_mcp_server(
+            &call(
+                "find_mcp_server",
+                json!({"query": "summarize the slack channel"}),
+            ),
+            &manager,
+        )
+        .await
+        .unwrap();
+        let first = result.content.lines().next().unwrap();
+        assert!(first.starts_with("- slack [configured, not connected]: "));
+        assert!(result
+            .content
+            .contains("needs: SLACK_BOT_TOKEN, SLACK_TEAM_ID"));
+    }
+}
@@ -3,10 +3,12 @@
 #[macro_use]
 pub mod arg_macros;
 
+pub mod ask_user;
 pub mod bash;
 pub mod builtin;
 pub mod complete_todo;
 pub mod create_directory;
+pub mod create_pr;
 pub mod create_todo;
 pub mod delete_file;
 pub mod edit_file;
@@ -14,9 +16,11 @@ pub mod glob;
 pub mod list_directory;
 pub mod list_todos;
 pub mod mcp;
+pub mod mcp_setup;
 pub mod multi_read_files;
 pub mod path;
 pub mod read_file;
+pub mod replace_in_files;
 pub mod search_in_files;
 pub mod security_utils;
 pub mod types;
@@ -38,8 +42,10 @@ pub use types::*;
 pub use registry::*;
 
 // Re-export tool creation functions for security manager integration
-pub use bash::{bash, create_bash_tool};
+pub use bash::{bash, bash_with_env, create_bash_tool};
 pub use create_directory::{create_create_directory_tool, create_directory};
+pub use create_pr::{create_create_pr_tool, create_pr};
 pub use delete_file::{create_delete_file_tool, delete_file};
 pub use edit_file::{create_edit_file_tool, edit_file};
+pub use replace_in_files::{create_replace_in_files_tool, replace_in_files};
 pub use write_file::{create_write_file_tool, write_file};
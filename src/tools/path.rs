@@ -1,6 +1,27 @@
 use anyhow::{bail, Result};
 use path_absolutize::*;
 use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+static ADDITIONAL_ROOTS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// Let file tools work in these directories as well as the current one, as
+/// set by `--add-dir`. The paths must be absolute.
+pub fn set_additional_roots(roots: Vec<PathBuf>) {
+    let _ = ADDITIONAL_ROOTS.set(roots);
+}
+
+/// Directories added with `--add-dir`
+pub fn additional_roots() -> &'static [PathBuf] {
+    ADDITIONAL_ROOTS.get().map(Vec::as_slice).unwrap_or(&[])
+}
+
+/// The current directory followed by any added directories
+pub fn workspace_roots() -> Result<Vec<PathBuf>> {
+    let mut roots = vec![std::env::current_dir()?.absolutize()?.to_path_buf()];
+    roots.extend(additional_roots().iter().cloned());
+    Ok(roots)
+}
 
 /// Expand tilde (~) and convert to absolute path without security restrictions.
 /// This is used for read-only operations where path traversal is allowed.
@@ -16,6 +37,12 @@ pub fn expand_and_absolutize(path: &str) -> Result<PathBuf> {
 }
 
 pub fn resolve_project_path(path: &str) -> Result<PathBuf> {
+    resolve_within_roots(path, &workspace_roots()?)
+}
+
+/// Resolve `path` against the first root, requiring the result to be inside
+/// one of the roots
+fn resolve_within_roots(path: &str, roots: &[PathBuf]) -> Result<PathBuf> {
     let expanded_path = shellexpand::tilde(path);
     let raw_path = Path::new(expanded_path.as_ref());
 
@@ -26,18 +53,26 @@ pub fn resolve_project_path(path: &str) -> Result<PathBuf> {
         bail!("Path traversal ('..') is not allowed: {}", path);
     }
 
-    let current_dir = std::env::current_dir()?;
-    let project_root = current_dir.absolutize()?.to_path_buf();
+    let Some(project_root) = roots.first() else {
+        bail!("No project directory");
+    };
     let absolute_path = if raw_path.is_absolute() {
         raw_path.absolutize()?.to_path_buf()
     } else {
         project_root.join(raw_path).absolutize()?.to_path_buf()
     };
 
-    if !absolute_path.starts_with(&project_root) {
+    if !roots.iter().any(|root| absolute_path.starts_with(root)) {
+        if roots.len() == 1 {
+            bail!(
+                "Path must be within the project directory: {}",
+                project_root.display()
+            );
+        }
+        let roots: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
         bail!(
-            "Path must be within the project directory: {}",
-            project_root.display()
+            "Path must be within the project directory or an added directory: {}",
+            roots.join(", ")
         );
     }
 
@@ -76,4 +111,23 @@ mod tests {
         let result = resolve_project_path(&absolute.to_string_lossy());
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn resolve_within_roots_accepts_added_directories() {
+        let project = PathBuf::from("/work/app");
+        let shared = PathBuf::from("/work/shared-lib");
+        let roots = vec![project.clone(), shared.clone()];
+
+        assert_eq!(
+            resolve_within_roots("src/main.rs", &roots).unwrap(),
+            project.join("src/main.rs")
+        );
+        assert_eq!(
+            resolve_within_roots("/work/shared-lib/lib.rs", &roots).unwrap(),
+            shared.join("lib.rs")
+        );
+        let error = resolve_within_roots("/work/other/lib.rs", &roots).unwrap_err();
+        assert!(error.to_string().contains("/work/shared-lib"));
+        assert!(resolve_within_roots("/work/shared-lib/../other", &roots).is_err());
+    }
 }
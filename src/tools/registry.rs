@@ -116,6 +116,13 @@ impl ToolRegistry {
             readonly: false,
         });
 
+        registry.register_tool(ToolMetadata {
+            name: "replace_in_files".to_string(),
+            icon: "🔄",
+            display_format: DisplayFormat::Generic,
+            readonly: false,
+        });
+
         registry.register_tool(ToolMetadata {
             name: "delete_file".to_string(),
             icon: "🗑️",
@@ -137,6 +144,13 @@ impl ToolRegistry {
             readonly: false,
         });
 
+        registry.register_tool(ToolMetadata {
+            name: "create_pr".to_string(),
+            icon: "🔀",
+            display_format: DisplayFormat::Generic,
+            readonly: false,
+        });
+
         registry.register_tool(ToolMetadata {
             name: "search_in_files".to_string(),
             icon: "🔍",
@@ -151,6 +165,27 @@ impl ToolRegistry {
             readonly: true,
         });
 
+        registry.register_tool(ToolMetadata {
+            name: "ask_user".to_string(),
+            icon: "❓",
+            display_format: DisplayFormat::Generic,
+            readonly: true,
+        });
+
+        registry.register_tool(ToolMetadata {
+            name: "find_mcp_server".to_string(),
+            icon: "🧩",
+            display_format: DisplayFormat::Generic,
+            readonly: true,
+        });
+
+        registry.register_tool(ToolMetadata {
+            name: "add_mcp_server".to_string(),
+            icon: "🧩",
+            display_format: DisplayFormat::Generic,
+            readonly: false,
+        });
+
         registry.register_tool(ToolMetadata {
             name: "use_skill".to_string(),
             icon: "🎯",
@@ -1,3 +1,4 @@
+use crate::script_preview::ScriptPreview;
 use crate::security::{FilePermissionResult, FileSecurityManager};
 use crate::tools::types::ToolResult;
 use log::{debug, info};
@@ -12,6 +13,7 @@ use std::path::Path;
 /// * `tool_use_id` - The tool use ID for error reporting
 /// * `file_security_manager` - The security manager to check permissions
 /// * `yolo_mode` - If true, bypasses all security checks
+/// * `preview` - Commands the operation adds to a script or CI workflow, shown when asking
 ///
 /// # Returns
 /// * `Ok(None)` - Permission granted, operation should proceed
@@ -23,39 +25,71 @@ pub async fn check_file_security(
     tool_use_id: String,
     file_security_manager: &mut FileSecurityManager,
     yolo_mode: bool,
+    preview: Option<&ScriptPreview>,
 ) -> anyhow::Result<Option<ToolResult>> {
+    let shown = path.display().to_string();
+    let previews: Vec<(&str, &ScriptPreview)> = preview
+        .map(|preview| (shown.as_str(), preview))
+        .into_iter()
+        .collect();
+    check_files_security(
+        operation_name,
+        std::slice::from_ref(&shown),
+        tool_use_id,
+        file_security_manager,
+        yolo_mode,
+        &previews,
+    )
+    .await
+}
+
+/// The same for an operation that changes several files at once, asking
+/// once for all of them. Each entry of `files` is one line of the listing
+/// shown when asking, such as a path and how much changes there, and
+/// `previews` pairs each script or workflow changed with its file's name.
+pub async fn check_files_security(
+    operation_name: &str,
+    files: &[String],
+    tool_use_id: String,
+    file_security_manager: &mut FileSecurityManager,
+    yolo_mode: bool,
+    previews: &[(&str, &ScriptPreview)],
+) -> anyhow::Result<Option<ToolResult>> {
+    let target = match files {
+        [file] => format!("'{}'", file),
+        _ => format!("{} files", files.len()),
+    };
+
     // Check file security permissions
     if yolo_mode {
         debug!(
-            "YOLO MODE: Bypassing file security for '{}' on '{}'",
-            operation_name,
-            path.display()
+            "YOLO MODE: Bypassing file security for '{}' on {}",
+            operation_name, target
         );
         return Ok(None);
     }
 
-    match file_security_manager.check_file_permission(operation_name, &path.to_string_lossy()) {
+    match file_security_manager.check_file_permission(operation_name, &files.join(", ")) {
         FilePermissionResult::Allowed => {
             debug!(
-                "File operation '{}' on '{}' is allowed by security policy",
-                operation_name,
-                path.display()
+                "File operation '{}' on {} is allowed by security policy",
+                operation_name, target
             );
             Ok(None)
         }
         FilePermissionResult::Denied => Ok(Some(ToolResult {
             tool_use_id,
             content: format!(
-                "🔒 Security: File {} operation on '{}' is not allowed by security policy.",
+                "🔒 Security: File {} operation on {} is not allowed by security policy.",
                 get_operation_display_name(operation_name),
-                path.display()
+                target
             ),
             is_error: true,
         })),
         FilePermissionResult::RequiresPermission => {
             // Ask user for permission
             match file_security_manager
-                .ask_file_permission(operation_name, &path.to_string_lossy())
+                .ask_files_permission(operation_name, files, previews)
                 .await
             {
                 Ok(Some(_)) => {
@@ -63,25 +97,25 @@ pub async fn check_file_security(
                     info!(
                         "User granted permission for file {} operation: {}",
                         get_operation_display_name(operation_name),
-                        path.display()
+                        files.join(", ")
                     );
                     Ok(None)
                 }
                 Ok(None) => Ok(Some(ToolResult {
                     tool_use_id,
                     content: format!(
-                        "🔒 Security: Permission denied for file {} operation on '{}'",
+                        "🔒 Security: Permission denied for file {} operation on {}",
                         get_operation_display_name(operation_name),
-                        path.display()
+                        target
                     ),
                     is_error: true,
                 })),
                 Err(e) => Ok(Some(ToolResult {
                     tool_use_id,
                     content: format!(
-                        "🔒 Security: Error checking permission for file {} operation on '{}': {}",
+                        "🔒 Security: Error checking permission for file {} operation on {}: {}",
                         get_operation_display_name(operation_name),
-                        path.display(),
+                        target,
                         e
                     ),
                     is_error: true,
@@ -97,6 +131,7 @@ fn get_operation_display_name(operation_name: &str) -> &str {
         "Write" => "write",
         "delete_file" => "delete",
         "Edit" => "edit",
+        "replace_in_files" => "replace",
         "create_directory" => "create",
         _ => operation_name,
     }
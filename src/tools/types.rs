@@ -42,9 +42,11 @@ impl Tool {
             "glob" => Box::new(crate::tools::glob::glob_files_sync),
             "Write" => Box::new(crate::tools::write_file::write_file_sync),
             "Edit" => Box::new(crate::tools::edit_file::edit_file_sync),
+            "replace_in_files" => Box::new(crate::tools::replace_in_files::replace_in_files_sync),
             "delete_file" => Box::new(crate::tools::delete_file::delete_file_sync),
             "create_directory" => Box::new(crate::tools::create_directory::create_directory_sync),
-            "create_todo" | "complete_todo" | "list_todos" => {
+            "create_pr" => Box::new(crate::tools::create_pr::create_pr_sync),
+            "create_todo" | "complete_todo" | "list_todos" | "ask_user" => {
                 // Todo tools are handled internally by the Agent
                 Box::new(|_call| {
                     Box::pin(async move {
@@ -1,4 +1,5 @@
-use crate::security::FileSecurityManager;
+use crate::script_preview::ScriptPreview;
+use crate::security::{FileSecurityManager, FileWrite};
 use crate::tools::path::resolve_project_path;
 use crate::tools::security_utils::check_file_security;
 use crate::tools::types::{Tool, ToolCall, ToolResult};
@@ -30,6 +31,9 @@ pub async fn write_file(
         }
     };
 
+    let old_content = fs::read_to_string(&absolute_path).await.ok();
+    let preview = ScriptPreview::for_change(&absolute_path, old_content.as_deref(), content);
+
     // Check file security permissions
     if let Some(result) = check_file_security(
         "Write",
@@ -37,12 +41,33 @@ pub async fn write_file(
         tool_use_id.clone(),
         file_security_manager,
         yolo_mode,
+        preview.as_ref(),
     )
     .await?
     {
         return Ok(result);
     }
 
+    if let Some(handler) = file_security_manager.write_handler() {
+        let write = FileWrite {
+            path: absolute_path.clone(),
+            old_content,
+            new_content: content.to_string(),
+        };
+        return Ok(match handler(write).await {
+            Ok(()) => ToolResult {
+                tool_use_id,
+                content: format!("Successfully wrote to file: {}", absolute_path.display()),
+                is_error: false,
+            },
+            Err(e) => ToolResult {
+                tool_use_id,
+                content: format!("Error writing to file '{}': {}", absolute_path.display(), e),
+                is_error: true,
+            },
+        });
+    }
+
     // Create parent directory if it doesn't exist
     if let Some(parent) = absolute_path.parent() {
         if let Err(e) = fs::create_dir_all(parent).await {
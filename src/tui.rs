@@ -17,7 +17,7 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph},
     Terminal,
 };
 use std::collections::VecDeque;
@@ -29,6 +29,8 @@ const MIN_OUTPUT_HEIGHT: usize = 3;
 const INPUT_PREFIX_MAIN: &str = "> ";
 const INPUT_PREFIX_CONT: &str = "... ";
 const RENDER_INTERVAL: Duration = Duration::from_millis(50);
+/// Narrowest terminal the diff pane is shown beside the output in
+const MIN_SPLIT_WIDTH: u16 = 80;
 
 pub struct Tui {
     state: Arc<Mutex<TuiState>>,
@@ -46,12 +48,24 @@ struct TuiState {
     output_dirty: bool,
     last_render: Instant,
     output_scroll: usize,
+    /// Terminal width the scroll position was measured at
+    output_width: usize,
     // Selection tracking
     selection_start: Option<TextPosition>,
     selection_end: Option<TextPosition>,
     selection_active: bool,
     // Todo tracking
     todos: Vec<crate::tools::create_todo::TodoItem>,
+    /// Shown under the input, e.g. background startup progress
+    status: Option<String>,
+    /// The session's token usage, shown at the right under the input
+    usage: Option<String>,
+    /// Images pasted into the input, attached when it's submitted
+    pending_images: Vec<crate::clipboard::PastedImage>,
+    /// The session's diff, one entry per line, while the diff pane is open
+    diff: Option<Vec<String>>,
+    /// Lines of the diff scrolled past
+    diff_scroll: usize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -97,10 +111,15 @@ pub struct TuiSnapshot {
     output_scroll: usize,
     selection_range: Option<(TextPosition, TextPosition)>,
     todos: Vec<crate::tools::create_todo::TodoItem>,
+    status: Option<String>,
+    usage: Option<String>,
+    diff: Option<Vec<String>>,
+    diff_scroll: usize,
 }
 
 pub enum InputResult {
-    Submitted(String),
+    /// The text, and the images pasted into it
+    Submitted(String, Vec<crate::clipboard::PastedImage>),
     Cancelled,
     Exit,
 }
@@ -112,6 +131,15 @@ pub struct SelectionPrompt {
     pub options: Vec<String>,
 }
 
+/// What the model picker overlay shows
+struct ModelPickerView {
+    query: String,
+    /// Label, hint and whether it's the model in use, for each match
+    rows: Vec<(String, String, bool)>,
+    selected: usize,
+    total: usize,
+}
+
 struct TuiOutputSink {
     state: Arc<Mutex<TuiState>>,
     screen: Arc<Mutex<TuiScreen>>,
@@ -177,10 +205,16 @@ impl Tui {
             output_dirty: true,
             last_render: Instant::now(),
             output_scroll: 0,
+            output_width: terminal.size()?.width as usize,
             selection_start: None,
             selection_end: None,
             selection_active: false,
             todos: Vec::new(),
+            status: None,
+            usage: None,
+            pending_images: Vec::new(),
+            diff: None,
+            diff_scroll: 0,
         }));
 
         let screen = Arc::new(Mutex::new(TuiScreen { terminal }));
@@ -253,8 +287,16 @@ impl Tui {
                         // Ignore mouse event errors - they're non-critical
                         let _ = self.handle_mouse_event(mouse_event);
                     }
-                    Event::Resize(_, _) => {
+                    Event::Resize(width, _) => {
                         let mut guard = self.state.lock().expect("tui state lock");
+                        let width = width as usize;
+                        guard.output_scroll = rescale_scroll(
+                            &guard.output.lines,
+                            guard.output_width,
+                            width,
+                            guard.output_scroll,
+                        );
+                        guard.output_width = width;
                         guard.output_dirty = true;
                         drop(guard);
                         let _ = self.render();
@@ -277,6 +319,45 @@ impl Tui {
         Ok(())
     }
 
+    /// Fill the up-arrow history with prompts from earlier sessions, oldest
+    /// first
+    pub fn load_history(&self, prompts: &[String]) {
+        let mut guard = self.state.lock().expect("tui state lock");
+        for prompt in prompts {
+            guard.history.add_entry(prompt.clone());
+        }
+    }
+
+    /// Insert text into the input at the cursor, as if it had been typed
+    pub fn insert_input(&self, text: &str) -> Result<()> {
+        self.handle_paste(text)
+    }
+
+    /// Show the session's token usage under the input, or clear it with None
+    pub fn set_usage(&self, usage: Option<&str>) -> Result<()> {
+        {
+            let mut guard = self.state.lock().expect("tui state lock");
+            if guard.usage.as_deref() == usage {
+                return Ok(());
+            }
+            guard.usage = usage.map(str::to_string);
+            guard.output_dirty = true;
+        }
+        self.render()?;
+        Ok(())
+    }
+
+    /// Show a line of status under the input, or clear it with None
+    pub fn set_status(&self, status: Option<&str>) -> Result<()> {
+        {
+            let mut guard = self.state.lock().expect("tui state lock");
+            guard.status = status.map(str::to_string);
+            guard.output_dirty = true;
+        }
+        self.render()?;
+        Ok(())
+    }
+
     pub fn set_todos(&self, todos: &[crate::tools::create_todo::TodoItem]) -> Result<()> {
         {
             let mut guard = self.state.lock().expect("tui state lock");
@@ -287,6 +368,41 @@ impl Tui {
         Ok(())
     }
 
+    /// Open or close the diff pane beside the output, returning whether
+    /// it's now open
+    pub fn toggle_diff_pane(&self) -> Result<bool> {
+        let open = {
+            let mut guard = self.state.lock().expect("tui state lock");
+            guard.diff = match guard.diff {
+                Some(_) => None,
+                None => Some(Vec::new()),
+            };
+            guard.diff_scroll = 0;
+            guard.output_dirty = true;
+            guard.diff.is_some()
+        };
+        self.render()?;
+        Ok(open)
+    }
+
+    pub fn diff_pane_open(&self) -> bool {
+        self.state.lock().expect("tui state lock").diff.is_some()
+    }
+
+    /// Show `diff` in the diff pane, if it's open
+    pub fn set_diff(&self, diff: &str) -> Result<()> {
+        {
+            let mut guard = self.state.lock().expect("tui state lock");
+            let Some(lines) = guard.diff.as_mut() else {
+                return Ok(());
+            };
+            *lines = diff.lines().map(str::to_string).collect();
+            guard.output_dirty = true;
+        }
+        self.render()?;
+        Ok(())
+    }
+
     pub fn prompt_permission(&self, prompt: &PermissionPrompt) -> Option<usize> {
         let mut selected = 0usize;
         let mut buffer = String::new();
@@ -352,6 +468,66 @@ impl Tui {
         }
     }
 
+    /// Ask a question and read a one-line answer in the input box. Whatever
+    /// the user was typing is put back afterwards. Returns None if cancelled.
+    pub fn prompt_text(&self, question: &str) -> Option<String> {
+        app_println!("? {}", question);
+        let (saved_input, saved_cursor) = {
+            let mut guard = self.state.lock().expect("tui state lock");
+            let saved = (std::mem::take(&mut guard.input), guard.cursor_pos);
+            guard.cursor_pos = 0;
+            guard.output_dirty = true;
+            saved
+        };
+        let _ = self.render();
+
+        let answer = loop {
+            let event = match event::read() {
+                Ok(event) => event,
+                Err(_) => break None,
+            };
+            let mut guard = self.state.lock().expect("tui state lock");
+            match event {
+                Event::Key(key_event)
+                    if matches!(key_event.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
+                {
+                    match key_event.code {
+                        KeyCode::Esc => break None,
+                        KeyCode::Char('c')
+                            if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+                        {
+                            break None
+                        }
+                        KeyCode::Enter => break Some(guard.input.clone()),
+                        KeyCode::Backspace => {
+                            guard.input.pop();
+                        }
+                        KeyCode::Char(c) => guard.input.push(c),
+                        _ => {}
+                    }
+                }
+                Event::Paste(text) => {
+                    let line = text.lines().next().unwrap_or("").to_string();
+                    guard.input.push_str(&line);
+                }
+                _ => {}
+            }
+            guard.cursor_pos = guard.input.len();
+            guard.output_dirty = true;
+            drop(guard);
+            let _ = self.render();
+        };
+
+        {
+            let mut guard = self.state.lock().expect("tui state lock");
+            guard.input = saved_input;
+            guard.cursor_pos = saved_cursor;
+            guard.output_dirty = true;
+        }
+        let _ = self.render();
+        answer
+    }
+
     /// Select from a list of options with arrow key navigation.
     /// Returns the selected index or None if cancelled.
     pub fn select_option(&self, prompt: &SelectionPrompt) -> Option<usize> {
@@ -491,6 +667,85 @@ impl Tui {
         result
     }
 
+    /// Show the model picker over the screen and return the index of the
+    /// chosen model in `choices`, or None if cancelled. Typing filters the
+    /// list by fuzzy match.
+    pub fn pick_model(&self, choices: &[crate::model_picker::ModelChoice]) -> Option<usize> {
+        let mut query = String::new();
+        let mut selected = choices
+            .iter()
+            .position(|choice| choice.current)
+            .unwrap_or(0);
+        let page = self
+            .terminal_height()
+            .map_or(10, |height| (height as usize / 2).max(1));
+
+        let result = loop {
+            let matches = crate::model_picker::filter(choices, &query);
+            selected = selected.min(matches.len().saturating_sub(1));
+            let view = ModelPickerView {
+                query: query.clone(),
+                rows: matches
+                    .iter()
+                    .map(|&index| {
+                        let choice = &choices[index];
+                        (choice.label(), choice.hint(), choice.current)
+                    })
+                    .collect(),
+                selected,
+                total: choices.len(),
+            };
+            let snapshot = {
+                let guard = self.state.lock().expect("tui state lock");
+                let formatter = self.formatter.lock().expect("tui formatter lock");
+                guard.snapshot(&formatter)
+            };
+            if let Ok(mut screen) = self.screen.lock() {
+                let _ = screen.render_model_picker(&snapshot, &view);
+            }
+
+            let Ok(event) = event::read() else {
+                break None;
+            };
+            match event {
+                Event::Key(key_event)
+                    if matches!(key_event.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
+                {
+                    let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+                    match key_event.code {
+                        KeyCode::Esc => break None,
+                        KeyCode::Char('c') if control => break None,
+                        KeyCode::Enter => break matches.get(selected).copied(),
+                        KeyCode::Up => selected = selected.saturating_sub(1),
+                        KeyCode::Char('p') if control => selected = selected.saturating_sub(1),
+                        KeyCode::Down => selected += 1,
+                        KeyCode::Char('n') if control => selected += 1,
+                        KeyCode::PageUp => selected = selected.saturating_sub(page),
+                        KeyCode::PageDown => selected += page,
+                        KeyCode::Backspace => {
+                            query.pop();
+                            selected = 0;
+                        }
+                        KeyCode::Char(c) if !control => {
+                            query.push(c);
+                            selected = 0;
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Paste(text) => {
+                    query.push_str(text.lines().next().unwrap_or(""));
+                    selected = 0;
+                }
+                _ => {}
+            }
+        };
+
+        self.state.lock().expect("tui state lock").output_dirty = true;
+        let _ = self.render();
+        result
+    }
+
     /// Get the current terminal height
     pub fn terminal_height(&self) -> Option<u16> {
         let screen = self.screen.lock().ok()?;
@@ -516,6 +771,22 @@ impl Tui {
         let mut guard = self.state.lock().expect("tui state lock");
         let mut changed = false;
 
+        if guard.diff.is_some() && self.in_diff_pane(event.column) {
+            match event.kind {
+                MouseEventKind::ScrollUp => {
+                    guard.diff_scroll = guard.diff_scroll.saturating_sub(3);
+                }
+                MouseEventKind::ScrollDown => {
+                    let last = guard.diff.as_ref().map_or(0, |lines| lines.len());
+                    guard.diff_scroll = (guard.diff_scroll + 3).min(last.saturating_sub(1));
+                }
+                _ => return Ok(()),
+            }
+            guard.output_dirty = true;
+            drop(guard);
+            return self.render();
+        }
+
         match event.kind {
             MouseEventKind::ScrollUp => {
                 guard.output_scroll = guard.output_scroll.saturating_add(3);
@@ -586,15 +857,8 @@ impl Tui {
             if guard.selection_start.is_some() {
                 // Copy selected text to clipboard
                 if let Some(text) = self.get_selected_text(&guard) {
-                    // Try to copy to clipboard
-                    match arboard::Clipboard::new() {
-                        Ok(mut clipboard) => {
-                            let _ = clipboard.set_text(text);
-                        }
-                        Err(_) => {
-                            // Clipboard unavailable, silently ignore
-                        }
-                    }
+                    // Clipboard unavailable, silently ignore
+                    let _ = crate::clipboard::copy(&text);
                 }
                 // Clear selection after copying
                 guard.selection_start = None;
@@ -634,6 +898,57 @@ impl Tui {
                 // This case is now handled above, but keep it for safety
                 return Ok(Some(InputResult::Exit));
             }
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                // Same as `/copy`; its messages go through the output sink,
+                // which needs the state lock
+                drop(guard);
+                crate::commands::handle_copy_command(&[])?;
+                return Ok(None);
+            }
+            KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                // Same as `/model pick`, run like a typed command so the
+                // agent isn't switched in the middle of a turn
+                return Ok(Some(InputResult::Submitted(
+                    "/model pick".to_string(),
+                    Vec::new(),
+                )));
+            }
+            KeyEvent {
+                code: KeyCode::Char('v'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                // Terminals paste text themselves, so this arrives when the
+                // clipboard holds an image, or from terminals that leave
+                // pasting to the application
+                drop(guard);
+                match crate::clipboard::paste_image() {
+                    Ok(Some(image)) => {
+                        let placeholder = image.placeholder();
+                        self.state
+                            .lock()
+                            .expect("tui state lock")
+                            .pending_images
+                            .push(image);
+                        self.handle_paste(&placeholder)?;
+                    }
+                    Ok(None) => {
+                        if let Some(text) = crate::clipboard::paste_text() {
+                            self.handle_paste(&text)?;
+                        }
+                    }
+                    Err(e) => log::debug!("Clipboard unavailable: {}", e),
+                }
+                return Ok(None);
+            }
             KeyEvent {
                 code: KeyCode::Char('r'),
                 modifiers: KeyModifiers::CONTROL,
@@ -667,11 +982,15 @@ impl Tui {
                     if !submitted.trim().is_empty() {
                         guard.history.add_entry(submitted.clone());
                     }
+                    let images = crate::clipboard::attached_images(
+                        std::mem::take(&mut guard.pending_images),
+                        &submitted,
+                    );
                     guard.input.clear();
                     guard.cursor_pos = 0;
                     drop(guard);
                     self.render()?;
-                    return Ok(Some(InputResult::Submitted(submitted)));
+                    return Ok(Some(InputResult::Submitted(submitted, images)));
                 }
             }
             KeyEvent {
@@ -752,6 +1071,16 @@ impl Tui {
         Ok(None)
     }
 
+    /// Whether screen column `column` is in the open diff pane
+    fn in_diff_pane(&self, column: u16) -> bool {
+        let screen = self.screen.lock().expect("tui screen lock");
+        let Ok(size) = screen.terminal.size() else {
+            return false;
+        };
+        let (_, diff_rect) = split_diff_pane(Rect::new(0, 0, size.width, size.height), true);
+        diff_rect.is_some_and(|rect| column >= rect.x)
+    }
+
     /// Convert screen coordinates to text buffer position
     fn screen_to_text_position(
         &self,
@@ -768,6 +1097,10 @@ impl Tui {
         let formatter = self.formatter.lock().expect("tui formatter lock");
         let snapshot = guard.snapshot(&formatter);
         drop(formatter);
+        let (size, _) = split_diff_pane(
+            Rect::new(0, 0, size.width, size.height),
+            snapshot.diff.is_some(),
+        );
 
         let max_input_height = size.height.saturating_sub(3).max(2);
         let input_layout = build_input_layout(&snapshot, size.width as usize);
@@ -805,7 +1138,7 @@ impl Tui {
         // Map back to original line
         let mut wrapped_count = 0;
         for (line_idx, orig_line) in snapshot.output_lines.iter().enumerate() {
-            let wrapped = wrap_ansi_line(orig_line, width);
+            let wrapped = wrap_output_line(orig_line, width);
 
             if wrapped_count + wrapped.len() > wrapped_line_idx {
                 let wrap_offset = wrapped_line_idx - wrapped_count;
@@ -815,7 +1148,11 @@ impl Tui {
                 let char_in_wrapped = screen_col_to_char_offset(wrapped_line, screen_col as usize);
 
                 // Calculate offset in original line
-                let char_offset = (wrap_offset * width) + char_in_wrapped;
+                let char_offset = wrapped[..wrap_offset]
+                    .iter()
+                    .map(|segment| strip_ansi_codes(segment).chars().count())
+                    .sum::<usize>()
+                    + char_in_wrapped;
                 let line_char_count = strip_ansi_codes(orig_line).chars().count();
                 let clamped_offset = char_offset.min(line_char_count);
 
@@ -936,6 +1273,10 @@ impl TuiState {
             output_scroll: self.output_scroll,
             selection_range,
             todos: self.todos.clone(),
+            status: self.status.clone(),
+            usage: self.usage.clone(),
+            diff: self.diff.clone(),
+            diff_scroll: self.diff_scroll,
         }
     }
 }
@@ -950,111 +1291,19 @@ impl TuiScreen {
     }
 
     fn render_frame(&mut self, snapshot: &TuiSnapshot) -> Result<()> {
-        self.terminal.draw(|frame| {
-            let size = frame.area();
-            let max_input_height = size.height.saturating_sub(MIN_OUTPUT_HEIGHT as u16).max(2);
-            let input_layout = build_input_layout(snapshot, size.width as usize);
-            let input_lines = input_layout.lines.len().max(1);
-            let input_height = (input_lines + 2).min(max_input_height as usize) as u16;
-            let max_queue_height = size
-                .height
-                .saturating_sub(MIN_OUTPUT_HEIGHT as u16 + input_height);
-            let (queue_height, queue_lines) =
-                build_queue_layout(snapshot, size.width as usize, max_queue_height);
-
-            // Calculate todo pane height
-            let max_todo_height = size
-                .height
-                .saturating_sub(MIN_OUTPUT_HEIGHT as u16 + input_height + queue_height);
-            let (todo_height, todo_lines) =
-                build_todo_layout(snapshot, size.width as usize, max_todo_height);
-
-            let output_height = size.height.saturating_sub(
-                input_height
-                    .saturating_add(queue_height)
-                    .saturating_add(todo_height),
-            );
-
-            let chunks = if todo_height > 0 && queue_height > 0 {
-                Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([
-                        Constraint::Length(output_height),
-                        Constraint::Length(queue_height),
-                        Constraint::Length(todo_height),
-                        Constraint::Length(input_height),
-                    ])
-                    .split(size)
-            } else if todo_height > 0 {
-                Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([
-                        Constraint::Length(output_height),
-                        Constraint::Length(todo_height),
-                        Constraint::Length(input_height),
-                    ])
-                    .split(size)
-            } else if queue_height > 0 {
-                Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([
-                        Constraint::Length(output_height),
-                        Constraint::Length(queue_height),
-                        Constraint::Length(input_height),
-                    ])
-                    .split(size)
-            } else {
-                Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([
-                        Constraint::Length(output_height),
-                        Constraint::Length(input_height),
-                    ])
-                    .split(size)
-            };
-
-            let output_rect = chunks[0];
-
-            let (queue_rect, todo_rect, input_rect) = if queue_height > 0 && todo_height > 0 {
-                (Some(chunks[1]), Some(chunks[2]), chunks[3])
-            } else if todo_height > 0 {
-                (None, Some(chunks[1]), chunks[2])
-            } else if queue_height > 0 {
-                (Some(chunks[1]), None, chunks[2])
-            } else {
-                (None, None, chunks[1])
-            };
-
-            let output_text = build_output_text(snapshot, output_rect);
-            let output_para = Paragraph::new(output_text);
-            frame.render_widget(output_para, output_rect);
-
-            if let Some(queue_rect) = queue_rect {
-                let queue_text = build_queue_text(&queue_lines);
-                let title = format!("Queued ({})", snapshot.queued.len());
-                let queue_block = Block::default().borders(Borders::NONE).title(title);
-                let queue_para = Paragraph::new(queue_text).block(queue_block);
-                frame.render_widget(queue_para, queue_rect);
-            }
-
-            if let Some(todo_rect) = todo_rect {
-                let todo_text = build_todo_text(&todo_lines);
-                let title = format!("Todos ({})", snapshot.todos.len());
-                let todo_block = Block::default().borders(Borders::NONE).title(title);
-                let todo_para = Paragraph::new(todo_text).block(todo_block);
-                frame.render_widget(todo_para, todo_rect);
-            }
-
-            let (input_text, cursor_row_offset, cursor_col) =
-                build_input_text_with_layout(input_rect, &input_layout);
-            let input_block = Block::default().borders(Borders::TOP | Borders::BOTTOM);
-            let input_para = Paragraph::new(input_text).block(input_block);
-            frame.render_widget(input_para, input_rect);
+        self.terminal.draw(|frame| draw_main(frame, snapshot))?;
+        Ok(())
+    }
 
-            let cursor_row = input_rect.y + 1 + cursor_row_offset;
-            frame.set_cursor_position((input_rect.x + cursor_col, cursor_row));
+    fn render_model_picker(
+        &mut self,
+        snapshot: &TuiSnapshot,
+        picker: &ModelPickerView,
+    ) -> Result<()> {
+        self.terminal.draw(|frame| {
+            draw_main(frame, snapshot);
+            draw_model_picker(frame, picker);
         })?;
-
         Ok(())
     }
 
@@ -1066,7 +1315,10 @@ impl TuiScreen {
         buffer: &str,
     ) -> Result<()> {
         self.terminal.draw(|frame| {
-            let size = frame.area();
+            let (size, diff_rect) = split_diff_pane(frame.area(), snapshot.diff.is_some());
+            if let Some(diff_rect) = diff_rect {
+                render_diff_pane(frame, snapshot, diff_rect);
+            }
             let max_input_height = size.height.saturating_sub(MIN_OUTPUT_HEIGHT as u16).max(2);
             let input_layout = build_input_layout(snapshot, size.width as usize);
             let input_lines = input_layout.lines.len().max(1);
@@ -1162,7 +1414,7 @@ impl TuiScreen {
 
             let (input_text, cursor_row_offset, cursor_col) =
                 build_input_text_with_layout(input_rect, &input_layout);
-            let input_block = Block::default().borders(Borders::TOP | Borders::BOTTOM);
+            let input_block = input_block(snapshot);
             let input_para = Paragraph::new(input_text).block(input_block);
             frame.render_widget(input_para, input_rect);
 
@@ -1177,6 +1429,246 @@ impl TuiScreen {
         let _ = self.terminal.clear();
     }
 }
+/// The output, queue, todos and input, beside the diff pane when it's open
+fn draw_main(frame: &mut ratatui::Frame, snapshot: &TuiSnapshot) {
+    let (size, diff_rect) = split_diff_pane(frame.area(), snapshot.diff.is_some());
+    if let Some(diff_rect) = diff_rect {
+        render_diff_pane(frame, snapshot, diff_rect);
+    }
+    let max_input_height = size.height.saturating_sub(MIN_OUTPUT_HEIGHT as u16).max(2);
+    let input_layout = build_input_layout(snapshot, size.width as usize);
+    let input_lines = input_layout.lines.len().max(1);
+    let input_height = (input_lines + 2).min(max_input_height as usize) as u16;
+    let max_queue_height = size
+        .height
+        .saturating_sub(MIN_OUTPUT_HEIGHT as u16 + input_height);
+    let (queue_height, queue_lines) =
+        build_queue_layout(snapshot, size.width as usize, max_queue_height);
+
+    // Calculate todo pane height
+    let max_todo_height = size
+        .height
+        .saturating_sub(MIN_OUTPUT_HEIGHT as u16 + input_height + queue_height);
+    let (todo_height, todo_lines) =
+        build_todo_layout(snapshot, size.width as usize, max_todo_height);
+
+    let output_height = size.height.saturating_sub(
+        input_height
+            .saturating_add(queue_height)
+            .saturating_add(todo_height),
+    );
+
+    let chunks = if todo_height > 0 && queue_height > 0 {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(output_height),
+                Constraint::Length(queue_height),
+                Constraint::Length(todo_height),
+                Constraint::Length(input_height),
+            ])
+            .split(size)
+    } else if todo_height > 0 {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(output_height),
+                Constraint::Length(todo_height),
+                Constraint::Length(input_height),
+            ])
+            .split(size)
+    } else if queue_height > 0 {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(output_height),
+                Constraint::Length(queue_height),
+                Constraint::Length(input_height),
+            ])
+            .split(size)
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(output_height),
+                Constraint::Length(input_height),
+            ])
+            .split(size)
+    };
+
+    let output_rect = chunks[0];
+
+    let (queue_rect, todo_rect, input_rect) = if queue_height > 0 && todo_height > 0 {
+        (Some(chunks[1]), Some(chunks[2]), chunks[3])
+    } else if todo_height > 0 {
+        (None, Some(chunks[1]), chunks[2])
+    } else if queue_height > 0 {
+        (Some(chunks[1]), None, chunks[2])
+    } else {
+        (None, None, chunks[1])
+    };
+
+    let output_text = build_output_text(snapshot, output_rect);
+    let output_para = Paragraph::new(output_text);
+    frame.render_widget(output_para, output_rect);
+
+    if let Some(queue_rect) = queue_rect {
+        let queue_text = build_queue_text(&queue_lines);
+        let title = format!("Queued ({})", snapshot.queued.len());
+        let queue_block = Block::default().borders(Borders::NONE).title(title);
+        let queue_para = Paragraph::new(queue_text).block(queue_block);
+        frame.render_widget(queue_para, queue_rect);
+    }
+
+    if let Some(todo_rect) = todo_rect {
+        let todo_text = build_todo_text(&todo_lines);
+        let title = format!("Todos ({})", snapshot.todos.len());
+        let todo_block = Block::default().borders(Borders::NONE).title(title);
+        let todo_para = Paragraph::new(todo_text).block(todo_block);
+        frame.render_widget(todo_para, todo_rect);
+    }
+
+    let (input_text, cursor_row_offset, cursor_col) =
+        build_input_text_with_layout(input_rect, &input_layout);
+    let input_block = input_block(snapshot);
+    let input_para = Paragraph::new(input_text).block(input_block);
+    frame.render_widget(input_para, input_rect);
+
+    let cursor_row = input_rect.y + 1 + cursor_row_offset;
+    frame.set_cursor_position((input_rect.x + cursor_col, cursor_row));
+}
+
+/// The model picker, centered over the rest of the screen
+fn draw_model_picker(frame: &mut ratatui::Frame, picker: &ModelPickerView) {
+    let area = frame.area();
+    let width = area.width.saturating_sub(4).min(100);
+    let height = area
+        .height
+        .saturating_sub(4)
+        .min(picker.rows.len() as u16 + 4)
+        .max(5);
+    let rect = Rect::new(
+        area.x + (area.width.saturating_sub(width)) / 2,
+        area.y + (area.height.saturating_sub(height)) / 3,
+        width,
+        height,
+    );
+
+    let title = format!(" Switch model ({}/{}) ", picker.rows.len(), picker.total);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(" ↑/↓ move · Enter select · Esc cancel ");
+    let inner = block.inner(rect);
+
+    let mut text = Text::default();
+    text.push_line(Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan)),
+        Span::raw(picker.query.clone()),
+    ]));
+    let visible = (inner.height as usize).saturating_sub(1);
+    let first = (picker.selected + 1).saturating_sub(visible);
+    if picker.rows.is_empty() {
+        text.push_line(Line::from(Span::styled(
+            "No matching models",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (index, (label, hint, current)) in picker.rows.iter().enumerate().skip(first).take(visible)
+    {
+        let marker = if *current { "● " } else { "  " };
+        let left = format!("{}{}", marker, label);
+        let gap = (inner.width as usize)
+            .saturating_sub(left.chars().count() + hint.chars().count())
+            .max(1);
+        let mut line = Line::from(vec![
+            Span::raw(left),
+            Span::raw(" ".repeat(gap)),
+            Span::styled(hint.clone(), Style::default().fg(Color::DarkGray)),
+        ]);
+        if index == picker.selected {
+            line = line.style(Style::default().add_modifier(Modifier::REVERSED));
+        }
+        text.push_line(line);
+    }
+
+    frame.render_widget(Clear, rect);
+    frame.render_widget(Paragraph::new(text).block(block), rect);
+    frame.set_cursor_position((inner.x + 2 + picker.query.chars().count() as u16, inner.y));
+}
+
+/// Split `area` into the main area and, when `diff_open` and there's room,
+/// the diff pane at its right
+fn split_diff_pane(area: Rect, diff_open: bool) -> (Rect, Option<Rect>) {
+    if !diff_open || area.width < MIN_SPLIT_WIDTH {
+        return (area, None);
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(area);
+    (chunks[0], Some(chunks[1]))
+}
+
+fn render_diff_pane(frame: &mut ratatui::Frame, snapshot: &TuiSnapshot, rect: Rect) {
+    let lines = snapshot.diff.as_deref().unwrap_or_default();
+    let files = lines.iter().filter(|line| line.starts_with("+++ ")).count();
+    let title = match files {
+        0 => " Session diff ".to_string(),
+        1 => " Session diff (1 file) ".to_string(),
+        n => format!(" Session diff ({} files) ", n),
+    };
+    let block = Block::default().borders(Borders::LEFT).title(title);
+    let height = rect.height as usize;
+    let para = Paragraph::new(build_diff_text(lines, snapshot.diff_scroll, height)).block(block);
+    frame.render_widget(para, rect);
+}
+
+/// The visible part of the session diff, colored like `git diff`
+fn build_diff_text(lines: &[String], scroll: usize, height: usize) -> Text<'static> {
+    if lines.is_empty() {
+        return Text::from(Line::from(Span::styled(
+            "No files changed yet",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let mut text = Text::default();
+    for line in lines.iter().skip(scroll).take(height) {
+        let style = if line.starts_with("+++ ") || line.starts_with("--- ") {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else if line.starts_with("@@") {
+            Style::default().fg(Color::Cyan)
+        } else if line.starts_with('+') {
+            Style::default().fg(Color::Green)
+        } else if line.starts_with('-') {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+        text.lines
+            .push(Line::from(Span::styled(line.replace('\t', "    "), style)));
+    }
+    text
+}
+
+/// The input box, with any status and the token usage in its bottom border
+fn input_block(snapshot: &TuiSnapshot) -> Block<'static> {
+    let mut block = Block::default().borders(Borders::TOP | Borders::BOTTOM);
+    if let Some(status) = &snapshot.status {
+        block = block.title_bottom(
+            Line::from(format!(" {} ", status)).style(Style::default().fg(Color::DarkGray)),
+        );
+    }
+    if let Some(usage) = &snapshot.usage {
+        block = block.title_bottom(
+            Line::from(format!(" {} ", usage))
+                .style(Style::default().fg(Color::DarkGray))
+                .right_aligned(),
+        );
+    }
+    block
+}
+
 impl OutputSink for TuiOutputSink {
     fn write(&self, text: &str, _is_err: bool) {
         let (snapshot, _should_render) = {
@@ -1213,6 +1705,112 @@ impl OutputSink for TuiOutputSink {
             let _ = screen.render_full(&snapshot);
         }
     }
+
+    fn page(&self, text: &str) {
+        let Ok((width, height)) = terminal::size() else {
+            return;
+        };
+        let (width, height) = (width as usize, height as usize);
+        if !crate::pager::exceeds_screen(text, width, height) {
+            return;
+        }
+
+        let lines: Vec<String> = text
+            .trim_end_matches('\n')
+            .split('\n')
+            .flat_map(|line| wrap_ansi_line(line, width))
+            .collect();
+        show_pager(&lines, height);
+
+        // Redraw the TUI over the pager view
+        if let Ok(mut screen) = self.screen.lock() {
+            let _ = screen.terminal.clear();
+            let snapshot = {
+                let mut guard = self.state.lock().expect("tui state lock");
+                let formatter = self.formatter.lock().expect("tui formatter lock");
+                guard.output_dirty = false;
+                guard.last_render = Instant::now();
+                guard.snapshot(&formatter)
+            };
+            let _ = screen.render_full(&snapshot);
+        }
+    }
+}
+
+/// Where a pager view is scrolled to after a key press, or None to close it
+fn pager_scroll(key: KeyEvent, top: usize, page: usize, last_top: usize) -> Option<usize> {
+    let top = match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => return None,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return None,
+        KeyCode::Up | KeyCode::Char('k') => top.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => top + 1,
+        KeyCode::PageUp | KeyCode::Char('b') => top.saturating_sub(page),
+        KeyCode::PageDown | KeyCode::Char(' ') | KeyCode::Char('f') => top + page,
+        KeyCode::Home | KeyCode::Char('g') => 0,
+        KeyCode::End | KeyCode::Char('G') => last_top,
+        _ => top,
+    };
+    Some(top.min(last_top))
+}
+
+/// Show already-wrapped lines one screen at a time, less-style, until the
+/// user closes the view
+fn show_pager(lines: &[String], term_height: usize) {
+    use crossterm::{cursor, queue, style::Print, terminal as ct};
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    let page = term_height.saturating_sub(1).max(1);
+    let last_top = lines.len().saturating_sub(page);
+    let mut top = 0usize;
+
+    loop {
+        let _ = queue!(stdout, ct::Clear(ct::ClearType::All));
+        for (row, line) in lines.iter().skip(top).take(page).enumerate() {
+            let _ = queue!(
+                stdout,
+                cursor::MoveTo(0, row as u16),
+                Print(line),
+                Print("\x1b[0m")
+            );
+        }
+        let shown = (top + page).min(lines.len());
+        let footer = format!(
+            "\x1b[7m lines {}-{} of {} ({}%)  ↑/↓ scroll, Space/b page, g/G top/bottom, q close \x1b[0m",
+            top + 1,
+            shown,
+            lines.len(),
+            shown * 100 / lines.len().max(1)
+        );
+        let _ = queue!(stdout, cursor::MoveTo(0, page as u16), Print(footer));
+        let _ = stdout.flush();
+
+        let next = loop {
+            match event::read() {
+                Ok(Event::Key(key))
+                    if matches!(key.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
+                {
+                    break pager_scroll(key, top, page, last_top);
+                }
+                Ok(Event::Mouse(mouse)) => match mouse.kind {
+                    crossterm::event::MouseEventKind::ScrollUp => {
+                        break Some(top.saturating_sub(3))
+                    }
+                    crossterm::event::MouseEventKind::ScrollDown => {
+                        break Some((top + 3).min(last_top))
+                    }
+                    _ => {}
+                },
+                Ok(Event::Resize(_, _)) => break Some(top),
+                Ok(_) => {}
+                Err(_) => break None,
+            }
+        };
+        match next {
+            Some(next) => top = next,
+            None => break,
+        }
+    }
 }
 
 fn handle_reverse_search_key(guard: &mut TuiState, key_event: KeyEvent) -> Option<InputResult> {
@@ -1356,7 +1954,7 @@ fn build_line_mapping(lines: &[String], width: usize) -> Vec<WrappedLineInfo> {
     let mut mapping = Vec::new();
 
     for (line_idx, line) in lines.iter().enumerate() {
-        let wrapped = wrap_ansi_line(line, width);
+        let wrapped = wrap_output_line(line, width);
         let mut char_start = 0;
 
         for segment in wrapped {
@@ -1846,7 +2444,7 @@ fn build_output_lines(lines: &[String], width: usize) -> Vec<String> {
     let width = width.max(1);
 
     for line in lines {
-        let wrapped = wrap_ansi_line(line, width);
+        let wrapped = wrap_output_line(line, width);
         output_lines.extend(wrapped);
     }
 
@@ -1903,7 +2501,10 @@ fn build_permission_lines(
 
     if !buffer.is_empty() {
         lines.push(Line::from(""));
-        lines.push(Line::from(format!("Selection: {}", buffer)));
+        lines.push(Line::from(crate::i18n::tr_args(
+            "permission-selection",
+            &[("selection", buffer)],
+        )));
     }
 
     lines
@@ -1961,6 +2562,40 @@ fn drain_queued_input_after_enter() -> Result<Option<String>> {
     }
 }
 
+/// Split an output line into screen rows following the `output_width`
+/// setting. With wrapping off the line is cut at the screen edge; selecting
+/// it still copies the whole line.
+fn wrap_output_line(line: &str, width: usize) -> Vec<String> {
+    split_output_line(line, crate::output::wrap_width(width), width)
+}
+
+fn split_output_line(line: &str, wrap: Option<usize>, width: usize) -> Vec<String> {
+    match wrap {
+        Some(wrap) => wrap_ansi_line(line, wrap.max(1)),
+        None => wrap_ansi_line(line, width).into_iter().take(1).collect(),
+    }
+}
+
+/// Keep the same text at the bottom of the view when a resize changes how
+/// many rows each line wraps to. `scroll` counts rows up from the bottom.
+fn rescale_scroll(lines: &[String], old_width: usize, new_width: usize, scroll: usize) -> usize {
+    if scroll == 0 || old_width == new_width {
+        return scroll;
+    }
+    let mut remaining = scroll;
+    let mut rows_below = 0;
+    for line in lines.iter().rev() {
+        let old_rows = wrap_output_line(line, old_width).len();
+        let new_rows = wrap_output_line(line, new_width).len();
+        if remaining < old_rows {
+            return rows_below + remaining * new_rows / old_rows;
+        }
+        remaining -= old_rows;
+        rows_below += new_rows;
+    }
+    rows_below
+}
+
 fn wrap_ansi_line(line: &str, width: usize) -> Vec<String> {
     if width == 0 {
         return vec![String::new()];
@@ -2287,6 +2922,23 @@ mod tests {
         assert_eq!(screen_col_to_char_offset(line, 3), 3);
     }
 
+    // =============================================================================
+    // pager_scroll Tests
+    // =============================================================================
+
+    #[test]
+    fn test_pager_scroll_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(pager_scroll(key(KeyCode::Down), 0, 10, 40), Some(1));
+        assert_eq!(pager_scroll(key(KeyCode::Up), 0, 10, 40), Some(0));
+        assert_eq!(pager_scroll(key(KeyCode::Char(' ')), 35, 10, 40), Some(40));
+        assert_eq!(pager_scroll(key(KeyCode::Char('b')), 15, 10, 40), Some(5));
+        assert_eq!(pager_scroll(key(KeyCode::Char('G')), 0, 10, 40), Some(40));
+        assert_eq!(pager_scroll(key(KeyCode::Char('g')), 20, 10, 40), Some(0));
+        assert_eq!(pager_scroll(key(KeyCode::Char('q')), 20, 10, 40), None);
+        assert_eq!(pager_scroll(key(KeyCode::Esc), 20, 10, 40), None);
+    }
+
     // =============================================================================
     // wrap_ansi_line Tests
     // =============================================================================
@@ -2646,6 +3298,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert_eq!(layout.lines.len(), 1);
@@ -2665,6 +3321,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert_eq!(layout.lines.len(), 1);
@@ -2684,6 +3344,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert_eq!(layout.lines.len(), 2);
@@ -2702,6 +3366,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert_eq!(layout.cursor_row, 0);
@@ -2719,6 +3387,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert_eq!(layout.cursor_row, 0);
@@ -2736,6 +3408,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 40);
         // Should have multiple lines due to wrapping
@@ -2987,6 +3663,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert_eq!(layout.cursor_col, 6); // 2 for "> " + 4 for "Test"
@@ -3004,6 +3684,10 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert!(layout.lines.len() >= 1);
@@ -3077,6 +3761,28 @@ mod tests {
         assert_eq!(result[1], "6");
     }
 
+    #[test]
+    fn test_split_output_line_follows_width_setting() {
+        let line = "1234567890";
+        assert_eq!(
+            split_output_line(line, Some(4), 8),
+            vec!["1234", "5678", "90"]
+        );
+        assert_eq!(split_output_line(line, None, 8), vec!["12345678"]);
+        assert_eq!(split_output_line("", None, 8), vec![""]);
+    }
+
+    #[test]
+    fn test_rescale_scroll_keeps_bottom_line() {
+        let lines = vec!["a".repeat(20), "b".repeat(20), "c".repeat(5)];
+        // At width 10 the last two lines take 3 rows; at 5 they take 5
+        assert_eq!(rescale_scroll(&lines, 10, 5, 3), 5);
+        assert_eq!(rescale_scroll(&lines, 10, 5, 0), 0);
+        assert_eq!(rescale_scroll(&lines, 10, 10, 4), 4);
+        // Scrolled past the top stays past the top
+        assert_eq!(rescale_scroll(&lines, 10, 20, 9), 3);
+    }
+
     #[test]
     fn test_build_output_lines_single_empty_line() {
         let lines = vec![String::new()];
@@ -3146,8 +3852,40 @@ mod tests {
             output_scroll: 0,
             selection_range: None,
             todos: vec![],
+            status: None,
+            usage: None,
+            diff: None,
+            diff_scroll: 0,
         };
         let layout = build_input_layout(&snapshot, 80);
         assert!(layout.lines.len() > 1);
     }
+
+    #[test]
+    fn test_split_diff_pane_needs_room() {
+        let wide = Rect::new(0, 0, 120, 40);
+        let (main, diff) = split_diff_pane(wide, true);
+        let diff = diff.expect("diff pane on a wide terminal");
+        assert_eq!(main.width + diff.width, 120);
+        assert_eq!(diff.x, main.width);
+
+        assert_eq!(split_diff_pane(wide, false), (wide, None));
+        let narrow = Rect::new(0, 0, 60, 40);
+        assert_eq!(split_diff_pane(narrow, true), (narrow, None));
+    }
+
+    #[test]
+    fn test_build_diff_text_colors_and_scrolls() {
+        let lines: Vec<String> = ["--- a.rs", "+++ a.rs", "@@ -1,1 +1,1 @@", "-old", "+new"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let text = build_diff_text(&lines, 3, 10);
+        assert_eq!(text.lines.len(), 2);
+        assert_eq!(text.lines[0].spans[0].style.fg, Some(Color::Red));
+        assert_eq!(text.lines[1].spans[0].style.fg, Some(Color::Green));
+
+        let empty = build_diff_text(&[], 0, 10);
+        assert_eq!(empty.lines[0].spans[0].content, "No files changed yet");
+    }
 }
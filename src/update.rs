@@ -0,0 +1,365 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::http_client;
+
+/// The GitHub repository releases are published to
+const REPOSITORY: &str = "martydill/flexorama";
+
+/// The release file listing a SHA-256 checksum for every binary. It comes
+/// from the same release as the binaries, so it catches a corrupted or
+/// truncated download but not a release that was tampered with as a whole:
+/// releases aren't signed.
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// The version of the running binary
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How often interactive sessions look for a new release
+const CHECK_INTERVAL_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// Parse `1.2.3` or `v1.2.3-beta` into comparable numbers. Pre-release and
+/// build suffixes are ignored.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// Whether `latest` is a newer version than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// The release asset for this platform, e.g. `flx-linux-x86_64` or
+/// `flx-windows-x86_64.exe`
+pub fn platform_asset_name() -> String {
+    format!(
+        "flx-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Read a `sha256sum`-style file: a hex digest and a file name per line
+fn parse_checksums(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let digest = parts.next()?;
+            let name = parts.next()?.trim_start_matches('*');
+            Some((name.to_string(), digest.to_lowercase()))
+        })
+        .collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn verify_checksum(bytes: &[u8], expected: &str) -> Result<()> {
+    let actual = sha256_hex(bytes);
+    if actual != expected.to_lowercase() {
+        return Err(anyhow!(
+            "Checksum mismatch: expected {}, downloaded file has {}",
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+async fn fetch_latest_release(client: &reqwest::Client) -> Result<Release> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        REPOSITORY
+    );
+    let response = client
+        .get(&url)
+        .header("User-Agent", format!("flexorama/{}", CURRENT_VERSION))
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .context("Failed to reach GitHub")?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "GitHub returned {} for the latest release",
+            response.status()
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+async fn download(client: &reqwest::Client, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+    let response = client
+        .get(&asset.browser_download_url)
+        .header("User-Agent", format!("flexorama/{}", CURRENT_VERSION))
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {}", asset.name))?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Downloading {} failed with {}",
+            asset.name,
+            response.status()
+        ));
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Replace the binary at `target` with `bytes`. The new file is written next
+/// to it and renamed into place; the old one is moved aside first because
+/// Windows can't overwrite a running executable.
+fn replace_binary(target: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = target.with_extension("new");
+    let backup = target.with_extension("old");
+    std::fs::write(&staged, bytes)
+        .with_context(|| format!("Failed to write {}", staged.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    let _ = std::fs::remove_file(&backup);
+    std::fs::rename(target, &backup)
+        .with_context(|| format!("Failed to move {} aside", target.display()))?;
+    if let Err(e) = std::fs::rename(&staged, target) {
+        // Put the old binary back rather than leave nothing installed
+        let _ = std::fs::rename(&backup, target);
+        return Err(e).with_context(|| format!("Failed to install {}", target.display()));
+    }
+    if let Err(e) = std::fs::remove_file(&backup) {
+        debug!("Leaving {} in place: {}", backup.display(), e);
+    }
+    Ok(())
+}
+
+/// What `flexorama update` did
+pub enum UpdateOutcome {
+    UpToDate,
+    Available(Release),
+    Installed { release: Release, path: PathBuf },
+}
+
+/// Check GitHub for a newer release and, unless `check_only`, download the
+/// binary for this platform, verify it against the release checksums and
+/// install it over the running executable. The checksums prove the download
+/// is intact, not who published it; see [`CHECKSUMS_ASSET`].
+pub async fn run_update(check_only: bool) -> Result<UpdateOutcome> {
+    let client = http_client::client();
+    let release = fetch_latest_release(&client).await?;
+    if !is_newer(release.version(), CURRENT_VERSION) {
+        return Ok(UpdateOutcome::UpToDate);
+    }
+    if check_only {
+        return Ok(UpdateOutcome::Available(release));
+    }
+
+    let asset_name = platform_asset_name();
+    let asset = release.asset(&asset_name).ok_or_else(|| {
+        anyhow!(
+            "Release {} has no {} binary; download it from {}",
+            release.tag_name,
+            asset_name,
+            release.html_url
+        )
+    })?;
+    let checksums_asset = release.asset(CHECKSUMS_ASSET).ok_or_else(|| {
+        anyhow!(
+            "Release {} has no {}; refusing to install an unverified binary",
+            release.tag_name,
+            CHECKSUMS_ASSET
+        )
+    })?;
+    let checksums = parse_checksums(&String::from_utf8_lossy(
+        &download(&client, checksums_asset).await?,
+    ));
+    let expected = checksums
+        .get(&asset_name)
+        .ok_or_else(|| anyhow!("{} has no entry for {}", CHECKSUMS_ASSET, asset_name))?;
+
+    let bytes = download(&client, asset).await?;
+    verify_checksum(&bytes, expected)?;
+
+    let path = std::env::current_exe()?;
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    replace_binary(&path, &bytes)?;
+    Ok(UpdateOutcome::Installed { release, path })
+}
+
+/// The last daily check, cached in ~/.flexorama/update-check.json
+#[derive(Debug, Serialize, Deserialize)]
+struct UpdateCheck {
+    checked_at: DateTime<Utc>,
+    latest_version: String,
+}
+
+fn update_check_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".flexorama").join("update-check.json"))
+}
+
+fn checks_disabled() -> bool {
+    std::env::var("FLEXORAMA_NO_UPDATE_CHECK").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+async fn save_update_check(path: &Path, check: &UpdateCheck) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_string(check)?).await?;
+    Ok(())
+}
+
+/// A notice for interactive mode if the last check found a newer release.
+/// When that check is over a day old, a new one runs in the background so
+/// startup never waits on the network; its result shows next time.
+pub fn new_version_notice() -> Option<String> {
+    if checks_disabled() {
+        return None;
+    }
+    let path = update_check_path()?;
+    let cached: Option<UpdateCheck> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok());
+
+    let stale = cached
+        .as_ref()
+        .is_none_or(|check| Utc::now() - check.checked_at > Duration::hours(CHECK_INTERVAL_HOURS));
+    if stale {
+        let previous = cached.as_ref().map(|check| check.latest_version.clone());
+        tokio::spawn(async move {
+            let client = http_client::client();
+            // A failed check is recorded too, keeping what the last good one
+            // found, so being offline doesn't mean a request every startup
+            let latest_version = match fetch_latest_release(&client).await {
+                Ok(release) => release.version().to_string(),
+                Err(e) => {
+                    debug!("Update check failed: {}", e);
+                    previous.unwrap_or_default()
+                }
+            };
+            let check = UpdateCheck {
+                checked_at: Utc::now(),
+                latest_version,
+            };
+            if let Err(e) = save_update_check(&path, &check).await {
+                debug!("Failed to save update check: {}", e);
+            }
+        });
+    }
+
+    let latest = cached?.latest_version;
+    is_newer(&latest, CURRENT_VERSION).then(|| {
+        format!(
+            "Flexorama {} is available (you have {}). Run `flexorama update` to install it.",
+            latest, CURRENT_VERSION
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions() {
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta", "0.1.0"));
+        assert!(!is_newer("0.0.9", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn verifies_checksums_from_sha256sums() {
+        let binary = b"flexorama binary";
+        let digest = sha256_hex(binary);
+        let sums = format!(
+            "{}  flx-linux-x86_64\n{} *flx-windows-x86_64.exe\n",
+            digest, "ab12"
+        );
+        let checksums = parse_checksums(&sums);
+        assert_eq!(checksums.get("flx-linux-x86_64"), Some(&digest));
+        assert_eq!(
+            checksums.get("flx-windows-x86_64.exe").map(String::as_str),
+            Some("ab12")
+        );
+
+        assert!(verify_checksum(binary, &digest.to_uppercase()).is_ok());
+        assert!(verify_checksum(b"tampered", &digest).is_err());
+    }
+
+    #[test]
+    fn replaces_binary_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("flx");
+        std::fs::write(&target, b"old").unwrap();
+
+        replace_binary(&target, b"new").unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+        assert!(!target.with_extension("new").exists());
+        assert!(!target.with_extension("old").exists());
+    }
+
+    #[tokio::test]
+    async fn saving_a_check_creates_the_flexorama_directory() {
+        let home = tempfile::TempDir::new().unwrap();
+        let path = home.path().join(".flexorama").join("update-check.json");
+        let check = UpdateCheck {
+            checked_at: Utc::now(),
+            latest_version: "0.2.0".to_string(),
+        };
+        save_update_check(&path, &check).await.unwrap();
+
+        let saved: UpdateCheck =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.latest_version, "0.2.0");
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+use crate::config::VoiceConfig;
+
+/// Programs that can record the microphone to a WAV file, in the order
+/// they're tried
+const RECORDERS: &[&str] = &["rec", "arecord", "ffmpeg"];
+
+/// The arguments that make `recorder` write 16 kHz mono audio, which is
+/// what whisper.cpp expects, to `path` for at most `max_seconds`
+fn recorder_args(recorder: &str, path: &Path, max_seconds: u64) -> Vec<String> {
+    let path = path.to_string_lossy().into_owned();
+    let max = max_seconds.to_string();
+    let args: Vec<&str> = match recorder {
+        // sox
+        "rec" => vec![
+            "-q", "-c", "1", "-r", "16000", "-b", "16", &path, "trim", "0", &max,
+        ],
+        "arecord" => vec![
+            "-q", "-f", "S16_LE", "-c", "1", "-r", "16000", "-d", &max, &path,
+        ],
+        _ => {
+            let (format, input) = if cfg!(target_os = "macos") {
+                ("avfoundation", ":0")
+            } else if cfg!(windows) {
+                ("dshow", "audio=default")
+            } else {
+                ("pulse", "default")
+            };
+            vec![
+                "-loglevel",
+                "error",
+                "-f",
+                format,
+                "-i",
+                input,
+                "-ac",
+                "1",
+                "-ar",
+                "16000",
+                "-t",
+                &max,
+                "-y",
+                &path,
+            ]
+        }
+    };
+    args.into_iter().map(str::to_string).collect()
+}
+
+/// The model used when the config doesn't name one
+fn default_model() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow!("Could not determine home directory"))?
+        .join(".flexorama")
+        .join("models")
+        .join("ggml-base.en.bin"))
+}
+
+/// A microphone recording in progress
+pub struct Recording {
+    child: Child,
+    path: PathBuf,
+}
+
+impl Recording {
+    /// Start recording with the first recorder found on the PATH
+    pub fn start(config: &VoiceConfig) -> Result<Self> {
+        let recorder = RECORDERS
+            .iter()
+            .find(|recorder| which::which(recorder).is_ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No audio recorder found; install sox (rec), alsa-utils (arecord) or ffmpeg"
+                )
+            })?;
+        let path =
+            std::env::temp_dir().join(format!("flexorama-voice-{}.wav", uuid::Uuid::new_v4()));
+        let child = Command::new(recorder)
+            .args(recorder_args(recorder, &path, config.max_seconds))
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", recorder))?;
+        Ok(Self { child, path })
+    }
+
+    /// Stop recording and return the audio file. The recorder is interrupted
+    /// rather than killed where possible, so it finishes writing the file.
+    pub async fn stop(mut self) -> Result<PathBuf> {
+        if self.child.try_wait()?.is_none() {
+            #[cfg(unix)]
+            if let Some(pid) = self.child.id() {
+                let _ = Command::new("kill")
+                    .args(["-INT", &pid.to_string()])
+                    .status()
+                    .await;
+            }
+            if tokio::time::timeout(Duration::from_secs(5), self.child.wait())
+                .await
+                .is_err()
+            {
+                self.child.kill().await?;
+            }
+        }
+        if !self.path.exists() {
+            return Err(anyhow!("The recorder didn't save any audio"));
+        }
+        Ok(self.path)
+    }
+}
+
+/// Transcribe a WAV file with the whisper.cpp command line program
+pub async fn transcribe(config: &VoiceConfig, audio: &Path) -> Result<String> {
+    let model = match &config.model {
+        Some(model) => PathBuf::from(shellexpand::tilde(&model.to_string_lossy()).into_owned()),
+        None => default_model()?,
+    };
+    if !model.exists() {
+        return Err(anyhow!(
+            "No whisper model at {}; download one such as ggml-base.en.bin from \
+             https://huggingface.co/ggerganov/whisper.cpp",
+            model.display()
+        ));
+    }
+    let output = Command::new(&config.whisper_command)
+        .arg("-m")
+        .arg(&model)
+        .arg("-f")
+        .arg(audio)
+        .args(["-l", &config.language, "-nt", "-np"])
+        .output()
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to run {}; install whisper.cpp or set whisper_command under [voice]",
+                config.whisper_command
+            )
+        })?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            config.whisper_command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(clean_transcript(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The spoken text from whisper.cpp's output, on one line and without
+/// markers such as `[BLANK_AUDIO]`
+fn clean_transcript(output: &str) -> String {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            let marker = (line.starts_with('[') && line.ends_with(']'))
+                || (line.starts_with('(') && line.ends_with(')'));
+            !line.is_empty() && !marker
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_sixteen_kilohertz_mono() {
+        let path = Path::new("/tmp/in.wav");
+        let args = recorder_args("arecord", path, 30);
+        assert_eq!(
+            args,
+            vec![
+                "-q",
+                "-f",
+                "S16_LE",
+                "-c",
+                "1",
+                "-r",
+                "16000",
+                "-d",
+                "30",
+                "/tmp/in.wav"
+            ]
+        );
+        let args = recorder_args("rec", path, 30);
+        assert_eq!(args[args.len() - 4..], ["/tmp/in.wav", "trim", "0", "30"]);
+        let args = recorder_args("ffmpeg", path, 30);
+        assert!(args.windows(2).any(|pair| pair == ["-ar", "16000"]));
+        assert_eq!(args.last().map(String::as_str), Some("/tmp/in.wav"));
+    }
+
+    #[test]
+    fn cleans_whisper_output() {
+        assert_eq!(
+            clean_transcript("\n Why does the parser test fail?\n And only on CI.\n"),
+            "Why does the parser test fail? And only on CI."
+        );
+        assert_eq!(clean_transcript("[BLANK_AUDIO]\n"), "");
+        assert_eq!(
+            clean_transcript(" (keyboard clicking)\nRun it again\n"),
+            "Run it again"
+        );
+    }
+}
@@ -1,34 +1,43 @@
-use crate::agent::{Agent, ConversationSnapshot, StreamToolEvent};
+use crate::agent::{Agent, ConversationSnapshot, StreamToolEvent, STREAM_SCHEMA_VERSION};
 use crate::anthropic::ContentBlock;
 use crate::config;
+use crate::config_watch::ConfigReload;
+use crate::context_usage::{ContextBreakdown, ContextItemKind};
 use crate::conversation::ConversationManager;
 use crate::csrf::CsrfManager;
 use crate::custom_commands;
-use crate::database::{Conversation, DatabaseManager, ToolCallRecord};
-use crate::mcp::{McpAuthConfig, McpManager, McpServerConfig};
+use crate::database::{Conversation, ConversationSettings, DatabaseManager, ToolCallRecord};
+use crate::math::{self, MathSegment};
+use crate::mcp::{McpAuthConfig, McpManager, McpSamplingPolicy, McpServerConfig, McpTransport};
 use crate::security::{PermissionHandler, PermissionKind, PermissionPrompt};
 use crate::skill::SkillManager;
 use crate::subagent::{SubagentConfig, SubagentManager};
+use crate::templates;
 use anyhow::Result;
 use axum::body::Body;
-use axum::extract::{Path, Query, State};
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
 use axum::http::{header, Request, StatusCode};
 use axum::middleware::{self, Next};
 use axum::response::{Html, IntoResponse, Response};
-use axum::routing::{get, post, put};
+use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
 use bytes::Bytes;
 use chrono::{Duration, Utc};
+use log::debug;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::Infallible;
 use std::net::SocketAddr;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Instant;
 use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{Mutex, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
+use tower_governor::governor::GovernorConfigBuilder;
+use tower_governor::GovernorLayer;
 use tower_http::cors::{Any, CorsLayer};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 #[derive(Clone)]
@@ -39,14 +48,41 @@ pub struct WebState {
     pub subagent_manager: Arc<Mutex<SubagentManager>>,
     pub permission_hub: Arc<PermissionHub>,
     pub skill_manager: Arc<Mutex<SkillManager>>,
-    pub conversation_agents: Arc<Mutex<HashMap<String, Arc<Mutex<Agent>>>>>,
+    pub conversation_agents: Arc<Mutex<HashMap<String, ConversationAgent>>>,
     pub csrf_manager: Arc<CsrfManager>,
-    pub config: Arc<config::Config>,
+    pub config: Arc<RwLock<config::Config>>,
+    pub started_at: Instant,
 }
 
-#[derive(Serialize)]
+/// The agent kept in memory for one conversation between requests
+#[derive(Clone)]
+pub struct ConversationAgent {
+    pub agent: Arc<Mutex<Agent>>,
+    /// Cancels the turn the agent is running, if any
+    cancellation: Arc<AtomicBool>,
+    /// When a request last used the agent
+    last_used: Instant,
+}
+
+impl ConversationAgent {
+    fn new(agent: Agent) -> Self {
+        Self {
+            agent: Arc::new(Mutex::new(agent)),
+            cancellation: Arc::new(AtomicBool::new(false)),
+            last_used: Instant::now(),
+        }
+    }
+
+    /// Whether a request holds the agent, e.g. to run a turn
+    fn is_busy(&self) -> bool {
+        self.agent.try_lock().is_err()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 struct ConversationListItem {
     id: String,
+    name: Option<String>,
     created_at: String,
     updated_at: String,
     model: String,
@@ -57,7 +93,7 @@ struct ConversationListItem {
     message_count: usize,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, ToSchema)]
 struct ImageSourceDto {
     #[serde(rename = "type")]
     source_type: String,
@@ -65,21 +101,63 @@ struct ImageSourceDto {
     data: String,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, ToSchema)]
 struct ContentBlockDto {
     #[serde(rename = "type")]
     block_type: String,
     text: Option<String>,
     id: Option<String>,
     name: Option<String>,
+    #[schema(value_type = Option<Object>)]
     input: Option<serde_json::Value>,
     tool_use_id: Option<String>,
     content: Option<String>,
     is_error: Option<bool>,
     source: Option<ImageSourceDto>,
+    /// Text blocks containing LaTeX, split so the UI can typeset the math
+    #[serde(skip_serializing_if = "Option::is_none")]
+    segments: Option<Vec<TextSegmentDto>>,
+}
+
+/// Prose (`type: "text"`) or TeX source for KaTeX (`type: "math"`), with
+/// `display` for `$$...$$` blocks
+#[derive(Serialize, Clone, ToSchema)]
+struct TextSegmentDto {
+    #[serde(rename = "type")]
+    segment_type: String,
+    text: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    display: bool,
 }
 
-#[derive(Serialize)]
+fn math_segments(text: &str) -> Option<Vec<TextSegmentDto>> {
+    let segments = math::split_math(text);
+    if !segments
+        .iter()
+        .any(|segment| matches!(segment, MathSegment::Math { .. }))
+    {
+        return None;
+    }
+    Some(
+        segments
+            .into_iter()
+            .map(|segment| match segment {
+                MathSegment::Text(text) => TextSegmentDto {
+                    segment_type: "text".to_string(),
+                    text: text.to_string(),
+                    display: false,
+                },
+                MathSegment::Math { tex, display } => TextSegmentDto {
+                    segment_type: "math".to_string(),
+                    text: tex.to_string(),
+                    display,
+                },
+            })
+            .collect(),
+    )
+}
+
+#[derive(Serialize, ToSchema)]
 struct MessageDto {
     id: String,
     role: String,
@@ -88,16 +166,26 @@ struct MessageDto {
     created_at: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationDetail {
     conversation: ConversationMeta,
     messages: Vec<MessageDto>,
+    next_cursor: Option<String>,
+    has_more: bool,
     context_files: Vec<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+struct MessagePageDto {
+    messages: Vec<MessageDto>,
+    next_cursor: Option<String>,
+    has_more: bool,
+}
+
+#[derive(Serialize, ToSchema)]
 struct ConversationMeta {
     id: String,
+    name: Option<String>,
     created_at: String,
     updated_at: String,
     system_prompt: Option<String>,
@@ -107,36 +195,140 @@ struct ConversationMeta {
     request_count: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, ToSchema)]
+struct ConversationSettingsDto {
+    model: String,
+    temperature: f32,
+    system_prompt: Option<String>,
+    active_skills: Vec<String>,
+    plan_mode: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ConversationSettingsUpdate {
+    model: Option<String>,
+    temperature: Option<f32>,
+    /// An empty string clears the conversation's system prompt
+    system_prompt: Option<String>,
+    active_skills: Option<Vec<String>>,
+    plan_mode: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ContextItemDto {
+    /// system_prompt, agents_md, context_file, image, tool_schemas or history
+    kind: String,
+    label: String,
+    /// Estimated tokens
+    tokens: usize,
+    /// Share of the model's context window, as a percentage
+    percent: f64,
+    /// Pass to DELETE /api/conversations/{id}/context/{message_index} to
+    /// remove the item; only context files and images have one
+    message_index: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ContextBreakdownDto {
+    model: String,
+    context_window: usize,
+    total_tokens: usize,
+    percent: f64,
+    items: Vec<ContextItemDto>,
+}
+
+impl From<&ContextBreakdown> for ContextBreakdownDto {
+    fn from(breakdown: &ContextBreakdown) -> Self {
+        let total = breakdown.total_tokens();
+        Self {
+            model: breakdown.model.clone(),
+            context_window: breakdown.window,
+            total_tokens: total,
+            percent: breakdown.percent_of_window(total),
+            items: breakdown
+                .items
+                .iter()
+                .map(|item| ContextItemDto {
+                    kind: match item.kind {
+                        ContextItemKind::SystemPrompt => "system_prompt",
+                        ContextItemKind::AgentsMd => "agents_md",
+                        ContextItemKind::ContextFile => "context_file",
+                        ContextItemKind::Image => "image",
+                        ContextItemKind::ToolSchemas => "tool_schemas",
+                        ContextItemKind::History => "history",
+                    }
+                    .to_string(),
+                    label: item.label.clone(),
+                    tokens: item.tokens,
+                    percent: breakdown.percent_of_window(item.tokens),
+                    message_index: item.message_index,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 struct NewConversationRequest {
     system_prompt: Option<String>,
+    /// Template to set the conversation up from; `system_prompt` overrides its prompt
+    #[serde(default)]
+    template: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct TemplateDto {
+    name: String,
+    description: Option<String>,
+    system_prompt: Option<String>,
+    context: Vec<String>,
+    skills: Vec<String>,
+    model: Option<String>,
+}
+
+impl From<templates::ConversationTemplate> for TemplateDto {
+    fn from(template: templates::ConversationTemplate) -> Self {
+        Self {
+            name: template.name,
+            description: template.config.description,
+            system_prompt: template.config.system_prompt,
+            context: template.config.context,
+            skills: template.config.skills,
+            model: template.config.model,
+        }
+    }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
 struct ImageData {
     media_type: String,
     data: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct MessageRequest {
     message: String,
     images: Option<Vec<ImageData>>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+struct EditMessageRequest {
+    message: String,
+}
+
+#[derive(Serialize, ToSchema)]
 struct ModelListResponse {
     provider: String,
     active_model: String,
     models: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct ModelUpdateRequest {
     model: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct PlanDto {
     id: String,
     conversation_id: Option<String>,
@@ -146,14 +338,14 @@ struct PlanDto {
     created_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct PlanUpdateRequest {
     title: Option<String>,
     user_request: Option<String>,
     plan_markdown: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct PlanCreateRequest {
     title: Option<String>,
     user_request: String,
@@ -161,25 +353,33 @@ struct PlanCreateRequest {
     conversation_id: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct UpsertServerRequest {
     name: Option<String>,
     command: Option<String>,
     args: Option<Vec<String>>,
     url: Option<String>,
     env: Option<HashMap<String, String>>,
+    #[schema(value_type = Option<Object>)]
     auth: Option<McpAuthConfig>,
     enabled: Option<bool>,
+    #[schema(value_type = Option<String>)]
+    transport: Option<McpTransport>,
+    #[schema(value_type = Option<String>)]
+    sampling: Option<McpSamplingPolicy>,
+    lazy: Option<bool>,
+    aliases: Option<HashMap<String, String>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ServerDto {
     name: String,
+    #[schema(value_type = Object)]
     config: McpServerConfig,
     connected: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct AgentDto {
     name: String,
     allowed_tools: Vec<String>,
@@ -193,7 +393,7 @@ struct AgentDto {
     active: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct AgentUpdateRequest {
     system_prompt: String,
     allowed_tools: Vec<String>,
@@ -203,7 +403,7 @@ struct AgentUpdateRequest {
     model: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct NewAgentRequest {
     name: String,
     system_prompt: String,
@@ -214,12 +414,12 @@ struct NewAgentRequest {
     model: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct ActivateAgentRequest {
     name: Option<String>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, ToSchema)]
 struct PermissionRequestDto {
     id: String,
     kind: String,
@@ -230,40 +430,42 @@ struct PermissionRequestDto {
     created_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct PermissionResolveRequest {
     id: String,
     selection: Option<usize>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct PermissionPendingQuery {
     conversation_id: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct PlanModeResponse {
     enabled: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct FileAutocompleteQuery {
     prefix: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct FileAutocompleteResponse {
     files: Vec<FileAutocompleteItem>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct FileAutocompleteItem {
     path: String,
     is_directory: bool,
 }
 
 // Skill-related DTOs
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct SkillDto {
     name: String,
     description: String,
@@ -280,7 +482,7 @@ struct SkillDto {
     updated_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct NewSkillRequest {
     name: String,
     description: String,
@@ -293,7 +495,7 @@ struct NewSkillRequest {
     tags: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct SkillUpdateRequest {
     description: String,
     content: String,
@@ -305,8 +507,23 @@ struct SkillUpdateRequest {
     tags: Vec<String>,
 }
 
+#[derive(Deserialize, ToSchema)]
+struct BundleImportRequest {
+    /// Path, URL or git repository to import from
+    source: Option<String>,
+    /// A bundle as written by export, to import directly
+    #[schema(value_type = Object)]
+    bundle: Option<crate::bundle::Bundle>,
+    /// Install into the project's .flexorama directory
+    #[serde(default)]
+    project: bool,
+    /// Replace an existing skill or agent with the same name
+    #[serde(default)]
+    force: bool,
+}
+
 // Custom command DTOs
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct CustomCommandDto {
     name: String,
     description: String,
@@ -316,7 +533,7 @@ struct CustomCommandDto {
     content: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct NewCustomCommandRequest {
     name: String,
     description: String,
@@ -326,7 +543,7 @@ struct NewCustomCommandRequest {
     content: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct CustomCommandUpdateRequest {
     description: String,
     argument_hint: Option<String>,
@@ -335,26 +552,27 @@ struct CustomCommandUpdateRequest {
     content: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct PlanModeRequest {
     enabled: bool,
 }
 
 // Stats API DTOs
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct StatsQueryParams {
     period: Option<String>,
     start_date: Option<String>,
     end_date: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct UsageStatsResponse {
     period: String,
     data: Vec<UsageStatsPoint>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct UsageStatsPoint {
     date: String,
     total_requests: i32,
@@ -363,25 +581,25 @@ struct UsageStatsPoint {
     total_tokens: i32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationStatsResponse {
     period: String,
     data: Vec<ConversationStatsPoint>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationStatsPoint {
     date: String,
     count: i32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ModelStatsResponse {
     period: String,
     data: Vec<ModelStatsPoint>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ModelStatsPoint {
     model: String,
     provider: String,
@@ -390,32 +608,58 @@ struct ModelStatsPoint {
     request_count: i32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationsByProviderResponse {
     period: String,
     data: Vec<ConversationsByProviderPoint>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationsByProviderPoint {
     date: String,
     provider: String,
     count: i32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationsBySubagentResponse {
     period: String,
     data: Vec<ConversationsBySubagentPoint>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ConversationsBySubagentPoint {
     date: String,
     subagent: String,
     count: i32,
 }
 
+#[derive(Serialize, ToSchema)]
+struct ProjectStatsResponse {
+    period: String,
+    data: Vec<ProjectStatsPoint>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Default)]
+struct ProjectStatsPoint {
+    /// The directory's name
+    project: String,
+    /// The directory, when it was recorded
+    path: Option<String>,
+    total_conversations: i64,
+    total_tokens: i64,
+    input_tokens: i64,
+    output_tokens: i64,
+    request_count: i64,
+    tool_calls: i64,
+    tool_errors: i64,
+    /// Estimated from list prices; tokens of models without one aren't counted
+    cost_usd: f64,
+    /// Input and output tokens of models without a known price
+    unpriced_tokens: i64,
+    models: Vec<String>,
+}
+
 pub struct PermissionHub {
     pending: Mutex<HashMap<String, PermissionRequestDto>>,
     responders: Mutex<HashMap<String, oneshot::Sender<Option<usize>>>>,
@@ -458,6 +702,19 @@ impl PermissionHub {
             .collect()
     }
 
+    /// Answer every pending request of a conversation with no selection
+    async fn dismiss_conversation(&self, conversation_id: &str) {
+        let ids: Vec<String> = self
+            .list_pending(Some(conversation_id))
+            .await
+            .into_iter()
+            .map(|req| req.id)
+            .collect();
+        for id in ids {
+            self.resolve(&id, None).await;
+        }
+    }
+
     async fn resolve(&self, id: &str, selection: Option<usize>) -> bool {
         let sender = self.responders.lock().await.remove(id);
         self.pending.lock().await.remove(id);
@@ -472,24 +729,198 @@ impl PermissionHub {
 
 const INDEX_HTML: &str = include_str!("../web/index.html");
 const APP_JS: &str = include_str!("../web/app.js");
+const API_DOCS_HTML: &str = include_str!("../web/api-docs.html");
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Flexorama API",
+        description = "HTTP API behind the Flexorama web UI. State-changing requests require the `X-CSRF-Token` header."
+    ),
+    paths(
+        health,
+        list_conversations,
+        search_conversations,
+        get_conversation,
+        get_conversation_messages,
+        get_conversation_settings,
+        update_conversation_settings,
+        get_conversation_context,
+        remove_conversation_context_item,
+        create_conversation,
+        delete_conversation,
+        close_conversation_agent_handler,
+        send_message_to_conversation,
+        stream_message_to_conversation,
+        regenerate_message,
+        edit_message,
+        get_models,
+        set_model,
+        list_plans,
+        get_plan,
+        create_plan,
+        update_plan,
+        delete_plan,
+        list_mcp_servers,
+        get_mcp_server,
+        upsert_mcp_server,
+        upsert_mcp_server_named,
+        delete_mcp_server,
+        connect_mcp_server,
+        disconnect_mcp_server,
+        list_agents,
+        get_agent,
+        create_agent,
+        update_agent,
+        delete_agent,
+        export_agent_bundle,
+        import_agent_bundle,
+        get_active_agent,
+        set_active_agent,
+        list_skills,
+        get_skill,
+        create_skill,
+        update_skill,
+        delete_skill,
+        export_skill_bundle,
+        import_skill_bundle,
+        activate_skill,
+        deactivate_skill,
+        get_active_skills,
+        list_templates,
+        list_custom_commands,
+        get_custom_command,
+        create_custom_command,
+        update_custom_command,
+        delete_custom_command,
+        list_pending_permissions,
+        resolve_permission_request,
+        get_plan_mode,
+        set_plan_mode,
+        list_todos,
+        get_stats_overview,
+        get_usage_stats,
+        get_model_stats,
+        get_conversation_stats,
+        get_conversation_stats_by_provider,
+        get_conversation_stats_by_subagent,
+        get_project_stats,
+        get_file_autocomplete,
+    ),
+    components(schemas(
+        ConversationListItem,
+        ConversationDetail,
+        ConversationMeta,
+        MessagePageDto,
+        ConversationSettingsDto,
+        ConversationSettingsUpdate,
+        ContextBreakdownDto,
+        ContextItemDto,
+        MessageDto,
+        ContentBlockDto,
+        TextSegmentDto,
+        ImageSourceDto,
+        NewConversationRequest,
+        TemplateDto,
+        MessageRequest,
+        EditMessageRequest,
+        ImageData,
+        ModelListResponse,
+        ModelUpdateRequest,
+        PlanDto,
+        PlanCreateRequest,
+        PlanUpdateRequest,
+        UpsertServerRequest,
+        ServerDto,
+        AgentDto,
+        NewAgentRequest,
+        AgentUpdateRequest,
+        ActivateAgentRequest,
+        SkillDto,
+        NewSkillRequest,
+        SkillUpdateRequest,
+        BundleImportRequest,
+        CustomCommandDto,
+        NewCustomCommandRequest,
+        CustomCommandUpdateRequest,
+        PermissionRequestDto,
+        PermissionResolveRequest,
+        PlanModeRequest,
+        PlanModeResponse,
+        FileAutocompleteResponse,
+        FileAutocompleteItem,
+        UsageStatsResponse,
+        UsageStatsPoint,
+        ModelStatsResponse,
+        ModelStatsPoint,
+        ConversationStatsResponse,
+        ConversationStatsPoint,
+        ConversationsByProviderResponse,
+        ConversationsByProviderPoint,
+        ConversationsBySubagentResponse,
+        ConversationsBySubagentPoint,
+        ProjectStatsResponse,
+        ProjectStatsPoint,
+        HealthReport,
+        DatabaseHealth,
+        McpServerHealth,
+        ProviderHealth,
+    ))
+)]
+struct ApiDoc;
+
+/// Apply a reloaded config to the server and every live agent
+pub async fn apply_config_reload(state: &WebState, reload: &ConfigReload) {
+    *state.config.write().await = reload.config.clone();
+
+    let mut agents = vec![Arc::clone(&state.agent)];
+    agents.extend(
+        state
+            .conversation_agents
+            .lock()
+            .await
+            .values()
+            .map(|entry| Arc::clone(&entry.agent)),
+    );
+    for agent in agents {
+        if let Err(e) = agent
+            .lock()
+            .await
+            .apply_config_reload(&reload.previous, &reload.config)
+            .await
+        {
+            app_eprintln!("Failed to apply reloaded config to agent: {}", e);
+        }
+    }
+
+    state
+        .mcp_manager
+        .set_project_servers(reload.sources.project_mcp_servers())
+        .await;
+    state
+        .mcp_manager
+        .reload_servers(reload.config.mcp.clone())
+        .await;
+}
 
 /// Get or create an agent for a specific conversation
 /// This allows multiple conversations to be processed concurrently without blocking
 async fn get_or_create_conversation_agent(
     state: &WebState,
     conversation_id: &str,
-) -> Result<Arc<Mutex<Agent>>> {
+) -> Result<ConversationAgent> {
     // Check if agent already exists for this conversation
     {
-        let agents = state.conversation_agents.lock().await;
-        if let Some(agent) = agents.get(conversation_id) {
-            return Ok(agent.clone());
+        let mut agents = state.conversation_agents.lock().await;
+        if let Some(entry) = agents.get_mut(conversation_id) {
+            entry.last_used = Instant::now();
+            return Ok(entry.clone());
         }
     }
 
     // Create a new agent for this conversation
     // Use the config from WebState (which includes CLI-provided API key)
-    let config = (*state.config).clone();
+    let config = state.config.read().await.clone();
     let (model, plan_mode) = {
         let template_agent = state.agent.lock().await;
         (
@@ -504,24 +935,152 @@ async fn get_or_create_conversation_agent(
         .with_database_manager(state.database.clone())
         .with_skill_manager(state.skill_manager.clone());
 
-    // Resume the conversation in the new agent
-    new_agent.resume_conversation(conversation_id).await?;
+    // Resume the conversation in the new agent. If another process has it
+    // open it can still be looked at, but turns wait until it's released.
+    if let Err(error) = new_agent.resume_conversation(conversation_id).await {
+        if error
+            .downcast_ref::<crate::conversation_lock::ConversationInUse>()
+            .is_none()
+        {
+            return Err(error);
+        }
+        new_agent
+            .open_conversation_read_only(conversation_id)
+            .await?;
+    }
+    let settings = state
+        .database
+        .get_conversation_settings(conversation_id)
+        .await?;
+    apply_conversation_settings(&mut new_agent, &settings).await?;
+
+    // Store in pool, unless a concurrent request got there first
+    let mut agents = state.conversation_agents.lock().await;
+    let entry = agents
+        .entry(conversation_id.to_string())
+        .or_insert_with(|| ConversationAgent::new(new_agent));
+    Ok(entry.clone())
+}
 
-    let agent_arc = Arc::new(Mutex::new(new_agent));
+/// Lock a conversation's agent to run a turn, with its cancellation flag
+/// reset. Fails if the agent was closed while the turn waited for it.
+async fn begin_conversation_turn(
+    state: &WebState,
+    conversation_id: &str,
+) -> Result<(tokio::sync::OwnedMutexGuard<Agent>, Arc<AtomicBool>)> {
+    let entry = get_or_create_conversation_agent(state, conversation_id).await?;
+    let mut agent = Arc::clone(&entry.agent).lock_owned().await;
+    let still_open = state
+        .conversation_agents
+        .lock()
+        .await
+        .get(conversation_id)
+        .is_some_and(|current| Arc::ptr_eq(&current.agent, &entry.agent));
+    if !still_open {
+        return Err(anyhow::anyhow!("Conversation was closed"));
+    }
+    if agent.is_read_only() {
+        // Take the conversation over if the other process is done with it
+        agent.resume_conversation(conversation_id).await?;
+        let settings = state
+            .database
+            .get_conversation_settings(conversation_id)
+            .await?;
+        apply_conversation_settings(&mut agent, &settings).await?;
+    }
+    entry.cancellation.store(false, Ordering::SeqCst);
+    Ok((agent, entry.cancellation))
+}
 
-    // Store in pool
+/// Mark a conversation's agent as used just now, so a long turn doesn't
+/// count towards its idle time
+async fn touch_conversation_agent(state: &WebState, conversation_id: &str) {
+    if let Some(entry) = state
+        .conversation_agents
+        .lock()
+        .await
+        .get_mut(conversation_id)
     {
-        let mut agents = state.conversation_agents.lock().await;
-        agents.insert(conversation_id.to_string(), agent_arc.clone());
+        entry.last_used = Instant::now();
     }
+}
+
+/// Drop a conversation's agent from the pool, cancelling the turn it's
+/// running and dismissing its pending permission requests. Returns whether
+/// there was one.
+async fn close_conversation_agent(state: &WebState, conversation_id: &str) -> bool {
+    let Some(entry) = state
+        .conversation_agents
+        .lock()
+        .await
+        .remove(conversation_id)
+    else {
+        return false;
+    };
+    entry.cancellation.store(true, Ordering::SeqCst);
+    state
+        .permission_hub
+        .dismiss_conversation(conversation_id)
+        .await;
+    true
+}
+
+/// Drop the agents no request has used for `idle_timeout`, leaving those
+/// running a turn. Returns how many were dropped.
+async fn evict_idle_conversation_agents(
+    state: &WebState,
+    idle_timeout: std::time::Duration,
+) -> usize {
+    let mut agents = state.conversation_agents.lock().await;
+    let before = agents.len();
+    agents.retain(|_, entry| entry.last_used.elapsed() < idle_timeout || entry.is_busy());
+    before - agents.len()
+}
+
+/// Periodically drop idle conversation agents, per `[web] agent_idle_timeout_secs`
+fn spawn_idle_agent_eviction(state: WebState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let timeout_secs = state.config.read().await.web.agent_idle_timeout_secs;
+            if timeout_secs == 0 {
+                continue;
+            }
+            let evicted = evict_idle_conversation_agents(
+                &state,
+                std::time::Duration::from_secs(timeout_secs),
+            )
+            .await;
+            if evicted > 0 {
+                debug!("Dropped {} idle conversation agent(s)", evicted);
+            }
+        }
+    });
+}
 
-    Ok(agent_arc)
+/// Apply a conversation's stored overrides on top of an agent's global defaults
+async fn apply_conversation_settings(
+    agent: &mut Agent,
+    settings: &ConversationSettings,
+) -> Result<()> {
+    if let Some(temperature) = settings.temperature {
+        agent.set_temperature(temperature);
+    }
+    if let Some(plan_mode) = settings.plan_mode {
+        agent.set_plan_mode(plan_mode).await?;
+    }
+    agent
+        .set_conversation_skills(settings.active_skills.clone())
+        .await
 }
 
 fn permission_kind_label(kind: &PermissionKind) -> &'static str {
     match kind {
         PermissionKind::Bash => "bash",
         PermissionKind::File => "file",
+        PermissionKind::Sampling => "sampling",
+        PermissionKind::Question => "question",
     }
 }
 
@@ -650,6 +1209,7 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
     app_println!("🌐 Web UI starting on http://{} (Ctrl+C to stop)", addr);
 
     ensure_default_conversation(&state).await?;
+    spawn_idle_agent_eviction(state.clone());
 
     // Routes that require CSRF protection (state-changing operations)
     // Configure CORS to only allow requests from the same origin
@@ -662,8 +1222,10 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
         .allow_methods(Any)
         .allow_headers(Any);
 
-    let protected_routes = Router::new()
-        .route("/api/conversations", post(create_conversation))
+    let web_config = state.config.read().await.web.clone();
+
+    // Message endpoints accept base64 images, so they get a larger body allowance
+    let message_routes = Router::new()
         .route(
             "/api/conversations/:id/message",
             post(send_message_to_conversation),
@@ -672,6 +1234,32 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
             "/api/conversations/:id/message/stream",
             post(stream_message_to_conversation),
         )
+        .route(
+            "/api/conversations/:id/messages/:msg_id/regenerate",
+            post(regenerate_message),
+        )
+        .route(
+            "/api/conversations/:id/messages/:msg_id/edit",
+            post(edit_message),
+        )
+        .layer(DefaultBodyLimit::max(web_config.max_message_body_bytes));
+
+    let protected_routes = Router::new()
+        .merge(message_routes)
+        .route("/api/conversations", post(create_conversation))
+        .route("/api/conversations/:id", delete(delete_conversation))
+        .route(
+            "/api/conversations/:id/agent",
+            delete(close_conversation_agent_handler),
+        )
+        .route(
+            "/api/conversations/:id/settings",
+            put(update_conversation_settings),
+        )
+        .route(
+            "/api/conversations/:id/context/:message_index",
+            delete(remove_conversation_context_item),
+        )
         .route("/api/models", post(set_model))
         .route("/api/plans", post(create_plan))
         .route("/api/plans/:id", put(update_plan).delete(delete_plan))
@@ -686,9 +1274,11 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
             post(disconnect_mcp_server),
         )
         .route("/api/agents", post(create_agent))
+        .route("/api/agents/import", post(import_agent_bundle))
         .route("/api/agents/:name", put(update_agent).delete(delete_agent))
         .route("/api/agents/active", post(set_active_agent))
         .route("/api/skills", post(create_skill))
+        .route("/api/skills/import", post(import_skill_bundle))
         .route("/api/skills/:name", put(update_skill).delete(delete_skill))
         .route("/api/skills/:name/activate", post(activate_skill))
         .route("/api/skills/:name/deactivate", post(deactivate_skill))
@@ -709,9 +1299,23 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
         .route("/", get(serve_index))
         .route("/app.js", get(serve_app_js))
         .route("/api/health", get(health))
+        .route("/api/openapi.json", get(get_openapi_spec))
+        .route("/api/docs", get(serve_api_docs))
         .route("/api/conversations", get(list_conversations))
         .route("/api/conversations/search", get(search_conversations))
         .route("/api/conversations/:id", get(get_conversation))
+        .route(
+            "/api/conversations/:id/messages",
+            get(get_conversation_messages),
+        )
+        .route(
+            "/api/conversations/:id/settings",
+            get(get_conversation_settings),
+        )
+        .route(
+            "/api/conversations/:id/context",
+            get(get_conversation_context),
+        )
         .route("/api/models", get(get_models))
         .route("/api/plans", get(list_plans))
         .route("/api/plans/:id", get(get_plan))
@@ -719,10 +1323,13 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
         .route("/api/mcp/servers/:name", get(get_mcp_server))
         .route("/api/agents", get(list_agents))
         .route("/api/agents/:name", get(get_agent))
+        .route("/api/agents/:name/export", get(export_agent_bundle))
         .route("/api/agents/active", get(get_active_agent))
         .route("/api/skills", get(list_skills))
         .route("/api/skills/:name", get(get_skill))
+        .route("/api/skills/:name/export", get(export_skill_bundle))
         .route("/api/skills/active", get(get_active_skills))
+        .route("/api/templates", get(list_templates))
         .route("/api/commands", get(list_custom_commands))
         .route("/api/commands/:name", get(get_custom_command))
         .route("/api/permissions/pending", get(list_pending_permissions))
@@ -741,14 +1348,54 @@ pub async fn launch_web_ui(state: WebState, port: u16) -> Result<()> {
             "/api/stats/conversations-by-subagent",
             get(get_conversation_stats_by_subagent),
         )
+        .route("/api/stats/projects", get(get_project_stats))
         .merge(protected_routes)
         .with_state(state)
-        .layer(cors);
+        .layer(DefaultBodyLimit::max(web_config.max_body_bytes));
+    let router = apply_rate_limit(router, &web_config).layer(cors);
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, router).await?;
+    axum::serve(
+        tokio::net::TcpListener::bind(addr).await?,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
+/// Throttle each client IP according to the configured quota
+fn apply_rate_limit(router: Router, web_config: &config::WebConfig) -> Router {
+    if web_config.rate_limit_per_second == 0 {
+        return router;
+    }
+
+    let period_ms = (1000 / web_config.rate_limit_per_second).max(1);
+    let governor_config = match GovernorConfigBuilder::default()
+        .per_millisecond(period_ms)
+        .burst_size(web_config.rate_limit_burst.max(1))
+        .finish()
+    {
+        Some(config) => Arc::new(config),
+        None => {
+            app_eprintln!("Invalid web rate limit configuration; rate limiting disabled");
+            return router;
+        }
+    };
+
+    // Forget idle clients periodically so the limiter state doesn't grow unbounded
+    let limiter = governor_config.limiter().clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            limiter.retain_recent();
+        }
+    });
+
+    router.layer(GovernorLayer {
+        config: governor_config,
+    })
+}
+
 async fn serve_index(State(state): State<WebState>) -> impl IntoResponse {
     // Generate CSRF token and inject it into the HTML
     let csrf_token = state.csrf_manager.generate_token().await;
@@ -766,16 +1413,171 @@ async fn serve_app_js() -> impl IntoResponse {
     ([(header::CONTENT_TYPE, "application/javascript")], APP_JS)
 }
 
-async fn health() -> impl IntoResponse {
-    Json(HashMap::from([("status", "ok")]))
+async fn get_openapi_spec() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
+
+async fn serve_api_docs() -> impl IntoResponse {
+    Html(API_DOCS_HTML)
+}
+
+#[derive(Serialize, ToSchema)]
+struct HealthReport {
+    /// "ok", or "degraded" when the database or a pinged provider is unreachable
+    status: String,
+    version: String,
+    uptime_seconds: u64,
+    database: DatabaseHealth,
+    mcp_servers: Vec<McpServerHealth>,
+    provider: ProviderHealth,
+    active_conversation_agents: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DatabaseHealth {
+    reachable: bool,
+    path: String,
+    size_bytes: Option<i64>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct McpServerHealth {
+    name: String,
+    enabled: bool,
+    connected: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ProviderHealth {
+    name: String,
+    base_url: String,
+    model: String,
+    /// Only set when the provider was pinged (`?ping=true`)
+    reachable: Option<bool>,
+    latency_ms: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HealthQuery {
+    /// Also check that the LLM provider's base URL answers
+    ping: Option<bool>,
+}
+
+const PROVIDER_PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Check that the provider endpoint answers HTTP at all; any status code counts as reachable
+async fn ping_provider(base_url: &str) -> (bool, Option<u64>, Option<String>) {
+    let client = match crate::http_client::client_builder()
+        .timeout(PROVIDER_PING_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return (false, None, Some(e.to_string())),
+    };
+    let started = Instant::now();
+    match client.get(base_url).send().await {
+        Ok(_) => (true, Some(started.elapsed().as_millis() as u64), None),
+        Err(e) => (false, None, Some(e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "system",
+    params(HealthQuery),
+    responses(
+        (status = 200, description = "Server status report", body = HealthReport),
+        (status = 503, description = "Server is degraded", body = HealthReport)
+    )
+)]
+async fn health(
+    State(state): State<WebState>,
+    Query(query): Query<HealthQuery>,
+) -> impl IntoResponse {
+    let database = match state.database.size_bytes().await {
+        Ok(size) => DatabaseHealth {
+            reachable: true,
+            path: state.database.path().display().to_string(),
+            size_bytes: Some(size),
+            error: None,
+        },
+        Err(e) => DatabaseHealth {
+            reachable: false,
+            path: state.database.path().display().to_string(),
+            size_bytes: None,
+            error: Some(e.to_string()),
+        },
+    };
+
+    let mcp_servers = match state.mcp_manager.list_servers().await {
+        Ok(servers) => servers
+            .into_iter()
+            .map(|(name, config, connected)| McpServerHealth {
+                name,
+                enabled: config.enabled,
+                connected,
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let model = state.agent.lock().await.model().to_string();
+    let (provider_name, base_url) = {
+        let config = state.config.read().await;
+        (config.provider.to_string(), config.base_url.clone())
+    };
+    let mut provider = ProviderHealth {
+        name: provider_name,
+        base_url,
+        model,
+        reachable: None,
+        latency_ms: None,
+        error: None,
+    };
+    if query.ping.unwrap_or(false) {
+        let (reachable, latency_ms, error) = ping_provider(&provider.base_url).await;
+        provider.reachable = Some(reachable);
+        provider.latency_ms = latency_ms;
+        provider.error = error;
+    }
+
+    let active_conversation_agents = state.conversation_agents.lock().await.len();
+    let healthy = database.reachable && provider.reachable != Some(false);
+    let report = HealthReport {
+        status: if healthy { "ok" } else { "degraded" }.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_seconds: state.started_at.elapsed().as_secs(),
+        database,
+        mcp_servers,
+        provider,
+        active_conversation_agents,
+    };
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ConversationListQuery {
     limit: Option<i64>,
     offset: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/conversations",
+    tag = "conversations",
+    params(ConversationListQuery),
+    responses((status = 200, description = "Recent conversations", body = [ConversationListItem]))
+)]
 async fn list_conversations(
     State(state): State<WebState>,
     Query(query): Query<ConversationListQuery>,
@@ -808,11 +1610,19 @@ async fn list_conversations(
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ConversationSearchQuery {
     query: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/conversations/search",
+    tag = "conversations",
+    params(ConversationSearchQuery),
+    responses((status = 200, description = "Conversations matching the query", body = [ConversationListItem]))
+)]
 async fn search_conversations(
     State(state): State<WebState>,
     Query(query): Query<ConversationSearchQuery>,
@@ -866,6 +1676,7 @@ async fn build_conversation_list_items(
         let last_message = first_user.or_else(|| messages.last().map(|m| m.content.clone()));
         let item = ConversationListItem {
             id: conversation.id.clone(),
+            name: conversation.name.clone(),
             created_at: conversation.created_at.to_rfc3339(),
             updated_at: conversation.updated_at.to_rfc3339(),
             model: conversation.model.clone(),
@@ -880,9 +1691,25 @@ async fn build_conversation_list_items(
     items
 }
 
+/// Snapshot the agent most likely to hold the live state of a conversation
+async fn conversation_snapshot(state: &WebState, conversation_id: &str) -> ConversationSnapshot {
+    // First check if there's a dedicated agent for this conversation in the pool
+    let agents = state.conversation_agents.lock().await;
+    if let Some(entry) = agents.get(conversation_id) {
+        // Use the dedicated conversation agent's snapshot (most up-to-date)
+        let agent = entry.agent.lock().await;
+        agent.snapshot_conversation()
+    } else {
+        // Fall back to main agent's snapshot
+        let agent_guard = state.agent.lock().await;
+        agent_guard.snapshot_conversation()
+    }
+}
+
 fn conversation_to_meta(conversation: &Conversation) -> ConversationMeta {
     ConversationMeta {
         id: conversation.id.clone(),
+        name: conversation.name.clone(),
         created_at: conversation.created_at.to_rfc3339(),
         updated_at: conversation.updated_at.to_rfc3339(),
         system_prompt: conversation.system_prompt.clone(),
@@ -893,66 +1720,203 @@ fn conversation_to_meta(conversation: &Conversation) -> ConversationMeta {
     }
 }
 
-async fn get_conversation(
-    State(state): State<WebState>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    let db = state.database.clone();
+const DEFAULT_MESSAGE_PAGE_SIZE: i64 = 50;
+const MAX_MESSAGE_PAGE_SIZE: i64 = 500;
 
-    // First check if there's a dedicated agent for this conversation in the pool
-    let snapshot = {
-        let agents = state.conversation_agents.lock().await;
-        if let Some(agent_arc) = agents.get(&id) {
-            // Use the dedicated conversation agent's snapshot (most up-to-date)
-            let agent = agent_arc.lock().await;
-            agent.snapshot_conversation()
-        } else {
-            // Fall back to main agent's snapshot
-            let agent_guard = state.agent.lock().await;
-            agent_guard.snapshot_conversation()
-        }
-    };
-    let conversation = db.get_conversation(&id).await;
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MessagePageQuery {
+    before: Option<String>,
+    limit: Option<i64>,
+}
 
-    match conversation {
-        Ok(Some(conversation)) => {
-            let use_snapshot = snapshot
-                .id
-                .as_ref()
-                .map(|cid| cid == &conversation.id)
-                .unwrap_or(false);
+impl MessagePageQuery {
+    fn page_size(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_MESSAGE_PAGE_SIZE)
+            .clamp(1, MAX_MESSAGE_PAGE_SIZE)
+    }
+}
 
-            let mut meta = conversation_to_meta(&conversation);
+/// Slice a fully built snapshot timeline into the page ending before `before`
+fn page_snapshot_messages(
+    messages: Vec<MessageDto>,
+    before: Option<&str>,
+    limit: i64,
+) -> Option<MessagePageDto> {
+    let end = match before {
+        Some(cursor) => messages.iter().position(|m| m.id == cursor)?,
+        None => messages.len(),
+    };
+    let start = end.saturating_sub(limit as usize);
+    let has_more = start > 0;
+    let messages: Vec<MessageDto> = messages.into_iter().take(end).skip(start).collect();
+    let next_cursor = if has_more {
+        messages.first().map(|m| m.id.clone())
+    } else {
+        None
+    };
+    Some(MessagePageDto {
+        messages,
+        next_cursor,
+        has_more,
+    })
+}
 
-            let messages: Vec<MessageDto> = if use_snapshot {
-                meta.system_prompt = snapshot.system_prompt.clone();
-                meta.model = snapshot.model.clone();
-                snapshot_messages_to_dto(&snapshot)
-            } else {
-                let raw_messages = match db.get_conversation_messages(&id).await {
-                    Ok(messages) => messages,
-                    Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Failed to load messages: {}", e),
-                        )
-                            .into_response()
-                    }
-                };
-                let tool_calls = match db.get_conversation_tool_calls(&id).await {
-                    Ok(calls) => calls,
-                    Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Failed to load tool calls: {}", e),
-                        )
-                            .into_response()
-                    }
-                };
-                timeline_messages_to_dto(raw_messages.clone(), tool_calls)
-            };
+/// Build one page of the conversation timeline, walking backwards from `before`.
+/// Returns `Ok(None)` when the cursor does not belong to the conversation.
+async fn load_message_page(
+    db: &DatabaseManager,
+    snapshot: Option<&ConversationSnapshot>,
+    conversation_id: &str,
+    before: Option<&str>,
+    limit: i64,
+) -> Result<Option<MessagePageDto>> {
+    if let Some(snapshot) = snapshot {
+        return Ok(page_snapshot_messages(
+            snapshot_messages_to_dto(snapshot),
+            before,
+            limit,
+        ));
+    }
 
-            let raw_context_messages: Vec<MessageDto> =
+    let page = match db
+        .get_conversation_messages_page(conversation_id, before, limit)
+        .await?
+    {
+        Some(page) => page,
+        None => return Ok(None),
+    };
+
+    // Tool calls are attached to the page whose message window they fall into; the
+    // oldest page also takes anything recorded before its first message.
+    let from = if page.has_more {
+        page.messages.first().map(|m| m.created_at)
+    } else {
+        None
+    };
+    let tool_calls = db
+        .get_conversation_tool_calls_between(conversation_id, from, page.before)
+        .await?;
+
+    let next_cursor = if page.has_more {
+        page.messages.first().map(|m| m.id.clone())
+    } else {
+        None
+    };
+    Ok(Some(MessagePageDto {
+        messages: timeline_messages_to_dto(page.messages, tool_calls),
+        next_cursor,
+        has_more: page.has_more,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{id}/messages",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id"), MessagePageQuery),
+    responses((status = 200, description = "One page of messages, oldest first", body = MessagePageDto), (status = 400, description = "Unknown cursor"), (status = 404, description = "Conversation not found"))
+)]
+async fn get_conversation_messages(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+    Query(query): Query<MessagePageQuery>,
+) -> impl IntoResponse {
+    let db = state.database.clone();
+    match db.get_conversation(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    }
+
+    let snapshot = conversation_snapshot(&state, &id).await;
+    let live = snapshot.id.as_deref() == Some(id.as_str());
+    let result = load_message_page(
+        db.as_ref(),
+        live.then_some(&snapshot),
+        &id,
+        query.before.as_deref(),
+        query.page_size(),
+    )
+    .await;
+
+    match result {
+        Ok(Some(page)) => Json(page).into_response(),
+        Ok(None) => (StatusCode::BAD_REQUEST, "Unknown cursor".to_string()).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load messages: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{id}",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id"), MessagePageQuery),
+    responses((status = 200, description = "Conversation with its newest page of messages", body = ConversationDetail), (status = 404, description = "Conversation not found"))
+)]
+async fn get_conversation(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+    Query(query): Query<MessagePageQuery>,
+) -> impl IntoResponse {
+    let db = state.database.clone();
+
+    let snapshot = conversation_snapshot(&state, &id).await;
+    let conversation = db.get_conversation(&id).await;
+
+    match conversation {
+        Ok(Some(conversation)) => {
+            let use_snapshot = snapshot
+                .id
+                .as_ref()
+                .map(|cid| cid == &conversation.id)
+                .unwrap_or(false);
+
+            let mut meta = conversation_to_meta(&conversation);
+            if use_snapshot {
+                meta.system_prompt = snapshot.system_prompt.clone();
+                meta.model = snapshot.model.clone();
+            }
+
+            // Only the newest page is returned; older pages come from /messages?before=
+            let page = match load_message_page(
+                db.as_ref(),
+                use_snapshot.then_some(&snapshot),
+                &id,
+                None,
+                query.page_size(),
+            )
+            .await
+            {
+                Ok(Some(page)) => page,
+                Ok(None) => MessagePageDto {
+                    messages: Vec::new(),
+                    next_cursor: None,
+                    has_more: false,
+                },
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to load messages: {}", e),
+                    )
+                        .into_response()
+                }
+            };
+
+            let raw_context_messages: Vec<MessageDto> =
                 match db.get_conversation_messages(&id).await {
                     Ok(msgs) => msgs
                         .into_iter()
@@ -973,7 +1937,9 @@ async fn get_conversation(
 
             Json(ConversationDetail {
                 conversation: meta,
-                messages,
+                messages: page.messages,
+                next_cursor: page.next_cursor,
+                has_more: page.has_more,
                 context_files,
             })
             .into_response()
@@ -987,44 +1953,509 @@ async fn get_conversation(
     }
 }
 
+/// Resolve the effective settings for a conversation: stored overrides, else global defaults
+async fn conversation_settings_dto(
+    state: &WebState,
+    conversation: &Conversation,
+) -> Result<ConversationSettingsDto> {
+    let settings = state
+        .database
+        .get_conversation_settings(&conversation.id)
+        .await?;
+    let (global_plan_mode, global_skills) = {
+        let agent = state.agent.lock().await;
+        (agent.plan_mode(), agent.get_active_skills().to_vec())
+    };
+
+    Ok(ConversationSettingsDto {
+        model: conversation.model.clone(),
+        temperature: settings
+            .temperature
+            .unwrap_or(state.config.read().await.temperature),
+        system_prompt: conversation.system_prompt.clone(),
+        active_skills: settings.active_skills.unwrap_or(global_skills),
+        plan_mode: settings.plan_mode.unwrap_or(global_plan_mode),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{id}/settings",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    responses((status = 200, description = "Effective settings for the conversation", body = ConversationSettingsDto), (status = 404, description = "Conversation not found"))
+)]
+async fn get_conversation_settings(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let conversation = match state.database.get_conversation(&id).await {
+        Ok(Some(conversation)) => conversation,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    };
+
+    match conversation_settings_dto(&state, &conversation).await {
+        Ok(settings) => Json(settings).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load conversation settings: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/conversations/{id}/settings",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    request_body = ConversationSettingsUpdate,
+    responses((status = 200, description = "Updated effective settings", body = ConversationSettingsDto), (status = 400, description = "Invalid settings"), (status = 404, description = "Conversation not found"), (status = 409, description = "Conversation is busy"))
+)]
+async fn update_conversation_settings(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+    Json(payload): Json<ConversationSettingsUpdate>,
+) -> impl IntoResponse {
+    let db = state.database.clone();
+    match db.get_conversation(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    }
+
+    let model = payload.model.map(|m| m.trim().to_string());
+    if model.as_deref() == Some("") {
+        return (StatusCode::BAD_REQUEST, "Model is required".to_string()).into_response();
+    }
+    if let Some(temperature) = payload.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Temperature must be between 0 and 2".to_string(),
+            )
+                .into_response();
+        }
+    }
+    if let Some(skills) = &payload.active_skills {
+        let manager = state.skill_manager.lock().await;
+        if let Some(missing) = skills.iter().find(|name| manager.get_skill(name).is_none()) {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Skill '{}' not found", missing),
+            )
+                .into_response();
+        }
+    }
+
+    // Grab the live agent before persisting anything so a busy conversation is left untouched
+    let live_agent = state
+        .conversation_agents
+        .lock()
+        .await
+        .get(&id)
+        .map(|entry| Arc::clone(&entry.agent));
+    let mut live_agent = match &live_agent {
+        Some(agent) => match agent.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(_) => {
+                return (
+                    StatusCode::CONFLICT,
+                    "Conversation is busy; try again when the response finishes".to_string(),
+                )
+                    .into_response()
+            }
+        },
+        None => None,
+    };
+
+    let result: Result<ConversationSettings> = async {
+        if let Some(model) = &model {
+            db.update_conversation_model(&id, model).await?;
+        }
+        if let Some(system_prompt) = payload.system_prompt {
+            let system_prompt = normalize_optional(system_prompt);
+            db.update_conversation_system_prompt(&id, system_prompt.as_deref())
+                .await?;
+        }
+
+        let mut settings = db.get_conversation_settings(&id).await?;
+        if payload.temperature.is_some() {
+            settings.temperature = payload.temperature;
+        }
+        if payload.plan_mode.is_some() {
+            settings.plan_mode = payload.plan_mode;
+        }
+        if payload.active_skills.is_some() {
+            settings.active_skills = payload.active_skills;
+        }
+        db.save_conversation_settings(&id, &settings).await?;
+
+        // Reload model and prompt from the database, keeping plan mode consistent around it
+        if let Some(agent) = live_agent.as_mut() {
+            let plan_mode = agent.plan_mode();
+            agent.set_plan_mode(false).await?;
+            agent.resume_conversation(&id).await?;
+            agent.set_plan_mode(plan_mode).await?;
+            apply_conversation_settings(agent, &settings).await?;
+        }
+        Ok(settings)
+    }
+    .await;
+    drop(live_agent);
+
+    if let Err(e) = result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to update conversation settings: {}", e),
+        )
+            .into_response();
+    }
+
+    let conversation = match db.get_conversation(&id).await {
+        Ok(Some(conversation)) => conversation,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    };
+    match conversation_settings_dto(&state, &conversation).await {
+        Ok(settings) => Json(settings).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load conversation settings: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{id}/context",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    responses((status = 200, description = "How the conversation uses the model's context window", body = ContextBreakdownDto), (status = 404, description = "Conversation not found"))
+)]
+async fn get_conversation_context(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.database.get_conversation(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    }
+
+    let agent = match get_or_create_conversation_agent(&state, &id).await {
+        Ok(entry) => entry.agent,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    };
+    let breakdown = agent.lock().await.context_breakdown().await;
+    Json(ContextBreakdownDto::from(&breakdown)).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/conversations/{id}/context/{message_index}",
+    tag = "conversations",
+    params(
+        ("id" = String, Path, description = "Conversation id"),
+        ("message_index" = usize, Path, description = "message_index of the context item to remove")
+    ),
+    responses((status = 200, description = "The context after removing the item", body = ContextBreakdownDto), (status = 400, description = "Not a removable context item"), (status = 404, description = "Conversation not found"), (status = 409, description = "Conversation is busy"))
+)]
+async fn remove_conversation_context_item(
+    State(state): State<WebState>,
+    Path((id, message_index)): Path<(String, usize)>,
+) -> impl IntoResponse {
+    match state.database.get_conversation(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    }
+
+    let agent = match get_or_create_conversation_agent(&state, &id).await {
+        Ok(entry) => entry.agent,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load conversation: {}", e),
+            )
+                .into_response()
+        }
+    };
+    let Ok(mut agent) = agent.try_lock() else {
+        return (
+            StatusCode::CONFLICT,
+            "Conversation is busy; try again when the response finishes".to_string(),
+        )
+            .into_response();
+    };
+    if let Err(e) = agent.remove_context_message(message_index) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    let breakdown = agent.context_breakdown().await;
+    Json(ContextBreakdownDto::from(&breakdown)).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/conversations/{id}",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    responses((status = 204, description = "Conversation deleted, cancelling any turn it was running"), (status = 404, description = "Conversation not found"))
+)]
+async fn delete_conversation(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    close_conversation_agent(&state, &id).await;
+    match state.database.delete_conversation(&id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Conversation not found".to_string()).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to delete conversation: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/conversations/{id}/agent",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    responses((status = 204, description = "The conversation's agent was dropped from memory, cancelling any turn it was running"), (status = 404, description = "The conversation has no agent in memory"))
+)]
+async fn close_conversation_agent_handler(
+    State(state): State<WebState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if close_conversation_agent(&state, &id).await {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            "Conversation has no agent in memory".to_string(),
+        )
+            .into_response()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/conversations",
+    tag = "conversations",
+    request_body = NewConversationRequest,
+    responses((status = 200, description = "Id of the new conversation", body = Object))
+)]
 async fn create_conversation(
     State(state): State<WebState>,
     Json(payload): Json<NewConversationRequest>,
 ) -> impl IntoResponse {
-    let mut agent = state.agent.lock_owned().await;
+    let template = match &payload.template {
+        Some(name) => {
+            let configured = state.config.read().await.templates.clone();
+            match templates::find_template(&configured, name) {
+                Ok(template) => Some(template),
+                Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+            }
+        }
+        None => None,
+    };
+    let prompt_given = payload.system_prompt.is_some();
+
+    let mut agent = state.agent.clone().lock_owned().await;
 
     if let Some(prompt) = payload.system_prompt {
         agent.set_system_prompt(prompt);
     }
 
     let result = agent.clear_conversation_keep_agents_md().await;
+    let id = agent.current_conversation_id();
+    drop(agent);
 
-    match result {
-        Ok(_) => match agent.current_conversation_id() {
-            Some(id) => Json(HashMap::from([("id", id)])).into_response(),
-            None => (
+    let id = match (result, id) {
+        (Ok(_), Some(id)) => id,
+        (Ok(_), None) => {
+            return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Conversation ID missing".to_string(),
             )
-                .into_response(),
-        },
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to create conversation: {}", e),
-        )
-            .into_response(),
+                .into_response()
+        }
+        (Err(e), _) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to create conversation: {}", e),
+            )
+                .into_response()
+        }
+    };
+
+    if let Some(template) = template {
+        if let Err(e) = apply_template_to_conversation(&state, &id, &template, prompt_given).await {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Failed to apply template '{}': {}", template.name, e),
+            )
+                .into_response();
+        }
+    }
+    Json(HashMap::from([("id", id)])).into_response()
+}
+
+/// Store a template's model, prompt and skills as a new conversation's
+/// settings, and add its context files to the conversation's agent
+async fn apply_template_to_conversation(
+    state: &WebState,
+    conversation_id: &str,
+    template: &templates::ConversationTemplate,
+    prompt_given: bool,
+) -> Result<()> {
+    let db = &state.database;
+    if let Some(model) = &template.config.model {
+        db.update_conversation_model(conversation_id, model).await?;
+    }
+    if let (false, Some(prompt)) = (prompt_given, &template.config.system_prompt) {
+        db.update_conversation_system_prompt(conversation_id, Some(prompt.as_str()))
+            .await?;
+    }
+    if !template.config.skills.is_empty() {
+        let manager = state.skill_manager.lock().await;
+        if let Some(missing) = template
+            .config
+            .skills
+            .iter()
+            .find(|name| manager.get_skill(name).is_none())
+        {
+            anyhow::bail!("Skill '{}' not found", missing);
+        }
+        drop(manager);
+        let mut settings = db.get_conversation_settings(conversation_id).await?;
+        settings.active_skills = Some(template.config.skills.clone());
+        db.save_conversation_settings(conversation_id, &settings)
+            .await?;
+    }
+
+    let files = template.context_files(&std::env::current_dir()?);
+    if !files.is_empty() {
+        let entry = get_or_create_conversation_agent(state, conversation_id).await?;
+        let mut agent = entry.agent.lock().await;
+        for file in files {
+            agent.add_context_file(&file.to_string_lossy()).await?;
+        }
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/templates",
+    tag = "conversations",
+    responses((status = 200, description = "Templates new conversations can start from", body = [TemplateDto]))
+)]
+async fn list_templates(State(state): State<WebState>) -> impl IntoResponse {
+    let configured = state.config.read().await.templates.clone();
+    let templates: Vec<TemplateDto> = templates::load_templates(&configured)
+        .into_iter()
+        .map(TemplateDto::from)
+        .collect();
+    Json(templates).into_response()
+}
+
+/// Render a custom command and run its `!`command``s, or describe the
+/// arguments it's missing so the web UI can ask for them and send the
+/// command again with `name="value"`
+async fn resolve_custom_command(
+    message: &str,
+    agent: &Agent,
+) -> anyhow::Result<Option<Result<custom_commands::RenderedCustomCommand, serde_json::Value>>> {
+    let Some(invocation) = custom_commands::parse_custom_command_input(message).await? else {
+        return Ok(None);
+    };
+    let missing = invocation.missing_arguments();
+    if missing.is_empty() {
+        let mut rendered = invocation.render();
+        rendered
+            .expand(
+                &agent.get_bash_security_manager(),
+                agent.yolo_mode(),
+                agent.conversation_env(),
+            )
+            .await?;
+        return Ok(Some(Ok(rendered)));
     }
+    Ok(Some(Err(serde_json::json!({
+        "error": invocation.missing_args_message(),
+        "missing_arguments": missing,
+        "argument_hint": invocation.command.argument_hint,
+    }))))
 }
 
 #[axum::debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/conversations/{id}/message",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    request_body = MessageRequest,
+    responses((status = 200, description = "Final assistant response", body = Object), (status = 400, description = "Invalid message"))
+)]
 async fn send_message_to_conversation(
     State(state): State<WebState>,
     Path(id): Path<String>,
     Json(payload): Json<MessageRequest>,
 ) -> impl IntoResponse {
     // Get or create a dedicated agent for this conversation
-    let agent_arc = match get_or_create_conversation_agent(&state, &id).await {
-        Ok(agent) => agent,
+    let (mut agent, cancellation_flag) = match begin_conversation_turn(&state, &id).await {
+        Ok(turn) => turn,
         Err(e) => {
             return (
                 StatusCode::BAD_REQUEST,
@@ -1034,14 +2465,10 @@ async fn send_message_to_conversation(
         }
     };
 
-    let mut agent = agent_arc.lock().await;
-
     let permission_handler =
         build_permission_handler(state.permission_hub.clone(), Some(id.clone()), None);
     agent.set_permission_handler(Some(permission_handler)).await;
 
-    let cancellation_flag = Arc::new(std::sync::atomic::AtomicBool::new(false));
-
     // Add images to conversation if provided
     if let Some(images) = &payload.images {
         for image in images {
@@ -1050,8 +2477,9 @@ async fn send_message_to_conversation(
     }
 
     let mut message = payload.message.clone();
-    match custom_commands::render_custom_command_input(&message).await {
-        Ok(Some(rendered)) => {
+    match resolve_custom_command(&message, &agent).await {
+        Ok(Some(Err(missing))) => return (StatusCode::BAD_REQUEST, Json(missing)).into_response(),
+        Ok(Some(Ok(rendered))) => {
             if let Some(model) = rendered.command.model {
                 if let Err(e) = agent.set_model(model).await {
                     return (
@@ -1067,7 +2495,10 @@ async fn send_message_to_conversation(
         Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
     }
 
-    match agent.process_message(&message, cancellation_flag).await {
+    let result = agent.process_message(&message, cancellation_flag).await;
+    drop(agent);
+    touch_conversation_agent(&state, &id).await;
+    match result {
         Ok(response) => Json(HashMap::from([("response", response)])).into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1077,13 +2508,16 @@ async fn send_message_to_conversation(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/models",
+    tag = "models",
+    responses((status = 200, description = "Available and active models", body = ModelListResponse))
+)]
 async fn get_models(State(state): State<WebState>) -> impl IntoResponse {
     let agent = state.agent.lock().await;
     let provider = agent.provider();
-    let models = config::provider_models(provider)
-        .iter()
-        .map(|m| m.to_string())
-        .collect::<Vec<_>>();
+    let models = config::provider_models(provider);
     Json(ModelListResponse {
         provider: provider.to_string(),
         active_model: agent.model().to_string(),
@@ -1092,6 +2526,13 @@ async fn get_models(State(state): State<WebState>) -> impl IntoResponse {
     .into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/models",
+    tag = "models",
+    request_body = ModelUpdateRequest,
+    responses((status = 200, description = "Model switched", body = Object), (status = 400, description = "Model is required"))
+)]
 async fn set_model(
     State(state): State<WebState>,
     Json(payload): Json<ModelUpdateRequest>,
@@ -1112,18 +2553,35 @@ async fn set_model(
 }
 
 #[axum::debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/conversations/{id}/message/stream",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id")),
+    request_body = MessageRequest,
+    responses((status = 200, description = "Newline-delimited JSON events of the agent turn", body = String, content_type = "application/x-ndjson"))
+)]
 async fn stream_message_to_conversation(
     State(state): State<WebState>,
     Path(id): Path<String>,
     Json(payload): Json<MessageRequest>,
 ) -> impl IntoResponse {
+    stream_agent_turn(state, id, payload.message, payload.images, None)
+}
+
+/// Run one agent turn in the background and stream its events as NDJSON.
+/// When `rewind_from` is set, the stored history is truncated at that message
+/// (inclusive) and the agent reloaded before the message is replayed.
+fn stream_agent_turn(
+    state: WebState,
+    conversation_id: String,
+    message: String,
+    images: Option<Vec<ImageData>>,
+    rewind_from: Option<String>,
+) -> Response {
     let (tx, rx) = mpsc::channel::<Result<Bytes, Infallible>>(32);
-    let cancellation_flag = Arc::new(AtomicBool::new(false));
-    let message = payload.message.clone();
-    let images = payload.images.clone();
     let permission_hub = state.permission_hub.clone();
-    let conversation_id = id.clone();
-    let state_clone = state.clone();
+    let state_clone = state;
 
     tokio::spawn(async move {
         let stream_sender = tx.clone();
@@ -1135,24 +2593,49 @@ async fn stream_message_to_conversation(
                 let _ = sender.try_send(Ok(Bytes::from(text + "\n")));
             }
         };
+        send_json(
+            &stream_sender,
+            serde_json::json!({
+                "type": "start",
+                "schema_version": STREAM_SCHEMA_VERSION,
+            }),
+        );
 
         // Get or create agent for this conversation inside the spawned task
-        let agent_arc = match get_or_create_conversation_agent(&state_clone, &conversation_id).await
-        {
-            Ok(agent) => agent,
-            Err(e) => {
+        let (mut agent, cancellation_flag) =
+            match begin_conversation_turn(&state_clone, &conversation_id).await {
+                Ok(turn) => turn,
+                Err(e) => {
+                    send_json(
+                        &stream_sender,
+                        serde_json::json!({
+                            "type": "error",
+                            "error": format!("Failed to load conversation: {}", e)
+                        }),
+                    );
+                    return;
+                }
+            };
+
+        if let Some(message_id) = rewind_from {
+            let rewind = async {
+                state_clone
+                    .database
+                    .truncate_conversation_from(&conversation_id, &message_id)
+                    .await?;
+                agent.resume_conversation(&conversation_id).await
+            };
+            if let Err(e) = rewind.await {
                 send_json(
                     &stream_sender,
                     serde_json::json!({
                         "type": "error",
-                        "error": format!("Failed to load conversation: {}", e)
+                        "error": format!("Failed to rewind conversation: {}", e)
                     }),
                 );
                 return;
             }
-        };
-
-        let mut agent = agent_arc.lock().await;
+        }
 
         // Add images to conversation if provided
         if let Some(images) = images {
@@ -1176,14 +2659,19 @@ async fn stream_message_to_conversation(
 
         let permission_handler = build_permission_handler(
             permission_hub,
-            Some(conversation_id),
+            Some(conversation_id.clone()),
             Some(stream_sender.clone()),
         );
         agent.set_permission_handler(Some(permission_handler)).await;
 
         let mut resolved_message = message.clone();
-        match custom_commands::render_custom_command_input(&message).await {
-            Ok(Some(rendered)) => {
+        match resolve_custom_command(&message, &agent).await {
+            Ok(Some(Err(mut missing))) => {
+                missing["type"] = serde_json::json!("error");
+                send_json(&stream_sender, missing);
+                return;
+            }
+            Ok(Some(Ok(rendered))) => {
                 if let Some(model) = rendered.command.model {
                     if let Err(e) = agent.set_model(model).await {
                         send_json(
@@ -1216,21 +2704,15 @@ async fn stream_message_to_conversation(
                 &resolved_message,
                 Some(on_stream),
                 Some(Arc::new(move |evt: StreamToolEvent| {
-                    send_json(
-                        &tool_sender,
-                        serde_json::json!({
-                            "type": evt.event,
-                            "tool_use_id": evt.tool_use_id,
-                            "name": evt.name,
-                            "input": evt.input,
-                            "content": evt.content,
-                            "is_error": evt.is_error,
-                        }),
-                    );
+                    if let Ok(value) = serde_json::to_value(&evt) {
+                        send_json(&tool_sender, value);
+                    }
                 })),
                 cancellation_flag.clone(),
             )
             .await;
+        drop(agent);
+        touch_conversation_agent(&state_clone, &conversation_id).await;
 
         match result {
             Ok(final_response) => send_json(
@@ -1261,6 +2743,116 @@ async fn stream_message_to_conversation(
         .unwrap()
 }
 
+/// Find the stored user message a regenerate/edit should replay from.
+/// `msg_id` may be a database id or a `snapshot-N` id from a live agent snapshot;
+/// assistant messages resolve to the user message that prompted them.
+async fn resolve_replay_anchor(
+    state: &WebState,
+    conversation_id: &str,
+    msg_id: &str,
+) -> Result<Option<crate::database::Message>> {
+    let messages = state
+        .database
+        .get_conversation_messages(conversation_id)
+        .await?;
+
+    if let Some(index) = msg_id
+        .strip_prefix("snapshot-")
+        .and_then(|idx| idx.parse::<usize>().ok())
+    {
+        let snapshot = conversation_snapshot(state, conversation_id).await;
+        if snapshot.id.as_deref() != Some(conversation_id) || index >= snapshot.messages.len() {
+            return Ok(None);
+        }
+        // Snapshot user prompts line up one-to-one with stored user messages
+        let ordinal = snapshot.messages[..=index]
+            .iter()
+            .filter(|m| is_user_prompt(m))
+            .count();
+        if ordinal == 0 {
+            return Ok(None);
+        }
+        return Ok(messages
+            .into_iter()
+            .filter(|m| m.role == "user")
+            .nth(ordinal - 1));
+    }
+
+    let position = match messages.iter().position(|m| m.id == msg_id) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+    Ok(messages[..=position]
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .cloned())
+}
+
+fn is_user_prompt(message: &crate::agent::SnapshotMessage) -> bool {
+    message.role == "user"
+        && message.content.iter().all(|b| b.block_type == "text")
+        && message
+            .content
+            .iter()
+            .any(|b| b.block_type == "text" && !is_context_block(b))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/conversations/{id}/messages/{msg_id}/regenerate",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id"), ("msg_id" = String, Path, description = "Message id")),
+    responses((status = 200, description = "Newline-delimited JSON events of the regenerated turn", body = String, content_type = "application/x-ndjson"), (status = 404, description = "Message not found"))
+)]
+async fn regenerate_message(
+    State(state): State<WebState>,
+    Path((id, msg_id)): Path<(String, String)>,
+) -> Response {
+    match resolve_replay_anchor(&state, &id, &msg_id).await {
+        Ok(Some(anchor)) => stream_agent_turn(state, id, anchor.content, None, Some(anchor.id)),
+        Ok(None) => (StatusCode::NOT_FOUND, "Message not found".to_string()).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load messages: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/conversations/{id}/messages/{msg_id}/edit",
+    tag = "conversations",
+    params(("id" = String, Path, description = "Conversation id"), ("msg_id" = String, Path, description = "Message id")),
+    request_body = EditMessageRequest,
+    responses((status = 200, description = "Newline-delimited JSON events of the edited turn", body = String, content_type = "application/x-ndjson"), (status = 400, description = "Message is required"), (status = 404, description = "Message not found"))
+)]
+async fn edit_message(
+    State(state): State<WebState>,
+    Path((id, msg_id)): Path<(String, String)>,
+    Json(payload): Json<EditMessageRequest>,
+) -> Response {
+    if payload.message.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "Message is required".to_string()).into_response();
+    }
+    match resolve_replay_anchor(&state, &id, &msg_id).await {
+        Ok(Some(anchor)) => stream_agent_turn(state, id, payload.message, None, Some(anchor.id)),
+        Ok(None) => (StatusCode::NOT_FOUND, "Message not found".to_string()).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load messages: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/plans",
+    tag = "plans",
+    responses((status = 200, description = "Saved plans", body = [PlanDto]))
+)]
 async fn list_plans(State(state): State<WebState>) -> impl IntoResponse {
     match state.database.list_plans(None).await {
         Ok(plans) => {
@@ -1285,6 +2877,13 @@ async fn list_plans(State(state): State<WebState>) -> impl IntoResponse {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/plans/{id}",
+    tag = "plans",
+    params(("id" = String, Path, description = "Plan id")),
+    responses((status = 200, description = "Plan", body = PlanDto), (status = 404, description = "Plan not found"))
+)]
 async fn get_plan(State(state): State<WebState>, Path(id): Path<String>) -> impl IntoResponse {
     match state.database.get_plan(&id).await {
         Ok(Some(plan)) => Json(PlanDto {
@@ -1305,6 +2904,14 @@ async fn get_plan(State(state): State<WebState>, Path(id): Path<String>) -> impl
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/plans/{id}",
+    tag = "plans",
+    params(("id" = String, Path, description = "Plan id")),
+    request_body = PlanUpdateRequest,
+    responses((status = 200, description = "Updated plan", body = PlanDto))
+)]
 async fn update_plan(
     State(state): State<WebState>,
     Path(id): Path<String>,
@@ -1337,6 +2944,13 @@ async fn update_plan(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/plans/{id}",
+    tag = "plans",
+    params(("id" = String, Path, description = "Plan id")),
+    responses((status = 204, description = "Plan deleted"))
+)]
 async fn delete_plan(State(state): State<WebState>, Path(id): Path<String>) -> impl IntoResponse {
     match state.database.delete_plan(&id).await {
         Ok(_) => StatusCode::NO_CONTENT.into_response(),
@@ -1348,6 +2962,13 @@ async fn delete_plan(State(state): State<WebState>, Path(id): Path<String>) -> i
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/plans",
+    tag = "plans",
+    request_body = PlanCreateRequest,
+    responses((status = 200, description = "Id of the new plan", body = Object))
+)]
 async fn create_plan(
     State(state): State<WebState>,
     Json(payload): Json<PlanCreateRequest>,
@@ -1371,6 +2992,12 @@ async fn create_plan(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/mcp/servers",
+    tag = "mcp",
+    responses((status = 200, description = "Configured MCP servers", body = [ServerDto]))
+)]
 async fn list_mcp_servers(State(state): State<WebState>) -> impl IntoResponse {
     match state.mcp_manager.list_servers().await {
         Ok(servers) => {
@@ -1392,6 +3019,13 @@ async fn list_mcp_servers(State(state): State<WebState>) -> impl IntoResponse {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/mcp/servers/{name}",
+    tag = "mcp",
+    params(("name" = String, Path, description = "Server name")),
+    responses((status = 200, description = "MCP server", body = ServerDto), (status = 404, description = "Server not found"))
+)]
 async fn get_mcp_server(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1407,6 +3041,13 @@ async fn get_mcp_server(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/mcp/servers",
+    tag = "mcp",
+    request_body = UpsertServerRequest,
+    responses((status = 200, description = "Server saved", body = Object), (status = 400, description = "Invalid server configuration"))
+)]
 async fn upsert_mcp_server(
     State(state): State<WebState>,
     Json(payload): Json<UpsertServerRequest>,
@@ -1424,6 +3065,14 @@ async fn upsert_mcp_server(
     upsert_mcp_server_inner(state, name, payload).await
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/mcp/servers/{name}",
+    tag = "mcp",
+    params(("name" = String, Path, description = "Server name")),
+    request_body = UpsertServerRequest,
+    responses((status = 200, description = "Server saved", body = Object), (status = 400, description = "Invalid server configuration"))
+)]
 async fn upsert_mcp_server_named(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1455,6 +3104,10 @@ async fn upsert_mcp_server_inner(
         env: payload.env,
         auth: payload.auth,
         enabled,
+        transport: payload.transport,
+        sampling: payload.sampling,
+        lazy: payload.lazy.unwrap_or(false),
+        aliases: payload.aliases,
     };
 
     match state.mcp_manager.upsert_server(&name, config).await {
@@ -1467,6 +3120,13 @@ async fn upsert_mcp_server_inner(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/mcp/servers/{name}",
+    tag = "mcp",
+    params(("name" = String, Path, description = "Server name")),
+    responses((status = 204, description = "Server removed"))
+)]
 async fn delete_mcp_server(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1481,6 +3141,13 @@ async fn delete_mcp_server(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/mcp/servers/{name}/connect",
+    tag = "mcp",
+    params(("name" = String, Path, description = "Server name")),
+    responses((status = 204, description = "Server connected"))
+)]
 async fn connect_mcp_server(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1495,6 +3162,13 @@ async fn connect_mcp_server(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/mcp/servers/{name}/disconnect",
+    tag = "mcp",
+    params(("name" = String, Path, description = "Server name")),
+    responses((status = 204, description = "Server disconnected"))
+)]
 async fn disconnect_mcp_server(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1509,6 +3183,12 @@ async fn disconnect_mcp_server(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/agents",
+    tag = "agents",
+    responses((status = 200, description = "Configured subagents", body = [AgentDto]))
+)]
 async fn list_agents(State(state): State<WebState>) -> impl IntoResponse {
     let active = state.agent.lock().await.active_subagent_name();
     let mut manager = state.subagent_manager.lock().await;
@@ -1540,6 +3220,13 @@ async fn list_agents(State(state): State<WebState>) -> impl IntoResponse {
     Json(list).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/agents/{name}",
+    tag = "agents",
+    params(("name" = String, Path, description = "Agent name")),
+    responses((status = 200, description = "Subagent", body = AgentDto), (status = 404, description = "Agent not found"))
+)]
 async fn get_agent(State(state): State<WebState>, Path(name): Path<String>) -> impl IntoResponse {
     let active = state.agent.lock().await.active_subagent_name();
     let manager = state.subagent_manager.lock().await;
@@ -1561,6 +3248,13 @@ async fn get_agent(State(state): State<WebState>, Path(name): Path<String>) -> i
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/agents",
+    tag = "agents",
+    request_body = NewAgentRequest,
+    responses((status = 200, description = "Agent created", body = Object))
+)]
 async fn create_agent(
     State(state): State<WebState>,
     Json(payload): Json<NewAgentRequest>,
@@ -1601,6 +3295,14 @@ async fn create_agent(
     Json(HashMap::from([("name", payload.name)])).into_response()
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/agents/{name}",
+    tag = "agents",
+    params(("name" = String, Path, description = "Agent name")),
+    request_body = AgentUpdateRequest,
+    responses((status = 200, description = "Agent updated", body = Object), (status = 404, description = "Agent not found"))
+)]
 async fn update_agent(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1634,6 +3336,13 @@ async fn update_agent(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/agents/{name}",
+    tag = "agents",
+    params(("name" = String, Path, description = "Agent name")),
+    responses((status = 204, description = "Agent deleted"))
+)]
 async fn delete_agent(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1649,11 +3358,86 @@ async fn delete_agent(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/agents/{name}/export",
+    tag = "agents",
+    params(("name" = String, Path, description = "Agent name")),
+    responses((status = 200, description = "Agent bundle", body = Object), (status = 404, description = "Agent not found"))
+)]
+async fn export_agent_bundle(
+    State(state): State<WebState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let path = {
+        let manager = state.subagent_manager.lock().await;
+        if manager.get_subagent(&name).is_none() {
+            return (StatusCode::NOT_FOUND, "Agent not found".to_string()).into_response();
+        }
+        manager.subagent_file_path(&name)
+    };
+    match crate::bundle::export_agent(&path, &name).await {
+        Ok(bundle) => bundle_response(bundle),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to export agent: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/agents/import",
+    tag = "agents",
+    request_body = BundleImportRequest,
+    responses((status = 200, description = "Agent imported", body = Object), (status = 400, description = "Import failed"))
+)]
+async fn import_agent_bundle(
+    State(state): State<WebState>,
+    Json(payload): Json<BundleImportRequest>,
+) -> impl IntoResponse {
+    let mut manager = state.subagent_manager.lock().await;
+    let root = manager.agents_dir().to_path_buf();
+    let (name, path) =
+        match install_requested_bundle(payload, crate::bundle::BundleKind::Agent, root).await {
+            Ok(installed) => installed,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to import agent: {}", e),
+                )
+                    .into_response()
+            }
+        };
+    if let Err(e) = manager.load_all_subagents().await {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to reload agents: {}", e),
+        )
+            .into_response();
+    }
+    Json(serde_json::json!({ "name": name, "path": path })).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/agents/active",
+    tag = "agents",
+    responses((status = 200, description = "Name of the active subagent, if any", body = Object))
+)]
 async fn get_active_agent(State(state): State<WebState>) -> impl IntoResponse {
     let name = state.agent.lock().await.active_subagent_name();
     Json(HashMap::from([("active", name)])).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/agents/active",
+    tag = "agents",
+    request_body = ActivateAgentRequest,
+    responses((status = 200, description = "Active subagent changed", body = Object), (status = 404, description = "Agent not found"))
+)]
 async fn set_active_agent(
     State(state): State<WebState>,
     Json(payload): Json<ActivateAgentRequest>,
@@ -1709,6 +3493,12 @@ async fn set_active_agent(
 }
 
 // Skill API handlers
+#[utoipa::path(
+    get,
+    path = "/api/skills",
+    tag = "skills",
+    responses((status = 200, description = "Installed skills", body = [SkillDto]))
+)]
 async fn list_skills(State(state): State<WebState>) -> impl IntoResponse {
     let manager = state.skill_manager.lock().await;
     let skills = manager.list_skills();
@@ -1737,6 +3527,13 @@ async fn list_skills(State(state): State<WebState>) -> impl IntoResponse {
     Json(dtos).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/skills/{name}",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, description = "Skill", body = SkillDto), (status = 404, description = "Skill not found"))
+)]
 async fn get_skill(State(state): State<WebState>, Path(name): Path<String>) -> impl IntoResponse {
     let manager = state.skill_manager.lock().await;
     let agent = state.agent.lock().await;
@@ -1763,6 +3560,13 @@ async fn get_skill(State(state): State<WebState>, Path(name): Path<String>) -> i
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/skills",
+    tag = "skills",
+    request_body = NewSkillRequest,
+    responses((status = 200, description = "Skill created", body = Object))
+)]
 async fn create_skill(
     State(state): State<WebState>,
     Json(payload): Json<NewSkillRequest>,
@@ -1794,6 +3598,14 @@ async fn create_skill(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/skills/{name}",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    request_body = SkillUpdateRequest,
+    responses((status = 200, description = "Skill updated", body = Object), (status = 404, description = "Skill not found"))
+)]
 async fn update_skill(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1831,6 +3643,13 @@ async fn update_skill(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/skills/{name}",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 204, description = "Skill deleted"))
+)]
 async fn delete_skill(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1847,6 +3666,112 @@ async fn delete_skill(
     }
 }
 
+/// A bundle as a JSON download
+fn bundle_response(bundle: crate::bundle::Bundle) -> Response {
+    (
+        [(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", bundle.file_name()),
+        )],
+        Json(bundle),
+    )
+        .into_response()
+}
+
+/// Read the bundle an import request names and install it into `root`, or
+/// the project's directory when the request asks for it
+async fn install_requested_bundle(
+    payload: BundleImportRequest,
+    kind: crate::bundle::BundleKind,
+    root: std::path::PathBuf,
+) -> Result<(String, std::path::PathBuf)> {
+    let bundle = match (payload.bundle, payload.source) {
+        (Some(bundle), _) if bundle.kind == kind => bundle,
+        (Some(bundle), _) => {
+            return Err(anyhow::anyhow!(
+                "Expected a {} bundle, got a {} bundle",
+                kind.label(),
+                bundle.kind.label()
+            ))
+        }
+        (None, Some(source)) => crate::bundle::read_source(&source, kind).await?,
+        (None, None) => return Err(anyhow::anyhow!("Either source or bundle is required")),
+    };
+    let root = if payload.project {
+        crate::bundle::project_dir(kind)?
+    } else {
+        root
+    };
+    let path = crate::bundle::install(&bundle, &root, payload.force).await?;
+    Ok((bundle.name, path))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/skills/{name}/export",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, description = "Skill bundle", body = Object), (status = 404, description = "Skill not found"))
+)]
+async fn export_skill_bundle(
+    State(state): State<WebState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let path = state.skill_manager.lock().await.skill_path(&name);
+    let Some(path) = path else {
+        return (StatusCode::NOT_FOUND, "Skill not found".to_string()).into_response();
+    };
+    match crate::bundle::export_skill(&path, &name).await {
+        Ok(bundle) => bundle_response(bundle),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to export skill: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/skills/import",
+    tag = "skills",
+    request_body = BundleImportRequest,
+    responses((status = 200, description = "Skill imported", body = Object), (status = 400, description = "Import failed"))
+)]
+async fn import_skill_bundle(
+    State(state): State<WebState>,
+    Json(payload): Json<BundleImportRequest>,
+) -> impl IntoResponse {
+    let mut manager = state.skill_manager.lock().await;
+    let root = manager.skills_dir().to_path_buf();
+    let (name, path) =
+        match install_requested_bundle(payload, crate::bundle::BundleKind::Skill, root).await {
+            Ok(installed) => installed,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to import skill: {}", e),
+                )
+                    .into_response()
+            }
+        };
+    if let Err(e) = manager.load_all_skills().await {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to reload skills: {}", e),
+        )
+            .into_response();
+    }
+    Json(serde_json::json!({ "name": name, "path": path })).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/skills/{name}/activate",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, description = "Skill activated"))
+)]
 async fn activate_skill(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1863,6 +3788,13 @@ async fn activate_skill(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/skills/{name}/deactivate",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, description = "Skill deactivated"))
+)]
 async fn deactivate_skill(
     State(state): State<WebState>,
     Path(name): Path<String>,
@@ -1879,6 +3811,12 @@ async fn deactivate_skill(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/skills/active",
+    tag = "skills",
+    responses((status = 200, description = "Names of active skills", body = [String]))
+)]
 async fn get_active_skills(State(state): State<WebState>) -> impl IntoResponse {
     let agent = state.agent.lock().await;
     let active_skills = agent.get_active_skills();
@@ -1895,6 +3833,12 @@ fn normalize_optional(value: String) -> Option<String> {
 }
 
 // Custom command API handlers
+#[utoipa::path(
+    get,
+    path = "/api/commands",
+    tag = "commands",
+    responses((status = 200, description = "Custom slash commands", body = [CustomCommandDto]))
+)]
 async fn list_custom_commands(State(_state): State<WebState>) -> impl IntoResponse {
     match custom_commands::list_custom_commands().await {
         Ok(commands) => {
@@ -1919,6 +3863,13 @@ async fn list_custom_commands(State(_state): State<WebState>) -> impl IntoRespon
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/commands/{name}",
+    tag = "commands",
+    params(("name" = String, Path, description = "Command name")),
+    responses((status = 200, description = "Custom command", body = CustomCommandDto), (status = 404, description = "Command not found"))
+)]
 async fn get_custom_command(
     State(_state): State<WebState>,
     Path(name): Path<String>,
@@ -1942,6 +3893,13 @@ async fn get_custom_command(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/commands",
+    tag = "commands",
+    request_body = NewCustomCommandRequest,
+    responses((status = 200, description = "Command created", body = Object))
+)]
 async fn create_custom_command(
     State(_state): State<WebState>,
     Json(payload): Json<NewCustomCommandRequest>,
@@ -1973,6 +3931,14 @@ async fn create_custom_command(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/commands/{name}",
+    tag = "commands",
+    params(("name" = String, Path, description = "Command name")),
+    request_body = CustomCommandUpdateRequest,
+    responses((status = 200, description = "Command updated", body = Object), (status = 404, description = "Command not found"))
+)]
 async fn update_custom_command(
     State(_state): State<WebState>,
     Path(name): Path<String>,
@@ -2011,6 +3977,13 @@ async fn update_custom_command(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/commands/{name}",
+    tag = "commands",
+    params(("name" = String, Path, description = "Command name")),
+    responses((status = 204, description = "Command deleted"))
+)]
 async fn delete_custom_command(
     State(_state): State<WebState>,
     Path(name): Path<String>,
@@ -2025,6 +3998,13 @@ async fn delete_custom_command(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/permissions/pending",
+    tag = "permissions",
+    params(PermissionPendingQuery),
+    responses((status = 200, description = "Permission prompts awaiting an answer", body = [PermissionRequestDto]))
+)]
 async fn list_pending_permissions(
     State(state): State<WebState>,
     axum::extract::Query(query): axum::extract::Query<PermissionPendingQuery>,
@@ -2036,6 +4016,13 @@ async fn list_pending_permissions(
     Json(pending).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/permissions/respond",
+    tag = "permissions",
+    request_body = PermissionResolveRequest,
+    responses((status = 204, description = "Permission prompt answered"), (status = 404, description = "Permission request not found"))
+)]
 async fn resolve_permission_request(
     State(state): State<WebState>,
     Json(payload): Json<PermissionResolveRequest>,
@@ -2058,6 +4045,12 @@ async fn resolve_permission_request(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/plan-mode",
+    tag = "plan-mode",
+    responses((status = 200, description = "Whether plan mode is enabled", body = PlanModeResponse))
+)]
 async fn get_plan_mode(State(state): State<WebState>) -> impl IntoResponse {
     let agent = state.agent.lock().await;
     Json(PlanModeResponse {
@@ -2066,6 +4059,13 @@ async fn get_plan_mode(State(state): State<WebState>) -> impl IntoResponse {
     .into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/plan-mode",
+    tag = "plan-mode",
+    request_body = PlanModeRequest,
+    responses((status = 200, description = "Plan mode updated", body = PlanModeResponse))
+)]
 async fn set_plan_mode(
     State(state): State<WebState>,
     Json(payload): Json<PlanModeRequest>,
@@ -2084,11 +4084,19 @@ async fn set_plan_mode(
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct TodoQuery {
     conversation_id: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/todos",
+    tag = "todos",
+    params(TodoQuery),
+    responses((status = 200, description = "Todo items for the conversation", body = Object))
+)]
 async fn list_todos(
     State(state): State<WebState>,
     Query(query): Query<TodoQuery>,
@@ -2099,6 +4107,12 @@ async fn list_todos(
 }
 
 // Stats API handlers
+#[utoipa::path(
+    get,
+    path = "/api/stats/overview",
+    tag = "stats",
+    responses((status = 200, description = "Aggregate usage totals", body = Object))
+)]
 async fn get_stats_overview(State(state): State<WebState>) -> impl IntoResponse {
     db_result_to_response(
         state.database.get_stats_overview().await,
@@ -2107,6 +4121,13 @@ async fn get_stats_overview(State(state): State<WebState>) -> impl IntoResponse
     )
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/stats/usage",
+    tag = "stats",
+    params(StatsQueryParams),
+    responses((status = 200, description = "Token usage per day", body = UsageStatsResponse))
+)]
 async fn get_usage_stats(
     State(state): State<WebState>,
     axum::extract::Query(params): axum::extract::Query<StatsQueryParams>,
@@ -2139,6 +4160,13 @@ async fn get_usage_stats(
     )
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/stats/models",
+    tag = "stats",
+    params(StatsQueryParams),
+    responses((status = 200, description = "Usage per model", body = ModelStatsResponse))
+)]
 async fn get_model_stats(
     State(state): State<WebState>,
     axum::extract::Query(params): axum::extract::Query<StatsQueryParams>,
@@ -2171,6 +4199,13 @@ async fn get_model_stats(
     )
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/stats/conversations",
+    tag = "stats",
+    params(StatsQueryParams),
+    responses((status = 200, description = "Conversations per day", body = ConversationStatsResponse))
+)]
 async fn get_conversation_stats(
     State(state): State<WebState>,
     axum::extract::Query(params): axum::extract::Query<StatsQueryParams>,
@@ -2197,6 +4232,13 @@ async fn get_conversation_stats(
     )
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/stats/conversations-by-provider",
+    tag = "stats",
+    params(StatsQueryParams),
+    responses((status = 200, description = "Conversations per day and provider", body = ConversationsByProviderResponse))
+)]
 async fn get_conversation_stats_by_provider(
     State(state): State<WebState>,
     axum::extract::Query(params): axum::extract::Query<StatsQueryParams>,
@@ -2235,6 +4277,13 @@ async fn get_conversation_stats_by_provider(
     )
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/stats/conversations-by-subagent",
+    tag = "stats",
+    params(StatsQueryParams),
+    responses((status = 200, description = "Conversations per day and subagent", body = ConversationsBySubagentResponse))
+)]
 async fn get_conversation_stats_by_subagent(
     State(state): State<WebState>,
     axum::extract::Query(params): axum::extract::Query<StatsQueryParams>,
@@ -2264,9 +4313,132 @@ async fn get_conversation_stats_by_subagent(
     )
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/stats/projects",
+    tag = "stats",
+    params(StatsQueryParams),
+    responses((status = 200, description = "Usage, estimated cost and tool activity per project directory", body = ProjectStatsResponse))
+)]
+async fn get_project_stats(
+    State(state): State<WebState>,
+    axum::extract::Query(params): axum::extract::Query<StatsQueryParams>,
+) -> impl IntoResponse {
+    let (start_date, end_date) = calculate_date_range(&params);
+    let period = params.period.unwrap_or_else(|| "month".to_string());
+
+    let own_path = state.database.db_path().to_path_buf();
+    let mut rows = match state
+        .database
+        .get_stats_by_project(start_date, end_date)
+        .await
+    {
+        Ok(rows) => vec![(own_path.clone(), rows)],
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load project stats: {}", e),
+            )
+                .into_response()
+        }
+    };
+    // Each directory has its own database next to this one
+    if let Some(dir) = own_path.parent() {
+        for path in crate::database::database_files(dir) {
+            if path == own_path {
+                continue;
+            }
+            match crate::database::project_stats_at(&path, start_date, end_date).await {
+                Ok(stats) => rows.push((path, stats)),
+                Err(e) => debug!("Skipping {} for project stats: {}", path.display(), e),
+            }
+        }
+    }
+
+    Json(ProjectStatsResponse {
+        period,
+        data: aggregate_project_stats(rows),
+    })
+    .into_response()
+}
+
+/// Combine per-model rows into one entry per directory, most expensive
+/// first. Conversations from before directories were recorded are grouped
+/// under their database's name.
+fn aggregate_project_stats(
+    databases: Vec<(std::path::PathBuf, Vec<crate::database::ProjectModelStats>)>,
+) -> Vec<ProjectStatsPoint> {
+    let mut projects: BTreeMap<String, ProjectStatsPoint> = BTreeMap::new();
+    for (db_path, rows) in databases {
+        let db_name = db_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for row in rows {
+            let key = row
+                .workspace_path
+                .clone()
+                .unwrap_or_else(|| db_name.clone());
+            let entry = projects.entry(key).or_insert_with(|| ProjectStatsPoint {
+                project: row
+                    .workspace_path
+                    .as_deref()
+                    .and_then(|path| std::path::Path::new(path).file_name())
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| db_name.clone()),
+                path: row.workspace_path.clone(),
+                ..Default::default()
+            });
+            entry.total_conversations += row.total_conversations;
+            entry.total_tokens += row.total_tokens;
+            entry.input_tokens += row.input_tokens;
+            entry.output_tokens += row.output_tokens;
+            entry.request_count += row.request_count;
+            entry.tool_calls += row.tool_calls;
+            entry.tool_errors += row.tool_errors;
+            match crate::pricing::estimate_cost(
+                provider_for_model_stats(&row.model),
+                &row.model,
+                row.input_tokens.clamp(0, u32::MAX as i64) as u32,
+                row.output_tokens.clamp(0, u32::MAX as i64) as u32,
+            ) {
+                Some(cost) => entry.cost_usd += cost,
+                None => entry.unpriced_tokens += row.input_tokens + row.output_tokens,
+            }
+            if !entry.models.contains(&row.model) {
+                entry.models.push(row.model);
+            }
+        }
+    }
+
+    let mut data: Vec<ProjectStatsPoint> = projects.into_values().collect();
+    data.sort_by(|a, b| {
+        b.cost_usd
+            .total_cmp(&a.cost_usd)
+            .then(b.total_tokens.cmp(&a.total_tokens))
+    });
+    data
+}
+
+/// The provider offering `model`, for pricing; conversations don't record it
+fn provider_for_model_stats(model: &str) -> config::Provider {
+    crate::providers::all()
+        .into_iter()
+        .find(|provider| {
+            crate::providers::spec(*provider)
+                .models
+                .iter()
+                .any(|known| known == model)
+        })
+        .unwrap_or_default()
+}
+
 fn extract_provider_from_model(model: &str) -> String {
     let lower = model.to_lowercase();
-    if lower.contains("claude") {
+    // OpenRouter names its models "vendor/model"
+    if lower.contains('/') {
+        "OpenRouter".to_string()
+    } else if lower.contains("claude") {
         "Anthropic".to_string()
     } else if lower.contains("gpt") {
         "OpenAI".to_string()
@@ -2276,6 +4448,8 @@ fn extract_provider_from_model(model: &str) -> String {
         "Mistral".to_string()
     } else if lower.contains("glm") {
         "Z.AI".to_string()
+    } else if lower.contains("deepseek") {
+        "DeepSeek".to_string()
     } else if lower.contains("llama") || lower.contains("gemma") {
         "Ollama".to_string()
     } else {
@@ -2283,6 +4457,13 @@ fn extract_provider_from_model(model: &str) -> String {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/file-autocomplete",
+    tag = "files",
+    params(FileAutocompleteQuery),
+    responses((status = 200, description = "Files and directories matching the prefix", body = FileAutocompleteResponse))
+)]
 async fn get_file_autocomplete(
     axum::extract::Query(params): axum::extract::Query<FileAutocompleteQuery>,
 ) -> impl IntoResponse {
@@ -2296,6 +4477,28 @@ async fn get_file_autocomplete(
         }
     };
 
+    // Absolute prefixes complete inside directories added with --add-dir,
+    // and those results are absolute too
+    let added_root = crate::tools::path::additional_roots()
+        .iter()
+        .find(|dir| Path::new(prefix).starts_with(dir));
+    let (root, prefix, absolute) = match added_root {
+        Some(dir) => {
+            let relative = Path::new(prefix)
+                .strip_prefix(dir)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let relative = if prefix.ends_with('/') && !relative.is_empty() {
+                format!("{}/", relative)
+            } else {
+                relative
+            };
+            (dir.clone(), relative, true)
+        }
+        None => (root, prefix.to_string(), false),
+    };
+    let prefix = prefix.as_str();
+
     fn resolve_search_dir(root: &Path, dir_part: &str) -> Option<PathBuf> {
         if dir_part.is_empty() || dir_part == "." {
             return Some(root.to_path_buf());
@@ -2344,21 +4547,33 @@ async fn get_file_autocomplete(
         }
     };
 
+    // Offer the added directories themselves as their paths are typed
+    let mut files: Vec<FileAutocompleteItem> = Vec::new();
+    if !absolute && prefix.starts_with('/') {
+        for dir in crate::tools::path::additional_roots() {
+            if dir.to_string_lossy().starts_with(prefix) {
+                files.push(FileAutocompleteItem {
+                    path: dir.to_string_lossy().to_string(),
+                    is_directory: true,
+                });
+            }
+        }
+    }
+
     let search_dir_path = match resolve_search_dir(&root, search_dir) {
         Some(path) => path,
         None => {
-            return Json(FileAutocompleteResponse { files: Vec::new() }).into_response();
+            return Json(FileAutocompleteResponse { files }).into_response();
         }
     };
 
     let entries = match std::fs::read_dir(&search_dir_path) {
         Ok(entries) => entries,
         Err(_) => {
-            return Json(FileAutocompleteResponse { files: Vec::new() }).into_response();
+            return Json(FileAutocompleteResponse { files }).into_response();
         }
     };
 
-    let mut files: Vec<FileAutocompleteItem> = Vec::new();
     let filter_lower = filter_prefix.to_lowercase();
 
     for entry in entries {
@@ -2368,6 +4583,7 @@ async fn get_file_autocomplete(
 
             if filter_prefix.is_empty() || filename.to_lowercase().starts_with(&filter_lower) {
                 let relative_path = match path.strip_prefix(&root) {
+                    Ok(_) if absolute => path.as_path(),
                     Ok(rel_path) => rel_path,
                     Err(_) => continue,
                 };
@@ -2448,6 +4664,7 @@ fn block_to_dto(block: &ContentBlock) -> ContentBlockDto {
             media_type: s.media_type.clone(),
             data: s.data.clone(),
         }),
+        segments: block.text.as_deref().and_then(math_segments),
     }
 }
 
@@ -2714,6 +4931,10 @@ mod tests {
             "Mistral"
         );
         assert_eq!(extract_provider_from_model("glm-4.6"), "Z.AI");
+        assert_eq!(
+            extract_provider_from_model("anthropic/claude-sonnet-4.5"),
+            "OpenRouter"
+        );
         assert_eq!(extract_provider_from_model("local-model"), "Other");
     }
 
@@ -2884,18 +5105,55 @@ mod tests {
             skill_manager,
             conversation_agents: Arc::new(Mutex::new(HashMap::new())),
             csrf_manager: Arc::new(CsrfManager::new()),
-            config: Arc::new(config),
+            config: Arc::new(RwLock::new(config)),
+            started_at: Instant::now(),
         }
     }
 
     fn build_test_router(state: WebState) -> Router {
         Router::new()
             .route("/api/health", get(health))
+            .route("/api/openapi.json", get(get_openapi_spec))
             .route("/api/models", get(get_models).post(set_model))
             .route("/api/plan-mode", get(get_plan_mode).post(set_plan_mode))
-            .route("/api/conversations", get(list_conversations))
+            .route(
+                "/api/conversations",
+                get(list_conversations).post(create_conversation),
+            )
             .route("/api/conversations/search", get(search_conversations))
-            .route("/api/conversations/:id", get(get_conversation))
+            .route(
+                "/api/conversations/:id",
+                get(get_conversation).delete(delete_conversation),
+            )
+            .route("/api/templates", get(list_templates))
+            .route(
+                "/api/conversations/:id/agent",
+                delete(close_conversation_agent_handler),
+            )
+            .route(
+                "/api/conversations/:id/messages",
+                get(get_conversation_messages),
+            )
+            .route(
+                "/api/conversations/:id/settings",
+                get(get_conversation_settings).put(update_conversation_settings),
+            )
+            .route(
+                "/api/conversations/:id/context",
+                get(get_conversation_context),
+            )
+            .route(
+                "/api/conversations/:id/context/:message_index",
+                delete(remove_conversation_context_item),
+            )
+            .route(
+                "/api/conversations/:id/messages/:msg_id/regenerate",
+                post(regenerate_message),
+            )
+            .route(
+                "/api/conversations/:id/messages/:msg_id/edit",
+                post(edit_message),
+            )
             .route("/api/plans", get(list_plans).post(create_plan))
             .route("/api/permissions/pending", get(list_pending_permissions))
             .route("/api/permissions/respond", post(resolve_permission_request))
@@ -2915,7 +5173,11 @@ mod tests {
                 get(get_agent).put(update_agent).delete(delete_agent),
             )
             .route("/api/agents/active", get(get_active_agent))
+            .route("/api/agents/import", post(import_agent_bundle))
+            .route("/api/agents/:name/export", get(export_agent_bundle))
             .route("/api/skills", get(list_skills).post(create_skill))
+            .route("/api/skills/import", post(import_skill_bundle))
+            .route("/api/skills/:name/export", get(export_skill_bundle))
             .route(
                 "/api/skills/:name",
                 get(get_skill).put(update_skill).delete(delete_skill),
@@ -2959,6 +5221,34 @@ mod tests {
         let (status, body) = json_response(&router, request).await;
         assert_eq!(status, StatusCode::OK);
         assert_eq!(body["status"], "ok");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["database"]["reachable"], true);
+        assert!(body["database"]["size_bytes"].as_i64().unwrap() > 0);
+        assert_eq!(body["active_conversation_agents"], 0);
+        assert!(body["mcp_servers"].is_array());
+        assert!(body["provider"]["reachable"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_unreachable_provider() {
+        let state = build_test_state().await;
+        let mut config = state.config.read().await.clone();
+        config.base_url = "http://127.0.0.1:1".to_string();
+        let state = WebState {
+            config: Arc::new(RwLock::new(config)),
+            ..state
+        };
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/health?ping=true")
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["provider"]["reachable"], false);
+        assert!(body["provider"]["error"].is_string());
     }
 
     #[tokio::test]
@@ -2970,12 +5260,72 @@ mod tests {
             .method("GET")
             .body(Body::empty())
             .expect("build request");
-        let (status, body) = json_response(&router, request).await;
-        assert_eq!(status, StatusCode::OK);
-        let active_model = body["active_model"].as_str().expect("active model");
-        let models = body["models"].as_array().expect("models array");
-        assert!(models.iter().any(|m| m.as_str() == Some(active_model)));
-        assert!(body["provider"].as_str().is_some());
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let active_model = body["active_model"].as_str().expect("active model");
+        let models = body["models"].as_array().expect("models array");
+        assert!(models.iter().any(|m| m.as_str() == Some(active_model)));
+        assert!(body["provider"].as_str().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_create_conversation_from_template() {
+        let state = build_test_state().await;
+        state.config.write().await.templates.insert(
+            "test-review".to_string(),
+            templates::TemplateConfig {
+                description: Some("Review changes".to_string()),
+                system_prompt: Some("You review code.".to_string()),
+                model: Some("template-model".to_string()),
+                ..Default::default()
+            },
+        );
+        let router = build_test_router(state.clone());
+
+        let request = axum::http::Request::builder()
+            .uri("/api/templates")
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let template = body
+            .as_array()
+            .expect("templates array")
+            .iter()
+            .find(|t| t["name"] == "test-review")
+            .expect("configured template listed");
+        assert_eq!(template["description"], "Review changes");
+
+        let request = axum::http::Request::builder()
+            .uri("/api/conversations")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"template":"test-review"}"#))
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = body["id"].as_str().expect("conversation id");
+        let conversation = state
+            .database
+            .get_conversation(id)
+            .await
+            .expect("load conversation")
+            .expect("conversation exists");
+        assert_eq!(conversation.model, "template-model");
+        assert_eq!(
+            conversation.system_prompt.as_deref(),
+            Some("You review code.")
+        );
+
+        let request = axum::http::Request::builder()
+            .uri("/api/conversations")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"template":"missing"}"#))
+            .expect("build request");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
@@ -3114,6 +5464,84 @@ mod tests {
         assert_eq!(response.status(), StatusCode::NO_CONTENT);
     }
 
+    #[tokio::test]
+    async fn test_agent_bundle_export_and_import() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+
+        let payload = serde_json::json!({
+            "name": "bundled",
+            "system_prompt": "Review carefully",
+            "allowed_tools": [],
+            "denied_tools": ["Bash"],
+            "max_tokens": null,
+            "temperature": null,
+            "model": null
+        });
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/agents")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+        let (status, _) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/api/agents/bundled/export")
+            .body(Body::empty())
+            .unwrap();
+        let (status, bundle) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(bundle["kind"], "agent");
+        assert!(bundle["files"]["bundled.md"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("Review carefully"));
+
+        // The agent is still installed, so importing needs force
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/agents/import")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "bundle": bundle }).to_string(),
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.expect("import");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = axum::http::Request::builder()
+            .method("DELETE")
+            .uri("/api/agents/bundled")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.expect("delete");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/agents/import")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "bundle": bundle }).to_string(),
+            ))
+            .unwrap();
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["name"], "bundled");
+
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/api/agents/bundled")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["denied_tools"][0], "Bash");
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_custom_commands_crud() {
@@ -3298,161 +5726,523 @@ mod tests {
             .await
             .expect("get messages");
         assert_eq!(messages.len(), 1);
-        assert_eq!(messages[0].role, "assistant");
+        assert_eq!(messages[0].role, "assistant");
+    }
+
+    #[tokio::test]
+    async fn test_ensure_default_conversation_noop_when_existing() {
+        let state = build_test_state().await;
+        let conversation_id = state
+            .database
+            .create_conversation(None, "test-model", None)
+            .await
+            .expect("create conversation");
+        state
+            .database
+            .add_message(&conversation_id, "user", "Hello", "test-model", 1)
+            .await
+            .expect("add message");
+
+        let created = ensure_default_conversation(&state)
+            .await
+            .expect("ensure default conversation");
+        assert!(created.is_none());
+
+        let conversations = state
+            .database
+            .get_recent_conversations(10, None)
+            .await
+            .expect("list conversations");
+        assert_eq!(conversations.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_conversation_not_found() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/conversations/missing")
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_replay_anchor_uses_prompting_user_message() {
+        let state = build_test_state().await;
+        let conversation_id = state
+            .database
+            .create_conversation(None, "test-model", None)
+            .await
+            .expect("create conversation");
+        let user_id = state
+            .database
+            .add_message(&conversation_id, "user", "Question", "test-model", 1)
+            .await
+            .expect("add user message");
+        let assistant_id = state
+            .database
+            .add_message(&conversation_id, "assistant", "Answer", "test-model", 1)
+            .await
+            .expect("add assistant message");
+
+        let anchor = resolve_replay_anchor(&state, &conversation_id, &assistant_id)
+            .await
+            .expect("resolve anchor")
+            .expect("anchor exists");
+        assert_eq!(anchor.id, user_id);
+        assert_eq!(anchor.content, "Question");
+
+        let missing = resolve_replay_anchor(&state, &conversation_id, "unknown")
+            .await
+            .expect("resolve anchor");
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_and_edit_unknown_message() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/conversations/missing/messages/missing/regenerate")
+            .method("POST")
+            .body(Body::empty())
+            .expect("build request");
+        let response = router.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = axum::http::Request::builder()
+            .uri("/api/conversations/missing/messages/missing/edit")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"message":"   "}"#))
+            .expect("build request");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_create_plan_and_list_plans() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/plans")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                r#"{"title":"Plan A","user_request":"Do work","plan_markdown":"Step 1"}"#,
+            ))
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["id"].as_str().is_some());
+
+        let request = axum::http::Request::builder()
+            .uri("/api/plans")
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let plans = body.as_array().expect("plans array");
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0]["title"], "Plan A");
+    }
+
+    #[tokio::test]
+    async fn test_permissions_pending_empty() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/permissions/pending")
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let pending = body.as_array().expect("pending array");
+        assert!(pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_permissions_respond_requires_id() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/permissions/respond")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"id":""}"#))
+            .expect("build request");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_set_model_endpoint() {
+        let state = build_test_state().await;
+        let router = build_test_router(state.clone());
+        let request = axum::http::Request::builder()
+            .uri("/api/models")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"model":"glm-4.6"}"#))
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["model"], "glm-4.6");
+    }
+
+    #[tokio::test]
+    async fn test_set_model_rejects_empty() {
+        let state = build_test_state().await;
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/models")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"model":"  "}"#))
+            .expect("build request");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_conversation_success() {
+        let state = build_test_state().await;
+        let conversation_id = state
+            .database
+            .create_conversation(Some("Test prompt".to_string()), "test-model", None)
+            .await
+            .expect("create conversation");
+        state
+            .database
+            .add_message(&conversation_id, "user", "Hello", "test-model", 1)
+            .await
+            .expect("add message");
+
+        let router = build_test_router(state);
+        let request = axum::http::Request::builder()
+            .uri(&format!("/api/conversations/{}", conversation_id))
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["conversation"]["id"], conversation_id);
+        assert_eq!(body["conversation"]["system_prompt"], "Test prompt");
+        let messages = body["messages"].as_array().expect("messages array");
+        assert_eq!(messages.len(), 1);
     }
 
     #[tokio::test]
-    async fn test_ensure_default_conversation_noop_when_existing() {
+    async fn test_get_conversation_messages_paginates() {
         let state = build_test_state().await;
         let conversation_id = state
             .database
             .create_conversation(None, "test-model", None)
             .await
             .expect("create conversation");
-        state
-            .database
-            .add_message(&conversation_id, "user", "Hello", "test-model", 1)
-            .await
-            .expect("add message");
-
-        let created = ensure_default_conversation(&state)
-            .await
-            .expect("ensure default conversation");
-        assert!(created.is_none());
-
-        let conversations = state
-            .database
-            .get_recent_conversations(10, None)
-            .await
-            .expect("list conversations");
-        assert_eq!(conversations.len(), 1);
-    }
+        for i in 0..3 {
+            state
+                .database
+                .add_message(
+                    &conversation_id,
+                    "user",
+                    &format!("Hello {}", i),
+                    "test-model",
+                    1,
+                )
+                .await
+                .expect("add message");
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
 
-    #[tokio::test]
-    async fn test_get_conversation_not_found() {
-        let state = build_test_state().await;
         let router = build_test_router(state);
         let request = axum::http::Request::builder()
-            .uri("/api/conversations/missing")
+            .uri(format!("/api/conversations/{}?limit=2", conversation_id))
             .method("GET")
             .body(Body::empty())
             .expect("build request");
-        let response = router.oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-    }
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["has_more"], true);
+        let messages = body["messages"].as_array().expect("messages array");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["content"], "Hello 1");
+        let cursor = body["next_cursor"].as_str().expect("cursor").to_string();
 
-    #[tokio::test]
-    async fn test_create_plan_and_list_plans() {
-        let state = build_test_state().await;
-        let router = build_test_router(state);
         let request = axum::http::Request::builder()
-            .uri("/api/plans")
-            .method("POST")
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(Body::from(
-                r#"{"title":"Plan A","user_request":"Do work","plan_markdown":"Step 1"}"#,
+            .uri(format!(
+                "/api/conversations/{}/messages?before={}&limit=2",
+                conversation_id, cursor
             ))
+            .method("GET")
+            .body(Body::empty())
             .expect("build request");
         let (status, body) = json_response(&router, request).await;
         assert_eq!(status, StatusCode::OK);
-        assert!(body["id"].as_str().is_some());
+        assert_eq!(body["has_more"], false);
+        assert!(body["next_cursor"].is_null());
+        let messages = body["messages"].as_array().expect("messages array");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["content"], "Hello 0");
 
         let request = axum::http::Request::builder()
-            .uri("/api/plans")
+            .uri(format!(
+                "/api/conversations/{}/messages?before=missing",
+                conversation_id
+            ))
             .method("GET")
             .body(Body::empty())
             .expect("build request");
-        let (status, body) = json_response(&router, request).await;
-        assert_eq!(status, StatusCode::OK);
-        let plans = body.as_array().expect("plans array");
-        assert_eq!(plans.len(), 1);
-        assert_eq!(plans[0]["title"], "Plan A");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn test_permissions_pending_empty() {
+    async fn test_openapi_spec_lists_api_routes() {
         let state = build_test_state().await;
         let router = build_test_router(state);
         let request = axum::http::Request::builder()
-            .uri("/api/permissions/pending")
+            .uri("/api/openapi.json")
             .method("GET")
             .body(Body::empty())
             .expect("build request");
         let (status, body) = json_response(&router, request).await;
         assert_eq!(status, StatusCode::OK);
-        let pending = body.as_array().expect("pending array");
-        assert!(pending.is_empty());
+        assert_eq!(body["info"]["title"], "Flexorama API");
+
+        let paths = body["paths"].as_object().expect("paths object");
+        assert!(paths.keys().all(|p| p.starts_with("/api/")));
+        assert!(paths["/api/conversations"]["get"].is_object());
+        assert!(paths["/api/conversations"]["post"].is_object());
+        assert!(paths["/api/conversations/{id}/messages"]["get"]["parameters"].is_array());
+        assert!(paths["/api/plans/{id}"]["delete"].is_object());
+        assert!(
+            paths["/api/conversations/{id}/message/stream"]["post"]["responses"]["200"]["content"]
+                ["application/x-ndjson"]
+                .is_object()
+        );
+        assert!(body["components"]["schemas"]["MessageDto"].is_object());
     }
 
     #[tokio::test]
-    async fn test_permissions_respond_requires_id() {
+    async fn test_rate_limit_rejects_bursts_per_ip() {
+        let web_config = config::WebConfig {
+            rate_limit_per_second: 1,
+            rate_limit_burst: 1,
+            ..Default::default()
+        };
+        let router = apply_rate_limit(
+            Router::new().route("/api/health", get(|| async { "ok" })),
+            &web_config,
+        );
+
+        let request_from = |ip: [u8; 4]| {
+            let mut request = axum::http::Request::builder()
+                .uri("/api/health")
+                .body(Body::empty())
+                .expect("build request");
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((ip, 4000))));
+            request
+        };
+
+        let first = router
+            .clone()
+            .oneshot(request_from([10, 0, 0, 1]))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let second = router
+            .clone()
+            .oneshot(request_from([10, 0, 0, 1]))
+            .await
+            .unwrap();
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        let other_ip = router.oneshot(request_from([10, 0, 0, 2])).await.unwrap();
+        assert_eq!(other_ip.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_rejects_oversized_messages() {
         let state = build_test_state().await;
-        let router = build_test_router(state);
+        let router = Router::new()
+            .route(
+                "/api/conversations/:id/message",
+                post(send_message_to_conversation),
+            )
+            .layer(DefaultBodyLimit::max(64))
+            .with_state(state);
+        let body = serde_json::json!({ "message": "x".repeat(256) }).to_string();
         let request = axum::http::Request::builder()
-            .uri("/api/permissions/respond")
+            .uri("/api/conversations/missing/message")
             .method("POST")
             .header(header::CONTENT_TYPE, "application/json")
-            .body(Body::from(r#"{"id":""}"#))
+            .body(Body::from(body))
             .expect("build request");
         let response = router.oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 
     #[tokio::test]
-    async fn test_set_model_endpoint() {
+    async fn test_conversation_settings_update_is_scoped_to_conversation() {
         let state = build_test_state().await;
+        let first = state
+            .database
+            .create_conversation(Some("Original".to_string()), "test-model", None)
+            .await
+            .expect("create conversation");
+        let second = state
+            .database
+            .create_conversation(None, "test-model", None)
+            .await
+            .expect("create conversation");
+
         let router = build_test_router(state.clone());
         let request = axum::http::Request::builder()
-            .uri("/api/models")
-            .method("POST")
+            .uri(format!("/api/conversations/{}/settings", first))
+            .method("PUT")
             .header(header::CONTENT_TYPE, "application/json")
-            .body(Body::from(r#"{"model":"glm-4.6"}"#))
+            .body(Body::from(
+                r#"{"model":"other-model","temperature":0.2,"system_prompt":"Be brief","plan_mode":true}"#,
+            ))
             .expect("build request");
         let (status, body) = json_response(&router, request).await;
         assert_eq!(status, StatusCode::OK);
-        assert_eq!(body["model"], "glm-4.6");
+        assert_eq!(body["model"], "other-model");
+        assert_eq!(body["system_prompt"], "Be brief");
+        assert_eq!(body["plan_mode"], true);
+        assert!((body["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/conversations/{}/settings", second))
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["plan_mode"], false);
+        assert!(body["system_prompt"].is_null());
+
+        // Pool agents pick up the stored overrides when they are created
+        let agent = get_or_create_conversation_agent(&state, &first)
+            .await
+            .expect("create conversation agent")
+            .agent;
+        let agent = agent.lock().await;
+        assert_eq!(agent.model(), "other-model");
+        assert!(agent.plan_mode());
+        assert!((agent.temperature() - 0.2).abs() < 1e-6);
     }
 
     #[tokio::test]
-    async fn test_set_model_rejects_empty() {
+    async fn test_conversation_settings_rejects_invalid_values() {
         let state = build_test_state().await;
+        let conversation_id = state
+            .database
+            .create_conversation(None, "test-model", None)
+            .await
+            .expect("create conversation");
         let router = build_test_router(state);
+
+        for body in [
+            r#"{"temperature":3.5}"#,
+            r#"{"model":"  "}"#,
+            r#"{"active_skills":["missing-skill"]}"#,
+        ] {
+            let request = axum::http::Request::builder()
+                .uri(format!("/api/conversations/{}/settings", conversation_id))
+                .method("PUT")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .expect("build request");
+            let response = router.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+        }
+
         let request = axum::http::Request::builder()
-            .uri("/api/models")
-            .method("POST")
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(Body::from(r#"{"model":"  "}"#))
+            .uri("/api/conversations/missing/settings")
+            .method("GET")
+            .body(Body::empty())
             .expect("build request");
         let response = router.oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn test_get_conversation_success() {
+    async fn test_conversation_context_breakdown_and_removal() {
         let state = build_test_state().await;
         let conversation_id = state
             .database
-            .create_conversation(Some("Test prompt".to_string()), "test-model", None)
+            .create_conversation(Some("Be brief".to_string()), "claude-sonnet-4", None)
             .await
             .expect("create conversation");
-        state
-            .database
-            .add_message(&conversation_id, "user", "Hello", "test-model", 1)
+        let agent = get_or_create_conversation_agent(&state, &conversation_id)
             .await
-            .expect("add message");
+            .expect("create conversation agent")
+            .agent;
+        agent
+            .lock()
+            .await
+            .add_context_text("file 'notes.md'", "Remember the milk");
 
         let router = build_test_router(state);
         let request = axum::http::Request::builder()
-            .uri(&format!("/api/conversations/{}", conversation_id))
+            .uri(format!("/api/conversations/{}/context", conversation_id))
             .method("GET")
             .body(Body::empty())
             .expect("build request");
         let (status, body) = json_response(&router, request).await;
         assert_eq!(status, StatusCode::OK);
-        assert_eq!(body["conversation"]["id"], conversation_id);
-        assert_eq!(body["conversation"]["system_prompt"], "Test prompt");
-        let messages = body["messages"].as_array().expect("messages array");
-        assert_eq!(messages.len(), 1);
+        assert_eq!(body["context_window"], 200_000);
+        let kinds: Vec<&str> = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["kind"].as_str().unwrap())
+            .collect();
+        assert!(kinds.contains(&"system_prompt"));
+        assert!(kinds.contains(&"history"));
+        let file = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item["label"] == "notes.md")
+            .expect("context file item");
+        let message_index = file["message_index"].as_u64().unwrap();
+
+        let request = axum::http::Request::builder()
+            .uri(format!(
+                "/api/conversations/{}/context/{}",
+                conversation_id, message_index
+            ))
+            .method("DELETE")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|item| item["label"] == "notes.md"));
+
+        let request = axum::http::Request::builder()
+            .uri(format!(
+                "/api/conversations/{}/context/{}",
+                conversation_id, message_index
+            ))
+            .method("DELETE")
+            .body(Body::empty())
+            .expect("build request");
+        let response = router.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
@@ -3623,6 +6413,10 @@ mod tests {
             env: None,
             auth: None,
             enabled: false,
+            transport: None,
+            sampling: None,
+            lazy: false,
+            aliases: None,
         };
         state
             .mcp_manager
@@ -3668,6 +6462,10 @@ mod tests {
             env: None,
             auth: None,
             enabled: false,
+            transport: None,
+            sampling: None,
+            lazy: false,
+            aliases: None,
         };
         state
             .mcp_manager
@@ -3972,6 +6770,74 @@ mod tests {
         assert!(body["data"].is_array());
     }
 
+    #[tokio::test]
+    async fn test_project_stats() {
+        let state = build_test_state().await;
+        let conversation_id = state
+            .database
+            .create_conversation(None, "test-model", None)
+            .await
+            .expect("create conversation");
+        state
+            .database
+            .add_message(&conversation_id, "user", "Hello", "test-model", 1)
+            .await
+            .expect("add message");
+        let router = Router::new()
+            .route("/api/stats/projects", get(get_project_stats))
+            .with_state(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/stats/projects?period=lifetime")
+            .method("GET")
+            .body(Body::empty())
+            .expect("build request");
+        let (status, body) = json_response(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["period"], "lifetime");
+        assert_eq!(body["data"][0]["total_conversations"], 1);
+        assert_eq!(body["data"][0]["models"][0], "test-model");
+    }
+
+    #[test]
+    fn test_aggregate_project_stats() {
+        use crate::database::ProjectModelStats;
+        let row = |path: Option<&str>, model: &str, input: i64, output: i64| ProjectModelStats {
+            workspace_path: path.map(str::to_string),
+            model: model.to_string(),
+            total_conversations: 1,
+            total_tokens: input + output,
+            input_tokens: input,
+            output_tokens: output,
+            request_count: 1,
+            tool_calls: 2,
+            tool_errors: 0,
+        };
+        let data = aggregate_project_stats(vec![
+            (
+                std::path::PathBuf::from("/home/me/.flexorama/me_src_app.db"),
+                vec![
+                    row(Some("/src/app"), "gpt-4o", 1_000_000, 0),
+                    row(Some("/src/app"), "my-finetune", 10, 10),
+                    row(None, "gpt-4o", 5, 5),
+                ],
+            ),
+            (
+                std::path::PathBuf::from("/home/me/.flexorama/me_src_lib.db"),
+                vec![row(Some("/src/lib"), "gpt-4o-mini", 1_000, 0)],
+            ),
+        ]);
+
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].project, "app");
+        assert_eq!(data[0].total_conversations, 2);
+        assert_eq!(data[0].tool_calls, 4);
+        assert_eq!(data[0].unpriced_tokens, 20);
+        assert_eq!(data[0].models, vec!["gpt-4o", "my-finetune"]);
+        assert!(data[0].cost_usd > data[1].cost_usd);
+        let legacy = data.iter().find(|p| p.path.is_none()).unwrap();
+        assert_eq!(legacy.project, "me_src_app");
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_file_autocomplete_empty_prefix() {
@@ -4337,7 +7203,7 @@ mod tests {
 
         // Create a config with Mistral provider and custom API key
         let mut config = config::Config::default();
-        config.provider = config::Provider::Mistral;
+        config.provider = config::Provider::MISTRAL;
         config.api_key = "test-mistral-api-key-12345".to_string();
         config.default_model = "mistral-large-latest".to_string();
 
@@ -4368,7 +7234,8 @@ mod tests {
             skill_manager,
             conversation_agents: Arc::new(Mutex::new(HashMap::new())),
             csrf_manager: Arc::new(CsrfManager::new()),
-            config: Arc::new(config.clone()),
+            config: Arc::new(RwLock::new(config.clone())),
+            started_at: Instant::now(),
         };
 
         // Create a conversation
@@ -4380,11 +7247,12 @@ mod tests {
         // Get or create conversation agent
         let agent_arc = get_or_create_conversation_agent(&state, &conversation_id)
             .await
-            .expect("get or create conversation agent");
+            .expect("get or create conversation agent")
+            .agent;
 
         // Verify the agent has the correct provider (which indicates it got the right config)
         let agent = agent_arc.lock().await;
-        assert_eq!(agent.provider(), config::Provider::Mistral);
+        assert_eq!(agent.provider(), config::Provider::MISTRAL);
         assert_eq!(agent.model(), "mistral-large-latest");
     }
 
@@ -4402,17 +7270,108 @@ mod tests {
         // Get agent for first time
         let agent1 = get_or_create_conversation_agent(&state, &conversation_id)
             .await
-            .expect("get agent first time");
+            .expect("get agent first time")
+            .agent;
 
         // Get agent for second time
         let agent2 = get_or_create_conversation_agent(&state, &conversation_id)
             .await
-            .expect("get agent second time");
+            .expect("get agent second time")
+            .agent;
 
         // Verify they are the same Arc (same memory address)
         assert!(Arc::ptr_eq(&agent1, &agent2));
     }
 
+    #[tokio::test]
+    async fn test_idle_conversation_agents_are_evicted() {
+        let state = build_test_state().await;
+        let idle = state
+            .database
+            .create_conversation(None, "claude-sonnet-4-5", None)
+            .await
+            .expect("create conversation");
+        let busy = state
+            .database
+            .create_conversation(None, "claude-sonnet-4-5", None)
+            .await
+            .expect("create conversation");
+        get_or_create_conversation_agent(&state, &idle)
+            .await
+            .expect("create idle agent");
+        let busy_agent = get_or_create_conversation_agent(&state, &busy)
+            .await
+            .expect("create busy agent");
+        let _turn = busy_agent.agent.lock().await;
+
+        // Fresh agents are kept
+        let timeout = std::time::Duration::from_secs(60);
+        assert_eq!(evict_idle_conversation_agents(&state, timeout).await, 0);
+
+        // Stale ones go, unless a request holds them
+        for entry in state.conversation_agents.lock().await.values_mut() {
+            entry.last_used = Instant::now() - timeout;
+        }
+        assert_eq!(evict_idle_conversation_agents(&state, timeout).await, 1);
+        let agents = state.conversation_agents.lock().await;
+        assert!(!agents.contains_key(&idle));
+        assert!(agents.contains_key(&busy));
+    }
+
+    #[tokio::test]
+    async fn test_closing_a_conversation_cancels_its_turn() {
+        let state = build_test_state().await;
+        let conversation_id = state
+            .database
+            .create_conversation(None, "claude-sonnet-4-5", None)
+            .await
+            .expect("create conversation");
+        let (turn, cancellation) = begin_conversation_turn(&state, &conversation_id)
+            .await
+            .expect("begin turn");
+        let router = build_test_router(state.clone());
+
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/conversations/{}/agent", conversation_id))
+            .method("DELETE")
+            .body(Body::empty())
+            .expect("build request");
+        let response = router.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(cancellation.load(Ordering::SeqCst));
+        assert!(state.conversation_agents.lock().await.is_empty());
+        drop(turn);
+
+        // Nothing left to close
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/conversations/{}/agent", conversation_id))
+            .method("DELETE")
+            .body(Body::empty())
+            .expect("build request");
+        let response = router.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Deleting the conversation cancels its turn too
+        let (_turn, cancellation) = begin_conversation_turn(&state, &conversation_id)
+            .await
+            .expect("begin turn");
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/conversations/{}", conversation_id))
+            .method("DELETE")
+            .body(Body::empty())
+            .expect("build request");
+        let response = router.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(cancellation.load(Ordering::SeqCst));
+        assert!(state.conversation_agents.lock().await.is_empty());
+        assert!(state
+            .database
+            .get_conversation(&conversation_id)
+            .await
+            .expect("load conversation")
+            .is_none());
+    }
+
     #[test]
     fn test_block_to_dto_with_image_source() {
         let image_block = ContentBlock::image(
@@ -4439,6 +7398,28 @@ mod tests {
         assert_eq!(dto.block_type, "text");
         assert_eq!(dto.text.as_deref(), Some("Hello world"));
         assert!(dto.source.is_none());
+        assert!(dto.segments.is_none());
+    }
+
+    #[test]
+    fn test_block_to_dto_splits_math() {
+        let dto = block_to_dto(&ContentBlock::text(
+            "Euler: $e^{i\\pi} + 1 = 0$ and $$\\int_0^1 x\\,dx$$".to_string(),
+        ));
+        let segments = dto.segments.expect("math segments");
+        let summary: Vec<(&str, &str, bool)> = segments
+            .iter()
+            .map(|s| (s.segment_type.as_str(), s.text.as_str(), s.display))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("text", "Euler: ", false),
+                ("math", "e^{i\\pi} + 1 = 0", false),
+                ("text", " and ", false),
+                ("math", "\\int_0^1 x\\,dx", true),
+            ]
+        );
     }
 
     #[test]
@@ -4457,6 +7438,7 @@ mod tests {
                 media_type: "image/png".to_string(),
                 data: "fake_data".to_string(),
             }),
+            segments: None,
         };
 
         let summary = block_text_summary(&image_dto);
@@ -4475,6 +7457,7 @@ mod tests {
             content: None,
             is_error: None,
             source: None,
+            segments: None,
         };
 
         let summary = block_text_summary(&text_dto);
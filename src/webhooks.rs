@@ -0,0 +1,219 @@
+use chrono::Utc;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Agent events that can be delivered to webhooks
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    TurnCompleted,
+    PermissionRequested,
+    BudgetExceeded,
+    ToolError,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::TurnCompleted => "turn_completed",
+            WebhookEvent::PermissionRequested => "permission_requested",
+            WebhookEvent::BudgetExceeded => "budget_exceeded",
+            WebhookEvent::ToolError => "tool_error",
+        }
+    }
+}
+
+/// Body shape sent to the webhook URL
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// Full event envelope as JSON
+    #[default]
+    Json,
+    /// `{"text": "..."}` summary accepted by Slack incoming webhooks
+    Slack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Events to deliver; empty means all events
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Extra request headers, e.g. an Authorization token
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl WebhookConfig {
+    fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// Fire-and-forget delivery of agent events to configured webhooks
+pub struct WebhookDispatcher {
+    webhooks: Vec<WebhookConfig>,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    /// Build a dispatcher, or `None` when no webhooks are configured
+    pub fn from_config(webhooks: &[WebhookConfig]) -> Option<Arc<Self>> {
+        if webhooks.is_empty() {
+            return None;
+        }
+        let client = crate::http_client::client_builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Some(Arc::new(Self {
+            webhooks: webhooks.to_vec(),
+            client,
+        }))
+    }
+
+    /// Deliver an event to every interested webhook without blocking the caller
+    pub fn emit(
+        &self,
+        event: WebhookEvent,
+        conversation_id: Option<&str>,
+        model: &str,
+        data: Value,
+    ) {
+        let envelope = serde_json::json!({
+            "event": event.as_str(),
+            "timestamp": Utc::now().to_rfc3339(),
+            "conversation_id": conversation_id,
+            "model": model,
+            "data": data,
+        });
+
+        for webhook in self.webhooks.iter().filter(|w| w.wants(event)) {
+            let body = match webhook.format {
+                WebhookFormat::Json => envelope.clone(),
+                WebhookFormat::Slack => serde_json::json!({ "text": slack_summary(&envelope) }),
+            };
+            let mut request = self.client.post(&webhook.url).json(&body);
+            for (name, value) in &webhook.headers {
+                request = request.header(name, value);
+            }
+            let url = webhook.url.clone();
+            tokio::spawn(async move {
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => {
+                        debug!("Delivered {} webhook to {}", event.as_str(), url);
+                    }
+                    Ok(response) => {
+                        warn!(
+                            "Webhook {} rejected {} event: HTTP {}",
+                            url,
+                            event.as_str(),
+                            response.status()
+                        );
+                    }
+                    Err(e) => warn!(
+                        "Failed to deliver {} webhook to {}: {}",
+                        event.as_str(),
+                        url,
+                        e
+                    ),
+                }
+            });
+        }
+    }
+}
+
+/// One-line human readable description of an event envelope
+fn slack_summary(envelope: &Value) -> String {
+    let conversation = envelope["conversation_id"].as_str().unwrap_or("unknown");
+    let data = &envelope["data"];
+    match envelope["event"].as_str().unwrap_or_default() {
+        "turn_completed" => format!(
+            "Flexorama finished a turn in conversation {}:\n{}",
+            conversation,
+            truncate(data["response"].as_str().unwrap_or_default(), 500)
+        ),
+        "permission_requested" => format!(
+            "Flexorama is waiting for permission in conversation {}: {}",
+            conversation,
+            data["detail"].as_str().unwrap_or_default()
+        ),
+        "budget_exceeded" => format!(
+            "Flexorama exceeded its token budget in conversation {} ({} of {} tokens)",
+            conversation, data["total_tokens"], data["token_budget"]
+        ),
+        "tool_error" => format!(
+            "Flexorama tool `{}` failed in conversation {}: {}",
+            data["tool_name"].as_str().unwrap_or("unknown"),
+            conversation,
+            truncate(data["error"].as_str().unwrap_or_default(), 500)
+        ),
+        other => format!("Flexorama event {} in conversation {}", other, conversation),
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(max_chars).collect();
+        format!("{}…", truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_config_parses_from_toml() {
+        let config: WebhookConfig = toml::from_str(
+            r#"
+            url = "https://example.com/hook"
+            events = ["turn_completed", "tool_error"]
+            format = "slack"
+            [headers]
+            Authorization = "Bearer token"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.format, WebhookFormat::Slack);
+        assert!(config.wants(WebhookEvent::ToolError));
+        assert!(!config.wants(WebhookEvent::BudgetExceeded));
+        assert_eq!(config.headers["Authorization"], "Bearer token");
+    }
+
+    #[test]
+    fn empty_event_list_subscribes_to_everything() {
+        let config = WebhookConfig {
+            url: "https://example.com".to_string(),
+            events: Vec::new(),
+            format: WebhookFormat::Json,
+            headers: HashMap::new(),
+        };
+        assert!(config.wants(WebhookEvent::PermissionRequested));
+        assert!(WebhookDispatcher::from_config(&[]).is_none());
+    }
+
+    #[test]
+    fn slack_summary_describes_tool_errors() {
+        let envelope = serde_json::json!({
+            "event": "tool_error",
+            "conversation_id": "abc",
+            "data": { "tool_name": "bash", "error": "exit status 1" },
+        });
+        assert_eq!(
+            slack_summary(&envelope),
+            "Flexorama tool `bash` failed in conversation abc: exit status 1"
+        );
+    }
+}
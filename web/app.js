@@ -114,6 +114,12 @@ const state = {
     hasMore: true,
     isLoadingMore: false,
   },
+  messagePagination: {
+    conversationId: null,
+    nextCursor: null,
+    hasMore: false,
+    isLoading: false,
+  },
   csrfToken: null,
 };
 
@@ -295,9 +301,13 @@ function renderConversationList() {
     item.className = "list-item" + (isActive ? " active" : "");
     const streamingIndicator = isStreaming ? '<span style="color: var(--accent-neon); margin-left: 6px;" title="Streaming">●</span>' : '';
     item.innerHTML = `
-      <div style="font-weight:600;">${conv.last_message ? conv.last_message.slice(0, 50) : "new chat"}${streamingIndicator}</div>
+      <div style="font-weight:600;"></div>
       <small>${new Date(conv.updated_at).toLocaleString()} • ${conv.model}</small>
     `;
+    // Named conversations (--session-name, /rename) show their name instead of the last message
+    const title = item.firstElementChild;
+    title.textContent = conv.name || (conv.last_message ? conv.last_message.slice(0, 50) : "new chat");
+    title.insertAdjacentHTML("beforeend", streamingIndicator);
     item.addEventListener("click", () => selectConversation(conv.id));
     list.appendChild(item);
   });
@@ -509,11 +519,47 @@ function renderBlock(block) {
   }
 
   wrapper.className = "text-block";
+  if (Array.isArray(block.segments) && block.segments.length) {
+    wrapper.appendChild(renderMathText(block.segments));
+    return wrapper;
+  }
   const text = block.text || block.content || "";
   wrapper.appendChild(renderTextContent(text));
   return wrapper;
 }
 
+// Text the server split around LaTeX; math is typeset with KaTeX when it loaded
+function renderMathText(segments) {
+  const container = document.createElement("div");
+  segments.forEach((segment) => {
+    if (segment.type === "math") {
+      const el = document.createElement(segment.display ? "div" : "span");
+      el.className = segment.display ? "math math-display" : "math math-inline";
+      const source = segment.display ? `$$${segment.text}$$` : `$${segment.text}$`;
+      if (window.katex) {
+        try {
+          window.katex.render(segment.text, el, {
+            displayMode: !!segment.display,
+            throwOnError: false,
+          });
+        } catch (_) {
+          el.textContent = source;
+        }
+      } else {
+        el.textContent = source;
+      }
+      container.appendChild(el);
+    } else if (segment.text.includes("```")) {
+      container.appendChild(renderTextContent(segment.text));
+    } else {
+      const span = document.createElement("span");
+      span.textContent = segment.text;
+      container.appendChild(span);
+    }
+  });
+  return container;
+}
+
 function renderTextContent(text) {
   const container = document.createElement("div");
   const regex = /```(\w+)?\n([\s\S]*?)```/g;
@@ -581,9 +627,69 @@ function renderMessageBubble(msg) {
     checkAndAppendPlanButton(bubble, text);
   }
 
+  const isTextOnly = blocks.every((b) => (b.type || "text") === "text");
+  if (msg.id && isTextOnly) {
+    appendMessageActions(bubble, msg);
+  }
+
   return bubble;
 }
 
+function appendMessageActions(bubble, msg) {
+  const actions = document.createElement("div");
+  actions.className = "message-actions";
+
+  if (msg.role === "user") {
+    const editButton = document.createElement("button");
+    editButton.className = "secondary";
+    editButton.textContent = "Edit";
+    editButton.addEventListener("click", () => editMessage(bubble, msg));
+    actions.appendChild(editButton);
+  } else if (msg.role === "assistant") {
+    const regenerateButton = document.createElement("button");
+    regenerateButton.className = "secondary";
+    regenerateButton.textContent = "Regenerate";
+    regenerateButton.addEventListener("click", () => regenerateMessage(bubble, msg));
+    actions.appendChild(regenerateButton);
+  }
+
+  if (actions.childElementCount) {
+    bubble.appendChild(actions);
+  }
+}
+
+function removeBubblesFrom(bubble) {
+  let node = bubble;
+  while (node) {
+    const next = node.nextElementSibling;
+    node.remove();
+    node = next;
+  }
+}
+
+async function editMessage(bubble, msg) {
+  const conversationId = state.activeConversationId;
+  if (!conversationId || conversationStreams.has(String(conversationId))) return;
+  const updated = window.prompt("Edit message", msg.content || "");
+  if (updated === null || !updated.trim()) return;
+
+  removeBubblesFrom(bubble);
+  appendMessage("user", updated.trim());
+  await sendMessageStreaming(updated.trim(), null, {
+    endpoint: `/api/conversations/${conversationId}/messages/${encodeURIComponent(msg.id)}/edit`,
+  });
+}
+
+async function regenerateMessage(bubble, msg) {
+  const conversationId = state.activeConversationId;
+  if (!conversationId || conversationStreams.has(String(conversationId))) return;
+
+  removeBubblesFrom(bubble);
+  await sendMessageStreaming(null, null, {
+    endpoint: `/api/conversations/${conversationId}/messages/${encodeURIComponent(msg.id)}/regenerate`,
+  });
+}
+
 function renderMessages(messages) {
   const container = document.getElementById("messages");
   container.innerHTML = "";
@@ -595,6 +701,42 @@ function renderMessages(messages) {
   highlightCodes(container);
 }
 
+async function loadOlderMessages() {
+  const pagination = state.messagePagination;
+  if (!pagination.hasMore || pagination.isLoading || !pagination.nextCursor) return;
+  const conversationId = pagination.conversationId;
+  if (String(conversationId) !== String(state.activeConversationId)) return;
+
+  pagination.isLoading = true;
+  try {
+    const page = await api(
+      `/api/conversations/${conversationId}/messages?before=${encodeURIComponent(pagination.nextCursor)}`
+    );
+    // The user may have switched conversations while the page was loading
+    if (String(conversationId) !== String(state.activeConversationId)) return;
+
+    const container = document.getElementById("messages");
+    const previousHeight = container.scrollHeight;
+    const fragment = document.createDocumentFragment();
+    page.messages.forEach((msg) => {
+      const bubble = renderMessageBubble(msg);
+      if (bubble) fragment.appendChild(bubble);
+    });
+    const firstNew = fragment.firstChild;
+    container.insertBefore(fragment, container.firstChild);
+    // Keep the viewport anchored on the message the user was reading
+    container.scrollTop += container.scrollHeight - previousHeight;
+    if (firstNew) highlightCodes(container);
+
+    pagination.nextCursor = page.next_cursor || null;
+    pagination.hasMore = Boolean(page.has_more);
+  } catch (err) {
+    console.error("Failed to load older messages:", err);
+  } finally {
+    pagination.isLoading = false;
+  }
+}
+
 function appendMessage(role, content, blocks = null) {
   const container = document.getElementById("messages");
   const bubble = renderMessageBubble({ role, content, blocks });
@@ -1090,6 +1232,7 @@ async function selectConversation(id) {
     }
 
     await loadModels();
+    await loadConversationSettings();
     await loadPendingPermissions();
     await loadTodos();
     return;
@@ -1098,13 +1241,20 @@ async function selectConversation(id) {
   setStatus("Loading conversation...");
   const detail = await api(`/api/conversations/${id}`);
   const meta = detail.conversation;
-  setStatus(`${detail.messages.length} messages`);
+  setStatus(`${detail.messages.length}${detail.has_more ? "+" : ""} messages`);
+  state.messagePagination = {
+    conversationId: id,
+    nextCursor: detail.next_cursor || null,
+    hasMore: Boolean(detail.has_more),
+    isLoading: false,
+  };
   renderMessages(detail.messages);
   const select = document.getElementById("agent-selector");
   if (select) {
     select.value = meta.subagent || "";
   }
   await loadModels();
+  await loadConversationSettings();
   await loadPendingPermissions();
   await loadTodos();
 }
@@ -1181,7 +1331,7 @@ async function sendMessageOnce(text, images = null) {
   }
 }
 
-async function sendMessageStreaming(text, images = null) {
+async function sendMessageStreaming(text, images = null, { endpoint = null } = {}) {
   // Capture the conversation ID at the start - use this throughout the function
   const conversationId = state.activeConversationId;
   const convIdStr = String(conversationId);
@@ -1230,6 +1380,7 @@ async function sendMessageStreaming(text, images = null) {
 
   let toolBubble = null;
   let buffer = "";
+  let retryText = null;
   const poller = startPermissionPolling();
 
   try {
@@ -1240,7 +1391,7 @@ async function sendMessageStreaming(text, images = null) {
       headers["X-CSRF-Token"] = state.csrfToken;
     }
 
-    const requestBody = { message: text };
+    const requestBody = text === null ? {} : { message: text };
     if (images && images.length > 0) {
       requestBody.images = images.map(img => ({
         media_type: img.media_type,
@@ -1248,7 +1399,8 @@ async function sendMessageStreaming(text, images = null) {
       }));
     }
 
-    const res = await fetch(`/api/conversations/${conversationId}/message/stream`, {
+    const url = endpoint || `/api/conversations/${conversationId}/message/stream`;
+    const res = await fetch(url, {
       method: "POST",
       headers: headers,
       body: JSON.stringify(requestBody),
@@ -1340,6 +1492,15 @@ async function sendMessageStreaming(text, images = null) {
           if (isActiveConversation()) {
             renderPermissionRequest(evt);
           }
+        } else if (evt.type === "error" && Array.isArray(evt.missing_arguments) && text) {
+          retryText = promptForCommandArguments(text, evt);
+          const bubble = getActiveBubble();
+          if (bubble && retryText) {
+            bubble.remove();
+          } else if (bubble) {
+            updateBubbleContent(bubble, `Error: ${evt.error}`);
+            setStatus("Error");
+          }
         } else if (evt.type === "error") {
           const bubble = getActiveBubble();
           if (bubble) {
@@ -1388,6 +1549,22 @@ async function sendMessageStreaming(text, images = null) {
     // Update conversation list to remove streaming indicator
     renderConversationList();
   }
+  if (retryText && isActiveConversation()) {
+    await sendMessageStreaming(retryText, null, { endpoint });
+  }
+}
+
+// Ask for the arguments a custom command is missing and add them to the
+// command as name="value". Returns null if the user cancels.
+function promptForCommandArguments(text, evt) {
+  let completed = text;
+  for (const name of evt.missing_arguments) {
+    const usage = evt.argument_hint ? ` (usage: ${text.split(/\s+/)[0]} ${evt.argument_hint})` : "";
+    const value = window.prompt(`${name}${usage}`);
+    if (value === null || !value.trim()) return null;
+    completed += ` ${name}="${value.trim().replace(/["\\]/g, "\\$&")}"`;
+  }
+  return completed;
 }
 
 // Plans
@@ -1469,7 +1646,11 @@ function resetMcpForm() {
   document.getElementById("mcp-command").value = "";
   document.getElementById("mcp-args").value = "";
   document.getElementById("mcp-url").value = "";
+  document.getElementById("mcp-transport").value = "";
+  document.getElementById("mcp-sampling").value = "";
+  document.getElementById("mcp-lazy").value = "false";
   document.getElementById("mcp-env").value = "";
+  document.getElementById("mcp-aliases").value = "";
   document.getElementById("mcp-enabled").value = "true";
   document.getElementById("mcp-auth-type").value = "none";
   document.getElementById("mcp-auth-client").value = "body";
@@ -1497,10 +1678,17 @@ function setMcpForm(server) {
   document.getElementById("mcp-command").value = server.config.command || "";
   document.getElementById("mcp-args").value = (server.config.args || []).join(" ");
   document.getElementById("mcp-url").value = server.config.url || "";
+  document.getElementById("mcp-transport").value = server.config.transport || "";
+  document.getElementById("mcp-sampling").value =
+    server.config.sampling === "ask" ? "" : server.config.sampling || "";
+  document.getElementById("mcp-lazy").value = String(Boolean(server.config.lazy));
   const env = server.config.env || {};
   document.getElementById("mcp-env").value = Object.entries(env)
     .map(([k, v]) => `${k}=${v}`)
     .join("\n");
+  document.getElementById("mcp-aliases").value = Object.entries(server.config.aliases || {})
+    .map(([tool, alias]) => `${tool}=${alias}`)
+    .join("\n");
   document.getElementById("mcp-enabled").value = String(server.config.enabled);
   const auth = server.config.auth || null;
   if (!auth) {
@@ -1682,7 +1870,11 @@ async function saveMcpServer() {
       .split(" ")
       .filter(Boolean),
     url: document.getElementById("mcp-url").value.trim() || null,
+    transport: document.getElementById("mcp-transport").value || null,
+    sampling: document.getElementById("mcp-sampling").value || null,
+    lazy: document.getElementById("mcp-lazy").value === "true",
     env: parseEnv(document.getElementById("mcp-env").value),
+    aliases: parseAliases(document.getElementById("mcp-aliases").value),
     auth,
     enabled: document.getElementById("mcp-enabled").value === "true",
   };
@@ -1698,6 +1890,11 @@ function updateMcpAuthVisibility() {
   document.getElementById("mcp-auth-extra").style.display = showOAuth ? "" : "none";
 }
 
+function parseAliases(text) {
+  const aliases = parseEnv(text);
+  return Object.keys(aliases).length ? aliases : null;
+}
+
 function parseEnv(text) {
   const env = {};
   text
@@ -2044,7 +2241,26 @@ function renderCommands() {
   const list = document.getElementById("command-list");
   if (!list) return;
   list.innerHTML = "";
-  state.commands.forEach((command) => {
+  // Commands from subdirectories are namespaced, like git:commit; list
+  // each namespace under its own heading, after the plain commands
+  const namespaceOf = (name) =>
+    name.includes(":") ? name.slice(0, name.lastIndexOf(":")) : "";
+  const sorted = [...state.commands].sort(
+    (a, b) =>
+      namespaceOf(a.name).localeCompare(namespaceOf(b.name)) || a.name.localeCompare(b.name)
+  );
+  let currentNamespace = "";
+  sorted.forEach((command) => {
+    const namespace = namespaceOf(command.name);
+    if (namespace && namespace !== currentNamespace) {
+      const heading = document.createElement("small");
+      heading.className = "muted";
+      heading.style.display = "block";
+      heading.style.margin = "8px 0 4px";
+      heading.textContent = `${namespace}:`;
+      list.appendChild(heading);
+    }
+    currentNamespace = namespace;
     const item = document.createElement("div");
     item.className =
       "list-item" + (command.name === state.activeCommandEditing ? " active" : "");
@@ -2153,6 +2369,29 @@ async function loadModels() {
   renderModelSelector();
 }
 
+// Model and plan mode are stored per conversation once one is selected
+async function loadConversationSettings() {
+  const conversationId = state.activeConversationId;
+  if (!conversationId) return;
+  try {
+    const settings = await api(`/api/conversations/${conversationId}/settings`);
+    if (String(conversationId) !== String(state.activeConversationId)) return;
+    state.activeModel = settings.model;
+    state.planMode = settings.plan_mode;
+    renderModelSelector();
+    renderPlanModeButton();
+  } catch (err) {
+    console.error("Failed to load conversation settings:", err);
+  }
+}
+
+async function updateConversationSettings(changes) {
+  return api(`/api/conversations/${state.activeConversationId}/settings`, {
+    method: "PUT",
+    body: changes,
+  });
+}
+
 // Plan Mode
 async function loadPlanMode() {
   try {
@@ -2167,10 +2406,14 @@ async function loadPlanMode() {
 async function setPlanMode(enabled) {
   if (state.planMode === enabled) return;
   try {
-    await api("/api/plan-mode", {
-      method: "POST",
-      body: { enabled },
-    });
+    if (state.activeConversationId) {
+      await updateConversationSettings({ plan_mode: enabled });
+    } else {
+      await api("/api/plan-mode", {
+        method: "POST",
+        body: { enabled },
+      });
+    }
     state.planMode = enabled;
     renderPlanModeButton();
     setStatus(enabled ? "Plan mode enabled" : "Plan mode disabled");
@@ -2670,6 +2913,16 @@ function bindEvents() {
     });
   }
 
+  // Fetch older pages of the active conversation when scrolling to the top
+  const messagesContainer = document.getElementById("messages");
+  if (messagesContainer) {
+    messagesContainer.addEventListener("scroll", () => {
+      if (messagesContainer.scrollTop < 100) {
+        loadOlderMessages();
+      }
+    });
+  }
+
   const todoToggle = document.getElementById("todo-toggle");
   if (todoToggle) {
     todoToggle.addEventListener("click", () => {
@@ -2751,7 +3004,11 @@ function bindEvents() {
     const model = e.target.value;
     if (!model) return;
     try {
-      await api("/api/models", { method: "POST", body: { model } });
+      if (state.activeConversationId) {
+        await updateConversationSettings({ model });
+      } else {
+        await api("/api/models", { method: "POST", body: { model } });
+      }
       state.activeModel = model;
       await loadConversations();
       if (state.activeConversationId) {
@@ -3093,11 +3350,14 @@ function aggregateByProvider(modelStats) {
 
 function extractProvider(model) {
   const lower = model.toLowerCase();
+  // OpenRouter names its models "vendor/model"
+  if (lower.includes('/')) return 'OpenRouter';
   if (lower.includes('claude')) return 'Anthropic';
   if (lower.includes('gpt')) return 'OpenAI';
   if (lower.includes('gemini')) return 'Gemini';
   if (lower.includes('mistral')) return 'Mistral';
   if (lower.includes('glm')) return 'Z.AI';
+  if (lower.includes('deepseek')) return 'DeepSeek';
   return 'Other';
 }
 
@@ -3170,6 +3430,52 @@ function updateStatsCharts() {
       state.statsData.conversationsBySubagent
     );
   }
+
+  if (state.statsData.projects) {
+    state.statsCharts.projects = createProjectsChart(
+      document.getElementById('chart-projects')?.getContext('2d'),
+      state.statsData.projects
+    );
+  }
+}
+
+function createProjectsChart(ctx, data) {
+  if (!data || data.length === 0) return null;
+  const projects = data.slice(0, 10);
+  const defaults = getChartDefaults();
+  return new Chart(ctx, {
+    type: 'bar',
+    data: {
+      labels: projects.map(d => d.project),
+      datasets: [{
+        label: 'Estimated Cost (USD)',
+        data: projects.map(d => d.cost_usd),
+        backgroundColor: CHART_COLORS.blue,
+        borderColor: CHART_COLORS.blue,
+        borderWidth: 1,
+      }],
+    },
+    options: {
+      ...defaults,
+      indexAxis: 'y',
+      plugins: {
+        ...defaults.plugins,
+        tooltip: {
+          ...defaults.plugins.tooltip,
+          callbacks: {
+            afterLabel: (item) => {
+              const project = projects[item.dataIndex];
+              return [
+                project.path || '(directory not recorded)',
+                `${project.total_tokens.toLocaleString()} tokens in ${project.total_conversations} conversations`,
+                `${project.tool_calls} tool calls, ${project.tool_errors} failed`,
+              ];
+            },
+          },
+        },
+      },
+    },
+  });
 }
 
 async function loadStats() {
@@ -3209,6 +3515,10 @@ async function loadStats() {
     const conversationsBySubagent = await api(`/api/stats/conversations-by-subagent?${queryParams}`);
     state.statsData.conversationsBySubagent = conversationsBySubagent.data;
 
+    // Load usage by project directory
+    const projects = await api(`/api/stats/projects?${queryParams}`);
+    state.statsData.projects = projects.data;
+
     // Update charts
     updateStatsCharts();
   } catch (err) {
@@ -3308,6 +3618,10 @@ async function showContextModal() {
     if (!detail.messages.length) {
       lines.push("No context yet. Start a conversation to see context here.");
     } else {
+      if (detail.has_more) {
+        lines.push(`(showing the ${detail.messages.length} most recent messages)`);
+        lines.push("");
+      }
       detail.messages.forEach((m, idx) => {
         const role = m.role.toUpperCase();
         const preview = m.content.replace(/\n/g, " ");
@@ -3319,6 +3633,7 @@ async function showContextModal() {
     }
 
     document.getElementById("context-content").textContent = lines.join("\n");
+    await renderContextBreakdown();
     document.getElementById("context-modal").classList.add("open");
   } catch (err) {
     setStatus(`Failed to load context: ${err.message}`);
@@ -3327,6 +3642,80 @@ async function showContextModal() {
   }
 }
 
+function formatTokens(tokens) {
+  if (tokens < 1000) return String(tokens);
+  if (tokens < 100000) return `${(tokens / 1000).toFixed(1)}k`;
+  if (tokens < 1000000) return `${Math.floor(tokens / 1000)}k`;
+  return `${(tokens / 1000000).toFixed(1)}M`;
+}
+
+function contextBar(percent) {
+  const bar = document.createElement("div");
+  bar.className = "context-bar";
+  const fill = document.createElement("div");
+  fill.className = "context-bar-fill" + (percent >= 90 ? " full" : percent >= 70 ? " warn" : "");
+  fill.style.width = `${Math.min(Math.max(percent, percent > 0 ? 1 : 0), 100)}%`;
+  bar.appendChild(fill);
+  return bar;
+}
+
+// Token counts per contributor, with remove buttons for context files and images
+async function renderContextBreakdown() {
+  const container = document.getElementById("context-breakdown");
+  if (!container) return;
+  container.innerHTML = "";
+  const conversationId = state.activeConversationId;
+  let breakdown;
+  try {
+    breakdown = await api(`/api/conversations/${conversationId}/context`);
+  } catch (err) {
+    console.error("Failed to load context breakdown:", err);
+    return;
+  }
+
+  const summary = document.createElement("div");
+  summary.textContent = `${breakdown.model}: ${formatTokens(breakdown.total_tokens)} of ${formatTokens(
+    breakdown.context_window
+  )} tokens (${breakdown.percent.toFixed(1)}%)`;
+  container.appendChild(summary);
+  container.appendChild(contextBar(breakdown.percent));
+
+  breakdown.items.forEach((item) => {
+    const row = document.createElement("div");
+    row.className = "context-row";
+    const label = document.createElement("span");
+    label.className = "context-label";
+    label.textContent = item.label;
+    label.title = item.label;
+    const tokens = document.createElement("span");
+    tokens.className = "muted";
+    tokens.textContent = `${formatTokens(item.tokens)} (${item.percent.toFixed(1)}%)`;
+    row.appendChild(label);
+    row.appendChild(tokens);
+    row.appendChild(contextBar(item.percent));
+    if (item.message_index !== null && item.message_index !== undefined) {
+      const remove = document.createElement("button");
+      remove.className = "secondary control-compact";
+      remove.textContent = "Remove";
+      remove.addEventListener("click", async () => {
+        try {
+          await api(`/api/conversations/${conversationId}/context/${item.message_index}`, {
+            method: "DELETE",
+          });
+          await renderContextBreakdown();
+          setStatus(`Removed ${item.label} from the context`);
+        } catch (err) {
+          setStatus(`Failed to remove context: ${err.message}`);
+        }
+      });
+      row.appendChild(remove);
+    } else {
+      row.appendChild(document.createElement("span"));
+    }
+    container.appendChild(row);
+  });
+}
+
 function closeContextModal() {
   document.getElementById("context-modal").classList.remove("open");
 }